@@ -0,0 +1,206 @@
+//! Content negotiation for write endpoints: request bodies may be JSON or YAML,
+//! and responses are YAML when the `Accept` header prefers it.
+
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{FromRequest, FromRequestParts, Request},
+    http::{
+        HeaderMap, HeaderValue,
+        header::{ACCEPT, CONTENT_TYPE},
+        request::Parts,
+    },
+    response::{IntoResponse, Response},
+};
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::json;
+
+use crate::error::AppError;
+
+/// Media types accepted as YAML, in `Content-Type` and `Accept`.
+const YAML_TYPES: &[&str] = &["application/yaml", "application/x-yaml", "text/yaml", "text/x-yaml"];
+/// `Accept` entries that are satisfied by JSON.
+const JSON_TYPES: &[&str] = &["application/json", "application/*", "*/*"];
+
+/// Media type of a header value, without parameters, lowercased.
+fn media_type(value: &str) -> String {
+    value.split(';').next().unwrap_or_default().trim().to_ascii_lowercase()
+}
+
+fn is_yaml_content(headers: &HeaderMap) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| YAML_TYPES.contains(&media_type(v).as_str()))
+}
+
+/// Request body decoded from JSON, or from YAML when `Content-Type` is
+/// `application/yaml` or `text/yaml` (or their `x-` variants). JSON bodies keep
+/// axum's `Json` behaviour and rejections; YAML errors are `400 invalid_body`
+/// with `details: { line, column }` pointing into the body.
+pub struct JsonOrYaml<T>(pub T);
+
+impl<S, T> FromRequest<S> for JsonOrYaml<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if !is_yaml_content(req.headers()) {
+            return Json::<T>::from_request(req, state)
+                .await
+                .map(|Json(value)| JsonOrYaml(value))
+                .map_err(IntoResponse::into_response);
+        }
+
+        let bytes = Bytes::from_request(req, state).await.map_err(IntoResponse::into_response)?;
+        serde_yaml::from_slice(&bytes)
+            .map(JsonOrYaml)
+            .map_err(|e| yaml_error(e).into_response())
+    }
+}
+
+fn yaml_error(e: serde_yaml::Error) -> AppError {
+    let details = match e.location() {
+        Some(at) => json!({ "format": "yaml", "line": at.line(), "column": at.column() }),
+        None => json!({ "format": "yaml" }),
+    };
+    AppError::InvalidBody { message: e.to_string(), details }
+}
+
+/// Response encoding picked from the request's `Accept` header. YAML wins only
+/// when a YAML media type has a strictly higher q-value than anything JSON
+/// satisfies; a missing header, `*/*` or a tie means JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResponseFormat {
+    #[default]
+    Json,
+    Yaml,
+}
+
+impl ResponseFormat {
+    pub fn from_accept(accept: Option<&str>) -> Self {
+        let Some(accept) = accept else {
+            return ResponseFormat::Json;
+        };
+        let (mut yaml_q, mut json_q) = (0.0f32, 0.0f32);
+        for entry in accept.split(',') {
+            let media = media_type(entry);
+            let q = entry
+                .split(';')
+                .skip(1)
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if YAML_TYPES.contains(&media.as_str()) {
+                yaml_q = yaml_q.max(q);
+            } else if JSON_TYPES.contains(&media.as_str()) {
+                json_q = json_q.max(q);
+            }
+        }
+        if yaml_q > json_q { ResponseFormat::Yaml } else { ResponseFormat::Json }
+    }
+
+    /// Encode `value` as the response body.
+    pub fn render<T: Serialize>(self, value: T) -> Response {
+        match self {
+            ResponseFormat::Json => Json(value).into_response(),
+            ResponseFormat::Yaml => match serde_yaml::to_string(&value) {
+                Ok(body) => {
+                    ([(CONTENT_TYPE, HeaderValue::from_static("application/yaml"))], body).into_response()
+                }
+                Err(e) => AppError::serialization(e).into_response(),
+            },
+        }
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ResponseFormat {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let accept = parts.headers.get(ACCEPT).and_then(|v| v.to_str().ok());
+        Ok(ResponseFormat::from_accept(accept))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, http::StatusCode};
+    use serde_json::Value;
+
+    use super::*;
+
+    async fn extract(content_type: &str, body: &str) -> Result<Value, Response> {
+        let req = Request::builder()
+            .method("POST")
+            .uri("/")
+            .header(CONTENT_TYPE, content_type)
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        JsonOrYaml::<Value>::from_request(req, &()).await.map(|JsonOrYaml(v)| v)
+    }
+
+    async fn error_body(resp: Response) -> Value {
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn json_and_yaml_bodies_decode_to_the_same_value() {
+        let from_json = extract("application/json", r#"{"id":"g_ops","name":"Ops","labels":{"team":"sre"}}"#)
+            .await
+            .unwrap();
+        let yaml = "# comments are fine\nid: g_ops\nname: Ops\nlabels: &l\n  team: sre\n";
+        for content_type in ["application/yaml", "text/yaml; charset=utf-8", "application/x-yaml"] {
+            assert_eq!(extract(content_type, yaml).await.unwrap(), from_json, "{}", content_type);
+        }
+    }
+
+    #[tokio::test]
+    async fn malformed_yaml_reports_line_and_column() {
+        let resp = extract("application/yaml", "id: g_ops\nname: Ops\n  nested: wrong\n").await.unwrap_err();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body = error_body(resp).await;
+        assert_eq!(body["error"]["code"], "invalid_body");
+        assert_eq!(body["error"]["details"]["format"], "yaml");
+        assert_eq!(body["error"]["details"]["line"], 3);
+        assert!(body["error"]["details"]["column"].as_u64().unwrap() > 0);
+    }
+
+    #[tokio::test]
+    async fn json_rejections_are_unchanged() {
+        let resp = extract("application/json", "{not json").await.unwrap_err();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let resp = extract("text/plain", "id: g_ops").await.unwrap_err();
+        assert_eq!(resp.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    #[test]
+    fn accept_prefers_yaml_only_when_ranked_higher() {
+        let cases = [
+            (None, ResponseFormat::Json),
+            (Some("*/*"), ResponseFormat::Json),
+            (Some("application/json"), ResponseFormat::Json),
+            (Some("application/yaml"), ResponseFormat::Yaml),
+            (Some("text/yaml"), ResponseFormat::Yaml),
+            (Some("application/yaml, */*;q=0.5"), ResponseFormat::Yaml),
+            (Some("application/json;q=0.9, application/yaml"), ResponseFormat::Yaml),
+            (Some("application/yaml;q=0.5, application/json"), ResponseFormat::Json),
+            (Some("application/yaml, application/json"), ResponseFormat::Json),
+        ];
+        for (accept, expected) in cases {
+            assert_eq!(ResponseFormat::from_accept(accept), expected, "{:?}", accept);
+        }
+    }
+
+    #[tokio::test]
+    async fn yaml_responses_carry_the_yaml_content_type() {
+        let resp = ResponseFormat::Yaml.render(json!({ "id": "g_ops" }));
+        assert_eq!(resp.headers()[CONTENT_TYPE], "application/yaml");
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(std::str::from_utf8(&bytes).unwrap(), "id: g_ops\n");
+    }
+}
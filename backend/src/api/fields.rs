@@ -0,0 +1,104 @@
+//! Sparse fieldsets for list endpoints (`?fields=id,labels,personal.name`).
+//!
+//! Selection happens after `to_external`, so paths name fields as clients see
+//! them. A dotted path keeps only that branch of a nested object; paths that
+//! do not exist on a document are simply absent from its output.
+
+use serde_json::{Map, Value};
+
+use crate::error::AppError;
+
+/// Fields that are never returned, even when asked for by name.
+const SECRET_FIELDS: &[&str] = &["password", "password_hash", "totp"];
+
+/// Parse a comma-separated `fields` parameter into dotted paths.
+/// Secret fields are refused with 400 rather than silently dropped.
+pub fn parse_fields(spec: &str) -> Result<Vec<Vec<String>>, AppError> {
+    let mut paths = Vec::new();
+    for raw in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let path: Vec<String> = raw.split('.').map(str::to_string).collect();
+        if path.iter().any(|segment| segment.is_empty()) {
+            return Err(AppError::bad_request(format!("invalid field path '{}'", raw)));
+        }
+        if path.iter().any(|segment| SECRET_FIELDS.contains(&segment.as_str())) {
+            return Err(AppError::bad_request(format!("field '{}' cannot be requested", raw)));
+        }
+        paths.push(path);
+    }
+    if paths.is_empty() {
+        return Err(AppError::bad_request("fields must name at least one field"));
+    }
+    Ok(paths)
+}
+
+/// Keep only the given paths of `doc`, preserving their nesting.
+pub fn select_fields(doc: &Value, paths: &[Vec<String>]) -> Value {
+    let mut out = Map::new();
+    for path in paths {
+        copy_path(doc, &mut out, path);
+    }
+    Value::Object(out)
+}
+
+fn copy_path(src: &Value, dst: &mut Map<String, Value>, path: &[String]) {
+    let Some((head, rest)) = path.split_first() else {
+        return;
+    };
+    let Some(value) = src.get(head) else {
+        return;
+    };
+    if rest.is_empty() {
+        dst.insert(head.clone(), value.clone());
+        return;
+    }
+    if !value.is_object() {
+        return;
+    }
+    match dst.get_mut(head) {
+        Some(Value::Object(child)) => copy_path(value, child, rest),
+        Some(_) => {}
+        None => {
+            let mut child = Map::new();
+            copy_path(value, &mut child, rest);
+            if !child.is_empty() {
+                dst.insert(head.clone(), Value::Object(child));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn select(doc: Value, spec: &str) -> Value {
+        select_fields(&doc, &parse_fields(spec).unwrap())
+    }
+
+    #[test]
+    fn nested_paths_keep_their_branch() {
+        let doc = json!({
+            "id": "u_alice",
+            "labels": { "team": "sre" },
+            "personal": { "name": "Alice", "email": "a@example.com" },
+        });
+        assert_eq!(
+            select(doc.clone(), "id, personal.name"),
+            json!({ "id": "u_alice", "personal": { "name": "Alice" } })
+        );
+        assert_eq!(
+            select(doc, "personal.name,personal,missing,labels.team.deeper"),
+            json!({ "personal": { "name": "Alice", "email": "a@example.com" } })
+        );
+    }
+
+    #[test]
+    fn secret_and_malformed_fields_are_rejected() {
+        assert!(parse_fields("id,password_hash").is_err());
+        assert!(parse_fields("personal.password").is_err());
+        assert!(parse_fields("id,,labels").is_ok());
+        assert!(parse_fields("labels..team").is_err());
+        assert!(parse_fields(" , ").is_err());
+    }
+}
@@ -0,0 +1,111 @@
+//! JSON Merge Patch (RFC 7386) bodies for `PATCH` endpoints.
+//!
+//! A patch is applied to the document as clients see it: object members merge
+//! recursively, an explicit `null` deletes the member, and anything else
+//! (arrays, strings, numbers) replaces the target value wholesale.
+
+use axum::{
+    body::Bytes,
+    extract::{FromRequest, Request},
+    http::{StatusCode, header::CONTENT_TYPE},
+    response::{IntoResponse, Response},
+};
+use serde_json::{Map, Value, json};
+
+use crate::error::AppError;
+
+pub const MERGE_PATCH_TYPE: &str = "application/merge-patch+json";
+
+/// Request body sent as `Content-Type: application/merge-patch+json`.
+/// Other content types are refused with 415, malformed JSON with `400 invalid_body`.
+pub struct MergePatch(pub Value);
+
+impl<S: Send + Sync> FromRequest<S> for MergePatch {
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let is_merge_patch = req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.split(';').next())
+            .is_some_and(|v| v.trim().eq_ignore_ascii_case(MERGE_PATCH_TYPE));
+        if !is_merge_patch {
+            return Err((
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                format!("Expected request with `Content-Type: {}`", MERGE_PATCH_TYPE),
+            )
+                .into_response());
+        }
+
+        let bytes = Bytes::from_request(req, state).await.map_err(IntoResponse::into_response)?;
+        serde_json::from_slice(&bytes).map(MergePatch).map_err(|e| {
+            AppError::InvalidBody {
+                message: e.to_string(),
+                details: json!({ "format": "json", "line": e.line(), "column": e.column() }),
+            }
+            .into_response()
+        })
+    }
+}
+
+/// Apply `patch` to `target` in place, per RFC 7386.
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(members) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    let Value::Object(obj) = target else {
+        return;
+    };
+    for (key, value) in members {
+        if value.is_null() {
+            obj.remove(key);
+        } else {
+            merge_patch(obj.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patched(mut target: Value, patch: Value) -> Value {
+        merge_patch(&mut target, &patch);
+        target
+    }
+
+    #[test]
+    fn object_members_merge() {
+        let doc = json!({ "name": "Ops", "labels": { "team": "sre", "tier": "1" } });
+        assert_eq!(
+            patched(doc, json!({ "labels": { "tier": "2", "env": "prod" } })),
+            json!({ "name": "Ops", "labels": { "team": "sre", "tier": "2", "env": "prod" } })
+        );
+    }
+
+    #[test]
+    fn null_deletes_the_member() {
+        let doc = json!({ "name": "Ops", "labels": { "team": "sre", "tier": "1" } });
+        assert_eq!(
+            patched(doc, json!({ "labels": { "tier": null }, "missing": null })),
+            json!({ "name": "Ops", "labels": { "team": "sre" } })
+        );
+    }
+
+    #[test]
+    fn arrays_and_scalars_replace() {
+        let doc = json!({ "tags": ["a", "b"], "personal": { "name": "Bob" } });
+        assert_eq!(
+            patched(doc, json!({ "tags": ["c"], "personal": "gone" })),
+            json!({ "tags": ["c"], "personal": "gone" })
+        );
+        // A non-object patch replaces the whole document, and vice versa.
+        assert_eq!(patched(json!({ "a": 1 }), json!([1])), json!([1]));
+        assert_eq!(patched(json!([1]), json!({ "a": { "b": null, "c": 1 } })), json!({ "a": { "c": 1 } }));
+    }
+}
@@ -1 +1,4 @@
-pub mod v1;
\ No newline at end of file
+pub mod extract;
+pub mod fields;
+pub mod merge_patch;
+pub mod v1;
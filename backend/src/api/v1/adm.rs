@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+
+use crate::{audit_log::AuditQuery, error::AppError, godmode, middleware::RequireAdmin, state::AppState};
+
+/// Query recent mutating requests from the audit log, newest first.
+///
+/// `GET /v1/adm/audit?actor=<user_id>&kind=<kind>&key=<key>&since=<rfc3339>&limit=<n>`
+/// Admins only (`RequireAdmin`).
+pub async fn query_audit_log(
+    _admin: RequireAdmin,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<Value>, AppError> {
+    let entries = state.audit.query(&query).await;
+    Ok(Json(json!({ "items": entries })))
+}
+
+/// Forget every cached godmode decision, so `ADM_GODMODE` grants and
+/// revocations apply now instead of after `SPECIAL_ACCESS_TTL`.
+///
+/// `POST /v1/adm/reload-admins` → `{ "cleared": n }`
+/// Admins only (`RequireAdmin`).
+pub async fn reload_admins(_admin: RequireAdmin, State(state): State<Arc<AppState>>) -> Json<Value> {
+    let cleared = state.cache.clear(godmode::SPECIAL_ACCESS_CACHE).await;
+    log::info!("[GODMODE] cached admin decisions dropped: {}", cleared);
+    Json(json!({ "cleared": cleared }))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RebuildQuery {
+    /// Rebuild only this index; all of them when absent.
+    pub index: Option<String>,
+}
+
+/// Rescan the database and regenerate secondary indexes from scratch, e.g.
+/// after a write that bypassed the handlers.
+///
+/// `POST /v1/adm/rebuild_indexes[?index=<name>]` → `{ "rebuilt": { "<name>": scanned, ... } }`;
+/// 404 listing the known indexes for an unknown name.
+/// Admins only (`RequireAdmin`).
+pub async fn rebuild_indexes(
+    RequireAdmin(admin): RequireAdmin,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<RebuildQuery>,
+) -> Result<Json<Value>, AppError> {
+    log::info!("[INDEX] rebuild requested by {}", admin);
+    let Some(rebuilt) = state.indexes.rebuild_indexes(state.db.as_ref(), query.index.as_deref()).await? else {
+        let known: Vec<&str> = state.indexes.names().collect();
+        return Err(AppError::not_found(format!(
+            "unknown index '{}'; known indexes: {}",
+            query.index.unwrap_or_default(),
+            known.join(", ")
+        )));
+    };
+    let rebuilt: Map<String, Value> = rebuilt.into_iter().map(|(name, scanned)| (name.to_string(), json!(scanned))).collect();
+    Ok(Json(json!({ "rebuilt": rebuilt })))
+}
@@ -1,17 +1,21 @@
 use std::sync::Arc;
 
 use axum::{
+    body::Bytes,
     extract::{Json, State},
     http::{HeaderMap, HeaderValue, header},
     response::IntoResponse,
 };
 use serde_json::json;
 
+use crit_shared::util_models::PrincipalId;
+
 use crate::{
     error::AppError,
-    schema::{Created, LoginRequest, LoginResponse, RegisterRequest},
+    middleware::auth::Auth,
+    schema::{Created, LoginBody, LoginResponse, RefreshRequest, RegisterRequest},
     state::AppState,
-    validation::naming::validate_username,
+    validation::naming::{normalize_uid, validate_username},
 };
 
 #[utoipa::path(
@@ -29,8 +33,8 @@ pub async fn register(
         ));
     }
 
-    let username = validate_username(&req.user).map_err(AppError::Validation)?;
-    let user_id = format!("u_{}", username);
+    let username = validate_username(&req.user).map_err(|e| AppError::invalid_field("user", e))?;
+    let user_id = PrincipalId::user(&username).into_string();
 
     // Build a JSON body and go through the standard controller pipeline
     let mut body = json!({
@@ -55,6 +59,7 @@ pub async fn register(
                 AppError::Internal(e)
             }
         })?;
+    app_state.invalidate_cached_user("users", &user_id).await;
 
     // Grant default permissions to new users
     app_state
@@ -77,11 +82,12 @@ pub async fn register(
 
 pub async fn login(
     State(app_state): State<Arc<AppState>>,
-    Json(req): Json<LoginRequest>,
+    Json(LoginBody { credentials: req, totp_code }): Json<LoginBody>,
 ) -> Result<impl IntoResponse, AppError> {
+    let uid = PrincipalId::user(&normalize_uid(&req.user));
     let user = app_state
         .db
-        .get_user_by_id(&req.user)
+        .get_user_by_id(uid.as_str())
         .await
         .map_err(|_e| AppError::Authorization("Unauthorized".to_string()))?;
 
@@ -94,45 +100,138 @@ pub async fn login(
         return Err(AppError::Authorization("Unauthorized".to_string()));
     }
 
-    let (token_str, exp) = app_state.auth.create_token(&true_user.id)?;
+    if let Some(factor) = true_user.totp.as_ref().filter(|f| f.confirmed) {
+        super::two_factor::check_login_code(&app_state, &true_user.id, factor, totp_code.as_deref()).await?;
+    }
+
+    // Hashes from an older algorithm or cost are replaced while the plain
+    // password is at hand. Failing to do so must not fail the login.
+    if app_state.auth.needs_rehash(&true_user.password_hash) {
+        let rehashed = match app_state.auth.hash_password(&req.password) {
+            Ok(hash) => app_state.db.set_user_password_hash(&true_user.id, &hash).await.map_err(AppError::from),
+            Err(e) => Err(e),
+        };
+        match rehashed {
+            Ok(()) => log::info!("Auth event -> Password hash upgraded: {}", &true_user.id),
+            Err(e) => log::warn!("Could not upgrade password hash of {}: {}", &true_user.id, e),
+        }
+    }
 
     log::info!("Auth event -> User logged in: {}", &true_user.id);
 
     // Record sign-in event (non-fatal — login still succeeds if event writing fails)
     let _ = app_state
         .db
-        .write_event("users", &true_user.id, "sign_in", Some(&true_user.id), None)
+        .write_event("users", &true_user.id, "sign_in", Some(true_user.id.as_str()), None)
         .await;
 
+    issue_session(&app_state, &true_user.id).await
+}
+
+/// Exchange a refresh token (JSON body or `refresh_token` cookie) for a new
+/// access/refresh pair. The presented token is consumed, so replaying it fails.
+pub async fn refresh(
+    State(app_state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<impl IntoResponse, AppError> {
+    let unauthorized = || AppError::Authorization("Unauthorized".to_string());
+    let token = presented_refresh_token(&headers, &body).ok_or_else(unauthorized)?;
+    let record = app_state
+        .db
+        .take_refresh_token(&Auth::refresh_token_hash(&token))
+        .await?
+        .ok_or_else(unauthorized)?;
+
+    if record.expires_at < chrono::Utc::now().timestamp() || !app_state.is_active_user(&record.user_id).await {
+        return Err(unauthorized());
+    }
+
+    log::info!("Auth event -> Session refreshed: {}", &record.user_id);
+    issue_session(&app_state, &record.user_id).await
+}
+
+/// Refresh token from a `{"refresh_token": ...}` body, else from the cookie.
+fn presented_refresh_token(headers: &HeaderMap, body: &[u8]) -> Option<String> {
+    if let Ok(req) = serde_json::from_slice::<RefreshRequest>(body) {
+        return Some(req.refresh_token);
+    }
+    headers
+        .get(header::COOKIE)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|cookies| {
+            cookies
+                .split(';')
+                .find_map(|cookie| cookie.trim().strip_prefix("refresh_token="))
+                .map(|s| s.to_string())
+        })
+}
+
+/// Issue and store a token pair; respond with both tokens and their cookies.
+pub(super) async fn issue_session(app_state: &AppState, user_id: &str) -> Result<(HeaderMap, Json<LoginResponse>), AppError> {
+    let pair = app_state.auth.create_token_pair(user_id)?;
+    app_state
+        .db
+        .store_refresh_token(
+            &Auth::refresh_token_hash(&pair.refresh_token),
+            user_id,
+            pair.refresh_expires_at as i64,
+        )
+        .await?;
+
     // Calculate max-age from expiration timestamp
     let now = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap()
         .as_secs() as usize;
-    let max_age = exp.saturating_sub(now);
 
-    let cookie = format!(
-        "token={}; HttpOnly; Secure; SameSite=Lax; Path=/; Max-Age={}",
-        token_str, max_age
-    );
+    let cookies = [
+        format!(
+            "token={}; HttpOnly; Secure; SameSite=Lax; Path=/; Max-Age={}",
+            pair.access_token,
+            pair.access_expires_at.saturating_sub(now)
+        ),
+        // Only sent to the session endpoints (refresh, logout).
+        format!(
+            "refresh_token={}; HttpOnly; Secure; SameSite=Strict; Path=/api/v1; Max-Age={}",
+            pair.refresh_token,
+            pair.refresh_expires_at.saturating_sub(now)
+        ),
+    ];
 
     let mut headers = HeaderMap::new();
-    headers.insert(
-        header::SET_COOKIE,
-        HeaderValue::from_str(&cookie).map_err(|_| {
-            AppError::Internal(anyhow::anyhow!("Failed to build Set-Cookie header"))
-        })?,
-    );
+    for cookie in cookies {
+        headers.append(
+            header::SET_COOKIE,
+            HeaderValue::from_str(&cookie).map_err(|_| {
+                AppError::Internal(anyhow::anyhow!("Failed to build Set-Cookie header"))
+            })?,
+        );
+    }
 
-    Ok((headers, Json(LoginResponse { token: token_str })))
+    Ok((
+        headers,
+        Json(LoginResponse { token: pair.access_token, refresh_token: Some(pair.refresh_token) }),
+    ))
 }
 
-pub async fn logout() -> impl IntoResponse {
+/// Expire the session cookies and revoke the presented refresh token, if any.
+pub async fn logout(State(app_state): State<Arc<AppState>>, req_headers: HeaderMap, body: Bytes) -> impl IntoResponse {
+    if let Some(token) = presented_refresh_token(&req_headers, &body)
+        && let Err(e) = app_state.db.take_refresh_token(&Auth::refresh_token_hash(&token)).await
+    {
+        log::error!("Auth event -> failed to revoke refresh token on logout: {}", e);
+    }
+
     let mut headers = HeaderMap::new();
-    // Expire the token cookie immediately
-    headers.insert(
+    // Expire the token cookies immediately
+    headers.append(
         header::SET_COOKIE,
         HeaderValue::from_static("token=; HttpOnly; Secure; SameSite=Lax; Path=/; Max-Age=0"),
     );
+    headers.append(
+        header::SET_COOKIE,
+        HeaderValue::from_static("refresh_token=; HttpOnly; Secure; SameSite=Strict; Path=/api/v1; Max-Age=0"),
+    );
     (headers, axum::http::StatusCode::NO_CONTENT)
 }
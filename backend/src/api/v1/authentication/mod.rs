@@ -1 +1,3 @@
-pub mod login;
\ No newline at end of file
+pub mod login;
+pub mod oauth;
+pub mod two_factor;
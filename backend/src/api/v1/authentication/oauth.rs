@@ -0,0 +1,175 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    response::{IntoResponse, Redirect, Response},
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use crit_shared::data_models::OAuthIdentity;
+
+use crate::{
+    cache::OIDC_LOGINS_CACHE,
+    controllers::gitops_controller::generated_id,
+    error::AppError,
+    middleware::{AuditChange, auth::NO_PASSWORD},
+    services::oidc::{IdTokenClaims, OidcClient, PendingLogin},
+    state::AppState,
+    validation::naming::slugify,
+};
+
+use super::login::issue_session;
+
+/// Attempts at a free user id before provisioning gives up with 409.
+const MAX_ID_ATTEMPTS: u32 = 5;
+
+#[derive(Deserialize)]
+pub struct CallbackParams {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+fn client(app_state: &AppState) -> Result<&OidcClient, AppError> {
+    (*app_state.oidc)
+        .as_ref()
+        .ok_or_else(|| AppError::not_found("OIDC login is not configured"))
+}
+
+/// Start an authorization-code login: remember the PKCE verifier and nonce
+/// under a fresh `state`, then send the browser to the provider.
+pub async fn login(State(app_state): State<Arc<AppState>>) -> Result<impl IntoResponse, AppError> {
+    let oidc = client(&app_state)?;
+    let meta = oidc.discover().await?;
+    let (url, state, pending) = oidc.authorization_url(&meta)?;
+    app_state
+        .cache
+        .set(OIDC_LOGINS_CACHE, state, serde_json::to_value(pending)?)
+        .await;
+    Ok(Redirect::to(&url))
+}
+
+/// Finish a login: redeem the code, verify the ID token, find or provision
+/// the user bound to its issuer and subject, and start one of our sessions.
+pub async fn callback(
+    State(app_state): State<Arc<AppState>>,
+    Query(params): Query<CallbackParams>,
+) -> Result<Response, AppError> {
+    let oidc = client(&app_state)?;
+    if let Some(error) = params.error {
+        log::info!("Auth event -> OIDC provider refused login: {} {}", error, params.error_description.unwrap_or_default());
+        return Err(AppError::Authorization(format!("OIDC provider refused login: {}", error)));
+    }
+    let (Some(code), Some(state)) = (params.code, params.state) else {
+        return Err(AppError::bad_request("code and state are required"));
+    };
+
+    // Taken, not read: a state is good for one callback only.
+    let pending: PendingLogin = match app_state.cache.take(OIDC_LOGINS_CACHE, &state).await {
+        Some(value) => serde_json::from_value(value)?,
+        None => return Err(AppError::Authorization("unknown or expired OIDC state".to_string())),
+    };
+
+    let meta = oidc.discover().await?;
+    let verified = match oidc.exchange_code(&meta, &code, &pending.code_verifier).await {
+        Ok(id_token) => {
+            oidc.verify_id_token(&meta, &id_token, &pending.nonce, app_state.config.jwt_leeway_secs)
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    let claims = verified.map_err(|e| {
+        log::warn!("Auth event -> OIDC login rejected: {}", e);
+        AppError::Authorization("Unauthorized".to_string())
+    })?;
+
+    let (user_id, provisioned) = match app_state.db.find_user_by_oauth(&claims.iss, &claims.sub).await? {
+        Some(user) => (user.id.into(), false),
+        None => (provision_user(&app_state, &claims).await?, true),
+    };
+    if !app_state.is_active_user(&user_id).await {
+        return Err(AppError::Authorization("Unauthorized".to_string()));
+    }
+
+    log::info!("Auth event -> User logged in via OIDC: {}", &user_id);
+    let _ = app_state
+        .db
+        .write_event("users", &user_id, "sign_in", Some(user_id.as_str()), None)
+        .await;
+
+    let session = issue_session(&app_state, &user_id).await?;
+    // A GET, so only recorded in the audit log when it created the user.
+    if provisioned {
+        return Ok(AuditChange::new("create", None, None).on("users", &user_id).attach(session));
+    }
+    Ok(session.into_response())
+}
+
+/// Create the user for a first OIDC login. The id is derived from
+/// `preferred_username` or the email's local part; existing accounts are
+/// never matched by name or email, only by `oauth`. Refused in read-only
+/// mode: the callback is a GET, so `read_only_middleware` lets it through.
+async fn provision_user(app_state: &AppState, claims: &IdTokenClaims) -> Result<String, AppError> {
+    if app_state.is_read_only() {
+        log::debug!("[READ-ONLY] refused to provision OIDC subject {} at {}", &claims.sub, &claims.iss);
+        return Err(AppError::ReadOnly);
+    }
+    let slug = claims
+        .preferred_username
+        .as_deref()
+        .and_then(slugify)
+        .or_else(|| claims.email.as_deref().and_then(|e| e.split('@').next()).and_then(slugify));
+
+    let mut annotations = json!({ "registered_at": chrono::Utc::now().to_rfc3339() });
+    if let Some(email) = &claims.email {
+        annotations["oidc_email"] = json!(email);
+    }
+
+    for attempt in 0..MAX_ID_ATTEMPTS {
+        let user_id = generated_id("u_", "users", slug.as_deref(), attempt);
+        let mut body = json!({
+            "id": &user_id,
+            "annotations": annotations.clone(),
+            "personal": {
+                "name": claims.name.clone().unwrap_or_default(),
+                "gender": "",
+                "job_title": "",
+                "manager": null,
+            },
+        });
+        let ctrl = app_state.controller.for_kind("users");
+        ctrl.prepare_create(&mut body, &user_id);
+        let mut doc = ctrl.to_internal(body, &app_state.auth)?;
+        doc["password_hash"] = json!(NO_PASSWORD);
+        doc["oauth"] = serde_json::to_value(OAuthIdentity {
+            issuer: claims.iss.clone(),
+            subject: claims.sub.clone(),
+        })?;
+        match app_state.db.generic_create("users", doc).await {
+            Ok(_) => {
+                app_state.invalidate_cached_user("users", &user_id).await;
+                app_state
+                    .db
+                    .grant_permission(crate::util_models::super_permissions::USR_CREATE_GROUPS, &user_id)
+                    .await
+                    .map_err(AppError::Internal)?;
+                log::info!("Register event -> User {} provisioned for OIDC subject {} at {}", &user_id, &claims.sub, &claims.iss);
+                return Ok(user_id);
+            }
+            Err(e) => {
+                let msg = e.to_string();
+                if !(msg.contains("unique constraint") || msg.contains("1210")) {
+                    return Err(AppError::Internal(e));
+                }
+                // Either the id is taken or a concurrent first login won the race.
+                if let Some(user) = app_state.db.find_user_by_oauth(&claims.iss, &claims.sub).await? {
+                    return Ok(user.id.into());
+                }
+                log::debug!("[OIDC] provisioning: user id {} taken, retrying", user_id);
+            }
+        }
+    }
+    Err(AppError::conflict("could not find a free user id for the OIDC account"))
+}
@@ -0,0 +1,120 @@
+use std::sync::Arc;
+
+use axum::extract::{Json, State};
+
+use crit_shared::data_models::{TotpFactor, User};
+
+use crate::{
+    error::AppError,
+    middleware::auth::AuthenticatedUser,
+    schema::{TotpEnrollResponse, TotpVerifyRequest, TotpVerifyResponse},
+    services::totp,
+    state::AppState,
+};
+
+fn now_secs() -> u64 {
+    chrono::Utc::now().timestamp().max(0) as u64
+}
+
+async fn load_user(app_state: &AppState, user_id: &str) -> Result<User, AppError> {
+    app_state
+        .db
+        .get_user_by_id(user_id)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("users/{}", user_id)))
+}
+
+/// Start (or restart) TOTP enrollment for the caller: store a new encrypted
+/// secret, unconfirmed, and return it for the authenticator app. Login is
+/// unaffected until `verify` confirms it.
+///
+/// `POST /v1/2fa/enroll`
+pub async fn enroll(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Json<TotpEnrollResponse>, AppError> {
+    let user = load_user(&app_state, &user_id).await?;
+    if user.totp.as_ref().is_some_and(|f| f.confirmed) {
+        return Err(AppError::conflict("two-factor authentication is already enabled"));
+    }
+
+    let secret = totp::generate_secret();
+    let factor = TotpFactor {
+        secret: totp::seal(&app_state.config.totp_key, &secret)?,
+        confirmed: false,
+        recovery_codes: Vec::new(),
+    };
+    app_state.db.set_user_totp(&user.id, Some(&factor)).await?;
+    log::info!("Auth event -> TOTP enrollment started: {}", &user.id);
+
+    Ok(Json(TotpEnrollResponse {
+        secret: totp::encode_secret(&secret),
+        otpauth_uri: totp::otpauth_uri(&user.id, &secret),
+    }))
+}
+
+/// Confirm enrollment with a code from the app. From then on login asks for
+/// a code; the returned recovery codes are stored only as hashes.
+///
+/// `POST /v1/2fa/verify`
+pub async fn verify(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    State(app_state): State<Arc<AppState>>,
+    Json(req): Json<TotpVerifyRequest>,
+) -> Result<Json<TotpVerifyResponse>, AppError> {
+    let user = load_user(&app_state, &user_id).await?;
+    let Some(mut factor) = user.totp else {
+        return Err(AppError::bad_request("no two-factor enrollment in progress; POST /v1/2fa/enroll first"));
+    };
+    if factor.confirmed {
+        return Err(AppError::conflict("two-factor authentication is already enabled"));
+    }
+
+    let secret = totp::open(&app_state.config.totp_key, &factor.secret)?;
+    if !totp::verify(&secret, &req.code, now_secs()) {
+        return Err(AppError::invalid_field("code", "invalid or expired code"));
+    }
+
+    let recovery_codes = totp::generate_recovery_codes();
+    factor.confirmed = true;
+    factor.recovery_codes = recovery_codes.iter().map(|c| totp::hash_recovery_code(c)).collect();
+    app_state.db.set_user_totp(&user.id, Some(&factor)).await?;
+    log::info!("Auth event -> TOTP enabled: {}", &user.id);
+
+    Ok(Json(TotpVerifyResponse { recovery_codes }))
+}
+
+/// Second step of password login for a user with a confirmed factor: a
+/// current code (one step of clock skew either way) or an unused recovery
+/// code, which is then spent. A missing code is reported as such so clients
+/// know to ask for one.
+pub(super) async fn check_login_code(
+    app_state: &AppState,
+    user_id: &str,
+    factor: &TotpFactor,
+    code: Option<&str>,
+) -> Result<(), AppError> {
+    let Some(code) = code.map(str::trim).filter(|c| !c.is_empty()) else {
+        return Err(AppError::Authorization("two-factor code required".to_string()));
+    };
+
+    let secret = totp::open(&app_state.config.totp_key, &factor.secret)?;
+    if totp::verify(&secret, code, now_secs()) {
+        return Ok(());
+    }
+
+    let hashed = totp::hash_recovery_code(code);
+    if let Some(pos) = factor.recovery_codes.iter().position(|h| *h == hashed) {
+        let mut spent = factor.clone();
+        spent.recovery_codes.remove(pos);
+        app_state.db.set_user_totp(user_id, Some(&spent)).await?;
+        log::info!(
+            "Auth event -> Recovery code used: {} ({} left)",
+            user_id,
+            spent.recovery_codes.len()
+        );
+        return Ok(());
+    }
+
+    Err(AppError::Authorization("Unauthorized".to_string()))
+}
@@ -3,19 +3,50 @@ use std::sync::Arc;
 use axum::{
     Json,
     extract::{Path, Query, State},
+    http::HeaderMap,
     response::IntoResponse,
 };
 use serde::Deserialize;
 use serde_json::{Value, json};
 
-use crit_shared::compute_value_hash;
+use crit_shared::compute_value_hash_excluding;
+use crit_shared::requests::{ApplyAction, ApplyResponse};
+
+use crate::{
+    api::{
+        extract::{JsonOrYaml, ResponseFormat},
+        fields::{parse_fields, select_fields},
+        merge_patch::{MergePatch, merge_patch},
+    },
+    cache::{conditional_response, list_etag, resource_etag},
+    controllers::gitops_controller::{
+        CascadePolicy, KindController, advance_generation, delete_cascading, generated_id, prefixed_id,
+        stamp_update,
+    },
+    error::AppError,
+    middleware::{AuditChange, NoChange, auth::AuthenticatedUser},
+    quota,
+    state::AppState,
+    validation::{metadata::validate_resource_metadata, naming::slugify},
+    watch::ChangeType,
+};
 
-use crate::{error::AppError, middleware::auth::AuthenticatedUser, state::AppState};
+/// Generated-id candidates tried on create before reporting a conflict.
+const MAX_ID_ATTEMPTS: u32 = 5;
 
 #[derive(Deserialize)]
 pub struct ListQuery {
     pub limit: Option<u32>,
     pub cursor: Option<String>,
+    /// Comma-separated (dotted) paths to keep in each item, e.g. `id,personal.name`.
+    pub fields: Option<String>,
+}
+
+impl ListQuery {
+    /// Parsed `fields` selection; `None` means the kind's usual brief items.
+    pub fn field_paths(&self) -> Result<Option<Vec<Vec<String>>>, AppError> {
+        self.fields.as_deref().map(parse_fields).transpose()
+    }
 }
 
 #[derive(Deserialize)]
@@ -28,6 +59,30 @@ pub struct GetObjectQuery {
     pub with_history: Option<String>,
 }
 
+#[derive(Deserialize)]
+pub struct DeleteQuery {
+    /// `delete` or `reassign`; absent means refuse while owned resources remain.
+    pub cascade: Option<String>,
+    /// Target principal for `cascade=reassign`.
+    pub reassign_to: Option<String>,
+}
+
+impl DeleteQuery {
+    pub fn policy(self) -> Result<CascadePolicy, AppError> {
+        match (self.cascade.as_deref(), self.reassign_to) {
+            (None, None) => Ok(CascadePolicy::Refuse),
+            (Some("delete"), None) => Ok(CascadePolicy::Delete),
+            (Some("reassign"), Some(to)) if !to.is_empty() => Ok(CascadePolicy::Reassign(to)),
+            (Some("reassign"), _) => Err(AppError::bad_request("cascade=reassign requires reassign_to=<id>")),
+            (_, Some(_)) => Err(AppError::bad_request("reassign_to is only valid with cascade=reassign")),
+            (Some(other), None) => Err(AppError::bad_request(format!(
+                "unknown cascade policy '{}'; expected 'delete' or 'reassign'",
+                other
+            ))),
+        }
+    }
+}
+
 /// Validate that a kind string is a safe collection name (alphanumeric + underscores).
 pub fn validate_kind(kind: &str) -> Result<(), AppError> {
     if kind.is_empty() {
@@ -46,23 +101,40 @@ pub fn validate_kind(kind: &str) -> Result<(), AppError> {
 
 /// GET /global/{kind} — list all objects of this kind.
 /// Supports optional pagination via `?limit=N&cursor=<key>`.
-/// ACL filtering is pushed into a single AQL query for efficiency.
+/// `?fields=a,b.c` returns only those paths of each full item instead of the brief.
+/// Answers with an `ETag` and honors `If-None-Match`.
 pub async fn list_objects(
     AuthenticatedUser(user_id): AuthenticatedUser,
     Path(kind): Path<String>,
     Query(query): Query<ListQuery>,
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
 ) -> Result<impl IntoResponse, AppError> {
     validate_kind(&kind)?;
-    state.db.ensure_collection(&kind).await?;
+    let response = list_page(&state, &user_id, &kind, &query).await?;
+    let etag = list_etag(response["items"].as_array().map(Vec::as_slice).unwrap_or_default());
+    Ok(conditional_response(&headers, &etag, Json(response)))
+}
 
-    let ctrl = state.controller.for_kind(&kind);
+/// One page of `kind` as the caller may see it: `{ items }`, plus `has_more`
+/// and `next_cursor` when `?limit=` is given. ACL filtering is pushed into a
+/// single AQL query for efficiency.
+pub(crate) async fn list_page(
+    state: &AppState,
+    user_id: &str,
+    kind: &str,
+    query: &ListQuery,
+) -> Result<Value, AppError> {
+    let fields = query.field_paths()?;
+    state.db.ensure_collection(kind).await?;
+
+    let ctrl = state.controller.for_kind(kind);
 
     // Godmode bypasses all ACL checks
-    let godmode = state.has_godmode(&user_id).await.unwrap_or(false);
+    let godmode = state.has_godmode(user_id).await.unwrap_or(false);
 
     // Resolve principals once for the entire request
-    let principals = state.get_cached_principals(&user_id).await?;
+    let principals = state.get_cached_principals(user_id).await?;
 
     // Check super-permission bypass. If None (no super-permission defined),
     // treat as fully permissive (matches DefaultKindController behavior).
@@ -77,34 +149,41 @@ pub async fn list_objects(
     let result = state
         .db
         .generic_list_acl(
-            &kind,
+            kind,
             &principals,
             ctrl.read_permission_bits(),
             super_bypass,
-            ctrl.list_projection_fields(),
+            if fields.is_some() { None } else { ctrl.list_projection_fields() },
             query.limit,
             query.cursor.as_deref(),
         )
         .await?;
 
-    let filtered: Vec<Value> = result
-        .docs
-        .into_iter()
-        .map(|doc| ctrl.to_list_external(doc))
-        .collect();
+    let filtered = list_items(ctrl, result.docs, fields.as_deref());
 
+    let mut response = json!({ "items": filtered });
     if query.limit.is_some() {
-        let mut response = json!({
-            "items": filtered,
-            "has_more": result.has_more,
-        });
+        response["has_more"] = Value::Bool(result.has_more);
         if let Some(cursor) = result.next_cursor {
             response["next_cursor"] = Value::String(cursor);
         }
-        Ok(Json(response))
-    } else {
-        Ok(Json(json!({ "items": filtered })))
     }
+    Ok(response)
+}
+
+/// Shape listed documents: the kind's brief by default, or exactly the
+/// requested paths of the full external form.
+pub fn list_items(
+    ctrl: &dyn KindController,
+    docs: Vec<Value>,
+    fields: Option<&[Vec<String>]>,
+) -> Vec<Value> {
+    docs.into_iter()
+        .map(|doc| match fields {
+            Some(paths) => select_fields(&ctrl.to_external(doc), paths),
+            None => ctrl.to_list_external(doc),
+        })
+        .collect()
 }
 
 /// POST /global/{kind} — create a new object (id read from body).
@@ -112,11 +191,38 @@ pub async fn create_object(
     AuthenticatedUser(user_id): AuthenticatedUser,
     Path(kind): Path<String>,
     State(state): State<Arc<AppState>>,
-    Json(mut body): Json<Value>,
+    format: ResponseFormat,
+    JsonOrYaml(mut body): JsonOrYaml<Value>,
 ) -> Result<impl IntoResponse, AppError> {
     log::debug!("[HANDLER] create_object: user={}, kind={}", user_id, kind);
     validate_kind(&kind)?;
 
+    let ctrl = state.controller.for_kind(&kind);
+
+    // A missing or empty id is generated from the kind's prefix and, where the
+    // kind has one, a slug of its name field; collisions retry with a suffix.
+    // A given id without the prefix is prefixed or refused (ID_PREFIX_POLICY).
+    // `slug` is `Some` exactly when the id is generated.
+    let given_id = body.get("id").and_then(|v| v.as_str()).filter(|id| !id.is_empty());
+    let slug = match given_id {
+        Some(id) => {
+            let id = prefixed_id(id, ctrl.id_prefix(), state.config.id_prefix_policy)
+                .map_err(|e| AppError::invalid_field("id", e))?;
+            body["id"] = json!(id);
+            None
+        }
+        None if !body.is_object() => return Err(AppError::bad_request("request body must be an object")),
+        None => Some(
+            ctrl.slug_source()
+                .and_then(|ptr| body.pointer(ptr))
+                .and_then(|v| v.as_str())
+                .and_then(slugify),
+        ),
+    };
+    if let Some(slug) = &slug {
+        body["id"] = json!(generated_id(ctrl.id_prefix(), &kind, slug.as_deref(), 0));
+    }
+
     // Read the raw id for the auth check — to_internal hasn't run yet so the
     // id may not have its kind prefix (e.g. "qqq" before becoming "g_qqq").
     let raw_id = body
@@ -125,8 +231,6 @@ pub async fn create_object(
         .ok_or_else(|| AppError::bad_request("missing 'id' field in request body"))?
         .to_string();
 
-    let ctrl = state.controller.for_kind(&kind);
-
     let godmode = state.has_godmode(&user_id).await.unwrap_or(false);
     let allowed = godmode || ctrl.can_create(&user_id, &body).await?;
     log::debug!("[HANDLER] create_object: can_create={}, godmode={}, user={}, kind={}, raw_id={}", allowed, godmode, user_id, kind, raw_id);
@@ -134,65 +238,82 @@ pub async fn create_object(
         log::debug!("[HANDLER] create_object: DENIED for user={}, kind={}, id={}", user_id, kind, raw_id);
         return Err(AppError::forbidden(format!("not allowed to create {}/{}", kind, raw_id)));
     }
+    if !godmode {
+        quota::check_create(&state, &kind, &user_id).await?;
+    }
 
     ctrl.prepare_create(&mut body, &user_id);
 
     state.db.ensure_collection(&kind).await?;
 
-    // to_internal may transform the id (e.g. add a kind prefix, rename to _key).
-    // Extract the final _key from the transformed document so that after_create,
-    // error messages, and the success response all use the canonical stored key.
-    let mut doc = ctrl.to_internal(body, &state.auth)?;
-    // Compute and inject the desired-state hash before writing to DB.
-    let hash = compute_value_hash(&doc);
-    if let Some(obj) = doc.as_object_mut() {
-        obj.insert("hash_code".to_string(), json!(hash));
-    }
-    let final_id = doc
-        .get("_key")
-        .and_then(|v| v.as_str())
-        .unwrap_or(&raw_id)
-        .to_string();
-
-    // Validate ACL principals (e.g. group members check) before writing
-    ctrl.validate_acl_principals(&doc, &state.db).await?;
-
-    state
-        .db
-        .generic_create(&kind, doc)
-        .await
-        .map_err(|e| {
-            let msg = e.to_string();
-            if msg.contains("unique constraint") || msg.contains("1210") {
-                AppError::conflict(format!("{}/{} already exists", kind, final_id))
-            } else {
-                AppError::Internal(e)
+    let mut attempt = 0;
+    let (final_id, hash) = loop {
+        // to_internal may transform the id (e.g. add a kind prefix, rename to _key).
+        // Extract the final _key from the transformed document so that after_create,
+        // error messages, and the success response all use the canonical stored key.
+        let mut doc = ctrl.to_internal(body.clone(), &state.auth)?;
+        validate_resource_metadata(&doc).map_err(AppError::unprocessable)?;
+        // Compute and inject the desired-state hash before writing to DB.
+        let hash = compute_value_hash_excluding(&doc, ctrl.hash_excluded_fields());
+        if let Some(obj) = doc.as_object_mut() {
+            obj.insert("hash_code".to_string(), json!(hash));
+        }
+        let final_id = doc
+            .get("_key")
+            .and_then(|v| v.as_str())
+            .unwrap_or(&raw_id)
+            .to_string();
+
+        // Validate ACL principals (e.g. group members check) before writing
+        ctrl.validate_acl_principals(&doc, &*state.db).await?;
+
+        match state.db.generic_create(&kind, doc).await {
+            Ok(_) => break (final_id, hash),
+            Err(e) => {
+                let msg = e.to_string();
+                if !(msg.contains("unique constraint") || msg.contains("1210")) {
+                    return Err(AppError::Internal(e));
+                }
+                match &slug {
+                    Some(slug) if attempt + 1 < MAX_ID_ATTEMPTS => {
+                        attempt += 1;
+                        log::debug!("[HANDLER] create_object: generated id {}/{} taken, retrying", kind, final_id);
+                        body["id"] = json!(generated_id(ctrl.id_prefix(), &kind, slug.as_deref(), attempt));
+                    }
+                    _ => return Err(AppError::conflict(format!("{}/{} already exists", kind, final_id))),
+                }
             }
-        })?;
+        }
+    };
+    state.invalidate_cached_user(&kind, &final_id).await;
 
-    if let Err(e) = ctrl.after_create(&final_id, &user_id, &state.db).await {
+    if let Err(e) = ctrl.after_create(&final_id, &user_id, &*state.db).await {
         log::error!("[HANDLER] create_object: after_create hook failed: kind={}, id={}, error={}", kind, final_id, e);
         return Err(e);
     }
 
     // Write initial history entry — non-fatal
     if let Ok(Some(snap)) = state.db.generic_get(&kind, &final_id).await {
-        if let Err(e) = state.db.write_history_entry(&kind, &final_id, snap, &user_id).await {
+        if let Err(e) = state.db.write_history_entry(&kind, &final_id, snap.clone(), &user_id).await {
             log::error!("[HANDLER] create_object: write_history_entry failed: kind={}, id={}, error={}", kind, final_id, e);
         }
+        state.publish_change(ChangeType::Created, &kind, &final_id, snap).await;
     }
 
-    Ok((axum::http::StatusCode::CREATED, Json(json!({ "id": final_id }))))
+    let change = AuditChange::new("create", None, Some(&hash));
+    Ok(change.attach((axum::http::StatusCode::CREATED, format.render(json!({ "id": final_id })))))
 }
 
 /// GET /global/{kind}/{id} — get a single object.
 /// 404 if not found or if ACL check fails, to avoid leaking existence information.
 /// Supports `?with_history=true` to attach the latest history revision as `_history`.
+/// Answers with an `ETag` and honors `If-None-Match` (304, no body).
 pub async fn get_object(
     AuthenticatedUser(user_id): AuthenticatedUser,
     Path((kind, id)): Path<(String, String)>,
     Query(params): Query<GetObjectQuery>,
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
 ) -> Result<impl IntoResponse, AppError> {
     validate_kind(&kind)?;
 
@@ -205,6 +326,7 @@ pub async fn get_object(
                 return Err(AppError::not_found(format!("{}/{}", kind, id)));
             }
             let mut result = ctrl.to_external(d);
+            let etag = resource_etag(&result);
             if params.with_history.as_deref() == Some("true") {
                 if let Ok(Some(history)) = state.db.get_latest_history_entry(&kind, &id).await {
                     if let Some(obj) = result.as_object_mut() {
@@ -212,7 +334,7 @@ pub async fn get_object(
                     }
                 }
             }
-            Ok(Json(result))
+            Ok(conditional_response(&headers, &etag, Json(result)))
         }
         None => Err(AppError::not_found(format!("{}/{}", kind, id))),
     }
@@ -223,7 +345,8 @@ pub async fn upsert_object(
     AuthenticatedUser(user_id): AuthenticatedUser,
     Path((kind, id)): Path<(String, String)>,
     State(state): State<Arc<AppState>>,
-    Json(mut body): Json<Value>,
+    format: ResponseFormat,
+    JsonOrYaml(mut body): JsonOrYaml<Value>,
 ) -> Result<impl IntoResponse, AppError> {
     validate_kind(&kind)?;
 
@@ -261,34 +384,61 @@ pub async fn upsert_object(
         if !godmode && !ctrl.can_write(&user_id, existing.as_ref()).await? {
             return Err(AppError::not_found(format!("{}/{}", kind, id)));
         }
+        if let Some(ref prev) = existing {
+            stamp_update(&mut body, prev, &user_id);
+        }
     } else {
         if !godmode && !ctrl.can_create(&user_id, &body).await? {
             return Err(AppError::not_found(format!("{}/{}", kind, id)));
         }
+        if !godmode {
+            quota::check_create(&state, &kind, &user_id).await?;
+        }
         ctrl.prepare_create(&mut body, &user_id);
     }
 
     state.db.ensure_collection(&kind).await?;
 
     let mut doc = ctrl.to_internal(body, &state.auth)?;
+    validate_resource_metadata(&doc).map_err(AppError::unprocessable)?;
     // Compute and inject the desired-state hash before writing to DB.
-    let hash = compute_value_hash(&doc);
+    let hash = compute_value_hash_excluding(&doc, ctrl.hash_excluded_fields());
     if let Some(obj) = doc.as_object_mut() {
         obj.insert("hash_code".to_string(), json!(hash));
     }
+    let prev_hash = existing.as_ref().and_then(|d| d.get("hash_code")).and_then(|v| v.as_str());
+    if is_update {
+        advance_generation(&mut doc, prev_hash);
+    }
+    let action = if !is_update {
+        ApplyAction::Created
+    } else if prev_hash == Some(hash.as_str()) {
+        ApplyAction::Unchanged
+    } else {
+        ApplyAction::Updated
+    };
+
+    // Same desired state: nothing to store, so no hooks, history or watch
+    // events either, and the audit middleware leaves the request out.
+    if action == ApplyAction::Unchanged {
+        let mut response = format.render(ApplyResponse { key: id, kind, action, hash }).into_response();
+        response.extensions_mut().insert(NoChange);
+        return Ok(response);
+    }
 
     // Validate ACL principals (e.g. group members check) before writing
-    ctrl.validate_acl_principals(&doc, &state.db).await?;
+    ctrl.validate_acl_principals(&doc, &*state.db).await?;
 
     state.db.generic_upsert(&kind, &id, doc).await?;
+    state.invalidate_cached_user(&kind, &id).await;
 
     if is_update {
-        if let Err(e) = ctrl.after_update(&id, &state.db).await {
+        if let Err(e) = ctrl.after_update(&id, &*state.db).await {
             log::error!("[HANDLER] upsert_object: after_update hook failed: kind={}, id={}, error={}", kind, id, e);
             return Err(e);
         }
     } else {
-        if let Err(e) = ctrl.after_create(&id, &user_id, &state.db).await {
+        if let Err(e) = ctrl.after_create(&id, &user_id, &*state.db).await {
             log::error!("[HANDLER] upsert_object: after_create hook failed: kind={}, id={}, error={}", kind, id, e);
             return Err(e);
         }
@@ -296,12 +446,15 @@ pub async fn upsert_object(
 
     // Write history entry after upsert — non-fatal
     if let Ok(Some(snap)) = state.db.generic_get(&kind, &id).await {
-        if let Err(e) = state.db.write_history_entry(&kind, &id, snap, &user_id).await {
+        if let Err(e) = state.db.write_history_entry(&kind, &id, snap.clone(), &user_id).await {
             log::error!("[HANDLER] upsert_object: write_history_entry failed: kind={}, id={}, error={}", kind, id, e);
         }
+        let change = if is_update { ChangeType::Updated } else { ChangeType::Created };
+        state.publish_change(change, &kind, &id, snap).await;
     }
 
-    Ok(Json(json!({ "id": id })))
+    let change = AuditChange::new(if is_update { "update" } else { "create" }, prev_hash, Some(&hash));
+    Ok(change.attach(format.render(ApplyResponse { key: id, kind, action, hash })))
 }
 
 /// PUT /global/{kind}/{id} — update (fails if not exists with 404 or on update conflict with 409).
@@ -310,7 +463,8 @@ pub async fn update_object(
     AuthenticatedUser(user_id): AuthenticatedUser,
     Path((kind, id)): Path<(String, String)>,
     State(state): State<Arc<AppState>>,
-    Json(mut body): Json<Value>,
+    format: ResponseFormat,
+    JsonOrYaml(mut body): JsonOrYaml<Value>,
 ) -> Result<impl IntoResponse, AppError> {
     validate_kind(&kind)?;
 
@@ -318,9 +472,57 @@ pub async fn update_object(
         obj.insert("id".to_string(), Value::String(id.clone()));
     }
 
-    let ctrl = state.controller.for_kind(&kind);
     let existing = state.db.generic_get(&kind, &id).await?;
     let existing = existing.ok_or_else(|| AppError::not_found(format!("{}/{}", kind, id)))?;
+    let hash = replace_object(&state, &user_id, &kind, &id, &existing, body, false).await?;
+
+    let change = AuditChange::new("update", existing.get("hash_code").and_then(|v| v.as_str()), Some(&hash));
+    Ok(change.attach(format.render(json!({ "id": id }))))
+}
+
+/// PATCH /global/{kind}/{id} — apply a JSON Merge Patch (RFC 7386) to the
+/// stored document as `GET` returns it, then store the result like `PUT`.
+/// Fields the API never returns (e.g. `password_hash`) are kept. A `hash_code`
+/// in the patch is checked like `PUT`'s.
+pub async fn patch_object(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    Path((kind, id)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+    format: ResponseFormat,
+    MergePatch(patch): MergePatch,
+) -> Result<impl IntoResponse, AppError> {
+    validate_kind(&kind)?;
+
+    let existing = state.db.generic_get(&kind, &id).await?;
+    let existing = existing.ok_or_else(|| AppError::not_found(format!("{}/{}", kind, id)))?;
+
+    let mut body = state.controller.for_kind(&kind).to_external(existing.clone());
+    merge_patch(&mut body, &patch);
+    if !body.is_object() {
+        return Err(AppError::bad_request("a merge patch must leave the resource a JSON object"));
+    }
+    body["id"] = Value::String(id.clone());
+    let hash = replace_object(&state, &user_id, &kind, &id, &existing, body, true).await?;
+
+    let change = AuditChange::new("update", existing.get("hash_code").and_then(|v| v.as_str()), Some(&hash));
+    Ok(change.attach(format.render(json!({ "id": id }))))
+}
+
+/// Store `body` in place of `existing`: the `PUT` path shared with `PATCH`.
+/// Checks a client `hash_code` and write access, stamps `state`, re-hashes,
+/// then runs the update hook, writes history and notifies watchers.
+/// `keep_hidden` carries over stored fields that `to_external` hides when the
+/// new document lacks them. Returns the new `hash_code`.
+pub(crate) async fn replace_object(
+    state: &AppState,
+    user_id: &str,
+    kind: &str,
+    id: &str,
+    existing: &Value,
+    mut body: Value,
+    keep_hidden: bool,
+) -> Result<String, AppError> {
+    let ctrl = state.controller.for_kind(kind);
 
     // Extract client hash before `to_internal` consumes `body`.
     let client_hash = body
@@ -342,24 +544,38 @@ pub async fn update_object(
         }
     }
 
-    let godmode = state.has_godmode(&user_id).await.unwrap_or(false);
-    if !godmode && !ctrl.can_write(&user_id, Some(&existing)).await? {
+    let godmode = state.has_godmode(user_id).await.unwrap_or(false);
+    if !godmode && !ctrl.can_write(user_id, Some(existing)).await? {
         return Err(AppError::not_found(format!("{}/{}", kind, id)));
     }
+    stamp_update(&mut body, existing, user_id);
 
     let mut doc = ctrl.to_internal(body, &state.auth)?;
+    if keep_hidden {
+        let shown = ctrl.to_external(existing.clone());
+        if let (Some(stored), Some(obj)) = (existing.as_object(), doc.as_object_mut()) {
+            for (key, value) in stored {
+                let hidden = shown.get(key).is_none() && !matches!(key.as_str(), "_key" | "_id" | "_rev");
+                if hidden && !obj.contains_key(key) {
+                    obj.insert(key.clone(), value.clone());
+                }
+            }
+        }
+    }
+    validate_resource_metadata(&doc).map_err(AppError::unprocessable)?;
     // Compute and inject the desired-state hash before writing to DB.
-    let hash = compute_value_hash(&doc);
+    let hash = compute_value_hash_excluding(&doc, ctrl.hash_excluded_fields());
     if let Some(obj) = doc.as_object_mut() {
         obj.insert("hash_code".to_string(), json!(hash));
     }
+    advance_generation(&mut doc, existing.get("hash_code").and_then(|v| v.as_str()));
 
     // Validate ACL principals (e.g. group members check) before writing
-    ctrl.validate_acl_principals(&doc, &state.db).await?;
+    ctrl.validate_acl_principals(&doc, &*state.db).await?;
 
     state
         .db
-        .generic_update(&kind, &id, doc)
+        .generic_update(kind, id, doc)
         .await
         .map_err(|e| {
             let msg = e.to_string();
@@ -369,29 +585,34 @@ pub async fn update_object(
                 AppError::Internal(e)
             }
         })?;
+    state.invalidate_cached_user(kind, id).await;
 
-    if let Err(e) = ctrl.after_update(&id, &state.db).await {
-        log::error!("[HANDLER] update_object: after_update hook failed: kind={}, id={}, error={}", kind, id, e);
+    if let Err(e) = ctrl.after_update(id, &*state.db).await {
+        log::error!("[HANDLER] replace_object: after_update hook failed: kind={}, id={}, error={}", kind, id, e);
         return Err(e);
     }
 
     // Write history entry on update — non-fatal
-    if let Ok(Some(snap)) = state.db.generic_get(&kind, &id).await {
-        if let Err(e) = state.db.write_history_entry(&kind, &id, snap, &user_id).await {
-            log::error!("[HANDLER] update_object: write_history_entry failed: kind={}, id={}, error={}", kind, id, e);
+    if let Ok(Some(snap)) = state.db.generic_get(kind, id).await {
+        if let Err(e) = state.db.write_history_entry(kind, id, snap.clone(), user_id).await {
+            log::error!("[HANDLER] replace_object: write_history_entry failed: kind={}, id={}, error={}", kind, id, e);
         }
+        state.publish_change(ChangeType::Updated, kind, id, snap).await;
     }
 
-    Ok(Json(json!({ "id": id })))
+    Ok(hash)
 }
 
 /// DELETE /global/{kind}/{id} — delete an object.
+/// `?cascade=delete|reassign[&reassign_to=<id>]` decides what happens to resources it owns.
 pub async fn delete_object(
     AuthenticatedUser(user_id): AuthenticatedUser,
     Path((kind, id)): Path<(String, String)>,
+    Query(query): Query<DeleteQuery>,
     State(state): State<Arc<AppState>>,
 ) -> Result<impl IntoResponse, AppError> {
     validate_kind(&kind)?;
+    let policy = query.policy()?;
 
     let ctrl = state.controller.for_kind(&kind);
     let existing = state.db.generic_get(&kind, &id).await?;
@@ -402,25 +623,16 @@ pub async fn delete_object(
         return Err(AppError::not_found(format!("{}/{}", kind, id)));
     }
 
-    state
-        .db
-        .generic_soft_delete(&kind, &id, &user_id)
-        .await
-        .map_err(|e| {
-            let msg = e.to_string();
-            if msg.contains("not found or already deleted") {
-                AppError::not_found(format!("{}/{}", kind, id))
-            } else {
-                AppError::Internal(e)
-            }
-        })?;
+    let cascaded = delete_cascading(ctrl, &*state.db, &kind, &id, &user_id, &policy).await?;
+    state.invalidate_cached_user(&kind, &id).await;
 
-    if let Err(e) = ctrl.after_delete(&id, &state.db).await {
-        log::error!("[HANDLER] delete_object: after_delete hook failed: kind={}, id={}, error={}", kind, id, e);
-        return Err(e);
+    let change = AuditChange::new("delete", existing.get("hash_code").and_then(|v| v.as_str()), None);
+    for c in cascaded {
+        state.publish_change(c.change, c.kind, &c.id, c.doc).await;
     }
+    state.publish_change(ChangeType::Deleted, &kind, &id, existing).await;
 
-    Ok(axum::http::StatusCode::NO_CONTENT)
+    Ok(change.attach(axum::http::StatusCode::NO_CONTENT))
 }
 
 /// GET /global/{kind}/search?startwith={prefix} — quick prefix search on _key.
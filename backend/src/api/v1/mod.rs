@@ -1,7 +1,12 @@
+pub mod adm;
 pub mod authentication;
 pub mod debug;
 pub mod gitops;
+pub mod ops;
+pub mod personal;
 pub mod scoped_gitops;
+pub mod search;
 pub mod static_files;
+pub mod system;
 pub mod upload;
 pub mod ws;
\ No newline at end of file
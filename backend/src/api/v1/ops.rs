@@ -0,0 +1,761 @@
+use std::{convert::Infallible, sync::Arc, time::Duration};
+
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{
+        IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+};
+use futures_util::{Stream, stream};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tokio::sync::broadcast::error::RecvError;
+
+use crit_shared::compute_value_hash_excluding;
+use crit_shared::data_models::Project;
+use crit_shared::requests::{KindInfo, ListResponse};
+use crit_shared::util_models::{PrincipalId, ProjectRole, super_permissions};
+
+use crate::{
+    api::v1::{
+        gitops::{ListQuery, list_page, replace_object, validate_kind},
+        scoped_gitops::{resolve_auth, validate_project},
+    },
+    controllers::{
+        Controller,
+        gitops_controller::{KindController, advance_generation},
+        membership_controller::MissingPrincipals,
+        project_controller::ProjectController,
+    },
+    db::{MemberFilter, arangodb::collection_for_principal},
+    error::AppError,
+    middleware::{AuditChange, auth::AuthenticatedUser},
+    reconcile::ReconcileStatus,
+    state::AppState,
+    validation::metadata::check_client_key,
+    watch::ChangeType,
+};
+
+/// Interval between SSE keep-alive comments on the watch stream.
+const WATCH_KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+#[derive(Deserialize)]
+pub struct LongPollQuery {
+    /// `version` of the caller's previous list; absent on the first poll.
+    pub since: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CountQuery {
+    /// Count within this project; required for project-scoped kinds.
+    pub project: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SetMemberRequest {
+    pub principal: String,
+    pub role: ProjectRole,
+}
+
+/// One entry of `GET /v1/ops/projects/{project}/members`.
+#[derive(Serialize)]
+pub struct ProjectMember {
+    pub principal: String,
+    pub role: ProjectRole,
+    /// List view of the principal; `None` if it no longer exists.
+    pub brief: Option<Value>,
+}
+
+/// Body of `POST /v1/ops/groups/{group}/members`: one principal, or an array
+/// of principal ids to add in one write.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum AddGroupMemberRequest {
+    One { principal: String },
+    Many(Vec<String>),
+}
+
+#[derive(Deserialize)]
+pub struct AddGroupMembersQuery {
+    /// `reject` (default) or `skip` principals that do not exist.
+    #[serde(default)]
+    pub missing: MissingPrincipals,
+}
+
+/// Principals accepted by one bulk `POST /v1/ops/groups/{group}/members`.
+const MAX_BULK_MEMBERS: usize = 1000;
+
+/// Body of `PATCH /v1/ops/annotate|label/{kind}/{key}`.
+#[derive(Deserialize)]
+pub struct MetadataEdit {
+    #[serde(default)]
+    pub set: std::collections::BTreeMap<String, String>,
+    #[serde(default)]
+    pub remove: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct GroupMembersQuery {
+    /// `users`, `groups` or `all` (the default).
+    #[serde(rename = "type", default)]
+    pub filter: MemberFilter,
+    #[serde(default)]
+    pub offset: usize,
+    /// Every member from `offset` on when absent.
+    pub limit: Option<usize>,
+}
+
+/// One entry of `GET /v1/ops/groups/{group}/members`.
+#[derive(Serialize)]
+pub struct GroupMember {
+    pub principal: String,
+    /// List view of the principal; `None` if it no longer exists.
+    pub brief: Option<Value>,
+}
+
+/// One field of `KindDescription::fields`.
+#[derive(Serialize)]
+pub struct FieldDescription {
+    pub name: &'static str,
+    /// Rust type as written in the model, e.g. `Option<String>`.
+    pub rust_type: &'static str,
+    pub optional: bool,
+    /// Part of the list (brief) view.
+    pub brief: bool,
+}
+
+/// `GET /v1/ops/describe/{kind}` response.
+#[derive(Serialize)]
+pub struct KindDescription {
+    pub kind: &'static str,
+    pub model: &'static str,
+    pub id_prefix: &'static str,
+    pub scoped: bool,
+    /// Served by a dedicated `KindController` rather than the default one.
+    pub dedicated_controller: bool,
+    /// Storage backend and the collection holding the kind.
+    pub backend: &'static str,
+    pub collection: &'static str,
+    pub fields: Vec<FieldDescription>,
+    /// Live (not soft-deleted) documents, regardless of ACLs.
+    pub count: u64,
+}
+
+/// Schema, storage and size of any kind in `state.resources`.
+///
+/// `GET /v1/ops/describe/{kind}` — 404 listing the known kinds otherwise.
+/// Requires ADM_GODMODE (enforced by `godmode_middleware` on the route group).
+pub async fn describe_kind(
+    Path(kind): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<KindDescription>, AppError> {
+    validate_kind(&kind)?;
+    let Some(descriptor) = state.resources.get(&kind) else {
+        let known: Vec<&str> = state.resources.kinds().collect();
+        return Err(AppError::not_found(format!(
+            "unknown kind '{}'; known kinds: {}",
+            kind,
+            known.join(", ")
+        )));
+    };
+    let ctrl = state.controller.for_kind(descriptor.kind);
+    state.db.ensure_collection(descriptor.kind).await?;
+    let count = state
+        .db
+        .generic_count_acl(descriptor.kind, &[], ctrl.read_permission_bits(), true)
+        .await?;
+
+    Ok(Json(KindDescription {
+        kind: descriptor.kind,
+        model: descriptor.model,
+        id_prefix: descriptor.id_prefix,
+        scoped: ctrl.is_scoped(),
+        dedicated_controller: Controller::registered_kinds().any(|k| k == descriptor.kind),
+        backend: "arangodb",
+        collection: descriptor.kind,
+        fields: descriptor
+            .fields
+            .iter()
+            .map(|f| FieldDescription {
+                name: f.name,
+                rust_type: f.rust_type,
+                optional: f.optional,
+                brief: f.brief,
+            })
+            .collect(),
+        count,
+    }))
+}
+
+/// Every kind the server serves — those with a dedicated controller and those
+/// described in `state.resources` — sorted, for clients to validate kind
+/// arguments and complete them.
+///
+/// `can_list` is decided for the caller with the same rule as `list_objects`:
+/// godmode or the kind's super-permission (none means permissive), otherwise
+/// at least one document passing the read ACL. Scoped kinds are listed per
+/// project and are never globally listable.
+///
+/// `GET /v1/ops/kinds` — any authenticated user.
+pub async fn list_kinds(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListResponse<KindInfo>>, AppError> {
+    let godmode = state.has_godmode(&user_id).await.unwrap_or(false);
+    let principals = state.get_cached_principals(&user_id).await?;
+
+    let mut kinds: Vec<&'static str> = Controller::registered_kinds().chain(state.resources.kinds()).collect();
+    kinds.sort_unstable();
+    kinds.dedup();
+
+    let mut items = Vec::with_capacity(kinds.len());
+    for kind in kinds {
+        let ctrl = state.controller.for_kind(kind);
+        let scoped = ctrl.is_scoped();
+        let can_list = !scoped && {
+            let super_bypass = godmode
+                || match ctrl.super_permission() {
+                    Some(perm) => state.db.has_permission_with_principals(&principals, perm).await?,
+                    None => true,
+                };
+            super_bypass || {
+                state.db.ensure_collection(kind).await?;
+                state
+                    .db
+                    .generic_count_acl(kind, &principals, ctrl.read_permission_bits(), false)
+                    .await?
+                    > 0
+            }
+        };
+        let descriptor = state.resources.get(kind);
+        items.push(KindInfo {
+            name: kind.to_string(),
+            scoped,
+            id_prefix: descriptor.map(|d| d.id_prefix.to_string()).unwrap_or_default(),
+            brief_fields: descriptor
+                .map(|d| d.fields.iter().filter(|f| f.brief).map(|f| f.name.to_string()).collect())
+                .unwrap_or_default(),
+            aliases: descriptor.map(|d| d.aliases()).unwrap_or_default(),
+            key_field: "id".to_string(),
+            can_list,
+        });
+    }
+    Ok(Json(ListResponse::complete(items)))
+}
+
+/// Rescan the kind behind secondary index `name` and replace its contents.
+///
+/// `POST /v1/ops/indexes/{name}/rebuild` → `{ "index": name, "scanned": n }`;
+/// 404 listing the known indexes otherwise.
+/// Requires ADM_GODMODE (enforced by `godmode_middleware` on the route group).
+pub async fn rebuild_index(
+    Path(name): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Value>, AppError> {
+    let Some(scanned) = state.indexes.rebuild(&name, state.db.as_ref()).await? else {
+        let known: Vec<&str> = state.indexes.names().collect();
+        return Err(AppError::not_found(format!(
+            "unknown index '{}'; known indexes: {}",
+            name,
+            known.join(", ")
+        )));
+    };
+    log::info!("[INDEX] {} rebuilt on request from {} documents", name, scanned);
+    Ok(Json(json!({ "index": name, "scanned": scanned })))
+}
+
+/// Run a reconcile pass over every document of `kind` and return its outcome.
+///
+/// `POST /v1/ops/reconcile/{kind}`
+/// Requires ADM_GODMODE (enforced by `godmode_middleware` on the route group).
+pub async fn trigger_reconcile(
+    Path(kind): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ReconcileStatus>, AppError> {
+    validate_kind(&kind)?;
+    let ctrl = state.controller.for_kind(&kind);
+    let status = state.reconciler.run_kind(&kind, ctrl, &state.db).await?;
+    Ok(Json(status))
+}
+
+/// Outcome of the last reconcile pass for `kind`.
+///
+/// `GET /v1/ops/reconcile/{kind}` — 404 if no pass has run since startup.
+pub async fn reconcile_status(
+    Path(kind): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ReconcileStatus>, AppError> {
+    validate_kind(&kind)?;
+    state
+        .reconciler
+        .status(&kind)
+        .await
+        .map(Json)
+        .ok_or_else(|| AppError::not_found(format!("no reconcile pass has run for {}", kind)))
+}
+
+/// Number of objects of `kind` the caller can see, without listing them.
+///
+/// `GET /v1/ops/count/{kind}[?project=<id>]` → `{ "kind": ..., "count": n }`
+/// Uses the same ACL rules as the corresponding list endpoint.
+pub async fn count_objects(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    Path(kind): Path<String>,
+    Query(query): Query<CountQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Value>, AppError> {
+    validate_kind(&kind)?;
+    let ctrl = state.controller.for_kind(&kind);
+    state.db.ensure_collection(&kind).await?;
+
+    let count = match query.project {
+        Some(project) => {
+            if !ctrl.is_scoped() {
+                return Err(AppError::bad_request(format!(
+                    "'{}' is not a project-scoped resource kind",
+                    kind
+                )));
+            }
+            validate_project(&state, &project).await?;
+            let (principals, super_bypass) =
+                resolve_auth(&state, &user_id, ctrl.super_permission()).await?;
+            state
+                .db
+                .generic_count_scoped(&kind, &project, &principals, ctrl.read_permission_bits(), super_bypass)
+                .await?
+        }
+        None => {
+            // Same bypass rule as `list_objects`: no super-permission means permissive.
+            let godmode = state.has_godmode(&user_id).await.unwrap_or(false);
+            let principals = state.get_cached_principals(&user_id).await?;
+            let super_bypass = godmode
+                || match ctrl.super_permission() {
+                    Some(perm) => state.db.has_permission_with_principals(&principals, perm).await?,
+                    None => true,
+                };
+            state
+                .db
+                .generic_count_acl(&kind, &principals, ctrl.read_permission_bits(), super_bypass)
+                .await?
+        }
+    };
+
+    Ok(Json(json!({ "kind": kind, "count": count })))
+}
+
+/// Fetch a project the caller can read; 404 otherwise (no existence leak).
+async fn readable_project(state: &AppState, user_id: &str, project: &str) -> Result<Value, AppError> {
+    let not_found = || AppError::not_found(format!("projects/{}", project));
+    let doc = state.db.generic_get("projects", project).await?.ok_or_else(not_found)?;
+    let godmode = state.has_godmode(user_id).await.unwrap_or(false);
+    if !godmode && !state.controller.project.can_read(user_id, Some(&doc)).await? {
+        return Err(not_found());
+    }
+    Ok(doc)
+}
+
+/// Role changes need Admin on the project; granting or revoking Owner needs Owner.
+/// Godmode and the project super-permission bypass both checks.
+async fn authorize_role_change(
+    state: &AppState,
+    user_id: &str,
+    doc: &Value,
+    target: &str,
+    new_role: Option<ProjectRole>,
+) -> Result<(), AppError> {
+    if state.has_godmode(user_id).await.unwrap_or(false) {
+        return Ok(());
+    }
+    let principals = state.get_cached_principals(user_id).await?;
+    if state
+        .db
+        .has_permission_with_principals(&principals, super_permissions::ADM_CONFIG_EDITOR)
+        .await?
+    {
+        return Ok(());
+    }
+    let actor = ProjectController::role_of(doc, &principals);
+    let current = ProjectController::role_of(doc, &[target.to_string()]);
+    let touches_owner =
+        current == Some(ProjectRole::Owner) || new_role == Some(ProjectRole::Owner);
+    let required = if touches_owner { ProjectRole::Owner } else { ProjectRole::Admin };
+    if actor < Some(required) {
+        return Err(AppError::forbidden(format!(
+            "changing project roles requires the {:?} role",
+            required
+        )));
+    }
+    Ok(())
+}
+
+/// Persist a role change: re-stamp `hash_code`, write history, notify watchers.
+async fn save_project(state: &AppState, user_id: &str, project: &str, mut doc: Value) -> Result<(), AppError> {
+    if let Some(obj) = doc.as_object_mut() {
+        obj.remove("_id");
+        obj.remove("_rev");
+    }
+    let prev_hash = doc.get("hash_code").and_then(|v| v.as_str()).map(String::from);
+    let hash = compute_value_hash_excluding(&doc, Project::hash_excluded_fields());
+    if let Some(obj) = doc.as_object_mut() {
+        obj.insert("hash_code".to_string(), json!(hash));
+    }
+    advance_generation(&mut doc, prev_hash.as_deref());
+    state.db.generic_update("projects", project, doc).await?;
+    if let Ok(Some(snap)) = state.db.generic_get("projects", project).await {
+        if let Err(e) = state.db.write_history_entry("projects", project, snap.clone(), user_id).await {
+            log::error!("[HANDLER] save_project: write_history_entry failed: project={}, error={}", project, e);
+        }
+        state.publish_change(ChangeType::Updated, "projects", project, snap).await;
+    }
+    Ok(())
+}
+
+/// List project members with their roles and a brief of each principal.
+///
+/// `GET /v1/ops/projects/{project}/members`
+pub async fn list_project_members(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    Path(project): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListResponse<ProjectMember>>, AppError> {
+    let doc = readable_project(&state, &user_id, &project).await?;
+    let mut items = Vec::new();
+    for (principal, role) in ProjectController::members(&doc) {
+        let kind = collection_for_principal(&principal);
+        let brief = state
+            .db
+            .generic_get(kind, &principal)
+            .await?
+            .map(|d| state.controller.for_kind(kind).to_list_external(d));
+        items.push(ProjectMember { principal, role, brief });
+    }
+    Ok(Json(ListResponse::complete(items)))
+}
+
+/// Add a member or change their role.
+///
+/// `POST /v1/ops/projects/{project}/members` with `{ "principal": "u_bob", "role": "member" }`
+pub async fn set_project_member(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    Path(project): Path<String>,
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<SetMemberRequest>,
+) -> Result<Json<Value>, AppError> {
+    let principal: PrincipalId = req.principal.parse().map_err(AppError::bad_request)?;
+    let mut doc = readable_project(&state, &user_id, &project).await?;
+    authorize_role_change(&state, &user_id, &doc, &principal, Some(req.role)).await?;
+
+    let kind = collection_for_principal(&principal);
+    if state.db.generic_get(kind, &principal).await?.is_none() {
+        return Err(AppError::not_found(format!("{}/{}", kind, principal)));
+    }
+
+    ProjectController::set_member_role(&mut doc, &principal, Some(req.role))?;
+    save_project(&state, &user_id, &project, doc).await?;
+    Ok(Json(json!({ "principal": principal, "role": req.role })))
+}
+
+/// Remove a member from the project.
+///
+/// `DELETE /v1/ops/projects/{project}/members/{principal}`
+pub async fn remove_project_member(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    Path((project, principal)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> Result<StatusCode, AppError> {
+    let mut doc = readable_project(&state, &user_id, &project).await?;
+    if ProjectController::role_of(&doc, std::slice::from_ref(&principal)).is_none() {
+        return Err(AppError::not_found(format!("member {} of projects/{}", principal, project)));
+    }
+    authorize_role_change(&state, &user_id, &doc, &principal, None).await?;
+
+    ProjectController::set_member_role(&mut doc, &principal, None)?;
+    save_project(&state, &user_id, &project, doc).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Fetch a group the caller can read; 404 otherwise (no existence leak).
+async fn readable_group(state: &AppState, user_id: &str, group: &str) -> Result<Value, AppError> {
+    let not_found = || AppError::not_found(format!("groups/{}", group));
+    let doc = state.db.generic_get("groups", group).await?.ok_or_else(not_found)?;
+    let godmode = state.has_godmode(user_id).await.unwrap_or(false);
+    if !godmode && !state.controller.group.can_read(user_id, Some(&doc)).await? {
+        return Err(not_found());
+    }
+    Ok(doc)
+}
+
+/// Member changes need MODIFY on the group or ADM_USER_MANAGER, the same rule
+/// as writing `memberships` through the gitops API.
+async fn authorize_member_change(state: &AppState, user_id: &str, group: &str) -> Result<(), AppError> {
+    if state.has_godmode(user_id).await.unwrap_or(false)
+        || state.controller.membership.can_create(user_id, &json!({ "group": group })).await?
+    {
+        return Ok(());
+    }
+    Err(AppError::forbidden(format!("changing members of groups/{} requires MODIFY on the group", group)))
+}
+
+/// List the direct members of a group with a brief of each principal,
+/// sorted by id. `type` keeps only `users` or `groups`; `offset` and `limit`
+/// page through the result, and `total` counts every member `type` keeps.
+///
+/// `GET /v1/ops/groups/{group}/members?type=users&offset=0&limit=50`
+pub async fn list_group_members(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    Path(group): Path<String>,
+    Query(query): Query<GroupMembersQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListResponse<GroupMember>>, AppError> {
+    readable_group(&state, &user_id, &group).await?;
+    let page = state.db.list_members(&group, query.filter, query.offset, query.limit).await?;
+    let mut items = Vec::new();
+    for principal in page.members {
+        let kind = collection_for_principal(&principal);
+        let brief = state
+            .db
+            .generic_get(kind, &principal)
+            .await?
+            .map(|d| state.controller.for_kind(kind).to_list_external(d));
+        items.push(GroupMember { principal, brief });
+    }
+    let total = page.total as usize;
+    let limit = query.limit.unwrap_or(total.saturating_sub(query.offset));
+    Ok(Json(ListResponse { items, total, offset: query.offset, limit }))
+}
+
+/// Add a user, group or account to a group. 201 when added, 200 when it
+/// already was a member.
+///
+/// `POST /v1/ops/groups/{group}/members` with `{ "principal": "u_bob" }`
+///
+/// With an array body (`["u_bob", "g_ops"]`) all new members are added in one
+/// transaction and the response lists `added`, `already_members` and `skipped`;
+/// 201 when anyone was added. `?missing=skip` adds the existing principals
+/// instead of failing the whole batch with 404.
+pub async fn add_group_member(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    Path(group): Path<String>,
+    Query(query): Query<AddGroupMembersQuery>,
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<AddGroupMemberRequest>,
+) -> Result<(StatusCode, Json<Value>), AppError> {
+    let principals = match &req {
+        AddGroupMemberRequest::One { principal } => std::slice::from_ref(principal),
+        AddGroupMemberRequest::Many(principals) if principals.is_empty() => {
+            return Err(AppError::bad_request("no principals given"));
+        }
+        AddGroupMemberRequest::Many(principals) if principals.len() > MAX_BULK_MEMBERS => {
+            return Err(AppError::bad_request(format!(
+                "at most {} principals can be added at once",
+                MAX_BULK_MEMBERS
+            )));
+        }
+        AddGroupMemberRequest::Many(principals) => principals.as_slice(),
+    };
+    readable_group(&state, &user_id, &group).await?;
+    authorize_member_change(&state, &user_id, &group).await?;
+
+    let outcome = state.controller.membership.add_many(&user_id, &group, principals, query.missing).await?;
+    for principal in &outcome.added {
+        let key = format!("{}::{}", principal, group);
+        if let Some(doc) = state.db.generic_get("memberships", &key).await? {
+            state.publish_change(ChangeType::Created, "memberships", &key, doc).await;
+        }
+    }
+    let status = if outcome.added.is_empty() { StatusCode::OK } else { StatusCode::CREATED };
+    let body = match req {
+        AddGroupMemberRequest::One { principal } => json!({ "principal": principal, "group": group }),
+        AddGroupMemberRequest::Many(_) => json!({
+            "group": group,
+            "added": outcome.added,
+            "already_members": outcome.already_members,
+            "skipped": outcome.skipped,
+        }),
+    };
+    Ok((status, Json(body)))
+}
+
+/// Remove a direct member from a group. Like deleting the membership through
+/// the gitops API, removing the last member deletes the group.
+///
+/// `DELETE /v1/ops/groups/{group}/members/{principal}`
+pub async fn remove_group_member(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    Path((group, principal)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+) -> Result<StatusCode, AppError> {
+    readable_group(&state, &user_id, &group).await?;
+    let key = format!("{}::{}", principal, group);
+    let Some(existing) = state.db.generic_get("memberships", &key).await? else {
+        return Err(AppError::not_found(format!("member {} of groups/{}", principal, group)));
+    };
+    authorize_member_change(&state, &user_id, &group).await?;
+
+    state.db.remove_principal_from_group(&principal, &group, None).await?;
+    state.controller.membership.after_delete(&key, &*state.db).await?;
+    state.publish_change(ChangeType::Deleted, "memberships", &key, existing).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Set or remove annotations without sending the whole resource.
+///
+/// `PATCH /v1/ops/annotate/{kind}/{key}` with `{ "set": { "k": "v" }, "remove": ["k2"] }`
+pub async fn annotate_object(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    Path((kind, key)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+    Json(edit): Json<MetadataEdit>,
+) -> Result<Response, AppError> {
+    edit_metadata(&state, &user_id, &kind, &key, "annotations", edit).await
+}
+
+/// Like `annotate_object`, for `labels`; values follow the label rules.
+///
+/// `PATCH /v1/ops/label/{kind}/{key}`
+pub async fn label_object(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    Path((kind, key)): Path<(String, String)>,
+    State(state): State<Arc<AppState>>,
+    Json(edit): Json<MetadataEdit>,
+) -> Result<Response, AppError> {
+    edit_metadata(&state, &user_id, &kind, &key, "labels", edit).await
+}
+
+/// Apply `edit` to one metadata map of a global resource and store it through
+/// the `PUT` path, which checks write access, validates the metadata, stamps
+/// `state` and re-hashes. Keys under the reserved prefix get 422.
+async fn edit_metadata(
+    state: &AppState,
+    user_id: &str,
+    kind: &str,
+    id: &str,
+    field: &'static str,
+    edit: MetadataEdit,
+) -> Result<Response, AppError> {
+    validate_kind(kind)?;
+    if edit.set.is_empty() && edit.remove.is_empty() {
+        return Err(AppError::bad_request("nothing to change: give `set` and/or `remove`"));
+    }
+    if let Some(key) = edit.remove.iter().find(|k| edit.set.contains_key(*k)) {
+        return Err(AppError::bad_request(format!("'{}' is both set and removed", key)));
+    }
+    for key in edit.set.keys().chain(&edit.remove) {
+        check_client_key(key).map_err(AppError::unprocessable)?;
+    }
+
+    let existing = state
+        .db
+        .generic_get(kind, id)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("{}/{}", kind, id)))?;
+    let mut body = state.controller.for_kind(kind).to_external(existing.clone());
+    let mut map = body.get(field).and_then(Value::as_object).cloned().unwrap_or_default();
+    for key in &edit.remove {
+        map.remove(key);
+    }
+    for (key, value) in edit.set {
+        map.insert(key, Value::String(value));
+    }
+    body[field] = Value::Object(map.clone());
+
+    let hash = replace_object(state, user_id, kind, id, &existing, body, true).await?;
+    let action = if field == "labels" { "label" } else { "annotate" };
+    let change = AuditChange::new(action, existing.get("hash_code").and_then(|v| v.as_str()), Some(&hash));
+    Ok(change.attach(Json(json!({ "id": id, field: map, "hash_code": hash })).into_response()))
+}
+
+/// GET /ops/list/{kind} — long-poll list. Takes the list endpoint's `limit`,
+/// `cursor` and `fields`, and adds a `version` token to the page. With
+/// `?since=<version>` it blocks until the kind changes, then returns the new
+/// page, or `304` once `LONG_POLL_TIMEOUT_SECS` pass without a change.
+pub async fn long_poll_objects(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    Path(kind): Path<String>,
+    Query(list): Query<ListQuery>,
+    Query(poll): Query<LongPollQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, AppError> {
+    validate_kind(&kind)?;
+    let Some(version) = wait_for_version_change(&state, &kind, poll.since.as_deref()).await else {
+        return Ok(StatusCode::NOT_MODIFIED.into_response());
+    };
+    let mut response = list_page(&state, &user_id, &kind, &list).await?;
+    response["version"] = Value::String(version);
+    Ok(Json(response).into_response())
+}
+
+/// Block until the version of `kind` differs from `since`. Returns the new
+/// version, or `None` if the long-poll timeout elapsed first. Without `since`
+/// (first poll) the current version is returned immediately. Versions count
+/// the writes published by this replica, so writes through other replicas
+/// neither change them nor end the wait.
+async fn wait_for_version_change(state: &AppState, kind: &str, since: Option<&str>) -> Option<String> {
+    // Subscribe before reading the version so no change can slip in between.
+    let mut rx = state.watch.subscribe(kind).await;
+    let current = state.watch.version(kind);
+    if since.is_none_or(|since| since != current) {
+        return Some(current);
+    }
+
+    let deadline =
+        tokio::time::Instant::now() + Duration::from_secs(state.config.long_poll_timeout_secs);
+    match tokio::time::timeout_at(deadline, rx.recv()).await {
+        Err(_) | Ok(Err(RecvError::Closed)) => None,
+        Ok(Ok(_)) | Ok(Err(RecvError::Lagged(_))) => Some(state.watch.version(kind)),
+    }
+}
+
+/// GET /ops/watch/{kind} — Server-Sent Events stream of changes to this kind.
+/// Emits `created` / `updated` / `deleted` events carrying `{ id, object }` where
+/// `object` is the brief (list) view. Events for objects the caller cannot read
+/// are skipped. No backfill: only changes after the connection opens are sent.
+/// The subscription is dropped together with the stream when the client disconnects.
+pub async fn watch_objects(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    Path(kind): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    validate_kind(&kind)?;
+
+    let godmode = state.has_godmode(&user_id).await.unwrap_or(false);
+    let rx = state.watch.subscribe(&kind).await;
+
+    let events = stream::unfold(rx, move |mut rx| {
+        let state = state.clone();
+        let user_id = user_id.clone();
+        async move {
+            loop {
+                let ev = match rx.recv().await {
+                    Ok(ev) => ev,
+                    Err(RecvError::Lagged(skipped)) => {
+                        log::warn!("[WATCH] subscriber for {} lagged, skipped {} events", user_id, skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return None,
+                };
+
+                let ctrl = state.controller.for_kind(&ev.kind);
+                let readable = godmode
+                    || ctrl.can_read(&user_id, Some(&ev.doc)).await.unwrap_or(false);
+                if !readable {
+                    continue;
+                }
+
+                let payload = json!({
+                    "id": ev.id,
+                    "object": ctrl.to_list_external(ev.doc),
+                });
+                let event = Event::default()
+                    .event(ev.change.as_str())
+                    .data(payload.to_string());
+                return Some((Ok(event), rx));
+            }
+        }
+    });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::new().interval(WATCH_KEEP_ALIVE)))
+}
@@ -0,0 +1,20 @@
+use std::sync::Arc;
+
+use axum::{Json, extract::State};
+use serde_json::{Value, json};
+
+use crate::{error::AppError, middleware::auth::AuthenticatedUser, quota, state::AppState};
+
+/// The caller's usage against each of their quotas, for the dashboard.
+///
+/// `GET /v1/personal/quota` →
+/// `{ "exempt": false, "items": [{ "quota": "owned_projects", "current": 2, "limit": 5 }] }`;
+/// `limit` is `null` when unlimited. Admins are `exempt`: their creates skip quota checks.
+pub async fn my_quota(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Value>, AppError> {
+    let exempt = state.has_godmode(&user_id).await.unwrap_or(false);
+    let items = quota::usage(&state, &user_id).await?;
+    Ok(Json(json!({ "exempt": exempt, "items": items })))
+}
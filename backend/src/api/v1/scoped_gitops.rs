@@ -3,29 +3,34 @@ use std::sync::Arc;
 use axum::{
     Json,
     extract::{Path, Query, State},
+    http::HeaderMap,
     response::IntoResponse,
 };
 use serde_json::{Value, json};
 
 use crate::{
-    controllers::gitops_controller::parse_acl,
+    api::extract::{JsonOrYaml, ResponseFormat},
+    cache::{conditional_response, list_etag, resource_etag},
+    controllers::gitops_controller::{parse_acl, stamp_update},
     error::AppError,
-    middleware::auth::AuthenticatedUser,
+    middleware::{AuditChange, auth::AuthenticatedUser},
     state::AppState,
+    validation::metadata::validate_resource_metadata,
+    watch::ChangeType,
 };
 use crit_shared::util_models::Permissions;
 
-use super::gitops::{ListQuery, validate_kind};
+use super::gitops::{ListQuery, list_items, validate_kind};
 
 /// Validate that a project exists and is not deleted. Returns the project doc.
-async fn validate_project(state: &AppState, project_id: &str) -> Result<Value, AppError> {
+pub(crate) async fn validate_project(state: &AppState, project_id: &str) -> Result<Value, AppError> {
     let project = state.db.generic_get("projects", project_id).await?;
     project.ok_or_else(|| AppError::not_found(format!("projects/{}", project_id)))
 }
 
 /// Resolve user principals and check super-permission bypass for a controller.
 /// Also checks godmode — if the user has ADM_GODMODE, super_bypass is always true.
-async fn resolve_auth(
+pub(crate) async fn resolve_auth(
     state: &AppState,
     user_id: &str,
     super_perm: Option<&str>,
@@ -43,13 +48,17 @@ async fn resolve_auth(
 }
 
 /// GET /v1/projects/{project}/{kind}
+/// Takes the same `limit`, `cursor` and `fields` parameters as the global list.
+/// Answers with an `ETag` and honors `If-None-Match`.
 pub async fn list_scoped_objects(
     AuthenticatedUser(user_id): AuthenticatedUser,
     Path((project_id, kind)): Path<(String, String)>,
     Query(query): Query<ListQuery>,
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
 ) -> Result<impl IntoResponse, AppError> {
     validate_kind(&kind)?;
+    let fields = query.field_paths()?;
     let _project_doc = validate_project(&state, &project_id).await?;
 
     let ctrl = state.controller.for_kind(&kind);
@@ -74,19 +83,16 @@ pub async fn list_scoped_objects(
             &principals,
             ctrl.read_permission_bits(),
             super_bypass,
-            ctrl.list_projection_fields(),
+            if fields.is_some() { None } else { ctrl.list_projection_fields() },
             query.limit,
             query.cursor.as_deref(),
         )
         .await?;
 
-    let filtered: Vec<Value> = result
-        .docs
-        .into_iter()
-        .map(|doc| ctrl.to_list_external(doc))
-        .collect();
+    let filtered = list_items(ctrl, result.docs, fields.as_deref());
 
-    if query.limit.is_some() {
+    let etag = list_etag(&filtered);
+    let response = if query.limit.is_some() {
         let mut response = json!({
             "items": filtered,
             "has_more": result.has_more,
@@ -94,17 +100,20 @@ pub async fn list_scoped_objects(
         if let Some(cursor) = result.next_cursor {
             response["next_cursor"] = Value::String(cursor);
         }
-        Ok(Json(response))
+        response
     } else {
-        Ok(Json(json!({ "items": filtered })))
-    }
+        json!({ "items": filtered })
+    };
+    Ok(conditional_response(&headers, &etag, Json(response)))
 }
 
 /// GET /v1/projects/{project}/{kind}/{id}
+/// Answers with an `ETag` and honors `If-None-Match`.
 pub async fn get_scoped_object(
     AuthenticatedUser(user_id): AuthenticatedUser,
     Path((project_id, kind, id)): Path<(String, String, String)>,
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
 ) -> Result<impl IntoResponse, AppError> {
     validate_kind(&kind)?;
     let project_doc = validate_project(&state, &project_id).await?;
@@ -134,7 +143,8 @@ pub async fn get_scoped_object(
                 }
             }
 
-            Ok(Json(ctrl.to_external(d)))
+            let result = ctrl.to_external(d);
+            Ok(conditional_response(&headers, &resource_etag(&result), Json(result)))
         }
         None => Err(AppError::not_found(format!("{}/{}", kind, id))),
     }
@@ -145,7 +155,8 @@ pub async fn create_scoped_object(
     AuthenticatedUser(user_id): AuthenticatedUser,
     Path((project_id, kind)): Path<(String, String)>,
     State(state): State<Arc<AppState>>,
-    Json(mut body): Json<Value>,
+    format: ResponseFormat,
+    JsonOrYaml(mut body): JsonOrYaml<Value>,
 ) -> Result<impl IntoResponse, AppError> {
     validate_kind(&kind)?;
     let project_doc = validate_project(&state, &project_id).await?;
@@ -190,6 +201,7 @@ pub async fn create_scoped_object(
     state.db.ensure_collection(&kind).await?;
 
     let doc = ctrl.to_internal(body, &state.auth)?;
+    validate_resource_metadata(&doc).map_err(AppError::unprocessable)?;
     state.db.generic_create(&kind, doc).await.map_err(|e| {
         let msg = e.to_string();
         if msg.contains("unique constraint") || msg.contains("1210") {
@@ -199,9 +211,13 @@ pub async fn create_scoped_object(
         }
     })?;
 
-    ctrl.after_create(&id, &user_id, &state.db).await?;
+    ctrl.after_create(&id, &user_id, &*state.db).await?;
+    if let Ok(Some(snap)) = state.db.generic_get(&kind, &id).await {
+        state.publish_change(ChangeType::Created, &kind, &id, snap).await;
+    }
 
-    Ok((axum::http::StatusCode::CREATED, Json(json!({ "id": id }))))
+    let change = AuditChange::new("create", None, None);
+    Ok(change.attach((axum::http::StatusCode::CREATED, format.render(json!({ "id": id })))))
 }
 
 /// PUT /v1/projects/{project}/{kind}/{id}
@@ -209,7 +225,8 @@ pub async fn update_scoped_object(
     AuthenticatedUser(user_id): AuthenticatedUser,
     Path((project_id, kind, id)): Path<(String, String, String)>,
     State(state): State<Arc<AppState>>,
-    Json(mut body): Json<Value>,
+    format: ResponseFormat,
+    JsonOrYaml(mut body): JsonOrYaml<Value>,
 ) -> Result<impl IntoResponse, AppError> {
     validate_kind(&kind)?;
     let project_doc = validate_project(&state, &project_id).await?;
@@ -251,8 +268,10 @@ pub async fn update_scoped_object(
             Value::String(project_id.clone()),
         );
     }
+    stamp_update(&mut body, &existing, &user_id);
 
     let doc = ctrl.to_internal(body, &state.auth)?;
+    validate_resource_metadata(&doc).map_err(AppError::unprocessable)?;
     state
         .db
         .generic_update(&kind, &id, doc)
@@ -266,9 +285,12 @@ pub async fn update_scoped_object(
             }
         })?;
 
-    ctrl.after_update(&id, &state.db).await?;
+    ctrl.after_update(&id, &*state.db).await?;
+    if let Ok(Some(snap)) = state.db.generic_get(&kind, &id).await {
+        state.publish_change(ChangeType::Updated, &kind, &id, snap).await;
+    }
 
-    Ok(Json(json!({ "id": id })))
+    Ok(AuditChange::new("update", None, None).attach(format.render(json!({ "id": id }))))
 }
 
 /// DELETE /v1/projects/{project}/{kind}/{id}
@@ -322,7 +344,8 @@ pub async fn delete_scoped_object(
             }
         })?;
 
-    ctrl.after_delete(&id, &state.db).await?;
+    ctrl.after_delete(&id, &*state.db).await?;
+    state.publish_change(ChangeType::Deleted, &kind, &id, existing).await;
 
-    Ok(axum::http::StatusCode::NO_CONTENT)
+    Ok(AuditChange::new("delete", None, None).attach(axum::http::StatusCode::NO_CONTENT))
 }
@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::{db::AclFilter, error::AppError, middleware::auth::AuthenticatedUser, state::AppState};
+
+/// Results per kind unless `limit` says otherwise.
+const DEFAULT_LIMIT: usize = 10;
+const MAX_LIMIT: usize = 50;
+/// Query terms beyond this are refused rather than silently dropped.
+const MAX_TOKENS: usize = 8;
+
+#[derive(Deserialize)]
+pub struct GlobalSearchQuery {
+    pub q: Option<String>,
+    /// Comma-separated kinds; every searchable kind when absent.
+    pub kinds: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// Lowercased whitespace-separated terms of a query.
+pub fn tokenize(q: &str) -> Vec<String> {
+    q.split_whitespace().map(str::to_lowercase).collect()
+}
+
+/// Match quality of one document: per token, the best of exact field (4),
+/// field prefix (3), word prefix (2) or substring (1), summed. `None` when
+/// some token matches no field. `texts` are already lowercased.
+pub fn score(tokens: &[String], texts: &[&str]) -> Option<u32> {
+    tokens
+        .iter()
+        .map(|token| texts.iter().map(|text| token_score(token, text)).max().filter(|s| *s > 0))
+        .sum()
+}
+
+fn token_score(token: &str, text: &str) -> u32 {
+    if text == token {
+        4
+    } else if text.starts_with(token) {
+        3
+    } else if text
+        .match_indices(token)
+        .any(|(i, _)| !text[..i].chars().next_back().is_some_and(char::is_alphanumeric))
+    {
+        2
+    } else if text.contains(token) {
+        1
+    } else {
+        0
+    }
+}
+
+/// Search readable global resources by text.
+///
+/// `GET /v1/search?q=apollo&kinds=projects,users&limit=10` →
+/// `{ "q", "results": { kind: [brief, ...] } }`, each list ranked best match
+/// first (ties by id). Every token must occur in one of the kind's search
+/// fields, case-insensitively. Results are ACL-filtered as in list responses.
+pub async fn search(
+    AuthenticatedUser(user_id): AuthenticatedUser,
+    Query(query): Query<GlobalSearchQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Value>, AppError> {
+    let q = query.q.unwrap_or_default();
+    let tokens = tokenize(&q);
+    if tokens.is_empty() {
+        return Err(AppError::invalid_field("q", "search query must not be empty"));
+    }
+    if tokens.len() > MAX_TOKENS {
+        return Err(AppError::invalid_field("q", format!("at most {} search terms are allowed", MAX_TOKENS)));
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    let searchable: Vec<&'static str> = state
+        .resources
+        .kinds()
+        .filter(|kind| {
+            let ctrl = state.controller.for_kind(kind);
+            !ctrl.is_scoped() && !ctrl.search_fields().is_empty()
+        })
+        .collect();
+    let kinds: Vec<&str> = match query.kinds.as_deref() {
+        None => searchable.clone(),
+        Some(list) => {
+            let requested: Vec<&str> = list.split(',').map(str::trim).filter(|k| !k.is_empty()).collect();
+            if let Some(bad) = requested.iter().find(|k| !searchable.contains(k)) {
+                return Err(AppError::invalid_field(
+                    "kinds",
+                    format!("'{}' is not searchable; searchable kinds: {}", bad, searchable.join(", ")),
+                ));
+            }
+            requested
+        }
+    };
+
+    let godmode = state.has_godmode(&user_id).await.unwrap_or(false);
+    let principals = state.get_cached_principals(&user_id).await?;
+
+    let mut results = BTreeMap::new();
+    for kind in kinds {
+        let ctrl = state.controller.for_kind(kind);
+        let super_bypass = godmode
+            || match ctrl.super_permission() {
+                Some(perm) => state.db.has_permission_with_principals(&principals, perm).await?,
+                None => true,
+            };
+        state.db.ensure_collection(kind).await?;
+        let candidates = state
+            .db
+            .generic_text_search_acl(
+                kind,
+                AclFilter { principals: &principals, required_perm: ctrl.read_permission_bits(), super_bypass },
+                ctrl.list_projection_fields(),
+                ctrl.search_fields(),
+                &tokens,
+            )
+            .await?;
+
+        let mut ranked: Vec<(u32, String, Value)> = candidates
+            .into_iter()
+            .filter_map(|mut candidate| {
+                let texts: Vec<String> = serde_json::from_value(candidate.get_mut("texts")?.take()).ok()?;
+                let texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+                let score = score(&tokens, &texts)?;
+                let doc = candidate.get_mut("doc")?.take();
+                let key = doc.get("_key").and_then(Value::as_str).unwrap_or_default().to_string();
+                Some((score, key, doc))
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+        let items: Vec<Value> = ranked
+            .into_iter()
+            .take(limit)
+            .map(|(_, _, doc)| ctrl.to_list_external(doc))
+            .collect();
+        results.insert(kind.to_string(), items);
+    }
+
+    Ok(Json(json!({ "q": q, "results": results })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_beats_prefix_beats_word_beats_substring() {
+        let tokens = tokenize("Apollo");
+        assert_eq!(score(&tokens, &["apollo"]), Some(4));
+        assert_eq!(score(&tokens, &["apollo-web"]), Some(3));
+        assert_eq!(score(&tokens, &["project apollo"]), Some(2));
+        assert_eq!(score(&tokens, &["xapollo"]), Some(1));
+        assert_eq!(score(&tokens, &["zeus"]), None);
+        // The best field counts.
+        assert_eq!(score(&tokens, &["xapollo", "apollo"]), Some(4));
+    }
+
+    #[test]
+    fn every_token_must_match_some_field() {
+        let tokens = tokenize("  alice   ENGINEER ");
+        assert_eq!(tokens, vec!["alice", "engineer"]);
+        assert_eq!(score(&tokens, &["u_alice", "alice smith", "staff engineer"]), Some(3 + 2));
+        assert_eq!(score(&tokens, &["u_alice", "alice smith", "manager"]), None);
+    }
+}
@@ -0,0 +1,16 @@
+use std::sync::Arc;
+
+use axum::{Json, extract::State};
+use serde_json::{Value, json};
+
+use crate::state::AppState;
+
+/// Server build and mode, for clients and operators.
+///
+/// `GET /v1/system/info` → `{ "version": "0.1.0", "read_only": false }`
+pub async fn system_info(State(state): State<Arc<AppState>>) -> Json<Value> {
+    Json(json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "read_only": state.is_read_only(),
+    }))
+}
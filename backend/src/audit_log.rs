@@ -0,0 +1,260 @@
+//! Request-level audit trail ("who did what when").
+//!
+//! Every mutating request under `/api/v1` is recorded by `audit_middleware`
+//! as an `AuditEntry`, authenticated or not; `actor` is `None` for register,
+//! login, refresh and requests without a valid token. Entries are kept in an in-memory ring buffer for the
+//! admin query endpoint, emitted on the `audit` log target, and, when
+//! `AUDIT_LOG_PATH` is set, appended as JSON lines to that file. On startup
+//! the tail of the file is loaded back into the ring buffer so recent history
+//! survives restarts.
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::{io::AsyncWriteExt, sync::RwLock};
+
+/// Number of entries kept in memory for the query endpoint.
+pub const AUDIT_RING_CAPACITY: usize = 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Utc>,
+    pub actor: Option<String>,
+    /// Admin who sent the request when it ran under impersonation; `actor` is
+    /// then the impersonated user.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub real_actor: Option<String>,
+    pub method: String,
+    pub path: String,
+    pub status: u16,
+    pub latency_ms: u64,
+    pub kind: Option<String>,
+    pub key: Option<String>,
+    /// Client `X-Request-Id`, or a generated ULID echoed back in that header.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    /// `create`, `update` or `delete` when a resource handler made the write.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    /// Stored `hash_code` before the write (updates and deletes).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before_hash: Option<String>,
+    /// Stored `hash_code` after the write (creates and updates).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after_hash: Option<String>,
+    /// Set on the in-memory copy when appending to `AUDIT_LOG_PATH` failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sink_error: Option<String>,
+    /// Always `true`; lets log pipelines pick audit lines out of the general stream.
+    pub audit: bool,
+}
+
+/// Filters accepted by `AuditLog::query`.
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditQuery {
+    #[serde(alias = "principal")]
+    pub actor: Option<String>,
+    pub kind: Option<String>,
+    pub key: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+}
+
+pub struct AuditLog {
+    entries: RwLock<VecDeque<AuditEntry>>,
+    capacity: usize,
+    file: Option<PathBuf>,
+}
+
+impl AuditLog {
+    /// Create the audit log, preloading the last `capacity` entries from `file` if it exists.
+    pub fn new(capacity: usize, file: Option<PathBuf>) -> Self {
+        let mut entries = VecDeque::with_capacity(capacity);
+        if let Some(path) = &file
+            && let Ok(content) = std::fs::read_to_string(path)
+        {
+            for line in content.lines() {
+                match serde_json::from_str::<AuditEntry>(line) {
+                    Ok(entry) => {
+                        if entries.len() == capacity {
+                            entries.pop_front();
+                        }
+                        entries.push_back(entry);
+                    }
+                    Err(e) => log::warn!("[AUDIT] skipping unreadable line in {:?}: {}", path, e),
+                }
+            }
+        }
+        Self {
+            entries: RwLock::new(entries),
+            capacity,
+            file,
+        }
+    }
+
+    /// Record an entry. File write failures are logged and noted on the
+    /// in-memory copy as `sink_error`; they never surface to the caller.
+    pub async fn record(&self, mut entry: AuditEntry) {
+        let line = serde_json::to_string(&entry).unwrap_or_default();
+        log::info!(target: "audit", "{}", line);
+
+        if let Some(path) = &self.file
+            && let Err(e) = append_line(path, &line).await
+        {
+            log::error!("[AUDIT] failed to append to {:?}: {}", path, e);
+            entry.sink_error = Some(e.to_string());
+        }
+
+        let mut entries = self.entries.write().await;
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// Most recent entries first, filtered by actor (effective or real),
+    /// resource kind and key, and minimum timestamp.
+    pub async fn query(&self, q: &AuditQuery) -> Vec<AuditEntry> {
+        let limit = q.limit.unwrap_or(100).min(self.capacity);
+        let entries = self.entries.read().await;
+        entries
+            .iter()
+            .rev()
+            .filter(|e| q.actor.is_none() || e.actor == q.actor || e.real_actor == q.actor)
+            .filter(|e| q.kind.is_none() || e.kind == q.kind)
+            .filter(|e| q.key.is_none() || e.key == q.key)
+            .filter(|e| q.since.is_none_or(|since| e.timestamp >= since))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+}
+
+async fn append_line(path: &PathBuf, line: &str) -> std::io::Result<()> {
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    // Single write call per line so concurrent appends never interleave mid-line.
+    file.write_all(format!("{}\n", line).as_bytes()).await
+}
+
+/// Extract `(kind, key)` from an API path when it addresses a gitops resource.
+/// Understands `/api/v1/global/{kind}[/{id}...]`,
+/// `/api/v1/projects/{project}/{kind}[/{id}]` and the metadata edits
+/// `/api/v1/ops/{annotate|label}/{kind}/{id}`.
+pub fn resource_from_path(path: &str) -> (Option<String>, Option<String>) {
+    let segments: Vec<&str> = path
+        .trim_start_matches("/api")
+        .trim_start_matches("/v1")
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect();
+    match segments.as_slice() {
+        ["global", kind, rest @ ..]
+        | ["projects", _, kind, rest @ ..]
+        | ["ops", "annotate" | "label", kind, rest @ ..] => (
+            Some(kind.to_string()),
+            rest.first().map(|s| s.to_string()),
+        ),
+        _ => (None, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(actor: &str, path: &str) -> AuditEntry {
+        let (kind, key) = resource_from_path(path);
+        AuditEntry {
+            timestamp: Utc::now(),
+            actor: Some(actor.to_string()),
+            real_actor: None,
+            method: "DELETE".to_string(),
+            path: path.to_string(),
+            status: 204,
+            latency_ms: 1,
+            kind,
+            key,
+            request_id: None,
+            action: None,
+            before_hash: None,
+            after_hash: None,
+            sink_error: None,
+            audit: true,
+        }
+    }
+
+    #[test]
+    fn test_resource_from_path() {
+        assert_eq!(
+            resource_from_path("/api/v1/global/groups/g_ops"),
+            (Some("groups".into()), Some("g_ops".into()))
+        );
+        assert_eq!(
+            resource_from_path("/api/v1/projects/p1/tasks/t1"),
+            (Some("tasks".into()), Some("t1".into()))
+        );
+        assert_eq!(resource_from_path("/api/v1/global/users"), (Some("users".into()), None));
+        assert_eq!(resource_from_path("/api/v1/ws"), (None, None));
+        assert_eq!(
+            resource_from_path("/api/v1/ops/annotate/groups/g_ops"),
+            (Some("groups".into()), Some("g_ops".into()))
+        );
+        assert_eq!(resource_from_path("/api/v1/ops/count/groups"), (None, None));
+    }
+
+    #[tokio::test]
+    async fn test_ring_buffer_evicts_oldest_and_filters_by_actor() {
+        let log = AuditLog::new(2, None);
+        log.record(entry("u_a", "/api/v1/global/groups/g_1")).await;
+        log.record(entry("u_b", "/api/v1/global/groups/g_2")).await;
+        log.record(entry("u_a", "/api/v1/global/groups/g_3")).await;
+
+        let all = log.query(&AuditQuery::default()).await;
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].key.as_deref(), Some("g_3"));
+
+        let only_a = log
+            .query(&AuditQuery { actor: Some("u_a".into()), ..Default::default() })
+            .await;
+        assert_eq!(only_a.len(), 1);
+
+        let by_key = log
+            .query(&AuditQuery { kind: Some("groups".into()), key: Some("g_2".into()), ..Default::default() })
+            .await;
+        assert_eq!(by_key.len(), 1);
+        assert_eq!(by_key[0].actor.as_deref(), Some("u_b"));
+    }
+
+    #[tokio::test]
+    async fn test_failed_file_append_is_noted_in_memory() {
+        let dir = std::env::temp_dir().join(format!("crit_audit_dir_{}", ulid::Ulid::new()));
+        std::fs::create_dir(&dir).unwrap();
+        // Appending to a directory fails; the entry is still kept, marked.
+        let log = AuditLog::new(10, Some(dir.clone()));
+        log.record(entry("u_a", "/api/v1/global/groups/g_1")).await;
+
+        let entries = log.query(&AuditQuery::default()).await;
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].sink_error.is_some());
+        let _ = std::fs::remove_dir(dir);
+    }
+
+    #[tokio::test]
+    async fn test_file_tail_is_reloaded() {
+        let path = std::env::temp_dir().join(format!("crit_audit_{}.log", ulid::Ulid::new()));
+        let log = AuditLog::new(10, Some(path.clone()));
+        log.record(entry("u_a", "/api/v1/global/groups/g_1")).await;
+
+        let reloaded = AuditLog::new(10, Some(path.clone()));
+        let entries = reloaded.query(&AuditQuery::default()).await;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].actor.as_deref(), Some("u_a"));
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -7,6 +7,10 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+use axum::{
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
 use serde_json::Value;
 use tokio::sync::RwLock;
 
@@ -15,6 +19,14 @@ use crate::godmode;
 pub const PRINCIPALS_CACHE: &str = "principals";
 pub const PRINCIPALS_TTL: Duration = Duration::from_secs(5);
 
+/// Whether a JWT subject is still an active user. Checked by `jwt_auth_middleware`
+/// on every request; invalidated whenever a user document is written.
+pub const ACTIVE_USERS_CACHE: &str = "active_users";
+
+/// OIDC logins in flight, keyed by `state`; taken once by the callback.
+pub const OIDC_LOGINS_CACHE: &str = "oidc_logins";
+pub const OIDC_LOGINS_TTL: Duration = Duration::from_secs(600);
+
 /// A single cached entry with its insertion timestamp.
 struct CacheEntry {
     value: Value,
@@ -57,6 +69,11 @@ impl TtlCache {
     fn invalidate(&mut self, key: &str) {
         self.entries.remove(key);
     }
+
+    fn take(&mut self, key: &str) -> Option<Value> {
+        let entry = self.entries.remove(key)?;
+        (entry.inserted_at.elapsed() < self.ttl).then_some(entry.value)
+    }
 }
 
 /// Thread-safe container holding multiple named TTL caches.
@@ -103,14 +120,184 @@ impl CacheStore {
             cache.invalidate(key);
         }
     }
+
+    /// Drop every entry of a named cache; returns how many there were.
+    pub async fn clear(&self, cache_name: &str) -> usize {
+        let mut caches = self.caches.write().await;
+        caches.get_mut(cache_name).map_or(0, |c| std::mem::take(&mut c.entries).len())
+    }
+
+    /// Remove a key and return its value if it had not expired. Two concurrent
+    /// `take`s of the same key never both see the value.
+    pub async fn take(&self, cache_name: &str, key: &str) -> Option<Value> {
+        let mut caches = self.caches.write().await;
+        caches.get_mut(cache_name).and_then(|c| c.take(key))
+    }
 }
 
 /// Create a new `CacheStore` with the standard caches pre-registered.
-pub async fn create_default_cache() -> Arc<CacheStore> {
+/// `active_users_ttl` comes from `AppConfig::user_cache_ttl_secs`.
+pub async fn create_default_cache(active_users_ttl: Duration) -> Arc<CacheStore> {
     let store = Arc::new(CacheStore::new());
     store
         .register_cache(godmode::SPECIAL_ACCESS_CACHE, godmode::SPECIAL_ACCESS_TTL)
         .await;
     store.register_cache(PRINCIPALS_CACHE, PRINCIPALS_TTL).await;
+    store.register_cache(ACTIVE_USERS_CACHE, active_users_ttl).await;
+    store.register_cache(OIDC_LOGINS_CACHE, OIDC_LOGINS_TTL).await;
     store
 }
+
+// ---------------------------------------------------------------------------
+// HTTP validators (ETag / If-None-Match)
+// ---------------------------------------------------------------------------
+
+/// FNV-1a of `bytes`, as 16 hex characters (same scheme as `compute_value_hash`).
+fn fnv_hex(bytes: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// Strong ETag (quoted) of one resource: its `hash_code`, or a hash of the
+/// whole serialized document when it has none. Server-managed `state` is not
+/// part of `hash_code`, so re-stamping it alone keeps the ETag.
+pub fn resource_etag(doc: &Value) -> String {
+    match doc.get("hash_code").and_then(|v| v.as_str()) {
+        Some(hash) if !hash.is_empty() => format!("\"{}\"", hash),
+        _ => format!("\"{}\"", fnv_hex(doc.to_string().as_bytes())),
+    }
+}
+
+/// Strong ETag of a list response: a hash over its items' ETags, in order.
+pub fn list_etag(items: &[Value]) -> String {
+    let joined: Vec<String> = items.iter().map(resource_etag).collect();
+    format!("\"{}\"", fnv_hex(joined.join(",").as_bytes()))
+}
+
+/// Whether an `If-None-Match` header value names `etag` (or is `*`).
+/// Comparison is weak, as RFC 9110 prescribes for `If-None-Match`.
+pub fn etag_matches(if_none_match: Option<&HeaderValue>, etag: &str) -> bool {
+    let Some(value) = if_none_match.and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
+    value
+        .split(',')
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// `304 Not Modified` (no body) when the request's `If-None-Match` names
+/// `etag`, otherwise `body`; both carry the `ETag` header.
+pub fn conditional_response(headers: &HeaderMap, etag: &str, body: impl IntoResponse) -> Response {
+    let mut response = if etag_matches(headers.get(header::IF_NONE_MATCH), etag) {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        body.into_response()
+    };
+    if let Ok(value) = HeaderValue::from_str(etag) {
+        response.headers_mut().insert(header::ETAG, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn hit_within_ttl_then_expiry() {
+        let store = CacheStore::new();
+        store.register_cache("t", Duration::from_millis(50)).await;
+        store.set("t", "u_alice".into(), json!(true)).await;
+        assert_eq!(store.get("t", "u_alice").await, Some(json!(true)));
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(store.get("t", "u_alice").await, None);
+    }
+
+    #[tokio::test]
+    async fn invalidate_drops_entry_immediately() {
+        let store = CacheStore::new();
+        store.register_cache("t", Duration::from_secs(60)).await;
+        store.set("t", "u_alice".into(), json!(true)).await;
+        store.invalidate("t", "u_alice").await;
+        assert_eq!(store.get("t", "u_alice").await, None);
+    }
+
+    #[tokio::test]
+    async fn take_returns_value_once() {
+        let store = CacheStore::new();
+        store.register_cache("t", Duration::from_secs(60)).await;
+        store.set("t", "state".into(), json!("pending")).await;
+        assert_eq!(store.take("t", "state").await, Some(json!("pending")));
+        assert_eq!(store.take("t", "state").await, None);
+    }
+
+    #[tokio::test]
+    async fn clear_empties_only_that_cache() {
+        let store = CacheStore::new();
+        store.register_cache("a", Duration::from_secs(60)).await;
+        store.register_cache("b", Duration::from_secs(60)).await;
+        store.set("a", "x".into(), json!(1)).await;
+        store.set("a", "y".into(), json!(2)).await;
+        store.set("b", "x".into(), json!(3)).await;
+        assert_eq!(store.clear("a").await, 2);
+        assert_eq!(store.get("a", "x").await, None);
+        assert_eq!(store.get("b", "x").await, Some(json!(3)));
+    }
+
+    #[tokio::test]
+    async fn zero_ttl_never_hits() {
+        let store = create_default_cache(Duration::ZERO).await;
+        store.set(ACTIVE_USERS_CACHE, "u_alice".into(), json!(true)).await;
+        assert_eq!(store.get(ACTIVE_USERS_CACHE, "u_alice").await, None);
+    }
+
+    #[test]
+    fn resource_etag_prefers_hash_code() {
+        assert_eq!(resource_etag(&json!({ "id": "g_a", "hash_code": "abc123" })), "\"abc123\"");
+        let a = resource_etag(&json!({ "id": "g_a", "name": "A" }));
+        let b = resource_etag(&json!({ "id": "g_a", "name": "B" }));
+        assert_ne!(a, b);
+        assert!(a.starts_with('"') && a.ends_with('"'));
+        assert_eq!(a, resource_etag(&json!({ "id": "g_a", "name": "A" })));
+    }
+
+    #[test]
+    fn list_etag_depends_on_items_and_order() {
+        let a = json!({ "hash_code": "1" });
+        let b = json!({ "hash_code": "2" });
+        assert_eq!(list_etag(&[a.clone(), b.clone()]), list_etag(&[a.clone(), b.clone()]));
+        assert_ne!(list_etag(&[a.clone(), b.clone()]), list_etag(&[b.clone(), a.clone()]));
+        assert_ne!(list_etag(std::slice::from_ref(&a)), list_etag(&[a, b]));
+        assert_ne!(list_etag(&[]), list_etag(&[json!({})]));
+    }
+
+    #[test]
+    fn if_none_match_parsing() {
+        let h = |s: &str| HeaderValue::from_str(s).unwrap();
+        assert!(etag_matches(Some(&h("\"x\"")), "\"x\""));
+        assert!(etag_matches(Some(&h("\"a\", W/\"x\"")), "\"x\""));
+        assert!(etag_matches(Some(&h("*")), "\"x\""));
+        assert!(!etag_matches(Some(&h("\"y\"")), "\"x\""));
+        assert!(!etag_matches(None, "\"x\""));
+    }
+
+    #[test]
+    fn conditional_response_304_has_no_body() {
+        let mut headers = HeaderMap::new();
+        let fresh = conditional_response(&headers, "\"x\"", "body");
+        assert_eq!(fresh.status(), StatusCode::OK);
+        assert_eq!(fresh.headers()[header::ETAG], "\"x\"");
+
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("\"x\""));
+        let cached = conditional_response(&headers, "\"x\"", "body");
+        assert_eq!(cached.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(cached.headers()[header::ETAG], "\"x\"");
+    }
+}
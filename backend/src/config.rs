@@ -1,15 +1,75 @@
 use std::env;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::path::PathBuf;
 
 use dotenvy::dotenv;
 use serde::{Deserialize, Serialize};
 
-use crate::error::AppError;
+use crate::{db::{ConnectRetry, DEFAULT_MEMBERSHIP_DEPTH}, error::AppError, validation::naming::validate_username};
 
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct RuntimeConfig {
     pub user_login_allowed: bool,
 }
 
+/// PEM files for HTTPS termination.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TlsConfig {
+    /// Certificate chain, leaf first.
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+}
+
+/// Cross-origin access for browser clients on other origins.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CorsConfig {
+    pub origins: CorsOrigins,
+    /// Send `Access-Control-Allow-Credentials: true` (cookies, auth headers).
+    pub allow_credentials: bool,
+    /// How long browsers may cache a preflight answer.
+    pub max_age_secs: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CorsOrigins {
+    /// `CORS_ALLOWED_ORIGINS=*`
+    Any,
+    /// Exact origins, e.g. `https://status.example.com`.
+    List(Vec<String>),
+}
+
+/// OpenID Connect provider used for `GET /api/v1/oauth/login`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OidcConfig {
+    /// Issuer URL; `{issuer}/.well-known/openid-configuration` must exist.
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: String,
+    /// Where the provider sends the browser back: this server's `/api/v1/oauth/callback`.
+    pub redirect_url: String,
+}
+
+/// Account created at startup when the database has none by that name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DefaultAdmin {
+    /// Bare username, without the `u_` prefix.
+    pub username: String,
+    pub password: String,
+}
+
+/// What `POST /v1/global/{kind}` does with a client-supplied id that lacks
+/// the kind's prefix (`ID_PREFIX_POLICY`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IdPrefixPolicy {
+    /// Prepend the prefix: `ops` becomes `g_ops`.
+    Add,
+    /// Refuse the create with 400.
+    Reject,
+}
+
+/// Used when neither `DEFAULT_ADMIN_PASSWORD` nor `ROOT_PASSWORD` is set.
+pub const FALLBACK_ADMIN_PASSWORD: &str = "changeme";
+
 #[derive(Clone, Debug)]
 pub struct AppConfig {
     pub jwt_secret: String,
@@ -17,11 +77,51 @@ pub struct AppConfig {
     pub database_name: String,
     pub database_user: String,
     pub database_password: String,
+    /// Startup connection attempts before giving up (exponential backoff).
+    pub database_connect_attempts: u32,
+    pub database_connect_delay_ms: u64,
     pub client_api_keys: Vec<String>,
-    pub host: String,
-    pub port: u16,
-    pub root_password: String,
-    pub jwt_expiry_days: u64,
+    /// Listen address: `BIND_ADDR`, else `HOST`:`PORT`.
+    pub bind_addr: SocketAddr,
+    /// Serve HTTPS when `TLS_CERT` and `TLS_KEY` are both set; plain HTTP otherwise.
+    pub tls: Option<TlsConfig>,
+    /// CORS policy from `CORS_ALLOWED_ORIGINS`; `None` keeps the permissive default.
+    pub cors: Option<CorsConfig>,
+    /// `None` unless all `OIDC_*` variables are set.
+    pub oidc: Option<OidcConfig>,
+    /// `None` unless `CREATE_DEFAULT_ADMIN` is on (the default in debug builds only).
+    pub default_admin: Option<DefaultAdmin>,
+    /// Access-token lifetime: `JWT_TTL_SECONDS`, else `JWT_EXPIRY_DAYS` days.
+    pub jwt_ttl_seconds: u64,
+    /// Clock skew tolerated when checking a token's expiry.
+    pub jwt_leeway_secs: u64,
+    /// Lifetime of refresh tokens issued at login and on refresh.
+    pub refresh_token_ttl_seconds: u64,
+    /// Argon2id cost for new password hashes (`ARGON2_MEMORY_KIB`, `ARGON2_ITERATIONS`,
+    /// `ARGON2_PARALLELISM`). Stored hashes with other parameters are re-hashed on login.
+    pub argon2_params: argon2::Params,
+    /// Max seconds a `/v1/ops/list/{kind}` long-poll blocks before answering 304.
+    pub long_poll_timeout_secs: u64,
+    /// Append-only JSON-lines file for the request audit log. `None` keeps it in memory only.
+    pub audit_log_path: Option<std::path::PathBuf>,
+    /// Seconds between periodic reconcile passes over registered kinds; 0 disables them.
+    pub reconcile_interval_secs: u64,
+    /// How long an "is this JWT subject an active user" answer is reused; 0 disables caching.
+    pub user_cache_ttl_secs: u64,
+    /// Start in read-only maintenance mode: every mutating request gets 503.
+    pub read_only: bool,
+    /// Deepest group nesting followed when resolving transitive memberships;
+    /// deeper chains are an error (`MEMBERSHIP_MAX_DEPTH`).
+    pub membership_max_depth: u32,
+    /// Largest accepted request body on resource and ops endpoints; bigger ones get 413.
+    pub max_body_bytes: usize,
+    /// Handling of created ids that lack the kind's prefix.
+    pub id_prefix_policy: IdPrefixPolicy,
+    /// Projects a user may own unless their `quotas` document says otherwise
+    /// (`QUOTA_MAX_OWNED_PROJECTS`); `None` means unlimited.
+    pub quota_max_owned_projects: Option<u64>,
+    /// AES-256 key for stored TOTP secrets (`TOTP_ENCRYPTION_KEY`, else derived from `JWT_SECRET`).
+    pub totp_key: [u8; 32],
     // Object store
     pub object_store_backend: String,
     pub object_store_path: String,
@@ -33,6 +133,17 @@ pub struct AppConfig {
 }
 
 impl AppConfig {
+    pub fn db_connect_retry(&self) -> ConnectRetry {
+        ConnectRetry {
+            attempts: self.database_connect_attempts,
+            initial_delay: std::time::Duration::from_millis(self.database_connect_delay_ms),
+        }
+    }
+
+    pub fn user_cache_ttl(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.user_cache_ttl_secs)
+    }
+
     pub fn runtime_from_env() -> Result<RuntimeConfig, AppError> {
         // Load .env file if it exists
         dotenv().ok();
@@ -65,6 +176,14 @@ impl AppConfig {
         let database_password =
             env::var("DB_PASSWORD").unwrap_or_else(|_| String::new());
 
+        let database_connect_attempts = env::var("DB_CONNECT_ATTEMPTS")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse::<u32>()?;
+
+        let database_connect_delay_ms = env::var("DB_CONNECT_DELAY_MS")
+            .unwrap_or_else(|_| "500".to_string())
+            .parse::<u64>()?;
+
         let client_api_keys = env::var("CLIENT_API_KEYS")
             .unwrap_or_else(|_| String::new())
             .split(':')
@@ -72,18 +191,97 @@ impl AppConfig {
             .map(|s| s.to_string())
             .collect();
 
-        let root_password = env::var("ROOT_PASSWORD")
-            .unwrap_or_else(|_| "changeme".to_string());
+        let default_admin = resolve_default_admin(
+            env::var("CREATE_DEFAULT_ADMIN").ok().as_deref(),
+            env::var("DEFAULT_ADMIN_USER").ok().as_deref(),
+            env::var("DEFAULT_ADMIN_PASSWORD").or_else(|_| env::var("ROOT_PASSWORD")).ok().as_deref(),
+            cfg!(debug_assertions),
+        )?;
 
         let jwt_expiry_days = env::var("JWT_EXPIRY_DAYS")
             .unwrap_or_else(|_| "90".to_string())
             .parse::<u64>()?;
 
-        let host = env::var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
+        let jwt_ttl_seconds = match env::var("JWT_TTL_SECONDS") {
+            Ok(s) if !s.trim().is_empty() => s.trim().parse::<u64>()?,
+            _ => jwt_expiry_days * 86400,
+        };
+
+        let jwt_leeway_secs = env::var("JWT_LEEWAY_SECS")
+            .unwrap_or_else(|_| "60".to_string())
+            .parse::<u64>()?;
+
+        let refresh_token_ttl_seconds = env::var("REFRESH_TOKEN_TTL_SECONDS")
+            .unwrap_or_else(|_| (90 * 86400).to_string())
+            .parse::<u64>()?;
+
+        let argon2_params = resolve_argon2_params(
+            env::var("ARGON2_MEMORY_KIB").ok().as_deref(),
+            env::var("ARGON2_ITERATIONS").ok().as_deref(),
+            env::var("ARGON2_PARALLELISM").ok().as_deref(),
+        )?;
+
+        let long_poll_timeout_secs = env::var("LONG_POLL_TIMEOUT_SECS")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse::<u64>()?;
+
+        let audit_log_path = env::var("AUDIT_LOG_PATH")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(std::path::PathBuf::from);
+
+        let reconcile_interval_secs = env::var("RECONCILE_INTERVAL_SECS")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<u64>()?;
+
+        let user_cache_ttl_secs = env::var("USER_CACHE_TTL_SECS")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse::<u64>()?;
+
+        let membership_max_depth = env::var("MEMBERSHIP_MAX_DEPTH")
+            .unwrap_or_else(|_| DEFAULT_MEMBERSHIP_DEPTH.to_string())
+            .parse::<u32>()?;
+
+        let read_only = env::var("READ_ONLY")
+            .map(|s| matches!(s.to_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or(false);
+
+        let max_body_bytes = env::var("MAX_BODY_BYTES")
+            .unwrap_or_else(|_| (1024 * 1024).to_string())
+            .parse::<usize>()?;
+
+        let id_prefix_policy = resolve_id_prefix_policy(env::var("ID_PREFIX_POLICY").ok().as_deref())?;
+
+        let quota_max_owned_projects = match env::var("QUOTA_MAX_OWNED_PROJECTS") {
+            Ok(s) if !s.trim().is_empty() => Some(s.trim().parse::<u64>()?),
+            _ => None,
+        };
+
+        let totp_key = resolve_totp_key(env::var("TOTP_ENCRYPTION_KEY").ok().as_deref(), &jwt_secret)?;
+
+        let bind_addr = resolve_bind_addr(
+            env::var("BIND_ADDR").ok().as_deref(),
+            env::var("HOST").ok().as_deref(),
+            env::var("PORT").ok().as_deref(),
+        )?;
+
+        let tls = resolve_tls(
+            env::var("TLS_CERT").ok().as_deref(),
+            env::var("TLS_KEY").ok().as_deref(),
+        )?;
 
-        let port = env::var("PORT")
-            .unwrap_or_else(|_| "3069".to_string())
-            .parse::<u16>()?;
+        let cors = resolve_cors(
+            env::var("CORS_ALLOWED_ORIGINS").ok().as_deref(),
+            env::var("CORS_ALLOW_CREDENTIALS").ok().as_deref(),
+            env::var("CORS_MAX_AGE_SECS").ok().as_deref(),
+        )?;
+
+        let oidc = resolve_oidc(
+            env::var("OIDC_ISSUER").ok().as_deref(),
+            env::var("OIDC_CLIENT_ID").ok().as_deref(),
+            env::var("OIDC_CLIENT_SECRET").ok().as_deref(),
+            env::var("OIDC_REDIRECT_URL").ok().as_deref(),
+        )?;
 
         let object_store_backend =
             env::var("OBJECT_STORE_BACKEND").unwrap_or_else(|_| String::new());
@@ -106,11 +304,28 @@ impl AppConfig {
             database_name,
             database_user,
             database_password,
+            database_connect_attempts,
+            database_connect_delay_ms,
             client_api_keys,
-            host,
-            port,
-            root_password,
-            jwt_expiry_days,
+            bind_addr,
+            tls,
+            cors,
+            oidc,
+            default_admin,
+            jwt_ttl_seconds,
+            jwt_leeway_secs,
+            refresh_token_ttl_seconds,
+            argon2_params,
+            long_poll_timeout_secs,
+            audit_log_path,
+            reconcile_interval_secs,
+            user_cache_ttl_secs,
+            read_only,
+            membership_max_depth,
+            max_body_bytes,
+            id_prefix_policy,
+            quota_max_owned_projects,
+            totp_key,
             object_store_backend,
             object_store_path,
             object_store_url,
@@ -121,3 +336,362 @@ impl AppConfig {
         })
     }
 }
+
+const DEFAULT_HOST: &str = "0.0.0.0";
+const DEFAULT_PORT: u16 = 3069;
+
+/// Resolve the listen address. `BIND_ADDR` (`host:port`) wins; otherwise `HOST`
+/// (default `0.0.0.0`) and `PORT` (default 3069) are combined, so `PORT` alone
+/// just moves the port. Host names such as `localhost` are resolved.
+pub fn resolve_bind_addr(
+    bind_addr: Option<&str>,
+    host: Option<&str>,
+    port: Option<&str>,
+) -> Result<SocketAddr, String> {
+    let bind_addr = bind_addr.map(str::trim).filter(|s| !s.is_empty());
+    if let Some(addr) = bind_addr {
+        return to_socket_addr(addr).ok_or_else(|| {
+            format!("invalid BIND_ADDR '{}': expected host:port, e.g. 127.0.0.1:3069 or [::1]:3069", addr)
+        });
+    }
+
+    let port = match port.map(str::trim).filter(|s| !s.is_empty()) {
+        Some(p) => p
+            .parse::<u16>()
+            .map_err(|_| format!("invalid PORT '{}': expected a number between 0 and 65535", p))?,
+        None => DEFAULT_PORT,
+    };
+    let host = host.map(str::trim).filter(|s| !s.is_empty()).unwrap_or(DEFAULT_HOST);
+    // Bare IPv6 hosts need brackets to be joined with a port.
+    let joined = if host.contains(':') && !host.starts_with('[') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    };
+    to_socket_addr(&joined).ok_or_else(|| format!("invalid HOST '{}': not an address or resolvable name", host))
+}
+
+/// TLS is on when both `TLS_CERT` and `TLS_KEY` are set, off when neither is;
+/// setting only one is a configuration error. The files are read at startup.
+pub fn resolve_tls(cert: Option<&str>, key: Option<&str>) -> Result<Option<TlsConfig>, String> {
+    let cert = cert.map(str::trim).filter(|s| !s.is_empty());
+    let key = key.map(str::trim).filter(|s| !s.is_empty());
+    match (cert, key) {
+        (Some(cert), Some(key)) => Ok(Some(TlsConfig {
+            cert_path: PathBuf::from(cert),
+            key_path: PathBuf::from(key),
+        })),
+        (None, None) => Ok(None),
+        (Some(_), None) => Err("TLS_CERT is set but TLS_KEY is not; set both to serve HTTPS".to_string()),
+        (None, Some(_)) => Err("TLS_KEY is set but TLS_CERT is not; set both to serve HTTPS".to_string()),
+    }
+}
+
+/// CORS is configured when `CORS_ALLOWED_ORIGINS` is set: a comma list of exact
+/// origins, or `*`. Credentials cannot be combined with `*` (browsers reject
+/// it), and `*` cannot be mixed with explicit origins.
+pub fn resolve_cors(
+    origins: Option<&str>,
+    credentials: Option<&str>,
+    max_age: Option<&str>,
+) -> Result<Option<CorsConfig>, String> {
+    let Some(origins) = origins.map(str::trim).filter(|s| !s.is_empty()) else {
+        return Ok(None);
+    };
+    let list: Vec<String> = origins
+        .split(',')
+        .map(|o| o.trim().trim_end_matches('/').to_string())
+        .filter(|o| !o.is_empty())
+        .collect();
+    let origins = if list.iter().any(|o| o == "*") {
+        if list.len() > 1 {
+            return Err("CORS_ALLOWED_ORIGINS: '*' cannot be combined with explicit origins".to_string());
+        }
+        CorsOrigins::Any
+    } else {
+        if let Some(bad) = list
+            .iter()
+            .find(|o| !(o.starts_with("http://") || o.starts_with("https://")) || o.contains(char::is_whitespace))
+        {
+            return Err(format!("CORS_ALLOWED_ORIGINS: invalid origin '{}', expected e.g. https://tools.example.com", bad));
+        }
+        CorsOrigins::List(list)
+    };
+
+    let allow_credentials = credentials.is_some_and(|s| matches!(s.trim().to_lowercase().as_str(), "1" | "true" | "yes"));
+    if allow_credentials && origins == CorsOrigins::Any {
+        return Err("CORS_ALLOW_CREDENTIALS cannot be used with CORS_ALLOWED_ORIGINS=*".to_string());
+    }
+
+    let max_age_secs = match max_age.map(str::trim).filter(|s| !s.is_empty()) {
+        Some(s) => s
+            .parse::<u64>()
+            .map_err(|_| format!("invalid CORS_MAX_AGE_SECS '{}': expected seconds", s))?,
+        None => 600,
+    };
+
+    Ok(Some(CorsConfig { origins, allow_credentials, max_age_secs }))
+}
+
+/// OIDC login is enabled by setting all four variables; setting only some of
+/// them is a startup error rather than a silently disabled login.
+pub fn resolve_oidc(
+    issuer: Option<&str>,
+    client_id: Option<&str>,
+    client_secret: Option<&str>,
+    redirect_url: Option<&str>,
+) -> Result<Option<OidcConfig>, String> {
+    let vars = [
+        ("OIDC_ISSUER", issuer),
+        ("OIDC_CLIENT_ID", client_id),
+        ("OIDC_CLIENT_SECRET", client_secret),
+        ("OIDC_REDIRECT_URL", redirect_url),
+    ];
+    let set: Vec<(&str, &str)> = vars
+        .iter()
+        .filter_map(|(name, v)| v.map(str::trim).filter(|v| !v.is_empty()).map(|v| (*name, v)))
+        .collect();
+    if set.is_empty() {
+        return Ok(None);
+    }
+    if set.len() < vars.len() {
+        let missing: Vec<&str> = vars
+            .iter()
+            .map(|(name, _)| *name)
+            .filter(|name| !set.iter().any(|(n, _)| n == name))
+            .collect();
+        return Err(format!("OIDC login is partially configured; also set {}", missing.join(", ")));
+    }
+    let value = |i: usize| set[i].1.to_string();
+    for (name, url) in [("OIDC_ISSUER", set[0].1), ("OIDC_REDIRECT_URL", set[3].1)] {
+        if !(url.starts_with("https://") || url.starts_with("http://")) {
+            return Err(format!("{}: expected an http(s) URL, got '{}'", name, url));
+        }
+    }
+    Ok(Some(OidcConfig {
+        issuer: value(0),
+        client_id: value(1),
+        client_secret: value(2),
+        redirect_url: value(3),
+    }))
+}
+
+/// Whether and as whom to seed an admin account. `enabled` defaults to
+/// `default_on` (debug builds), so release builds never create a well-known
+/// account unless asked to. The username defaults to `root`.
+pub fn resolve_default_admin(
+    enabled: Option<&str>,
+    username: Option<&str>,
+    password: Option<&str>,
+    default_on: bool,
+) -> Result<Option<DefaultAdmin>, String> {
+    let enabled = match enabled.map(str::trim).filter(|s| !s.is_empty()) {
+        Some(v) => matches!(v.to_lowercase().as_str(), "1" | "true" | "yes"),
+        None => default_on,
+    };
+    if !enabled {
+        return Ok(None);
+    }
+    let username = username.map(str::trim).filter(|s| !s.is_empty()).unwrap_or("root");
+    let username = username.strip_prefix("u_").unwrap_or(username);
+    let username = validate_username(username).map_err(|e| format!("DEFAULT_ADMIN_USER: {}", e))?;
+    let password = password.filter(|s| !s.is_empty()).unwrap_or(FALLBACK_ADMIN_PASSWORD);
+    Ok(Some(DefaultAdmin { username, password: password.to_string() }))
+}
+
+/// Argon2id cost parameters; unset values take the OWASP-recommended defaults
+/// (19 MiB, 2 iterations, 1 lane).
+pub fn resolve_argon2_params(
+    memory_kib: Option<&str>,
+    iterations: Option<&str>,
+    parallelism: Option<&str>,
+) -> Result<argon2::Params, String> {
+    fn parse(var: &str, value: Option<&str>, default: u32) -> Result<u32, String> {
+        match value.map(str::trim).filter(|s| !s.is_empty()) {
+            Some(s) => s.parse::<u32>().map_err(|_| format!("invalid {} '{}': expected a number", var, s)),
+            None => Ok(default),
+        }
+    }
+    let m_cost = parse("ARGON2_MEMORY_KIB", memory_kib, argon2::Params::DEFAULT_M_COST)?;
+    let t_cost = parse("ARGON2_ITERATIONS", iterations, argon2::Params::DEFAULT_T_COST)?;
+    let p_cost = parse("ARGON2_PARALLELISM", parallelism, argon2::Params::DEFAULT_P_COST)?;
+    argon2::Params::new(m_cost, t_cost, p_cost, None).map_err(|e| format!("invalid Argon2 parameters: {}", e))
+}
+
+/// `add` (the default) or `reject`.
+pub fn resolve_id_prefix_policy(value: Option<&str>) -> Result<IdPrefixPolicy, String> {
+    match value.map(|s| s.trim().to_lowercase()).filter(|s| !s.is_empty()).as_deref() {
+        None | Some("add") => Ok(IdPrefixPolicy::Add),
+        Some("reject") => Ok(IdPrefixPolicy::Reject),
+        Some(other) => Err(format!("invalid ID_PREFIX_POLICY '{}': expected add or reject", other)),
+    }
+}
+
+/// `TOTP_ENCRYPTION_KEY` is 32 bytes, base64-encoded. Unset, the key is
+/// derived from the JWT secret, so rotating `JWT_SECRET` then makes enrolled
+/// TOTP secrets unreadable.
+pub fn resolve_totp_key(value: Option<&str>, jwt_secret: &str) -> Result<[u8; 32], String> {
+    use base64::Engine;
+    use sha2::{Digest, Sha256};
+
+    match value.map(str::trim).filter(|s| !s.is_empty()) {
+        Some(encoded) => base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .ok()
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+            .ok_or_else(|| "invalid TOTP_ENCRYPTION_KEY: expected 32 bytes, base64-encoded".to_string()),
+        None => Ok(Sha256::digest(format!("crit-totp-key:{}", jwt_secret).as_bytes()).into()),
+    }
+}
+
+fn to_socket_addr(addr: &str) -> Option<SocketAddr> {
+    addr.parse()
+        .ok()
+        .or_else(|| addr.to_socket_addrs().ok().and_then(|mut it| it.next()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(s: &str) -> SocketAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn defaults_when_nothing_is_set() {
+        assert_eq!(resolve_bind_addr(None, None, None).unwrap(), addr("0.0.0.0:3069"));
+        assert_eq!(resolve_bind_addr(Some(""), Some(" "), Some("")).unwrap(), addr("0.0.0.0:3069"));
+    }
+
+    #[test]
+    fn port_alone_overrides_only_the_port() {
+        assert_eq!(resolve_bind_addr(None, None, Some("8080")).unwrap(), addr("0.0.0.0:8080"));
+        assert_eq!(resolve_bind_addr(None, Some("127.0.0.1"), Some("9000")).unwrap(), addr("127.0.0.1:9000"));
+        assert_eq!(resolve_bind_addr(None, Some("::1"), Some("9000")).unwrap(), addr("[::1]:9000"));
+    }
+
+    #[test]
+    fn bind_addr_wins_over_host_and_port() {
+        let resolved = resolve_bind_addr(Some("127.0.0.1:4000"), Some("0.0.0.0"), Some("8080")).unwrap();
+        assert_eq!(resolved, addr("127.0.0.1:4000"));
+        assert_eq!(resolve_bind_addr(Some("[::1]:4000"), None, None).unwrap(), addr("[::1]:4000"));
+    }
+
+    #[test]
+    fn invalid_values_name_the_variable() {
+        let err = resolve_bind_addr(Some("localhost"), None, None).unwrap_err();
+        assert!(err.starts_with("invalid BIND_ADDR 'localhost'"), "{}", err);
+        let err = resolve_bind_addr(Some("127.0.0.1:99999"), None, None).unwrap_err();
+        assert!(err.contains("BIND_ADDR"), "{}", err);
+        let err = resolve_bind_addr(None, None, Some("http")).unwrap_err();
+        assert!(err.starts_with("invalid PORT 'http'"), "{}", err);
+    }
+
+    #[test]
+    fn tls_needs_both_cert_and_key() {
+        assert_eq!(resolve_tls(None, None).unwrap(), None);
+        assert_eq!(resolve_tls(Some(""), Some(" ")).unwrap(), None);
+        assert_eq!(
+            resolve_tls(Some("/etc/crit/cert.pem"), Some("/etc/crit/key.pem")).unwrap(),
+            Some(TlsConfig {
+                cert_path: PathBuf::from("/etc/crit/cert.pem"),
+                key_path: PathBuf::from("/etc/crit/key.pem"),
+            })
+        );
+        assert!(resolve_tls(Some("cert.pem"), None).unwrap_err().starts_with("TLS_CERT is set but TLS_KEY is not"));
+        assert!(resolve_tls(None, Some("key.pem")).unwrap_err().starts_with("TLS_KEY is set but TLS_CERT is not"));
+    }
+
+    #[test]
+    fn cors_is_off_unless_origins_are_set() {
+        assert_eq!(resolve_cors(None, Some("true"), Some("60")).unwrap(), None);
+        assert_eq!(resolve_cors(Some(" "), None, None).unwrap(), None);
+    }
+
+    #[test]
+    fn cors_parses_origin_lists_and_wildcard() {
+        assert_eq!(
+            resolve_cors(Some("https://a.example.com, http://localhost:5173/"), Some("true"), None).unwrap(),
+            Some(CorsConfig {
+                origins: CorsOrigins::List(vec!["https://a.example.com".into(), "http://localhost:5173".into()]),
+                allow_credentials: true,
+                max_age_secs: 600,
+            })
+        );
+        assert_eq!(
+            resolve_cors(Some("*"), None, Some("30")).unwrap(),
+            Some(CorsConfig { origins: CorsOrigins::Any, allow_credentials: false, max_age_secs: 30 })
+        );
+    }
+
+    #[test]
+    fn cors_rejects_inconsistent_settings() {
+        assert!(resolve_cors(Some("*"), Some("true"), None).unwrap_err().contains("CORS_ALLOW_CREDENTIALS"));
+        assert!(resolve_cors(Some("*,https://a.example.com"), None, None).is_err());
+        assert!(resolve_cors(Some("a.example.com"), None, None).unwrap_err().contains("invalid origin"));
+        assert!(resolve_cors(Some("https://a.example.com"), None, Some("soon")).is_err());
+    }
+
+    #[test]
+    fn argon2_params_default_and_validate() {
+        let params = resolve_argon2_params(None, Some(""), None).unwrap();
+        assert_eq!((params.m_cost(), params.t_cost(), params.p_cost()), (19456, 2, 1));
+        let params = resolve_argon2_params(Some("65536"), Some("3"), Some("4")).unwrap();
+        assert_eq!((params.m_cost(), params.t_cost(), params.p_cost()), (65536, 3, 4));
+        assert!(resolve_argon2_params(Some("lots"), None, None).unwrap_err().contains("ARGON2_MEMORY_KIB"));
+        assert!(resolve_argon2_params(None, Some("0"), None).is_err());
+    }
+
+    #[test]
+    fn id_prefix_policy_defaults_to_add() {
+        assert_eq!(resolve_id_prefix_policy(None).unwrap(), IdPrefixPolicy::Add);
+        assert_eq!(resolve_id_prefix_policy(Some(" ")).unwrap(), IdPrefixPolicy::Add);
+        assert_eq!(resolve_id_prefix_policy(Some("Reject")).unwrap(), IdPrefixPolicy::Reject);
+        assert!(resolve_id_prefix_policy(Some("strip")).unwrap_err().contains("ID_PREFIX_POLICY"));
+    }
+
+    #[test]
+    fn totp_key_is_given_or_derived() {
+        let derived = resolve_totp_key(None, "secret").unwrap();
+        assert_eq!(resolve_totp_key(Some(" "), "secret").unwrap(), derived);
+        assert_ne!(resolve_totp_key(None, "other").unwrap(), derived);
+        let given = "AQIDBAUGBwgJCgsMDQ4PEBESExQVFhcYGRobHB0eHyA=";
+        assert_eq!(resolve_totp_key(Some(given), "secret").unwrap()[..3], [1, 2, 3]);
+        assert!(resolve_totp_key(Some("AQID"), "secret").unwrap_err().contains("TOTP_ENCRYPTION_KEY"));
+        assert!(resolve_totp_key(Some("not base64!"), "secret").is_err());
+    }
+
+    #[test]
+    fn oidc_needs_all_or_nothing() {
+        assert_eq!(resolve_oidc(None, None, Some(" "), None).unwrap(), None);
+        let err = resolve_oidc(Some("https://id.example.com"), Some("crit"), None, None).unwrap_err();
+        assert!(err.contains("OIDC_CLIENT_SECRET, OIDC_REDIRECT_URL"), "{}", err);
+        assert!(resolve_oidc(Some("id.example.com"), Some("crit"), Some("s"), Some("https://crit/cb")).is_err());
+        assert_eq!(
+            resolve_oidc(Some(" https://id.example.com"), Some("crit"), Some("s"), Some("https://crit/api/v1/oauth/callback"))
+                .unwrap(),
+            Some(OidcConfig {
+                issuer: "https://id.example.com".into(),
+                client_id: "crit".into(),
+                client_secret: "s".into(),
+                redirect_url: "https://crit/api/v1/oauth/callback".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn default_admin_is_opt_in_and_configurable() {
+        assert_eq!(resolve_default_admin(None, Some("ops"), Some("pw"), false).unwrap(), None);
+        assert_eq!(resolve_default_admin(Some("0"), None, None, true).unwrap(), None);
+        assert_eq!(
+            resolve_default_admin(None, None, None, true).unwrap(),
+            Some(DefaultAdmin { username: "root".into(), password: FALLBACK_ADMIN_PASSWORD.into() })
+        );
+        assert_eq!(
+            resolve_default_admin(Some("true"), Some(" u_Ops "), Some("s3cret"), false).unwrap(),
+            Some(DefaultAdmin { username: "ops".into(), password: "s3cret".into() })
+        );
+        assert!(resolve_default_admin(Some("yes"), Some("9lives"), None, false).unwrap_err().contains("DEFAULT_ADMIN_USER"));
+    }
+}
@@ -3,15 +3,52 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use serde_json::{Value, json};
 
-use crate::db::ArangoDb;
+use crate::config::IdPrefixPolicy;
+use crate::controllers::group_controller::GroupController;
+use crate::db::{CascadeWrite, DatabaseInterface};
 use crate::error::AppError;
 use crate::middleware::auth::Auth;
+use crate::watch::ChangeType;
 use crit_shared::util_models::{AccessControlList, AccessControlStore, Permissions};
 
 // ---------------------------------------------------------------------------
 // KindController trait
 // ---------------------------------------------------------------------------
 
+/// What a delete does with resources owned by the deleted object, chosen by
+/// `?cascade=` on `DELETE /v1/global/{kind}/{id}`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum CascadePolicy {
+    /// No `?cascade=`: refuse with 409 while owned resources remain.
+    #[default]
+    Refuse,
+    /// `?cascade=delete`: soft-delete the owned resources too.
+    Delete,
+    /// `?cascade=reassign&reassign_to=<id>`: hand the owned resources to another principal.
+    Reassign(String),
+}
+
+/// A write a `before_delete` cascade plans for some other document. The delete
+/// applies it in the same transaction as the delete itself, then publishes it.
+#[derive(Debug, Clone)]
+pub struct CascadeChange {
+    pub change: ChangeType,
+    pub kind: &'static str,
+    pub id: String,
+    /// The document to store for `Updated`; the current one for `Deleted`.
+    pub doc: Value,
+}
+
+impl CascadeChange {
+    pub fn write(&self) -> CascadeWrite {
+        let (collection, key) = (self.kind.to_string(), self.id.clone());
+        match self.change {
+            ChangeType::Deleted => CascadeWrite::SoftDelete { collection, key },
+            ChangeType::Created | ChangeType::Updated => CascadeWrite::Replace { collection, key, doc: self.doc.clone() },
+        }
+    }
+}
+
 /// Trait that each kind-specific controller implements to handle authorization
 /// and document transformation for the generic gitops API.
 #[async_trait]
@@ -47,13 +84,28 @@ pub trait KindController: Send + Sync {
     /// Called after a document is successfully created. Used for post-creation
     /// setup (e.g. inserting creator as group member).
     /// Default is a no-op.
-    async fn after_create(&self, _key: &str, _user_id: &str, _db: &ArangoDb) -> Result<(), AppError> {
+    async fn after_create(&self, _key: &str, _user_id: &str, _db: &dyn DatabaseInterface) -> Result<(), AppError> {
         Ok(())
     }
 
+    /// Called before a document is soft-deleted, with the caller's cascade choice.
+    /// Refuse (409) or plan the cleanup of resources the document owns per
+    /// `policy`. Must not write: `delete_cascading` applies the returned
+    /// changes atomically with the delete.
+    /// Default is a no-op.
+    async fn before_delete(
+        &self,
+        _key: &str,
+        _actor: &str,
+        _policy: &CascadePolicy,
+        _db: &dyn DatabaseInterface,
+    ) -> Result<Vec<CascadeChange>, AppError> {
+        Ok(Vec::new())
+    }
+
     /// Called after a document is deleted. Used for cascade cleanup.
     /// Default is a no-op.
-    async fn after_delete(&self, _key: &str, _db: &ArangoDb) -> Result<(), AppError> {
+    async fn after_delete(&self, _key: &str, _db: &dyn DatabaseInterface) -> Result<(), AppError> {
         // TODO: log any errors here explicitly, as after_delete may break data integrity and should be treated as major error if it does
         Ok(())
     }
@@ -61,11 +113,27 @@ pub trait KindController: Send + Sync {
     /// Called after a document is updated/upserted. Used for post-update checks
     /// (e.g. empty-group deletion).
     /// Default is a no-op.
-    async fn after_update(&self, _key: &str, _db: &ArangoDb) -> Result<(), AppError> {
+    async fn after_update(&self, _key: &str, _db: &dyn DatabaseInterface) -> Result<(), AppError> {
         // TODO: if it can fail, log it explicitly, as after_update may break data integrity and should be treated as major error if it does
         Ok(())
     }
 
+    /// Hash of the state actually in effect for a stored document, compared by
+    /// the `Reconciler` against the document's `hash_code` (its desired state).
+    /// `None` means this kind has nothing to observe beyond the document itself,
+    /// so it is never considered drifted.
+    /// Default returns `None`.
+    async fn observe(&self, _doc: &Value, _db: &dyn DatabaseInterface) -> Result<Option<String>, AppError> {
+        Ok(None)
+    }
+
+    /// Bring observed state back in line with the stored document. Called by the
+    /// `Reconciler` only when `observe` disagrees with `hash_code`.
+    /// Default is a no-op.
+    async fn reconcile(&self, _doc: &Value, _db: &dyn DatabaseInterface) -> Result<(), AppError> {
+        Ok(())
+    }
+
     /// Convert an internal ArangoDB document to the external representation
     /// suitable for list responses (brief/summary view).
     /// Default delegates to `to_external`.
@@ -80,6 +148,12 @@ pub trait KindController: Send + Sync {
         None
     }
 
+    /// Fields `GET /v1/search` matches the query against, as ArangoDB dot
+    /// paths (e.g. `personal.name`). Empty means the kind is not searchable.
+    fn search_fields(&self) -> &'static [&'static str] {
+        &[]
+    }
+
     /// Whether this resource kind is project-scoped.
     /// Scoped resources live under `/v1/projects/{project}/{kind}`.
     /// Defaults to `false`; override to `true` for project-scoped kinds.
@@ -89,6 +163,24 @@ pub trait KindController: Send + Sync {
         false
     }
 
+    /// Prefix of ids generated for creates that omit `id` (the resource's
+    /// `#[crit_resource(prefix)]`). Defaults to none.
+    fn id_prefix(&self) -> &'static str {
+        ""
+    }
+
+    /// Top-level fields left out of the desired-state hash on top of the
+    /// standard ones (the resource's `#[hash_exclude]` fields). Defaults to none.
+    fn hash_excluded_fields(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// JSON pointer to the body field a readable id is derived from when a
+    /// create omits `id` (e.g. `/name`). `None` means a random id.
+    fn slug_source(&self) -> Option<&'static str> {
+        None
+    }
+
     /// Super-permission that short-circuits ACL checks for this kind.
     /// Return `None` to indicate no super-permission bypass (fully permissive for list).
     fn super_permission(&self) -> Option<&str> {
@@ -109,7 +201,7 @@ pub trait KindController: Send + Sync {
     /// For groups, this means every ACL principal must be a member (direct or transitive).
     /// Returns Ok(()) if valid, or a BadRequest error listing invalid principals.
     /// Default is a no-op (all principals allowed).
-    async fn validate_acl_principals(&self, _body: &Value, _db: &ArangoDb) -> Result<(), AppError> {
+    async fn validate_acl_principals(&self, _body: &Value, _db: &dyn DatabaseInterface) -> Result<(), AppError> {
         Ok(())
     }
 
@@ -141,6 +233,37 @@ pub trait KindController: Send + Sync {
 // Shared helpers (reusable by all controllers)
 // ---------------------------------------------------------------------------
 
+/// Id for attempt `attempt` (0-based) of a create that omitted one:
+/// `{prefix}{slug}` first, then `{prefix}{slug}_{random}` after collisions.
+/// Without a slug the kind's singular name stands in and the suffix is
+/// always added, e.g. `u_user_7k2m9q`.
+pub fn generated_id(prefix: &str, kind: &str, slug: Option<&str>, attempt: u32) -> String {
+    match slug {
+        Some(slug) if attempt == 0 => format!("{}{}", prefix, slug),
+        Some(slug) => format!("{}{}_{}", prefix, slug, random_suffix()),
+        None => format!("{}{}_{}", prefix, kind.trim_end_matches('s'), random_suffix()),
+    }
+}
+
+/// A client-supplied id checked against the kind's prefix: kept as-is when it
+/// already carries the prefix (or the kind has none), otherwise prefixed or
+/// refused according to `policy`.
+pub fn prefixed_id(id: &str, prefix: &str, policy: IdPrefixPolicy) -> Result<String, String> {
+    if prefix.is_empty() || id.starts_with(prefix) {
+        return Ok(id.to_string());
+    }
+    match policy {
+        IdPrefixPolicy::Add => Ok(format!("{}{}", prefix, id)),
+        IdPrefixPolicy::Reject => Err(format!("id '{}' must start with '{}'", id, prefix)),
+    }
+}
+
+/// Six lowercase base32 characters from the random half of a ULID.
+fn random_suffix() -> String {
+    let ulid = ulid::Ulid::new().to_string().to_lowercase();
+    ulid[ulid.len() - 6..].to_string()
+}
+
 /// Rename `id` → `_key` in a JSON object.
 pub fn rename_id_to_key(body: &mut Value) {
     if let Some(obj) = body.as_object_mut() {
@@ -178,31 +301,60 @@ pub fn standard_to_external(mut doc: Value) -> Value {
 }
 
 /// Inject common creation defaults into a document body:
-/// labels, annotations (empty if absent), and state audit timestamps.
+/// labels, annotations (empty if absent), state audit timestamps and generation 1.
+/// `state` is server-managed: whatever the client sent is replaced.
 pub fn inject_create_defaults(body: &mut Value, user_id: &str) {
     let Some(obj) = body.as_object_mut() else {
         return;
     };
     obj.entry("labels").or_insert_with(|| json!({}));
     obj.entry("annotations").or_insert_with(|| json!({}));
-    let state = obj.entry("state").or_insert_with(|| json!({}));
-    if let Some(state_obj) = state.as_object_mut() {
-        state_obj
-            .entry("created_at")
-            .or_insert_with(|| json!(chrono::Utc::now().to_rfc3339()));
-        state_obj
-            .entry("created_by")
-            .or_insert_with(|| json!(user_id));
-        state_obj
-            .entry("updated_at")
-            .or_insert_with(|| json!(chrono::Utc::now().to_rfc3339()));
-    }
+    obj.insert(
+        "state".to_string(),
+        json!({
+            "created_at": chrono::Utc::now().to_rfc3339(),
+            "created_by": user_id,
+            "generation": 1,
+        }),
+    );
+}
+
+/// Stamp `updated_at` / `updated_by` on a replacement body, carrying the
+/// creation fields over from the stored document. Client-sent `state` is ignored.
+pub fn stamp_update(body: &mut Value, existing: &Value, user_id: &str) {
+    let Some(obj) = body.as_object_mut() else {
+        return;
+    };
+    let mut state = existing
+        .get("state")
+        .and_then(|s| s.as_object())
+        .cloned()
+        .unwrap_or_default();
+    state.insert("updated_at".to_string(), json!(chrono::Utc::now().to_rfc3339()));
+    state.insert("updated_by".to_string(), json!(user_id));
+    obj.insert("state".to_string(), Value::Object(state));
+}
+
+/// Advance `state.generation` of a freshly hashed document when its
+/// `hash_code` differs from `prev_hash`, the hash of the stored version.
+/// Documents stored before generations existed count as generation 1.
+pub fn advance_generation(doc: &mut Value, prev_hash: Option<&str>) {
+    let changed = doc.get("hash_code").and_then(|v| v.as_str()) != prev_hash;
+    let Some(state) = doc.get_mut("state").and_then(|s| s.as_object_mut()) else {
+        return;
+    };
+    let current = state.get("generation").and_then(|v| v.as_u64()).filter(|g| *g > 0).unwrap_or(1);
+    state.insert("generation".to_string(), json!(current + changed as u64));
 }
 
 /// Filter a JSON object to only keep the given field names.
-/// Used by `to_list_external` to produce brief representations.
+/// Used by `to_list_external` to produce brief representations; `generation`
+/// is lifted out of `state` first so briefs can carry it.
 pub fn filter_to_brief(mut value: Value, fields: &[&str]) -> Value {
     if let Some(obj) = value.as_object_mut() {
+        if let Some(generation) = obj.get("state").and_then(|s| s.get("generation")).cloned() {
+            obj.insert("generation".to_string(), generation);
+        }
         obj.retain(|key, _| fields.contains(&key.as_str()));
     }
     value
@@ -269,6 +421,51 @@ pub fn parse_acl(doc: &Value) -> Result<AccessControlStore, ()> {
     })
 }
 
+/// Soft-delete `kind/id` with the cascade `policy` asks for. The writes planned
+/// by `before_delete`, the delete and the removal of the document's memberships
+/// happen in one transaction; groups left empty are deleted afterwards, then
+/// `after_delete` runs. Returns the cascaded changes for the caller to publish.
+pub async fn delete_cascading(
+    ctrl: &dyn KindController,
+    db: &dyn DatabaseInterface,
+    kind: &str,
+    id: &str,
+    actor: &str,
+    policy: &CascadePolicy,
+) -> Result<Vec<CascadeChange>, AppError> {
+    let cascaded = ctrl.before_delete(id, actor, policy, db).await?;
+    let writes: Vec<CascadeWrite> = cascaded.iter().map(CascadeChange::write).collect();
+
+    let emptied = db
+        .soft_delete_cascading(kind, id, actor, &writes)
+        .await
+        .map_err(|e| {
+            if e.to_string().contains(&format!("not found or already deleted: {}/{}", kind, id)) {
+                AppError::not_found(format!("{}/{}", kind, id))
+            } else {
+                AppError::Internal(e)
+            }
+        })?;
+
+    for group_id in emptied {
+        log::debug!("[CASCADE] group {} lost its last member with {}/{}, deleting", group_id, kind, id);
+        GroupController::cascade_delete_group(db, &group_id).await?;
+    }
+
+    if let Err(e) = ctrl.after_delete(id, db).await {
+        log::error!("[CASCADE] after_delete hook failed: kind={}, id={}, error={}", kind, id, e);
+        return Err(e);
+    }
+
+    // History is best effort, as for direct writes.
+    for c in cascaded.iter().filter(|c| c.change == ChangeType::Updated) {
+        if let Err(e) = db.write_history_entry(c.kind, &c.id, c.doc.clone(), actor).await {
+            log::error!("[CASCADE] write_history_entry failed: kind={}, id={}, error={}", c.kind, c.id, e);
+        }
+    }
+    Ok(cascaded)
+}
+
 // ---------------------------------------------------------------------------
 // DefaultKindController — permissive fallback for unknown kinds
 // ---------------------------------------------------------------------------
@@ -306,11 +503,11 @@ impl KindController for DefaultKindController {
 // ---------------------------------------------------------------------------
 
 pub struct GitopsController {
-    pub db: Arc<ArangoDb>,
+    pub db: Arc<dyn DatabaseInterface>,
 }
 
 impl GitopsController {
-    pub fn new(db: Arc<ArangoDb>) -> Self {
+    pub fn new(db: Arc<dyn DatabaseInterface>) -> Self {
         Self { db }
     }
 }
@@ -3,12 +3,12 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use serde_json::{Value, json};
 
-use crate::db::ArangoDb;
+use crate::db::DatabaseInterface;
 use crate::error::AppError;
 use crate::middleware::auth::Auth;
 use crate::validation::naming::validate_group_id;
 use crit_shared::data_models::Group;
-use crit_shared::util_models::{Permissions, super_permissions};
+use crit_shared::util_models::{Permissions, PrincipalId, super_permissions};
 
 use super::gitops_controller::{
     KindController, filter_to_brief, inject_create_defaults, parse_acl, standard_to_external,
@@ -16,11 +16,11 @@ use super::gitops_controller::{
 };
 
 pub struct GroupController {
-    pub db: Arc<ArangoDb>,
+    pub db: Arc<dyn DatabaseInterface>,
 }
 
 impl GroupController {
-    pub fn new(db: Arc<ArangoDb>) -> Self {
+    pub fn new(db: Arc<dyn DatabaseInterface>) -> Self {
         Self { db }
     }
 
@@ -30,21 +30,23 @@ impl GroupController {
     /// Remove all membership references for a group and return parent groups
     /// that became empty as a result.
     async fn cleanup_group_references(
-        db: &ArangoDb,
+        db: &dyn DatabaseInterface,
         group_id: &str,
     ) -> Result<Vec<String>, AppError> {
+        let group_id = PrincipalId::from(group_id);
+
         // Remove all membership edges where this group is the target (members OF this group)
-        db.remove_all_members_of_group(group_id).await?;
+        db.remove_all_members_of_group(&group_id).await?;
 
         // Remove this group as a member of all parent groups, get list of now-empty parents
-        let empty_parents = db.remove_principal_from_all_groups(group_id).await?;
+        let empty_parents = db.remove_principal_from_all_groups(&group_id).await?;
 
         Ok(empty_parents)
     }
 
     /// Recursively delete a group and cascade: remove it from parent groups,
     /// delete any parent groups that become empty.
-    pub async fn cascade_delete_group(db: &ArangoDb, group_id: &str) -> Result<(), AppError> {
+    pub async fn cascade_delete_group(db: &dyn DatabaseInterface, group_id: &str) -> Result<(), AppError> {
         log::debug!(
             "[CASCADE] GroupController::cascade_delete_group: group={}",
             group_id
@@ -75,7 +77,7 @@ impl KindController for GroupController {
         // ADM_USER_MANAGER can read any group
         let is_admin = self
             .db
-            .has_permission(user_id, super_permissions::ADM_USER_MANAGER)
+            .has_permission(&PrincipalId::from(user_id), super_permissions::ADM_USER_MANAGER)
             .await?;
         log::debug!(
             "[ACL] GroupController::can_read: is_admin(ADM_USER_MANAGER)={}",
@@ -88,7 +90,7 @@ impl KindController for GroupController {
         // Check document-level ACL for READ
         if let Some(doc) = doc {
             if let Ok(acl) = parse_acl(doc) {
-                let principals = self.db.get_user_principals(user_id).await?;
+                let principals = self.db.get_user_principals(&PrincipalId::from(user_id)).await?;
                 log::debug!(
                     "[ACL] GroupController::can_read: principals={:?}",
                     principals
@@ -109,7 +111,7 @@ impl KindController for GroupController {
         // ADM_USER_MANAGER can write any group
         let is_admin = self
             .db
-            .has_permission(user_id, super_permissions::ADM_USER_MANAGER)
+            .has_permission(&PrincipalId::from(user_id), super_permissions::ADM_USER_MANAGER)
             .await?;
         log::debug!(
             "[ACL] GroupController::can_write: is_admin(ADM_USER_MANAGER)={}",
@@ -123,7 +125,7 @@ impl KindController for GroupController {
             Some(doc) => {
                 // Existing group: check ACL for MODIFY permission
                 if let Ok(acl) = parse_acl(doc) {
-                    let principals = self.db.get_user_principals(user_id).await?;
+                    let principals = self.db.get_user_principals(&PrincipalId::from(user_id)).await?;
                     log::debug!(
                         "[ACL] GroupController::can_write: principals={:?}",
                         principals
@@ -141,7 +143,7 @@ impl KindController for GroupController {
                 // New group: check usr_create_groups super-permission
                 let has_perm = self
                     .db
-                    .has_permission(user_id, super_permissions::USR_CREATE_GROUPS)
+                    .has_permission(&PrincipalId::from(user_id), super_permissions::USR_CREATE_GROUPS)
                     .await?;
                 log::debug!(
                     "[ACL] GroupController::can_write: new group, has_permission(USR_CREATE_GROUPS)={}",
@@ -157,11 +159,11 @@ impl KindController for GroupController {
         if let Some(obj) = body.as_object_mut() {
             if let Some(id) = obj.get("id").and_then(|v| v.as_str()) {
                 // Validate (strips g_ prefix if present, validates, returns without prefix)
-                let validated_id = validate_group_id(id).map_err(AppError::Validation)?;
+                let validated_id = validate_group_id(id).map_err(|e| AppError::invalid_field("id", e))?;
 
                 // Add g_ prefix
-                let prefixed_id = format!("g_{}", validated_id);
-                obj.insert("id".to_string(), Value::String(prefixed_id));
+                let prefixed_id = PrincipalId::group(&validated_id);
+                obj.insert("id".to_string(), Value::String(prefixed_id.into_string()));
             }
         }
 
@@ -179,14 +181,30 @@ impl KindController for GroupController {
 
     fn list_projection_fields(&self) -> Option<&'static [&'static str]> {
         // _key → "id" after to_external; "acl" needed by can_read for ACL checks
-        Some(&["_key", "name", "acl", "labels"])
+        Some(&["_key", "name", "acl", "labels", "state"])
+    }
+
+    fn search_fields(&self) -> &'static [&'static str] {
+        &["_key", "name"]
+    }
+
+    fn id_prefix(&self) -> &'static str {
+        Group::id_prefix()
+    }
+
+    fn hash_excluded_fields(&self) -> &'static [&'static str] {
+        Group::hash_excluded_fields()
+    }
+
+    fn slug_source(&self) -> Option<&'static str> {
+        Some("/name")
     }
 
     fn super_permission(&self) -> Option<&str> {
         Some(super_permissions::ADM_USER_MANAGER)
     }
 
-    async fn validate_acl_principals(&self, body: &Value, db: &ArangoDb) -> Result<(), AppError> {
+    async fn validate_acl_principals(&self, body: &Value, db: &dyn DatabaseInterface) -> Result<(), AppError> {
         // Extract the group ID from the body (_key after to_internal, or id before)
         let group_id = body
             .get("_key")
@@ -222,7 +240,7 @@ impl KindController for GroupController {
         }
 
         // Get all transitive members of this group
-        let members = db.get_all_group_members_transitive(group_id).await?;
+        let members = db.get_all_group_members_transitive(&PrincipalId::from(group_id)).await?;
         log::debug!(
             "[ACL] GroupController::validate_acl_principals: group={}, members={:?}, acl_principals={:?}",
             group_id, members, acl_principals
@@ -283,7 +301,7 @@ impl KindController for GroupController {
         }
     }
 
-    async fn after_create(&self, key: &str, user_id: &str, db: &ArangoDb) -> Result<(), AppError> {
+    async fn after_create(&self, key: &str, user_id: &str, db: &dyn DatabaseInterface) -> Result<(), AppError> {
         log::debug!(
             "[LIFECYCLE] GroupController::after_create: group={}, creator={}",
             key,
@@ -291,7 +309,7 @@ impl KindController for GroupController {
         );
 
         // Insert creator as a member of the new group
-        db.add_principal_to_group(user_id, key, None).await?;
+        db.add_principal_to_group(&PrincipalId::from(user_id), &PrincipalId::from(key)).await?;
         log::debug!(
             "[LIFECYCLE] GroupController::after_create: added creator {} as member of group {}",
             user_id,
@@ -301,7 +319,7 @@ impl KindController for GroupController {
         Ok(())
     }
 
-    async fn after_delete(&self, key: &str, db: &ArangoDb) -> Result<(), AppError> {
+    async fn after_delete(&self, key: &str, db: &dyn DatabaseInterface) -> Result<(), AppError> {
         log::debug!("[LIFECYCLE] GroupController::after_delete: group={}", key);
 
         let empty_parents = Self::cleanup_group_references(db, key).await?;
@@ -318,9 +336,9 @@ impl KindController for GroupController {
         Ok(())
     }
 
-    async fn after_update(&self, key: &str, db: &ArangoDb) -> Result<(), AppError> {
+    async fn after_update(&self, key: &str, db: &dyn DatabaseInterface) -> Result<(), AppError> {
         // Check if the group is now empty (zero members) and delete if so
-        let count = db.count_group_members(key).await?;
+        let count = db.count_group_members(&PrincipalId::from(key)).await?;
         log::debug!(
             "[LIFECYCLE] GroupController::after_update: group={}, member_count={}",
             key,
@@ -1,27 +1,15 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use serde_json::Value;
-
-/// Map a principal ID to its ArangoDB collection name based on the ID prefix.
-fn collection_for_principal(principal: &str) -> &'static str {
-    if principal.starts_with("u_") {
-        "users"
-    } else if principal.starts_with("g_") {
-        "groups"
-    } else if principal.starts_with("sa_") {
-        "service_accounts"
-    } else if principal.starts_with("pa_") {
-        "pipeline_accounts"
-    } else {
-        "users"
-    }
-}
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
 
-use crate::db::ArangoDb;
+use crate::db::DatabaseInterface;
+use crate::db::arangodb::collection_for_principal;
 use crate::error::AppError;
 use crate::middleware::auth::Auth;
-use crit_shared::util_models::{Permissions, super_permissions};
+use crit_shared::compute_value_hash;
+use crit_shared::util_models::{Permissions, PrincipalId, PrincipalKind, super_permissions};
 
 use super::gitops_controller::{
     KindController, parse_acl, standard_to_external, standard_to_internal,
@@ -29,11 +17,30 @@ use super::gitops_controller::{
 use super::group_controller::GroupController;
 
 pub struct MembershipController {
-    pub db: Arc<ArangoDb>,
+    pub db: Arc<dyn DatabaseInterface>,
+}
+
+/// What [`MembershipController::add_many`] does with principals that do not exist.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MissingPrincipals {
+    /// Fail the whole batch with 404, adding nobody.
+    #[default]
+    Reject,
+    /// Add the others and report the missing ones as skipped.
+    Skip,
+}
+
+/// Outcome of [`MembershipController::add_many`], principals in input order.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct BulkAddOutcome {
+    pub added: Vec<String>,
+    pub already_members: Vec<String>,
+    pub skipped: Vec<String>,
 }
 
 impl MembershipController {
-    pub fn new(db: Arc<ArangoDb>) -> Self {
+    pub fn new(db: Arc<dyn DatabaseInterface>) -> Self {
         Self { db }
     }
 
@@ -42,7 +49,7 @@ impl MembershipController {
         // ADM_USER_MANAGER bypasses group ACL
         let is_admin = self
             .db
-            .has_permission(user_id, super_permissions::ADM_USER_MANAGER)
+            .has_permission(&PrincipalId::from(user_id), super_permissions::ADM_USER_MANAGER)
             .await?;
         log::debug!(
             "[ACL] MembershipController::can_modify_group: is_admin(ADM_USER_MANAGER)={}",
@@ -56,7 +63,7 @@ impl MembershipController {
         let group_doc = self.db.generic_get("groups", group_id).await?;
         if let Some(doc) = group_doc {
             if let Ok(acl) = parse_acl(&doc) {
-                let principals = self.db.get_user_principals(user_id).await?;
+                let principals = self.db.get_user_principals(&PrincipalId::from(user_id)).await?;
                 log::debug!(
                     "[ACL] MembershipController::can_modify_group: principals={:?}",
                     principals
@@ -73,6 +80,90 @@ impl MembershipController {
         Ok(false)
     }
 
+    /// Add several principals to a group in one write. Duplicates in the input
+    /// are ignored; principals that already are direct members are reported,
+    /// not re-added. Self-membership and cycles fail the batch with 422, and
+    /// nonexistent principals either fail it with 404 or are skipped. The
+    /// caller checks that `user_id` may change the group's members.
+    pub async fn add_many(
+        &self,
+        user_id: &str,
+        group_id: &str,
+        principals: &[String],
+        missing: MissingPrincipals,
+    ) -> Result<BulkAddOutcome, AppError> {
+        let mut unique: Vec<PrincipalId> = Vec::new();
+        for raw in principals {
+            let principal: PrincipalId = raw.parse().map_err(AppError::bad_request)?;
+            if !unique.contains(&principal) {
+                unique.push(principal);
+            }
+        }
+
+        for principal in unique.iter().filter(|p| p.kind() == PrincipalKind::Group) {
+            if principal == group_id {
+                return Err(AppError::unprocessable(format!("groups/{} cannot be a member of itself", group_id)));
+            }
+            if self.db.get_all_group_members_transitive(principal).await?.iter().any(|m| m == group_id) {
+                return Err(AppError::unprocessable(format!(
+                    "groups/{} already contains groups/{}; adding it would create a cycle",
+                    principal, group_id
+                )));
+            }
+        }
+
+        let mut outcome = BulkAddOutcome::default();
+        let mut absent = Vec::new();
+        let group = PrincipalId::from(group_id);
+        let current = self.db.list_group_members(&group).await?;
+        let mut to_add = Vec::new();
+        for principal in unique {
+            let kind = collection_for_principal(&principal);
+            if self.db.generic_get(kind, &principal).await?.is_none() {
+                absent.push(format!("{}/{}", kind, principal));
+                outcome.skipped.push(principal.into());
+            } else if current.iter().any(|m| principal == *m) {
+                outcome.already_members.push(principal.into());
+            } else {
+                to_add.push(principal);
+            }
+        }
+        if !absent.is_empty() && missing == MissingPrincipals::Reject {
+            return Err(AppError::not_found(absent.join(", ")));
+        }
+        if to_add.is_empty() {
+            return Ok(outcome);
+        }
+
+        self.db.add_principals_to_group(&to_add, &group).await?;
+        for principal in &to_add {
+            self.after_create(&format!("{}::{}", principal, group_id), user_id, &*self.db).await?;
+        }
+        log::debug!(
+            "[LIFECYCLE] MembershipController::add_many: group={}, added={}, already={}, skipped={}",
+            group_id,
+            to_add.len(),
+            outcome.already_members.len(),
+            outcome.skipped.len()
+        );
+        outcome.added = to_add.into_iter().map(String::from).collect();
+        Ok(outcome)
+    }
+
+    /// Derive `_from`/`_to` from `principal`/`group`. ArangoDB edge collections
+    /// require them in "collection/key" format.
+    fn set_edge_endpoints(doc: &mut Value) {
+        if let Some(obj) = doc.as_object_mut() {
+            if let Some(principal) = obj.get("principal").and_then(|v| v.as_str()).map(String::from) {
+                let col = collection_for_principal(&principal);
+                obj.insert("_from".to_string(), Value::String(format!("{}/{}", col, principal)));
+            }
+            if let Some(group) = obj.get("group").and_then(|v| v.as_str()).map(String::from) {
+                obj.insert("_to".to_string(), Value::String(format!("groups/{}", group)));
+            }
+        }
+    }
+
     /// Extract the group ID from a membership document or request body.
     fn extract_group_id(doc: &Value) -> Option<String> {
         doc.get("group")
@@ -87,7 +178,7 @@ impl KindController for MembershipController {
         // ADM_USER_MANAGER can read all memberships
         let is_admin = self
             .db
-            .has_permission(user_id, super_permissions::ADM_USER_MANAGER)
+            .has_permission(&PrincipalId::from(user_id), super_permissions::ADM_USER_MANAGER)
             .await?;
         if is_admin {
             return Ok(true);
@@ -99,7 +190,7 @@ impl KindController for MembershipController {
                 let group_doc = self.db.generic_get("groups", &group_id).await?;
                 if let Some(gdoc) = group_doc {
                     if let Ok(acl) = parse_acl(&gdoc) {
-                        let principals = self.db.get_user_principals(user_id).await?;
+                        let principals = self.db.get_user_principals(&PrincipalId::from(user_id)).await?;
                         let result = acl.check_permission(&principals, Permissions::READ);
                         log::debug!(
                             "[ACL] MembershipController::can_read: group={}, READ={}",
@@ -140,17 +231,52 @@ impl KindController for MembershipController {
 
     fn to_internal(&self, body: Value, _auth: &Auth) -> Result<Value, AppError> {
         let mut doc = standard_to_internal(body);
-        // ArangoDB edge collections require _from and _to in "collection/key" format.
-        if let Some(obj) = doc.as_object_mut() {
-            if let Some(principal) = obj.get("principal").and_then(|v| v.as_str()).map(String::from) {
-                let col = collection_for_principal(&principal);
-                obj.insert("_from".to_string(), Value::String(format!("{}/{}", col, principal)));
-            }
-            if let Some(group) = obj.get("group").and_then(|v| v.as_str()).map(String::from) {
-                obj.insert("_to".to_string(), Value::String(format!("groups/{}", group)));
+        Self::set_edge_endpoints(&mut doc);
+        Ok(doc)
+    }
+
+    /// The observed state of a membership is the stored edge itself; it drifts
+    /// from `hash_code` when `_from`/`_to` were changed (or never stamped) outside
+    /// the gitops API.
+    async fn observe(&self, doc: &Value, _db: &dyn DatabaseInterface) -> Result<Option<String>, AppError> {
+        Ok(Some(compute_value_hash(doc)))
+    }
+
+    /// Restore the edge to what the membership declares: `principal` and
+    /// `group` come from the key (`{principal}::{group}`) and `_from`/`_to`
+    /// from those, and the member keeps the READ grant `after_create` gives.
+    /// An edge never written through the API has no `hash_code` yet and gets
+    /// one; drift the restore cannot explain is refused, not re-stamped.
+    async fn reconcile(&self, doc: &Value, db: &dyn DatabaseInterface) -> Result<(), AppError> {
+        let key = doc
+            .get("_key")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| AppError::bad_request("membership without _key"))?;
+        let (principal, group) = key
+            .split_once("::")
+            .ok_or_else(|| AppError::bad_request(format!("membership key {} is not principal::group", key)))?;
+
+        let mut restored = doc.clone();
+        if let Some(obj) = restored.as_object_mut() {
+            obj.remove("_id");
+            obj.remove("_rev");
+            obj.insert("principal".to_string(), json!(principal));
+            obj.insert("group".to_string(), json!(group));
+        }
+        Self::set_edge_endpoints(&mut restored);
+        let hash = compute_value_hash(&restored);
+        match doc.get("hash_code").and_then(|v| v.as_str()) {
+            None => restored["hash_code"] = json!(hash),
+            Some(desired) if desired == hash => {}
+            Some(desired) => {
+                return Err(AppError::conflict(format!(
+                    "memberships/{} drifted beyond its principal and group (desired {}, restored {}); re-apply it",
+                    key, desired, hash
+                )));
             }
         }
-        Ok(doc)
+        db.generic_update("memberships", key, restored).await?;
+        self.after_create(key, "system", db).await
     }
 
     fn to_external(&self, doc: Value) -> Value {
@@ -163,17 +289,17 @@ impl KindController for MembershipController {
         doc
     }
 
-    async fn after_create(&self, key: &str, _user_id: &str, db: &ArangoDb) -> Result<(), AppError> {
+    async fn after_create(&self, key: &str, _user_id: &str, db: &dyn DatabaseInterface) -> Result<(), AppError> {
         // Key format: "{principal}::{group}"
         // Grant READ ACL on the group to the new member
         let parts: Vec<&str> = key.splitn(2, "::").collect();
         if parts.len() != 2 {
             return Ok(());
         }
-        let principal_id = parts[0];
-        let group_id = parts[1];
+        let principal_id = PrincipalId::from(parts[0]);
+        let group_id = PrincipalId::from(parts[1]);
 
-        db.add_principal_to_group_acl(group_id, principal_id, Permissions::READ.bits())
+        db.add_principal_to_group_acl(&group_id, &principal_id, Permissions::READ.bits())
             .await
             .map_err(|e| {
                 log::error!(
@@ -195,16 +321,16 @@ impl KindController for MembershipController {
         Some(super_permissions::ADM_USER_MANAGER)
     }
 
-    async fn after_delete(&self, key: &str, db: &ArangoDb) -> Result<(), AppError> {
+    async fn after_delete(&self, key: &str, db: &dyn DatabaseInterface) -> Result<(), AppError> {
         // The key format is "{principal}::{group}"
         // After a membership is deleted, check if the group is now empty
         let parts: Vec<&str> = key.splitn(2, "::").collect();
         if parts.len() != 2 {
             return Ok(());
         }
-        let group_id = parts[1];
+        let group_id = PrincipalId::from(parts[1]);
 
-        let count = db.count_group_members(group_id).await?;
+        let count = db.count_group_members(&group_id).await?;
         log::debug!(
             "[LIFECYCLE] MembershipController::after_delete: group={}, member_count={}",
             group_id, count
@@ -215,7 +341,7 @@ impl KindController for MembershipController {
                 "[LIFECYCLE] MembershipController::after_delete: group {} is empty, deleting",
                 group_id
             );
-            GroupController::cascade_delete_group(db, group_id).await?;
+            GroupController::cascade_delete_group(db, &group_id).await?;
         }
 
         Ok(())
@@ -1,48 +1,76 @@
 use std::sync::Arc;
 
-use crate::db::ArangoDb;
+use crate::db::DatabaseInterface;
 
 pub mod user_controller;
 pub mod group_controller;
 pub mod gitops_controller;
 pub mod membership_controller;
 pub mod project_controller;
+pub mod quota_controller;
 
 use gitops_controller::{DefaultKindController, GitopsController, KindController};
 use group_controller::GroupController;
 use membership_controller::MembershipController;
 use project_controller::ProjectController;
+use quota_controller::QuotaController;
 use user_controller::UserController;
 
+/// Accessor for a kind's dedicated controller.
+type ControllerFor = fn(&Controller) -> &dyn KindController;
+
+/// The kind registry: every kind with a dedicated controller, in one place.
+/// Handlers dispatch through `for_kind`, and the periodic reconciler walks
+/// `registered_kinds`, so neither needs editing when a kind is added. Kinds not
+/// listed here are still served, by `DefaultKindController`.
+///
+/// To register a kind, implement `KindController` (see `gitops_controller.rs`),
+/// add it as a field of `Controller`, and add one line here:
+///
+/// ```ignore
+/// ("tickets", |c| &c.ticket),
+/// ```
+const REGISTRY: &[(&str, ControllerFor)] = &[
+    ("users", |c| &c.user),
+    ("groups", |c| &c.group),
+    ("memberships", |c| &c.membership),
+    ("projects", |c| &c.project),
+    ("quotas", |c| &c.quota),
+];
+
 pub struct Controller {
     pub user: UserController,
     pub group: GroupController,
     pub gitops: GitopsController,
     pub membership: MembershipController,
     pub project: ProjectController,
+    pub quota: QuotaController,
     default: DefaultKindController,
 }
 
 impl Controller {
-    pub fn new(db: Arc<ArangoDb>) -> Self {
+    pub fn new(db: Arc<dyn DatabaseInterface>) -> Self {
         Self {
             user: UserController::new(db.clone()),
             group: GroupController::new(db.clone()),
             gitops: GitopsController::new(db.clone()),
             membership: MembershipController::new(db.clone()),
             project: ProjectController::new(db.clone()),
+            quota: QuotaController::new(db.clone()),
             default: DefaultKindController,
         }
     }
 
-    /// Dispatch to the appropriate kind-specific controller.
+    /// Dispatch to the kind's registered controller, or the default one.
     pub fn for_kind(&self, kind: &str) -> &dyn KindController {
-        match kind {
-            "users" => &self.user,
-            "groups" => &self.group,
-            "memberships" => &self.membership,
-            "projects" => &self.project,
-            _ => &self.default,
-        }
+        REGISTRY
+            .iter()
+            .find(|(name, _)| *name == kind)
+            .map_or(&self.default as &dyn KindController, |(_, get)| get(self))
+    }
+
+    /// Kinds with a dedicated controller, in registration order.
+    pub fn registered_kinds() -> impl Iterator<Item = &'static str> {
+        REGISTRY.iter().map(|(name, _)| *name)
     }
 }
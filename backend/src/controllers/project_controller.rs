@@ -1,32 +1,171 @@
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 use async_trait::async_trait;
 use serde_json::{Value, json};
 
-use crate::db::ArangoDb;
+use crate::db::DatabaseInterface;
 use crate::error::AppError;
 use crate::middleware::auth::Auth;
+use crit_shared::compute_value_hash_excluding;
 use crit_shared::data_models::Project;
-use crit_shared::util_models::{Permissions, super_permissions};
+use crit_shared::util_models::{Permissions, PrincipalId, ProjectRole, super_permissions};
 
 use super::gitops_controller::{
-    KindController, filter_to_brief, inject_create_defaults, parse_acl, standard_to_external, standard_to_internal,
+    KindController, advance_generation, filter_to_brief, inject_create_defaults, parse_acl, standard_to_external,
+    standard_to_internal,
 };
 
 pub struct ProjectController {
-    pub db: Arc<ArangoDb>,
+    pub db: Arc<dyn DatabaseInterface>,
 }
 
 impl ProjectController {
-    pub fn new(db: Arc<ArangoDb>) -> Self {
+    pub fn new(db: Arc<dyn DatabaseInterface>) -> Self {
         Self { db }
     }
+
+    /// Project members with their roles, read from the unscoped entries of the
+    /// project ACL. Principals whose bits fall below Reporter are omitted.
+    pub fn members(doc: &Value) -> Vec<(String, ProjectRole)> {
+        let mut granted: BTreeMap<String, Permissions> = BTreeMap::new();
+        if let Ok(acl) = parse_acl(doc) {
+            for entry in acl.list.iter().filter(|e| is_unscoped(e.scope.as_deref())) {
+                for p in &entry.principals {
+                    *granted.entry(p.clone()).or_default() |= entry.permissions;
+                }
+            }
+        }
+        granted
+            .into_iter()
+            .filter_map(|(p, perms)| ProjectRole::from_permissions(perms).map(|r| (p, r)))
+            .collect()
+    }
+
+    /// Highest role held by any of `principals` (a user plus their groups).
+    pub fn role_of(doc: &Value, principals: &[String]) -> Option<ProjectRole> {
+        Self::members(doc)
+            .into_iter()
+            .filter(|(p, _)| principals.contains(p))
+            .map(|(_, r)| r)
+            .max()
+    }
+
+    /// Set (`Some`) or remove (`None`) `principal`'s role in the project ACL.
+    /// The principal is taken out of every unscoped entry, then added to the
+    /// entry matching the role exactly. Scoped entries are left alone.
+    /// Refuses to demote or remove the last owner.
+    pub fn set_member_role(
+        doc: &mut Value,
+        principal: &str,
+        role: Option<ProjectRole>,
+    ) -> Result<(), AppError> {
+        let members = Self::members(doc);
+        let is_owner = |p: &str| {
+            members
+                .iter()
+                .any(|(m, r)| m == p && *r == ProjectRole::Owner)
+        };
+        let owners = members.iter().filter(|(_, r)| *r == ProjectRole::Owner).count();
+        if is_owner(principal) && role != Some(ProjectRole::Owner) && owners == 1 {
+            return Err(AppError::conflict(format!(
+                "{} is the last owner of the project",
+                principal
+            )));
+        }
+
+        let Some(obj) = doc.as_object_mut() else {
+            return Err(AppError::bad_request("project document is not an object"));
+        };
+        let acl = obj
+            .entry("acl")
+            .or_insert_with(|| json!({ "list": [] }));
+        let list = acl
+            .as_object_mut()
+            .ok_or_else(|| AppError::bad_request("project acl is not an object"))?
+            .entry("list")
+            .or_insert_with(|| json!([]))
+            .as_array_mut()
+            .ok_or_else(|| AppError::bad_request("project acl list is not an array"))?;
+
+        for entry in list.iter_mut() {
+            if !is_unscoped(entry.get("scope").and_then(|v| v.as_str())) {
+                continue;
+            }
+            if let Some(principals) = entry.get_mut("principals").and_then(|v| v.as_array_mut()) {
+                principals.retain(|p| p.as_str() != Some(principal));
+            }
+        }
+        list.retain(|entry| {
+            entry
+                .get("principals")
+                .and_then(|v| v.as_array())
+                .is_some_and(|p| !p.is_empty())
+        });
+
+        if let Some(role) = role {
+            let bits = role.permissions().bits();
+            let existing = list.iter_mut().find(|entry| {
+                is_unscoped(entry.get("scope").and_then(|v| v.as_str()))
+                    && entry.get("permissions").and_then(|v| v.as_u64()) == Some(bits as u64)
+            });
+            match existing.and_then(|e| e.get_mut("principals")).and_then(|v| v.as_array_mut()) {
+                Some(principals) => principals.push(json!(principal)),
+                None => list.push(json!({ "permissions": bits, "principals": [principal] })),
+            }
+        }
+
+        if let Some(acl_obj) = acl.as_object_mut() {
+            acl_obj.insert("last_mod_date".to_string(), json!(chrono::Utc::now().to_rfc3339()));
+        }
+        Ok(())
+    }
+
+    /// Live projects where `principal` is the only direct Owner, i.e. the ones
+    /// that would be left ownerless if the principal went away.
+    pub async fn sole_owned_by(db: &dyn DatabaseInterface, principal: &str) -> Result<Vec<Value>, AppError> {
+        let docs = db.generic_list("projects", None, None, None).await?.docs;
+        Ok(docs
+            .into_iter()
+            .filter(|doc| {
+                let owners: Vec<String> = Self::members(doc)
+                    .into_iter()
+                    .filter(|(_, role)| *role == ProjectRole::Owner)
+                    .map(|(p, _)| p)
+                    .collect();
+                owners == [principal]
+            })
+            .collect())
+    }
+
+    /// Make `to` an Owner in place of `from` and re-stamp `hash_code`. Returns
+    /// the document to store; the caller writes it.
+    pub fn reassign_owner(mut doc: Value, from: &str, to: &str) -> Result<Value, AppError> {
+        Self::set_member_role(&mut doc, to, Some(ProjectRole::Owner))?;
+        Self::set_member_role(&mut doc, from, None)?;
+        if let Some(obj) = doc.as_object_mut() {
+            obj.remove("_id");
+            obj.remove("_rev");
+        }
+        let prev_hash = doc.get("hash_code").and_then(|v| v.as_str()).map(String::from);
+        let hash = compute_value_hash_excluding(&doc, Project::hash_excluded_fields());
+        if let Some(obj) = doc.as_object_mut() {
+            obj.insert("hash_code".to_string(), json!(hash));
+        }
+        advance_generation(&mut doc, prev_hash.as_deref());
+        Ok(doc)
+    }
+}
+
+/// Unscoped ACL entries (no scope or `"*"`) carry project-wide roles.
+fn is_unscoped(scope: Option<&str>) -> bool {
+    matches!(scope, None | Some("*"))
 }
 
 #[async_trait]
 impl KindController for ProjectController {
     async fn can_read(&self, user_id: &str, doc: Option<&Value>) -> Result<bool, AppError> {
-        let principals = self.db.get_user_principals(user_id).await?;
+        let principals = self.db.get_user_principals(&PrincipalId::from(user_id)).await?;
 
         // ADM_CONFIG_EDITOR can read any project
         if self
@@ -56,7 +195,7 @@ impl KindController for ProjectController {
     }
 
     async fn can_write(&self, user_id: &str, doc: Option<&Value>) -> Result<bool, AppError> {
-        let principals = self.db.get_user_principals(user_id).await?;
+        let principals = self.db.get_user_principals(&PrincipalId::from(user_id)).await?;
 
         // ADM_CONFIG_EDITOR can write any project
         if self
@@ -116,7 +255,23 @@ impl KindController for ProjectController {
     }
 
     fn list_projection_fields(&self) -> Option<&'static [&'static str]> {
-        Some(&["_key", "name", "acl", "labels"])
+        Some(&["_key", "name", "acl", "labels", "state"])
+    }
+
+    fn search_fields(&self) -> &'static [&'static str] {
+        &["_key", "name", "description"]
+    }
+
+    fn id_prefix(&self) -> &'static str {
+        Project::id_prefix()
+    }
+
+    fn hash_excluded_fields(&self) -> &'static [&'static str] {
+        Project::hash_excluded_fields()
+    }
+
+    fn slug_source(&self) -> Option<&'static str> {
+        Some("/name")
     }
 
     fn super_permission(&self) -> Option<&str> {
@@ -168,3 +323,67 @@ impl KindController for ProjectController {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(entries: Value) -> Value {
+        json!({ "_key": "p1", "acl": { "list": entries, "last_mod_date": "2025-01-01T00:00:00Z" } })
+    }
+
+    #[test]
+    fn members_take_highest_unscoped_role() {
+        let doc = project(json!([
+            { "permissions": Permissions::ROOT.bits(), "principals": ["u_owner"] },
+            { "permissions": ProjectRole::Reporter.permissions().bits(), "principals": ["u_bob", "g_qa"] },
+            { "permissions": Permissions::WRITE.bits(), "principals": ["u_bob"] },
+            { "permissions": Permissions::ROOT.bits(), "principals": ["u_tasks"], "scope": "tasks" },
+        ]));
+        assert_eq!(
+            ProjectController::members(&doc),
+            vec![
+                ("g_qa".to_string(), ProjectRole::Reporter),
+                ("u_bob".to_string(), ProjectRole::Member),
+                ("u_owner".to_string(), ProjectRole::Owner),
+            ]
+        );
+        assert_eq!(
+            ProjectController::role_of(&doc, &["u_carol".into(), "g_qa".into()]),
+            Some(ProjectRole::Reporter)
+        );
+    }
+
+    #[test]
+    fn set_member_role_moves_principal_between_entries() {
+        let mut doc = project(json!([
+            { "permissions": Permissions::ROOT.bits(), "principals": ["u_owner"] },
+            { "permissions": Permissions::ROOT.bits(), "principals": ["u_tasks"], "scope": "tasks" },
+        ]));
+        ProjectController::set_member_role(&mut doc, "u_bob", Some(ProjectRole::Member)).unwrap();
+        ProjectController::set_member_role(&mut doc, "u_bob", Some(ProjectRole::Admin)).unwrap();
+        assert_eq!(ProjectController::role_of(&doc, &["u_bob".into()]), Some(ProjectRole::Admin));
+        // The Member entry emptied out and was dropped; the scoped entry is untouched.
+        assert_eq!(doc["acl"]["list"].as_array().unwrap().len(), 3);
+
+        ProjectController::set_member_role(&mut doc, "u_bob", None).unwrap();
+        assert_eq!(ProjectController::role_of(&doc, &["u_bob".into()]), None);
+    }
+
+    #[test]
+    fn last_owner_cannot_be_demoted_or_removed() {
+        let mut doc = project(json!([
+            { "permissions": Permissions::ROOT.bits(), "principals": ["u_owner"] },
+        ]));
+        let err = ProjectController::set_member_role(&mut doc, "u_owner", Some(ProjectRole::Admin));
+        assert!(matches!(err, Err(AppError::Conflict(_))));
+        assert!(ProjectController::set_member_role(&mut doc, "u_owner", None).is_err());
+
+        ProjectController::set_member_role(&mut doc, "u_second", Some(ProjectRole::Owner)).unwrap();
+        ProjectController::set_member_role(&mut doc, "u_owner", None).unwrap();
+        assert_eq!(
+            ProjectController::members(&doc),
+            vec![("u_second".to_string(), ProjectRole::Owner)]
+        );
+    }
+}
@@ -0,0 +1,68 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::db::DatabaseInterface;
+use crate::error::AppError;
+use crate::middleware::auth::Auth;
+use crit_shared::util_models::{PrincipalId, super_permissions};
+
+use super::gitops_controller::{KindController, standard_to_external, standard_to_internal};
+
+/// Limit fields a `quotas` document may set. Each is a count, or `null` for
+/// no limit; a field left out falls back to the server-wide default.
+pub const QUOTA_FIELDS: &[&str] = &["max_owned_projects"];
+
+/// Per-user quota overrides, one document per user keyed by the user id
+/// (`u_alice`). Managed by user managers; users may read their own.
+pub struct QuotaController {
+    pub db: Arc<dyn DatabaseInterface>,
+}
+
+impl QuotaController {
+    pub fn new(db: Arc<dyn DatabaseInterface>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl KindController for QuotaController {
+    async fn can_read(&self, user_id: &str, doc: Option<&Value>) -> Result<bool, AppError> {
+        if doc.and_then(|d| d.get("_key")).and_then(|v| v.as_str()) == Some(user_id) {
+            return Ok(true);
+        }
+        Ok(self.db.has_permission(&PrincipalId::from(user_id), super_permissions::ADM_USER_MANAGER).await?)
+    }
+
+    async fn can_write(&self, user_id: &str, _doc: Option<&Value>) -> Result<bool, AppError> {
+        Ok(self.db.has_permission(&PrincipalId::from(user_id), super_permissions::ADM_USER_MANAGER).await?)
+    }
+
+    fn to_internal(&self, body: Value, _auth: &Auth) -> Result<Value, AppError> {
+        let id = body.get("id").and_then(|v| v.as_str()).unwrap_or_default();
+        if !id.starts_with("u_") {
+            return Err(AppError::invalid_field("id", "quotas are keyed by user id (u_...)"));
+        }
+        for field in QUOTA_FIELDS {
+            match body.get(*field) {
+                None | Some(Value::Null) => {}
+                Some(v) if v.is_u64() => {}
+                Some(_) => return Err(AppError::invalid_field(*field, "must be a non-negative integer or null")),
+            }
+        }
+        Ok(standard_to_internal(body))
+    }
+
+    fn to_external(&self, doc: Value) -> Value {
+        standard_to_external(doc)
+    }
+
+    fn id_prefix(&self) -> &'static str {
+        "u_"
+    }
+
+    fn super_permission(&self) -> Option<&str> {
+        Some(super_permissions::ADM_USER_MANAGER)
+    }
+}
@@ -3,27 +3,34 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use serde_json::Value;
 
-use crate::db::ArangoDb;
+use crate::db::DatabaseInterface;
 use crate::error::AppError;
 use crate::middleware::auth::Auth;
 use crate::validation::naming::validate_username;
+use crate::watch::ChangeType;
 use crit_shared::data_models::User;
-use crit_shared::util_models::super_permissions;
+use crit_shared::util_models::{PrincipalId, super_permissions};
 
-use super::gitops_controller::{KindController, filter_to_brief, standard_to_external, rename_id_to_key};
+use super::gitops_controller::{
+    CascadeChange, CascadePolicy, KindController, filter_to_brief, rename_id_to_key, standard_to_external,
+};
+use super::project_controller::ProjectController;
 
 pub struct UserController {
-    pub db: Arc<ArangoDb>,
+    pub db: Arc<dyn DatabaseInterface>,
 }
 
 impl UserController {
-    pub fn new(db: Arc<ArangoDb>) -> Self {
+    pub fn new(db: Arc<dyn DatabaseInterface>) -> Self {
         Self { db }
     }
 
+    /// Whether `username` is an existing, not soft-deleted user.
     pub async fn validate_user(&self, username: &str) -> bool {
-        let user_res = self.db.get_user_by_id(username).await;
-        user_res.is_ok()
+        matches!(
+            self.db.get_user_by_id(&PrincipalId::from(username)).await,
+            Ok(Some(user)) if user.deletion.is_none()
+        )
     }
 }
 
@@ -36,7 +43,7 @@ impl KindController for UserController {
     async fn can_write(&self, user_id: &str, _doc: Option<&Value>) -> Result<bool, AppError> {
         let has_perm = self
             .db
-            .has_permission(user_id, super_permissions::ADM_USER_MANAGER)
+            .has_permission(&PrincipalId::from(user_id), super_permissions::ADM_USER_MANAGER)
             .await?;
         log::debug!(
             "[ACL] UserController::can_write: has_permission(ADM_USER_MANAGER)={}",
@@ -54,11 +61,11 @@ impl KindController for UserController {
 
                 // Validate username
                 let validated_username = validate_username(id_without_prefix)
-                    .map_err(AppError::Validation)?;
+                    .map_err(|e| AppError::invalid_field("id", e))?;
 
                 // Add u_ prefix
-                let prefixed_id = format!("u_{}", validated_username);
-                obj.insert("id".to_string(), Value::String(prefixed_id));
+                let prefixed_id = PrincipalId::user(&validated_username);
+                obj.insert("id".to_string(), Value::String(prefixed_id.into_string()));
             }
 
             // Hash password if provided
@@ -86,6 +93,7 @@ impl KindController for UserController {
     fn to_external(&self, mut doc: Value) -> Value {
         if let Some(obj) = doc.as_object_mut() {
             obj.remove("password_hash");
+            obj.remove("totp");
         }
         standard_to_external(doc)
     }
@@ -97,27 +105,74 @@ impl KindController for UserController {
 
     fn list_projection_fields(&self) -> Option<&'static [&'static str]> {
         // _key maps to "id" after to_external
-        Some(&["_key", "personal", "labels"])
+        Some(&["_key", "personal", "labels", "state"])
     }
 
-    async fn after_delete(&self, key: &str, db: &ArangoDb) -> Result<(), AppError> {
-        log::debug!(
-            "[LIFECYCLE] UserController::after_delete: user={}",
-            key
-        );
+    fn search_fields(&self) -> &'static [&'static str] {
+        &["_key", "personal.name", "personal.job_title"]
+    }
 
-        // Remove user from all groups, get list of now-empty groups
-        let empty_groups = db.remove_principal_from_all_groups(key).await?;
+    fn id_prefix(&self) -> &'static str {
+        User::id_prefix()
+    }
 
-        // Cascade: delete any groups that became empty
-        for group_id in empty_groups {
-            log::debug!(
-                "[LIFECYCLE] UserController::after_delete: group {} is now empty, cascading",
-                group_id
-            );
-            super::group_controller::GroupController::cascade_delete_group(db, &group_id).await?;
-        }
+    fn hash_excluded_fields(&self) -> &'static [&'static str] {
+        User::hash_excluded_fields()
+    }
+
+    fn slug_source(&self) -> Option<&'static str> {
+        Some("/personal/name")
+    }
 
-        Ok(())
+    /// Projects the user solely owns block the delete unless the caller picks
+    /// a cascade: delete those projects, or hand them to another user.
+    async fn before_delete(
+        &self,
+        key: &str,
+        _actor: &str,
+        policy: &CascadePolicy,
+        db: &dyn DatabaseInterface,
+    ) -> Result<Vec<CascadeChange>, AppError> {
+        let owned = ProjectController::sole_owned_by(db, key).await?;
+        if owned.is_empty() {
+            return Ok(Vec::new());
+        }
+        let project_key = |doc: &Value| doc.get("_key").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+
+        match policy {
+            CascadePolicy::Refuse => {
+                let names: Vec<String> = owned.iter().map(project_key).collect();
+                Err(AppError::conflict(format!(
+                    "{} is the only owner of projects [{}]; pass ?cascade=delete or ?cascade=reassign&reassign_to=<user>",
+                    key,
+                    names.join(", ")
+                )))
+            }
+            CascadePolicy::Delete => {
+                let mut changes = Vec::with_capacity(owned.len());
+                for doc in owned {
+                    let project = project_key(&doc);
+                    log::info!("[CASCADE] deleting project {} owned by {}", project, key);
+                    changes.push(CascadeChange { change: ChangeType::Deleted, kind: "projects", id: project, doc });
+                }
+                Ok(changes)
+            }
+            CascadePolicy::Reassign(to) => {
+                if to == key {
+                    return Err(AppError::bad_request("cannot reassign projects to the user being deleted"));
+                }
+                if !self.validate_user(to).await {
+                    return Err(AppError::not_found(format!("users/{}", to)));
+                }
+                let mut changes = Vec::with_capacity(owned.len());
+                for doc in owned {
+                    let project = project_key(&doc);
+                    log::info!("[CASCADE] reassigning project {} from {} to {}", project, key, to);
+                    let doc = ProjectController::reassign_owner(doc, key, to)?;
+                    changes.push(CascadeChange { change: ChangeType::Updated, kind: "projects", id: project, doc });
+                }
+                Ok(changes)
+            }
+        }
     }
 }
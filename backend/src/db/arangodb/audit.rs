@@ -4,7 +4,7 @@ use serde_json::{Value, json};
 
 use crit_shared::util_models::*;
 
-use super::ArangoDb;
+use super::{ArangoDb, ArangoTx, CascadeWrite};
 
 impl ArangoDb {
     /// Patch a user document to update a single image ULID field (`avatar_ulid` or
@@ -42,6 +42,108 @@ impl ArangoDb {
         collection: &str,
         key: &str,
         deleted_by: &str,
+    ) -> Result<()> {
+        self.soft_delete_maybe_tx(None, collection, key, deleted_by).await
+    }
+
+    /// Delete `collection/key` together with the writes of its cascade, all or
+    /// none, in one stream transaction: apply `writes`, soft-delete the
+    /// document, then remove the membership edges it is the member side of.
+    /// Returns the groups those edges left without members.
+    pub async fn soft_delete_cascading(
+        &self,
+        collection: &str,
+        key: &str,
+        deleted_by: &str,
+        writes: &[CascadeWrite],
+    ) -> Result<Vec<String>> {
+        let mut tx = self.begin_transaction().await?;
+        match self.soft_delete_cascading_in(&mut tx, collection, key, deleted_by, writes).await {
+            Ok(emptied) => {
+                tx.commit().await?;
+                Ok(emptied)
+            }
+            Err(e) => {
+                if let Err(abort_err) = tx.abort().await {
+                    log::warn!("[DB] soft_delete_cascading: abort failed: {}", abort_err);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    async fn soft_delete_cascading_in(
+        &self,
+        tx: &mut ArangoTx,
+        collection: &str,
+        key: &str,
+        deleted_by: &str,
+        writes: &[CascadeWrite],
+    ) -> Result<Vec<String>> {
+        for write in writes {
+            match write {
+                CascadeWrite::Replace { collection, key, doc } => {
+                    let query = r#"
+                        LET existing = DOCUMENT(@@col, @key)
+                        FILTER existing != null
+                        REPLACE existing WITH @doc IN @@col
+                        RETURN NEW._key
+                    "#;
+                    let vars = std::collections::HashMap::from([
+                        ("@col", Value::String(collection.clone())),
+                        ("key", Value::String(key.clone())),
+                        ("doc", doc.clone()),
+                    ]);
+                    let replaced: Vec<Value> = self.aql_maybe_tx(Some(&mut *tx), query, vars).await?;
+                    if replaced.is_empty() {
+                        return Err(anyhow!("document not found: {}/{}", collection, key));
+                    }
+                }
+                CascadeWrite::SoftDelete { collection, key } => {
+                    self.soft_delete_maybe_tx(Some(&mut *tx), collection, key, deleted_by).await?;
+                }
+            }
+        }
+
+        self.soft_delete_maybe_tx(Some(&mut *tx), collection, key, deleted_by).await?;
+
+        // A query cannot read a collection after writing it, so finding the
+        // groups left empty takes a second query.
+        let remove_query = r#"
+            FOR m IN memberships
+                FILTER m._from == @from_path
+                REMOVE m IN memberships
+                RETURN OLD.deletion == null ? OLD.group : null
+        "#;
+        let vars = std::collections::HashMap::from([(
+            "from_path",
+            Value::String(format!("{}/{}", collection, key)),
+        )]);
+        let left: Vec<Option<String>> = self.aql_maybe_tx(Some(&mut *tx), remove_query, vars).await?;
+        let mut left: Vec<String> = left.into_iter().flatten().collect();
+        left.sort();
+        left.dedup();
+
+        let empty_query = r#"
+            FOR g IN @groups
+                FILTER LENGTH(
+                    FOR m IN memberships
+                        FILTER m.group == g AND m.deletion == null
+                        LIMIT 1
+                        RETURN 1
+                ) == 0
+                RETURN g
+        "#;
+        let vars = std::collections::HashMap::from([("groups", json!(left))]);
+        self.aql_maybe_tx(Some(tx), empty_query, vars).await
+    }
+
+    async fn soft_delete_maybe_tx(
+        &self,
+        mut tx: Option<&mut ArangoTx>,
+        collection: &str,
+        key: &str,
+        deleted_by: &str,
     ) -> Result<()> {
         let from_path = format!("{}/{}", collection, key);
         // When deleting a group, also capture edges of members pointing TO this group
@@ -58,7 +160,7 @@ impl ArangoDb {
             ("to_path", Value::String(to_path)),
         ]);
 
-        let edges: Vec<Value> = self.aql(edge_query, vars).await?;
+        let edges: Vec<Value> = self.aql_maybe_tx(tx.as_deref_mut(), edge_query, vars).await?;
 
         let disconnected_edges: Vec<DisconnectedEdge> = edges
             .into_iter()
@@ -74,7 +176,7 @@ impl ArangoDb {
 
         let deletion = DeletionInfo {
             deleted_at: chrono::Utc::now(),
-            deleted_by: deleted_by.to_string(),
+            deleted_by: deleted_by.into(),
             disconnected_edges,
         };
         let deletion_val = serde_json::to_value(&deletion).map_err(|e| anyhow!(e))?;
@@ -90,7 +192,7 @@ impl ArangoDb {
             ("key", Value::String(key.to_string())),
             ("deletion", deletion_val),
         ]);
-        let result: Vec<Value> = self.aql(update_query, vars).await?;
+        let result: Vec<Value> = self.aql_maybe_tx(tx, update_query, vars).await?;
 
         if result.is_empty() {
             return Err(anyhow!("document not found or already deleted: {}/{}", collection, key));
@@ -130,7 +232,7 @@ impl ArangoDb {
             resource_key: key.to_string(),
             revision,
             snapshot,
-            changed_by: changed_by.to_string(),
+            changed_by: changed_by.into(),
             changed_at: chrono::Utc::now(),
         };
 
@@ -180,7 +282,7 @@ impl ArangoDb {
             resource_key: key.to_string(),
             event_type: event_type.to_string(),
             timestamp: chrono::Utc::now(),
-            actor: actor.map(String::from),
+            actor: actor.map(PrincipalId::from),
             details,
         };
 
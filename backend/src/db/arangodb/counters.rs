@@ -0,0 +1,38 @@
+use anyhow::{Result, anyhow};
+use serde_json::Value;
+
+use super::ArangoDb;
+
+impl ArangoDb {
+    /// Atomically add `by` to the counter `key` (created at 0 on first use) and
+    /// return the new value. `by = 0` reads the current value.
+    ///
+    /// The UPSERT runs with an exclusive lock on `counters`, so concurrent
+    /// increments are serialized by ArangoDB instead of racing a read and a write.
+    /// Use it to allocate monotonically increasing numbers, e.g. per-project ticket ids
+    /// under `"{project}/tickets"`.
+    pub async fn increment_counter(&self, key: &str, by: i64) -> Result<i64> {
+        let query = r#"
+            UPSERT { _key: @key }
+            INSERT { _key: @key, value: @by }
+            UPDATE { value: OLD.value + @by }
+            IN counters
+            OPTIONS { exclusive: true }
+            RETURN NEW.value
+        "#;
+        let vars = std::collections::HashMap::from([
+            ("key", Value::String(counter_key(key))),
+            ("by", Value::from(by)),
+        ]);
+        let result: Vec<i64> = self.aql(query, vars).await?;
+        result
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("counter {} was not written", key))
+    }
+}
+
+/// ArangoDB keys cannot contain `/`; map it to `:` so callers can use path-like names.
+fn counter_key(key: &str) -> String {
+    key.replace('/', ":")
+}
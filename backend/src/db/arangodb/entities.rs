@@ -3,8 +3,11 @@ use arangors::document::Document;
 use serde_json::json;
 
 use crit_shared::data_models::*;
+use crit_shared::util_models::{PrincipalId, PrincipalKind};
 
-use super::{ArangoDb, ArangoTx, collection_for_principal};
+use super::{
+    ArangoDb, ArangoTx, EffectiveMember, EffectiveMembership, MemberFilter, MemberPage, collection_for_principal,
+};
 
 impl ArangoDb {
     pub async fn create_user(&self, user: User, tx: Option<&mut ArangoTx>) -> Result<()> {
@@ -88,6 +91,31 @@ impl ArangoDb {
         Ok(())
     }
 
+    /// Insert one membership edge per principal in a single transaction,
+    /// replacing any edge (e.g. a soft-deleted one) already holding the key.
+    pub async fn add_principals_to_group(&self, principal_ids: &[PrincipalId], group_id: &str) -> Result<()> {
+        let mut tx = self.begin_transaction().await?;
+        let mut result = Ok(());
+        for principal_id in principal_ids {
+            result = match self.remove_principal_from_group(principal_id, group_id, Some(&mut tx)).await {
+                Ok(_) => self.add_principal_to_group(principal_id, group_id, Some(&mut tx)).await,
+                Err(e) => Err(e),
+            };
+            if result.is_err() {
+                break;
+            }
+        }
+        match result {
+            Ok(()) => tx.commit().await,
+            Err(e) => {
+                if let Err(abort_err) = tx.abort().await {
+                    log::warn!("[DB] add_principals_to_group: abort failed: {}", abort_err);
+                }
+                Err(e)
+            }
+        }
+    }
+
     pub async fn get_users_list(&self) -> Result<Vec<User>> {
         let query = "FOR u IN users RETURN u";
         let users: Vec<User> = self.aql_str_query(query).await?;
@@ -100,38 +128,68 @@ impl ArangoDb {
         Ok(groups)
     }
 
-    pub async fn get_users_in_group(&self, group_id: &str) -> Result<Vec<String>> {
+    /// A page of a group's direct members of the kinds `filter` keeps, sorted
+    /// by id, with the total count. `limit: None` returns the rest from `offset`.
+    pub async fn list_members(
+        &self,
+        group_id: &str,
+        filter: MemberFilter,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> Result<MemberPage> {
         let query = r#"
-            FOR m IN memberships
-                FILTER m.group == @group
-                FILTER LIKE(m.principal, "u_%")
-                RETURN m.principal
+            LET matching = (
+                FOR m IN memberships
+                    FILTER m._to == @to
+                    FILTER m.deletion == null
+                    FILTER LIKE(m.principal, @pattern)
+                    SORT m.principal
+                    RETURN m.principal
+            )
+            RETURN {
+                total: LENGTH(matching),
+                members: SLICE(matching, @offset, @limit == null ? LENGTH(matching) : @limit)
+            }
         "#;
-
-        let vars = std::collections::HashMap::from([(
-            "group",
-            serde_json::Value::String(group_id.to_string()),
-        )]);
-
-        let res: Vec<String> = self.aql(query, vars).await?;
-        Ok(res)
+        let vars = std::collections::HashMap::from([
+            ("to", serde_json::Value::String(format!("groups/{}", group_id))),
+            ("pattern", json!(filter.like_pattern())),
+            ("offset", json!(offset)),
+            ("limit", json!(limit)),
+        ]);
+        let mut pages: Vec<MemberPage> = self.aql(query, vars).await?;
+        pages.pop().ok_or_else(|| anyhow!("list_members: empty AQL result"))
     }
 
-    pub async fn get_groups_in_group(&self, group_id: &str) -> Result<Vec<String>> {
+    /// Direct members of a group, sorted. Served by the edge index on `_to`.
+    pub async fn list_group_members(&self, group_id: &str) -> Result<Vec<String>> {
         let query = r#"
             FOR m IN memberships
-                FILTER m.group == @group
-                FILTER LIKE(m.principal, "g:%")
+                FILTER m._to == @to
+                FILTER m.deletion == null
+                SORT m.principal
                 RETURN m.principal
         "#;
-
         let vars = std::collections::HashMap::from([(
-            "group",
-            serde_json::Value::String(group_id.to_string()),
+            "to",
+            serde_json::Value::String(format!("groups/{}", group_id)),
         )]);
+        self.aql(query, vars).await
+    }
 
-        let res: Vec<String> = self.aql(query, vars).await?;
-        Ok(res)
+    /// Groups a principal is a direct member of, sorted. Served by the edge
+    /// index on `_from`; `get_user_principals` adds the transitive ones.
+    pub async fn list_groups_of(&self, principal_id: &str) -> Result<Vec<String>> {
+        let query = r#"
+            FOR m IN memberships
+                FILTER m._from == @from
+                FILTER m.deletion == null
+                SORT m.group
+                RETURN m.group
+        "#;
+        let from = format!("{}/{}", collection_for_principal(principal_id), principal_id);
+        let vars = std::collections::HashMap::from([("from", serde_json::Value::String(from))]);
+        self.aql(query, vars).await
     }
 
     /// Remove a principal from all groups it belongs to.
@@ -268,6 +326,67 @@ impl ArangoDb {
         Ok(result.into_iter().next().unwrap_or_default())
     }
 
+    /// Resolve every user reachable from `group_id` through nested groups, with
+    /// the shortest membership path for each. BFS with global vertex uniqueness
+    /// makes cycles (a group containing itself, directly or indirectly) and
+    /// diamonds (a user reachable via two groups) terminate and count once.
+    /// Errors if the nesting goes deeper than `membership_max_depth`.
+    pub async fn resolve_effective_members(&self, group_id: &str) -> Result<EffectiveMembership> {
+        let query = r#"
+            FOR v, e, p IN 1..@depth INBOUND CONCAT(@col, "/", @start) memberships
+                OPTIONS { uniqueVertices: "global", order: "bfs" }
+                FILTER v.deletion == null
+                FILTER v._key != @start
+                RETURN { principal: v._key, path: p.vertices[*]._key }
+        "#;
+        let reached = self.membership_traversal(query, group_id).await?;
+
+        let users = reached
+            .into_iter()
+            .filter(|m| PrincipalId::from(m.principal.as_str()).is_user())
+            .collect();
+        Ok(EffectiveMembership {
+            group: group_id.to_string(),
+            users,
+        })
+    }
+
+    /// All groups `principal_id` belongs to, directly or through nested groups.
+    /// Cycle-safe; errors if the nesting goes deeper than `membership_max_depth`.
+    pub async fn get_groups_of_principal(&self, principal_id: &str) -> Result<Vec<String>> {
+        let query = r#"
+            FOR v, e, p IN 1..@depth OUTBOUND CONCAT(@col, "/", @start) memberships
+                OPTIONS { uniqueVertices: "global", order: "bfs" }
+                FILTER v.deletion == null
+                FILTER v._key != @start
+                RETURN { principal: v._key, path: p.vertices[*]._key }
+        "#;
+        let reached = self.membership_traversal(query, principal_id).await?;
+        Ok(reached.into_iter().map(|m| m.principal).collect())
+    }
+
+    /// Run a membership traversal one level past `membership_max_depth` so that
+    /// exceeding the cap is detected instead of silently truncated.
+    async fn membership_traversal(&self, query: &str, start: &str) -> Result<Vec<EffectiveMember>> {
+        let max_depth = self.membership_max_depth;
+        let vars = std::collections::HashMap::from([
+            ("start", json!(start)),
+            ("col", json!(collection_for_principal(start))),
+            ("depth", json!(max_depth + 1)),
+        ]);
+        let reached: Vec<EffectiveMember> = self.aql(query, vars).await?;
+
+        if let Some(too_deep) = reached.iter().find(|m| m.path.len() > max_depth as usize + 1) {
+            return Err(anyhow!(
+                "membership nesting from {} exceeds max depth {} (reached {})",
+                start,
+                max_depth,
+                too_deep.principal
+            ));
+        }
+        Ok(reached)
+    }
+
     /// Remove all membership edges where this group is the target (members OF this group).
     pub async fn remove_all_members_of_group(&self, group_id: &str) -> Result<()> {
         let query = r#"
@@ -298,12 +417,133 @@ impl ArangoDb {
         Ok(())
     }
 
-    pub async fn get_user_by_id(&self, user_id: &str) -> Result<Option<User>> {
-        let id = if user_id.starts_with("u_") {
-            user_id.to_string()
+    /// Replace only `password_hash` on a user (AQL UPDATE), e.g. after a
+    /// transparent re-hash on login. Other fields are left as they are.
+    pub async fn set_user_password_hash(&self, user_id: &str, password_hash: &str) -> Result<()> {
+        let query = r#"
+            FOR doc IN users
+              FILTER doc._key == @id
+              UPDATE doc WITH { password_hash: @hash } IN users
+        "#;
+        let vars = std::collections::HashMap::from([
+            ("id", serde_json::Value::String(PrincipalId::parse(PrincipalKind::User, user_id).to_string())),
+            ("hash", serde_json::Value::String(password_hash.to_string())),
+        ]);
+        self.aql::<serde_json::Value>(query, vars).await?;
+        Ok(())
+    }
+
+    /// Replace only `totp` on a user (AQL UPDATE); `None` removes the factor.
+    pub async fn set_user_totp(&self, user_id: &str, totp: Option<&TotpFactor>) -> Result<()> {
+        let query = r#"
+            FOR doc IN users
+              FILTER doc._key == @id
+              UPDATE doc WITH { totp: @totp } IN users OPTIONS { keepNull: false }
+        "#;
+        let vars = std::collections::HashMap::from([
+            ("id", serde_json::Value::String(PrincipalId::parse(PrincipalKind::User, user_id).to_string())),
+            ("totp", serde_json::to_value(totp)?),
+        ]);
+        self.aql::<serde_json::Value>(query, vars).await?;
+        Ok(())
+    }
+
+    /// The user an OIDC identity was provisioned as. Soft-deleted users are
+    /// returned too, so a deleted account is refused rather than recreated.
+    pub async fn find_user_by_oauth(&self, issuer: &str, subject: &str) -> Result<Option<User>> {
+        let query = r#"
+            FOR u IN users
+              FILTER u.oauth.issuer == @issuer AND u.oauth.subject == @subject
+              LIMIT 1
+              RETURN u
+        "#;
+        let vars = std::collections::HashMap::from([
+            ("issuer", serde_json::Value::String(issuer.to_string())),
+            ("subject", serde_json::Value::String(subject.to_string())),
+        ]);
+        let users: Vec<User> = self.aql(query, vars).await?;
+        Ok(users.into_iter().next())
+    }
+
+    pub async fn modify_group(&self, group: Group, tx: Option<&mut ArangoTx>) -> Result<()> {
+        let key = group.id.clone();
+        let doc = Document::new(group);
+        if let Some(tr) = tx {
+            let col = tr.inner.collection("groups").await?;
+            col.replace_document(&key, doc, Default::default(), None)
+                .await?;
         } else {
-            format!("u_{}", user_id)
-        };
+            self.groups
+                .replace_document(&key, doc, Default::default(), None)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Hard-delete a user together with every membership edge it is part of.
+    /// Runs as a single AQL query (inside `tx` when given).
+    /// Returns `Ok(false)` if the user did not exist — deleting is idempotent.
+    pub async fn delete_user(&self, user_id: &str, tx: Option<&mut ArangoTx>) -> Result<bool> {
+        self.delete_principal_with_edges("users", user_id, tx).await
+    }
+
+    /// Hard-delete a group together with its membership edges in both directions
+    /// (its members, and its own membership in parent groups).
+    /// Returns `Ok(false)` if the group did not exist — deleting is idempotent.
+    pub async fn delete_group(&self, group_id: &str, tx: Option<&mut ArangoTx>) -> Result<bool> {
+        self.delete_principal_with_edges("groups", group_id, tx).await
+    }
+
+    async fn delete_principal_with_edges(
+        &self,
+        collection: &str,
+        key: &str,
+        tx: Option<&mut ArangoTx>,
+    ) -> Result<bool> {
+        let query = r#"
+            LET edges = (
+                FOR m IN memberships
+                    FILTER m.principal == @key OR m.group == @key
+                    REMOVE m IN memberships
+                    RETURN 1
+            )
+            LET doc = DOCUMENT(@@col, @key)
+            FILTER doc != null
+            REMOVE doc IN @@col
+            RETURN 1
+        "#;
+        let vars = std::collections::HashMap::from([
+            ("@col", serde_json::Value::String(collection.to_string())),
+            ("key", serde_json::Value::String(key.to_string())),
+        ]);
+        let removed: Vec<serde_json::Value> = self.aql_maybe_tx(tx, query, vars).await?;
+        Ok(!removed.is_empty())
+    }
+
+    /// Remove a single membership edge. Returns `Ok(false)` if the principal
+    /// was not a member of the group.
+    pub async fn remove_principal_from_group(
+        &self,
+        principal_id: &str,
+        group_id: &str,
+        tx: Option<&mut ArangoTx>,
+    ) -> Result<bool> {
+        let query = r#"
+            FOR m IN memberships
+                FILTER m.principal == @principal AND m.group == @group
+                REMOVE m IN memberships
+                RETURN 1
+        "#;
+        let vars = std::collections::HashMap::from([
+            ("principal", serde_json::Value::String(principal_id.to_string())),
+            ("group", serde_json::Value::String(group_id.to_string())),
+        ]);
+        let removed: Vec<serde_json::Value> = self.aql_maybe_tx(tx, query, vars).await?;
+        Ok(!removed.is_empty())
+    }
+
+    pub async fn get_user_by_id(&self, user_id: &str) -> Result<Option<User>> {
+        let id = PrincipalId::parse(PrincipalKind::User, user_id);
         match self.users.document::<User>(&id).await {
             Ok(doc) => Ok(Some(doc.document)),
             Err(arangors::ClientError::Arango(ref e)) if e.code() == 404 => Ok(None),
@@ -1,7 +1,7 @@
 use anyhow::{Result, anyhow};
 use serde_json::{Value, json};
 
-use super::{ArangoDb, PaginatedResult};
+use super::{AclFilter, ArangoDb, PaginatedResult};
 
 impl ArangoDb {
     //
@@ -225,6 +225,83 @@ impl ArangoDb {
         self.aql(&query, vars).await
     }
 
+    /// Candidates for a text search: documents `acl` may read where every token is a
+    /// substring of at least one of `search_fields` (dot paths, compared
+    /// lowercased). Each comes back as `{ doc, texts }`, `doc` projected to
+    /// `fields` and `texts` the lowercased field values for ranking. Returns at
+    /// most 200 candidates, the first in key order.
+    ///
+    /// This is a collection scan. An index-backed lookup (an ArangoSearch view)
+    /// can replace the query as long as it returns the same shape.
+    pub async fn generic_text_search_acl(
+        &self,
+        collection: &str,
+        acl: AclFilter<'_>,
+        fields: Option<&[&str]>,
+        search_fields: &[&str],
+        tokens: &[String],
+    ) -> Result<Vec<Value>> {
+        let projected = match fields {
+            Some(f) => {
+                let quoted: Vec<String> = f.iter().map(|s| format!("\"{}\"", s)).collect();
+                format!("KEEP(doc, {})", quoted.join(", "))
+            }
+            None => "doc".to_string(),
+        };
+        // Field paths come from controllers, not from the request; backticks
+        // keep names like `_key` valid attribute accesses.
+        let texts: Vec<String> = search_fields
+            .iter()
+            .map(|path| {
+                let access: String = path.split('.').map(|part| format!(".`{}`", part)).collect();
+                format!("LOWER(TO_STRING(doc{}))", access)
+            })
+            .collect();
+        let texts = texts.join(", ");
+
+        let vars = std::collections::HashMap::from([
+            ("@col", Value::String(collection.to_string())),
+            ("principals", serde_json::to_value(acl.principals)?),
+            ("required_perm", json!(acl.required_perm)),
+            ("super_bypass", Value::Bool(acl.super_bypass)),
+            ("tokens", serde_json::to_value(tokens)?),
+        ]);
+
+        let query = format!(
+            r#"
+            FOR doc IN @@col
+                FILTER doc.deletion == null
+
+                LET texts = [{texts}]
+                LET missing = (
+                    FOR token IN @tokens
+                        FILTER LENGTH(FOR text IN texts FILTER CONTAINS(text, token) LIMIT 1 RETURN 1) == 0
+                        LIMIT 1
+                        RETURN 1
+                )
+                FILTER LENGTH(missing) == 0
+
+                LET acl_pass = @super_bypass OR (
+                    LENGTH(doc.acl.list || []) == 0 OR
+                    LENGTH(
+                        FOR entry IN (doc.acl.list || [])
+                            FILTER BIT_AND(entry.permissions, @required_perm) == @required_perm
+                            FILTER LENGTH(INTERSECTION(entry.principals, @principals)) > 0
+                            LIMIT 1
+                            RETURN 1
+                    ) > 0
+                )
+                FILTER acl_pass
+
+                SORT doc._key ASC
+                LIMIT 200
+                RETURN {{ doc: {projected}, texts }}
+            "#
+        );
+
+        self.aql(&query, vars).await
+    }
+
     /// List project-scoped documents with hybrid ACL resolution in a single AQL query.
     /// If a document has its own ACL entries, they are used.
     /// Otherwise, falls back to the project's full ACL (all entries, no scope filtering).
@@ -323,6 +400,97 @@ impl ArangoDb {
         })
     }
 
+    /// Count live documents visible under the same ACL rules as `generic_list_acl`,
+    /// without fetching them.
+    pub async fn generic_count_acl(
+        &self,
+        collection: &str,
+        principals: &[String],
+        required_perm: u8,
+        super_bypass: bool,
+    ) -> Result<u64> {
+        let vars = std::collections::HashMap::from([
+            ("@col", Value::String(collection.to_string())),
+            ("principals", serde_json::to_value(principals)?),
+            ("required_perm", json!(required_perm)),
+            ("super_bypass", Value::Bool(super_bypass)),
+        ]);
+
+        let query = r#"
+            FOR doc IN @@col
+                FILTER doc.deletion == null
+
+                LET acl_pass = @super_bypass OR (
+                    LENGTH(doc.acl.list || []) == 0 OR
+                    LENGTH(
+                        FOR entry IN (doc.acl.list || [])
+                            FILTER BIT_AND(entry.permissions, @required_perm) == @required_perm
+                            FILTER LENGTH(INTERSECTION(entry.principals, @principals)) > 0
+                            LIMIT 1
+                            RETURN 1
+                    ) > 0
+                )
+                FILTER acl_pass
+
+                COLLECT WITH COUNT INTO n
+                RETURN n
+        "#;
+
+        let counts: Vec<u64> = self.aql(query, vars).await?;
+        Ok(counts.first().copied().unwrap_or(0))
+    }
+
+    /// Count project-scoped documents visible under the same hybrid ACL rules
+    /// as `generic_list_scoped`, without fetching them.
+    pub async fn generic_count_scoped(
+        &self,
+        collection: &str,
+        project_id: &str,
+        principals: &[String],
+        required_perm: u8,
+        super_bypass: bool,
+    ) -> Result<u64> {
+        let vars = std::collections::HashMap::from([
+            ("@col", Value::String(collection.to_string())),
+            ("project_id", Value::String(project_id.to_string())),
+            ("principals", serde_json::to_value(principals)?),
+            ("required_perm", json!(required_perm)),
+            ("super_bypass", Value::Bool(super_bypass)),
+        ]);
+
+        let query = r#"
+            LET project_doc = DOCUMENT("projects", @project_id)
+            LET project_acl = (project_doc != null AND project_doc.deletion == null)
+                ? (project_doc.acl.list || [])
+                : []
+
+            FOR doc IN @@col
+                FILTER doc.project == @project_id
+                FILTER doc.deletion == null
+
+                LET effective_acl = LENGTH(doc.acl.list || []) > 0
+                    ? (doc.acl.list || [])
+                    : project_acl
+
+                LET acl_pass = @super_bypass OR (
+                    LENGTH(
+                        FOR entry IN effective_acl
+                            FILTER BIT_AND(entry.permissions, @required_perm) == @required_perm
+                            FILTER LENGTH(INTERSECTION(entry.principals, @principals)) > 0
+                            LIMIT 1
+                            RETURN 1
+                    ) > 0
+                )
+                FILTER acl_pass
+
+                COLLECT WITH COUNT INTO n
+                RETURN n
+        "#;
+
+        let counts: Vec<u64> = self.aql(query, vars).await?;
+        Ok(counts.first().copied().unwrap_or(0))
+    }
+
     /// Fetch a single project-scoped document, validating project membership.
     pub async fn generic_get_scoped(
         &self,
@@ -25,6 +25,8 @@ const VERTEX_COLLECTIONS: &[&str] = &[
     "resource_events",
     "unprocessed_images",
     "persistent_files",
+    "counters",
+    "refresh_tokens",
 ];
 
 /// Edge collections created at startup.
@@ -41,6 +43,7 @@ pub const WRITE_COLLECTIONS: &[&str] = &[
     "permissions",
     "resource_history",
     "resource_events",
+    "counters",
 ];
 
 /// Cached collection handles opened from a database.
@@ -138,6 +141,9 @@ pub async fn open_collections(db: &Database<ReqwestClient>) -> Result<Collection
 /// implemented" in the crate), so we call `POST /_db/{db}/_api/index` with
 /// `reqwest`.  Both HTTP 200 (index already exists) and 201 (just created)
 /// are treated as success; any other status is an error.
+///
+/// A `unique` index is also sparse, so documents without the fields are
+/// not constrained.
 async fn create_persistent_index(
     base_url: &str,
     db_name: &str,
@@ -145,6 +151,7 @@ async fn create_persistent_index(
     password: &str,
     collection: &str,
     fields: &[&str],
+    unique: bool,
 ) -> Result<()> {
     let url = format!(
         "{}/_db/{}/_api/index?collection={}",
@@ -155,6 +162,8 @@ async fn create_persistent_index(
     let body = serde_json::json!({
         "type": "persistent",
         "fields": fields,
+        "unique": unique,
+        "sparse": unique,
     });
     let client = reqwest::Client::new();
     let resp = client
@@ -198,13 +207,16 @@ pub async fn ensure_indexes(
 ) -> Result<()> {
     // Indexes for global (non-scoped) collections: filter on deletion only.
     for col in &["users", "groups", "projects", "service_accounts", "pipeline_accounts"] {
-        create_persistent_index(base_url, db_name, user, password, col, &["deletion"]).await?;
+        create_persistent_index(base_url, db_name, user, password, col, &["deletion"], false).await?;
     }
 
+    // One user per OIDC identity, even when two first logins race.
+    create_persistent_index(base_url, db_name, user, password, "users", &["oauth.issuer", "oauth.subject"], true).await?;
+
     // Indexes for project-scoped collections: composite filter on project + deletion.
     // Add new scoped collections here as they are introduced.
     // Example (uncomment when tasks collection is added):
-    // create_persistent_index(base_url, db_name, user, password, "tasks", &["project", "deletion"]).await?;
+    // create_persistent_index(base_url, db_name, user, password, "tasks", &["project", "deletion"], false).await?;
 
     Ok(())
 }
@@ -10,11 +10,17 @@ use arangors::transaction::{
 };
 use serde_json::Value;
 
+use crit_shared::util_models::{PrincipalId, PrincipalKind};
+
 mod init;
 mod entities;
 mod permissions;
 mod gitops;
 mod audit;
+mod counters;
+mod refresh_tokens;
+
+pub use refresh_tokens::RefreshTokenRecord;
 
 //
 // ------------------- PAGINATION --------------------
@@ -26,6 +32,85 @@ pub struct PaginatedResult {
     pub has_more: bool,
 }
 
+/// Who is reading, for ACL filters pushed into AQL: the caller's pre-resolved
+/// principals (user id plus transitive groups), the permission bits an entry
+/// must grant, and whether the caller skips the ACL check altogether.
+#[derive(Debug, Clone, Copy)]
+pub struct AclFilter<'a> {
+    pub principals: &'a [String],
+    pub required_perm: u8,
+    pub super_bypass: bool,
+}
+
+/// One write of a delete cascade, applied by [`ArangoDb::soft_delete_cascading`]
+/// in the same transaction as the delete itself.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CascadeWrite {
+    /// Replace `collection/key` with `doc`; fails if the document is missing.
+    Replace { collection: String, key: String, doc: Value },
+    /// Soft-delete `collection/key`; fails if it is missing or already deleted.
+    SoftDelete { collection: String, key: String },
+}
+
+//
+// ------------------- MEMBERSHIP RESOLUTION --------------------
+//
+
+/// Which direct members of a group [`ArangoDb::list_members`] returns.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MemberFilter {
+    Users,
+    Groups,
+    #[default]
+    All,
+}
+
+impl MemberFilter {
+    /// AQL `LIKE` pattern for the member ids this filter keeps.
+    pub fn like_pattern(&self) -> String {
+        match self {
+            MemberFilter::Users => PrincipalKind::User.like_pattern(),
+            MemberFilter::Groups => PrincipalKind::Group.like_pattern(),
+            MemberFilter::All => "%".to_string(),
+        }
+    }
+
+    pub fn matches(&self, principal_id: &str) -> bool {
+        match self {
+            MemberFilter::Users => PrincipalId::from(principal_id).kind() == PrincipalKind::User,
+            MemberFilter::Groups => PrincipalId::from(principal_id).kind() == PrincipalKind::Group,
+            MemberFilter::All => true,
+        }
+    }
+}
+
+/// One page of a group's direct members, sorted by id; `total` counts every
+/// member the filter keeps.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+pub struct MemberPage {
+    pub members: Vec<String>,
+    pub total: u64,
+}
+
+/// Default cap on group nesting depth for transitive membership queries.
+pub const DEFAULT_MEMBERSHIP_DEPTH: u32 = 10;
+
+/// A user reachable from a group, with the shortest chain of groups leading to it.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct EffectiveMember {
+    pub principal: String,
+    /// Keys from the queried group down to the user, e.g. `[g_admins, g_core, u_alice]`.
+    pub path: Vec<String>,
+}
+
+/// All users transitively contained in a group.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct EffectiveMembership {
+    pub group: String,
+    pub users: Vec<EffectiveMember>,
+}
+
 //
 // ------------------- TRANSACTION WRAPPER --------------------
 //
@@ -75,6 +160,8 @@ pub struct ArangoDb {
     pub resource_events: Collection<ReqwestClient>,
     pub unprocessed_images: Collection<ReqwestClient>,
     pub persistent_files: Collection<ReqwestClient>,
+    /// Cap for transitive membership traversals; see `with_membership_max_depth`.
+    pub membership_max_depth: u32,
 }
 
 // ---------------------------------------------------------------------------
@@ -115,7 +202,92 @@ where
     unreachable!()
 }
 
+// ---------------------------------------------------------------------------
+// Connection retry
+// ---------------------------------------------------------------------------
+
+/// How many times to try connecting at startup and how long to wait between
+/// attempts. The delay doubles after every failed attempt.
+#[derive(Debug, Clone, Copy)]
+pub struct ConnectRetry {
+    pub attempts: u32,
+    pub initial_delay: Duration,
+}
+
+impl Default for ConnectRetry {
+    fn default() -> Self {
+        Self {
+            attempts: 5,
+            initial_delay: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Run `connect` until it succeeds or `retry.attempts` are exhausted,
+/// sleeping with exponential backoff in between. Returns the last error.
+async fn with_connect_retry<T, F, Fut>(retry: ConnectRetry, mut connect: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T>>,
+{
+    let attempts = retry.attempts.max(1);
+    let mut delay = retry.initial_delay;
+    for attempt in 1..=attempts {
+        match connect().await {
+            Ok(v) => return Ok(v),
+            Err(e) if attempt < attempts => {
+                log::warn!(
+                    "[DB] connection attempt {}/{} failed: {} — retrying in {:?}",
+                    attempt, attempts, e, delay
+                );
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    unreachable!()
+}
+
+/// Result of a successful `ArangoDb::health` probe.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ArangoHealth {
+    pub latency_ms: u64,
+}
+
 impl ArangoDb {
+    /// `connect_basic` with exponential-backoff retry, for startup when the
+    /// database container may still be coming up.
+    ///
+    /// No reconnect wrapper is needed after startup: arangors talks HTTP through
+    /// a pooled reqwest client, so a dropped connection is re-established on the
+    /// next request and only that request fails.
+    pub async fn connect_basic_with_retry(
+        url: &str,
+        user: &str,
+        pass: &str,
+        db_name: &str,
+        retry: ConnectRetry,
+    ) -> Result<Self> {
+        with_connect_retry(retry, || Self::connect_basic(url, user, pass, db_name)).await
+    }
+
+    /// Follow group nesting at most `depth` levels in `resolve_effective_members`
+    /// and `get_groups_of_principal`; deeper chains are an error.
+    pub fn with_membership_max_depth(mut self, depth: u32) -> Self {
+        self.membership_max_depth = depth;
+        self
+    }
+
+    /// Cheap liveness probe (`RETURN 1`) with round-trip latency.
+    pub async fn health(&self) -> Result<ArangoHealth> {
+        let started = std::time::Instant::now();
+        let _: Vec<Value> = self.aql_str_query("RETURN 1").await?;
+        Ok(ArangoHealth {
+            latency_ms: started.elapsed().as_millis() as u64,
+        })
+    }
+
     pub async fn connect_basic(url: &str, user: &str, pass: &str, db_name: &str) -> Result<Self> {
         let conn = Connection::establish_basic_auth(url, user, pass)
             .await
@@ -141,6 +313,7 @@ impl ArangoDb {
             resource_events: handles.resource_events,
             unprocessed_images: handles.unprocessed_images,
             persistent_files: handles.persistent_files,
+            membership_max_depth: DEFAULT_MEMBERSHIP_DEPTH,
         };
 
         instance.seed_permissions().await?;
@@ -176,6 +349,7 @@ impl ArangoDb {
             resource_events: handles.resource_events,
             unprocessed_images: handles.unprocessed_images,
             persistent_files: handles.persistent_files,
+            membership_max_depth: DEFAULT_MEMBERSHIP_DEPTH,
         })
     }
 
@@ -208,6 +382,7 @@ impl ArangoDb {
             resource_events: handles.resource_events,
             unprocessed_images: handles.unprocessed_images,
             persistent_files: handles.persistent_files,
+            membership_max_depth: DEFAULT_MEMBERSHIP_DEPTH,
         })
     }
 
@@ -237,6 +412,26 @@ impl ArangoDb {
             .map_err(|e| anyhow!(e.to_string()))
     }
 
+    /// Execute an AQL query with bind variables, inside `tx` when one is given.
+    /// Used by entity operations that accept an optional transaction.
+    async fn aql_maybe_tx<T: serde::de::DeserializeOwned>(
+        &self,
+        tx: Option<&mut ArangoTx>,
+        query: &str,
+        vars: std::collections::HashMap<&str, Value>,
+    ) -> Result<Vec<T>> {
+        match tx {
+            Some(tr) => {
+                log::debug!("[AQL tx]\n{}", query.trim());
+                tr.inner
+                    .aql_bind_vars(query, vars)
+                    .await
+                    .map_err(|e| anyhow!(e.to_string()))
+            }
+            None => self.aql(query, vars).await,
+        }
+    }
+
     /// Execute a bare AQL string (no bind variables).
     /// Logs the query at DEBUG level.
     async fn aql_str_query<T: serde::de::DeserializeOwned>(&self, query: &str) -> Result<Vec<T>> {
@@ -278,13 +473,47 @@ impl ArangoDb {
 
 /// Resolve a principal ID prefix to its ArangoDB collection name.
 pub fn collection_for_principal(principal_id: &str) -> &'static str {
-    if principal_id.starts_with("g_") {
-        "groups"
-    } else if principal_id.starts_with("sa_") {
-        "service_accounts"
-    } else if principal_id.starts_with("pa_") {
-        "pipeline_accounts"
-    } else {
-        "users" // u_ prefix or fallback
+    match PrincipalId::from(principal_id).kind() {
+        PrincipalKind::Group => "groups",
+        PrincipalKind::ServiceAccount => "service_accounts",
+        PrincipalKind::PipelineAccount => "pipeline_accounts",
+        PrincipalKind::User | PrincipalKind::Other => "users", // u_ prefix or fallback
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn fast_retry(attempts: u32) -> ConnectRetry {
+        ConnectRetry {
+            attempts,
+            initial_delay: Duration::from_millis(1),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connect_retry_succeeds_after_transient_failures() {
+        let calls = AtomicU32::new(0);
+        let result = with_connect_retry(fast_retry(5), || async {
+            let n = calls.fetch_add(1, Ordering::SeqCst) + 1;
+            if n < 3 { Err(anyhow!("connection refused")) } else { Ok(n) }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_connect_retry_gives_up_after_max_attempts() {
+        let calls = AtomicU32::new(0);
+        let result: Result<()> = with_connect_retry(fast_retry(2), || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(anyhow!("connection refused"))
+        })
+        .await;
+        assert!(result.unwrap_err().to_string().contains("connection refused"));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
     }
 }
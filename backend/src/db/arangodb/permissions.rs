@@ -6,24 +6,34 @@ use crit_shared::util_models::*;
 use super::ArangoDb;
 
 impl ArangoDb {
-    /// Ensure all super-permissions exist and that u_root has every one of them.
+    /// Ensure every super-permission document exists. Nobody is granted
+    /// anything here; the default admin, if configured, is seeded at startup.
     pub(super) async fn seed_permissions(&self) -> Result<()> {
-        use super_permissions::*;
-
-        let all = [
-            ADM_USER_MANAGER,
-            ADM_CONFIG_EDITOR,
-            USR_CREATE_GROUPS,
-            USR_CREATE_PROJECTS,
-        ];
-
-        for perm in all {
-            self.grant_permission(perm, "u_root").await?;
+        for perm in super_permissions::ALL {
+            self.ensure_permission(perm).await?;
         }
-
         Ok(())
     }
 
+    /// Create the permission document with no principals if it is missing.
+    pub async fn ensure_permission(&self, permission: &str) -> Result<()> {
+        let query = r#"
+            UPSERT { _key: @permission }
+            INSERT { _key: @permission, principals: [] }
+            UPDATE {}
+            IN permissions
+        "#;
+        let vars = std::collections::HashMap::from([(
+            "permission",
+            serde_json::Value::String(permission.to_string()),
+        )]);
+        super::upsert_with_retry(|| {
+            let vars = vars.clone();
+            async move { self.aql::<serde_json::Value>(query, vars).await.map(|_| ()) }
+        })
+        .await
+    }
+
     pub async fn has_permission(&self, user_id: &str, permission: &str) -> Result<bool> {
         let query = r#"
             LET perm = DOCUMENT("permissions", @permission)
@@ -105,7 +115,6 @@ impl ArangoDb {
     }
 
     pub async fn grant_permission(&self, permission: &str, principal: &str) -> Result<()> {
-        // TODO: add "ensure permission exists" logic to add multiple permissions without worrying about
         // TODO: add "ensure permission not exists" to mass revoke permissions
         let query = r#"
             UPSERT { _key: @permission }
@@ -0,0 +1,46 @@
+use anyhow::Result;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::ArangoDb;
+
+/// A stored refresh token: the SHA-256 of the token is the `_key`, so the
+/// token itself never reaches the database.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RefreshTokenRecord {
+    pub user_id: String,
+    /// Unix seconds.
+    pub expires_at: i64,
+}
+
+impl ArangoDb {
+    /// Remember a refresh token by its hash until `expires_at` (unix seconds).
+    pub async fn store_refresh_token(&self, hash: &str, user_id: &str, expires_at: i64) -> Result<()> {
+        let query = r#"
+            INSERT { _key: @key, user_id: @user_id, expires_at: @expires_at, created_at: DATE_ISO8601(DATE_NOW()) }
+            INTO refresh_tokens
+        "#;
+        let vars = std::collections::HashMap::from([
+            ("key", Value::String(hash.to_string())),
+            ("user_id", Value::String(user_id.to_string())),
+            ("expires_at", Value::from(expires_at)),
+        ]);
+        self.aql::<Value>(query, vars).await?;
+        Ok(())
+    }
+
+    /// Remove the token with this hash and return what it was issued for.
+    /// A single `REMOVE ... RETURN OLD`, so each token can be redeemed once
+    /// even under concurrent requests. `None` if unknown or already used.
+    pub async fn take_refresh_token(&self, hash: &str) -> Result<Option<RefreshTokenRecord>> {
+        let query = r#"
+            FOR t IN refresh_tokens
+              FILTER t._key == @key
+              REMOVE t IN refresh_tokens
+              RETURN OLD
+        "#;
+        let vars = std::collections::HashMap::from([("key", Value::String(hash.to_string()))]);
+        let taken: Vec<RefreshTokenRecord> = self.aql(query, vars).await?;
+        Ok(taken.into_iter().next())
+    }
+}
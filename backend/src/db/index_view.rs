@@ -0,0 +1,250 @@
+//! Secondary indexes derived from resource documents.
+//!
+//! An [`IndexSpec`] maps each document of one kind to index values, e.g. a
+//! project to the principals owning it. [`IndexView`] keeps the reverse mapping
+//! `value -> {resource keys}` in memory and updates it on every write the
+//! handlers record through `AppState::publish_change`, dropping values a
+//! document no longer produces. It starts empty: `main` rebuilds every index
+//! from the database at startup, and `POST /v1/ops/indexes/{name}/rebuild`
+//! rescans one on demand.
+
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::Result;
+use serde_json::Value;
+use tokio::sync::RwLock;
+
+use crit_shared::util_models::ProjectRole;
+
+use super::DatabaseInterface;
+use crate::controllers::{gitops_controller::parse_acl, project_controller::ProjectController};
+
+/// One declared index over `kind`.
+pub struct IndexSpec {
+    pub name: &'static str,
+    pub kind: &'static str,
+    /// Index values of a stored (internal) document; may repeat or be empty.
+    pub extract: fn(&Value) -> Vec<String>,
+}
+
+#[derive(Default)]
+struct IndexData {
+    /// Index value -> keys of the documents producing it.
+    reverse: HashMap<String, BTreeSet<String>>,
+    /// Document key -> the values it produced last, to find stale entries.
+    forward: HashMap<String, Vec<String>>,
+}
+
+impl IndexData {
+    fn set(&mut self, key: &str, mut values: Vec<String>) {
+        values.sort();
+        values.dedup();
+        let old = self.forward.remove(key).unwrap_or_default();
+        for value in old.iter().filter(|v| !values.contains(v)) {
+            if let Some(keys) = self.reverse.get_mut(value) {
+                keys.remove(key);
+                if keys.is_empty() {
+                    self.reverse.remove(value);
+                }
+            }
+        }
+        for value in &values {
+            self.reverse.entry(value.clone()).or_default().insert(key.to_string());
+        }
+        if !values.is_empty() {
+            self.forward.insert(key.to_string(), values);
+        }
+    }
+}
+
+pub struct IndexView {
+    specs: Vec<IndexSpec>,
+    data: RwLock<HashMap<&'static str, IndexData>>,
+}
+
+impl IndexView {
+    pub fn new(specs: Vec<IndexSpec>) -> Self {
+        Self { specs, data: RwLock::new(HashMap::new()) }
+    }
+
+    /// Registered index names, in registration order.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.specs.iter().map(|s| s.name)
+    }
+
+    /// Update every index over `kind` for a written (`Some`) or deleted (`None`) document.
+    pub async fn apply(&self, kind: &str, key: &str, doc: Option<&Value>) {
+        let mut data = self.data.write().await;
+        for spec in self.specs.iter().filter(|s| s.kind == kind) {
+            let values = doc.map(|d| (spec.extract)(d)).unwrap_or_default();
+            data.entry(spec.name).or_default().set(key, values);
+        }
+    }
+
+    /// Keys of the documents whose index `name` contains `value`, sorted.
+    /// Empty for unknown names and values.
+    pub async fn query_index(&self, name: &str, value: &str) -> Vec<String> {
+        self.data
+            .read()
+            .await
+            .get(name)
+            .and_then(|d| d.reverse.get(value))
+            .map(|keys| keys.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Rescan the kind behind index `name` and replace its contents.
+    /// Returns the number of documents scanned, or `None` for an unknown name.
+    ///
+    /// Writes landing during the scan may be missed until the next write of
+    /// the same document; rebuild again if that matters.
+    pub async fn rebuild(&self, name: &str, db: &dyn DatabaseInterface) -> Result<Option<usize>> {
+        let Some(spec) = self.specs.iter().find(|s| s.name == name) else {
+            return Ok(None);
+        };
+        let docs = db.generic_list(spec.kind, None, None, None).await?.docs;
+        let mut fresh = IndexData::default();
+        for doc in &docs {
+            if let Some(key) = doc.get("_key").and_then(|v| v.as_str()) {
+                fresh.set(key, (spec.extract)(doc));
+            }
+        }
+        self.data.write().await.insert(spec.name, fresh);
+        Ok(Some(docs.len()))
+    }
+
+    /// Rebuild every registered index, logging (not returning) failures.
+    pub async fn rebuild_all(&self, db: &dyn DatabaseInterface) {
+        for name in self.names() {
+            match self.rebuild(name, db).await {
+                Ok(scanned) => log::info!("[INDEX] {} rebuilt from {} documents", name, scanned.unwrap_or(0)),
+                Err(e) => log::error!("[INDEX] {} rebuild failed: {}", name, e),
+            }
+        }
+    }
+
+    /// Rebuild index `only`, or every index, replacing each wholesale so no
+    /// drifted entry survives. Returns `(name, documents scanned)` per index,
+    /// or `None` for an unknown name. Stops at the first failed scan.
+    pub async fn rebuild_indexes(
+        &self,
+        db: &dyn DatabaseInterface,
+        only: Option<&str>,
+    ) -> Result<Option<Vec<(&'static str, usize)>>> {
+        let names: Vec<&'static str> = self.names().filter(|n| only.is_none_or(|o| o == *n)).collect();
+        if names.is_empty() {
+            return Ok(None);
+        }
+        let mut rebuilt = Vec::with_capacity(names.len());
+        for (i, name) in names.iter().enumerate() {
+            log::info!("[INDEX] rebuilding {} ({}/{})", name, i + 1, names.len());
+            let scanned = self.rebuild(name, db).await?.unwrap_or(0);
+            log::info!("[INDEX] {} rebuilt from {} documents", name, scanned);
+            rebuilt.push((*name, scanned));
+        }
+        Ok(Some(rebuilt))
+    }
+}
+
+impl Default for IndexView {
+    /// The indexes the server maintains out of the box.
+    fn default() -> Self {
+        Self::new(vec![
+            IndexSpec { name: "projects_by_owner", kind: "projects", extract: project_owners },
+            IndexSpec { name: "projects_by_principal", kind: "projects", extract: project_principals },
+        ])
+    }
+}
+
+/// Principals holding the `owner` role on the whole project.
+pub fn project_owners(doc: &Value) -> Vec<String> {
+    ProjectController::members(doc)
+        .into_iter()
+        .filter(|(_, role)| *role == ProjectRole::Owner)
+        .map(|(principal, _)| principal)
+        .collect()
+}
+
+/// Every principal named anywhere in the project ACL, scoped entries included.
+pub fn project_principals(doc: &Value) -> Vec<String> {
+    parse_acl(doc).map(|acl| acl.list.into_iter().flat_map(|e| e.principals).collect()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn project(owners: &[&str], members: &[&str]) -> Value {
+        json!({
+            "_key": "p_demo",
+            "acl": {
+                "list": [
+                    { "permissions": ProjectRole::Owner.permissions().bits(), "principals": owners },
+                    { "permissions": ProjectRole::Member.permissions().bits(), "principals": members },
+                ],
+                "last_mod_date": "2025-01-01T00:00:00Z",
+            },
+        })
+    }
+
+    #[tokio::test]
+    async fn changed_values_replace_stale_entries() {
+        let view = IndexView::default();
+        view.apply("projects", "p_demo", Some(&project(&["u_alice"], &["u_bob"]))).await;
+        assert_eq!(view.query_index("projects_by_owner", "u_alice").await, vec!["p_demo"]);
+        assert_eq!(view.query_index("projects_by_principal", "u_bob").await, vec!["p_demo"]);
+
+        view.apply("projects", "p_demo", Some(&project(&["u_carol"], &["u_bob"]))).await;
+        assert!(view.query_index("projects_by_owner", "u_alice").await.is_empty());
+        assert_eq!(view.query_index("projects_by_owner", "u_carol").await, vec!["p_demo"]);
+        assert_eq!(view.query_index("projects_by_principal", "u_bob").await, vec!["p_demo"]);
+
+        view.apply("projects", "p_demo", None).await;
+        assert!(view.query_index("projects_by_owner", "u_carol").await.is_empty());
+        assert!(view.query_index("projects_by_principal", "u_bob").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn rebuild_corrects_a_corrupted_index() {
+        let db = crate::db::InMemoryDb::new();
+        let mut alpha = project(&["u_alice"], &["u_bob"]);
+        alpha["_key"] = json!("p_alpha");
+        let mut beta = project(&["u_bob"], &[]);
+        beta["_key"] = json!("p_beta");
+        db.insert("projects", alpha).unwrap();
+        db.insert("projects", beta).unwrap();
+
+        let view = IndexView::default();
+        view.rebuild_all(&db).await;
+        // Drift: a write that never reached the index, and one that no longer exists.
+        view.apply("projects", "p_alpha", Some(&project(&["u_mallory"], &[]))).await;
+        view.apply("projects", "p_gone", Some(&project(&["u_alice"], &[]))).await;
+        assert_eq!(view.query_index("projects_by_owner", "u_alice").await, vec!["p_gone"]);
+
+        let rebuilt = view.rebuild_indexes(&db, None).await.unwrap().unwrap();
+        assert_eq!(rebuilt, vec![("projects_by_owner", 2), ("projects_by_principal", 2)]);
+        assert_eq!(view.query_index("projects_by_owner", "u_alice").await, vec!["p_alpha"]);
+        assert_eq!(view.query_index("projects_by_owner", "u_bob").await, vec!["p_beta"]);
+        assert!(view.query_index("projects_by_owner", "u_mallory").await.is_empty());
+        assert_eq!(view.query_index("projects_by_principal", "u_bob").await, vec!["p_alpha", "p_beta"]);
+
+        assert!(view.rebuild_indexes(&db, Some("nope")).await.unwrap().is_none());
+        assert_eq!(view.rebuild_indexes(&db, Some("projects_by_owner")).await.unwrap().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn other_kinds_and_scoped_entries_are_ignored() {
+        let view = IndexView::default();
+        view.apply("groups", "g_demo", Some(&project(&["u_alice"], &[]))).await;
+        assert!(view.query_index("projects_by_owner", "u_alice").await.is_empty());
+
+        let mut doc = project(&[], &[]);
+        doc["acl"]["list"][0] = json!({
+            "permissions": ProjectRole::Owner.permissions().bits(), "principals": ["u_dave"], "scope": "tasks",
+        });
+        view.apply("projects", "p_demo", Some(&doc)).await;
+        assert!(view.query_index("projects_by_owner", "u_dave").await.is_empty());
+        assert_eq!(view.query_index("projects_by_principal", "u_dave").await, vec!["p_demo"]);
+    }
+}
@@ -0,0 +1,571 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use serde_json::{Value, json};
+
+use crit_shared::data_models::User;
+use crit_shared::util_models::{DeletionInfo, DisconnectedEdge, HistoryEntry, PrincipalId, PrincipalKind};
+
+use super::arangodb::{
+    CascadeWrite, DEFAULT_MEMBERSHIP_DEPTH, EffectiveMember, EffectiveMembership, MemberFilter, MemberPage, PaginatedResult,
+    collection_for_principal,
+};
+use super::interface::DatabaseInterface;
+
+/// `DatabaseInterface` over plain maps, for exercising controllers in tests
+/// without ArangoDB. Collections are created on first write; graph traversals
+/// follow the `_from`/`_to` of documents in `memberships` like the AQL ones do.
+pub struct InMemoryDb {
+    state: Mutex<State>,
+    membership_max_depth: u32,
+}
+
+impl Default for InMemoryDb {
+    fn default() -> Self {
+        Self {
+            state: Mutex::default(),
+            membership_max_depth: DEFAULT_MEMBERSHIP_DEPTH,
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// collection -> `_key` -> document, ordered by key like `SORT doc._key`.
+    collections: HashMap<String, BTreeMap<String, Value>>,
+    /// permission -> principals holding it.
+    permissions: HashMap<String, Vec<String>>,
+    history: Vec<HistoryEntry>,
+}
+
+fn membership_edge(principal_id: &str, group_id: &str) -> Value {
+    json!({
+        "_key": format!("{}::{}", principal_id, group_id),
+        "_from": format!("{}/{}", collection_for_principal(principal_id), principal_id),
+        "_to": format!("groups/{}", group_id),
+        "principal": principal_id,
+        "group": group_id,
+    })
+}
+
+fn is_live(doc: &Value) -> bool {
+    doc.get("deletion").is_none_or(Value::is_null)
+}
+
+impl State {
+    fn collection(&self, name: &str) -> impl Iterator<Item = &Value> {
+        self.collections.get(name).into_iter().flat_map(|c| c.values())
+    }
+
+    fn live_edges(&self) -> impl Iterator<Item = &Value> {
+        self.collection("memberships").filter(|m| is_live(m))
+    }
+
+    /// Whether a `collection/key` vertex is soft-deleted. A missing vertex is
+    /// not, as in an AQL traversal.
+    fn vertex_deleted(&self, id: &str) -> bool {
+        let Some((collection, key)) = id.split_once('/') else {
+            return false;
+        };
+        self.collections
+            .get(collection)
+            .and_then(|c| c.get(key))
+            .is_some_and(|doc| !is_live(doc))
+    }
+
+    /// Keys of live vertices reachable from `start` over membership edges, BFS,
+    /// each visited once. `outbound` follows `_from` -> `_to`.
+    fn traverse(&self, start: &str, outbound: bool) -> Vec<String> {
+        let (near, far) = if outbound { ("_from", "_to") } else { ("_to", "_from") };
+        let mut seen = HashSet::from([start.to_string()]);
+        let mut queue = VecDeque::from([(start.to_string(), 0)]);
+        let mut reached = Vec::new();
+        while let Some((vertex, depth)) = queue.pop_front() {
+            if depth == DEFAULT_MEMBERSHIP_DEPTH {
+                continue;
+            }
+            for edge in self.collection("memberships") {
+                if edge.get(near).and_then(Value::as_str) != Some(vertex.as_str()) {
+                    continue;
+                }
+                let Some(next) = edge.get(far).and_then(Value::as_str) else {
+                    continue;
+                };
+                if !seen.insert(next.to_string()) {
+                    continue;
+                }
+                if !self.vertex_deleted(next) {
+                    reached.push(next.rsplit('/').next().unwrap_or(next).to_string());
+                }
+                queue.push_back((next.to_string(), depth + 1));
+            }
+        }
+        reached
+    }
+
+    /// BFS from principal `start` recording the shortest key path to every live
+    /// vertex. Each vertex is visited once, so cycles terminate and diamonds
+    /// count once. Like `ArangoDb::membership_traversal` it looks one level past
+    /// `max_depth` and errors if anything is found there.
+    fn traverse_with_paths(&self, start: &str, outbound: bool, max_depth: u32) -> Result<Vec<EffectiveMember>> {
+        let (near, far) = if outbound { ("_from", "_to") } else { ("_to", "_from") };
+        let origin = format!("{}/{}", collection_for_principal(start), start);
+        let limit = max_depth as usize + 1;
+        let mut seen = HashSet::from([origin.clone()]);
+        let mut queue = VecDeque::from([(origin, vec![start.to_string()])]);
+        let mut reached = Vec::new();
+        while let Some((vertex, path)) = queue.pop_front() {
+            if path.len() > limit {
+                continue;
+            }
+            for edge in self.collection("memberships") {
+                if edge.get(near).and_then(Value::as_str) != Some(vertex.as_str()) {
+                    continue;
+                }
+                let Some(next) = edge.get(far).and_then(Value::as_str) else {
+                    continue;
+                };
+                if !seen.insert(next.to_string()) {
+                    continue;
+                }
+                let mut next_path = path.clone();
+                next_path.push(next.rsplit('/').next().unwrap_or(next).to_string());
+                if !self.vertex_deleted(next) {
+                    if next_path.len() > limit {
+                        return Err(anyhow!(
+                            "membership nesting from {} exceeds max depth {} (reached {})",
+                            start,
+                            max_depth,
+                            next_path[next_path.len() - 1]
+                        ));
+                    }
+                    reached.push(EffectiveMember {
+                        principal: next_path[next_path.len() - 1].clone(),
+                        path: next_path.clone(),
+                    });
+                }
+                queue.push_back((next.to_string(), next_path));
+            }
+        }
+        Ok(reached)
+    }
+
+    fn replace(&mut self, collection: &str, key: &str, mut doc: Value) -> Result<()> {
+        let existing = self
+            .collections
+            .get_mut(collection)
+            .and_then(|c| c.get_mut(key))
+            .ok_or_else(|| anyhow!("document not found: {}/{}", collection, key))?;
+        if let Some(obj) = doc.as_object_mut() {
+            obj.insert("_key".to_string(), json!(key));
+        }
+        *existing = doc;
+        Ok(())
+    }
+
+    fn soft_delete(&mut self, collection: &str, key: &str, deleted_by: &str) -> Result<()> {
+        let from_path = format!("{}/{}", collection, key);
+        let to_path = format!("groups/{}", key);
+        let disconnected_edges = self
+            .collection("memberships")
+            .filter(|m| {
+                m.get("_from").and_then(Value::as_str) == Some(from_path.as_str())
+                    || m.get("_to").and_then(Value::as_str) == Some(to_path.as_str())
+            })
+            .filter_map(|m| {
+                Some(DisconnectedEdge {
+                    collection: "memberships".to_string(),
+                    key: m.get("_key")?.as_str()?.to_string(),
+                    from: m.get("_from")?.as_str()?.to_string(),
+                    to: m.get("_to")?.as_str()?.to_string(),
+                })
+            })
+            .collect();
+        let deletion = DeletionInfo {
+            deleted_at: chrono::Utc::now(),
+            deleted_by: deleted_by.into(),
+            disconnected_edges,
+        };
+
+        let doc = self
+            .collections
+            .get_mut(collection)
+            .and_then(|c| c.get_mut(key))
+            .filter(|doc| is_live(doc))
+            .ok_or_else(|| anyhow!("document not found or already deleted: {}/{}", collection, key))?;
+        if let Some(obj) = doc.as_object_mut() {
+            obj.insert("deletion".to_string(), serde_json::to_value(&deletion)?);
+        }
+        Ok(())
+    }
+
+    fn soft_delete_cascading(
+        &mut self,
+        collection: &str,
+        key: &str,
+        deleted_by: &str,
+        writes: &[CascadeWrite],
+    ) -> Result<Vec<String>> {
+        for write in writes {
+            match write {
+                CascadeWrite::Replace { collection, key, doc } => self.replace(collection, key, doc.clone())?,
+                CascadeWrite::SoftDelete { collection, key } => self.soft_delete(collection, key, deleted_by)?,
+            }
+        }
+        self.soft_delete(collection, key, deleted_by)?;
+
+        let from_path = format!("{}/{}", collection, key);
+        let edges = self.collections.entry("memberships".to_string()).or_default();
+        let mut left = Vec::new();
+        edges.retain(|_, m| {
+            if m.get("_from").and_then(Value::as_str) != Some(from_path.as_str()) {
+                return true;
+            }
+            if let Some(group) = m.get("group").and_then(Value::as_str).filter(|_| is_live(m)) {
+                left.push(group.to_string());
+            }
+            false
+        });
+        left.sort();
+        left.dedup();
+        Ok(left.into_iter().filter(|g| self.count_members(g) == 0).collect())
+    }
+
+    fn user_principals(&self, user_id: &str) -> Vec<String> {
+        let mut principals = vec![user_id.to_string()];
+        for group in self.traverse(&format!("users/{}", user_id), true) {
+            if !principals.contains(&group) {
+                principals.push(group);
+            }
+        }
+        principals
+    }
+
+    fn holds(&self, principals: &[String], permission: &str) -> bool {
+        self.permissions
+            .get(permission)
+            .is_some_and(|holders| holders.iter().any(|p| principals.contains(p)))
+    }
+
+    fn count_members(&self, group_id: &str) -> u64 {
+        self.live_edges()
+            .filter(|m| m.get("group").and_then(Value::as_str) == Some(group_id))
+            .count() as u64
+    }
+}
+
+impl InMemoryDb {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Same as `ArangoDb::with_membership_max_depth`.
+    pub fn with_membership_max_depth(mut self, depth: u32) -> Self {
+        self.membership_max_depth = depth;
+        self
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Insert a document keyed by its `_key`; errors on a duplicate key.
+    pub fn insert(&self, collection: &str, doc: Value) -> Result<()> {
+        let key = doc
+            .get("_key")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("document without _key in {}", collection))?
+            .to_string();
+        let mut state = self.state();
+        let docs = state.collections.entry(collection.to_string()).or_default();
+        if docs.contains_key(&key) {
+            return Err(anyhow!("unique constraint violated: {}/{}", collection, key));
+        }
+        docs.insert(key, doc);
+        Ok(())
+    }
+
+    /// A stored document whether or not it is soft-deleted.
+    pub fn raw(&self, collection: &str, key: &str) -> Option<Value> {
+        self.state().collections.get(collection)?.get(key).cloned()
+    }
+
+    pub fn grant_permission(&self, permission: &str, principal: &str) {
+        let mut state = self.state();
+        let holders = state.permissions.entry(permission.to_string()).or_default();
+        if !holders.iter().any(|p| p == principal) {
+            holders.push(principal.to_string());
+        }
+    }
+
+    /// History entries of one resource, oldest first.
+    pub fn history(&self, kind: &str, key: &str) -> Vec<HistoryEntry> {
+        self.state()
+            .history
+            .iter()
+            .filter(|h| h.resource_kind == kind && h.resource_key == key)
+            .cloned()
+            .collect()
+    }
+}
+
+#[async_trait]
+impl DatabaseInterface for InMemoryDb {
+    async fn generic_get(&self, collection: &str, key: &str) -> Result<Option<Value>> {
+        Ok(self.raw(collection, key).filter(is_live))
+    }
+
+    async fn generic_list(
+        &self,
+        collection: &str,
+        fields: Option<&[&str]>,
+        limit: Option<u32>,
+        cursor: Option<&str>,
+    ) -> Result<PaginatedResult> {
+        let state = self.state();
+        let mut docs: Vec<Value> = state
+            .collections
+            .get(collection)
+            .into_iter()
+            .flat_map(|c| c.iter())
+            .filter(|(key, doc)| is_live(doc) && cursor.is_none_or(|c| key.as_str() > c))
+            .take(limit.map_or(usize::MAX, |l| l as usize + 1))
+            .map(|(_, doc)| match fields {
+                Some(fields) => Value::Object(
+                    fields
+                        .iter()
+                        .filter_map(|f| doc.get(*f).map(|v| (f.to_string(), v.clone())))
+                        .collect(),
+                ),
+                None => doc.clone(),
+            })
+            .collect();
+
+        let has_more = limit.is_some_and(|l| docs.len() > l as usize);
+        if has_more {
+            docs.pop();
+        }
+        let next_cursor = if has_more {
+            docs.last().and_then(|d| d.get("_key")).and_then(Value::as_str).map(String::from)
+        } else {
+            None
+        };
+        Ok(PaginatedResult { docs, next_cursor, has_more })
+    }
+
+    async fn generic_create(&self, collection: &str, doc: Value) -> Result<()> {
+        self.insert(collection, doc)
+    }
+
+    async fn generic_update(&self, collection: &str, key: &str, doc: Value) -> Result<()> {
+        self.state().replace(collection, key, doc)
+    }
+
+    async fn generic_soft_delete(&self, collection: &str, key: &str, deleted_by: &str) -> Result<()> {
+        self.state().soft_delete(collection, key, deleted_by)
+    }
+
+    async fn soft_delete_cascading(
+        &self,
+        collection: &str,
+        key: &str,
+        deleted_by: &str,
+        writes: &[CascadeWrite],
+    ) -> Result<Vec<String>> {
+        let mut state = self.state();
+        // All or none: work on the live maps and put the snapshot back on error.
+        let snapshot = state.collections.clone();
+        let result = state.soft_delete_cascading(collection, key, deleted_by, writes);
+        if result.is_err() {
+            state.collections = snapshot;
+        }
+        result
+    }
+
+    async fn write_history_entry(&self, kind: &str, key: &str, snapshot: Value, changed_by: &str) -> Result<()> {
+        let mut state = self.state();
+        let revision = state
+            .history
+            .iter()
+            .filter(|h| h.resource_kind == kind && h.resource_key == key)
+            .count() as u64
+            + 1;
+        state.history.push(HistoryEntry {
+            id: format!("{}_{}_{:06}", kind, key, revision),
+            resource_kind: kind.to_string(),
+            resource_key: key.to_string(),
+            revision,
+            snapshot,
+            changed_by: changed_by.into(),
+            changed_at: chrono::Utc::now(),
+        });
+        Ok(())
+    }
+
+    async fn get_user_by_id(&self, user_id: &PrincipalId) -> Result<Option<User>> {
+        match self.raw("users", &PrincipalId::parse(PrincipalKind::User, user_id)) {
+            Some(doc) => Ok(Some(serde_json::from_value(doc)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn get_user_principals(&self, user_id: &PrincipalId) -> Result<Vec<String>> {
+        Ok(self.state().user_principals(user_id))
+    }
+
+    async fn has_permission(&self, user_id: &PrincipalId, permission: &str) -> Result<bool> {
+        let state = self.state();
+        Ok(state.holds(&state.user_principals(user_id), permission))
+    }
+
+    async fn has_permission_with_principals(&self, principals: &[String], permission: &str) -> Result<bool> {
+        Ok(self.state().holds(principals, permission))
+    }
+
+    async fn add_principal_to_group(&self, principal_id: &PrincipalId, group_id: &PrincipalId) -> Result<()> {
+        self.insert("memberships", membership_edge(principal_id, group_id))
+    }
+
+    async fn add_principals_to_group(&self, principal_ids: &[PrincipalId], group_id: &PrincipalId) -> Result<()> {
+        let mut state = self.state();
+        let edges = state.collections.entry("memberships".to_string()).or_default();
+        for principal_id in principal_ids {
+            edges.insert(format!("{}::{}", principal_id, group_id), membership_edge(principal_id, group_id));
+        }
+        Ok(())
+    }
+
+    async fn add_principal_to_group_acl(&self, group_id: &PrincipalId, principal_id: &PrincipalId, permissions_bits: u8) -> Result<()> {
+        let mut state = self.state();
+        let Some(doc) = state
+            .collections
+            .get_mut("groups")
+            .and_then(|c| c.get_mut(group_id.as_str()))
+            .filter(|doc| is_live(doc))
+        else {
+            return Ok(());
+        };
+        let mut list = doc.pointer("/acl/list").and_then(Value::as_array).cloned().unwrap_or_default();
+        let already = list.iter().any(|entry| {
+            entry
+                .get("principals")
+                .and_then(Value::as_array)
+                .is_some_and(|ps| ps.iter().any(|p| p.as_str() == Some(principal_id.as_str())))
+        });
+        if already {
+            return Ok(());
+        }
+        list.push(json!({ "permissions": permissions_bits, "principals": [principal_id] }));
+        if let Some(obj) = doc.as_object_mut() {
+            obj.insert(
+                "acl".to_string(),
+                json!({ "list": list, "last_mod_date": chrono::Utc::now().to_rfc3339() }),
+            );
+        }
+        Ok(())
+    }
+
+    async fn count_group_members(&self, group_id: &PrincipalId) -> Result<u64> {
+        Ok(self.state().count_members(group_id))
+    }
+
+    async fn get_all_group_members_transitive(&self, group_id: &PrincipalId) -> Result<Vec<String>> {
+        let state = self.state();
+        let mut members: Vec<String> = state
+            .live_edges()
+            .filter(|m| m.get("group").and_then(Value::as_str) == Some(group_id.as_str()))
+            .filter_map(|m| m.get("principal").and_then(Value::as_str).map(String::from))
+            .collect();
+        for principal in state.traverse(&format!("groups/{}", group_id), false) {
+            if !members.contains(&principal) {
+                members.push(principal);
+            }
+        }
+        Ok(members)
+    }
+
+    async fn list_group_members(&self, group_id: &PrincipalId) -> Result<Vec<String>> {
+        let mut members: Vec<String> = self
+            .state()
+            .live_edges()
+            .filter(|m| m.get("group").and_then(Value::as_str) == Some(group_id.as_str()))
+            .filter_map(|m| m.get("principal").and_then(Value::as_str).map(String::from))
+            .collect();
+        members.sort();
+        Ok(members)
+    }
+
+    async fn list_members(
+        &self,
+        group_id: &PrincipalId,
+        filter: MemberFilter,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> Result<MemberPage> {
+        let mut matching = self.list_group_members(group_id).await?;
+        matching.retain(|m| filter.matches(m));
+        let total = matching.len() as u64;
+        let members = matching.into_iter().skip(offset).take(limit.unwrap_or(usize::MAX)).collect();
+        Ok(MemberPage { members, total })
+    }
+
+    async fn resolve_effective_members(&self, group_id: &PrincipalId) -> Result<EffectiveMembership> {
+        let reached = self.state().traverse_with_paths(group_id, false, self.membership_max_depth)?;
+        Ok(EffectiveMembership {
+            group: group_id.to_string(),
+            users: reached
+                .into_iter()
+                .filter(|m| PrincipalId::from(m.principal.as_str()).is_user())
+                .collect(),
+        })
+    }
+
+    async fn get_groups_of_principal(&self, principal_id: &PrincipalId) -> Result<Vec<String>> {
+        let reached = self.state().traverse_with_paths(principal_id, true, self.membership_max_depth)?;
+        Ok(reached.into_iter().map(|m| m.principal).collect())
+    }
+
+    async fn list_groups_of(&self, principal_id: &PrincipalId) -> Result<Vec<String>> {
+        let mut groups: Vec<String> = self
+            .state()
+            .live_edges()
+            .filter(|m| m.get("principal").and_then(Value::as_str) == Some(principal_id.as_str()))
+            .filter_map(|m| m.get("group").and_then(Value::as_str).map(String::from))
+            .collect();
+        groups.sort();
+        Ok(groups)
+    }
+
+    async fn remove_principal_from_group(&self, principal_id: &PrincipalId, group_id: &PrincipalId) -> Result<bool> {
+        let key = format!("{}::{}", principal_id, group_id);
+        Ok(self
+            .state()
+            .collections
+            .get_mut("memberships")
+            .and_then(|edges| edges.remove(&key))
+            .is_some())
+    }
+
+    async fn remove_all_members_of_group(&self, group_id: &PrincipalId) -> Result<()> {
+        if let Some(edges) = self.state().collections.get_mut("memberships") {
+            edges.retain(|_, m| m.get("group").and_then(Value::as_str) != Some(group_id));
+        }
+        Ok(())
+    }
+
+    async fn remove_principal_from_all_groups(&self, principal_id: &PrincipalId) -> Result<Vec<String>> {
+        let mut state = self.state();
+        let of_principal = |m: &Value| m.get("principal").and_then(Value::as_str) == Some(principal_id);
+        let affected: Vec<String> = state
+            .live_edges()
+            .filter(|m| of_principal(m))
+            .filter_map(|m| m.get("group").and_then(Value::as_str).map(String::from))
+            .collect();
+        if let Some(edges) = state.collections.get_mut("memberships") {
+            edges.retain(|_, m| !of_principal(m));
+        }
+        Ok(affected.into_iter().filter(|g| state.count_members(g) == 0).collect())
+    }
+}
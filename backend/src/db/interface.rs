@@ -0,0 +1,225 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crit_shared::data_models::User;
+use crit_shared::util_models::PrincipalId;
+
+use super::arangodb::{ArangoDb, CascadeWrite, EffectiveMembership, MemberFilter, MemberPage, PaginatedResult};
+
+/// The database operations kind controllers depend on. `ArangoDb` is the
+/// production backend; `InMemoryDb` lets controller logic run in tests without
+/// an ArangoDB server. Handlers keep using `ArangoDb` directly.
+///
+/// Semantics follow the ArangoDB implementation: soft-deleted documents are
+/// invisible to `generic_get`/`generic_list`, membership edges live in the
+/// `memberships` collection keyed `{principal}::{group}`.
+///
+/// Users, groups and other principals are addressed by their prefixed
+/// [`PrincipalId`] (`u_alice`, `g_admins`), never by bare names.
+#[async_trait]
+pub trait DatabaseInterface: Send + Sync {
+    async fn generic_get(&self, collection: &str, key: &str) -> Result<Option<Value>>;
+
+    async fn generic_list(
+        &self,
+        collection: &str,
+        fields: Option<&[&str]>,
+        limit: Option<u32>,
+        cursor: Option<&str>,
+    ) -> Result<PaginatedResult>;
+
+    /// Insert a new document keyed by its `_key`; errors if the key is taken.
+    async fn generic_create(&self, collection: &str, doc: Value) -> Result<()>;
+
+    /// Replace an existing document; errors if it does not exist.
+    async fn generic_update(&self, collection: &str, key: &str, doc: Value) -> Result<()>;
+
+    /// Mark a live document deleted; errors if it is missing or already deleted.
+    async fn generic_soft_delete(&self, collection: &str, key: &str, deleted_by: &str) -> Result<()>;
+
+    /// Soft-delete a document together with the writes of its cascade, all or
+    /// none: apply `writes`, mark the document deleted, and remove the
+    /// membership edges it is the member side of. Returns the groups left
+    /// without members.
+    async fn soft_delete_cascading(
+        &self,
+        collection: &str,
+        key: &str,
+        deleted_by: &str,
+        writes: &[CascadeWrite],
+    ) -> Result<Vec<String>>;
+
+    async fn write_history_entry(&self, kind: &str, key: &str, snapshot: Value, changed_by: &str) -> Result<()>;
+
+    async fn get_user_by_id(&self, user_id: &PrincipalId) -> Result<Option<User>>;
+
+    /// The user's id plus every group reachable through memberships.
+    async fn get_user_principals(&self, user_id: &PrincipalId) -> Result<Vec<String>>;
+
+    async fn has_permission(&self, user_id: &PrincipalId, permission: &str) -> Result<bool>;
+
+    async fn has_permission_with_principals(&self, principals: &[String], permission: &str) -> Result<bool>;
+
+    async fn add_principal_to_group(&self, principal_id: &PrincipalId, group_id: &PrincipalId) -> Result<()>;
+
+    /// Add several principals to a group, all or none. An existing edge for one
+    /// of the pairs, live or soft-deleted, is replaced.
+    async fn add_principals_to_group(&self, principal_ids: &[PrincipalId], group_id: &PrincipalId) -> Result<()>;
+
+    /// Append a `permissions_bits` ACL entry for the principal unless it already has one.
+    async fn add_principal_to_group_acl(&self, group_id: &PrincipalId, principal_id: &PrincipalId, permissions_bits: u8) -> Result<()>;
+
+    async fn count_group_members(&self, group_id: &PrincipalId) -> Result<u64>;
+
+    async fn get_all_group_members_transitive(&self, group_id: &PrincipalId) -> Result<Vec<String>>;
+
+    /// Direct members of a group, sorted.
+    async fn list_group_members(&self, group_id: &PrincipalId) -> Result<Vec<String>>;
+
+    /// One page of the direct members `filter` keeps, sorted, with their total.
+    async fn list_members(
+        &self,
+        group_id: &PrincipalId,
+        filter: MemberFilter,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> Result<MemberPage>;
+
+    /// Every user reachable from the group through nested groups, each once,
+    /// with the shortest membership path. Cycles terminate; nesting deeper than
+    /// the backend's `membership_max_depth` is an error.
+    async fn resolve_effective_members(&self, group_id: &PrincipalId) -> Result<EffectiveMembership>;
+
+    /// Every group the principal belongs to, directly or through nested groups.
+    /// Same cycle and depth rules as `resolve_effective_members`.
+    async fn get_groups_of_principal(&self, principal_id: &PrincipalId) -> Result<Vec<String>>;
+
+    /// Groups the principal is a direct member of, sorted.
+    async fn list_groups_of(&self, principal_id: &PrincipalId) -> Result<Vec<String>>;
+
+    /// Remove one membership edge; `false` if there was none.
+    async fn remove_principal_from_group(&self, principal_id: &PrincipalId, group_id: &PrincipalId) -> Result<bool>;
+
+    async fn remove_all_members_of_group(&self, group_id: &PrincipalId) -> Result<()>;
+
+    /// Returns the groups left without members.
+    async fn remove_principal_from_all_groups(&self, principal_id: &PrincipalId) -> Result<Vec<String>>;
+}
+
+#[async_trait]
+impl DatabaseInterface for ArangoDb {
+    async fn generic_get(&self, collection: &str, key: &str) -> Result<Option<Value>> {
+        ArangoDb::generic_get(self, collection, key).await
+    }
+
+    async fn generic_list(
+        &self,
+        collection: &str,
+        fields: Option<&[&str]>,
+        limit: Option<u32>,
+        cursor: Option<&str>,
+    ) -> Result<PaginatedResult> {
+        ArangoDb::generic_list(self, collection, fields, limit, cursor).await
+    }
+
+    async fn generic_create(&self, collection: &str, doc: Value) -> Result<()> {
+        ArangoDb::generic_create(self, collection, doc).await
+    }
+
+    async fn generic_update(&self, collection: &str, key: &str, doc: Value) -> Result<()> {
+        ArangoDb::generic_update(self, collection, key, doc).await
+    }
+
+    async fn generic_soft_delete(&self, collection: &str, key: &str, deleted_by: &str) -> Result<()> {
+        ArangoDb::generic_soft_delete(self, collection, key, deleted_by).await
+    }
+
+    async fn soft_delete_cascading(
+        &self,
+        collection: &str,
+        key: &str,
+        deleted_by: &str,
+        writes: &[CascadeWrite],
+    ) -> Result<Vec<String>> {
+        ArangoDb::soft_delete_cascading(self, collection, key, deleted_by, writes).await
+    }
+
+    async fn write_history_entry(&self, kind: &str, key: &str, snapshot: Value, changed_by: &str) -> Result<()> {
+        ArangoDb::write_history_entry(self, kind, key, snapshot, changed_by).await
+    }
+
+    async fn get_user_by_id(&self, user_id: &PrincipalId) -> Result<Option<User>> {
+        ArangoDb::get_user_by_id(self, user_id.as_str()).await
+    }
+
+    async fn get_user_principals(&self, user_id: &PrincipalId) -> Result<Vec<String>> {
+        ArangoDb::get_user_principals(self, user_id.as_str()).await
+    }
+
+    async fn has_permission(&self, user_id: &PrincipalId, permission: &str) -> Result<bool> {
+        ArangoDb::has_permission(self, user_id.as_str(), permission).await
+    }
+
+    async fn has_permission_with_principals(&self, principals: &[String], permission: &str) -> Result<bool> {
+        ArangoDb::has_permission_with_principals(self, principals, permission).await
+    }
+
+    async fn add_principal_to_group(&self, principal_id: &PrincipalId, group_id: &PrincipalId) -> Result<()> {
+        ArangoDb::add_principal_to_group(self, principal_id.as_str(), group_id.as_str(), None).await
+    }
+
+    async fn add_principals_to_group(&self, principal_ids: &[PrincipalId], group_id: &PrincipalId) -> Result<()> {
+        ArangoDb::add_principals_to_group(self, principal_ids, group_id.as_str()).await
+    }
+
+    async fn add_principal_to_group_acl(&self, group_id: &PrincipalId, principal_id: &PrincipalId, permissions_bits: u8) -> Result<()> {
+        ArangoDb::add_principal_to_group_acl(self, group_id.as_str(), principal_id.as_str(), permissions_bits).await
+    }
+
+    async fn count_group_members(&self, group_id: &PrincipalId) -> Result<u64> {
+        ArangoDb::count_group_members(self, group_id.as_str()).await
+    }
+
+    async fn get_all_group_members_transitive(&self, group_id: &PrincipalId) -> Result<Vec<String>> {
+        ArangoDb::get_all_group_members_transitive(self, group_id.as_str()).await
+    }
+
+    async fn list_group_members(&self, group_id: &PrincipalId) -> Result<Vec<String>> {
+        ArangoDb::list_group_members(self, group_id.as_str()).await
+    }
+
+    async fn list_members(
+        &self,
+        group_id: &PrincipalId,
+        filter: MemberFilter,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> Result<MemberPage> {
+        ArangoDb::list_members(self, group_id.as_str(), filter, offset, limit).await
+    }
+
+    async fn resolve_effective_members(&self, group_id: &PrincipalId) -> Result<EffectiveMembership> {
+        ArangoDb::resolve_effective_members(self, group_id.as_str()).await
+    }
+
+    async fn get_groups_of_principal(&self, principal_id: &PrincipalId) -> Result<Vec<String>> {
+        ArangoDb::get_groups_of_principal(self, principal_id.as_str()).await
+    }
+
+    async fn list_groups_of(&self, principal_id: &PrincipalId) -> Result<Vec<String>> {
+        ArangoDb::list_groups_of(self, principal_id.as_str()).await
+    }
+
+    async fn remove_principal_from_group(&self, principal_id: &PrincipalId, group_id: &PrincipalId) -> Result<bool> {
+        ArangoDb::remove_principal_from_group(self, principal_id.as_str(), group_id.as_str(), None).await
+    }
+
+    async fn remove_all_members_of_group(&self, group_id: &PrincipalId) -> Result<()> {
+        ArangoDb::remove_all_members_of_group(self, group_id.as_str()).await
+    }
+
+    async fn remove_principal_from_all_groups(&self, principal_id: &PrincipalId) -> Result<Vec<String>> {
+        ArangoDb::remove_principal_from_all_groups(self, principal_id.as_str()).await
+    }
+}
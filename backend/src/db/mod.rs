@@ -1,3 +1,12 @@
 pub mod arangodb;
+pub mod index_view;
+pub mod inmemory;
+pub mod interface;
 
-pub use arangodb::{ArangoDb, ArangoTx};
+pub use arangodb::{
+    AclFilter, ArangoDb, ArangoHealth, ArangoTx, CascadeWrite, ConnectRetry, DEFAULT_MEMBERSHIP_DEPTH,
+    EffectiveMember, EffectiveMembership, MemberFilter, MemberPage,
+};
+pub use index_view::{IndexSpec, IndexView};
+pub use inmemory::InMemoryDb;
+pub use interface::DatabaseInterface;
@@ -5,8 +5,8 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use crit_shared::requests::{ErrorBody, ErrorResponse as SharedErrorResponse};
 use serde::Serialize;
-use serde_json::json;
 use thiserror::Error;
 use utoipa::{
     IntoResponses, PartialSchema, ToSchema,
@@ -30,6 +30,13 @@ pub enum AppError {
     #[error("Validation error: {0}")]
     Validation(String),
 
+    /// Validation failure tied to one request field, reported in `details.field`.
+    #[error("Validation error: {field}: {message}")]
+    InvalidField { field: String, message: String },
+
+    #[error("Unprocessable entity: {0}")]
+    Unprocessable(String),
+
     #[error("Not found: {0}")]
     NotFound(String),
 
@@ -45,6 +52,18 @@ pub enum AppError {
     #[error("Scheduling impossible: {0}")]
     SchedulingImpossible(String),
 
+    /// Request body over the route group's limit (`MAX_BODY_BYTES`).
+    #[error("request body exceeds the limit of {limit} bytes")]
+    PayloadTooLarge { limit: usize },
+
+    /// A create would take the caller past one of their quotas.
+    #[error("quota {quota} exceeded: {current} of {limit} used")]
+    QuotaExceeded { quota: &'static str, current: u64, limit: u64 },
+
+    /// A mutation was attempted while `READ_ONLY` is set.
+    #[error("server is in read-only maintenance mode")]
+    ReadOnly,
+
     #[error("JWT error: {0}")]
     Jwt(#[from] jsonwebtoken::errors::Error),
 
@@ -54,6 +73,10 @@ pub enum AppError {
     #[error("Parse error: {0}")]
     Parse(String),
 
+    /// Request body that could not be decoded; `details` says where (e.g. YAML line/column).
+    #[error("Invalid request body: {message}")]
+    InvalidBody { message: String, details: serde_json::Value },
+
     #[error("Bcrypt error: {0}")]
     BcryptError(#[from] bcrypt::BcryptError),
 }
@@ -67,6 +90,8 @@ impl AppError {
             AppError::Authentication(_) => StatusCode::UNAUTHORIZED,
             AppError::Authorization(_) => StatusCode::UNAUTHORIZED,
             AppError::Validation(_) => StatusCode::BAD_REQUEST,
+            AppError::InvalidField { .. } => StatusCode::BAD_REQUEST,
+            AppError::Unprocessable(_) => StatusCode::UNPROCESSABLE_ENTITY,
             AppError::NotFound(_) => StatusCode::NOT_FOUND,
             AppError::Conflict(_) => StatusCode::CONFLICT,
             AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
@@ -74,12 +99,18 @@ impl AppError {
             AppError::Jwt(_) => StatusCode::UNAUTHORIZED,
             AppError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
             AppError::Parse(_) => StatusCode::BAD_REQUEST,
+            AppError::InvalidBody { .. } => StatusCode::BAD_REQUEST,
             AppError::BcryptError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             AppError::SchedulingImpossible(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::ReadOnly => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::PayloadTooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::QuotaExceeded { .. } => StatusCode::UNPROCESSABLE_ENTITY,
         }
     }
 
-    /// Get error type as string for JSON responses
+    /// Stable machine-readable code sent as `error.code`. Clients match on
+    /// these, so an existing code must never change; the table in
+    /// docs/api.md and `codes_are_stable` below pin them.
     pub fn error_type(&self) -> &'static str {
         match self {
             AppError::Internal(_) => "internal_error",
@@ -87,6 +118,8 @@ impl AppError {
             AppError::Authentication(_) => "authentication_error",
             AppError::Authorization(_) => "authorization_error",
             AppError::Validation(_) => "validation_error",
+            AppError::InvalidField { .. } => "validation_error",
+            AppError::Unprocessable(_) => "unprocessable_entity",
             AppError::NotFound(_) => "not_found",
             AppError::Conflict(_) => "conflict",
             AppError::BadRequest(_) => "bad_request",
@@ -94,8 +127,25 @@ impl AppError {
             AppError::Jwt(_) => "jwt_error",
             AppError::Io(_) => "io_error",
             AppError::Parse(_) => "parse_error",
+            AppError::InvalidBody { .. } => "invalid_body",
             AppError::BcryptError(_) => "bcrypt_error",
-            AppError::SchedulingImpossible(_) => "scheduling impossible",
+            AppError::SchedulingImpossible(_) => "scheduling_impossible",
+            AppError::ReadOnly => "read_only",
+            AppError::PayloadTooLarge { .. } => "payload_too_large",
+            AppError::QuotaExceeded { .. } => "quota_exceeded",
+        }
+    }
+
+    /// Structured context for the `details` field of the error body.
+    pub fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            AppError::InvalidBody { details, .. } => Some(details.clone()),
+            AppError::InvalidField { field, .. } => Some(serde_json::json!({ "field": field })),
+            AppError::PayloadTooLarge { limit } => Some(serde_json::json!({ "limit_bytes": limit })),
+            AppError::QuotaExceeded { quota, current, limit } => {
+                Some(serde_json::json!({ "quota": quota, "current": current, "limit": limit }))
+            }
+            _ => None,
         }
     }
 
@@ -108,23 +158,32 @@ impl AppError {
             | AppError::BadRequest(_)
             | AppError::Forbidden(_)
             | AppError::Jwt(_)
-            | AppError::Parse(_) => false,
+            | AppError::Parse(_)
+            | AppError::InvalidBody { .. }
+            | AppError::Unprocessable(_) => false,
+            AppError::InvalidField { .. } => false,
             AppError::Validation(_)
             | AppError::Internal(_)
             | AppError::Serialization(_)
             | AppError::Io(_)
             | AppError::BcryptError(_) => true,
-        AppError::Conflict(_) => false,
+            AppError::Conflict(_) => false,
             AppError::SchedulingImpossible(_) => true,
+            AppError::ReadOnly => false,
+            AppError::PayloadTooLarge { .. } => false,
+            AppError::QuotaExceeded { .. } => false,
         }
     }
 }
 
+/// OpenAPI schema mirror of `crit_shared::requests::ErrorResponse`.
 #[derive(Serialize, ToSchema)]
 pub struct ErrorResponse {
-    pub r#type: String, // Use r# to allow "type" keyword
+    pub code: String,
     pub message: String,
     pub status: u16,
+    pub details: Option<serde_json::Value>,
+    pub request_id: Option<String>,
 }
 
 impl IntoResponses for AppError {
@@ -211,6 +270,22 @@ impl IntoResponses for AppError {
             ),
         );
 
+        // 422 Unprocessable Entity
+        responses.insert(
+            "422".to_string(),
+            RefOr::T(
+                ResponseBuilder::new()
+                    .description("Unprocessable Entity")
+                    .content(
+                        "application/json",
+                        ContentBuilder::new()
+                            .schema(Some(ErrorResponse::schema()))
+                            .build(),
+                    )
+                    .build(),
+            ),
+        );
+
         // 500 Internal Server Error
         responses.insert(
             "500".to_string(),
@@ -259,13 +334,15 @@ impl IntoResponse for AppError {
             tracing::debug!("AppError: {} (status: {})", self, status);
         }
 
-        let body = json!({
-            "error": {
-                "type": self.error_type(),
-                "message": self.to_string(),
-                "status": status.as_u16()
-            }
-        });
+        let body = ErrorBody {
+            error: SharedErrorResponse {
+                code: self.error_type().to_string(),
+                message: self.to_string(),
+                status: status.as_u16(),
+                details: self.details(),
+                request_id: None,
+            },
+        };
 
         (status, Json(body)).into_response()
     }
@@ -285,6 +362,17 @@ impl AppError {
         Self::Validation(msg.to_string())
     }
 
+    pub fn invalid_field<F: Into<String>, T: std::fmt::Display>(field: F, msg: T) -> Self {
+        Self::InvalidField {
+            field: field.into(),
+            message: msg.to_string(),
+        }
+    }
+
+    pub fn unprocessable<T: std::fmt::Display>(msg: T) -> Self {
+        Self::Unprocessable(msg.to_string())
+    }
+
     pub fn not_found<T: std::fmt::Display>(msg: T) -> Self {
         Self::NotFound(msg.to_string())
     }
@@ -357,5 +445,94 @@ mod tests {
             AppError::conflict("test").status_code(),
             StatusCode::CONFLICT
         );
+        assert_eq!(
+            AppError::unprocessable("test").status_code(),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+    }
+
+    fn every_variant() -> Vec<(&'static str, AppError)> {
+        vec![
+            ("Internal", AppError::Internal(anyhow::anyhow!("boom"))),
+            ("Serialization", AppError::serialization("x")),
+            ("Authentication", AppError::authentication("x")),
+            ("Authorization", AppError::authorization("x")),
+            ("Validation", AppError::validation("x")),
+            ("InvalidField", AppError::invalid_field("id", "x")),
+            ("Unprocessable", AppError::unprocessable("x")),
+            ("NotFound", AppError::not_found("x")),
+            ("Conflict", AppError::conflict("x")),
+            ("BadRequest", AppError::bad_request("x")),
+            ("Forbidden", AppError::Forbidden("x".into())),
+            ("SchedulingImpossible", AppError::SchedulingImpossible("x".into())),
+            ("ReadOnly", AppError::ReadOnly),
+            ("PayloadTooLarge", AppError::PayloadTooLarge { limit: 1024 }),
+            ("QuotaExceeded", AppError::QuotaExceeded { quota: "owned_projects", current: 3, limit: 3 }),
+            (
+                "Jwt",
+                AppError::Jwt(jsonwebtoken::errors::ErrorKind::InvalidToken.into()),
+            ),
+            ("Io", AppError::Io(std::io::Error::other("x"))),
+            ("Parse", AppError::Parse("x".into())),
+            (
+                "InvalidBody",
+                AppError::InvalidBody {
+                    message: "x".into(),
+                    details: serde_json::json!({}),
+                },
+            ),
+            ("BcryptError", AppError::BcryptError(bcrypt::verify("x", "not a hash").unwrap_err())),
+        ]
+    }
+
+    /// Codes are part of the API contract: update docs/api.md and expect
+    /// client breakage before touching this snapshot.
+    #[test]
+    fn codes_are_stable() {
+        let table: Vec<String> = every_variant()
+            .into_iter()
+            .map(|(name, e)| format!("{} {} {}", name, e.error_type(), e.status_code().as_u16()))
+            .collect();
+        assert_eq!(
+            table.join("\n"),
+            "\
+Internal internal_error 500
+Serialization serialization_error 500
+Authentication authentication_error 401
+Authorization authorization_error 401
+Validation validation_error 400
+InvalidField validation_error 400
+Unprocessable unprocessable_entity 422
+NotFound not_found 404
+Conflict conflict 409
+BadRequest bad_request 400
+Forbidden forbidden 403
+SchedulingImpossible scheduling_impossible 503
+ReadOnly read_only 503
+PayloadTooLarge payload_too_large 413
+QuotaExceeded quota_exceeded 422
+Jwt jwt_error 401
+Io io_error 500
+Parse parse_error 400
+InvalidBody invalid_body 400
+BcryptError bcrypt_error 500"
+        );
+    }
+
+    #[tokio::test]
+    async fn field_errors_name_the_field() {
+        let resp = AppError::invalid_field("id", "must be lowercase").into_response();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({ "error": {
+                "code": "validation_error",
+                "message": "Validation error: id: must be lowercase",
+                "status": 400,
+                "details": { "field": "id" },
+            }})
+        );
     }
 }
@@ -1,17 +1,25 @@
 pub mod api;
+pub mod audit_log;
 pub mod cache;
 pub mod config;
 pub mod controllers;
 pub mod db;
 pub mod error;
 pub mod middleware;
+pub mod quota;
+pub mod reconcile;
+pub mod resource_registry;
 pub use crit_shared::{data_models, util_models};
 pub mod schema;
+pub mod server;
 pub mod services;
 pub mod state;
+#[cfg(feature = "otel")]
+pub mod telemetry;
 pub mod test;
 pub mod utils;
 pub mod validation;
+pub mod watch;
 pub mod godmode;
 
 use std::sync::Arc;
@@ -22,14 +30,10 @@ use crate::{
     middleware::auth::Auth,
     state::AppState,
 };
-use axum::{Json, Router, middleware::from_fn_with_state, routing::*};
+use axum::{Json, Router, extract::DefaultBodyLimit, middleware::from_fn_with_state, routing::*};
 use log::info;
 use serde_json::{Value, json};
-use tokio::net::TcpListener;
-use tower_http::{
-    cors::{Any, CorsLayer},
-    trace::TraceLayer,
-};
+use tower_http::{limit::RequestBodyLimitLayer, trace::TraceLayer};
 use utoipa::OpenApi;
 use utoipa_axum::router::OpenApiRouter;
 use utoipa_swagger_ui::SwaggerUi;
@@ -42,11 +46,17 @@ use utoipa_swagger_ui::SwaggerUi;
 struct ApiDoc;
 
 pub fn create_app(shared_state: Arc<AppState>) -> IntoMakeService<Router> {
+    // Oversized bodies get 413 before any extractor buffers them: up front from
+    // Content-Length, or as soon as a streamed body passes the limit.
+    let body_limit = shared_state.config.max_body_bytes;
     let mainrt = Router::new()
         // Unauthenticated routes — outside the /v1 auth nest so no JWT is required.
         .route("/v1/register", post(api::v1::authentication::login::register))
         .route("/v1/login", post(api::v1::authentication::login::login))
         .route("/v1/logout", post(api::v1::authentication::login::logout))
+        .route("/v1/refresh", post(api::v1::authentication::login::refresh))
+        .route("/v1/oauth/login", get(api::v1::authentication::oauth::login))
+        .route("/v1/oauth/callback", get(api::v1::authentication::oauth::callback))
         // Unauthenticated object-store static files (avatars / wallpapers).
         .route(
             "/v1/static/{*path}",
@@ -56,36 +66,113 @@ pub fn create_app(shared_state: Arc<AppState>) -> IntoMakeService<Router> {
             "/v1",
             Router::new()
                 .route("/ws", get(ws_handler))
-                .route(
-                    "/global/{kind}",
-                    get(api::v1::gitops::list_objects).post(api::v1::gitops::create_object),
-                )
+                .route("/system/info", get(api::v1::system::system_info))
+                .route("/2fa/enroll", post(api::v1::authentication::two_factor::enroll))
+                .route("/2fa/verify", post(api::v1::authentication::two_factor::verify))
+                .route("/search", get(api::v1::search::search))
+                .route("/personal/quota", get(api::v1::personal::my_quota))
                 .route(
                     "/global/{kind}/search",
                     get(api::v1::gitops::search_objects),
                 )
-                .route(
-                    "/global/{kind}/{id}",
-                    get(api::v1::gitops::get_object)
-                        .post(api::v1::gitops::upsert_object)
-                        .put(api::v1::gitops::update_object)
-                        .delete(api::v1::gitops::delete_object),
-                )
                 .route(
                     "/global/{kind}/{id}/upload/{upload_type}",
                     post(api::v1::upload::upload_media),
                 )
-                // Project-scoped routes
-                .route(
-                    "/projects/{project}/{kind}",
-                    get(api::v1::scoped_gitops::list_scoped_objects)
-                        .post(api::v1::scoped_gitops::create_scoped_object),
+                // Resource reads and writes, global and project-scoped. JSON/YAML
+                // bodies are capped here; uploads have their own, larger limit.
+                .merge(
+                    Router::new()
+                        .route(
+                            "/global/{kind}",
+                            get(api::v1::gitops::list_objects).post(api::v1::gitops::create_object),
+                        )
+                        .route(
+                            "/global/{kind}/{id}",
+                            get(api::v1::gitops::get_object)
+                                .post(api::v1::gitops::upsert_object)
+                                .put(api::v1::gitops::update_object)
+                                .patch(api::v1::gitops::patch_object)
+                                .delete(api::v1::gitops::delete_object),
+                        )
+                        .route(
+                            "/projects/{project}/{kind}",
+                            get(api::v1::scoped_gitops::list_scoped_objects)
+                                .post(api::v1::scoped_gitops::create_scoped_object),
+                        )
+                        .route(
+                            "/projects/{project}/{kind}/{id}",
+                            get(api::v1::scoped_gitops::get_scoped_object)
+                                .put(api::v1::scoped_gitops::update_scoped_object)
+                                .delete(api::v1::scoped_gitops::delete_scoped_object),
+                        )
+                        .layer(DefaultBodyLimit::max(body_limit))
+                        .layer(RequestBodyLimitLayer::new(body_limit))
+                        .layer(from_fn_with_state(
+                            shared_state.clone(),
+                            middleware::payload_too_large_middleware,
+                        )),
                 )
-                .route(
-                    "/projects/{project}/{kind}/{id}",
-                    get(api::v1::scoped_gitops::get_scoped_object)
-                        .put(api::v1::scoped_gitops::update_scoped_object)
-                        .delete(api::v1::scoped_gitops::delete_scoped_object),
+                .nest(
+                    "/adm",
+                    Router::new()
+                        .route("/audit", get(api::v1::adm::query_audit_log))
+                        .route("/reload-admins", post(api::v1::adm::reload_admins))
+                        .route("/rebuild_indexes", post(api::v1::adm::rebuild_indexes))
+                        // Handlers also take `RequireAdmin`; the group layer keeps
+                        // a route added without it from opening up.
+                        .layer(from_fn_with_state(
+                            shared_state.clone(),
+                            middleware::godmode_middleware,
+                        )),
+                )
+                .nest(
+                    "/ops",
+                    Router::new()
+                        .route("/kinds", get(api::v1::ops::list_kinds))
+                        .route("/count/{kind}", get(api::v1::ops::count_objects))
+                        .route("/list/{kind}", get(api::v1::ops::long_poll_objects))
+                        .route("/watch/{kind}", get(api::v1::ops::watch_objects))
+                        .route("/annotate/{kind}/{key}", patch(api::v1::ops::annotate_object))
+                        .route("/label/{kind}/{key}", patch(api::v1::ops::label_object))
+                        .route(
+                            "/projects/{project}/members",
+                            get(api::v1::ops::list_project_members)
+                                .post(api::v1::ops::set_project_member),
+                        )
+                        .route(
+                            "/projects/{project}/members/{principal}",
+                            delete(api::v1::ops::remove_project_member),
+                        )
+                        .route(
+                            "/groups/{group}/members",
+                            get(api::v1::ops::list_group_members)
+                                .post(api::v1::ops::add_group_member),
+                        )
+                        .route(
+                            "/groups/{group}/members/{principal}",
+                            delete(api::v1::ops::remove_group_member),
+                        )
+                        .merge(
+                            Router::new()
+                                .route(
+                                    "/reconcile/{kind}",
+                                    get(api::v1::ops::reconcile_status)
+                                        .post(api::v1::ops::trigger_reconcile),
+                                )
+                                .route("/describe/{kind}", get(api::v1::ops::describe_kind))
+                                .route("/indexes/{name}/rebuild", post(api::v1::ops::rebuild_index))
+                                .layer(from_fn_with_state(
+                                    shared_state.clone(),
+                                    middleware::godmode_middleware,
+                                )),
+                        )
+                        .layer(DefaultBodyLimit::max(body_limit))
+                        .layer(RequestBodyLimitLayer::new(body_limit))
+                        .layer(from_fn_with_state(
+                            shared_state.clone(),
+                            middleware::payload_too_large_middleware,
+                        )),
                 )
                 .nest(
                     "/debug",
@@ -108,17 +195,23 @@ pub fn create_app(shared_state: Arc<AppState>) -> IntoMakeService<Router> {
                     middleware::jwt_auth_middleware,
                 )),
         )
+        // Outside the JWT layer so unauthenticated writes are recorded too.
+        .layer(from_fn_with_state(
+            shared_state.clone(),
+            middleware::audit_middleware,
+        ))
+        // Outermost so register is refused too, before any auth work.
+        .layer(from_fn_with_state(
+            shared_state.clone(),
+            middleware::read_only_middleware,
+        ))
         .with_state(shared_state.clone())
-        .layer(TraceLayer::new_for_http())
-        .layer(
-            CorsLayer::new()
-                .allow_origin(Any)
-                .allow_methods(Any)
-                .allow_headers(Any),
-        );
+        .layer(TraceLayer::new_for_http().make_span_with(middleware::request_span))
+        .layer(server::cors_layer(shared_state.config.cors.as_ref()));
     let (router, api) = OpenApiRouter::with_openapi(ApiDoc::openapi())
         .nest("/api", mainrt.into())
         .route("/health", get(health_check))
+        .route("/ready", get(readiness_check).with_state(shared_state.clone()))
         .split_for_parts();
     let router = router.merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", api));
 
@@ -127,9 +220,11 @@ pub fn create_app(shared_state: Arc<AppState>) -> IntoMakeService<Router> {
 
 pub async fn create_mock_shared_state() -> Result<AppState, Box<dyn std::error::Error>> {
     let config = config::AppConfig::from_env()?;
-    let auth = Auth::new(config.jwt_secret.as_bytes(), config.jwt_expiry_days);
-    let db = ArangoDb::connect_basic(&config.database_connection_string, &config.database_user, &config.database_password, &config.database_name).await?;
-    let cache = cache::create_default_cache().await;
+    let auth = Auth::from_config(&config);
+    let db = ArangoDb::connect_basic(&config.database_connection_string, &config.database_user, &config.database_password, &config.database_name)
+        .await?
+        .with_membership_max_depth(config.membership_max_depth);
+    let cache = cache::create_default_cache(config.user_cache_ttl()).await;
     Ok(AppState::new(
         config,
         auth,
@@ -140,6 +235,46 @@ pub async fn create_mock_shared_state() -> Result<AppState, Box<dyn std::error::
     ))
 }
 
+/// Seed the default admin from `CREATE_DEFAULT_ADMIN` / `DEFAULT_ADMIN_USER` /
+/// `DEFAULT_ADMIN_PASSWORD`. An existing user of that name is left as it is;
+/// either way the account is granted every super-permission. Returns the id
+/// of the user it created, if any.
+pub async fn create_default_user(
+    db: &Arc<ArangoDb>,
+    auth: &Auth,
+    admin: Option<&config::DefaultAdmin>,
+) -> anyhow::Result<Option<String>> {
+    let Some(admin) = admin else {
+        info!("CREATE_DEFAULT_ADMIN is off: no default admin account is seeded");
+        return Ok(None);
+    };
+    let user_id = crit_shared::util_models::PrincipalId::user(&admin.username).into_string();
+    let created = if db.get_user_by_id(&user_id).await?.is_none() {
+        use crate::controllers::gitops_controller::inject_create_defaults;
+        if admin.password == config::FALLBACK_ADMIN_PASSWORD {
+            log::warn!("!!! Creating default admin '{}' with the built-in password '{}'. Set DEFAULT_ADMIN_PASSWORD, or CREATE_DEFAULT_ADMIN=false once a real admin exists !!!", admin.username, config::FALLBACK_ADMIN_PASSWORD);
+        }
+        let mut body = serde_json::json!({
+            "id": &user_id,
+            "password": &admin.password,
+        });
+        inject_create_defaults(&mut body, &user_id);
+        let ctrl = controllers::Controller::new(db.clone());
+        let doc = ctrl.for_kind("users").to_internal(body, auth)?;
+        db.generic_create("users", doc).await?;
+        info!("Default admin account created (username: {})", admin.username);
+        Some(user_id.clone())
+    } else {
+        None
+    };
+
+    // Idempotent — safe to call on every startup.
+    for perm in crit_shared::util_models::super_permissions::ALL {
+        db.grant_permission(perm, &user_id).await?;
+    }
+    Ok(created)
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Initialize tracing
@@ -149,42 +284,49 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
 
     info!("Starting application with config:");
-    info!("  Host: {}", config.host);
-    info!("  Port: {}", config.port);
+    info!("  Bind address: {}", config.bind_addr);
     info!(
         "  Database connection: {}",
         config.database_connection_string
     );
     info!("  Database name: {}", config.database_name);
     info!("  Client API keys: {:?}", config.client_api_keys);
+    if config.read_only {
+        log::warn!("  READ_ONLY is set: all mutating requests will be refused with 503");
+    }
 
-    let db = ArangoDb::connect_basic(&config.database_connection_string, &config.database_user, &config.database_password, &config.database_name).await?;
+    #[cfg(feature = "otel")]
+    let otel = {
+        let otel = telemetry::OtelConfig::from_env();
+        info!("  OTLP traces: {} (service {})", otel.endpoint, otel.service_name);
+        telemetry::init(&otel)?
+    };
 
-    // Seed root account if it doesn't exist
-    let auth = Auth::new(config.jwt_secret.as_bytes(), config.jwt_expiry_days);
-    let db = Arc::new(db);
-    if db.get_user_by_id("u_root").await?.is_none() {
-        use crate::controllers::gitops_controller::inject_create_defaults;
-        let mut body = serde_json::json!({
-            "id": "u_root",
-            "password": &config.root_password,
-        });
-        inject_create_defaults(&mut body, "u_root");
-        let ctrl = controllers::Controller::new(db.clone());
-        let doc = ctrl.for_kind("users").to_internal(body, &auth)?;
-        db.generic_create("users", doc).await?;
-        info!("Root account created (username: root)");
-    }
+    // Load TLS material before anything else so a bad cert fails fast.
+    let tls = match &config.tls {
+        Some(tls) => {
+            info!("  TLS: cert {}, key {}", tls.cert_path.display(), tls.key_path.display());
+            Some(server::load_tls(tls).await?)
+        }
+        None => None,
+    };
 
-    // Ensure root has ADM_GODMODE (idempotent — safe to call on every startup)
-    db.grant_permission(
-        crit_shared::util_models::super_permissions::ADM_GODMODE,
-        "u_root",
+    let db = ArangoDb::connect_basic_with_retry(
+        &config.database_connection_string,
+        &config.database_user,
+        &config.database_password,
+        &config.database_name,
+        config.db_connect_retry(),
     )
-    .await?;
+    .await?
+    .with_membership_max_depth(config.membership_max_depth);
+
+    let auth = Auth::from_config(&config);
+    let db = Arc::new(db);
+    create_default_user(&db, &auth, config.default_admin.as_ref()).await?;
 
     // Create app state
-    let cache = cache::create_default_cache().await;
+    let cache = cache::create_default_cache(config.user_cache_ttl()).await;
     let objectstore = services::objectstore::ObjectStoreService::try_from_config(&config);
     let app_state = AppState::new(
         config.clone(),
@@ -195,15 +337,30 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         objectstore,
     );
     let shared_state = Arc::new(app_state);
+    shared_state.indexes.rebuild_all(shared_state.db.as_ref()).await;
+
+    if config.reconcile_interval_secs > 0 {
+        info!("  Reconcile interval: {}s", config.reconcile_interval_secs);
+        shared_state.reconciler.clone().spawn_periodic(
+            shared_state.controller.clone(),
+            shared_state.db.clone(),
+            std::time::Duration::from_secs(config.reconcile_interval_secs),
+        );
+    }
 
     // Build the application router
     let app = create_app(shared_state);
 
     // Start the server
-    let bind_address = format!("{}:{}", config.host, config.port);
-    let listener = TcpListener::bind(&bind_address).await?;
-    info!("Server starting on http://{}", bind_address);
-    axum::serve(listener, app).await?;
+    let listener = std::net::TcpListener::bind(config.bind_addr)?;
+    let scheme = if tls.is_some() { "https" } else { "http" };
+    info!("Server starting on {}://{}", scheme, listener.local_addr()?);
+    server::serve(listener, app, tls, server::shutdown_signal()).await?;
+    info!("Server stopped");
+    #[cfg(feature = "otel")]
+    if let Err(e) = otel.shutdown() {
+        log::warn!("Flushing OTLP spans failed: {}", e);
+    }
 
     Ok(())
 }
@@ -215,3 +372,28 @@ async fn health_check() -> Json<Value> {
         "timestamp": chrono::Utc::now()
     }))
 }
+
+/// Readiness probe: healthy only when the database answers a trivial query.
+async fn readiness_check(
+    axum::extract::State(state): axum::extract::State<Arc<AppState>>,
+) -> (axum::http::StatusCode, Json<Value>) {
+    match state.db.health().await {
+        Ok(health) => (
+            axum::http::StatusCode::OK,
+            Json(json!({
+                "status": "ready",
+                "database": { "latency_ms": health.latency_ms },
+            })),
+        ),
+        Err(e) => {
+            log::warn!("Readiness check failed: {}", e);
+            (
+                axum::http::StatusCode::SERVICE_UNAVAILABLE,
+                Json(json!({
+                    "status": "unavailable",
+                    "database": { "error": e.to_string() },
+                })),
+            )
+        }
+    }
+}
@@ -1,13 +1,38 @@
 // src/auth/mod.rs
-use bcrypt::{DEFAULT_COST, hash, verify};
+use argon2::{Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version};
+use argon2::password_hash::SaltString;
 use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
+use rand::RngCore;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::error::AppError;
+use crate::{config::AppConfig, error::AppError};
 
 pub struct AuthenticatedUser(pub String);
 
+/// Header an admin sends to act as another user (`X-Crit-Impersonate: u_alice`).
+pub const IMPERSONATE_HEADER: &str = "x-crit-impersonate";
+
+/// Who a request runs as and who actually sent it. The two differ only when
+/// an admin impersonates a user via [`IMPERSONATE_HEADER`]; `effective` is
+/// also the request's `String` user-id extension.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestActor {
+    pub effective: String,
+    pub real: String,
+}
+
+impl RequestActor {
+    pub fn is_impersonated(&self) -> bool {
+        self.effective != self.real
+    }
+}
+
+/// Stored as `password_hash` for accounts that sign in only through OIDC;
+/// [`Auth::verify_password`] never matches it.
+pub const NO_PASSWORD: &str = "!";
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,
@@ -20,6 +45,20 @@ pub struct Auth {
     encoding_key: EncodingKey,
     decoding_key: DecodingKey,
     expiry_seconds: usize,
+    leeway_secs: u64,
+    refresh_ttl_seconds: usize,
+    argon2_params: Params,
+}
+
+/// Access token plus the opaque refresh token that can renew it.
+#[derive(Debug)]
+pub struct TokenPair {
+    pub access_token: String,
+    /// Unix seconds.
+    pub access_expires_at: usize,
+    pub refresh_token: String,
+    /// Unix seconds.
+    pub refresh_expires_at: usize,
 }
 
 impl std::fmt::Debug for Auth {
@@ -40,19 +79,95 @@ impl Auth {
             encoding_key,
             decoding_key,
             expiry_seconds: (expiry_days * 86400) as usize,
+            leeway_secs: 60,
+            refresh_ttl_seconds: (expiry_days * 86400) as usize,
+            argon2_params: Params::default(),
+        }
+    }
+
+    /// Lifetimes and clock-skew leeway from `JWT_TTL_SECONDS`, `JWT_LEEWAY_SECS`
+    /// and `REFRESH_TOKEN_TTL_SECONDS`.
+    pub fn from_config(config: &AppConfig) -> Self {
+        Auth {
+            expiry_seconds: config.jwt_ttl_seconds as usize,
+            leeway_secs: config.jwt_leeway_secs,
+            refresh_ttl_seconds: config.refresh_token_ttl_seconds as usize,
+            argon2_params: config.argon2_params.clone(),
+            ..Auth::new(config.jwt_secret.as_bytes(), 0)
         }
     }
 
-    /// Hashes a plain text password using bcrypt.
+    fn argon2(&self) -> Argon2<'static> {
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, self.argon2_params.clone())
+    }
+
+    /// Hashes a plain text password with Argon2id, in PHC string format
+    /// (`$argon2id$v=19$m=...,t=...,p=...$salt$hash`).
     pub fn hash_password(&self, password: &str) -> Result<String, AppError> {
-        // bcrypt::hash is a synchronous operation
-        hash(password, DEFAULT_COST).map_err(AppError::BcryptError)
+        let mut salt = [0u8; 16];
+        rand::rng().fill_bytes(&mut salt);
+        let salt = SaltString::encode_b64(&salt)
+            .map_err(|e| AppError::Internal(anyhow::anyhow!("password salt: {}", e)))?;
+        self.argon2()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|e| AppError::Internal(anyhow::anyhow!("password hashing: {}", e)))
     }
 
-    /// Verifies a plain text password against a bcrypt hash.
+    /// Verifies a plain text password against a stored hash. The algorithm is
+    /// taken from the hash prefix: Argon2id (`$argon2id$`) or, for hashes
+    /// written before the switch, bcrypt (`$2a$`/`$2b$`/`$2y$`). A malformed or
+    /// unknown hash never matches; it is logged rather than surfaced as a 500.
     pub fn verify_password(&self, password: &str, hash: &str) -> Result<bool, AppError> {
-        // bcrypt::verify is a synchronous operation
-        verify(password, hash).map_err(AppError::BcryptError)
+        if hash == NO_PASSWORD {
+            return Ok(false);
+        }
+        if hash.starts_with("$argon2id$") {
+            let parsed = match PasswordHash::new(hash) {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    log::warn!("Malformed argon2id password hash: {}", e);
+                    return Ok(false);
+                }
+            };
+            return match self.argon2().verify_password(password.as_bytes(), &parsed) {
+                Ok(()) => Ok(true),
+                Err(argon2::password_hash::Error::Password) => Ok(false),
+                Err(e) => {
+                    log::warn!("Unverifiable argon2id password hash: {}", e);
+                    Ok(false)
+                }
+            };
+        }
+        if is_bcrypt_hash(hash) {
+            // bcrypt is kept for verification only; see `needs_rehash`.
+            return bcrypt::verify(password, hash).or_else(|e| {
+                log::warn!("Malformed bcrypt password hash: {}", e);
+                Ok(false)
+            });
+        }
+        log::warn!("Password hash has an unknown format");
+        Ok(false)
+    }
+
+    /// Whether a hash that just verified should be replaced by a fresh
+    /// [`Auth::hash_password`]: it is not Argon2id, or it was made with other
+    /// cost parameters than the configured ones.
+    pub fn needs_rehash(&self, hash: &str) -> bool {
+        let Ok(parsed) = PasswordHash::new(hash) else {
+            return true;
+        };
+        if parsed.algorithm != Algorithm::Argon2id.ident() || parsed.version != Some(Version::V0x13.into()) {
+            return true;
+        }
+        match Params::try_from(&parsed) {
+            Ok(params) => {
+                params.m_cost() != self.argon2_params.m_cost()
+                    || params.t_cost() != self.argon2_params.t_cost()
+                    || params.p_cost() != self.argon2_params.p_cost()
+            }
+            Err(_) => true,
+        }
     }
 
     /// Creates a new JWT token for the given user ID.
@@ -74,11 +189,96 @@ impl Auth {
             .map_err(AppError::Jwt)
     }
 
+    /// A fresh access token and a random refresh token for `user_id`. Only
+    /// the refresh token's [`Auth::refresh_token_hash`] should be stored.
+    pub fn create_token_pair(&self, user_id: &str) -> Result<TokenPair, AppError> {
+        let (access_token, access_expires_at) = self.create_token(user_id)?;
+        let mut bytes = [0u8; 32];
+        rand::rng().fill_bytes(&mut bytes);
+        let refresh_token = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        Ok(TokenPair {
+            access_token,
+            access_expires_at,
+            refresh_token,
+            refresh_expires_at: access_expires_at + self.refresh_ttl_seconds - self.expiry_seconds,
+        })
+    }
+
+    /// Storage key for a refresh token: hex SHA-256, so a database leak does
+    /// not hand out usable tokens.
+    pub fn refresh_token_hash(token: &str) -> String {
+        Sha256::digest(token.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
     /// Decodes and validates a JWT token, returning the claims if valid.
+    /// Expiry is checked with `JWT_LEEWAY_SECS` of tolerance for clock skew.
     pub fn decode_token(&self, token: &str) -> Result<Claims, AppError> {
+        let mut validation = Validation::default();
+        validation.leeway = self.leeway_secs;
         // Decode the token and validate it (signature, expiration)
-        decode::<Claims>(token, &self.decoding_key, &Validation::default())
+        decode::<Claims>(token, &self.decoding_key, &validation)
             .map(|data| data.claims) // Extract the claims from the token data
             .map_err(AppError::Jwt) // Convert jsonwebtoken error to AppError
     }
 }
+
+fn is_bcrypt_hash(hash: &str) -> bool {
+    ["$2a$", "$2b$", "$2y$"].iter().any(|prefix| hash.starts_with(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cheap parameters so the tests stay fast.
+    fn auth() -> Auth {
+        Auth { argon2_params: Params::new(1024, 1, 1, None).unwrap(), ..Auth::new(b"secret", 1) }
+    }
+
+    #[test]
+    fn new_hashes_are_argon2id() {
+        let auth = auth();
+        let hash = auth.hash_password("hunter2").unwrap();
+        assert!(hash.starts_with("$argon2id$v=19$m=1024,t=1,p=1$"), "{}", hash);
+        assert!(auth.verify_password("hunter2", &hash).unwrap());
+        assert!(!auth.verify_password("hunter3", &hash).unwrap());
+        assert!(!auth.needs_rehash(&hash));
+    }
+
+    #[test]
+    fn bcrypt_hashes_still_verify_and_need_rehash() {
+        let auth = auth();
+        let two_b = bcrypt::hash("hunter2", 4).unwrap();
+        let two_a = bcrypt::hash_with_result("hunter2", 4).unwrap().format_for_version(bcrypt::Version::TwoA);
+        for hash in [two_b, two_a] {
+            assert!(auth.verify_password("hunter2", &hash).unwrap(), "{}", hash);
+            assert!(!auth.verify_password("hunter3", &hash).unwrap());
+            assert!(auth.needs_rehash(&hash));
+        }
+    }
+
+    #[test]
+    fn argon2id_with_other_cost_needs_rehash() {
+        let old = Auth { argon2_params: Params::new(2048, 1, 1, None).unwrap(), ..auth() };
+        let hash = old.hash_password("hunter2").unwrap();
+        assert!(auth().verify_password("hunter2", &hash).unwrap());
+        assert!(auth().needs_rehash(&hash));
+    }
+
+    #[test]
+    fn malformed_hashes_never_match() {
+        let auth = auth();
+        let malformed = [
+            "",
+            "hunter2",
+            "$2b$04$short",
+            "$argon2id$v=19$garbage",
+            "$argon2i$v=19$m=1024,t=1,p=1$c2FsdHNhbHQ$aGFzaA",
+            "$md5$abc",
+        ];
+        for hash in malformed {
+            assert!(!auth.verify_password("hunter2", hash).unwrap(), "{}", hash);
+            assert!(auth.needs_rehash(hash), "{}", hash);
+        }
+    }
+}
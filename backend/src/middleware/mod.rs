@@ -1,16 +1,24 @@
+use std::marker::PhantomData;
 use std::sync::Arc;
 
 use axum::{
     body::Body,
-    extract::{FromRequestParts, Request, State},
-    http::request::Parts,
+    extract::{FromRequestParts, OriginalUri, Request, State},
+    http::{HeaderValue, Method, StatusCode, request::Parts},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
 };
 
 pub mod auth;
 
-use crate::{error::AppError, middleware::auth::AuthenticatedUser, state::AppState};
+use crate::util_models::{PrincipalId, PrincipalKind, super_permissions};
+
+use crate::{
+    audit_log::{AuditEntry, resource_from_path},
+    error::AppError,
+    middleware::auth::{AuthenticatedUser, IMPERSONATE_HEADER, RequestActor},
+    state::AppState,
+};
 
 impl<S> FromRequestParts<S> for AuthenticatedUser
 where
@@ -31,6 +39,81 @@ where
     }
 }
 
+/// Handler argument that only admins (`ADM_GODMODE`) get past; anyone else
+/// is refused with 403 before the handler runs. Holds the caller's user id.
+pub struct RequireAdmin(pub String);
+
+impl FromRequestParts<Arc<AppState>> for RequireAdmin {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<AppState>) -> Result<Self, Self::Rejection> {
+        let AuthenticatedUser(user_id) = AuthenticatedUser::from_request_parts(parts, state).await?;
+        if state.has_godmode(&user_id).await.map_err(AppError::Internal)? {
+            Ok(RequireAdmin(user_id))
+        } else {
+            log::debug!("[REQUIRE] admin refused for {}", user_id);
+            Err(AppError::forbidden("admin (godmode) required"))
+        }
+    }
+}
+
+/// A super-permission named at the type level, for [`RequirePermission`].
+pub trait SuperPermission {
+    const NAME: &'static str;
+}
+
+/// Marker types for each entry of `super_permissions`.
+pub mod permission {
+    use super::{SuperPermission, super_permissions};
+
+    pub struct UserManager;
+    pub struct ConfigEditor;
+    pub struct CreateGroups;
+    pub struct CreateProjects;
+
+    impl SuperPermission for UserManager {
+        const NAME: &'static str = super_permissions::ADM_USER_MANAGER;
+    }
+    impl SuperPermission for ConfigEditor {
+        const NAME: &'static str = super_permissions::ADM_CONFIG_EDITOR;
+    }
+    impl SuperPermission for CreateGroups {
+        const NAME: &'static str = super_permissions::USR_CREATE_GROUPS;
+    }
+    impl SuperPermission for CreateProjects {
+        const NAME: &'static str = super_permissions::USR_CREATE_PROJECTS;
+    }
+}
+
+/// Handler argument requiring the super-permission `P`, held directly or
+/// through a group; godmode passes too. Refused with 403 otherwise:
+///
+/// ```ignore
+/// async fn edit_config(RequirePermission { user_id, .. }: RequirePermission<permission::ConfigEditor>) { ... }
+/// ```
+pub struct RequirePermission<P> {
+    pub user_id: String,
+    _permission: PhantomData<fn() -> P>,
+}
+
+impl<P: SuperPermission> FromRequestParts<Arc<AppState>> for RequirePermission<P> {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<AppState>) -> Result<Self, Self::Rejection> {
+        let AuthenticatedUser(user_id) = AuthenticatedUser::from_request_parts(parts, state).await?;
+        let allowed = state.has_godmode(&user_id).await.map_err(AppError::Internal)? || {
+            let principals = state.get_cached_principals(&user_id).await?;
+            state.db.has_permission_with_principals(&principals, P::NAME).await?
+        };
+        if allowed {
+            Ok(RequirePermission { user_id, _permission: PhantomData })
+        } else {
+            log::debug!("[REQUIRE] {} refused for {}", P::NAME, user_id);
+            Err(AppError::forbidden(format!("{} required", P::NAME)))
+        }
+    }
+}
+
 pub async fn jwt_auth_middleware(
     State(app_state): State<Arc<AppState>>,
     req: Request<Body>,
@@ -77,10 +160,16 @@ pub async fn jwt_auth_middleware(
 
     match app_state.auth.decode_token(&token) {
         Ok(claims) => {
-            if app_state.controller.user.validate_user(&claims.sub).await {
-                __parts__.extensions.insert(claims.sub);
+            if app_state.is_active_user(&claims.sub).await {
+                let actor = resolve_actor(&app_state, &__parts__, claims.sub).await?;
+                tracing::Span::current().record("principal", actor.effective.as_str());
+                __parts__.extensions.insert(actor.effective.clone());
+                __parts__.extensions.insert(actor.clone());
                 let req = Request::from_parts(__parts__, body);
-                Ok(next.run(req).await)
+                let mut response = next.run(req).await;
+                // Handed back for `audit_middleware`, which runs outside this layer.
+                response.extensions_mut().insert(actor);
+                Ok(response)
             } else {
                 log::warn!("User invalid: {}", &claims.sub);
                 Err(AppError::Authorization("Unauthorized".to_string()))
@@ -93,7 +182,43 @@ pub async fn jwt_auth_middleware(
     }
 }
 
-/// Middleware that allows only users with ADM_GODMODE through.
+/// Apply `X-Crit-Impersonate`: only godmode admins may send it (403 otherwise),
+/// it must name a user, bare or `u_`-prefixed (400 for other principals), and
+/// the user must be active (404 otherwise). Without the header the request
+/// runs as the token's subject.
+async fn resolve_actor(app_state: &AppState, parts: &Parts, real: String) -> Result<RequestActor, AppError> {
+    let Some(target) = parts.headers.get(IMPERSONATE_HEADER) else {
+        return Ok(RequestActor { effective: real.clone(), real });
+    };
+    let target = target
+        .to_str()
+        .map_err(|_| AppError::BadRequest(format!("invalid {} header", IMPERSONATE_HEADER)))?
+        .trim();
+
+    if !app_state.has_godmode(&real).await.map_err(AppError::Internal)? {
+        log::warn!("[IMPERSONATE] {} is not allowed to act as {}", real, target);
+        return Err(AppError::Forbidden("impersonation requires admin".to_string()));
+    }
+    // A bare name has no kind prefix and is taken as a username.
+    let effective = match target.parse::<PrincipalId>() {
+        Ok(id) if id.is_user() => id,
+        Ok(id) => {
+            return Err(AppError::BadRequest(format!("{} must name a user, not {}", IMPERSONATE_HEADER, id)));
+        }
+        Err(_) => PrincipalId::parse(PrincipalKind::User, target),
+    };
+    if effective.bare().is_empty() {
+        return Err(AppError::BadRequest(format!("{} must name a user", IMPERSONATE_HEADER)));
+    }
+    let effective = effective.into_string();
+    if !app_state.is_active_user(&effective).await {
+        return Err(AppError::NotFound(format!("user {}", effective)));
+    }
+    log::info!("[IMPERSONATE] {} acting as {}", real, effective);
+    Ok(RequestActor { effective, real })
+}
+
+/// Middleware that allows only users with ADM_GODMODE through; others get 403.
 /// Must be placed after `jwt_auth_middleware` so that the user identity
 /// is already in request extensions.
 pub async fn godmode_middleware(
@@ -114,12 +239,183 @@ pub async fn godmode_middleware(
         }
         Ok(false) => {
             log::debug!("[GODMODE MIDDLEWARE] access denied for {}", user_id);
-            Err(AppError::Authorization("Forbidden: godmode required".to_string()))
+            Err(AppError::forbidden("admin (godmode) required"))
         }
         Err(e) => Err(AppError::Internal(e)),
     }
 }
 
+/// Response extension set by handlers whose mutating request turned out to
+/// change nothing (e.g. an upsert of an identical document); `audit_middleware`
+/// does not record such requests.
+#[derive(Debug, Clone, Copy)]
+pub struct NoChange;
+
+/// Response extension set by resource write handlers: what the write did and
+/// the stored `hash_code` around it, copied into the audit entry.
+#[derive(Debug, Clone, Default)]
+pub struct AuditChange {
+    pub action: &'static str,
+    pub before_hash: Option<String>,
+    pub after_hash: Option<String>,
+    /// `(kind, key)` written, for routes whose path does not name it.
+    pub resource: Option<(String, String)>,
+}
+
+impl AuditChange {
+    pub fn new(action: &'static str, before: Option<&str>, after: Option<&str>) -> Self {
+        Self {
+            action,
+            before_hash: before.map(str::to_string),
+            after_hash: after.map(str::to_string),
+            resource: None,
+        }
+    }
+
+    /// Name the written resource instead of taking it from the request path.
+    pub fn on(mut self, kind: &str, key: &str) -> Self {
+        self.resource = Some((kind.to_string(), key.to_string()));
+        self
+    }
+
+    /// Attach to `response` for `audit_middleware` to pick up.
+    pub fn attach(self, response: impl IntoResponse) -> Response {
+        let mut response = response.into_response();
+        response.extensions_mut().insert(self);
+        response
+    }
+}
+
+/// Correlates a mutating request with its audit entry.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// The span `TraceLayer` opens per request: its default fields plus
+/// `request_id` (from `X-Request-Id`, or recorded by `audit_middleware` when it
+/// generates one) and `principal` (recorded by `jwt_auth_middleware`).
+pub fn request_span(req: &Request<Body>) -> tracing::Span {
+    let request_id = req.headers().get(REQUEST_ID_HEADER).and_then(|v| v.to_str().ok());
+    tracing::debug_span!(
+        "request",
+        method = %req.method(),
+        uri = %req.uri(),
+        version = ?req.version(),
+        request_id,
+        principal = tracing::field::Empty,
+    )
+}
+
+/// Middleware that records every mutating request (POST/PUT/PATCH/DELETE) in the
+/// audit log, except those answered with [`NoChange`]. It runs outside
+/// `jwt_auth_middleware`, so register, login, refresh and requests refused for
+/// want of a token are recorded too, with no actor; the actor of an
+/// authenticated request comes back on the response as its [`RequestActor`].
+/// GET/HEAD/OPTIONS requests pass through unrecorded unless the handler wrote
+/// anyway and says so with an [`AuditChange`], as the OIDC callback does when
+/// it provisions a user. The request id is the client's `X-Request-Id` or a
+/// fresh ULID, and is returned in that header on recorded requests.
+pub async fn audit_middleware(
+    State(app_state): State<Arc<AppState>>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let method = req.method().clone();
+    let mutating = matches!(method, Method::POST | Method::PUT | Method::PATCH | Method::DELETE);
+
+    // Nested routers see a stripped URI; prefer the original full path.
+    let path = req
+        .extensions()
+        .get::<OriginalUri>()
+        .map(|u| u.0.path().to_string())
+        .unwrap_or_else(|| req.uri().path().to_string());
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| ulid::Ulid::new().to_string());
+    if mutating {
+        tracing::Span::current().record("request_id", request_id.as_str());
+    }
+
+    let started = std::time::Instant::now();
+    let mut response = next.run(req).await;
+    let change = response.extensions().get::<AuditChange>().cloned();
+    if !mutating && change.is_none() {
+        return response;
+    }
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    if response.extensions().get::<NoChange>().is_some() {
+        return response;
+    }
+
+    let change = change.unwrap_or_default();
+    let actor = response.extensions().get::<RequestActor>();
+    let real_actor = actor.filter(|a| a.is_impersonated()).map(|a| a.real.clone());
+    let actor = actor.map(|a| a.effective.clone());
+    let (kind, key) = match change.resource {
+        Some((kind, key)) => (Some(kind), Some(key)),
+        None => resource_from_path(&path),
+    };
+    app_state
+        .audit
+        .record(AuditEntry {
+            timestamp: chrono::Utc::now(),
+            actor,
+            real_actor,
+            method: method.to_string(),
+            path,
+            status: response.status().as_u16(),
+            latency_ms: started.elapsed().as_millis() as u64,
+            kind,
+            key,
+            request_id: Some(request_id),
+            action: (!change.action.is_empty()).then(|| change.action.to_string()),
+            before_hash: change.before_hash,
+            after_hash: change.after_hash,
+            sink_error: None,
+            audit: true,
+        })
+        .await;
+
+    response
+}
+
+/// Middleware that refuses every mutating request (POST/PUT/PATCH/DELETE) with
+/// 503 while the server is in read-only maintenance mode. Login, logout and
+/// refresh stay open so reads can still be authenticated.
+pub async fn read_only_middleware(
+    State(app_state): State<Arc<AppState>>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<Response, AppError> {
+    let mutating = matches!(*req.method(), Method::POST | Method::PUT | Method::PATCH | Method::DELETE);
+    let path = req.uri().path();
+    let session = path.ends_with("/v1/login") || path.ends_with("/v1/logout") || path.ends_with("/v1/refresh");
+    if mutating && !session && app_state.is_read_only() {
+        log::debug!("[READ-ONLY] refused {} {}", req.method(), path);
+        return Err(AppError::ReadOnly);
+    }
+    Ok(next.run(req).await)
+}
+
+/// Turns the plain-text 413 from `RequestBodyLimitLayer` / `DefaultBodyLimit`
+/// into a JSON `payload_too_large` error that states the limit. Place it
+/// outside the limit layers of a route group.
+pub async fn payload_too_large_middleware(
+    State(app_state): State<Arc<AppState>>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let response = next.run(req).await;
+    if response.status() != StatusCode::PAYLOAD_TOO_LARGE {
+        return response;
+    }
+    AppError::PayloadTooLarge { limit: app_state.config.max_body_bytes }.into_response()
+}
+
 pub async fn apikey_auth_middleware_user(
     State(app_state): State<Arc<AppState>>,
     req: Request<Body>,
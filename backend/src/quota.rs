@@ -0,0 +1,93 @@
+//! Per-user resource quotas.
+//!
+//! Limits come from the server-wide defaults in `AppConfig`, overridden per
+//! user by a document in the `quotas` kind keyed by the user id. Usage is read
+//! from the secondary indexes, so checks never scan a collection. Creates are
+//! refused once the user is at the limit: owning exactly `limit` is fine, the
+//! create that would make it `limit + 1` gets 422 `quota_exceeded`.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::AppError;
+use crate::state::AppState;
+
+pub const OWNED_PROJECTS: &str = "owned_projects";
+
+/// One quota as the user stands against it; `limit: None` is unlimited.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct QuotaUsage {
+    pub quota: &'static str,
+    pub current: u64,
+    pub limit: Option<u64>,
+}
+
+impl QuotaUsage {
+    /// Whether one more resource would go past the limit.
+    pub fn is_full(&self) -> bool {
+        self.limit.is_some_and(|limit| self.current >= limit)
+    }
+}
+
+/// The override document's `field` when it sets one (`null` lifting the
+/// limit), else `default`.
+fn resolve_limit(overrides: Option<&Value>, field: &str, default: Option<u64>) -> Option<u64> {
+    match overrides.and_then(|doc| doc.get(field)) {
+        Some(value) => value.as_u64(),
+        None => default,
+    }
+}
+
+/// Every quota of `user_id` with its current usage, for `GET /v1/personal/quota`.
+pub async fn usage(state: &AppState, user_id: &str) -> Result<Vec<QuotaUsage>, AppError> {
+    let overrides = state.db.generic_get("quotas", user_id).await?;
+    let owned = state.indexes.query_index("projects_by_owner", user_id).await;
+    Ok(vec![QuotaUsage {
+        quota: OWNED_PROJECTS,
+        current: owned.len() as u64,
+        limit: resolve_limit(overrides.as_ref(), "max_owned_projects", state.config.quota_max_owned_projects),
+    }])
+}
+
+/// Refuse a create of `kind` that would take `user_id` past a quota. Kinds
+/// without a quota always pass; callers skip this for admins.
+pub async fn check_create(state: &AppState, kind: &str, user_id: &str) -> Result<(), AppError> {
+    if kind != "projects" {
+        return Ok(());
+    }
+    let usage = usage(state, user_id).await?;
+    match usage.into_iter().find(|u| u.quota == OWNED_PROJECTS) {
+        Some(u) if u.is_full() => Err(AppError::QuotaExceeded {
+            quota: u.quota,
+            current: u.current,
+            limit: u.limit.unwrap_or_default(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn overrides_win_over_the_default() {
+        assert_eq!(resolve_limit(None, "max_owned_projects", Some(3)), Some(3));
+        let doc = json!({ "_key": "u_alice", "max_owned_projects": 10 });
+        assert_eq!(resolve_limit(Some(&doc), "max_owned_projects", Some(3)), Some(10));
+        let unlimited = json!({ "_key": "u_alice", "max_owned_projects": null });
+        assert_eq!(resolve_limit(Some(&unlimited), "max_owned_projects", Some(3)), None);
+        let silent = json!({ "_key": "u_alice" });
+        assert_eq!(resolve_limit(Some(&silent), "max_owned_projects", Some(3)), Some(3));
+    }
+
+    #[test]
+    fn full_at_the_limit() {
+        let usage = |current, limit| QuotaUsage { quota: OWNED_PROJECTS, current, limit };
+        assert!(!usage(2, Some(3)).is_full());
+        assert!(usage(3, Some(3)).is_full());
+        assert!(usage(0, Some(0)).is_full());
+        assert!(!usage(1000, None).is_full());
+    }
+}
@@ -0,0 +1,176 @@
+//! Drift detection and repair for gitops resources.
+//!
+//! Every stored document carries a `hash_code` — the hash of its desired state,
+//! computed when it was last written through the API. The `Reconciler` walks all
+//! documents of a kind, asks the kind's `KindController::observe` for the hash of
+//! the state actually in effect, and calls `KindController::reconcile` for every
+//! document where the two disagree. Kinds whose `observe` returns `None` have no
+//! separately observable state and are skipped.
+//!
+//! Runs on demand via `POST /v1/ops/reconcile/{kind}` and, when
+//! `RECONCILE_INTERVAL_SECS` is non-zero, periodically for all registered kinds.
+//! While the server is in read-only mode drift is still detected, but repairs
+//! are deferred to the first pass after it leaves.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::RwLock;
+
+use crate::controllers::{Controller, gitops_controller::KindController};
+use crate::db::ArangoDb;
+use crate::error::AppError;
+
+/// A document the last pass failed to reconcile.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ReconcileFailure {
+    pub id: String,
+    pub error: String,
+    /// Consecutive failed attempts for this document, across passes.
+    pub retry_count: u32,
+}
+
+/// Outcome of the most recent reconcile pass for one kind.
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct ReconcileStatus {
+    pub kind: String,
+    pub last_run: Option<DateTime<Utc>>,
+    pub checked: usize,
+    pub in_sync: usize,
+    /// Documents whose controller has no observable state.
+    pub skipped: usize,
+    pub reconciled: Vec<String>,
+    /// Drifted documents left alone because the server was read-only.
+    pub deferred: Vec<String>,
+    pub failed: Vec<ReconcileFailure>,
+}
+
+pub struct Reconciler {
+    status: RwLock<HashMap<String, ReconcileStatus>>,
+    /// Consecutive failures per `(kind, id)`; cleared once the document reconciles.
+    retries: RwLock<HashMap<(String, String), u32>>,
+    /// The server's read-only switch; no repairs are written while it is set.
+    read_only: Arc<AtomicBool>,
+}
+
+impl Reconciler {
+    pub fn new() -> Self {
+        Self::with_read_only(Arc::new(AtomicBool::new(false)))
+    }
+
+    /// A reconciler that pauses repairs whenever `read_only` is set.
+    pub fn with_read_only(read_only: Arc<AtomicBool>) -> Self {
+        Self {
+            status: RwLock::new(HashMap::new()),
+            retries: RwLock::new(HashMap::new()),
+            read_only,
+        }
+    }
+
+    /// Status of the last pass for `kind`, if one has run.
+    pub async fn status(&self, kind: &str) -> Option<ReconcileStatus> {
+        self.status.read().await.get(kind).cloned()
+    }
+
+    /// Run one reconcile pass over every live document of `kind`.
+    pub async fn run_kind(
+        &self,
+        kind: &str,
+        ctrl: &dyn KindController,
+        db: &ArangoDb,
+    ) -> Result<ReconcileStatus, AppError> {
+        let docs = db.generic_list(kind, None, None, None).await?.docs;
+        let mut status = ReconcileStatus {
+            kind: kind.to_string(),
+            last_run: Some(Utc::now()),
+            ..Default::default()
+        };
+
+        for doc in docs {
+            let Some(id) = doc.get("_key").and_then(|v| v.as_str()).map(String::from) else {
+                continue;
+            };
+            status.checked += 1;
+
+            let desired = doc.get("hash_code").and_then(|v| v.as_str());
+            let result = match ctrl.observe(&doc, db).await {
+                Ok(None) => {
+                    status.skipped += 1;
+                    continue;
+                }
+                Ok(Some(observed)) if desired == Some(observed.as_str()) => {
+                    status.in_sync += 1;
+                    continue;
+                }
+                Ok(Some(_)) if self.read_only.load(Ordering::Relaxed) => {
+                    log::debug!("[RECONCILE] {}/{} drifted; repair deferred while read-only", kind, id);
+                    status.deferred.push(id);
+                    continue;
+                }
+                Ok(Some(_)) => ctrl.reconcile(&doc, db).await,
+                Err(e) => Err(e),
+            };
+
+            let retry_key = (kind.to_string(), id.clone());
+            match result {
+                Ok(()) => {
+                    log::info!("[RECONCILE] {}/{} reconciled", kind, id);
+                    self.retries.write().await.remove(&retry_key);
+                    status.reconciled.push(id);
+                }
+                Err(e) => {
+                    let mut retries = self.retries.write().await;
+                    let count = retries.entry(retry_key).or_insert(0);
+                    *count += 1;
+                    log::warn!(
+                        "[RECONCILE] {}/{} failed (attempt {}): {}",
+                        kind,
+                        id,
+                        count,
+                        e
+                    );
+                    status.failed.push(ReconcileFailure {
+                        id,
+                        error: e.to_string(),
+                        retry_count: *count,
+                    });
+                }
+            }
+        }
+
+        self.status
+            .write()
+            .await
+            .insert(kind.to_string(), status.clone());
+        Ok(status)
+    }
+
+    /// Spawn a background task reconciling every registered kind each `interval`.
+    pub fn spawn_periodic(
+        self: Arc<Self>,
+        controller: Arc<Controller>,
+        db: Arc<ArangoDb>,
+        interval: std::time::Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                for kind in Controller::registered_kinds() {
+                    if let Err(e) = self.run_kind(kind, controller.for_kind(kind), &db).await {
+                        log::error!("[RECONCILE] pass for {} failed: {}", kind, e);
+                    }
+                }
+            }
+        })
+    }
+}
+
+impl Default for Reconciler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
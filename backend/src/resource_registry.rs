@@ -0,0 +1,90 @@
+//! Resource models the server can describe, keyed by kind.
+//!
+//! Routing and authorization go through `Controller::for_kind`; this registry
+//! only answers "what does a `{kind}` document look like". Each entry is a
+//! type-erased [`ResourceDescriptor`] built from the `crit_resource` model, so
+//! `GET /v1/ops/describe/{kind}` serves every registered kind with one handler.
+
+use std::collections::BTreeMap;
+
+use crit_shared::data_models::{Group, PipelineAccount, Project, ServiceAccount, User};
+use crit_shared::typegen::TsField;
+
+/// What `crit_resource` knows about one model.
+#[derive(Debug, Clone, Copy)]
+pub struct ResourceDescriptor {
+    /// URL kind and ArangoDB collection, e.g. `users`.
+    pub kind: &'static str,
+    /// Rust model name, e.g. `User`.
+    pub model: &'static str,
+    pub id_prefix: &'static str,
+    /// Serialized fields, in struct order.
+    pub fields: &'static [TsField],
+}
+
+/// Descriptor of a `crit_resource` model: `describe!(User)`.
+macro_rules! describe {
+    ($model:ident) => {
+        ResourceDescriptor {
+            kind: $model::kind(),
+            model: stringify!($model),
+            id_prefix: $model::id_prefix(),
+            fields: $model::ts_fields(),
+        }
+    };
+}
+
+impl ResourceDescriptor {
+    /// Names accepted for the kind besides `kind`: the singular (`users` →
+    /// `user`) and the id prefix without its underscore (`u`), when there is one.
+    pub fn aliases(&self) -> Vec<String> {
+        let mut aliases = Vec::new();
+        if let Some(singular) = self.kind.strip_suffix('s') {
+            aliases.push(singular.to_string());
+        }
+        let short = self.id_prefix.trim_end_matches('_');
+        if !short.is_empty() {
+            aliases.push(short.to_string());
+        }
+        aliases
+    }
+}
+
+pub struct ResourceRegistry {
+    kinds: BTreeMap<&'static str, ResourceDescriptor>,
+}
+
+impl ResourceRegistry {
+    pub fn empty() -> Self {
+        Self { kinds: BTreeMap::new() }
+    }
+
+    /// Add a kind; a later registration of the same kind replaces the earlier one.
+    pub fn register(&mut self, descriptor: ResourceDescriptor) -> &mut Self {
+        self.kinds.insert(descriptor.kind, descriptor);
+        self
+    }
+
+    pub fn get(&self, kind: &str) -> Option<&ResourceDescriptor> {
+        self.kinds.get(kind)
+    }
+
+    /// Registered kinds, sorted.
+    pub fn kinds(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.kinds.keys().copied()
+    }
+}
+
+impl Default for ResourceRegistry {
+    /// Every model in `crit_shared::data_models` with a `crit_resource` derive.
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry
+            .register(describe!(User))
+            .register(describe!(Group))
+            .register(describe!(ServiceAccount))
+            .register(describe!(PipelineAccount))
+            .register(describe!(Project));
+        registry
+    }
+}
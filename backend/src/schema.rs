@@ -20,6 +20,37 @@ pub struct LoginRequest {
     pub password: String,
 }
 
+/// `POST /login` body as the handler reads it: the credentials plus, for a
+/// user with two-factor authentication on, a current TOTP code or an unused
+/// recovery code.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LoginBody {
+    #[serde(flatten)]
+    pub credentials: LoginRequest,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub totp_code: Option<String>,
+}
+
+/// Response of `POST /2fa/enroll`.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TotpEnrollResponse {
+    /// Base32 secret, for typing into an authenticator app.
+    pub secret: String,
+    /// The same secret as an `otpauth://` URI, for a QR code.
+    pub otpauth_uri: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TotpVerifyRequest {
+    pub code: String,
+}
+
+/// Response of `POST /2fa/verify`. The codes are shown only this once.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TotpVerifyResponse {
+    pub recovery_codes: Vec<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ImpersonateRequest {
     pub action: String,
@@ -29,6 +60,14 @@ pub struct ImpersonateRequest {
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct LoginResponse {
     pub token: String,
+    /// Opaque, single-use token for `POST /api/v1/refresh`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
 }
 
 #[derive(ToSchema)]
@@ -0,0 +1,122 @@
+//! Serving the app: plain HTTP, or HTTPS terminated with rustls when
+//! `TLS_CERT`/`TLS_KEY` are configured. Both paths stop accepting connections on
+//! shutdown and let in-flight requests finish. Also builds the CORS layer.
+
+use std::future::Future;
+use std::time::Duration;
+
+use axum::{
+    Router,
+    http::{
+        HeaderName, HeaderValue, Method,
+        header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE},
+    },
+    routing::IntoMakeService,
+};
+use axum_server::tls_rustls::RustlsConfig;
+use log::info;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+use crate::{
+    config::{CorsConfig, CorsOrigins, TlsConfig},
+    middleware::auth::IMPERSONATE_HEADER,
+};
+
+/// How long in-flight HTTPS connections get to finish after shutdown starts.
+const TLS_SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
+
+/// CORS for the API. Without `CORS_ALLOWED_ORIGINS` every origin, method and
+/// header is allowed without credentials, as before; with it, only the listed
+/// origins, the methods the API serves and the headers it reads. The layer sits
+/// outside the auth middleware, so preflight requests never need a token.
+pub fn cors_layer(cors: Option<&CorsConfig>) -> CorsLayer {
+    let Some(cors) = cors else {
+        return CorsLayer::new().allow_origin(Any).allow_methods(Any).allow_headers(Any);
+    };
+    let origins = match &cors.origins {
+        CorsOrigins::Any => AllowOrigin::any(),
+        // Origins are validated by `resolve_cors`.
+        CorsOrigins::List(list) => AllowOrigin::list(list.iter().filter_map(|o| HeaderValue::from_str(o).ok())),
+    };
+    CorsLayer::new()
+        .allow_origin(origins)
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE])
+        .allow_headers([AUTHORIZATION, CONTENT_TYPE, ACCEPT, HeaderName::from_static(IMPERSONATE_HEADER)])
+        .allow_credentials(cors.allow_credentials)
+        .max_age(Duration::from_secs(cors.max_age_secs))
+}
+
+/// Load the certificate chain and key. Fails with a message naming the variable
+/// and path when a file is missing, unreadable or not valid PEM.
+pub async fn load_tls(tls: &TlsConfig) -> Result<RustlsConfig, String> {
+    // Several crates in the tree enable different rustls providers; pick one
+    // explicitly so the server config builder does not have to guess.
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    for (var, path) in [("TLS_CERT", &tls.cert_path), ("TLS_KEY", &tls.key_path)] {
+        std::fs::File::open(path).map_err(|e| format!("{} '{}': {}", var, path.display(), e))?;
+    }
+    RustlsConfig::from_pem_file(&tls.cert_path, &tls.key_path)
+        .await
+        .map_err(|e| {
+            format!(
+                "invalid TLS certificate or key (TLS_CERT '{}', TLS_KEY '{}'): {}",
+                tls.cert_path.display(),
+                tls.key_path.display(),
+                e
+            )
+        })
+}
+
+/// Serve `app` on `listener` until `shutdown` resolves, over HTTPS when `tls` is given.
+pub async fn serve(
+    listener: std::net::TcpListener,
+    app: IntoMakeService<Router>,
+    tls: Option<RustlsConfig>,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> std::io::Result<()> {
+    listener.set_nonblocking(true)?;
+    match tls {
+        None => {
+            let listener = tokio::net::TcpListener::from_std(listener)?;
+            axum::serve(listener, app).with_graceful_shutdown(shutdown).await
+        }
+        Some(tls) => {
+            let handle = axum_server::Handle::new();
+            let on_shutdown = handle.clone();
+            tokio::spawn(async move {
+                shutdown.await;
+                on_shutdown.graceful_shutdown(Some(TLS_SHUTDOWN_GRACE));
+            });
+            axum_server::from_tcp_rustls(listener, tls)
+                .handle(handle)
+                .serve(app)
+                .await
+        }
+    }
+}
+
+/// Resolves on Ctrl-C, or SIGTERM on Unix (what container runtimes send).
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sig) => {
+                sig.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    info!("Shutdown signal received, draining connections");
+}
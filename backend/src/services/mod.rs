@@ -1,4 +1,6 @@
 pub mod github;
 pub mod image_processing;
 pub mod objectstore;
-pub mod offloadmq;
\ No newline at end of file
+pub mod offloadmq;
+pub mod oidc;
+pub mod totp;
@@ -16,6 +16,19 @@ pub enum StorageError {
     NotConfigured,
     #[error("unsupported backend: {0}")]
     UnsupportedBackend(String),
+    #[error("unsafe path: {0:?}")]
+    UnsafePath(String),
+}
+
+/// Parse an object path, refusing anything that could leave the store root on
+/// the `local` backend: `.`/`..` segments, empty segments and NUL (rejected by
+/// `Path::parse`), plus absolute paths and backslashes, which `Path::parse`
+/// accepts but Windows treats as separators.
+fn safe_location(path: &str) -> Result<Path, StorageError> {
+    if path.starts_with('/') || path.contains('\\') {
+        return Err(StorageError::UnsafePath(path.to_string()));
+    }
+    Ok(Path::parse(path)?)
 }
 
 #[derive(Clone)]
@@ -83,19 +96,19 @@ impl ObjectStoreService {
     }
 
     pub async fn put(&self, path: &str, data: Bytes) -> Result<(), StorageError> {
-        let location = Path::parse(path)?;
+        let location = safe_location(path)?;
         self.store.put(&location, data.into()).await?;
         Ok(())
     }
 
     pub async fn get(&self, path: &str) -> Result<Bytes, StorageError> {
-        let location = Path::parse(path)?;
+        let location = safe_location(path)?;
         let result = self.store.get(&location).await?;
         Ok(result.bytes().await?)
     }
 
     pub async fn delete(&self, path: &str) -> Result<(), StorageError> {
-        let location = Path::parse(path)?;
+        let location = safe_location(path)?;
         self.store.delete(&location).await?;
         Ok(())
     }
@@ -104,7 +117,7 @@ impl ObjectStoreService {
         let prefix_path = if prefix.is_empty() {
             None
         } else {
-            Some(Path::parse(prefix)?)
+            Some(safe_location(prefix)?)
         };
         let mut stream = self.store.list(prefix_path.as_ref());
         let mut results = Vec::new();
@@ -147,4 +160,24 @@ mod tests {
         let results = svc.list("docs").await.unwrap();
         assert_eq!(results.len(), 2);
     }
+
+    #[tokio::test]
+    async fn test_traversal_paths_are_rejected() {
+        let svc = memory_service();
+        for path in ["../escape.txt", "user_avatars/../../etc/passwd", "a/./b", "/etc/passwd", "a\\..\\b", "a/\0b", "a//b"] {
+            assert!(svc.put(path, Bytes::from("x")).await.is_err(), "put accepted {:?}", path);
+            assert!(svc.get(path).await.is_err(), "get accepted {:?}", path);
+        }
+        assert!(matches!(svc.list("/etc").await, Err(StorageError::UnsafePath(_))));
+    }
+
+    #[tokio::test]
+    async fn test_unusual_but_safe_names_round_trip() {
+        let svc = memory_service();
+        let path = "user_avatars/naïve name %2e%2e (1).webp";
+        svc.put(path, Bytes::from("img")).await.unwrap();
+        assert_eq!(svc.get(path).await.unwrap(), Bytes::from("img"));
+        let listed = svc.list("user_avatars").await.unwrap();
+        assert_eq!(listed[0].location.as_ref(), path);
+    }
 }
@@ -0,0 +1,214 @@
+use anyhow::{Result, anyhow};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode, decode_header};
+use rand::RngCore;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::OidcConfig;
+
+/// The parts of `/.well-known/openid-configuration` the login flow uses.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+/// What is remembered between `/oauth/login` and `/oauth/callback`, keyed by `state`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingLogin {
+    pub code_verifier: String,
+    pub nonce: String,
+}
+
+/// Verified ID token claims.
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdTokenClaims {
+    pub iss: String,
+    pub sub: String,
+    #[serde(default)]
+    pub nonce: Option<String>,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub preferred_username: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+/// Signature algorithms accepted on ID tokens. Symmetric ones are left out:
+/// the key comes from the provider's public JWKS.
+const ALLOWED_ALGORITHMS: &[Algorithm] = &[
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+    Algorithm::ES256,
+    Algorithm::ES384,
+    Algorithm::EdDSA,
+];
+
+/// Authorization-code flow with PKCE against one OpenID Connect provider.
+#[derive(Clone)]
+pub struct OidcClient {
+    http: Client,
+    config: OidcConfig,
+}
+
+impl OidcClient {
+    pub fn new(config: OidcConfig) -> Self {
+        Self {
+            http: Client::new(),
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &OidcConfig {
+        &self.config
+    }
+
+    /// Fetch the provider's metadata and check it is for the configured issuer.
+    pub async fn discover(&self) -> Result<ProviderMetadata> {
+        let url = format!("{}/.well-known/openid-configuration", self.config.issuer.trim_end_matches('/'));
+        let meta: ProviderMetadata = self.http.get(&url).send().await?.error_for_status()?.json().await?;
+        if meta.issuer != self.config.issuer {
+            return Err(anyhow!("discovery at {} names issuer '{}', expected '{}'", url, meta.issuer, self.config.issuer));
+        }
+        Ok(meta)
+    }
+
+    /// Where to send the browser, and the `state` plus what to remember under it.
+    pub fn authorization_url(&self, meta: &ProviderMetadata) -> Result<(String, String, PendingLogin)> {
+        let state = random_token();
+        let pending = PendingLogin {
+            code_verifier: random_token(),
+            nonce: random_token(),
+        };
+        let mut url = url::Url::parse(&meta.authorization_endpoint)?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.config.client_id)
+            .append_pair("redirect_uri", &self.config.redirect_url)
+            .append_pair("scope", "openid email profile")
+            .append_pair("state", &state)
+            .append_pair("nonce", &pending.nonce)
+            .append_pair("code_challenge", &code_challenge(&pending.code_verifier))
+            .append_pair("code_challenge_method", "S256");
+        Ok((url.into(), state, pending))
+    }
+
+    /// Redeem an authorization code at the token endpoint; returns the raw ID token.
+    pub async fn exchange_code(&self, meta: &ProviderMetadata, code: &str, code_verifier: &str) -> Result<String> {
+        let form = [
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.config.redirect_url.as_str()),
+            ("client_id", self.config.client_id.as_str()),
+            ("client_secret", self.config.client_secret.as_str()),
+            ("code_verifier", code_verifier),
+        ];
+        let res = self.http.post(&meta.token_endpoint).form(&form).send().await?;
+        if !res.status().is_success() {
+            let status = res.status();
+            let body = res.text().await.unwrap_or_default();
+            return Err(anyhow!("token endpoint answered {}: {}", status, body));
+        }
+        Ok(res.json::<TokenResponse>().await?.id_token)
+    }
+
+    /// Check the ID token's signature against the provider's JWKS, its issuer,
+    /// audience and expiry, and that it carries the nonce sent at login.
+    pub async fn verify_id_token(
+        &self,
+        meta: &ProviderMetadata,
+        id_token: &str,
+        nonce: &str,
+        leeway_secs: u64,
+    ) -> Result<IdTokenClaims> {
+        let header = decode_header(id_token)?;
+        if !ALLOWED_ALGORITHMS.contains(&header.alg) {
+            return Err(anyhow!("ID token signed with unsupported algorithm {:?}", header.alg));
+        }
+        let jwks: JwkSet = self.http.get(&meta.jwks_uri).send().await?.error_for_status()?.json().await?;
+        let jwk = match &header.kid {
+            Some(kid) => jwks.find(kid),
+            None if jwks.keys.len() == 1 => jwks.keys.first(),
+            None => None,
+        }
+        .ok_or_else(|| anyhow!("no key in the provider's JWKS matches the ID token"))?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_audience(&[&self.config.client_id]);
+        validation.set_issuer(&[&meta.issuer]);
+        validation.leeway = leeway_secs;
+        let claims = decode::<IdTokenClaims>(id_token, &DecodingKey::from_jwk(jwk)?, &validation)?.claims;
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err(anyhow!("ID token nonce does not match the login request"));
+        }
+        Ok(claims)
+    }
+}
+
+/// 32 random bytes, base64url: used for `state`, `nonce` and the PKCE verifier.
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// PKCE `S256` challenge for a verifier (RFC 7636).
+pub fn code_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pkce_challenge_matches_rfc_example() {
+        // RFC 7636, appendix B.
+        assert_eq!(
+            code_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn authorization_url_carries_pkce_state_and_nonce() {
+        let client = OidcClient::new(OidcConfig {
+            issuer: "https://id.example.com".into(),
+            client_id: "crit".into(),
+            client_secret: "secret".into(),
+            redirect_url: "https://crit.example.com/api/v1/oauth/callback".into(),
+        });
+        let meta = ProviderMetadata {
+            issuer: "https://id.example.com".into(),
+            authorization_endpoint: "https://id.example.com/authorize".into(),
+            token_endpoint: "https://id.example.com/token".into(),
+            jwks_uri: "https://id.example.com/jwks".into(),
+        };
+        let (url, state, pending) = client.authorization_url(&meta).unwrap();
+        let url = url::Url::parse(&url).unwrap();
+        let query: std::collections::HashMap<_, _> = url.query_pairs().into_owned().collect();
+        assert_eq!(query["state"], state);
+        assert_eq!(query["nonce"], pending.nonce);
+        assert_eq!(query["code_challenge"], code_challenge(&pending.code_verifier));
+        assert_eq!(query["code_challenge_method"], "S256");
+        assert_eq!(query["redirect_uri"], "https://crit.example.com/api/v1/oauth/callback");
+        assert!(!query.contains_key("client_secret"));
+        assert_ne!(state, pending.nonce);
+    }
+}
@@ -0,0 +1,203 @@
+//! TOTP second factor (RFC 6238) for `/v1/2fa/*` and login.
+//!
+//! Codes are 6 digits over 30-second steps with HMAC-SHA1, the defaults
+//! authenticator apps assume. Secrets are stored AES-256-GCM encrypted with
+//! the server's TOTP key, a random nonce in front of each ciphertext.
+//! Recovery codes are stored as SHA-256 hashes and removed once used.
+
+use anyhow::{Result, anyhow};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use data_encoding::BASE32_NOPAD;
+use rand::{Rng, RngCore};
+use ring::{aead, hmac};
+use sha2::{Digest, Sha256};
+
+/// Seconds per code.
+pub const STEP_SECS: u64 = 30;
+/// Steps accepted on either side of the current one, for clock drift.
+pub const SKEW_STEPS: u64 = 1;
+/// Recovery codes handed out when enrollment is confirmed.
+pub const RECOVERY_CODE_COUNT: usize = 10;
+/// Issuer shown by authenticator apps.
+pub const ISSUER: &str = "Critical";
+
+const DIGITS: usize = 6;
+const SECRET_BYTES: usize = 20;
+/// No `0/o`, `1/l/i`: recovery codes are read off paper.
+const RECOVERY_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
+/// A fresh 160-bit shared secret.
+pub fn generate_secret() -> Vec<u8> {
+    let mut secret = vec![0u8; SECRET_BYTES];
+    rand::rng().fill_bytes(&mut secret);
+    secret
+}
+
+/// The secret as authenticator apps take it when typed in: unpadded base32.
+pub fn encode_secret(secret: &[u8]) -> String {
+    BASE32_NOPAD.encode(secret)
+}
+
+/// `otpauth://` URI for QR codes.
+pub fn otpauth_uri(account: &str, secret: &[u8]) -> String {
+    let enc = |s: &str| url::form_urlencoded::byte_serialize(s.as_bytes()).collect::<String>();
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        enc(ISSUER),
+        enc(account),
+        encode_secret(secret),
+        enc(ISSUER),
+        DIGITS,
+        STEP_SECS
+    )
+}
+
+/// The code for the step containing `unix_secs`.
+pub fn code_at(secret: &[u8], unix_secs: u64) -> String {
+    code_for_step(secret, unix_secs / STEP_SECS)
+}
+
+fn code_for_step(secret: &[u8], step: u64) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, secret);
+    let mac = hmac::sign(&key, &step.to_be_bytes());
+    let mac = mac.as_ref();
+    // Dynamic truncation (RFC 4226 §5.3).
+    let offset = (mac[mac.len() - 1] & 0x0f) as usize;
+    let value = u32::from_be_bytes([mac[offset] & 0x7f, mac[offset + 1], mac[offset + 2], mac[offset + 3]]);
+    format!("{:0width$}", value % 10u32.pow(DIGITS as u32), width = DIGITS)
+}
+
+/// Whether `code` matches the step containing `unix_secs` or one within
+/// `SKEW_STEPS` of it. Spaces in the code are ignored.
+pub fn verify(secret: &[u8], code: &str, unix_secs: u64) -> bool {
+    let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+    if code.len() != DIGITS || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return false;
+    }
+    let step = unix_secs / STEP_SECS;
+    // Check every candidate so timing does not reveal which step matched.
+    (step.saturating_sub(SKEW_STEPS)..=step + SKEW_STEPS)
+        .fold(false, |ok, s| constant_time_eq(code_for_step(secret, s).as_bytes(), code.as_bytes()) | ok)
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// `RECOVERY_CODE_COUNT` single-use codes shaped `xxxxx-xxxxx`.
+pub fn generate_recovery_codes() -> Vec<String> {
+    let mut rng = rand::rng();
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let mut chars = (0..10).map(|_| RECOVERY_ALPHABET[rng.random_range(0..RECOVERY_ALPHABET.len())] as char);
+            let head: String = chars.by_ref().take(5).collect();
+            let tail: String = chars.collect();
+            format!("{}-{}", head, tail)
+        })
+        .collect()
+}
+
+/// Stored form of a recovery code; case, spaces and dashes don't matter.
+pub fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    Sha256::digest(normalized.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Encrypt a secret for storage: base64 of nonce ‖ ciphertext ‖ tag.
+pub fn seal(key: &[u8; 32], secret: &[u8]) -> Result<String> {
+    let key = aead_key(key)?;
+    let mut nonce = [0u8; aead::NONCE_LEN];
+    rand::rng().fill_bytes(&mut nonce);
+    let mut in_out = secret.to_vec();
+    key.seal_in_place_append_tag(aead::Nonce::assume_unique_for_key(nonce), aead::Aad::empty(), &mut in_out)
+        .map_err(|_| anyhow!("could not encrypt TOTP secret"))?;
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&in_out);
+    Ok(STANDARD.encode(sealed))
+}
+
+/// Decrypt a secret stored by `seal`. Fails if it was sealed with another key.
+pub fn open(key: &[u8; 32], sealed: &str) -> Result<Vec<u8>> {
+    let key = aead_key(key)?;
+    let bytes = STANDARD.decode(sealed).map_err(|_| anyhow!("stored TOTP secret is not base64"))?;
+    if bytes.len() < aead::NONCE_LEN {
+        return Err(anyhow!("stored TOTP secret is truncated"));
+    }
+    let (nonce, ciphertext) = bytes.split_at(aead::NONCE_LEN);
+    let nonce = aead::Nonce::try_assume_unique_for_key(nonce).map_err(|_| anyhow!("stored TOTP secret is truncated"))?;
+    let mut in_out = ciphertext.to_vec();
+    let plain = key
+        .open_in_place(nonce, aead::Aad::empty(), &mut in_out)
+        .map_err(|_| anyhow!("stored TOTP secret does not decrypt with the current TOTP key"))?;
+    Ok(plain.to_vec())
+}
+
+fn aead_key(key: &[u8; 32]) -> Result<aead::LessSafeKey> {
+    let key = aead::UnboundKey::new(&aead::AES_256_GCM, key).map_err(|_| anyhow!("invalid TOTP key"))?;
+    Ok(aead::LessSafeKey::new(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// RFC 6238 appendix B secret for SHA-1.
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn codes_match_the_rfc_vectors() {
+        // The RFC lists 8-digit codes; 6-digit ones are their last six digits.
+        assert_eq!(code_at(RFC_SECRET, 59), "287082");
+        assert_eq!(code_at(RFC_SECRET, 1_111_111_109), "081804");
+        assert_eq!(code_at(RFC_SECRET, 1_234_567_890), "005924");
+        assert_eq!(code_at(RFC_SECRET, 2_000_000_000), "279037");
+    }
+
+    #[test]
+    fn verify_tolerates_one_step_of_skew() {
+        let now = 1_111_111_109;
+        let code = code_at(RFC_SECRET, now);
+        assert!(verify(RFC_SECRET, &code, now));
+        assert!(verify(RFC_SECRET, &code, now + STEP_SECS));
+        assert!(verify(RFC_SECRET, &code, now - STEP_SECS));
+        assert!(!verify(RFC_SECRET, &code, now + 2 * STEP_SECS));
+        assert!(!verify(RFC_SECRET, &code, now - 2 * STEP_SECS));
+        assert!(verify(RFC_SECRET, "081 804", now));
+        assert!(!verify(RFC_SECRET, "81804", now));
+        assert!(!verify(RFC_SECRET, "08180x", now));
+    }
+
+    #[test]
+    fn sealed_secrets_open_only_with_their_key() {
+        let key = [7u8; 32];
+        let secret = generate_secret();
+        let sealed = seal(&key, &secret).unwrap();
+        assert_ne!(sealed, seal(&key, &secret).unwrap(), "nonces must differ");
+        assert_eq!(open(&key, &sealed).unwrap(), secret);
+        assert!(open(&[8u8; 32], &sealed).is_err());
+        assert!(open(&key, "AAAA").is_err());
+    }
+
+    #[test]
+    fn recovery_codes_hash_regardless_of_formatting() {
+        let codes = generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        assert!(codes.iter().all(|c| c.len() == 11 && c.as_bytes()[5] == b'-'));
+        assert_eq!(hash_recovery_code("abcde-fghjk"), hash_recovery_code("ABCDE FGHJK"));
+        assert_ne!(hash_recovery_code(&codes[0]), hash_recovery_code(&codes[1]));
+    }
+
+    #[test]
+    fn otpauth_uri_carries_the_base32_secret() {
+        let uri = otpauth_uri("u_alice", RFC_SECRET);
+        assert_eq!(
+            uri,
+            "otpauth://totp/Critical:u_alice?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Critical&algorithm=SHA1&digits=6&period=30"
+        );
+    }
+}
@@ -1,17 +1,25 @@
-use std::sync::Arc;
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
 
-use serde_json::json;
+use serde_json::{Value, json};
 use tokio::sync::Semaphore;
 
 use crate::{
+    audit_log::{AUDIT_RING_CAPACITY, AuditLog},
     cache::{self, CacheStore},
     config::{AppConfig, RuntimeConfig},
     controllers::Controller,
-    db::ArangoDb,
+    db::{ArangoDb, IndexView},
     godmode,
     middleware::auth::Auth,
+    reconcile::Reconciler,
+    resource_registry::ResourceRegistry,
     services::objectstore::ObjectStoreService,
     services::offloadmq::OffloadClient,
+    services::oidc::OidcClient,
+    watch::{ChangeType, WatchHub},
 };
 use crit_shared::util_models::super_permissions;
 
@@ -20,18 +28,36 @@ pub struct AppState {
     pub config: Arc<AppConfig>,
     pub auth: Arc<Auth>,
     pub controller: Arc<Controller>,
+    /// Models served by `GET /v1/ops/describe/{kind}`.
+    pub resources: Arc<ResourceRegistry>,
     pub db: Arc<ArangoDb>,
     pub cache: Arc<CacheStore>,
     pub runtime_config: Arc<RuntimeConfig>,
     pub offloadmq: Arc<Option<OffloadClient>>,
     pub objectstore: Arc<Option<ObjectStoreService>>,
+    /// Set when `OIDC_*` is configured; `/v1/oauth/*` answer 404 otherwise.
+    pub oidc: Arc<Option<OidcClient>>,
     /// Limits background image conversion to one task at a time.
     /// All other upload tasks queue up and wait their turn.
     pub image_processing_semaphore: Arc<Semaphore>,
+    /// Per-kind change feed consumed by the SSE watch endpoint.
+    pub watch: Arc<WatchHub>,
+    /// Secondary indexes over resource documents, fed by `publish_change`.
+    pub indexes: Arc<IndexView>,
+    /// Recent mutating requests, recorded by `audit_middleware`.
+    pub audit: Arc<AuditLog>,
+    /// Drift detection state, shared by the ops endpoint and the periodic pass.
+    pub reconciler: Arc<Reconciler>,
+    /// Read-only maintenance mode, seeded from `READ_ONLY`; checked by `read_only_middleware`,
+    /// OIDC provisioning and the reconciler.
+    pub read_only: Arc<AtomicBool>,
 }
 
 impl AppState {
     pub fn new(config: AppConfig, auth: Auth, database: Arc<ArangoDb>, cache: Arc<CacheStore>, offloadmq: Option<OffloadClient>, objectstore: Option<ObjectStoreService>) -> Self {
+        let audit = AuditLog::new(AUDIT_RING_CAPACITY, config.audit_log_path.clone());
+        let read_only = Arc::new(AtomicBool::new(config.read_only));
+        let oidc = config.oidc.clone().map(OidcClient::new);
         Self {
             config: Arc::new(config),
             auth: Arc::new(auth),
@@ -39,12 +65,37 @@ impl AppState {
             cache,
             runtime_config: Arc::new(AppConfig::runtime_from_env().unwrap_or_default()),
             controller: Arc::new(Controller::new(database.clone())),
+            resources: Arc::new(ResourceRegistry::default()),
             offloadmq: Arc::new(offloadmq),
             objectstore: Arc::new(objectstore),
+            oidc: Arc::new(oidc),
             image_processing_semaphore: Arc::new(Semaphore::new(1)),
+            watch: Arc::new(WatchHub::new()),
+            indexes: Arc::new(IndexView::default()),
+            audit: Arc::new(audit),
+            reconciler: Arc::new(Reconciler::with_read_only(read_only.clone())),
+            read_only,
         }
     }
 
+    /// Record a committed write of `kind/id`: update the secondary indexes,
+    /// then notify watchers. `doc` is the stored document (the last stored
+    /// version for deletes).
+    pub async fn publish_change(&self, change: ChangeType, kind: &str, id: &str, doc: Value) {
+        let live = (change != ChangeType::Deleted).then_some(&doc);
+        self.indexes.apply(kind, id, live).await;
+        self.watch.publish(change, kind, id, doc).await;
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only.load(Ordering::Relaxed)
+    }
+
+    /// Enter or leave read-only maintenance mode at runtime.
+    pub fn set_read_only(&self, on: bool) {
+        self.read_only.store(on, Ordering::Relaxed);
+    }
+
     /// Return the resolved principals (direct user ID + transitive group IDs) for a user,
     /// using the principals cache with 5s TTL. Falls back to a DB query on cache miss.
     ///
@@ -76,6 +127,30 @@ impl AppState {
         Ok(principals)
     }
 
+    /// Whether a JWT subject is an active user, cached in `ACTIVE_USERS_CACHE`.
+    /// Stale answers are possible within the TTL only if the user document is
+    /// changed outside the API; API writes call `invalidate_cached_user`.
+    pub async fn is_active_user(&self, user_id: &str) -> bool {
+        if let Some(cached) = self.cache.get(cache::ACTIVE_USERS_CACHE, user_id).await
+            && let Some(b) = cached.as_bool()
+        {
+            return b;
+        }
+        let valid = self.controller.user.validate_user(user_id).await;
+        self.cache
+            .set(cache::ACTIVE_USERS_CACHE, user_id.to_string(), json!(valid))
+            .await;
+        valid
+    }
+
+    /// Drop the cached active-user answer after a write to `kind/id`.
+    /// A no-op for kinds other than `users`.
+    pub async fn invalidate_cached_user(&self, kind: &str, id: &str) {
+        if kind == "users" {
+            self.cache.invalidate(cache::ACTIVE_USERS_CACHE, id).await;
+        }
+    }
+
     /// Check if a user has ADM_GODMODE, using the special_access_cache with
     /// 5-minute TTL. Falls back to a DB query on cache miss.
     pub async fn has_godmode(&self, user_id: &str) -> Result<bool, anyhow::Error> {
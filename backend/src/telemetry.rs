@@ -0,0 +1,70 @@
+//! OTLP export of request spans, compiled in with the `otel` feature.
+//!
+//! Spans go to `{OTEL_EXPORTER_OTLP_ENDPOINT}/v1/traces` over HTTP/protobuf,
+//! reported as service `OTEL_SERVICE_NAME`. Only this crate's and
+//! tower-http's spans are exported; see [`crate::middleware::request_span`]
+//! for the per-request attributes.
+
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{Resource, trace::SdkTracerProvider};
+use tracing::Level;
+use tracing_subscriber::{Layer, Registry, filter::Targets, layer::SubscriberExt};
+
+pub const DEFAULT_ENDPOINT: &str = "http://localhost:4318";
+pub const DEFAULT_SERVICE_NAME: &str = "critical";
+
+#[derive(Debug, Clone)]
+pub struct OtelConfig {
+    /// Collector base URL, without the `/v1/traces` path.
+    pub endpoint: String,
+    pub service_name: String,
+}
+
+impl OtelConfig {
+    pub fn from_env() -> Self {
+        let var = |name: &str, default: &str| {
+            std::env::var(name)
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+                .unwrap_or_else(|| default.to_string())
+        };
+        Self {
+            endpoint: var("OTEL_EXPORTER_OTLP_ENDPOINT", DEFAULT_ENDPOINT),
+            service_name: var("OTEL_SERVICE_NAME", DEFAULT_SERVICE_NAME),
+        }
+    }
+}
+
+/// A provider batching spans to the collector on its own thread.
+pub fn tracer_provider(config: &OtelConfig) -> anyhow::Result<SdkTracerProvider> {
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(format!("{}/v1/traces", config.endpoint.trim_end_matches('/')))
+        .build()?;
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(config.service_name.clone()).build())
+        .build())
+}
+
+/// A subscriber feeding `provider`. Spans of other crates, the exporter's own
+/// HTTP client among them, are filtered out.
+pub fn subscriber(provider: &SdkTracerProvider) -> impl tracing::Subscriber + Send + Sync {
+    let targets = Targets::new()
+        .with_target("axum_api", Level::DEBUG)
+        .with_target("tower_http", Level::DEBUG);
+    let layer = tracing_opentelemetry::layer()
+        .with_tracer(provider.tracer("axum-api"))
+        .with_filter(targets);
+    Registry::default().with(layer)
+}
+
+/// Install the exporter as the global subscriber. Call `shutdown` on the
+/// returned provider before exit so buffered spans are sent.
+pub fn init(config: &OtelConfig) -> anyhow::Result<SdkTracerProvider> {
+    let provider = tracer_provider(config)?;
+    tracing::subscriber::set_global_default(subscriber(&provider))?;
+    Ok(provider)
+}
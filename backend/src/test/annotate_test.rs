@@ -0,0 +1,142 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::{HeaderValue, StatusCode, header::AUTHORIZATION};
+    use axum_test::{TestResponse, TestServer};
+    use serial_test::serial;
+    use serde_json::{Value, json};
+
+    use crate::{audit_log::AuditQuery, create_app, create_mock_shared_state};
+    use crate::test::helpers::{register_and_login, unique};
+
+    /// A group owned by the caller, with one label and one annotation.
+    async fn create_group(server: &TestServer, auth: &HeaderValue) -> String {
+        let group = unique("annot");
+        server
+            .post("/api/v1/global/groups")
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&json!({
+                "id": &group,
+                "name": "Annotated",
+                "labels": { "team": "sre" },
+                "annotations": { "note": "old", "keep": "yes" },
+            }))
+            .await
+            .assert_status(StatusCode::CREATED);
+        format!("g_{}", group)
+    }
+
+    async fn edit(server: &TestServer, auth: &HeaderValue, what: &str, group: &str, body: Value) -> TestResponse {
+        server
+            .patch(&format!("/api/v1/ops/{}/groups/{}", what, group))
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&body)
+            .await
+    }
+
+    async fn fetch(server: &TestServer, auth: &HeaderValue, group: &str) -> Value {
+        let resp = server
+            .get(&format!("/api/v1/global/groups/{}", group))
+            .add_header(AUTHORIZATION, auth.clone())
+            .await;
+        resp.assert_status_ok();
+        resp.json::<Value>()
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_annotate_sets_and_removes_only_annotations() {
+        let state = create_mock_shared_state().await.unwrap();
+        let server = TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        let owner = unique("annowner");
+        let auth = register_and_login(&server, &owner).await;
+        let group = create_group(&server, &auth).await;
+        let before = fetch(&server, &auth, &group).await;
+
+        let resp = edit(&server, &auth, "annotate", &group, json!({ "set": { "note": "new", "added": "1" }, "remove": ["keep"] })).await;
+        resp.assert_status_ok();
+        let body = resp.json::<Value>();
+        assert_eq!(body["annotations"], json!({ "note": "new", "added": "1" }));
+
+        let after = fetch(&server, &auth, &group).await;
+        assert_eq!(after["annotations"], json!({ "note": "new", "added": "1" }));
+        assert_eq!(after["labels"], before["labels"]);
+        assert_eq!(after["name"], before["name"]);
+        assert_eq!(after["hash_code"], body["hash_code"]);
+        assert_ne!(after["hash_code"], before["hash_code"]);
+        assert_eq!(after["state"]["updated_by"], format!("u_{}", owner));
+        assert_eq!(after["state"]["generation"].as_u64(), before["state"]["generation"].as_u64().map(|g| g + 1));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_label_edits_are_validated() {
+        let state = create_mock_shared_state().await.unwrap();
+        let server = TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        let auth = register_and_login(&server, &unique("lblowner")).await;
+        let group = create_group(&server, &auth).await;
+
+        edit(&server, &auth, "label", &group, json!({ "set": { "tier": "gold" }, "remove": ["team"] }))
+            .await
+            .assert_status_ok();
+        assert_eq!(fetch(&server, &auth, &group).await["labels"], json!({ "tier": "gold" }));
+
+        let bad = edit(&server, &auth, "label", &group, json!({ "set": { "Bad Key": "x" } })).await;
+        bad.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(fetch(&server, &auth, &group).await["labels"], json!({ "tier": "gold" }));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_reserved_prefix_and_malformed_edits_are_refused() {
+        let state = create_mock_shared_state().await.unwrap();
+        let server = TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        let auth = register_and_login(&server, &unique("resowner")).await;
+        let group = create_group(&server, &auth).await;
+
+        let reserved = edit(&server, &auth, "annotate", &group, json!({ "set": { "critical.io/managed-by": "me" } })).await;
+        reserved.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(reserved.json::<Value>()["error"]["message"].as_str().unwrap().contains("reserved prefix"));
+        edit(&server, &auth, "annotate", &group, json!({ "remove": ["critical.io/managed-by"] }))
+            .await
+            .assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        edit(&server, &auth, "annotate", &group, json!({ "set": { "critical.io/description": "Ops on-call" } }))
+            .await
+            .assert_status_ok();
+
+        edit(&server, &auth, "annotate", &group, json!({})).await.assert_status(StatusCode::BAD_REQUEST);
+        edit(&server, &auth, "annotate", &group, json!({ "set": { "a": "1" }, "remove": ["a"] }))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+        edit(&server, &auth, "annotate", "g_missing_group", json!({ "set": { "a": "1" } }))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+
+        // Someone without write access sees the resource as missing.
+        let other = register_and_login(&server, &unique("resother")).await;
+        edit(&server, &other, "annotate", &group, json!({ "set": { "a": "1" } }))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_annotate_is_audited() {
+        let state = create_mock_shared_state().await.unwrap();
+        let audit = state.audit.clone();
+        let server = TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        let auth = register_and_login(&server, &unique("audowner")).await;
+        let group = create_group(&server, &auth).await;
+
+        let resp = edit(&server, &auth, "annotate", &group, json!({ "set": { "note": "audited" } })).await;
+        resp.assert_status_ok();
+        let hash = resp.json::<Value>()["hash_code"].as_str().unwrap().to_string();
+
+        let entries = audit.query(&AuditQuery { key: Some(group.clone()), ..Default::default() }).await;
+        let entry = entries.first().expect("annotate is audited");
+        assert_eq!(entry.kind.as_deref(), Some("groups"));
+        assert_eq!(entry.action.as_deref(), Some("annotate"));
+        assert_eq!(entry.after_hash.as_deref(), Some(hash.as_str()));
+    }
+}
@@ -0,0 +1,203 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::{HeaderValue, StatusCode, header::AUTHORIZATION};
+    use axum_test::TestServer;
+    use serial_test::serial;
+    use serde_json::{Value, json};
+
+    use crate::{create_app, create_mock_shared_state, schema::*, state::AppState};
+    use crate::test::helpers::unique;
+
+    const ROOT_PASSWORD: &str = "changeme";
+
+    /// Seed root with godmode (mirrors main.rs startup logic).
+    async fn ensure_root_godmode(state: &AppState) {
+        if state.db.get_user_by_id("u_root").await.unwrap().is_none() {
+            use crate::controllers::gitops_controller::inject_create_defaults;
+            let mut body = json!({ "id": "u_root", "password": ROOT_PASSWORD });
+            inject_create_defaults(&mut body, "u_root");
+            let doc = state.controller.for_kind("users").to_internal(body, &state.auth).unwrap();
+            state.db.generic_create("users", doc).await.unwrap();
+        }
+        state
+            .db
+            .grant_permission(crit_shared::util_models::super_permissions::ADM_GODMODE, "u_root")
+            .await
+            .unwrap();
+    }
+
+    async fn login(server: &TestServer, user: &str, password: &str) -> HeaderValue {
+        let resp = server
+            .post("/api/v1/login")
+            .json(&LoginRequest { user: user.to_string(), password: password.to_string() })
+            .await;
+        resp.assert_status_ok();
+        format!("Bearer {}", resp.json::<LoginResponse>().token).parse().unwrap()
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_delete_is_audited_and_get_is_not() {
+        let state = create_mock_shared_state().await.unwrap();
+        ensure_root_godmode(&state).await;
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+
+        let username = unique("auditor");
+        server
+            .post("/api/v1/register")
+            .json(&RegisterRequest { user: username.clone(), password: "testpassword123".into() })
+            .await
+            .assert_status(StatusCode::CREATED);
+        let user_auth = login(&server, &username, "testpassword123").await;
+        let root_auth = login(&server, "root", ROOT_PASSWORD).await;
+
+        let group = unique("auditgrp");
+        server
+            .post("/api/v1/global/groups")
+            .add_header(AUTHORIZATION, user_auth.clone())
+            .json(&json!({ "id": &group, "name": "Audited" }))
+            .await
+            .assert_status(StatusCode::CREATED);
+        server
+            .get(&format!("/api/v1/global/groups/g_{}", group))
+            .add_header(AUTHORIZATION, user_auth.clone())
+            .await
+            .assert_status_ok();
+        server
+            .delete(&format!("/api/v1/global/groups/g_{}", group))
+            .add_header(AUTHORIZATION, user_auth.clone())
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+
+        let resp = server
+            .get(&format!("/api/v1/adm/audit?actor=u_{}", username))
+            .add_header(AUTHORIZATION, root_auth)
+            .await;
+        resp.assert_status_ok();
+        let items = resp.json::<Value>()["items"].as_array().unwrap().clone();
+
+        let delete = items
+            .iter()
+            .find(|e| e["method"] == "DELETE")
+            .expect("delete request should be audited");
+        assert_eq!(delete["path"], format!("/api/v1/global/groups/g_{}", group));
+        assert_eq!(delete["actor"], format!("u_{}", username));
+        assert_eq!(delete["kind"], "groups");
+        assert_eq!(delete["status"], 204);
+        assert!(items.iter().all(|e| e["method"] != "GET"));
+
+        // Non-admins cannot read the audit log
+        server
+            .get("/api/v1/adm/audit")
+            .add_header(AUTHORIZATION, user_auth)
+            .await
+            .assert_status_not_ok();
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_upsert_records_principal_and_hashes() {
+        let state = create_mock_shared_state().await.unwrap();
+        ensure_root_godmode(&state).await;
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+
+        let username = unique("upserter");
+        server
+            .post("/api/v1/register")
+            .json(&RegisterRequest { user: username.clone(), password: "testpassword123".into() })
+            .await
+            .assert_status(StatusCode::CREATED);
+        let user_auth = login(&server, &username, "testpassword123").await;
+        let root_auth = login(&server, "root", ROOT_PASSWORD).await;
+
+        let group = format!("g_{}", unique("upsertgrp"));
+        let path = format!("/api/v1/global/groups/{}", group);
+        let first = server
+            .post(&path)
+            .add_header(AUTHORIZATION, user_auth.clone())
+            .json(&json!({ "name": "Before" }))
+            .await;
+        first.assert_status_ok();
+        let created_hash = first.json::<Value>()["hash"].as_str().unwrap().to_string();
+
+        let second = server
+            .post(&path)
+            .add_header(AUTHORIZATION, user_auth.clone())
+            .add_header("x-request-id", HeaderValue::from_static("req-upsert-2"))
+            .json(&json!({ "name": "After" }))
+            .await;
+        second.assert_status_ok();
+        assert_eq!(second.header("x-request-id"), "req-upsert-2");
+        let updated_hash = second.json::<Value>()["hash"].as_str().unwrap().to_string();
+
+        let resp = server
+            .get(&format!("/api/v1/adm/audit?kind=groups&key={}&principal=u_{}", group, username))
+            .add_header(AUTHORIZATION, root_auth)
+            .await;
+        resp.assert_status_ok();
+        let items = resp.json::<Value>()["items"].as_array().unwrap().clone();
+        assert_eq!(items.len(), 2);
+
+        // Newest first: the update, then the create.
+        assert_eq!(items[0]["actor"], format!("u_{}", username));
+        assert_eq!(items[0]["action"], "update");
+        assert_eq!(items[0]["request_id"], "req-upsert-2");
+        assert_eq!(items[0]["before_hash"], created_hash.as_str());
+        assert_eq!(items[0]["after_hash"], updated_hash.as_str());
+        assert_eq!(items[1]["action"], "create");
+        assert!(items[1].get("before_hash").is_none());
+        assert_eq!(items[1]["after_hash"], created_hash.as_str());
+        assert!(items[1]["request_id"].as_str().is_some_and(|id| !id.is_empty()));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_unauthenticated_writes_are_audited_without_actor() {
+        let state = create_mock_shared_state().await.unwrap();
+        ensure_root_godmode(&state).await;
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+
+        let username = unique("anonaudit");
+        server
+            .post("/api/v1/register")
+            .add_header("x-request-id", HeaderValue::from_static("req-anon-register"))
+            .json(&RegisterRequest { user: username.clone(), password: "testpassword123".into() })
+            .await
+            .assert_status(StatusCode::CREATED);
+        server
+            .post("/api/v1/login")
+            .add_header("x-request-id", HeaderValue::from_static("req-anon-login"))
+            .json(&LoginRequest { user: username, password: "wrong_password".into() })
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+        server
+            .post("/api/v1/global/groups")
+            .add_header("x-request-id", HeaderValue::from_static("req-anon-create"))
+            .json(&json!({ "name": "No token" }))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+
+        let root_auth = login(&server, "root", ROOT_PASSWORD).await;
+        let resp = server.get("/api/v1/adm/audit").add_header(AUTHORIZATION, root_auth).await;
+        resp.assert_status_ok();
+        let items = resp.json::<Value>()["items"].as_array().unwrap().clone();
+        for (request_id, path, status) in [
+            ("req-anon-register", "/api/v1/register", 201),
+            ("req-anon-login", "/api/v1/login", 401),
+            ("req-anon-create", "/api/v1/global/groups", 401),
+        ] {
+            let entry = items
+                .iter()
+                .find(|e| e["request_id"] == request_id)
+                .unwrap_or_else(|| panic!("{} should be audited", path));
+            assert_eq!(entry["path"], path);
+            assert_eq!(entry["status"], status);
+            assert!(entry["actor"].is_null(), "{}", entry);
+        }
+    }
+}
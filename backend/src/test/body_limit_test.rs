@@ -0,0 +1,87 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::{HeaderValue, StatusCode, header::AUTHORIZATION};
+    use axum_test::TestServer;
+    use serial_test::serial;
+    use serde_json::{Value, json};
+
+    use crate::{config::AppConfig, create_app, create_mock_shared_state, schema::*};
+    use crate::test::helpers::{PASSWORD, unique};
+
+    const LIMIT: usize = 2048;
+
+    async fn server_with_limit() -> (TestServer, HeaderValue) {
+        let mut state = create_mock_shared_state().await.unwrap();
+        state.config = Arc::new(AppConfig { max_body_bytes: LIMIT, ..(*state.config).clone() });
+        let server = TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        let user = unique("limituser");
+        server
+            .post("/api/v1/register")
+            .json(&RegisterRequest { user: user.clone(), password: PASSWORD.into() })
+            .await
+            .assert_status(StatusCode::CREATED);
+        let resp = server
+            .post("/api/v1/login")
+            .json(&LoginRequest { user, password: PASSWORD.into() })
+            .await;
+        resp.assert_status_ok();
+        let auth = format!("Bearer {}", resp.json::<LoginResponse>().token).parse().unwrap();
+        (server, auth)
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_body_over_limit_is_413_and_under_limit_succeeds() {
+        let (server, auth) = server_with_limit().await;
+
+        let small = json!({ "id": unique("g_small"), "name": "Small", "description": "x".repeat(LIMIT / 2) });
+        server
+            .post("/api/v1/global/groups")
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&small)
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        let big_id = unique("g_big");
+        let big = json!({ "id": big_id, "name": "Big", "description": "x".repeat(LIMIT * 2) });
+        let resp = server
+            .post("/api/v1/global/groups")
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&big)
+            .await;
+        resp.assert_status(StatusCode::PAYLOAD_TOO_LARGE);
+        let body = resp.json::<Value>();
+        assert_eq!(body["error"]["code"], "payload_too_large");
+        assert_eq!(body["error"]["details"]["limit_bytes"], LIMIT);
+        assert!(body["error"]["message"].as_str().unwrap().contains(&LIMIT.to_string()));
+        server
+            .post(&format!("/api/v1/global/groups/{}", big_id))
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&big)
+            .await
+            .assert_status(StatusCode::PAYLOAD_TOO_LARGE);
+
+        // Nothing was written for the rejected bodies.
+        server
+            .get(&format!("/api/v1/global/groups/{}", big_id))
+            .add_header(AUTHORIZATION, auth)
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_ops_routes_are_limited_too() {
+        let (server, auth) = server_with_limit().await;
+
+        let resp = server
+            .post("/api/v1/ops/projects/whatever/members")
+            .add_header(AUTHORIZATION, auth)
+            .json(&json!({ "principal": "x".repeat(LIMIT * 2), "role": "viewer" }))
+            .await;
+        resp.assert_status(StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(resp.json::<Value>()["error"]["code"], "payload_too_large");
+    }
+}
@@ -0,0 +1,68 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::{
+        Method, StatusCode,
+        header::{
+            ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_ORIGIN,
+            ACCESS_CONTROL_MAX_AGE, ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD, ORIGIN,
+        },
+    };
+    use axum_test::{TestResponse, TestServer};
+    use serial_test::serial;
+
+    use crate::{
+        config::{AppConfig, resolve_cors},
+        create_app, create_mock_shared_state,
+    };
+
+    const ALLOWED: &str = "https://status.example.com";
+
+    async fn server_with_cors(origins: &str) -> TestServer {
+        let mut state = create_mock_shared_state().await.unwrap();
+        let cors = resolve_cors(Some(origins), Some("true"), Some("120")).unwrap();
+        state.config = Arc::new(AppConfig { cors, ..(*state.config).clone() });
+        TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer")
+    }
+
+    async fn preflight(server: &TestServer, origin: &str) -> TestResponse {
+        server
+            .method(Method::OPTIONS, "/api/v1/global/groups")
+            .add_header(ORIGIN, origin)
+            .add_header(ACCESS_CONTROL_REQUEST_METHOD, "POST")
+            .add_header(ACCESS_CONTROL_REQUEST_HEADERS, "authorization,content-type")
+            .await
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn preflight_from_allowed_origin_skips_auth() {
+        let server = server_with_cors(ALLOWED).await;
+        let resp = preflight(&server, ALLOWED).await;
+        resp.assert_status(StatusCode::OK);
+        assert_eq!(resp.header(ACCESS_CONTROL_ALLOW_ORIGIN), ALLOWED);
+        assert_eq!(resp.header(ACCESS_CONTROL_ALLOW_CREDENTIALS), "true");
+        assert_eq!(resp.header(ACCESS_CONTROL_MAX_AGE), "120");
+        let headers = resp.header(ACCESS_CONTROL_ALLOW_HEADERS).to_str().unwrap().to_lowercase();
+        assert!(headers.contains("authorization"), "{}", headers);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn preflight_from_other_origin_is_not_allowed() {
+        let server = server_with_cors(ALLOWED).await;
+        let resp = preflight(&server, "https://evil.example.com").await;
+        assert!(resp.maybe_header(ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn simple_requests_get_the_origin_echoed() {
+        let server = server_with_cors(ALLOWED).await;
+        let resp = server.get("/api/v1/global/groups").add_header(ORIGIN, ALLOWED).await;
+        // Still unauthenticated, but the browser may read the error.
+        resp.assert_status(StatusCode::UNAUTHORIZED);
+        assert_eq!(resp.header(ACCESS_CONTROL_ALLOW_ORIGIN), ALLOWED);
+    }
+}
@@ -0,0 +1,96 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::{HeaderValue, StatusCode, header::AUTHORIZATION};
+    use axum_test::TestServer;
+    use serial_test::serial;
+    use serde_json::{Value, json};
+
+    use crate::{create_app, create_mock_shared_state};
+    use crate::test::helpers::{register_and_login, unique};
+    use crit_shared::util_models::super_permissions;
+
+    async fn count(server: &TestServer, auth: &HeaderValue, path: &str) -> u64 {
+        let resp = server.get(path).add_header(AUTHORIZATION, auth.clone()).await;
+        resp.assert_status_ok();
+        resp.json::<Value>()["count"].as_u64().unwrap()
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_count_global_kind_tracks_inserts_and_deletes() {
+        let state = create_mock_shared_state().await.unwrap();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        let auth = register_and_login(&server, &unique("counter")).await;
+        let kind = unique("countables");
+        let path = format!("/api/v1/ops/count/{}", kind);
+
+        assert_eq!(count(&server, &auth, &path).await, 0);
+        for id in ["a", "b"] {
+            server
+                .post(&format!("/api/v1/global/{}", kind))
+                .add_header(AUTHORIZATION, auth.clone())
+                .json(&json!({ "id": id }))
+                .await
+                .assert_status(StatusCode::CREATED);
+        }
+        assert_eq!(count(&server, &auth, &path).await, 2);
+
+        server
+            .delete(&format!("/api/v1/global/{}/a", kind))
+            .add_header(AUTHORIZATION, auth.clone())
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        assert_eq!(count(&server, &auth, &path).await, 1);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_count_scoped_kind_within_project() {
+        let state = create_mock_shared_state().await.unwrap();
+        let username = unique("pcounter");
+        let db = state.db.clone();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        let auth = register_and_login(&server, &username).await;
+        db.grant_permission(super_permissions::USR_CREATE_PROJECTS, &format!("u_{}", username))
+            .await
+            .unwrap();
+
+        let project = unique("cproj");
+        server
+            .post("/api/v1/global/projects")
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&json!({ "id": &project, "name": "Counted" }))
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        let kind = unique("ctasks");
+        let path = format!("/api/v1/ops/count/{}?project={}", kind, project);
+        for id in ["t1", "t2"] {
+            server
+                .post(&format!("/api/v1/projects/{}/{}", project, kind))
+                .add_header(AUTHORIZATION, auth.clone())
+                .json(&json!({ "id": id }))
+                .await
+                .assert_status(StatusCode::CREATED);
+        }
+        assert_eq!(count(&server, &auth, &path).await, 2);
+
+        server
+            .delete(&format!("/api/v1/projects/{}/{}/t1", project, kind))
+            .add_header(AUTHORIZATION, auth.clone())
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        assert_eq!(count(&server, &auth, &path).await, 1);
+
+        // Global kinds are rejected when a project is given.
+        server
+            .get(&format!("/api/v1/ops/count/users?project={}", project))
+            .add_header(AUTHORIZATION, auth.clone())
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+}
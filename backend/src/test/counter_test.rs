@@ -0,0 +1,55 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use serial_test::serial;
+
+    use crate::create_mock_shared_state;
+    use crate::test::helpers::unique;
+
+    #[tokio::test]
+    #[serial]
+    async fn test_counter_starts_at_zero_and_accepts_path_keys() {
+        let state = create_mock_shared_state().await.unwrap();
+        let key = format!("{}/tickets", unique("cproj"));
+
+        assert_eq!(state.db.increment_counter(&key, 0).await.unwrap(), 0);
+        assert_eq!(state.db.increment_counter(&key, 1).await.unwrap(), 1);
+        assert_eq!(state.db.increment_counter(&key, 5).await.unwrap(), 6);
+        assert_eq!(state.db.increment_counter(&key, -2).await.unwrap(), 4);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_concurrent_increments_lose_no_updates() {
+        const TASKS: usize = 16;
+        const PER_TASK: usize = 10;
+
+        let state = create_mock_shared_state().await.unwrap();
+        let key = unique("ccounter");
+
+        let handles: Vec<_> = (0..TASKS)
+            .map(|_| {
+                let db = state.db.clone();
+                let key = key.clone();
+                tokio::spawn(async move {
+                    let mut seen = Vec::with_capacity(PER_TASK);
+                    for _ in 0..PER_TASK {
+                        seen.push(db.increment_counter(&key, 1).await.unwrap());
+                    }
+                    seen
+                })
+            })
+            .collect();
+
+        let mut all = HashSet::new();
+        for handle in handles {
+            for value in handle.await.unwrap() {
+                assert!(all.insert(value), "value {} handed out twice", value);
+            }
+        }
+        let total = (TASKS * PER_TASK) as i64;
+        assert_eq!(all, (1..=total).collect::<HashSet<_>>());
+        assert_eq!(state.db.increment_counter(&key, 0).await.unwrap(), total);
+    }
+}
@@ -0,0 +1,332 @@
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::db::{CascadeWrite, DEFAULT_MEMBERSHIP_DEPTH, DatabaseInterface, MemberFilter};
+    use crate::test::helpers::unique;
+    use crit_shared::util_models::PrincipalId;
+
+    fn id(prefix: &str) -> PrincipalId {
+        PrincipalId::from(unique(prefix))
+    }
+
+    fn strings(ids: &[PrincipalId]) -> Vec<String> {
+        ids.iter().map(|p| p.to_string()).collect()
+    }
+
+    /// Each check runs twice: against `InMemoryDb`, and serially against the
+    /// ArangoDB test database, so the test double cannot drift from production.
+    macro_rules! conformance {
+        ($($check:ident),* $(,)?) => {
+            mod in_memory {
+                $(
+                    #[tokio::test]
+                    async fn $check() {
+                        super::$check(&crate::db::InMemoryDb::new()).await;
+                    }
+                )*
+            }
+
+            mod arangodb {
+                $(
+                    #[tokio::test]
+                    #[serial_test::serial]
+                    async fn $check() {
+                        let state = crate::create_mock_shared_state().await.unwrap();
+                        super::$check(&*state.db).await;
+                    }
+                )*
+            }
+        };
+    }
+
+    conformance!(
+        create_rejects_duplicate_keys,
+        update_replaces_existing_documents_only,
+        soft_delete_hides_documents,
+        list_pages_in_key_order,
+        membership_edges_are_keyed_principal_group,
+        bulk_membership_add_replaces_stale_edges,
+        member_pages_filter_by_principal_kind,
+        principals_follow_nested_groups,
+        effective_members_survive_diamonds_and_cycles,
+        effective_members_stop_at_the_depth_cap,
+        cascading_delete_is_all_or_none,
+    );
+
+    async fn create_rejects_duplicate_keys(db: &dyn DatabaseInterface) {
+        let key = id("g_conf_dup");
+        db.generic_create("groups", json!({ "_key": &key, "name": "First" })).await.unwrap();
+        assert!(db.generic_create("groups", json!({ "_key": &key, "name": "Second" })).await.is_err());
+
+        let stored = db.generic_get("groups", &key).await.unwrap().unwrap();
+        assert_eq!(stored["name"], "First");
+        assert!(db.generic_get("groups", &id("g_conf_missing")).await.unwrap().is_none());
+    }
+
+    async fn update_replaces_existing_documents_only(db: &dyn DatabaseInterface) {
+        let key = id("g_conf_upd");
+        db.generic_create("groups", json!({ "_key": &key, "name": "Old", "labels": { "a": "1" } }))
+            .await
+            .unwrap();
+        db.generic_update("groups", &key, json!({ "name": "New" })).await.unwrap();
+
+        let stored = db.generic_get("groups", &key).await.unwrap().unwrap();
+        assert_eq!(stored["_key"], key.as_str());
+        assert_eq!(stored["name"], "New");
+        assert!(stored.get("labels").is_none(), "update replaces, it does not merge: {}", stored);
+
+        let missing = id("g_conf_missing");
+        assert!(db.generic_update("groups", &missing, json!({ "name": "X" })).await.is_err());
+        assert!(db.generic_get("groups", &missing).await.unwrap().is_none());
+    }
+
+    async fn soft_delete_hides_documents(db: &dyn DatabaseInterface) {
+        let key = id("g_conf_del");
+        db.generic_create("groups", json!({ "_key": &key })).await.unwrap();
+        db.generic_soft_delete("groups", &key, "u_conf").await.unwrap();
+
+        assert!(db.generic_get("groups", &key).await.unwrap().is_none());
+        assert!(db.generic_soft_delete("groups", &key, "u_conf").await.is_err());
+        assert!(db.generic_soft_delete("groups", &id("g_conf_missing"), "u_conf").await.is_err());
+        // The key stays taken by the tombstone.
+        assert!(db.generic_create("groups", json!({ "_key": &key })).await.is_err());
+    }
+
+    async fn list_pages_in_key_order(db: &dyn DatabaseInterface) {
+        // Other tests share the ArangoDB collections: start from a fresh prefix
+        // so the first page holds only this test's documents.
+        let prefix = id("g_conf_list");
+        let [a, b, c, d] = ["a", "b", "c", "d"].map(|s| format!("{}_{}", prefix, s));
+        for key in [&c, &a, &d, &b] {
+            db.generic_create("groups", json!({ "_key": key, "name": key, "extra": true })).await.unwrap();
+        }
+        db.generic_soft_delete("groups", &b, "u_conf").await.unwrap();
+
+        let fields: &[&str] = &["_key", "name"];
+        let start = format!("{}_", prefix);
+        let page = db.generic_list("groups", Some(fields), Some(2), Some(&start)).await.unwrap();
+        assert_eq!(page.docs, vec![json!({ "_key": &a, "name": &a }), json!({ "_key": &c, "name": &c })]);
+        assert!(page.has_more);
+        assert_eq!(page.next_cursor.as_deref(), Some(c.as_str()));
+
+        let next = db.generic_list("groups", None, Some(1), page.next_cursor.as_deref()).await.unwrap();
+        assert_eq!(next.docs[0]["_key"], d.as_str());
+        assert_eq!(next.docs[0]["extra"], true);
+    }
+
+    async fn membership_edges_are_keyed_principal_group(db: &dyn DatabaseInterface) {
+        let user = id("u_conf_mem");
+        let (g1, g2) = (id("g_conf_mem1"), id("g_conf_mem2"));
+        db.generic_create("users", json!({ "_key": &user })).await.unwrap();
+        for group in [&g1, &g2] {
+            db.generic_create("groups", json!({ "_key": group })).await.unwrap();
+            db.add_principal_to_group(&user, group).await.unwrap();
+        }
+        assert!(db.add_principal_to_group(&user, &g1).await.is_err(), "duplicate edge");
+
+        let edge = db.generic_get("memberships", &format!("{}::{}", user, g1)).await.unwrap().unwrap();
+        assert_eq!(edge["_from"], format!("users/{}", user));
+        assert_eq!(edge["_to"], format!("groups/{}", g1));
+        assert_eq!(db.list_group_members(&g1).await.unwrap(), vec![user.clone()]);
+        let mut groups = vec![g1.clone(), g2.clone()];
+        groups.sort();
+        assert_eq!(db.list_groups_of(&user).await.unwrap(), groups);
+        assert_eq!(db.count_group_members(&g1).await.unwrap(), 1);
+
+        assert!(db.remove_principal_from_group(&user, &g1).await.unwrap());
+        assert!(!db.remove_principal_from_group(&user, &g1).await.unwrap());
+        assert_eq!(db.count_group_members(&g1).await.unwrap(), 0);
+
+        // g2 loses its only member, so it is reported as left empty.
+        assert_eq!(db.remove_principal_from_all_groups(&user).await.unwrap(), vec![g2.clone()]);
+        assert!(db.list_groups_of(&user).await.unwrap().is_empty());
+    }
+
+    async fn bulk_membership_add_replaces_stale_edges(db: &dyn DatabaseInterface) {
+        let (a, b) = (id("u_conf_bulk_a"), id("u_conf_bulk_b"));
+        let group = id("g_conf_bulk");
+        for user in [&a, &b] {
+            db.generic_create("users", json!({ "_key": user })).await.unwrap();
+        }
+        db.generic_create("groups", json!({ "_key": &group })).await.unwrap();
+        // A soft-deleted edge still holds its key.
+        db.add_principal_to_group(&a, &group).await.unwrap();
+        db.generic_soft_delete("memberships", &format!("{}::{}", a, group), "u_conf").await.unwrap();
+        assert!(db.list_group_members(&group).await.unwrap().is_empty());
+
+        db.add_principals_to_group(&[a.clone(), b.clone()], &group).await.unwrap();
+        let mut expected = vec![a.clone(), b.clone()];
+        expected.sort();
+        assert_eq!(db.list_group_members(&group).await.unwrap(), expected);
+        let edge = db.generic_get("memberships", &format!("{}::{}", a, group)).await.unwrap().unwrap();
+        assert_eq!(edge["_from"], format!("users/{}", a));
+
+        db.remove_all_members_of_group(&group).await.unwrap();
+    }
+
+    async fn member_pages_filter_by_principal_kind(db: &dyn DatabaseInterface) {
+        let tag = unique("conf_page");
+        let group = PrincipalId::group(&tag);
+        let child = PrincipalId::group(&format!("{}_child", tag));
+        let users: Vec<PrincipalId> = ["a", "b", "c"].iter().map(|s| PrincipalId::user(&format!("{}_{}", tag, s))).collect();
+        db.generic_create("groups", json!({ "_key": &group })).await.unwrap();
+        db.generic_create("groups", json!({ "_key": &child })).await.unwrap();
+        for user in &users {
+            db.generic_create("users", json!({ "_key": user })).await.unwrap();
+        }
+        let mut all = users.clone();
+        all.push(child.clone());
+        db.add_principals_to_group(&all, &group).await.unwrap();
+        all.sort();
+
+        let page = db.list_members(&group, MemberFilter::All, 0, None).await.unwrap();
+        assert_eq!((page.members, page.total), (strings(&all), 4));
+        let page = db.list_members(&group, MemberFilter::Groups, 0, None).await.unwrap();
+        assert_eq!((page.members, page.total), (strings(std::slice::from_ref(&child)), 1));
+
+        let page = db.list_members(&group, MemberFilter::Users, 0, Some(2)).await.unwrap();
+        assert_eq!((page.members, page.total), (strings(&users[..2]), 3));
+        let page = db.list_members(&group, MemberFilter::Users, 2, Some(2)).await.unwrap();
+        assert_eq!((page.members, page.total), (strings(&users[2..]), 3));
+        // Past the end, and an empty page, still report the total.
+        let page = db.list_members(&group, MemberFilter::Users, 3, Some(2)).await.unwrap();
+        assert_eq!((page.members.len(), page.total), (0, 3));
+        let page = db.list_members(&group, MemberFilter::All, 1, Some(0)).await.unwrap();
+        assert_eq!((page.members.len(), page.total), (0, 4));
+
+        db.remove_all_members_of_group(&group).await.unwrap();
+    }
+
+    async fn principals_follow_nested_groups(db: &dyn DatabaseInterface) {
+        let user = id("u_conf_nest");
+        let (inner, outer) = (id("g_conf_inner"), id("g_conf_outer"));
+        db.generic_create("users", json!({ "_key": &user })).await.unwrap();
+        for group in [&inner, &outer] {
+            db.generic_create("groups", json!({ "_key": group })).await.unwrap();
+        }
+        db.add_principal_to_group(&user, &inner).await.unwrap();
+        db.add_principal_to_group(&inner, &outer).await.unwrap();
+
+        let mut principals = db.get_user_principals(&user).await.unwrap();
+        principals.sort();
+        let mut expected = vec![user.clone(), inner.clone(), outer.clone()];
+        expected.sort();
+        assert_eq!(principals, expected);
+
+        let mut members = db.get_all_group_members_transitive(&outer).await.unwrap();
+        members.sort();
+        let mut expected = vec![user.clone(), inner.clone()];
+        expected.sort();
+        assert_eq!(members, expected);
+
+        // A soft-deleted group is no longer a principal; traversal still passes through it.
+        db.generic_soft_delete("groups", &inner, "u_conf").await.unwrap();
+        let mut principals = db.get_user_principals(&user).await.unwrap();
+        principals.sort();
+        let mut expected = vec![user.clone(), outer.clone()];
+        expected.sort();
+        assert_eq!(principals, expected);
+
+        db.remove_principal_from_all_groups(&user).await.unwrap();
+        db.remove_principal_from_all_groups(&inner).await.unwrap();
+    }
+
+    async fn effective_members_survive_diamonds_and_cycles(db: &dyn DatabaseInterface) {
+        let user = id("u_conf_eff");
+        let [top, left, right, bottom] = ["top", "left", "right", "bottom"].map(|s| id(&format!("g_conf_eff{}", s)));
+        db.generic_create("users", json!({ "_key": &user })).await.unwrap();
+        for group in [&top, &left, &right, &bottom] {
+            db.generic_create("groups", json!({ "_key": group })).await.unwrap();
+        }
+        // Diamond: top ⊃ left, right ⊃ bottom ⊃ user; plus a cycle bottom ⊃ top.
+        db.add_principal_to_group(&left, &top).await.unwrap();
+        db.add_principal_to_group(&right, &top).await.unwrap();
+        db.add_principal_to_group(&bottom, &left).await.unwrap();
+        db.add_principal_to_group(&bottom, &right).await.unwrap();
+        db.add_principal_to_group(&user, &bottom).await.unwrap();
+        db.add_principal_to_group(&top, &bottom).await.unwrap();
+
+        let eff = db.resolve_effective_members(&top).await.unwrap();
+        assert_eq!(eff.group, top);
+        assert_eq!(eff.users.len(), 1);
+        assert_eq!(eff.users[0].principal, user);
+        assert_eq!(eff.users[0].path.len(), 4);
+        assert_eq!(eff.users[0].path.first().map(String::as_str), Some(top.as_str()));
+        assert_eq!(eff.users[0].path.last().map(String::as_str), Some(user.as_str()));
+
+        let mut groups = db.get_groups_of_principal(&user).await.unwrap();
+        groups.sort();
+        let mut expected = vec![top.clone(), left.clone(), right.clone(), bottom.clone()];
+        expected.sort();
+        assert_eq!(groups, expected);
+
+        for group in [&top, &left, &right, &bottom] {
+            db.remove_all_members_of_group(group).await.unwrap();
+        }
+    }
+
+    async fn effective_members_stop_at_the_depth_cap(db: &dyn DatabaseInterface) {
+        let user = id("u_conf_deep");
+        // chain[0] ⊃ chain[1] ⊃ … ⊃ chain[cap]: the user sits one level past the cap.
+        let chain: Vec<PrincipalId> =
+            (0..=DEFAULT_MEMBERSHIP_DEPTH).map(|i| id(&format!("g_conf_deep{}", i))).collect();
+        db.generic_create("users", json!({ "_key": &user })).await.unwrap();
+        for group in &chain {
+            db.generic_create("groups", json!({ "_key": group })).await.unwrap();
+        }
+        for pair in chain.windows(2) {
+            db.add_principal_to_group(&pair[1], &pair[0]).await.unwrap();
+        }
+        db.add_principal_to_group(&user, chain.last().unwrap()).await.unwrap();
+
+        assert!(db.resolve_effective_members(&chain[0]).await.is_err());
+        assert!(db.get_groups_of_principal(&user).await.is_err());
+        // One level shallower is within the cap.
+        let eff = db.resolve_effective_members(&chain[1]).await.unwrap();
+        assert_eq!(eff.users.len(), 1);
+        assert_eq!(eff.users[0].path.len(), DEFAULT_MEMBERSHIP_DEPTH as usize + 1);
+
+        for group in &chain {
+            db.remove_all_members_of_group(group).await.unwrap();
+        }
+    }
+
+    async fn cascading_delete_is_all_or_none(db: &dyn DatabaseInterface) {
+        let (user, other) = (id("u_conf_casc"), id("u_conf_casc_other"));
+        let (solo, team) = (id("g_conf_casc_solo"), id("g_conf_casc_team"));
+        let project = unique("conf_casc_proj");
+        for u in [&user, &other] {
+            db.generic_create("users", json!({ "_key": u })).await.unwrap();
+        }
+        for g in [&solo, &team] {
+            db.generic_create("groups", json!({ "_key": g })).await.unwrap();
+        }
+        db.generic_create("projects", json!({ "_key": &project, "name": "Old" })).await.unwrap();
+        db.add_principal_to_group(&user, &solo).await.unwrap();
+        db.add_principals_to_group(&[user.clone(), other.clone()], &team).await.unwrap();
+
+        let rename = CascadeWrite::Replace {
+            collection: "projects".into(),
+            key: project.clone(),
+            doc: json!({ "name": "New" }),
+        };
+        let missing = CascadeWrite::SoftDelete { collection: "projects".into(), key: unique("conf_casc_missing") };
+
+        // One failing write leaves everything as it was.
+        let failed = db.soft_delete_cascading("users", &user, "u_conf", &[rename.clone(), missing]).await;
+        assert!(failed.is_err());
+        assert_eq!(db.generic_get("projects", &project).await.unwrap().unwrap()["name"], "Old");
+        assert!(db.generic_get("users", &user).await.unwrap().is_some());
+        assert_eq!(db.list_groups_of(&user).await.unwrap().len(), 2);
+
+        let emptied = db.soft_delete_cascading("users", &user, "u_conf", &[rename]).await.unwrap();
+        assert_eq!(emptied, strings(std::slice::from_ref(&solo)));
+        assert_eq!(db.generic_get("projects", &project).await.unwrap().unwrap()["name"], "New");
+        assert!(db.generic_get("users", &user).await.unwrap().is_none());
+        assert!(db.list_groups_of(&user).await.unwrap().is_empty());
+        assert_eq!(db.list_group_members(&team).await.unwrap(), strings(std::slice::from_ref(&other)));
+
+        db.remove_all_members_of_group(&team).await.unwrap();
+    }
+}
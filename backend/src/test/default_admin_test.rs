@@ -0,0 +1,50 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum_test::TestServer;
+    use serial_test::serial;
+
+    use crate::{
+        config::resolve_default_admin, create_app, create_default_user, create_mock_shared_state, schema::*,
+    };
+    use crate::test::helpers::unique;
+    use crit_shared::util_models::super_permissions;
+
+    #[tokio::test]
+    #[serial]
+    async fn test_flag_off_creates_no_admin() {
+        let state = create_mock_shared_state().await.unwrap();
+        let name = unique("noadmin");
+        let admin = resolve_default_admin(Some("false"), Some(&name), Some("s3cret-pw"), true).unwrap();
+        assert_eq!(admin, None);
+
+        let created = create_default_user(&state.db, &state.auth, admin.as_ref()).await.unwrap();
+        assert_eq!(created, None);
+        assert!(state.db.get_user_by_id(&format!("u_{}", name)).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_flag_on_creates_custom_admin_once() {
+        let state = create_mock_shared_state().await.unwrap();
+        let name = unique("ops");
+        let admin = resolve_default_admin(Some("true"), Some(&name), Some("s3cret-pw"), false).unwrap();
+
+        let created = create_default_user(&state.db, &state.auth, admin.as_ref()).await.unwrap();
+        let user_id = format!("u_{}", name);
+        assert_eq!(created.as_deref(), Some(user_id.as_str()));
+        assert!(state.db.has_permission(&user_id, super_permissions::ADM_GODMODE).await.unwrap());
+
+        // Existing user: skipped, not recreated.
+        let again = create_default_user(&state.db, &state.auth, admin.as_ref()).await.unwrap();
+        assert_eq!(again, None);
+
+        let server = TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        server
+            .post("/api/v1/login")
+            .json(&LoginRequest { user: name, password: "s3cret-pw".into() })
+            .await
+            .assert_status_ok();
+    }
+}
@@ -0,0 +1,124 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::{HeaderValue, StatusCode, header::AUTHORIZATION};
+    use axum_test::TestServer;
+    use serial_test::serial;
+    use serde_json::Value;
+
+    use crate::{create_app, create_mock_shared_state, schema::*};
+    use crit_shared::requests::{KindInfo, ListResponse};
+    use crit_shared::util_models::super_permissions;
+    use crate::test::helpers::{register_and_login, unique};
+
+    /// A fresh user holding ADM_GODMODE, logged in.
+    async fn godmode_server() -> (TestServer, HeaderValue) {
+        let state = create_mock_shared_state().await.unwrap();
+        let user = unique("describer");
+        let server = TestServer::new(create_app(Arc::new(state.clone()))).expect("Failed to create TestServer");
+        server
+            .post("/api/v1/register")
+            .json(&RegisterRequest { user: user.clone(), password: "password123".into() })
+            .await
+            .assert_status(StatusCode::CREATED);
+        state
+            .db
+            .grant_permission(super_permissions::ADM_GODMODE, &format!("u_{}", user))
+            .await
+            .unwrap();
+        let resp = server
+            .post("/api/v1/login")
+            .json(&LoginRequest { user, password: "password123".into() })
+            .await;
+        resp.assert_status_ok();
+        let token = format!("Bearer {}", resp.json::<LoginResponse>().token).parse().unwrap();
+        (server, token)
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_describe_registered_kind() {
+        let (server, token) = godmode_server().await;
+
+        let resp = server.get("/api/v1/ops/describe/users").add_header(AUTHORIZATION, token).await;
+        resp.assert_status_ok();
+        let body: Value = resp.json();
+        assert_eq!(body["kind"], "users");
+        assert_eq!(body["model"], "User");
+        assert_eq!(body["id_prefix"], "u_");
+        assert_eq!(body["backend"], "arangodb");
+        assert_eq!(body["dedicated_controller"], true);
+        assert!(body["count"].as_u64().unwrap() >= 1);
+        let fields: Vec<&str> = body["fields"].as_array().unwrap().iter().map(|f| f["name"].as_str().unwrap()).collect();
+        assert!(fields.contains(&"personal"));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_describe_unknown_kind_lists_known_kinds() {
+        let (server, token) = godmode_server().await;
+
+        let resp = server.get("/api/v1/ops/describe/widgets").add_header(AUTHORIZATION, token).await;
+        resp.assert_status(StatusCode::NOT_FOUND);
+        let message = resp.text();
+        assert!(message.contains("widgets"));
+        assert!(message.contains("groups, pipeline_accounts, projects, service_accounts, users"), "{}", message);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_list_kinds_for_any_user() {
+        let state = create_mock_shared_state().await.unwrap();
+        let server = TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        let token = register_and_login(&server, &unique("kinds")).await;
+
+        let resp = server.get("/api/v1/ops/kinds").add_header(AUTHORIZATION, token).await;
+        resp.assert_status_ok();
+        let kinds = resp.json::<ListResponse<KindInfo>>().items;
+        let users = kinds.iter().find(|k| k.name == "users").expect("users listed");
+        assert_eq!(users.id_prefix, "u_");
+        assert!(!users.scoped);
+        assert!(users.brief_fields.contains(&"id".to_string()));
+        assert!(users.brief_fields.contains(&"personal".to_string()));
+        assert!(!users.brief_fields.contains(&"password_hash".to_string()));
+        assert!(kinds.iter().any(|k| k.name == "groups" && k.id_prefix == "g_"));
+
+        for (name, aliases) in [("users", vec!["user", "u"]), ("groups", vec!["group", "g"]), ("projects", vec!["project"])] {
+            let kind = kinds.iter().find(|k| k.name == name).expect(name);
+            assert_eq!(kind.key_field, "id", "{}", name);
+            assert_eq!(kind.aliases, aliases, "{}", name);
+        }
+
+        // Kinds with a controller but no resource descriptor are listed too.
+        let names: Vec<&str> = kinds.iter().map(|k| k.name.as_str()).collect();
+        assert!(names.contains(&"memberships") && names.contains(&"quotas"), "{:?}", names);
+        assert!(names.windows(2).all(|w| w[0] < w[1]), "{:?}", names);
+
+        // A fresh user holds no user-manager rights and belongs to no group.
+        for name in ["groups", "memberships", "quotas"] {
+            let kind = kinds.iter().find(|k| k.name == name).expect(name);
+            assert!(!kind.can_list, "{}", name);
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_list_kinds_can_list_follows_the_caller() {
+        let (server, token) = godmode_server().await;
+
+        let resp = server.get("/api/v1/ops/kinds").add_header(AUTHORIZATION, token).await;
+        resp.assert_status_ok();
+        for kind in resp.json::<ListResponse<KindInfo>>().items {
+            assert_eq!(kind.can_list, !kind.scoped, "{}", kind.name);
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_list_kinds_requires_login() {
+        let state = create_mock_shared_state().await.unwrap();
+        let server = TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        server.get("/api/v1/ops/kinds").await.assert_status(StatusCode::UNAUTHORIZED);
+    }
+}
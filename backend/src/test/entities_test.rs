@@ -0,0 +1,86 @@
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+    use serde_json::json;
+
+    use crate::create_mock_shared_state;
+    use crate::test::helpers::unique;
+    use crate::db::MemberFilter;
+
+    #[tokio::test]
+    #[serial]
+    async fn test_delete_user_removes_membership_edges() {
+        let state = create_mock_shared_state().await.unwrap();
+        let db = &state.db;
+        let user = unique("u_deluser");
+        let group = unique("g_delgrp");
+
+        db.generic_create("users", json!({ "_key": &user })).await.unwrap();
+        db.generic_create("groups", json!({ "_key": &group, "name": "Del" })).await.unwrap();
+        db.add_principal_to_group(&user, &group, None).await.unwrap();
+        assert_eq!(db.count_group_members(&group).await.unwrap(), 1);
+
+        assert!(db.delete_user(&user, None).await.unwrap());
+        assert!(db.generic_get("users", &user).await.unwrap().is_none());
+        assert_eq!(db.count_group_members(&group).await.unwrap(), 0);
+
+        // Second delete is a no-op, not an error
+        assert!(!db.delete_user(&user, None).await.unwrap());
+
+        db.delete_group(&group, None).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_delete_group_in_transaction_and_remove_member() {
+        let state = create_mock_shared_state().await.unwrap();
+        let db = &state.db;
+        let user = unique("u_txuser");
+        let parent = unique("g_txparent");
+        let child = unique("g_txchild");
+
+        db.generic_create("users", json!({ "_key": &user })).await.unwrap();
+        db.generic_create("groups", json!({ "_key": &parent, "name": "Parent" })).await.unwrap();
+        db.generic_create("groups", json!({ "_key": &child, "name": "Child" })).await.unwrap();
+        db.add_principal_to_group(&user, &child, None).await.unwrap();
+        db.add_principal_to_group(&child, &parent, None).await.unwrap();
+
+        assert!(db.remove_principal_from_group(&user, &child, None).await.unwrap());
+        assert!(!db.remove_principal_from_group(&user, &child, None).await.unwrap());
+
+        let mut tx = db.begin_transaction().await.unwrap();
+        assert!(db.delete_group(&child, Some(&mut tx)).await.unwrap());
+        tx.commit().await.unwrap();
+
+        assert!(db.generic_get("groups", &child).await.unwrap().is_none());
+        assert_eq!(db.count_group_members(&parent).await.unwrap(), 0);
+
+        db.delete_group(&parent, None).await.unwrap();
+        db.delete_user(&user, None).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_member_filters_split_users_and_groups() {
+        let state = create_mock_shared_state().await.unwrap();
+        let db = &state.db;
+        let user = unique("u_filt");
+        let parent = unique("g_filtparent");
+        let child = unique("g_filtchild");
+
+        db.generic_create("users", json!({ "_key": &user })).await.unwrap();
+        db.generic_create("groups", json!({ "_key": &parent, "name": "Parent" })).await.unwrap();
+        db.generic_create("groups", json!({ "_key": &child, "name": "Child" })).await.unwrap();
+        db.add_principal_to_group(&user, &parent, None).await.unwrap();
+        db.add_principal_to_group(&child, &parent, None).await.unwrap();
+
+        let users = db.list_members(&parent, MemberFilter::Users, 0, None).await.unwrap();
+        assert_eq!(users.members, vec![user.clone()]);
+        let groups = db.list_members(&parent, MemberFilter::Groups, 0, None).await.unwrap();
+        assert_eq!(groups.members, vec![child.clone()]);
+
+        db.delete_group(&parent, None).await.unwrap();
+        db.delete_group(&child, None).await.unwrap();
+        db.delete_user(&user, None).await.unwrap();
+    }
+}
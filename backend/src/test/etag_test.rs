@@ -0,0 +1,93 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::{
+        HeaderValue, StatusCode,
+        header::{AUTHORIZATION, ETAG, IF_NONE_MATCH},
+    };
+    use axum_test::TestServer;
+    use serial_test::serial;
+    use serde_json::{Value, json};
+
+    use crate::{create_app, create_mock_shared_state};
+    use crate::test::helpers::{register_and_login, unique};
+
+    /// A logged-in user owning a fresh group; returns the group id.
+    async fn setup() -> (TestServer, HeaderValue, String) {
+        let state = create_mock_shared_state().await.unwrap();
+        let server = TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        let auth = register_and_login(&server, &unique("etaguser")).await;
+        let group = unique("etaggrp");
+        server
+            .post("/api/v1/global/groups")
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&json!({ "id": &group, "name": "Tagged" }))
+            .await
+            .assert_status(StatusCode::CREATED);
+        (server, auth, format!("g_{}", group))
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_get_honors_if_none_match_until_upsert() {
+        let (server, auth, id) = setup().await;
+        let url = format!("/api/v1/global/groups/{}", id);
+
+        let resp = server.get(&url).add_header(AUTHORIZATION, auth.clone()).await;
+        resp.assert_status_ok();
+        let etag = resp.header(ETAG);
+        let mut doc: Value = resp.json();
+
+        let cached = server
+            .get(&url)
+            .add_header(AUTHORIZATION, auth.clone())
+            .add_header(IF_NONE_MATCH, etag.clone())
+            .await;
+        cached.assert_status(StatusCode::NOT_MODIFIED);
+        assert!(cached.as_bytes().is_empty());
+        assert_eq!(cached.header(ETAG), etag);
+
+        doc["name"] = json!("Retagged");
+        server.post(&url).add_header(AUTHORIZATION, auth.clone()).json(&doc).await.assert_status_ok();
+
+        let fresh = server
+            .get(&url)
+            .add_header(AUTHORIZATION, auth.clone())
+            .add_header(IF_NONE_MATCH, etag.clone())
+            .await;
+        fresh.assert_status_ok();
+        assert_ne!(fresh.header(ETAG), etag);
+        assert_eq!(fresh.json::<Value>()["name"], "Retagged");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_list_etag_changes_when_an_item_changes() {
+        let (server, auth, id) = setup().await;
+
+        let resp = server.get("/api/v1/global/groups").add_header(AUTHORIZATION, auth.clone()).await;
+        resp.assert_status_ok();
+        let etag = resp.header(ETAG);
+
+        server
+            .get("/api/v1/global/groups")
+            .add_header(AUTHORIZATION, auth.clone())
+            .add_header(IF_NONE_MATCH, etag.clone())
+            .await
+            .assert_status(StatusCode::NOT_MODIFIED);
+
+        let url = format!("/api/v1/global/groups/{}", id);
+        let mut doc: Value = server.get(&url).add_header(AUTHORIZATION, auth.clone()).await.json();
+        doc["name"] = json!("Relisted");
+        server.post(&url).add_header(AUTHORIZATION, auth.clone()).json(&doc).await.assert_status_ok();
+
+        let fresh = server
+            .get("/api/v1/global/groups")
+            .add_header(AUTHORIZATION, auth)
+            .add_header(IF_NONE_MATCH, etag.clone())
+            .await;
+        fresh.assert_status_ok();
+        assert_ne!(fresh.header(ETAG), etag);
+    }
+}
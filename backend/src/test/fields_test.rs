@@ -0,0 +1,90 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::{StatusCode, header::AUTHORIZATION};
+    use axum_test::TestServer;
+    use serial_test::serial;
+    use serde_json::{Value, json};
+
+    use crate::{create_app, create_mock_shared_state};
+    use crate::test::helpers::{register_and_login, unique};
+
+    #[tokio::test]
+    #[serial]
+    async fn test_fields_select_nested_paths_across_pages() {
+        let state = create_mock_shared_state().await.unwrap();
+        let server = TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        let auth = register_and_login(&server, &unique("fieldsuser")).await;
+
+        let prefix = unique("fieldsgrp");
+        let mut ids = Vec::new();
+        for n in 0..3 {
+            let group = format!("{}_{}", prefix, n);
+            server
+                .post("/api/v1/global/groups")
+                .add_header(AUTHORIZATION, auth.clone())
+                .json(&json!({
+                    "id": &group,
+                    "name": format!("Group {}", n),
+                    "description": "selected away",
+                    "labels": { "team": "sre", "tier": n.to_string() },
+                }))
+                .await
+                .assert_status(StatusCode::CREATED);
+            ids.push(format!("g_{}", group));
+        }
+
+        let mut seen = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let mut url = "/api/v1/global/groups?fields=id,labels.tier&limit=2".to_string();
+            if let Some(c) = &cursor {
+                url.push_str(&format!("&cursor={}", c));
+            }
+            let resp = server.get(&url).add_header(AUTHORIZATION, auth.clone()).await;
+            resp.assert_status_ok();
+            let page: Value = resp.json();
+            let items = page["items"].as_array().unwrap();
+            assert!(items.len() <= 2);
+            for item in items.iter().filter(|i| ids.contains(&i["id"].as_str().unwrap().to_string())) {
+                let n = ids.iter().position(|id| id == &item["id"]).unwrap();
+                assert_eq!(item, &json!({ "id": ids[n], "labels": { "tier": n.to_string() } }));
+                seen.push(n);
+            }
+            if !page["has_more"].as_bool().unwrap() {
+                break;
+            }
+            cursor = Some(page["next_cursor"].as_str().unwrap().to_string());
+        }
+        seen.sort();
+        assert_eq!(seen, vec![0, 1, 2]);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_fields_refuses_secret_fields() {
+        let state = create_mock_shared_state().await.unwrap();
+        let server = TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        let auth = register_and_login(&server, &unique("fieldssecret")).await;
+
+        for fields in ["id,password_hash", "personal.password"] {
+            server
+                .get(&format!("/api/v1/global/users?fields={}", fields))
+                .add_header(AUTHORIZATION, auth.clone())
+                .await
+                .assert_status(StatusCode::BAD_REQUEST);
+        }
+
+        let resp = server
+            .get("/api/v1/global/users?fields=id,personal.name")
+            .add_header(AUTHORIZATION, auth.clone())
+            .await;
+        resp.assert_status_ok();
+        let body: Value = resp.json();
+        for item in body["items"].as_array().unwrap() {
+            let keys: Vec<&String> = item.as_object().unwrap().keys().collect();
+            assert!(keys.iter().all(|k| *k == "id" || *k == "personal"), "{}", item);
+        }
+    }
+}
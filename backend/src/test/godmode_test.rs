@@ -8,19 +8,10 @@ mod tests {
     use serde_json::json;
 
     use crate::{create_app, create_mock_shared_state, schema::*, state::AppState};
+    use crate::test::helpers::{register_and_login, unique};
 
     const ROOT_PASSWORD: &str = "changeme";
 
-    /// Generate a unique username to avoid collisions across test runs.
-    fn unique_user(prefix: &str) -> String {
-        use std::time::{SystemTime, UNIX_EPOCH};
-        let nanos = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .subsec_nanos();
-        format!("{}_{}", prefix, nanos)
-    }
-
     /// Seed the root user if it doesn't exist (mirrors main.rs startup logic).
     async fn ensure_root_user(state: &AppState) {
         if state.db.get_user_by_id("u_root").await.unwrap().is_none() {
@@ -48,30 +39,6 @@ mod tests {
         resp.json::<LoginResponse>().token
     }
 
-    /// Register a user and return the JWT token.
-    async fn register_and_login(server: &TestServer, username: &str) -> String {
-        let password = "testpassword123";
-
-        server
-            .post("/api/v1/register")
-            .json(&RegisterRequest {
-                user: username.to_string(),
-                password: password.to_string(),
-            })
-            .await
-            .assert_status(StatusCode::CREATED);
-
-        let resp = server
-            .post("/api/v1/login")
-            .json(&LoginRequest {
-                user: username.to_string(),
-                password: password.to_string(),
-            })
-            .await;
-        resp.assert_status_ok();
-        resp.json::<LoginResponse>().token
-    }
-
     #[tokio::test]
     #[serial]
     async fn test_root_godmode_bypasses_user_creation_acl() {
@@ -96,7 +63,7 @@ mod tests {
 
         // Root (with godmode) can create a user via gitops API — normally
         // requires ADM_USER_MANAGER which root doesn't explicitly have.
-        let new_user = unique_user("godcreated");
+        let new_user = unique("godcreated");
         let resp = server
             .post(&format!("/api/v1/global/users"))
             .add_header(
@@ -129,16 +96,16 @@ mod tests {
         let server =
             TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
 
-        let regular_user = unique_user("regular");
-        let regular_token = register_and_login(&server, &regular_user).await;
+        let regular_user = unique("regular");
+        let regular_auth = register_and_login(&server, &regular_user).await;
 
         // Regular user (no godmode, no ADM_USER_MANAGER) should be denied
-        let target_user = unique_user("target");
+        let target_user = unique("target");
         let resp = server
             .post(&format!("/api/v1/global/users"))
             .add_header(
                 axum::http::header::AUTHORIZATION,
-                format!("Bearer {}", regular_token).parse::<axum::http::HeaderValue>().unwrap(),
+                regular_auth.clone(),
             )
             .json(&json!({
                 "id": &target_user,
@@ -170,16 +137,16 @@ mod tests {
             TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
 
         let root_token = login_root(&server).await;
-        let regular_user = unique_user("grpmaker");
-        let regular_token = register_and_login(&server, &regular_user).await;
+        let regular_user = unique("grpmaker");
+        let regular_auth = register_and_login(&server, &regular_user).await;
 
         // Regular user creates a group (they have USR_CREATE_GROUPS by default)
-        let group_id = unique_user("secretgrp");
+        let group_id = unique("secretgrp");
         let resp = server
             .post("/api/v1/global/groups")
             .add_header(
                 axum::http::header::AUTHORIZATION,
-                format!("Bearer {}", regular_token).parse::<axum::http::HeaderValue>().unwrap(),
+                regular_auth.clone(),
             )
             .json(&json!({
                 "id": &group_id,
@@ -220,16 +187,16 @@ mod tests {
             TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
 
         let root_token = login_root(&server).await;
-        let regular_user = unique_user("grpowner");
-        let regular_token = register_and_login(&server, &regular_user).await;
+        let regular_user = unique("grpowner");
+        let regular_auth = register_and_login(&server, &regular_user).await;
 
         // Regular user creates a group
-        let group_id = unique_user("delgrp");
+        let group_id = unique("delgrp");
         server
             .post("/api/v1/global/groups")
             .add_header(
                 axum::http::header::AUTHORIZATION,
-                format!("Bearer {}", regular_token).parse::<axum::http::HeaderValue>().unwrap(),
+                regular_auth.clone(),
             )
             .json(&json!({
                 "id": &group_id,
@@ -267,16 +234,16 @@ mod tests {
         let server =
             TestServer::new(create_app(Arc::new(state.clone()))).expect("Failed to create TestServer");
 
-        let user = unique_user("promoted");
-        let token = register_and_login(&server, &user).await;
+        let user = unique("promoted");
+        let auth = register_and_login(&server, &user).await;
 
         // Before godmode: cannot create users
-        let target = unique_user("shouldfail");
+        let target = unique("shouldfail");
         let resp = server
             .post("/api/v1/global/users")
             .add_header(
                 axum::http::header::AUTHORIZATION,
-                format!("Bearer {}", token).parse::<axum::http::HeaderValue>().unwrap(),
+                auth.clone(),
             )
             .json(&json!({
                 "id": &target,
@@ -306,12 +273,12 @@ mod tests {
             .await;
 
         // After godmode: can create users
-        let target2 = unique_user("shouldpass");
+        let target2 = unique("shouldpass");
         let resp = server
             .post("/api/v1/global/users")
             .add_header(
                 axum::http::header::AUTHORIZATION,
-                format!("Bearer {}", token).parse::<axum::http::HeaderValue>().unwrap(),
+                auth.clone(),
             )
             .json(&json!({
                 "id": &target2,
@@ -341,16 +308,16 @@ mod tests {
             TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
 
         let root_token = login_root(&server).await;
-        let user = unique_user("listmaker");
-        let user_token = register_and_login(&server, &user).await;
+        let user = unique("listmaker");
+        let user_auth = register_and_login(&server, &user).await;
 
         // User creates a group (only they have ACL on it)
-        let group_id = unique_user("listgrp");
+        let group_id = unique("listgrp");
         server
             .post("/api/v1/global/groups")
             .add_header(
                 axum::http::header::AUTHORIZATION,
-                format!("Bearer {}", user_token).parse::<axum::http::HeaderValue>().unwrap(),
+                user_auth.clone(),
             )
             .json(&json!({
                 "id": &group_id,
@@ -380,4 +347,118 @@ mod tests {
             group_id
         );
     }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_deleted_user_token_is_rejected_despite_cache() {
+        let state = create_mock_shared_state().await.unwrap();
+        ensure_root_user(&state).await;
+        state
+            .db
+            .grant_permission(
+                crit_shared::util_models::super_permissions::ADM_GODMODE,
+                "u_root",
+            )
+            .await
+            .unwrap();
+
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+
+        let root_token = login_root(&server).await;
+        let doomed = unique("doomed");
+        let doomed_auth = register_and_login(&server, &doomed).await;
+
+        // First request caches the "active user" answer.
+        server
+            .get("/api/v1/global/groups")
+            .add_header(axum::http::header::AUTHORIZATION, doomed_auth.clone())
+            .await
+            .assert_status_ok();
+
+        server
+            .delete(&format!("/api/v1/global/users/u_{}", doomed))
+            .add_header(
+                axum::http::header::AUTHORIZATION,
+                format!("Bearer {}", root_token).parse::<axum::http::HeaderValue>().unwrap(),
+            )
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+
+        // The delete invalidated the cache entry, so the token stops working at once.
+        server
+            .get("/api/v1/global/groups")
+            .add_header(axum::http::header::AUTHORIZATION, doomed_auth)
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_reload_admins_applies_new_grant_immediately() {
+        let state = create_mock_shared_state().await.unwrap();
+        ensure_root_user(&state).await;
+        state
+            .db
+            .grant_permission(crit_shared::util_models::super_permissions::ADM_GODMODE, "u_root")
+            .await
+            .unwrap();
+
+        let server =
+            TestServer::new(create_app(Arc::new(state.clone()))).expect("Failed to create TestServer");
+        let root_auth = format!("Bearer {}", login_root(&server).await).parse::<axum::http::HeaderValue>().unwrap();
+        let user = unique("reloaded");
+        let user_auth = register_and_login(&server, &user).await;
+
+        // The refusal is cached...
+        server
+            .get("/api/v1/adm/audit")
+            .add_header(axum::http::header::AUTHORIZATION, user_auth.clone())
+            .await
+            .assert_status(StatusCode::FORBIDDEN);
+        state
+            .db
+            .grant_permission(crit_shared::util_models::super_permissions::ADM_GODMODE, &format!("u_{}", user))
+            .await
+            .unwrap();
+        server
+            .get("/api/v1/adm/audit")
+            .add_header(axum::http::header::AUTHORIZATION, user_auth.clone())
+            .await
+            .assert_status(StatusCode::FORBIDDEN);
+
+        // ...until an admin drops the cached decisions.
+        let resp = server
+            .post("/api/v1/adm/reload-admins")
+            .add_header(axum::http::header::AUTHORIZATION, root_auth)
+            .await;
+        resp.assert_status_ok();
+        assert!(resp.json::<serde_json::Value>()["cleared"].as_u64().unwrap() >= 1);
+        server
+            .get("/api/v1/adm/audit")
+            .add_header(axum::http::header::AUTHORIZATION, user_auth)
+            .await
+            .assert_status_ok();
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_every_adm_route_refuses_non_admins() {
+        let state = create_mock_shared_state().await.unwrap();
+        let server = TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        let auth = register_and_login(&server, &unique("notadmin")).await;
+
+        for (method, path) in [
+            ("GET", "/api/v1/adm/audit"),
+            ("POST", "/api/v1/adm/reload-admins"),
+            ("POST", "/api/v1/adm/rebuild_indexes"),
+        ] {
+            let request = match method {
+                "GET" => server.get(path),
+                _ => server.post(path),
+            };
+            let resp = request.add_header(axum::http::header::AUTHORIZATION, auth.clone()).await;
+            assert_eq!(resp.status_code(), StatusCode::FORBIDDEN, "{} {}", method, path);
+        }
+    }
 }
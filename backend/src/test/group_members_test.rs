@@ -0,0 +1,163 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::{HeaderValue, StatusCode, header::AUTHORIZATION};
+    use axum_test::TestServer;
+    use serial_test::serial;
+    use serde_json::{Value, json};
+
+    use crate::{create_app, create_mock_shared_state};
+    use crate::test::helpers::{register_and_login, unique};
+
+    async fn create_group(server: &TestServer, auth: &HeaderValue, prefix: &str) -> String {
+        let group = unique(prefix);
+        server
+            .post("/api/v1/global/groups")
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&json!({ "id": &group, "name": "Members" }))
+            .await
+            .assert_status(StatusCode::CREATED);
+        format!("g_{}", group)
+    }
+
+    async fn add(server: &TestServer, auth: &HeaderValue, group: &str, principal: &str) -> StatusCode {
+        server
+            .post(&format!("/api/v1/ops/groups/{}/members", group))
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&json!({ "principal": principal }))
+            .await
+            .status_code()
+    }
+
+    async fn members(server: &TestServer, auth: &HeaderValue, group: &str) -> Vec<Value> {
+        let resp = server
+            .get(&format!("/api/v1/ops/groups/{}/members", group))
+            .add_header(AUTHORIZATION, auth.clone())
+            .await;
+        resp.assert_status_ok();
+        resp.json::<Value>()["items"].as_array().unwrap().clone()
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_add_list_and_remove_group_members() {
+        let state = create_mock_shared_state().await.unwrap();
+        let db = state.db.clone();
+        let server = TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        let owner = unique("gmowner");
+        let member = unique("gmmember");
+        let owner_auth = register_and_login(&server, &owner).await;
+        let member_auth = register_and_login(&server, &member).await;
+        let (owner_id, member_id) = (format!("u_{}", owner), format!("u_{}", member));
+        let group = create_group(&server, &owner_auth, "gmgrp").await;
+
+        assert_eq!(add(&server, &owner_auth, &group, &member_id).await, StatusCode::CREATED);
+        assert_eq!(add(&server, &owner_auth, &group, &member_id).await, StatusCode::OK);
+        assert_eq!(add(&server, &owner_auth, &group, "u_nobody_here").await, StatusCode::NOT_FOUND);
+        assert_eq!(db.list_groups_of(&member_id).await.unwrap(), vec![group.clone()]);
+
+        let items = members(&server, &member_auth, &group).await;
+        let ids: Vec<&str> = items.iter().map(|i| i["principal"].as_str().unwrap()).collect();
+        let mut expected = vec![member_id.as_str(), owner_id.as_str()];
+        expected.sort();
+        assert_eq!(ids, expected);
+        assert!(items.iter().all(|i| i["brief"]["id"].is_string()));
+
+        let page = |query: &str| {
+            server
+                .get(&format!("/api/v1/ops/groups/{}/members?{}", group, query))
+                .add_header(AUTHORIZATION, owner_auth.clone())
+        };
+        let body = page("offset=1&limit=1").await.json::<Value>();
+        assert_eq!(body["items"].as_array().unwrap().len(), 1);
+        assert_eq!(body["items"][0]["principal"], expected[1]);
+        assert_eq!((body["total"].as_u64(), body["offset"].as_u64()), (Some(2), Some(1)));
+        let body = page("type=groups").await.json::<Value>();
+        assert_eq!((body["items"].as_array().unwrap().len(), body["total"].as_u64()), (0, Some(0)));
+        page("type=accounts").await.assert_status(StatusCode::BAD_REQUEST);
+
+        // Plain members only got READ on the group.
+        assert_eq!(add(&server, &member_auth, &group, &owner_id).await, StatusCode::FORBIDDEN);
+
+        server
+            .delete(&format!("/api/v1/ops/groups/{}/members/{}", group, member_id))
+            .add_header(AUTHORIZATION, owner_auth.clone())
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        server
+            .delete(&format!("/api/v1/ops/groups/{}/members/{}", group, member_id))
+            .add_header(AUTHORIZATION, owner_auth.clone())
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+        let items = members(&server, &owner_auth, &group).await;
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0]["principal"], owner_id.as_str());
+        assert!(db.list_groups_of(&member_id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_group_cannot_contain_itself() {
+        let state = create_mock_shared_state().await.unwrap();
+        let server = TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        let auth = register_and_login(&server, &unique("gmcycle")).await;
+        let inner = create_group(&server, &auth, "gminner").await;
+        let outer = create_group(&server, &auth, "gmouter").await;
+
+        assert_eq!(add(&server, &auth, &inner, &inner).await, StatusCode::UNPROCESSABLE_ENTITY);
+
+        assert_eq!(add(&server, &auth, &outer, &inner).await, StatusCode::CREATED);
+        assert_eq!(add(&server, &auth, &inner, &outer).await, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_bulk_add_mixed_batch() {
+        let state = create_mock_shared_state().await.unwrap();
+        let server = TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        let owner = unique("gmbulkowner");
+        let (old, new) = (unique("gmbulkold"), unique("gmbulknew"));
+        let owner_auth = register_and_login(&server, &owner).await;
+        register_and_login(&server, &old).await;
+        let new_auth = register_and_login(&server, &new).await;
+        let (owner_id, old_id, new_id) = (format!("u_{}", owner), format!("u_{}", old), format!("u_{}", new));
+        let ghost_id = format!("u_{}", unique("gmbulkghost"));
+        let group = create_group(&server, &owner_auth, "gmbulk").await;
+        assert_eq!(add(&server, &owner_auth, &group, &old_id).await, StatusCode::CREATED);
+
+        let batch = json!([&old_id, &new_id, &owner_id, &ghost_id, &new_id]);
+        let bulk = |query: &'static str| {
+            server
+                .post(&format!("/api/v1/ops/groups/{}/members{}", group, query))
+                .add_header(AUTHORIZATION, owner_auth.clone())
+                .json(&batch)
+        };
+
+        // By default a missing principal fails the whole batch.
+        let resp = bulk("").await;
+        resp.assert_status(StatusCode::NOT_FOUND);
+        assert!(resp.json::<Value>()["error"]["message"].as_str().unwrap().contains(&ghost_id));
+        assert_eq!(members(&server, &owner_auth, &group).await.len(), 2);
+
+        let resp = bulk("?missing=skip").await;
+        resp.assert_status(StatusCode::CREATED);
+        let body = resp.json::<Value>();
+        assert_eq!(body["added"], json!([&new_id]));
+        assert_eq!(body["already_members"], json!([&old_id, &owner_id]));
+        assert_eq!(body["skipped"], json!([&ghost_id]));
+        // New members can read the group, as after a single add.
+        assert_eq!(members(&server, &new_auth, &group).await.len(), 3);
+
+        let resp = bulk("?missing=skip").await;
+        resp.assert_status(StatusCode::OK);
+        assert_eq!(resp.json::<Value>()["added"], json!([]));
+
+        server
+            .post(&format!("/api/v1/ops/groups/{}/members", group))
+            .add_header(AUTHORIZATION, owner_auth.clone())
+            .json(&json!([]))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,32 @@
+//! Helpers shared by the handler tests.
+
+use axum::http::{HeaderValue, StatusCode};
+use axum_test::TestServer;
+
+use crate::schema::*;
+
+/// Password every user registered through [`register_and_login`] gets.
+pub const PASSWORD: &str = "testpassword123";
+
+/// `prefix` plus the current sub-second nanos, so names don't collide across
+/// test runs against a persistent database.
+pub fn unique(prefix: &str) -> String {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().subsec_nanos();
+    format!("{}_{}", prefix, nanos)
+}
+
+/// Register `username` with [`PASSWORD`], log in and return the `Bearer` header.
+pub async fn register_and_login(server: &TestServer, username: &str) -> HeaderValue {
+    server
+        .post("/api/v1/register")
+        .json(&RegisterRequest { user: username.to_string(), password: PASSWORD.into() })
+        .await
+        .assert_status(StatusCode::CREATED);
+    let resp = server
+        .post("/api/v1/login")
+        .json(&LoginRequest { user: username.to_string(), password: PASSWORD.into() })
+        .await;
+    resp.assert_status_ok();
+    format!("Bearer {}", resp.json::<LoginResponse>().token).parse().unwrap()
+}
@@ -0,0 +1,128 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::{HeaderValue, StatusCode, header::AUTHORIZATION};
+    use axum_test::TestServer;
+    use serde_json::{Value, json};
+    use serial_test::serial;
+
+    use crate::{
+        config::{AppConfig, IdPrefixPolicy},
+        create_app, create_mock_shared_state,
+        schema::*,
+        state::AppState,
+    };
+    use crate::test::helpers::unique;
+
+    const ROOT_PASSWORD: &str = "changeme";
+
+    async fn ensure_root_godmode(state: &AppState) {
+        if state.db.get_user_by_id("u_root").await.unwrap().is_none() {
+            use crate::controllers::gitops_controller::inject_create_defaults;
+            let mut body = json!({ "id": "u_root", "password": ROOT_PASSWORD });
+            inject_create_defaults(&mut body, "u_root");
+            let doc = state.controller.for_kind("users").to_internal(body, &state.auth).unwrap();
+            state.db.generic_create("users", doc).await.unwrap();
+        }
+        state
+            .db
+            .grant_permission(crit_shared::util_models::super_permissions::ADM_GODMODE, "u_root")
+            .await
+            .unwrap();
+    }
+
+    async fn root_server() -> (TestServer, HeaderValue) {
+        root_server_with(IdPrefixPolicy::Add).await
+    }
+
+    async fn root_server_with(policy: IdPrefixPolicy) -> (TestServer, HeaderValue) {
+        let mut state = create_mock_shared_state().await.unwrap();
+        state.config = Arc::new(AppConfig { id_prefix_policy: policy, ..(*state.config).clone() });
+        ensure_root_godmode(&state).await;
+        let server = TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        let resp = server
+            .post("/api/v1/login")
+            .json(&LoginRequest { user: "root".into(), password: ROOT_PASSWORD.into() })
+            .await;
+        resp.assert_status_ok();
+        let auth = format!("Bearer {}", resp.json::<LoginResponse>().token).parse().unwrap();
+        (server, auth)
+    }
+
+    async fn create(server: &TestServer, auth: &HeaderValue, kind: &str, body: Value) -> String {
+        let resp = server
+            .post(&format!("/api/v1/global/{}", kind))
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&body)
+            .await;
+        resp.assert_status(StatusCode::CREATED);
+        resp.json::<Value>()["id"].as_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn user_without_id_gets_prefixed_key() {
+        let (server, auth) = root_server().await;
+        let id = create(&server, &auth, "users", json!({ "password": "pass12345" })).await;
+        assert!(id.starts_with("u_user_"), "{}", id);
+
+        server
+            .get(&format!("/api/v1/global/users/{}", id))
+            .add_header(AUTHORIZATION, auth.clone())
+            .await
+            .assert_status_ok();
+
+        // An empty id counts as missing.
+        let id = create(&server, &auth, "users", json!({ "id": "", "password": "pass12345" })).await;
+        assert!(id.starts_with("u_user_"), "{}", id);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn slug_collision_retries_with_suffix() {
+        let (server, auth) = root_server().await;
+        let name = unique("Release Crew");
+        let slug = name.to_lowercase().replace(' ', "_");
+
+        let first = create(&server, &auth, "groups", json!({ "name": &name })).await;
+        assert_eq!(first, format!("g_{}", slug));
+
+        let second = create(&server, &auth, "groups", json!({ "name": &name })).await;
+        let suffix = second.strip_prefix(&format!("g_{}_", slug)).expect(&second);
+        assert_eq!(suffix.len(), 6);
+
+        // A client-supplied id that collides is still a plain conflict.
+        server
+            .post("/api/v1/global/groups")
+            .add_header(AUTHORIZATION, auth)
+            .json(&json!({ "id": &first, "name": &name }))
+            .await
+            .assert_status(StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn client_ids_get_the_kind_prefix_or_are_refused() {
+        let (server, auth) = root_server().await;
+        let bare = unique("ops");
+        let id = create(&server, &auth, "groups", json!({ "id": &bare, "name": "Ops" })).await;
+        assert_eq!(id, format!("g_{}", bare));
+
+        // Projects have no prefix, so the policy never touches their ids.
+        let project = unique("proj");
+        assert_eq!(create(&server, &auth, "projects", json!({ "id": &project })).await, project);
+
+        let (server, auth) = root_server_with(IdPrefixPolicy::Reject).await;
+        let resp = server
+            .post("/api/v1/global/groups")
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&json!({ "id": unique("ops"), "name": "Ops" }))
+            .await;
+        resp.assert_status(StatusCode::BAD_REQUEST);
+        assert_eq!(resp.json::<Value>()["error"]["details"]["field"], "id");
+
+        let prefixed = format!("g_{}", unique("ops"));
+        assert_eq!(create(&server, &auth, "groups", json!({ "id": &prefixed, "name": "Ops" })).await, prefixed);
+    }
+}
@@ -0,0 +1,158 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::{HeaderValue, StatusCode, header::AUTHORIZATION};
+    use axum_test::TestServer;
+    use serde_json::{Value, json};
+    use serial_test::serial;
+
+    use crate::{
+        create_app, create_mock_shared_state, middleware::auth::IMPERSONATE_HEADER, schema::*,
+        state::AppState,
+    };
+    use crate::test::helpers::unique;
+
+    const ROOT_PASSWORD: &str = "changeme";
+
+    async fn ensure_root_godmode(state: &AppState) {
+        if state.db.get_user_by_id("u_root").await.unwrap().is_none() {
+            use crate::controllers::gitops_controller::inject_create_defaults;
+            let mut body = json!({ "id": "u_root", "password": ROOT_PASSWORD });
+            inject_create_defaults(&mut body, "u_root");
+            let doc = state.controller.for_kind("users").to_internal(body, &state.auth).unwrap();
+            state.db.generic_create("users", doc).await.unwrap();
+        }
+        state
+            .db
+            .grant_permission(crit_shared::util_models::super_permissions::ADM_GODMODE, "u_root")
+            .await
+            .unwrap();
+    }
+
+    async fn login(server: &TestServer, user: &str, password: &str) -> HeaderValue {
+        let resp = server
+            .post("/api/v1/login")
+            .json(&LoginRequest { user: user.to_string(), password: password.to_string() })
+            .await;
+        resp.assert_status_ok();
+        format!("Bearer {}", resp.json::<LoginResponse>().token).parse().unwrap()
+    }
+
+    async fn register(server: &TestServer, prefix: &str) -> (String, HeaderValue) {
+        let username = unique(prefix);
+        server
+            .post("/api/v1/register")
+            .json(&RegisterRequest { user: username.clone(), password: "testpassword123".into() })
+            .await
+            .assert_status(StatusCode::CREATED);
+        let auth = login(server, &username, "testpassword123").await;
+        (username, auth)
+    }
+
+    async fn setup() -> (TestServer, HeaderValue) {
+        let state = create_mock_shared_state().await.unwrap();
+        ensure_root_godmode(&state).await;
+        let server = TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        let root_auth = login(&server, "root", ROOT_PASSWORD).await;
+        (server, root_auth)
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn admin_acts_as_user_and_audit_keeps_both() {
+        let (server, root_auth) = setup().await;
+        let (username, _) = register(&server, "imp_target").await;
+        let target = format!("u_{}", username);
+
+        // The group is created as the impersonated user...
+        let group = unique("impgrp");
+        server
+            .post("/api/v1/global/groups")
+            .add_header(AUTHORIZATION, root_auth.clone())
+            .add_header(IMPERSONATE_HEADER, HeaderValue::from_str(&target).unwrap())
+            .json(&json!({ "id": &group, "name": "Impersonated" }))
+            .await
+            .assert_status(StatusCode::CREATED);
+        let created = server
+            .get(&format!("/api/v1/global/groups/g_{}", group))
+            .add_header(AUTHORIZATION, root_auth.clone())
+            .await
+            .json::<Value>();
+        assert_eq!(created["state"]["created_by"], target);
+
+        // ...and the audit entry names both identities.
+        let items = server
+            .get(&format!("/api/v1/adm/audit?actor={}", target))
+            .add_header(AUTHORIZATION, root_auth.clone())
+            .await
+            .json::<Value>()["items"]
+            .as_array()
+            .unwrap()
+            .clone();
+        let entry = items
+            .iter()
+            .find(|e| e["method"] == "POST" && e["path"] == "/api/v1/global/groups")
+            .expect("impersonated create should be audited");
+        assert_eq!(entry["actor"], target);
+        assert_eq!(entry["real_actor"], "u_root");
+
+        // A plain request records no real_actor.
+        server
+            .delete(&format!("/api/v1/global/groups/g_{}", group))
+            .add_header(AUTHORIZATION, root_auth.clone())
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        let items = server
+            .get("/api/v1/adm/audit?actor=u_root")
+            .add_header(AUTHORIZATION, root_auth)
+            .await
+            .json::<Value>()["items"]
+            .as_array()
+            .unwrap()
+            .clone();
+        let delete = items.iter().find(|e| e["method"] == "DELETE").unwrap();
+        assert_eq!(delete["actor"], "u_root");
+        assert!(delete.get("real_actor").is_none());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn non_admin_cannot_impersonate() {
+        let (server, _) = setup().await;
+        let (_, user_auth) = register(&server, "imp_plain").await;
+
+        let resp = server
+            .get("/api/v1/global/groups")
+            .add_header(AUTHORIZATION, user_auth)
+            .add_header(IMPERSONATE_HEADER, HeaderValue::from_static("u_root"))
+            .await;
+        resp.assert_status(StatusCode::FORBIDDEN);
+        assert_eq!(resp.json::<Value>()["error"]["code"], "forbidden");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn impersonating_unknown_user_is_not_found() {
+        let (server, root_auth) = setup().await;
+        let resp = server
+            .get("/api/v1/global/groups")
+            .add_header(AUTHORIZATION, root_auth)
+            .add_header(IMPERSONATE_HEADER, HeaderValue::from_static("nobody_here"))
+            .await;
+        resp.assert_status(StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn impersonating_a_group_is_refused() {
+        let (server, root_auth) = setup().await;
+        let resp = server
+            .get("/api/v1/global/groups")
+            .add_header(AUTHORIZATION, root_auth)
+            .add_header(IMPERSONATE_HEADER, HeaderValue::from_static("g_admins"))
+            .await;
+        resp.assert_status(StatusCode::BAD_REQUEST);
+        assert!(resp.json::<Value>()["error"]["message"].as_str().unwrap().contains("g_admins"));
+    }
+}
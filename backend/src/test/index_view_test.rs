@@ -0,0 +1,125 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::{HeaderValue, StatusCode, header::AUTHORIZATION};
+    use axum_test::TestServer;
+    use serial_test::serial;
+    use serde_json::{Value, json};
+
+    use crate::{create_app, create_mock_shared_state};
+    use crate::test::helpers::{register_and_login, unique};
+    use crit_shared::util_models::super_permissions;
+
+    async fn set_role(server: &TestServer, auth: &HeaderValue, project: &str, principal: &str, role: &str) {
+        server
+            .post(&format!("/api/v1/ops/projects/{}/members", project))
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&json!({ "principal": principal, "role": role }))
+            .await
+            .assert_status_ok();
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_owner_change_moves_project_between_index_entries() {
+        let state = Arc::new(create_mock_shared_state().await.unwrap());
+        let server = TestServer::new(create_app(state.clone())).expect("Failed to create TestServer");
+        let old_owner = unique("oldowner");
+        let new_owner = unique("newowner");
+        let old_auth = register_and_login(&server, &old_owner).await;
+        register_and_login(&server, &new_owner).await;
+        let (old_id, new_id) = (format!("u_{}", old_owner), format!("u_{}", new_owner));
+
+        state.db.grant_permission(super_permissions::USR_CREATE_PROJECTS, &old_id).await.unwrap();
+        let project = unique("indexed");
+        server
+            .post("/api/v1/global/projects")
+            .add_header(AUTHORIZATION, old_auth.clone())
+            .json(&json!({ "id": &project, "name": "Indexed" }))
+            .await
+            .assert_status(StatusCode::CREATED);
+        assert_eq!(state.indexes.query_index("projects_by_owner", &old_id).await, vec![project.clone()]);
+
+        set_role(&server, &old_auth, &project, &new_id, "owner").await;
+        set_role(&server, &old_auth, &project, &old_id, "admin").await;
+
+        assert!(state.indexes.query_index("projects_by_owner", &old_id).await.is_empty());
+        assert_eq!(state.indexes.query_index("projects_by_owner", &new_id).await, vec![project.clone()]);
+        // Still in the ACL, as an admin.
+        assert!(state.indexes.query_index("projects_by_principal", &old_id).await.contains(&project));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_rebuild_index_requires_godmode_and_known_name() {
+        let state = Arc::new(create_mock_shared_state().await.unwrap());
+        let server = TestServer::new(create_app(state.clone())).expect("Failed to create TestServer");
+        let plain_auth = register_and_login(&server, &unique("plain")).await;
+        let user = unique("rebuilder");
+        let auth = register_and_login(&server, &user).await;
+        state.db.grant_permission(super_permissions::ADM_GODMODE, &format!("u_{}", user)).await.unwrap();
+
+        server
+            .post("/api/v1/ops/indexes/projects_by_owner/rebuild")
+            .add_header(AUTHORIZATION, plain_auth)
+            .await
+            .assert_status_not_ok();
+        let resp = server
+            .post("/api/v1/ops/indexes/projects_by_owner/rebuild")
+            .add_header(AUTHORIZATION, auth.clone())
+            .await;
+        resp.assert_status_ok();
+        assert_eq!(resp.json::<Value>()["index"], "projects_by_owner");
+
+        server
+            .post("/api/v1/ops/indexes/nope/rebuild")
+            .add_header(AUTHORIZATION, auth)
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_adm_rebuild_indexes_repairs_drift() {
+        let state = Arc::new(create_mock_shared_state().await.unwrap());
+        let server = TestServer::new(create_app(state.clone())).expect("Failed to create TestServer");
+        let plain_auth = register_and_login(&server, &unique("plain")).await;
+        let admin = unique("idxadmin");
+        let auth = register_and_login(&server, &admin).await;
+        let admin_id = format!("u_{}", admin);
+        state.db.grant_permission(super_permissions::ADM_GODMODE, &admin_id).await.unwrap();
+
+        let project = unique("drifted");
+        server
+            .post("/api/v1/global/projects")
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&json!({ "id": &project, "name": "Drifted" }))
+            .await
+            .assert_status(StatusCode::CREATED);
+        // Corrupt the index as a write behind the handlers' back would.
+        state.indexes.apply("projects", &project, None).await;
+        assert!(!state.indexes.query_index("projects_by_owner", &admin_id).await.contains(&project));
+
+        server
+            .post("/api/v1/adm/rebuild_indexes")
+            .add_header(AUTHORIZATION, plain_auth)
+            .await
+            .assert_status(StatusCode::FORBIDDEN);
+        let resp = server
+            .post("/api/v1/adm/rebuild_indexes")
+            .add_header(AUTHORIZATION, auth.clone())
+            .await;
+        resp.assert_status_ok();
+        let rebuilt = resp.json::<Value>()["rebuilt"].clone();
+        assert!(rebuilt["projects_by_owner"].as_u64().unwrap() >= 1, "{}", rebuilt);
+        assert!(rebuilt.get("projects_by_principal").is_some(), "{}", rebuilt);
+        assert!(state.indexes.query_index("projects_by_owner", &admin_id).await.contains(&project));
+
+        server
+            .post("/api/v1/adm/rebuild_indexes?index=nope")
+            .add_header(AUTHORIZATION, auth)
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,153 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::{Value, json};
+
+    use crate::controllers::Controller;
+    use crate::controllers::gitops_controller::KindController;
+    use crate::db::{DatabaseInterface, InMemoryDb};
+    use crate::middleware::auth::Auth;
+    use crit_shared::compute_value_hash;
+    use crit_shared::util_models::{Permissions, super_permissions};
+
+    /// g_ops, whose ACL lets u_alice modify it, with u_alice as its only member.
+    async fn setup() -> (Arc<InMemoryDb>, Controller) {
+        let db = Arc::new(InMemoryDb::new());
+        for user in ["u_alice", "u_bob", "u_carol"] {
+            db.insert("users", json!({ "_key": user })).unwrap();
+        }
+        for group in ["g_ops", "g_admins"] {
+            db.insert(
+                "groups",
+                json!({
+                    "_key": group,
+                    "acl": {
+                        "list": [{ "permissions": Permissions::ROOT.bits(), "principals": ["u_alice"] }],
+                        "last_mod_date": "2025-01-01T00:00:00Z",
+                    },
+                }),
+            )
+            .unwrap();
+        }
+        db.add_principal_to_group(&"u_alice".into(), &"g_ops".into()).await.unwrap();
+        db.add_principal_to_group(&"u_carol".into(), &"g_admins".into()).await.unwrap();
+        db.grant_permission(super_permissions::ADM_USER_MANAGER, "g_admins");
+
+        let controller = Controller::new(db.clone());
+        (db, controller)
+    }
+
+    /// What the create handler does: to_internal, insert, after_create.
+    async fn create_membership(db: &InMemoryDb, controller: &Controller, actor: &str, body: Value) -> String {
+        let ctrl = &controller.membership;
+        assert!(ctrl.can_create(actor, &body).await.unwrap(), "{} may not create {}", actor, body);
+        let doc = ctrl.to_internal(body, &Auth::new(b"secret", 1)).unwrap();
+        let key = doc["_key"].as_str().unwrap().to_string();
+        db.insert("memberships", doc).unwrap();
+        ctrl.after_create(&key, actor, db).await.unwrap();
+        key
+    }
+
+    #[tokio::test]
+    async fn group_acl_decides_who_may_add_members() {
+        let (_db, controller) = setup().await;
+        let ctrl = &controller.membership;
+        let body = json!({ "id": "u_bob::g_ops", "principal": "u_bob", "group": "g_ops" });
+
+        assert!(ctrl.can_create("u_alice", &body).await.unwrap());
+        assert!(!ctrl.can_create("u_bob", &body).await.unwrap());
+        // u_carol holds ADM_USER_MANAGER through g_admins.
+        assert!(ctrl.can_create("u_carol", &body).await.unwrap());
+        assert!(!ctrl.can_create("u_alice", &json!({ "principal": "u_bob" })).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn created_membership_is_an_edge_and_grants_read() {
+        let (db, controller) = setup().await;
+        let body = json!({ "id": "u_bob::g_ops", "principal": "u_bob", "group": "g_ops" });
+        let key = create_membership(&db, &controller, "u_alice", body).await;
+
+        let edge = db.generic_get("memberships", &key).await.unwrap().unwrap();
+        assert_eq!(edge["_from"], "users/u_bob");
+        assert_eq!(edge["_to"], "groups/g_ops");
+        let external = controller.membership.to_external(edge.clone());
+        assert_eq!(external["id"], "u_bob::g_ops");
+        assert!(external.get("_from").is_none());
+
+        // The new member can now read the group's memberships.
+        assert!(controller.membership.can_read("u_bob", Some(&edge)).await.unwrap());
+        assert!(db.get_user_principals(&"u_bob".into()).await.unwrap().contains(&"g_ops".to_string()));
+        assert_eq!(db.count_group_members(&"g_ops".into()).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn removing_the_last_member_deletes_the_group() {
+        let (db, controller) = setup().await;
+        let ctrl = &controller.membership;
+
+        let edge = db.generic_get("memberships", "u_alice::g_ops").await.unwrap().unwrap();
+        assert!(ctrl.can_write("u_alice", Some(&edge)).await.unwrap());
+        db.generic_soft_delete("memberships", "u_alice::g_ops", "u_alice").await.unwrap();
+        ctrl.after_delete("u_alice::g_ops", &*db).await.unwrap();
+
+        assert!(db.generic_get("groups", "g_ops").await.unwrap().is_none());
+        assert_eq!(db.raw("groups", "g_ops").unwrap()["deletion"]["deleted_by"], "system");
+        // Unrelated groups are untouched.
+        assert!(db.generic_get("groups", "g_admins").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn reconcile_restores_edge_endpoints() {
+        let (db, controller) = setup().await;
+        let ctrl = &controller.membership;
+        db.insert("memberships", json!({ "_key": "u_bob::g_ops", "principal": "u_bob", "group": "g_ops" }))
+            .unwrap();
+
+        let stored = db.generic_get("memberships", "u_bob::g_ops").await.unwrap().unwrap();
+        let observed = ctrl.observe(&stored, &*db).await.unwrap();
+        assert_ne!(observed.as_deref(), stored["hash_code"].as_str());
+
+        ctrl.reconcile(&stored, &*db).await.unwrap();
+        let fixed = db.generic_get("memberships", "u_bob::g_ops").await.unwrap().unwrap();
+        assert_eq!(fixed["_from"], "users/u_bob");
+        assert_eq!(fixed["_to"], "groups/g_ops");
+        assert_eq!(ctrl.observe(&fixed, &*db).await.unwrap().as_deref(), fixed["hash_code"].as_str());
+        // The declared member gets the READ grant a create would have given.
+        assert!(ctrl.can_read("u_bob", Some(&fixed)).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn reconcile_restores_declared_state_instead_of_accepting_drift() {
+        let (db, controller) = setup().await;
+        let ctrl = &controller.membership;
+        let body = json!({ "id": "u_bob::g_ops", "principal": "u_bob", "group": "g_ops" });
+        let key = create_membership(&db, &controller, "u_alice", body).await;
+        let mut declared = db.generic_get("memberships", &key).await.unwrap().unwrap();
+        let hash = compute_value_hash(&declared);
+        declared["hash_code"] = json!(hash);
+        db.generic_update("memberships", &key, declared).await.unwrap();
+
+        // Repointed at another member outside the API.
+        let mut drifted = db.generic_get("memberships", &key).await.unwrap().unwrap();
+        drifted["principal"] = json!("u_carol");
+        drifted["_from"] = json!("users/u_carol");
+        db.generic_update("memberships", &key, drifted.clone()).await.unwrap();
+        assert_ne!(ctrl.observe(&drifted, &*db).await.unwrap(), Some(hash.clone()));
+
+        ctrl.reconcile(&drifted, &*db).await.unwrap();
+        let fixed = db.generic_get("memberships", &key).await.unwrap().unwrap();
+        assert_eq!(fixed["principal"], "u_bob");
+        assert_eq!(fixed["_from"], "users/u_bob");
+        assert_eq!(fixed["hash_code"], json!(hash));
+        assert_eq!(ctrl.observe(&fixed, &*db).await.unwrap(), Some(hash.clone()));
+
+        // Drift in fields the key does not declare is reported, not re-stamped.
+        let mut relabelled = fixed.clone();
+        relabelled["labels"] = json!({ "team": "elsewhere" });
+        db.generic_update("memberships", &key, relabelled.clone()).await.unwrap();
+        assert!(ctrl.reconcile(&relabelled, &*db).await.is_err());
+        let kept = db.generic_get("memberships", &key).await.unwrap().unwrap();
+        assert_eq!(kept["hash_code"], json!(hash));
+    }
+}
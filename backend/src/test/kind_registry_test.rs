@@ -0,0 +1,74 @@
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use crate::controllers::{Controller, gitops_controller::KindController};
+    use crate::create_mock_shared_state;
+
+    /// Same controller instance, compared by address.
+    fn same(a: &dyn KindController, b: &dyn KindController) -> bool {
+        std::ptr::addr_eq(a as *const dyn KindController, b as *const dyn KindController)
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_registry_dispatches_to_dedicated_controllers() {
+        let state = create_mock_shared_state().await.unwrap();
+        let c = &state.controller;
+
+        assert!(same(c.for_kind("users"), &c.user));
+        assert!(same(c.for_kind("groups"), &c.group));
+        assert!(same(c.for_kind("memberships"), &c.membership));
+        assert!(same(c.for_kind("projects"), &c.project));
+
+        // Unregistered kinds share the permissive default controller.
+        let default = c.for_kind("widgets");
+        assert!(same(default, c.for_kind("tickets")));
+        assert!(!Controller::registered_kinds().any(|k| same(c.for_kind(k), default)));
+    }
+
+    #[test]
+    fn test_model_kind_is_the_route_kind() {
+        use crit_shared::data_models::{Group, Project, User};
+        use crate::resource_registry::ResourceRegistry;
+
+        assert_eq!(User::kind(), "users");
+        assert_eq!(Group::kind(), "groups");
+        assert_eq!(Project::kind(), "projects");
+        let registry = ResourceRegistry::default();
+        assert_eq!(registry.get(Project::kind()).map(|d| d.model), Some("Project"));
+        assert!(Controller::registered_kinds().any(|k| k == User::kind()));
+    }
+
+    #[test]
+    fn test_registered_kinds_in_order() {
+        assert_eq!(
+            Controller::registered_kinds().collect::<Vec<_>>(),
+            ["users", "groups", "memberships", "projects", "quotas"]
+        );
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_registered_controllers_keep_their_behaviour() {
+        let state = create_mock_shared_state().await.unwrap();
+        let c = &state.controller;
+
+        // Users: no ACL, password hashed on the way in and stripped on the way out.
+        let doc = c
+            .for_kind("users")
+            .to_internal(serde_json::json!({ "id": "u_parity", "password": "secret123" }), &state.auth)
+            .unwrap();
+        assert_eq!(doc["_key"], "u_parity");
+        assert!(doc.get("password").is_none());
+        assert!(c.for_kind("users").to_external(doc).get("password_hash").is_none());
+
+        // Projects: plain ids, not themselves project-scoped.
+        let doc = c
+            .for_kind("projects")
+            .to_internal(serde_json::json!({ "id": "parity", "name": "Parity" }), &state.auth)
+            .unwrap();
+        assert_eq!(doc["_key"], "parity");
+        assert!(!c.for_kind("projects").is_scoped());
+    }
+}
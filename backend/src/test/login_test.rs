@@ -9,13 +9,7 @@ mod tests {
     use serde_json::json;
 
     use crate::{create_app, create_mock_shared_state, schema::*, validation::limit_min_length};
-
-    /// Generate a unique username to avoid collisions across test runs against a persistent DB.
-    fn unique_user(prefix: &str) -> String {
-        use std::time::{SystemTime, UNIX_EPOCH};
-        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().subsec_nanos();
-        format!("{}_{}", prefix, nanos)
-    }
+    use crate::test::helpers::unique;
 
     #[tokio::test]
     #[serial]
@@ -40,7 +34,7 @@ mod tests {
         let server =
             TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
 
-        let email = unique_user("reglogin");
+        let email = unique("reglogin");
         let password = "securepassword123";
 
         let register_request = RegisterRequest {
@@ -70,7 +64,7 @@ mod tests {
         let server =
             TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
 
-        let user = unique_user("invalidcreds");
+        let user = unique("invalidcreds");
 
         server
             .post("/api/v1/register")
@@ -89,4 +83,110 @@ mod tests {
         let login_response = server.post("/api/v1/login").json(&login_request).await;
         login_response.assert_status(StatusCode::UNAUTHORIZED);
     }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_uid_is_normalized_on_register_and_login() {
+        let state = create_mock_shared_state().await.unwrap();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+
+        let user = unique("root");
+        let password = "securepassword123";
+
+        server
+            .post("/api/v1/register")
+            .json(&RegisterRequest {
+                user: format!("{} ", user.to_uppercase()),
+                password: password.to_string(),
+            })
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        for variant in [user.clone(), format!("  {}", user.to_uppercase())] {
+            server
+                .post("/api/v1/login")
+                .json(&LoginRequest {
+                    user: variant,
+                    password: password.to_string(),
+                })
+                .await
+                .assert_status_ok();
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_prefixed_username_is_a_separate_user() {
+        let state = Arc::new(create_mock_shared_state().await.unwrap());
+        let server =
+            TestServer::new(create_app(state.clone())).expect("Failed to create TestServer");
+
+        let bob = unique("bob");
+        let prefixed = format!("u_{}", bob);
+
+        for (user, password) in [(&bob, "bobpassword123"), (&prefixed, "otherpassword123")] {
+            server
+                .post("/api/v1/register")
+                .json(&RegisterRequest {
+                    user: user.clone(),
+                    password: password.to_string(),
+                })
+                .await
+                .assert_status(StatusCode::CREATED);
+        }
+
+        assert!(state.db.get_user_by_id(&format!("u_{}", bob)).await.unwrap().is_some());
+        assert!(state.db.get_user_by_id(&format!("u_u_{}", bob)).await.unwrap().is_some());
+
+        // Each name signs in with its own password only.
+        for (user, password, status) in [
+            (&bob, "bobpassword123", StatusCode::OK),
+            (&prefixed, "otherpassword123", StatusCode::OK),
+            (&prefixed, "bobpassword123", StatusCode::UNAUTHORIZED),
+        ] {
+            server
+                .post("/api/v1/login")
+                .json(&LoginRequest {
+                    user: user.clone(),
+                    password: password.to_string(),
+                })
+                .await
+                .assert_status(status);
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_bcrypt_hash_is_upgraded_on_login() {
+        let state = Arc::new(create_mock_shared_state().await.unwrap());
+        let server = TestServer::new(create_app(state.clone())).expect("Failed to create TestServer");
+
+        let user = unique("rehash");
+        let password = "securepassword123";
+        server
+            .post("/api/v1/register")
+            .json(&RegisterRequest { user: user.clone(), password: password.to_string() })
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        // Simulate an account created before the switch to Argon2id.
+        let legacy = bcrypt::hash(password, 4).unwrap();
+        state.db.set_user_password_hash(&user, &legacy).await.unwrap();
+
+        let login = LoginRequest { user: user.clone(), password: password.to_string() };
+        server.post("/api/v1/login").json(&login).await.assert_status_ok();
+
+        let stored = state.db.get_user_by_id(&user).await.unwrap().unwrap().password_hash;
+        assert!(stored.starts_with("$argon2id$"), "{}", stored);
+        assert!(!state.auth.needs_rehash(&stored));
+
+        // The upgraded hash keeps working, and still rejects a wrong password.
+        server.post("/api/v1/login").json(&login).await.assert_status_ok();
+        server
+            .post("/api/v1/login")
+            .json(&LoginRequest { user, password: "wrong_password".to_string() })
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
 }
@@ -0,0 +1,80 @@
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use axum::http::{HeaderValue, StatusCode, header::AUTHORIZATION};
+    use axum_test::TestServer;
+    use serial_test::serial;
+    use serde_json::{Value, json};
+
+    use crate::{config::AppConfig, create_app, create_mock_shared_state};
+    use crate::test::helpers::{register_and_login, unique};
+
+    /// Build a test server whose long-poll timeout is `timeout_secs`.
+    async fn server_with_timeout(timeout_secs: u64) -> TestServer {
+        let mut state = create_mock_shared_state().await.unwrap();
+        state.config = Arc::new(AppConfig {
+            long_poll_timeout_secs: timeout_secs,
+            ..(*state.config).clone()
+        });
+        TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer")
+    }
+
+    async fn current_version(server: &TestServer, auth: &HeaderValue, kind: &str) -> String {
+        let resp = server
+            .get(&format!("/api/v1/ops/list/{}", kind))
+            .add_header(AUTHORIZATION, auth.clone())
+            .await;
+        resp.assert_status_ok();
+        resp.json::<Value>()["version"].as_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_long_poll_times_out_with_304_when_nothing_changes() {
+        let server = server_with_timeout(1).await;
+        let auth = register_and_login(&server, &unique("lpidle")).await;
+        let kind = unique("lp_idle");
+
+        let version = current_version(&server, &auth, &kind).await;
+        let resp = server
+            .get(&format!("/api/v1/ops/list/{}?since={}", kind, version))
+            .add_header(AUTHORIZATION, auth.clone())
+            .await;
+        resp.assert_status(StatusCode::NOT_MODIFIED);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_long_poll_is_unblocked_by_concurrent_upsert() {
+        let server = server_with_timeout(10).await;
+        let auth = register_and_login(&server, &unique("lpwatch")).await;
+        let kind = unique("lp_change");
+
+        let version = current_version(&server, &auth, &kind).await;
+
+        let poll = async {
+            server
+                .get(&format!("/api/v1/ops/list/{}?since={}", kind, version))
+                .add_header(AUTHORIZATION, auth.clone())
+                .await
+        };
+        let write = async {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            server
+                .post(&format!("/api/v1/global/{}/item1", kind))
+                .add_header(AUTHORIZATION, auth.clone())
+                .json(&json!({ "value": 1 }))
+                .await
+                .assert_status_ok();
+        };
+        let started = std::time::Instant::now();
+        let (resp, _) = tokio::join!(poll, write);
+
+        resp.assert_status_ok();
+        assert!(started.elapsed() < Duration::from_secs(10));
+        let body = resp.json::<Value>();
+        assert_ne!(body["version"].as_str().unwrap(), version);
+        assert_eq!(body["items"].as_array().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,71 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::{HeaderValue, StatusCode, header::{AUTHORIZATION, CONTENT_TYPE}};
+    use axum_test::TestServer;
+    use serial_test::serial;
+    use serde_json::{Value, json};
+
+    use crate::{create_app, create_mock_shared_state};
+    use crate::test::helpers::{register_and_login, unique};
+
+    #[tokio::test]
+    #[serial]
+    async fn test_merge_patch_merges_members_and_deletes_nulls() {
+        let state = create_mock_shared_state().await.unwrap();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        let auth = register_and_login(&server, &unique("patchuser")).await;
+        let group = unique("patchgrp");
+        let id = format!("g_{}", group);
+
+        server
+            .post("/api/v1/global/groups")
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&json!({
+                "id": &group,
+                "name": "Patched",
+                "labels": { "team": "sre", "tier": "1" },
+                "annotations": { "note": "keep" },
+            }))
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        let patch = json!({ "labels": { "tier": null, "env": "prod" }, "annotations": null });
+        server
+            .patch(&format!("/api/v1/global/groups/{}", id))
+            .add_header(AUTHORIZATION, auth.clone())
+            .add_header(CONTENT_TYPE, HeaderValue::from_static("application/merge-patch+json"))
+            .bytes(patch.to_string().into())
+            .await
+            .assert_status_ok();
+
+        let doc: Value = server
+            .get(&format!("/api/v1/global/groups/{}", id))
+            .add_header(AUTHORIZATION, auth.clone())
+            .await
+            .json();
+        assert_eq!(doc["name"], "Patched");
+        assert_eq!(doc["labels"], json!({ "team": "sre", "env": "prod" }));
+        assert_eq!(doc["annotations"], json!({}));
+        assert_eq!(doc["state"]["generation"], 2);
+
+        // A stale hash in the patch is a conflict, like PUT.
+        server
+            .patch(&format!("/api/v1/global/groups/{}", id))
+            .add_header(AUTHORIZATION, auth.clone())
+            .add_header(CONTENT_TYPE, HeaderValue::from_static("application/merge-patch+json"))
+            .bytes(json!({ "name": "X", "hash_code": "stale" }).to_string().into())
+            .await
+            .assert_status(StatusCode::CONFLICT);
+
+        // Plain JSON is not taken for a merge patch.
+        server
+            .patch(&format!("/api/v1/global/groups/{}", id))
+            .add_header(AUTHORIZATION, auth)
+            .json(&json!({ "name": "X" }))
+            .await
+            .assert_status(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+}
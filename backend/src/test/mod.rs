@@ -1,2 +1,41 @@
+#[cfg(test)]
+pub mod helpers;
 pub mod login_test;
-pub mod godmode_test;
\ No newline at end of file
+pub mod godmode_test;
+pub mod long_poll_test;
+pub mod audit_test;
+pub mod entities_test;
+pub mod reconcile_test;
+pub mod project_members_test;
+pub mod count_test;
+pub mod wire_types_test;
+pub mod user_cascade_test;
+pub mod resource_state_test;
+pub mod read_only_test;
+pub mod counter_test;
+pub mod tls_test;
+pub mod yaml_body_test;
+pub mod body_limit_test;
+pub mod kind_registry_test;
+pub mod impersonation_test;
+pub mod cors_test;
+pub mod id_generation_test;
+pub mod refresh_token_test;
+pub mod inmemory_controller_test;
+pub mod oidc_test;
+pub mod default_admin_test;
+pub mod describe_test;
+pub mod require_test;
+pub mod index_view_test;
+pub mod etag_test;
+pub mod noop_upsert_test;
+pub mod merge_patch_test;
+pub mod fields_test;
+pub mod group_members_test;
+pub mod two_factor_test;
+pub mod annotate_test;
+pub mod db_conformance_test;
+pub mod search_test;
+pub mod otel_test;
+pub mod quota_test;
+pub mod watch_test;
@@ -0,0 +1,76 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::{HeaderValue, StatusCode, header::AUTHORIZATION};
+    use axum_test::TestServer;
+    use serial_test::serial;
+    use serde_json::{Value, json};
+
+    use crate::{audit_log::AuditQuery, create_app, create_mock_shared_state};
+    use crate::test::helpers::{register_and_login, unique};
+
+    async fn get_with_history(server: &TestServer, auth: &HeaderValue, id: &str) -> Value {
+        let resp = server
+            .get(&format!("/api/v1/global/groups/{}?with_history=true", id))
+            .add_header(AUTHORIZATION, auth.clone())
+            .await;
+        resp.assert_status_ok();
+        resp.json()
+    }
+
+    async fn upsert(server: &TestServer, auth: &HeaderValue, id: &str, body: &Value) -> Value {
+        let resp = server
+            .post(&format!("/api/v1/global/groups/{}", id))
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(body)
+            .await;
+        resp.assert_status_ok();
+        resp.json()
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_identical_upsert_is_not_written() {
+        let state = Arc::new(create_mock_shared_state().await.unwrap());
+        let server = TestServer::new(create_app(state.clone())).expect("Failed to create TestServer");
+        let user = unique("noopuser");
+        let auth = register_and_login(&server, &user).await;
+        let group = unique("noopgrp");
+        let id = format!("g_{}", group);
+
+        server
+            .post("/api/v1/global/groups")
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&json!({ "id": &group, "name": "Noop" }))
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        let mut body = get_with_history(&server, &auth, &id).await;
+        for key in ["hash_code", "_history", "state"] {
+            body.as_object_mut().unwrap().remove(key);
+        }
+        body["name"] = json!("Noop renamed");
+
+        let first = upsert(&server, &auth, &id, &body).await;
+        assert_eq!(first["action"], "updated");
+        let written = get_with_history(&server, &auth, &id).await;
+
+        let second = upsert(&server, &auth, &id, &body).await;
+        assert_eq!(second["action"], "unchanged");
+        assert_eq!(second["hash"], first["hash"]);
+
+        // Nothing was stored: no new timestamp, generation or history revision.
+        let after = get_with_history(&server, &auth, &id).await;
+        assert_eq!(after["state"], written["state"]);
+        assert_eq!(after["_history"]["revision"], written["_history"]["revision"]);
+
+        // Only the write that changed something is in the audit log.
+        let path = format!("/api/v1/global/groups/{}", id);
+        let entries = state
+            .audit
+            .query(&AuditQuery { actor: Some(format!("u_{}", user)), ..Default::default() })
+            .await;
+        assert_eq!(entries.iter().filter(|e| e.path == path).count(), 1, "{:?}", entries);
+    }
+}
@@ -0,0 +1,223 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{
+        Json, Router,
+        http::StatusCode,
+        routing::{get, post},
+    };
+    use axum_test::TestServer;
+    use jsonwebtoken::{Algorithm, EncodingKey, Header, encode};
+    use serde_json::{Value, json};
+    use serial_test::serial;
+
+    use crate::{
+        config::OidcConfig, create_app, create_mock_shared_state, schema::*, services::oidc::OidcClient,
+        state::AppState,
+    };
+    use crate::test::helpers::unique;
+
+    const SIGNING_KEY: &str = include_str!("fixtures/oidc/signing_key.pem");
+    const OTHER_KEY: &str = include_str!("fixtures/oidc/other_key.pem");
+    const JWKS: &str = include_str!("fixtures/oidc/jwks.json");
+
+    /// What the mock provider puts in the next ID token it hands out.
+    struct NextToken {
+        subject: String,
+        nonce: String,
+        key_pem: &'static str,
+    }
+
+    /// A provider on a local port: discovery, the JWKS from the fixtures, and
+    /// a token endpoint that signs whatever `NextToken` says. Returns the issuer.
+    async fn mock_provider(next: Arc<Mutex<NextToken>>) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let issuer = format!("http://{}", listener.local_addr().unwrap());
+        let discovery = json!({
+            "issuer": issuer,
+            "authorization_endpoint": format!("{}/authorize", issuer),
+            "token_endpoint": format!("{}/token", issuer),
+            "jwks_uri": format!("{}/jwks", issuer),
+        });
+        let jwks: Value = serde_json::from_str(JWKS).unwrap();
+        let token_issuer = issuer.clone();
+        let app = Router::new()
+            .route("/.well-known/openid-configuration", get(move || async move { Json(discovery) }))
+            .route("/jwks", get(move || async move { Json(jwks) }))
+            .route(
+                "/token",
+                post(move || {
+                    let issuer = token_issuer.clone();
+                    let next = next.clone();
+                    async move {
+                        let next = next.lock().unwrap();
+                        let now = chrono::Utc::now().timestamp();
+                        let mut header = Header::new(Algorithm::RS256);
+                        header.kid = Some("test-key".into());
+                        let claims = json!({
+                            "iss": issuer,
+                            "aud": "crit",
+                            "sub": next.subject,
+                            "nonce": next.nonce,
+                            "iat": now,
+                            "exp": now + 300,
+                            "preferred_username": "oidc user",
+                            "email": "oidc.user@example.com",
+                            "name": "OIDC User",
+                        });
+                        let key = EncodingKey::from_rsa_pem(next.key_pem.as_bytes()).unwrap();
+                        Json(json!({ "id_token": encode(&header, &claims, &key).unwrap(), "token_type": "Bearer" }))
+                    }
+                }),
+            );
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        issuer
+    }
+
+    async fn setup(key_pem: &'static str) -> (TestServer, Arc<AppState>, Arc<Mutex<NextToken>>, String) {
+        let next = Arc::new(Mutex::new(NextToken {
+            subject: unique("sub"),
+            nonce: String::new(),
+            key_pem,
+        }));
+        let issuer = mock_provider(next.clone()).await;
+        let mut state = create_mock_shared_state().await.unwrap();
+        state.oidc = Arc::new(Some(OidcClient::new(OidcConfig {
+            issuer: issuer.clone(),
+            client_id: "crit".into(),
+            client_secret: "secret".into(),
+            redirect_url: "http://localhost/api/v1/oauth/callback".into(),
+        })));
+        let state = Arc::new(state);
+        let server = TestServer::new(create_app(state.clone())).expect("Failed to create TestServer");
+        (server, state, next, issuer)
+    }
+
+    /// Follow `/oauth/login` to the provider: returns the `state` it was sent
+    /// and tells the mock provider which nonce to sign.
+    async fn begin_login(server: &TestServer, next: &Mutex<NextToken>) -> String {
+        let response = server.get("/api/v1/oauth/login").await;
+        assert!(response.status_code().is_redirection());
+        let location = url::Url::parse(response.header("location").to_str().unwrap()).unwrap();
+        let query: std::collections::HashMap<_, _> = location.query_pairs().into_owned().collect();
+        next.lock().unwrap().nonce = query["nonce"].clone();
+        query["state"].clone()
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_oidc_login_provisions_user_once() {
+        let (server, state, next, issuer) = setup(SIGNING_KEY).await;
+
+        let login_state = begin_login(&server, &next).await;
+        let response = server
+            .get("/api/v1/oauth/callback")
+            .add_query_params(json!({ "code": "abc", "state": login_state }))
+            .await;
+        response.assert_status_ok();
+        assert!(!response.json::<LoginResponse>().token.is_empty());
+
+        let subject = next.lock().unwrap().subject.clone();
+        let user = state.db.find_user_by_oauth(&issuer, &subject).await.unwrap().expect("user provisioned");
+        assert!(user.id.starts_with("u_oidc_user"));
+        assert_eq!(user.personal.name, "OIDC User");
+
+        // No password opens an OIDC-provisioned account.
+        let password_login = server
+            .post("/api/v1/login")
+            .json(&LoginRequest { user: user.id.to_string(), password: "!".into() })
+            .await;
+        password_login.assert_status(StatusCode::UNAUTHORIZED);
+
+        // The next login maps to the same user.
+        let login_state = begin_login(&server, &next).await;
+        server
+            .get("/api/v1/oauth/callback")
+            .add_query_params(json!({ "code": "abc", "state": login_state }))
+            .await
+            .assert_status_ok();
+        let again = state.db.find_user_by_oauth(&issuer, &subject).await.unwrap().unwrap();
+        assert_eq!(again.id, user.id);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_oidc_read_only_refuses_provisioning_but_signs_in_known_users() {
+        let (server, state, next, issuer) = setup(SIGNING_KEY).await;
+        state.set_read_only(true);
+
+        let login_state = begin_login(&server, &next).await;
+        server
+            .get("/api/v1/oauth/callback")
+            .add_query_params(json!({ "code": "abc", "state": login_state }))
+            .await
+            .assert_status(StatusCode::SERVICE_UNAVAILABLE);
+        let subject = next.lock().unwrap().subject.clone();
+        assert!(state.db.find_user_by_oauth(&issuer, &subject).await.unwrap().is_none());
+
+        // Provisioned before the switch: still signs in.
+        state.set_read_only(false);
+        let login_state = begin_login(&server, &next).await;
+        server
+            .get("/api/v1/oauth/callback")
+            .add_query_params(json!({ "code": "abc", "state": login_state }))
+            .await
+            .assert_status_ok();
+        state.set_read_only(true);
+        let login_state = begin_login(&server, &next).await;
+        server
+            .get("/api/v1/oauth/callback")
+            .add_query_params(json!({ "code": "abc", "state": login_state }))
+            .await
+            .assert_status_ok();
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_oidc_callback_rejects_unknown_or_reused_state() {
+        let (server, _state, next, _issuer) = setup(SIGNING_KEY).await;
+
+        let login_state = begin_login(&server, &next).await;
+        server
+            .get("/api/v1/oauth/callback")
+            .add_query_params(json!({ "code": "abc", "state": "not-the-state" }))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+
+        server
+            .get("/api/v1/oauth/callback")
+            .add_query_params(json!({ "code": "abc", "state": login_state }))
+            .await
+            .assert_status_ok();
+        server
+            .get("/api/v1/oauth/callback")
+            .add_query_params(json!({ "code": "abc", "state": login_state }))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_oidc_callback_rejects_bad_signature() {
+        let (server, state, next, issuer) = setup(OTHER_KEY).await;
+
+        let login_state = begin_login(&server, &next).await;
+        server
+            .get("/api/v1/oauth/callback")
+            .add_query_params(json!({ "code": "abc", "state": login_state }))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+
+        let subject = next.lock().unwrap().subject.clone();
+        assert!(state.db.find_user_by_oauth(&issuer, &subject).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_oidc_routes_404_when_not_configured() {
+        let state = create_mock_shared_state().await.unwrap();
+        let server = TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        server.get("/api/v1/oauth/login").await.assert_status(StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,100 @@
+#[cfg(all(test, feature = "otel"))]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::sync::{Arc, mpsc};
+    use std::time::Duration;
+
+    use axum::http::{HeaderName, HeaderValue, StatusCode, header::AUTHORIZATION};
+    use axum_test::TestServer;
+    use serial_test::serial;
+
+    use crate::middleware::REQUEST_ID_HEADER;
+    use crate::telemetry::{self, OtelConfig};
+    use crate::{create_app, create_mock_shared_state, schema::*};
+    use crate::test::helpers::{PASSWORD, unique};
+
+    /// A collector answering every request with 200 and handing over its
+    /// path and body.
+    fn mock_collector() -> (String, mpsc::Receiver<(String, Vec<u8>)>) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { return };
+                let mut reader = BufReader::new(&stream);
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let path = request_line.split_whitespace().nth(1).unwrap_or_default().to_string();
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            length = value.trim().parse().unwrap();
+                        }
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                stream.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n").unwrap();
+                if tx.send((path, body)).is_err() {
+                    return;
+                }
+            }
+        });
+        (endpoint, rx)
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_request_span_is_exported_with_request_id_and_principal() {
+        let (endpoint, exports) = mock_collector();
+        let provider =
+            telemetry::tracer_provider(&OtelConfig { endpoint, service_name: "critical-otel-test".into() }).unwrap();
+        let _subscriber = tracing::subscriber::set_default(telemetry::subscriber(&provider));
+
+        let state = create_mock_shared_state().await.unwrap();
+        let server = TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        let user = unique("oteluser");
+        server
+            .post("/api/v1/register")
+            .json(&RegisterRequest { user: user.clone(), password: PASSWORD.into() })
+            .await
+            .assert_status(StatusCode::CREATED);
+        let resp = server
+            .post("/api/v1/login")
+            .json(&LoginRequest { user: user.clone(), password: PASSWORD.into() })
+            .await;
+        let token = resp.json::<LoginResponse>().token;
+
+        let request_id = unique("otelreq");
+        server
+            .get("/api/v1/search?q=otel")
+            .add_header(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token)).unwrap())
+            .add_header(HeaderName::from_static(REQUEST_ID_HEADER), HeaderValue::from_str(&request_id).unwrap())
+            .await
+            .assert_status_ok();
+        provider.force_flush().unwrap();
+
+        // Attribute keys and values are plain UTF-8 inside the protobuf body.
+        let mut body = Vec::new();
+        while let Ok((path, chunk)) = exports.recv_timeout(Duration::from_secs(10)) {
+            assert_eq!(path, "/v1/traces");
+            body.extend(chunk);
+            if body.windows(request_id.len()).any(|w| w == request_id.as_bytes()) {
+                break;
+            }
+        }
+        let contains = |needle: &str| body.windows(needle.len()).any(|w| w == needle.as_bytes());
+        assert!(contains(&request_id), "no span with the request id was exported");
+        assert!(contains("request_id") && contains("principal"));
+        assert!(contains(&format!("u_{}", user)));
+        assert!(contains("critical-otel-test"));
+        provider.shutdown().unwrap();
+    }
+}
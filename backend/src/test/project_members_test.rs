@@ -0,0 +1,117 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::{HeaderValue, StatusCode, header::AUTHORIZATION};
+    use axum_test::TestServer;
+    use serial_test::serial;
+    use serde_json::{Value, json};
+
+    use crate::{create_app, create_mock_shared_state};
+    use crate::test::helpers::{register_and_login, unique};
+    use crit_shared::util_models::super_permissions;
+
+    async fn set_role(
+        server: &TestServer,
+        auth: &HeaderValue,
+        project: &str,
+        principal: &str,
+        role: &str,
+    ) -> StatusCode {
+        server
+            .post(&format!("/api/v1/ops/projects/{}/members", project))
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&json!({ "principal": principal, "role": role }))
+            .await
+            .status_code()
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_project_member_roles_and_permissions() {
+        let state = create_mock_shared_state().await.unwrap();
+        let owner = unique("powner");
+        let admin = unique("padmin");
+        let member = unique("pmember");
+        let outsider = unique("poutsider");
+        let db = state.db.clone();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+
+        let owner_auth = register_and_login(&server, &owner).await;
+        let admin_auth = register_and_login(&server, &admin).await;
+        let member_auth = register_and_login(&server, &member).await;
+        let outsider_auth = register_and_login(&server, &outsider).await;
+        let (owner_id, admin_id, member_id, outsider_id) = (
+            format!("u_{}", owner),
+            format!("u_{}", admin),
+            format!("u_{}", member),
+            format!("u_{}", outsider),
+        );
+
+        db.grant_permission(super_permissions::USR_CREATE_PROJECTS, &owner_id).await.unwrap();
+        let project = unique("proj");
+        server
+            .post("/api/v1/global/projects")
+            .add_header(AUTHORIZATION, owner_auth.clone())
+            .json(&json!({ "id": &project, "name": "Roles" }))
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        // Owner promotes an admin; the admin adds a member.
+        assert_eq!(set_role(&server, &owner_auth, &project, &admin_id, "admin").await, StatusCode::OK);
+        assert_eq!(set_role(&server, &admin_auth, &project, &member_id, "member").await, StatusCode::OK);
+
+        // Members cannot change roles; admins cannot grant owner.
+        assert_eq!(
+            set_role(&server, &member_auth, &project, &outsider_id, "reporter").await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            set_role(&server, &admin_auth, &project, &outsider_id, "owner").await,
+            StatusCode::FORBIDDEN
+        );
+
+        // Non-members cannot see the project at all.
+        server
+            .get(&format!("/api/v1/ops/projects/{}/members", project))
+            .add_header(AUTHORIZATION, outsider_auth.clone())
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+
+        // The last owner cannot demote themselves.
+        assert_eq!(
+            set_role(&server, &owner_auth, &project, &owner_id, "admin").await,
+            StatusCode::CONFLICT
+        );
+
+        let resp = server
+            .get(&format!("/api/v1/ops/projects/{}/members", project))
+            .add_header(AUTHORIZATION, member_auth.clone())
+            .await;
+        resp.assert_status_ok();
+        let items = resp.json::<Value>()["items"].as_array().unwrap().clone();
+        let role_of = |id: &str| {
+            items
+                .iter()
+                .find(|i| i["principal"] == id)
+                .map(|i| i["role"].as_str().unwrap().to_string())
+        };
+        assert_eq!(role_of(&owner_id).as_deref(), Some("owner"));
+        assert_eq!(role_of(&admin_id).as_deref(), Some("admin"));
+        assert_eq!(role_of(&member_id).as_deref(), Some("member"));
+        assert!(items.iter().all(|i| i["brief"]["id"].is_string()));
+
+        // Admin removes the member, who then loses access.
+        server
+            .delete(&format!("/api/v1/ops/projects/{}/members/{}", project, member_id))
+            .add_header(AUTHORIZATION, admin_auth.clone())
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        server
+            .get(&format!("/api/v1/ops/projects/{}/members", project))
+            .add_header(AUTHORIZATION, member_auth.clone())
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+    }
+}
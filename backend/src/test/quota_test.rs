@@ -0,0 +1,129 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::{HeaderValue, StatusCode, header::AUTHORIZATION};
+    use axum_test::TestServer;
+    use serial_test::serial;
+    use serde_json::{Value, json};
+
+    use crate::{config::AppConfig, create_app, create_mock_shared_state, state::AppState};
+    use crate::test::helpers::{register_and_login, unique};
+    use crit_shared::util_models::super_permissions;
+
+    /// A state whose users may own at most `max_owned_projects` projects by default.
+    async fn state_with_quota(max_owned_projects: Option<u64>) -> Arc<AppState> {
+        let mut state = create_mock_shared_state().await.unwrap();
+        state.config = Arc::new(AppConfig { quota_max_owned_projects: max_owned_projects, ..(*state.config).clone() });
+        Arc::new(state)
+    }
+
+    /// A fresh user allowed to create projects, with their id and token.
+    async fn project_creator(server: &TestServer, state: &AppState, prefix: &str) -> (String, HeaderValue) {
+        let user = unique(prefix);
+        let auth = register_and_login(server, &user).await;
+        let user_id = format!("u_{}", user);
+        state.db.grant_permission(super_permissions::USR_CREATE_PROJECTS, &user_id).await.unwrap();
+        (user_id, auth)
+    }
+
+    async fn create_project(server: &TestServer, auth: &HeaderValue) -> axum_test::TestResponse {
+        server
+            .post("/api/v1/global/projects")
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&json!({ "id": unique("quotaproj"), "name": "Quota" }))
+            .await
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_create_at_the_limit_is_refused() {
+        let state = state_with_quota(Some(1)).await;
+        let server = TestServer::new(create_app(state.clone())).expect("Failed to create TestServer");
+        let (_, auth) = project_creator(&server, &state, "quotaedge").await;
+
+        // Owning exactly the limit is allowed...
+        create_project(&server, &auth).await.assert_status(StatusCode::CREATED);
+        let usage = server.get("/api/v1/personal/quota").add_header(AUTHORIZATION, auth.clone()).await;
+        usage.assert_status_ok();
+        assert_eq!(
+            usage.json::<Value>(),
+            json!({ "exempt": false, "items": [{ "quota": "owned_projects", "current": 1, "limit": 1 }] })
+        );
+
+        // ...going past it is not, through either create route.
+        let resp = create_project(&server, &auth).await;
+        resp.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        let error = &resp.json::<Value>()["error"];
+        assert_eq!(error["code"], "quota_exceeded");
+        assert_eq!(error["details"], json!({ "quota": "owned_projects", "current": 1, "limit": 1 }));
+        server
+            .post(&format!("/api/v1/global/projects/{}", unique("quotaupsert")))
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&json!({ "name": "Upserted" }))
+            .await
+            .assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_user_override_wins_over_default() {
+        let state = state_with_quota(Some(1)).await;
+        let server = TestServer::new(create_app(state.clone())).expect("Failed to create TestServer");
+        let (user_id, auth) = project_creator(&server, &state, "quotaover").await;
+        let admin = unique("quotaadm");
+        let admin_auth = register_and_login(&server, &admin).await;
+        state.db.grant_permission(super_permissions::ADM_GODMODE, &format!("u_{}", admin)).await.unwrap();
+
+        // Users can read their own quota but not raise it.
+        server
+            .post(&format!("/api/v1/global/quotas/{}", user_id))
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&json!({ "max_owned_projects": 100 }))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+        server
+            .post(&format!("/api/v1/global/quotas/{}", user_id))
+            .add_header(AUTHORIZATION, admin_auth.clone())
+            .json(&json!({ "max_owned_projects": 2 }))
+            .await
+            .assert_status(StatusCode::CREATED);
+        server
+            .get(&format!("/api/v1/global/quotas/{}", user_id))
+            .add_header(AUTHORIZATION, auth.clone())
+            .await
+            .assert_status_ok();
+
+        create_project(&server, &auth).await.assert_status(StatusCode::CREATED);
+        create_project(&server, &auth).await.assert_status(StatusCode::CREATED);
+        create_project(&server, &auth).await.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+
+        // An explicit null lifts the limit altogether.
+        server
+            .put(&format!("/api/v1/global/quotas/{}", user_id))
+            .add_header(AUTHORIZATION, admin_auth.clone())
+            .json(&json!({ "max_owned_projects": null }))
+            .await
+            .assert_status_ok();
+        create_project(&server, &auth).await.assert_status(StatusCode::CREATED);
+        let usage = server.get("/api/v1/personal/quota").add_header(AUTHORIZATION, auth).await;
+        assert_eq!(usage.json::<Value>()["items"][0], json!({ "quota": "owned_projects", "current": 3, "limit": null }));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_admins_bypass_quotas() {
+        let state = state_with_quota(Some(0)).await;
+        let server = TestServer::new(create_app(state.clone())).expect("Failed to create TestServer");
+        let (_, auth) = project_creator(&server, &state, "quotaplain").await;
+        let admin = unique("quotaroot");
+        let admin_auth = register_and_login(&server, &admin).await;
+        state.db.grant_permission(super_permissions::ADM_GODMODE, &format!("u_{}", admin)).await.unwrap();
+
+        create_project(&server, &auth).await.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+        create_project(&server, &admin_auth).await.assert_status(StatusCode::CREATED);
+        create_project(&server, &admin_auth).await.assert_status(StatusCode::CREATED);
+        let usage = server.get("/api/v1/personal/quota").add_header(AUTHORIZATION, admin_auth).await;
+        assert_eq!(usage.json::<Value>()["exempt"], true);
+    }
+}
@@ -0,0 +1,111 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::{HeaderValue, StatusCode, header::AUTHORIZATION};
+    use axum_test::TestServer;
+    use serial_test::serial;
+    use serde_json::{Value, json};
+
+    use crate::{create_app, create_mock_shared_state, schema::*};
+    use crate::test::helpers::{PASSWORD, unique};
+    use crit_shared::requests::ErrorBody;
+
+    async fn login(server: &TestServer, username: &str) -> HeaderValue {
+        let resp = server
+            .post("/api/v1/login")
+            .json(&LoginRequest { user: username.to_string(), password: PASSWORD.into() })
+            .await;
+        resp.assert_status_ok();
+        format!("Bearer {}", resp.json::<LoginResponse>().token).parse().unwrap()
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_read_only_mode_refuses_mutations_and_keeps_reads() {
+        let state = Arc::new(create_mock_shared_state().await.unwrap());
+        let server =
+            TestServer::new(create_app(state.clone())).expect("Failed to create TestServer");
+        let user = unique("rouser");
+        server
+            .post("/api/v1/register")
+            .json(&RegisterRequest { user: user.clone(), password: PASSWORD.into() })
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        state.set_read_only(true);
+
+        // Login still works, so reads can be authenticated.
+        let auth = login(&server, &user).await;
+
+        let resp = server
+            .post("/api/v1/global/groups")
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&json!({ "id": unique("rogrp"), "name": "Blocked" }))
+            .await;
+        resp.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+        let body: ErrorBody = resp.json();
+        assert_eq!(body.error.code, "read_only");
+        assert_eq!(body.error.message, "server is in read-only maintenance mode");
+
+        server
+            .post("/api/v1/register")
+            .json(&RegisterRequest { user: unique("rolate"), password: PASSWORD.into() })
+            .await
+            .assert_status(StatusCode::SERVICE_UNAVAILABLE);
+
+        server
+            .get("/api/v1/global/groups")
+            .add_header(AUTHORIZATION, auth.clone())
+            .await
+            .assert_status_ok();
+        let info: Value = server
+            .get("/api/v1/system/info")
+            .add_header(AUTHORIZATION, auth.clone())
+            .await
+            .json();
+        assert_eq!(info["read_only"], true);
+
+        state.set_read_only(false);
+        server
+            .post("/api/v1/global/groups")
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&json!({ "id": unique("rogrp"), "name": "Allowed" }))
+            .await
+            .assert_status(StatusCode::CREATED);
+        let info: Value = server
+            .get("/api/v1/system/info")
+            .add_header(AUTHORIZATION, auth)
+            .await
+            .json();
+        assert_eq!(info["read_only"], false);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_reconciler_defers_repairs_while_read_only() {
+        let state = create_mock_shared_state().await.unwrap();
+        let db = &state.db;
+        let user = unique("u_roreco");
+        let group = unique("g_roreco");
+        db.generic_create("users", json!({ "_key": &user })).await.unwrap();
+        db.generic_create("groups", json!({ "_key": &group, "name": "Read-only" })).await.unwrap();
+        db.add_principal_to_group(&user, &group, None).await.unwrap();
+        let edge = format!("{}::{}", user, group);
+        let ctrl = state.controller.for_kind("memberships");
+
+        state.set_read_only(true);
+        let paused = state.reconciler.run_kind("memberships", ctrl, db).await.unwrap();
+        assert!(paused.deferred.contains(&edge));
+        assert!(!paused.reconciled.contains(&edge));
+        let stored = db.generic_get("memberships", &edge).await.unwrap().unwrap();
+        assert!(stored.get("hash_code").is_none());
+
+        state.set_read_only(false);
+        let resumed = state.reconciler.run_kind("memberships", ctrl, db).await.unwrap();
+        assert!(resumed.reconciled.contains(&edge));
+
+        db.delete_group(&group, None).await.unwrap();
+        db.delete_user(&user, None).await.unwrap();
+    }
+}
@@ -0,0 +1,126 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+    use serial_test::serial;
+    use serde_json::{Value, json};
+
+    use crate::controllers::gitops_controller::{
+        KindController, standard_to_external, standard_to_internal,
+    };
+    use crate::db::DatabaseInterface;
+    use crate::error::AppError;
+    use crate::middleware::auth::Auth;
+    use crate::reconcile::Reconciler;
+    use crate::test::helpers::unique;
+    use crate::create_mock_shared_state;
+
+    /// Reports `drifted` keys as out of sync and records every reconcile call.
+    struct FakeController {
+        drifted: HashSet<String>,
+        failing: HashSet<String>,
+        reconciled: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl KindController for FakeController {
+        async fn can_read(&self, _: &str, _: Option<&Value>) -> Result<bool, AppError> {
+            Ok(true)
+        }
+
+        async fn can_write(&self, _: &str, _: Option<&Value>) -> Result<bool, AppError> {
+            Ok(true)
+        }
+
+        fn to_internal(&self, body: Value, _: &Auth) -> Result<Value, AppError> {
+            Ok(standard_to_internal(body))
+        }
+
+        fn to_external(&self, doc: Value) -> Value {
+            standard_to_external(doc)
+        }
+
+        async fn observe(&self, doc: &Value, _db: &dyn DatabaseInterface) -> Result<Option<String>, AppError> {
+            let key = doc["_key"].as_str().unwrap_or_default();
+            if self.drifted.contains(key) {
+                return Ok(Some("observed-elsewhere".to_string()));
+            }
+            Ok(doc["hash_code"].as_str().map(String::from))
+        }
+
+        async fn reconcile(&self, doc: &Value, _db: &dyn DatabaseInterface) -> Result<(), AppError> {
+            let key = doc["_key"].as_str().unwrap_or_default().to_string();
+            if self.failing.contains(&key) {
+                return Err(AppError::bad_request("cannot converge"));
+            }
+            self.reconciled.lock().unwrap().push(key);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_only_drifted_documents_are_reconciled() {
+        let state = create_mock_shared_state().await.unwrap();
+        let kind = unique("reconcile_fake");
+        state.db.ensure_collection(&kind).await.unwrap();
+        for key in ["in_sync", "drifted", "broken"] {
+            state
+                .db
+                .generic_create(&kind, json!({ "_key": key, "hash_code": "h" }))
+                .await
+                .unwrap();
+        }
+
+        let ctrl = FakeController {
+            drifted: HashSet::from(["drifted".to_string(), "broken".to_string()]),
+            failing: HashSet::from(["broken".to_string()]),
+            reconciled: Mutex::new(vec![]),
+        };
+        let reconciler = Reconciler::new();
+
+        let status = reconciler.run_kind(&kind, &ctrl, &state.db).await.unwrap();
+        assert_eq!(status.checked, 3);
+        assert_eq!(status.in_sync, 1);
+        assert_eq!(status.reconciled, vec!["drifted".to_string()]);
+        assert_eq!(*ctrl.reconciled.lock().unwrap(), vec!["drifted".to_string()]);
+        assert_eq!(status.failed.len(), 1);
+        assert_eq!(status.failed[0].id, "broken");
+        assert_eq!(status.failed[0].retry_count, 1);
+
+        // Failures keep counting across passes; the stored status reflects the latest pass.
+        let again = reconciler.run_kind(&kind, &ctrl, &state.db).await.unwrap();
+        assert_eq!(again.failed[0].retry_count, 2);
+        assert_eq!(reconciler.status(&kind).await.unwrap().failed[0].retry_count, 2);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_membership_edge_without_hash_is_stamped_once() {
+        let state = create_mock_shared_state().await.unwrap();
+        let db = &state.db;
+        let user = unique("u_recon");
+        let group = unique("g_recon");
+        db.generic_create("users", json!({ "_key": &user })).await.unwrap();
+        db.generic_create("groups", json!({ "_key": &group, "name": "Recon" })).await.unwrap();
+        db.add_principal_to_group(&user, &group, None).await.unwrap();
+        let edge = format!("{}::{}", user, group);
+
+        let ctrl = state.controller.for_kind("memberships");
+        let reconciler = Reconciler::new();
+
+        let first = reconciler.run_kind("memberships", ctrl, db).await.unwrap();
+        assert!(first.reconciled.contains(&edge));
+        let stored = db.generic_get("memberships", &edge).await.unwrap().unwrap();
+        assert!(stored["hash_code"].is_string());
+        assert_eq!(stored["_from"], json!(format!("users/{}", user)));
+
+        let second = reconciler.run_kind("memberships", ctrl, db).await.unwrap();
+        assert!(!second.reconciled.contains(&edge));
+
+        db.delete_group(&group, None).await.unwrap();
+        db.delete_user(&user, None).await.unwrap();
+    }
+}
@@ -0,0 +1,105 @@
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use axum::http::{HeaderValue, StatusCode, header::AUTHORIZATION};
+    use axum_test::TestServer;
+    use serde_json::json;
+    use serial_test::serial;
+
+    use crate::{config::AppConfig, create_app, create_mock_shared_state, middleware::auth::Auth, schema::*};
+    use crate::test::helpers::{PASSWORD, unique};
+
+    /// Server whose access tokens live `ttl` seconds with no clock-skew leeway.
+    async fn server_with_ttl(ttl: u64) -> TestServer {
+        let mut state = create_mock_shared_state().await.unwrap();
+        let config = AppConfig { jwt_ttl_seconds: ttl, jwt_leeway_secs: 0, ..(*state.config).clone() };
+        state.auth = Arc::new(Auth::from_config(&config));
+        state.config = Arc::new(config);
+        TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer")
+    }
+
+    async fn register_and_login(server: &TestServer) -> LoginResponse {
+        let user = unique("refresher");
+        server
+            .post("/api/v1/register")
+            .json(&RegisterRequest { user: user.clone(), password: PASSWORD.into() })
+            .await
+            .assert_status(StatusCode::CREATED);
+        let resp = server.post("/api/v1/login").json(&LoginRequest { user, password: PASSWORD.into() }).await;
+        resp.assert_status_ok();
+        resp.json::<LoginResponse>()
+    }
+
+    fn bearer(token: &str) -> HeaderValue {
+        format!("Bearer {}", token).parse().unwrap()
+    }
+
+    async fn refresh(server: &TestServer, refresh_token: &str) -> axum_test::TestResponse {
+        server
+            .post("/api/v1/refresh")
+            .json(&json!({ "refresh_token": refresh_token }))
+            .await
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn refresh_rotates_and_old_token_is_rejected() {
+        let server = server_with_ttl(3600).await;
+        let session = register_and_login(&server).await;
+        let first = session.refresh_token.expect("login returns a refresh token");
+
+        let resp = refresh(&server, &first).await;
+        resp.assert_status_ok();
+        let renewed = resp.json::<LoginResponse>();
+        let second = renewed.refresh_token.unwrap();
+        assert_ne!(first, second);
+        server
+            .get("/api/v1/global/groups")
+            .add_header(AUTHORIZATION, bearer(&renewed.token))
+            .await
+            .assert_status_ok();
+
+        // The used token is gone; the new one still works exactly once.
+        refresh(&server, &first).await.assert_status(StatusCode::UNAUTHORIZED);
+        refresh(&server, &second).await.assert_status_ok();
+        refresh(&server, &second).await.assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn expired_access_token_recovers_with_refresh() {
+        let server = server_with_ttl(1).await;
+        let session = register_and_login(&server).await;
+
+        tokio::time::sleep(Duration::from_millis(2500)).await;
+        server
+            .get("/api/v1/global/groups")
+            .add_header(AUTHORIZATION, bearer(&session.token))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+
+        let resp = refresh(&server, &session.refresh_token.unwrap()).await;
+        resp.assert_status_ok();
+        server
+            .get("/api/v1/global/groups")
+            .add_header(AUTHORIZATION, bearer(&resp.json::<LoginResponse>().token))
+            .await
+            .assert_status_ok();
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn logout_revokes_the_refresh_token() {
+        let server = server_with_ttl(3600).await;
+        let refresh_token = register_and_login(&server).await.refresh_token.unwrap();
+
+        server
+            .post("/api/v1/logout")
+            .json(&json!({ "refresh_token": &refresh_token }))
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        refresh(&server, &refresh_token).await.assert_status(StatusCode::UNAUTHORIZED);
+        refresh(&server, "not-a-token").await.assert_status(StatusCode::UNAUTHORIZED);
+    }
+}
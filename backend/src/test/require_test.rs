@@ -0,0 +1,92 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{
+        Router,
+        http::{StatusCode, header::AUTHORIZATION},
+        middleware::from_fn_with_state,
+        routing::get,
+    };
+    use axum_test::TestServer;
+    use serial_test::serial;
+
+    use crate::{
+        create_app, create_mock_shared_state,
+        middleware::{RequireAdmin, RequirePermission, jwt_auth_middleware, permission},
+        state::AppState,
+        util_models::super_permissions,
+    };
+    use crate::test::helpers::{register_and_login, unique};
+
+    /// Sample routes guarded only by the extractors, behind the usual JWT layer.
+    fn probe_server(state: Arc<AppState>) -> TestServer {
+        let app = Router::new()
+            .route("/admin", get(|RequireAdmin(user_id): RequireAdmin| async move { user_id }))
+            .route(
+                "/config",
+                get(|p: RequirePermission<permission::ConfigEditor>| async move { p.user_id }),
+            )
+            .layer(from_fn_with_state(state.clone(), jwt_auth_middleware))
+            .with_state(state);
+        TestServer::new(app).expect("Failed to create TestServer")
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_require_admin_refuses_non_admin_with_403() {
+        let state = Arc::new(create_mock_shared_state().await.unwrap());
+        let server = TestServer::new(create_app(state.clone())).expect("Failed to create TestServer");
+        let probe = probe_server(state.clone());
+
+        let plain = unique("plain");
+        let plain_auth = register_and_login(&server, &plain).await;
+        let admin = unique("admin");
+        let admin_auth = register_and_login(&server, &admin).await;
+        state.db.grant_permission(super_permissions::ADM_GODMODE, &format!("u_{}", admin)).await.unwrap();
+
+        server
+            .get("/api/v1/adm/audit")
+            .add_header(AUTHORIZATION, plain_auth.clone())
+            .await
+            .assert_status(StatusCode::FORBIDDEN);
+        server.get("/api/v1/adm/audit").add_header(AUTHORIZATION, admin_auth.clone()).await.assert_status_ok();
+
+        probe.get("/admin").add_header(AUTHORIZATION, plain_auth).await.assert_status(StatusCode::FORBIDDEN);
+        let resp = probe.get("/admin").add_header(AUTHORIZATION, admin_auth).await;
+        resp.assert_status_ok();
+        assert_eq!(resp.text(), format!("u_{}", admin));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_require_permission_checks_the_named_permission() {
+        let state = Arc::new(create_mock_shared_state().await.unwrap());
+        let server = TestServer::new(create_app(state.clone())).expect("Failed to create TestServer");
+        let probe = probe_server(state.clone());
+
+        let user = unique("editor");
+        let auth = register_and_login(&server, &user).await;
+
+        // Registration grants USR_CREATE_GROUPS, which is not the one asked for.
+        probe.get("/config").add_header(AUTHORIZATION, auth.clone()).await.assert_status(StatusCode::FORBIDDEN);
+
+        state
+            .db
+            .grant_permission(super_permissions::ADM_CONFIG_EDITOR, &format!("u_{}", user))
+            .await
+            .unwrap();
+        let resp = probe.get("/config").add_header(AUTHORIZATION, auth).await;
+        resp.assert_status_ok();
+        assert_eq!(resp.text(), format!("u_{}", user));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_extractors_reject_missing_token_before_checking() {
+        let state = Arc::new(create_mock_shared_state().await.unwrap());
+        let probe = probe_server(state);
+        probe.get("/admin").await.assert_status(StatusCode::UNAUTHORIZED);
+        probe.get("/config").await.assert_status(StatusCode::UNAUTHORIZED);
+    }
+}
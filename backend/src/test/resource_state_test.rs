@@ -0,0 +1,138 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::{HeaderValue, StatusCode, header::AUTHORIZATION};
+    use axum_test::TestServer;
+    use chrono::DateTime;
+    use serial_test::serial;
+    use serde_json::{Value, json};
+
+    use crate::{create_app, create_mock_shared_state};
+    use crate::test::helpers::{register_and_login, unique};
+
+    async fn get_group(server: &TestServer, auth: &HeaderValue, id: &str) -> Value {
+        let resp = server
+            .get(&format!("/api/v1/global/groups/{}", id))
+            .add_header(AUTHORIZATION, auth.clone())
+            .await;
+        resp.assert_status_ok();
+        resp.json()
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_create_and_upsert_stamp_resource_state() {
+        let state = create_mock_shared_state().await.unwrap();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        let user = unique("stateuser");
+        let auth = register_and_login(&server, &user).await;
+        let group = unique("stategrp");
+        let id = format!("g_{}", group);
+
+        // Client-supplied state is ignored on create.
+        server
+            .post("/api/v1/global/groups")
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&json!({
+                "id": &group,
+                "name": "Stamped",
+                "state": { "created_by": "u_mallory", "updated_at": "2000-01-01T00:00:00Z" }
+            }))
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        let created = get_group(&server, &auth, &id).await;
+        let created_at = created["state"]["created_at"].as_str().expect("created_at set");
+        assert!(DateTime::parse_from_rfc3339(created_at).is_ok(), "{}", created_at);
+        assert_eq!(created["state"]["created_by"], format!("u_{}", user));
+        assert!(created["state"].get("updated_at").is_none(), "{}", created["state"]);
+
+        let mut body = created.clone();
+        body["name"] = json!("Renamed");
+        server
+            .post(&format!("/api/v1/global/groups/{}", id))
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&body)
+            .await
+            .assert_status_ok();
+
+        let updated = get_group(&server, &auth, &id).await;
+        assert_eq!(updated["name"], "Renamed");
+        assert_eq!(updated["state"]["created_at"], created_at);
+        let updated_at = updated["state"]["updated_at"].as_str().expect("updated_at set");
+        assert!(DateTime::parse_from_rfc3339(updated_at).is_ok(), "{}", updated_at);
+        assert_eq!(updated["state"]["updated_by"], format!("u_{}", user));
+
+        // Re-applying the same desired state is `unchanged`, since `state` is
+        // excluded from the hash.
+        let mut again = updated.clone();
+        again.as_object_mut().unwrap().remove("hash_code");
+        let resp = server
+            .post(&format!("/api/v1/global/groups/{}", id))
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&again)
+            .await;
+        resp.assert_status_ok();
+        assert_eq!(resp.json::<Value>()["action"], "unchanged");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_generation_advances_only_on_desired_state_changes() {
+        let state = create_mock_shared_state().await.unwrap();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        let auth = register_and_login(&server, &unique("genuser")).await;
+        let group = unique("gengrp");
+        let id = format!("g_{}", group);
+
+        server
+            .post("/api/v1/global/groups")
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&json!({ "id": &group, "name": "Gen", "state": { "generation": 40 } }))
+            .await
+            .assert_status(StatusCode::CREATED);
+        let created = get_group(&server, &auth, &id).await;
+        assert_eq!(created["state"]["generation"], 1);
+
+        let mut body = created.clone();
+        body["name"] = json!("Gen renamed");
+        server
+            .post(&format!("/api/v1/global/groups/{}", id))
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&body)
+            .await
+            .assert_status_ok();
+        let updated = get_group(&server, &auth, &id).await;
+        assert_eq!(updated["state"]["generation"], 2);
+
+        let mut again = updated.clone();
+        again.as_object_mut().unwrap().remove("hash_code");
+        server
+            .put(&format!("/api/v1/global/groups/{}", id))
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&again)
+            .await
+            .assert_status_ok();
+        let unchanged = get_group(&server, &auth, &id).await;
+        assert_eq!(unchanged["state"]["generation"], 2);
+        assert!(unchanged["state"]["updated_at"].is_string());
+
+        // Briefs carry the generation next to id and labels.
+        let list: Value = server
+            .get("/api/v1/global/groups")
+            .add_header(AUTHORIZATION, auth.clone())
+            .await
+            .json();
+        let brief = list["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|g| g["id"] == id.as_str())
+            .expect("group listed");
+        assert_eq!(brief["generation"], 2);
+        assert!(brief.get("state").is_none(), "{}", brief);
+    }
+}
@@ -0,0 +1,119 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::{HeaderValue, StatusCode, header::AUTHORIZATION};
+    use axum_test::TestServer;
+    use serial_test::serial;
+    use serde_json::{Value, json};
+
+    use crate::{create_app, create_mock_shared_state};
+    use crate::test::helpers::{register_and_login, unique};
+    use crit_shared::util_models::super_permissions;
+
+    async fn search(server: &TestServer, auth: &HeaderValue, query: &str) -> (StatusCode, Value) {
+        let resp = server
+            .get(&format!("/api/v1/search?{}", query))
+            .add_header(AUTHORIZATION, auth.clone())
+            .await;
+        (resp.status_code(), resp.json::<Value>())
+    }
+
+    fn ids(body: &Value, kind: &str) -> Vec<String> {
+        body["results"][kind]
+            .as_array()
+            .unwrap_or_else(|| panic!("no {} results in {}", kind, body))
+            .iter()
+            .map(|item| item["id"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_search_matches_project_name_and_ranks_exact_first() {
+        let state = create_mock_shared_state().await.unwrap();
+        let owner = unique("srchowner");
+        let db = state.db.clone();
+        let server = TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        let auth = register_and_login(&server, &owner).await;
+        db.grant_permission(super_permissions::USR_CREATE_PROJECTS, &format!("u_{}", owner)).await.unwrap();
+
+        // A marker no other test's data contains, used as the project names.
+        let marker = unique("apollo");
+        // Created so key order is the reverse of rank order.
+        let (exact, partial) = (unique("srchz"), unique("srcha"));
+        for (id, name) in [(&exact, marker.clone()), (&partial, format!("Launch {}", marker.to_uppercase()))] {
+            server
+                .post("/api/v1/global/projects")
+                .add_header(AUTHORIZATION, auth.clone())
+                .json(&json!({ "id": id, "name": name }))
+                .await
+                .assert_status(StatusCode::CREATED);
+        }
+
+        let (status, body) = search(&server, &auth, &format!("q={}&kinds=projects", marker)).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(ids(&body, "projects"), vec![exact.clone(), partial.clone()]);
+        assert_eq!(body["results"].as_object().unwrap().len(), 1, "only the requested kind: {}", body);
+        assert_eq!(body["results"]["projects"][0]["name"], marker.as_str());
+
+        // Every term has to match; `limit` caps each kind.
+        let (_, body) = search(&server, &auth, &format!("q=launch+{}&kinds=projects", marker)).await;
+        assert_eq!(ids(&body, "projects"), vec![partial.clone()]);
+        let (_, body) = search(&server, &auth, &format!("q={}&kinds=projects&limit=1", marker)).await;
+        assert_eq!(ids(&body, "projects"), vec![exact.clone()]);
+
+        // Without `kinds`, every searchable kind is listed.
+        let (_, body) = search(&server, &auth, &format!("q={}", marker)).await;
+        assert_eq!(ids(&body, "projects").len(), 2);
+        assert!(body["results"]["users"].is_array() && body["results"]["groups"].is_array(), "{}", body);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_search_excludes_unreadable_resources() {
+        let state = create_mock_shared_state().await.unwrap();
+        let owner = unique("srchowner");
+        let outsider = unique("srchoutsider");
+        let db = state.db.clone();
+        let server = TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        let owner_auth = register_and_login(&server, &owner).await;
+        let outsider_auth = register_and_login(&server, &outsider).await;
+        db.grant_permission(super_permissions::USR_CREATE_PROJECTS, &format!("u_{}", owner)).await.unwrap();
+
+        let marker = unique("hermes");
+        let project = unique("srchpriv");
+        server
+            .post("/api/v1/global/projects")
+            .add_header(AUTHORIZATION, owner_auth.clone())
+            .json(&json!({ "id": &project, "name": &marker }))
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        let query = format!("q={}&kinds=projects", marker);
+        let (_, body) = search(&server, &owner_auth, &query).await;
+        assert_eq!(ids(&body, "projects"), vec![project.clone()]);
+        let (status, body) = search(&server, &outsider_auth, &query).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(ids(&body, "projects").is_empty(), "outsider must not see {}: {}", project, body);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_search_rejects_empty_query_and_unknown_kinds() {
+        let state = create_mock_shared_state().await.unwrap();
+        let server = TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        let auth = register_and_login(&server, &unique("srchbad")).await;
+
+        let (status, body) = search(&server, &auth, "q=+++").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"]["details"]["field"], "q");
+
+        let (status, body) = search(&server, &auth, "q=x&kinds=projects,memberships").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"]["details"]["field"], "kinds");
+        assert!(body["error"]["message"].as_str().unwrap().contains("'memberships' is not searchable"), "{}", body);
+
+        server.get("/api/v1/search?q=x").await.assert_status(StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,99 @@
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use axum::{Router, routing::get};
+    use tokio::sync::oneshot;
+
+    use crate::config::TlsConfig;
+    use crate::server::{load_tls, serve};
+
+    /// Self-signed for `localhost` / `127.0.0.1`, valid for 100 years.
+    fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/test/fixtures/tls").join(name)
+    }
+
+    fn fixture_tls() -> TlsConfig {
+        TlsConfig { cert_path: fixture("cert.pem"), key_path: fixture("key.pem") }
+    }
+
+    fn app() -> axum::routing::IntoMakeService<Router> {
+        Router::new().route("/health", get(|| async { "ok" })).into_make_service()
+    }
+
+    #[tokio::test]
+    async fn serves_https_and_shuts_down_gracefully() {
+        let tls = load_tls(&fixture_tls()).await.expect("fixture cert loads");
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (stop, stopped) = oneshot::channel::<()>();
+        let server = tokio::spawn(serve(listener, app(), Some(tls), async {
+            let _ = stopped.await;
+        }));
+
+        let client = reqwest::Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+        let resp = client
+            .get(format!("https://localhost:{}/health", port))
+            .send()
+            .await
+            .expect("HTTPS request succeeds");
+        assert!(resp.status().is_success());
+        assert_eq!(resp.text().await.unwrap(), "ok");
+
+        // Plain HTTP on the TLS port is not served.
+        let plain = client.get(format!("http://localhost:{}/health", port)).send().await;
+        assert!(!plain.is_ok_and(|r| r.status().is_success()));
+
+        stop.send(()).unwrap();
+        tokio::time::timeout(Duration::from_secs(15), server)
+            .await
+            .expect("server stops after shutdown")
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn plain_http_shuts_down_gracefully() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (stop, stopped) = oneshot::channel::<()>();
+        let server = tokio::spawn(serve(listener, app(), None, async {
+            let _ = stopped.await;
+        }));
+
+        let body = reqwest::get(format!("http://127.0.0.1:{}/health", port))
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(body, "ok");
+
+        stop.send(()).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), server)
+            .await
+            .expect("server stops after shutdown")
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn missing_cert_file_names_the_variable_and_path() {
+        let tls = TlsConfig { cert_path: fixture("nope.pem"), key_path: fixture("key.pem") };
+        let err = load_tls(&tls).await.expect_err("missing cert is rejected");
+        assert!(err.starts_with("TLS_CERT '"), "{}", err);
+        assert!(err.contains("nope.pem"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn invalid_pem_is_rejected() {
+        // A key file where the certificate should be.
+        let tls = TlsConfig { cert_path: fixture("key.pem"), key_path: fixture("key.pem") };
+        let err = load_tls(&tls).await.expect_err("invalid cert is rejected");
+        assert!(err.starts_with("invalid TLS certificate or key"), "{}", err);
+    }
+}
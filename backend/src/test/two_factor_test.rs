@@ -0,0 +1,148 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::{HeaderValue, StatusCode, header::AUTHORIZATION};
+    use axum_test::{TestResponse, TestServer};
+    use serial_test::serial;
+    use serde_json::{Value, json};
+
+    use crate::{create_app, create_mock_shared_state, schema::*, services::totp};
+    use crate::test::helpers::{PASSWORD, unique};
+
+    fn now_secs() -> u64 {
+        chrono::Utc::now().timestamp() as u64
+    }
+
+    async fn server_with_user(prefix: &str) -> (TestServer, String) {
+        let state = create_mock_shared_state().await.unwrap();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        let username = unique(prefix);
+        server
+            .post("/api/v1/register")
+            .json(&RegisterRequest { user: username.clone(), password: PASSWORD.into() })
+            .await
+            .assert_status(StatusCode::CREATED);
+        (server, username)
+    }
+
+    async fn login_with(server: &TestServer, user: &str, code: Option<&str>) -> TestResponse {
+        let mut body = json!({ "user": user, "password": PASSWORD });
+        if let Some(code) = code {
+            body["totp_code"] = json!(code);
+        }
+        server.post("/api/v1/login").json(&body).await
+    }
+
+    fn bearer(resp: &TestResponse) -> HeaderValue {
+        format!("Bearer {}", resp.json::<LoginResponse>().token).parse().unwrap()
+    }
+
+    /// Enroll and confirm; returns the raw secret and the recovery codes.
+    async fn enable_totp(server: &TestServer, auth: &HeaderValue) -> (Vec<u8>, Vec<String>) {
+        let enrolled = server.post("/api/v1/2fa/enroll").add_header(AUTHORIZATION, auth.clone()).await;
+        enrolled.assert_status_ok();
+        let enrolled = enrolled.json::<TotpEnrollResponse>();
+        assert!(enrolled.otpauth_uri.starts_with("otpauth://totp/Critical:"));
+        let secret = data_encoding::BASE32_NOPAD.decode(enrolled.secret.as_bytes()).unwrap();
+
+        let verified = server
+            .post("/api/v1/2fa/verify")
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&TotpVerifyRequest { code: totp::code_at(&secret, now_secs()) })
+            .await;
+        verified.assert_status_ok();
+        (secret, verified.json::<TotpVerifyResponse>().recovery_codes)
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_login_without_2fa_needs_no_code() {
+        let (server, username) = server_with_user("no2fa").await;
+
+        login_with(&server, &username, None).await.assert_status_ok();
+        // A stray code is ignored for users without a confirmed factor.
+        login_with(&server, &username, Some("123456")).await.assert_status_ok();
+
+        // Starting enrollment alone does not change login.
+        let auth = bearer(&login_with(&server, &username, None).await);
+        server.post("/api/v1/2fa/enroll").add_header(AUTHORIZATION, auth).await.assert_status_ok();
+        login_with(&server, &username, None).await.assert_status_ok();
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_login_requires_code_once_enrolled() {
+        let (server, username) = server_with_user("with2fa").await;
+        let auth = bearer(&login_with(&server, &username, None).await);
+        let (secret, _) = enable_totp(&server, &auth).await;
+
+        let missing = login_with(&server, &username, None).await;
+        missing.assert_status(StatusCode::UNAUTHORIZED);
+        assert!(missing.json::<Value>()["error"]["message"].as_str().unwrap().contains("two-factor code required"));
+
+        let wrong = totp::code_at(&secret, now_secs() + 10 * totp::STEP_SECS);
+        login_with(&server, &username, Some(&wrong)).await.assert_status(StatusCode::UNAUTHORIZED);
+
+        let current = totp::code_at(&secret, now_secs());
+        login_with(&server, &username, Some(&current)).await.assert_status_ok();
+
+        // The secret never leaves the server.
+        let user = server.get(&format!("/api/v1/global/users/u_{}", username)).add_header(AUTHORIZATION, auth.clone()).await;
+        assert!(user.json::<Value>().get("totp").is_none());
+
+        // Enrolling again while enabled is refused.
+        server.post("/api/v1/2fa/enroll").add_header(AUTHORIZATION, auth).await.assert_status(StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_login_code_tolerates_one_step_of_clock_skew() {
+        let (server, username) = server_with_user("skew2fa").await;
+        let auth = bearer(&login_with(&server, &username, None).await);
+        let (secret, _) = enable_totp(&server, &auth).await;
+
+        let behind = totp::code_at(&secret, now_secs() - totp::STEP_SECS);
+        login_with(&server, &username, Some(&behind)).await.assert_status_ok();
+        let ahead = totp::code_at(&secret, now_secs() + totp::STEP_SECS);
+        login_with(&server, &username, Some(&ahead)).await.assert_status_ok();
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_recovery_codes_are_single_use() {
+        let (server, username) = server_with_user("recovery2fa").await;
+        let auth = bearer(&login_with(&server, &username, None).await);
+        let (_, codes) = enable_totp(&server, &auth).await;
+        assert_eq!(codes.len(), totp::RECOVERY_CODE_COUNT);
+
+        login_with(&server, &username, Some(&codes[0].to_uppercase())).await.assert_status_ok();
+        login_with(&server, &username, Some(&codes[0])).await.assert_status(StatusCode::UNAUTHORIZED);
+        login_with(&server, &username, Some(&codes[1])).await.assert_status_ok();
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_verify_rejects_a_wrong_code() {
+        let (server, username) = server_with_user("badverify2fa").await;
+        let auth = bearer(&login_with(&server, &username, None).await);
+
+        let no_enrollment = server
+            .post("/api/v1/2fa/verify")
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&TotpVerifyRequest { code: "000000".into() })
+            .await;
+        no_enrollment.assert_status(StatusCode::BAD_REQUEST);
+
+        server.post("/api/v1/2fa/enroll").add_header(AUTHORIZATION, auth.clone()).await.assert_status_ok();
+        let wrong = server
+            .post("/api/v1/2fa/verify")
+            .add_header(AUTHORIZATION, auth)
+            .json(&TotpVerifyRequest { code: "abcdef".into() })
+            .await;
+        wrong.assert_status(StatusCode::BAD_REQUEST);
+        assert_eq!(wrong.json::<Value>()["error"]["details"]["field"], "code");
+        login_with(&server, &username, None).await.assert_status_ok();
+    }
+}
@@ -0,0 +1,223 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::{HeaderValue, StatusCode, header::AUTHORIZATION};
+    use axum_test::TestServer;
+    use serial_test::serial;
+    use serde_json::json;
+
+    use crate::{
+        controllers::Controller,
+        controllers::gitops_controller::{CascadePolicy, delete_cascading},
+        controllers::project_controller::ProjectController, create_app, create_mock_shared_state,
+        db::{DatabaseInterface, InMemoryDb},
+        error::AppError,
+        state::AppState, watch::ChangeType,
+    };
+    use crate::test::helpers::{register_and_login, unique};
+    use crit_shared::util_models::{Permissions, ProjectRole, super_permissions};
+
+    /// A user manager, plus a user who solely owns one freshly created project.
+    /// Returns `(server, state, admin auth, owner id, project id)`.
+    async fn setup() -> (TestServer, Arc<AppState>, HeaderValue, String, String) {
+        let state = Arc::new(create_mock_shared_state().await.unwrap());
+        let db = state.db.clone();
+        let server = TestServer::new(create_app(state.clone())).expect("Failed to create TestServer");
+
+        let admin = unique("cadmin");
+        let admin_auth = register_and_login(&server, &admin).await;
+        db.grant_permission(super_permissions::ADM_USER_MANAGER, &format!("u_{}", admin))
+            .await
+            .unwrap();
+
+        let owner = unique("cowner");
+        let owner_auth = register_and_login(&server, &owner).await;
+        let owner_id = format!("u_{}", owner);
+        db.grant_permission(super_permissions::USR_CREATE_PROJECTS, &owner_id).await.unwrap();
+        let project = unique("cproj");
+        server
+            .post("/api/v1/global/projects")
+            .add_header(AUTHORIZATION, owner_auth)
+            .json(&json!({ "id": &project, "name": "Owned" }))
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        (server, state, admin_auth, owner_id, project)
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_delete_refused_while_user_solely_owns_projects() {
+        let (server, state, admin_auth, owner_id, project) = setup().await;
+        let db = &state.db;
+
+        let resp = server
+            .delete(&format!("/api/v1/global/users/{}", owner_id))
+            .add_header(AUTHORIZATION, admin_auth.clone())
+            .await;
+        resp.assert_status(StatusCode::CONFLICT);
+        assert!(resp.text().contains(&project));
+        assert!(db.generic_get("users", &owner_id).await.unwrap().is_some());
+
+        server
+            .delete(&format!("/api/v1/global/users/{}?cascade=archive", owner_id))
+            .add_header(AUTHORIZATION, admin_auth)
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_cascade_delete_removes_owned_projects() {
+        let (server, state, admin_auth, owner_id, project) = setup().await;
+        let db = &state.db;
+
+        server
+            .delete(&format!("/api/v1/global/users/{}?cascade=delete", owner_id))
+            .add_header(AUTHORIZATION, admin_auth)
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+
+        assert!(db.generic_get("users", &owner_id).await.unwrap().is_none());
+        assert!(db.generic_get("projects", &project).await.unwrap().is_none());
+        assert!(!state.indexes.query_index("projects_by_owner", &owner_id).await.contains(&project));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_cascade_reassign_hands_projects_to_another_user() {
+        let (server, state, admin_auth, owner_id, project) = setup().await;
+        let db = &state.db;
+        let heir = unique("cheir");
+        register_and_login(&server, &heir).await;
+        let heir_id = format!("u_{}", heir);
+
+        // The target must exist.
+        server
+            .delete(&format!("/api/v1/global/users/{}?cascade=reassign&reassign_to=u_nobody", owner_id))
+            .add_header(AUTHORIZATION, admin_auth.clone())
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+
+        server
+            .delete(&format!("/api/v1/global/users/{}?cascade=reassign&reassign_to={}", owner_id, heir_id))
+            .add_header(AUTHORIZATION, admin_auth)
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+
+        let doc = db.generic_get("projects", &project).await.unwrap().expect("project kept");
+        assert_eq!(ProjectController::role_of(&doc, &[heir_id]), Some(ProjectRole::Owner));
+        assert_eq!(ProjectController::role_of(&doc, std::slice::from_ref(&owner_id)), None);
+        assert!(db.generic_get("users", &owner_id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_cascade_writes_reach_indexes_and_watchers() {
+        let (server, state, admin_auth, owner_id, project) = setup().await;
+        let heir = unique("cwheir");
+        register_and_login(&server, &heir).await;
+        let heir_id = format!("u_{}", heir);
+        assert!(state.indexes.query_index("projects_by_owner", &owner_id).await.contains(&project));
+        let mut events = state.watch.subscribe("projects").await;
+
+        server
+            .delete(&format!("/api/v1/global/users/{}?cascade=reassign&reassign_to={}", owner_id, heir_id))
+            .add_header(AUTHORIZATION, admin_auth)
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+
+        assert!(!state.indexes.query_index("projects_by_owner", &owner_id).await.contains(&project));
+        assert!(state.indexes.query_index("projects_by_owner", &heir_id).await.contains(&project));
+        let event = events.try_recv().expect("cascade published a projects event");
+        assert_eq!(event.change, ChangeType::Updated);
+        assert_eq!(event.id, project);
+    }
+
+    /// The same cascade on `InMemoryDb`: u_owner solely owns p_owned and is the
+    /// last member of g_solo; g_team keeps u_heir.
+    fn in_memory_setup() -> (Arc<InMemoryDb>, Controller) {
+        let db = Arc::new(InMemoryDb::new());
+        for user in ["u_owner", "u_heir"] {
+            db.insert("users", json!({ "_key": user, "password_hash": "", "personal": {} })).unwrap();
+        }
+        for group in ["g_solo", "g_team"] {
+            db.insert("groups", json!({ "_key": group })).unwrap();
+        }
+        db.insert(
+            "projects",
+            json!({
+                "_key": "p_owned",
+                "name": "Owned",
+                "acl": {
+                    "list": [{ "permissions": Permissions::ROOT.bits(), "principals": ["u_owner"] }],
+                    "last_mod_date": "2025-01-01T00:00:00Z",
+                },
+            }),
+        )
+        .unwrap();
+        let controller = Controller::new(db.clone());
+        (db, controller)
+    }
+
+    async fn in_memory_memberships(db: &InMemoryDb) {
+        db.add_principal_to_group(&"u_owner".into(), &"g_solo".into()).await.unwrap();
+        db.add_principals_to_group(&["u_owner".into(), "u_heir".into()], &"g_team".into()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn in_memory_delete_refused_while_user_solely_owns_projects() {
+        let (db, controller) = in_memory_setup();
+        in_memory_memberships(&db).await;
+
+        let err = delete_cascading(&controller.user, &*db, "users", "u_owner", "u_admin", &CascadePolicy::Refuse)
+            .await
+            .unwrap_err();
+        assert!(matches!(&err, AppError::Conflict(msg) if msg.contains("p_owned")), "{:?}", err);
+
+        assert!(db.generic_get("users", "u_owner").await.unwrap().is_some());
+        assert!(db.generic_get("projects", "p_owned").await.unwrap().is_some());
+        assert_eq!(db.list_groups_of(&"u_owner".into()).await.unwrap(), vec!["g_solo", "g_team"]);
+    }
+
+    #[tokio::test]
+    async fn in_memory_cascade_delete_removes_projects_and_memberships() {
+        let (db, controller) = in_memory_setup();
+        in_memory_memberships(&db).await;
+
+        let cascaded = delete_cascading(&controller.user, &*db, "users", "u_owner", "u_admin", &CascadePolicy::Delete)
+            .await
+            .unwrap();
+        assert_eq!(cascaded.len(), 1);
+        assert_eq!((cascaded[0].change, cascaded[0].id.as_str()), (ChangeType::Deleted, "p_owned"));
+
+        assert!(db.generic_get("users", "u_owner").await.unwrap().is_none());
+        assert!(db.generic_get("projects", "p_owned").await.unwrap().is_none());
+        assert!(db.list_groups_of(&"u_owner".into()).await.unwrap().is_empty());
+        // The emptied group goes with its last member; the other keeps u_heir.
+        assert!(db.generic_get("groups", "g_solo").await.unwrap().is_none());
+        assert_eq!(db.list_group_members(&"g_team".into()).await.unwrap(), vec!["u_heir"]);
+    }
+
+    #[tokio::test]
+    async fn in_memory_cascade_reassign_hands_projects_to_another_user() {
+        let (db, controller) = in_memory_setup();
+        let reassign = |to: &str| CascadePolicy::Reassign(to.to_string());
+
+        let err = delete_cascading(&controller.user, &*db, "users", "u_owner", "u_admin", &reassign("u_nobody"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::NotFound(_)), "{:?}", err);
+        assert!(db.generic_get("users", "u_owner").await.unwrap().is_some());
+
+        delete_cascading(&controller.user, &*db, "users", "u_owner", "u_admin", &reassign("u_heir"))
+            .await
+            .unwrap();
+
+        let doc = db.generic_get("projects", "p_owned").await.unwrap().expect("project kept");
+        assert_eq!(ProjectController::role_of(&doc, &["u_heir".to_string()]), Some(ProjectRole::Owner));
+        assert_eq!(ProjectController::role_of(&doc, &["u_owner".to_string()]), None);
+        assert!(db.generic_get("users", "u_owner").await.unwrap().is_none());
+    }
+}
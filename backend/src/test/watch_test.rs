@@ -0,0 +1,153 @@
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode, header::AUTHORIZATION},
+    };
+    use axum_test::TestServer;
+    use futures_util::StreamExt;
+    use serial_test::serial;
+    use serde_json::{Value, json};
+    use tower::ServiceExt;
+
+    use crate::{create_app, create_mock_shared_state, watch::ChangeType};
+    use crate::test::helpers::{register_and_login, unique};
+    use crit_shared::util_models::super_permissions;
+
+    #[tokio::test]
+    #[serial]
+    async fn test_upsert_emits_updated_event_on_watch_stream() {
+        let state = Arc::new(create_mock_shared_state().await.unwrap());
+        let server = TestServer::new(create_app(state.clone())).expect("Failed to create TestServer");
+        let user = unique("watcher");
+        let auth = register_and_login(&server, &user).await;
+        state.db.grant_permission(super_permissions::ADM_GODMODE, &format!("u_{}", user)).await.unwrap();
+        let group = unique("watchgrp");
+        server
+            .post("/api/v1/global/groups")
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&json!({ "id": &group, "name": "Before" }))
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        // Opened on the router directly: TestServer would wait for the endless body.
+        let request = Request::get("/api/v1/ops/watch/groups")
+            .header(AUTHORIZATION, auth.clone())
+            .body(Body::empty())
+            .unwrap();
+        let router = create_app(state.clone()).oneshot(()).await.unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let mut body = response.into_body().into_data_stream();
+
+        server
+            .post(&format!("/api/v1/global/groups/{}", group))
+            .add_header(AUTHORIZATION, auth)
+            .json(&json!({ "name": "After" }))
+            .await
+            .assert_status_ok();
+
+        // Read whole SSE frames until the `updated` one arrives.
+        let frame = tokio::time::timeout(Duration::from_secs(5), async {
+            let mut received = String::new();
+            while let Some(chunk) = body.next().await {
+                received.push_str(std::str::from_utf8(&chunk.unwrap()).unwrap());
+                let complete = &received[..received.rfind("\n\n").unwrap_or(0)];
+                if let Some(frame) = complete.split("\n\n").find(|f| f.contains("event: updated")) {
+                    return frame.to_string();
+                }
+            }
+            panic!("watch stream ended without an updated event");
+        })
+        .await
+        .expect("no updated event within 5s");
+
+        let data = frame.lines().find_map(|l| l.strip_prefix("data: ")).expect("event has data");
+        let data: Value = serde_json::from_str(data).unwrap();
+        assert_eq!(data["id"], json!(group));
+        assert_eq!(data["object"]["name"], "After");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_object_with_id_watch_is_not_shadowed() {
+        let state = Arc::new(create_mock_shared_state().await.unwrap());
+        let server = TestServer::new(create_app(state.clone())).expect("Failed to create TestServer");
+        let auth = register_and_login(&server, &unique("watchid")).await;
+        let kind = unique("wwidgets");
+
+        server
+            .post(&format!("/api/v1/global/{}", kind))
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&json!({ "id": "watch", "size": 3 }))
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        let resp = server
+            .get(&format!("/api/v1/global/{}/watch", kind))
+            .add_header(AUTHORIZATION, auth)
+            .await;
+        resp.assert_status_ok();
+        assert_eq!(resp.json::<Value>()["size"], 3);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_scoped_and_metadata_writes_reach_watchers() {
+        let state = Arc::new(create_mock_shared_state().await.unwrap());
+        let server = TestServer::new(create_app(state.clone())).expect("Failed to create TestServer");
+        let user = unique("wscoped");
+        let auth = register_and_login(&server, &user).await;
+        state
+            .db
+            .grant_permission(super_permissions::USR_CREATE_PROJECTS, &format!("u_{}", user))
+            .await
+            .unwrap();
+        let project = unique("wproj");
+        server
+            .post("/api/v1/global/projects")
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&json!({ "id": &project, "name": "Watched" }))
+            .await
+            .assert_status(StatusCode::CREATED);
+
+        let kind = unique("wtasks");
+        let mut events = state.watch.subscribe(&kind).await;
+        let path = format!("/api/v1/projects/{}/{}", project, kind);
+        server
+            .post(&path)
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&json!({ "id": "t1", "title": "First" }))
+            .await
+            .assert_status(StatusCode::CREATED);
+        server
+            .put(&format!("{}/t1", path))
+            .add_header(AUTHORIZATION, auth.clone())
+            .json(&json!({ "title": "Second" }))
+            .await
+            .assert_status_ok();
+        server
+            .delete(&format!("{}/t1", path))
+            .add_header(AUTHORIZATION, auth.clone())
+            .await
+            .assert_status(StatusCode::NO_CONTENT);
+        for change in [ChangeType::Created, ChangeType::Updated, ChangeType::Deleted] {
+            let ev = events.try_recv().expect("scoped write published");
+            assert_eq!((ev.change, ev.id.as_str()), (change, "t1"));
+        }
+
+        let mut projects = state.watch.subscribe("projects").await;
+        for what in ["annotate", "label"] {
+            server
+                .patch(&format!("/api/v1/ops/{}/projects/{}", what, project))
+                .add_header(AUTHORIZATION, auth.clone())
+                .json(&json!({ "set": { "tier": "gold" } }))
+                .await
+                .assert_status_ok();
+            let ev = projects.try_recv().expect("metadata edit published");
+            assert_eq!((ev.change, ev.id.as_str()), (ChangeType::Updated, project.as_str()));
+        }
+    }
+}
@@ -0,0 +1,59 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::{StatusCode, header::AUTHORIZATION};
+    use axum_test::TestServer;
+    use serial_test::serial;
+    use serde_json::json;
+
+    use crate::{create_app, create_mock_shared_state};
+    use crate::test::helpers::{register_and_login, unique};
+    use crit_shared::requests::{ApplyAction, ApplyResponse, ErrorBody};
+
+    #[tokio::test]
+    #[serial]
+    async fn test_upsert_returns_apply_response() {
+        let state = create_mock_shared_state().await.unwrap();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        let auth = register_and_login(&server, &unique("applier")).await;
+        let kind = unique("appliables");
+        let path = format!("/api/v1/global/{}/w1", kind);
+
+        let apply = |body: serde_json::Value| {
+            server.post(&path).add_header(AUTHORIZATION, auth.clone()).json(&body)
+        };
+
+        let first: ApplyResponse = apply(json!({ "size": 1 })).await.json();
+        assert_eq!(first.key, "w1");
+        assert_eq!(first.kind, kind);
+        assert_eq!(first.action, ApplyAction::Created);
+
+        let again: ApplyResponse = apply(json!({ "size": 1 })).await.json();
+        assert_eq!(again.action, ApplyAction::Unchanged);
+        assert_eq!(again.hash, first.hash);
+
+        let changed: ApplyResponse = apply(json!({ "size": 2 })).await.json();
+        assert_eq!(changed.action, ApplyAction::Updated);
+        assert_ne!(changed.hash, first.hash);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_errors_use_shared_error_body() {
+        let state = create_mock_shared_state().await.unwrap();
+        let server =
+            TestServer::new(create_app(Arc::new(state))).expect("Failed to create TestServer");
+        let auth = register_and_login(&server, &unique("erring")).await;
+
+        let resp = server
+            .get(&format!("/api/v1/global/{}/missing", unique("nothings")))
+            .add_header(AUTHORIZATION, auth)
+            .await;
+        resp.assert_status(StatusCode::NOT_FOUND);
+        let body: ErrorBody = resp.json();
+        assert_eq!(body.error.code, "not_found");
+        assert_eq!(body.error.status, 404);
+    }
+}
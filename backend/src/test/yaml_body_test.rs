@@ -0,0 +1,90 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::http::{HeaderValue, StatusCode, header::{ACCEPT, AUTHORIZATION}};
+    use axum_test::TestServer;
+    use serial_test::serial;
+    use serde_json::Value;
+
+    use crate::{create_app, create_mock_shared_state, schema::*};
+    use crate::test::helpers::{PASSWORD, unique};
+    use crit_shared::requests::ErrorBody;
+
+    async fn setup() -> (TestServer, HeaderValue) {
+        let state = Arc::new(create_mock_shared_state().await.unwrap());
+        let server = TestServer::new(create_app(state)).expect("Failed to create TestServer");
+        let user = unique("yamluser");
+        server
+            .post("/api/v1/register")
+            .json(&RegisterRequest { user: user.clone(), password: PASSWORD.into() })
+            .await
+            .assert_status(StatusCode::CREATED);
+        let resp = server
+            .post("/api/v1/login")
+            .json(&LoginRequest { user, password: PASSWORD.into() })
+            .await;
+        resp.assert_status_ok();
+        let auth = format!("Bearer {}", resp.json::<LoginResponse>().token).parse().unwrap();
+        (server, auth)
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_yaml_body_creates_and_upserts() {
+        let (server, auth) = setup().await;
+        let id = unique("g_yaml");
+
+        // YAML in, JSON out (no Accept preference).
+        let yaml = format!("# created from YAML\nid: {}\nname: Yaml Group\nlabels:\n  team: sre\n", id);
+        let resp = server
+            .post("/api/v1/global/groups")
+            .add_header(AUTHORIZATION, auth.clone())
+            .bytes(yaml.into())
+            .content_type("application/yaml")
+            .await;
+        resp.assert_status(StatusCode::CREATED);
+        assert_eq!(resp.json::<Value>()["id"], id.as_str());
+
+        let stored: Value = server
+            .get(&format!("/api/v1/global/groups/{}", id))
+            .add_header(AUTHORIZATION, auth.clone())
+            .await
+            .json();
+        assert_eq!(stored["name"], "Yaml Group");
+        assert_eq!(stored["labels"]["team"], "sre");
+
+        // YAML in, YAML out.
+        let resp = server
+            .post(&format!("/api/v1/global/groups/{}", id))
+            .add_header(AUTHORIZATION, auth.clone())
+            .add_header(ACCEPT, HeaderValue::from_static("application/yaml"))
+            .bytes(format!("name: Renamed\nhash_code: {}\n", stored["hash_code"].as_str().unwrap()).into())
+            .content_type("text/yaml")
+            .await;
+        resp.assert_status_ok();
+        assert_eq!(resp.header("content-type"), "application/yaml");
+        let applied: Value = serde_yaml::from_str(&resp.text()).unwrap();
+        assert_eq!(applied["key"], id.as_str());
+        assert_eq!(applied["action"], "updated");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_malformed_yaml_reports_location() {
+        let (server, auth) = setup().await;
+
+        let resp = server
+            .post("/api/v1/global/groups")
+            .add_header(AUTHORIZATION, auth)
+            .bytes("id: g_bad\nname: Bad\n  nested: wrong\n".into())
+            .content_type("application/yaml")
+            .await;
+        resp.assert_status(StatusCode::BAD_REQUEST);
+        let body: ErrorBody = resp.json();
+        assert_eq!(body.error.code, "invalid_body");
+        let details = body.error.details.expect("details carry the location");
+        assert_eq!(details["line"], 3);
+        assert!(details["column"].as_u64().is_some());
+    }
+}
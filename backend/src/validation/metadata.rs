@@ -0,0 +1,135 @@
+use serde_json::Value;
+
+use crit_shared::labels::{validate_key, validate_value};
+
+/// Max total serialized size of all annotations on one resource.
+pub const ANNOTATIONS_MAX_BYTES: usize = 256 * 1024;
+
+/// Label and annotation keys under this prefix belong to the server.
+pub const RESERVED_PREFIX: &str = "critical.io/";
+
+/// Reserved keys that clients may still set or remove through
+/// `/v1/ops/annotate` and `/v1/ops/label`.
+pub const CLIENT_RESERVED_KEYS: &[&str] = &["critical.io/description", "critical.io/tier"];
+
+/// Whether a client may set or remove metadata key `key`.
+pub fn check_client_key(key: &str) -> Result<(), String> {
+    if key.starts_with(RESERVED_PREFIX) && !CLIENT_RESERVED_KEYS.contains(&key) {
+        return Err(format!(
+            "'{}' uses the reserved prefix '{}'; allowed reserved keys: {}",
+            key,
+            RESERVED_PREFIX,
+            CLIENT_RESERVED_KEYS.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// Validate the `labels` field of a resource document. Labels are queryable
+/// metadata, so keys and values follow the `crit_shared::labels` rules. They
+/// are checked here, on the way in; reads accept whatever is stored.
+/// A missing or null field is valid.
+pub fn validate_labels(labels: Option<&Value>) -> Result<(), String> {
+    let Some(labels) = labels.filter(|v| !v.is_null()) else {
+        return Ok(());
+    };
+    let map = labels
+        .as_object()
+        .ok_or_else(|| "labels must be an object of string values".to_string())?;
+    for (key, value) in map {
+        validate_key(key).map_err(|e| e.to_string())?;
+        let value = value
+            .as_str()
+            .ok_or_else(|| format!("label '{}' value must be a string", key))?;
+        validate_value(key, value).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Validate the `annotations` field of a resource document. Annotations are
+/// freeform; only their total serialized size is capped.
+pub fn validate_annotations(annotations: Option<&Value>) -> Result<(), String> {
+    let Some(annotations) = annotations.filter(|v| !v.is_null()) else {
+        return Ok(());
+    };
+    if !annotations.is_object() {
+        return Err("annotations must be an object of string values".to_string());
+    }
+    let size = serde_json::to_string(annotations).map(|s| s.len()).unwrap_or(0);
+    if size > ANNOTATIONS_MAX_BYTES {
+        return Err(format!(
+            "annotations too large: {} bytes, maximum is {}",
+            size, ANNOTATIONS_MAX_BYTES
+        ));
+    }
+    Ok(())
+}
+
+/// Validate both metadata maps of a resource document before it is written.
+pub fn validate_resource_metadata(doc: &Value) -> Result<(), String> {
+    validate_labels(doc.get("labels"))?;
+    validate_annotations(doc.get("annotations"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn ok_labels() {
+        let doc = json!({ "labels": { "team": "platform", "critical.io/tier": "Gold 1", "empty": "" } });
+        assert!(validate_resource_metadata(&doc).is_ok());
+    }
+
+    #[test]
+    fn missing_metadata_is_ok() {
+        assert!(validate_resource_metadata(&json!({ "name": "x" })).is_ok());
+        assert!(validate_resource_metadata(&json!({ "labels": null })).is_ok());
+    }
+
+    #[test]
+    fn too_long_label_key_is_rejected() {
+        let key = "k".repeat(64);
+        let err = validate_labels(Some(&json!({ &key: "v" }))).unwrap_err();
+        assert!(err.contains(&key));
+        assert!(err.contains("at most 63"));
+    }
+
+    #[test]
+    fn illegal_label_value_character_is_rejected() {
+        let err = validate_labels(Some(&json!({ "team": "plat\tform" }))).unwrap_err();
+        assert!(err.contains("'team'"));
+        assert!(err.contains("control characters"));
+    }
+
+    #[test]
+    fn uppercase_label_key_is_rejected() {
+        let err = validate_labels(Some(&json!({ "Team": "platform" }))).unwrap_err();
+        assert!(err.contains("label key 'Team'"));
+    }
+
+    #[test]
+    fn non_string_label_value_is_rejected() {
+        assert!(validate_labels(Some(&json!({ "count": 3 }))).is_err());
+    }
+
+    #[test]
+    fn reserved_prefix_is_refused_outside_the_allowlist() {
+        assert!(check_client_key("team").is_ok());
+        assert!(check_client_key("critical.io/tier").is_ok());
+        let err = check_client_key("critical.io/managed-by").unwrap_err();
+        assert!(err.contains("reserved prefix 'critical.io/'"), "{}", err);
+        assert!(check_client_key("example.com/critical.io/x").is_ok());
+    }
+
+    #[test]
+    fn annotations_are_freeform_but_size_capped() {
+        let doc = json!({ "annotations": { "note": "anything: goes / here!" } });
+        assert!(validate_resource_metadata(&doc).is_ok());
+
+        let big = "x".repeat(ANNOTATIONS_MAX_BYTES);
+        let err = validate_annotations(Some(&json!({ "blob": big }))).unwrap_err();
+        assert!(err.contains("annotations too large"));
+    }
+}
@@ -1,3 +1,4 @@
+pub mod metadata;
 pub mod naming;
 
 use std::collections::HashSet;
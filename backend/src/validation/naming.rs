@@ -1,9 +1,16 @@
 use crate::validation::*;
 
+/// Canonical form of a user-typed uid: surrounding whitespace trimmed, lowercased.
+/// Registration and login both go through this so the same human always
+/// resolves to the same account key.
+pub fn normalize_uid(uid: &str) -> String {
+    force_lowercase()(uid.trim())
+}
+
 /// Validate a username (without the u_ prefix).
-/// Returns the lowercased, validated username.
+/// Returns the normalized (see `normalize_uid`), validated username.
 pub fn validate_username(username: &str) -> Result<String, String> {
-    let lowercased = force_lowercase()(username);
+    let lowercased = normalize_uid(username);
     let validators: Vec<ValidatorFn> = vec![
             limit_length(63),
             limit_min_length(2),
@@ -33,6 +40,59 @@ pub fn validate_group_id(group_id: &str) -> Result<String, String> {
     Ok(lowercased)
 }
 
+/// Readable id fragment from free text ("Platform Team!" -> `platform_team`):
+/// lowercased, common Latin accents folded to ASCII ("Zürich" -> `zurich`),
+/// runs of other characters collapsed to `_`, at most 40 chars, starting
+/// with a letter. Valid as both a username and a group id. `None` when fewer
+/// than two usable characters remain.
+pub fn slugify(text: &str) -> Option<String> {
+    let mut slug = String::new();
+    for c in text.trim().to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if let Some(folded) = fold_latin(c) {
+            slug.push_str(folded);
+        } else if !slug.is_empty() && !slug.ends_with('_') {
+            slug.push('_');
+        }
+    }
+    let slug = slug.trim_start_matches(|c: char| c.is_ascii_digit() || c == '_');
+    let slug: String = slug.chars().take(40).collect();
+    let slug = slug.trim_end_matches('_');
+    (slug.len() >= 2).then(|| slug.to_string())
+}
+
+/// ASCII spelling of a lowercase accented Latin letter.
+fn fold_latin(c: char) -> Option<&'static str> {
+    const TABLE: &[(&str, &str)] = &[
+        ("àáâãäåāăą", "a"),
+        ("çćĉċč", "c"),
+        ("ďđ", "d"),
+        ("èéêëēĕėęě", "e"),
+        ("ĝğġģ", "g"),
+        ("ĥħ", "h"),
+        ("ìíîïĩīĭįı", "i"),
+        ("ĵ", "j"),
+        ("ķ", "k"),
+        ("ĺļľŀł", "l"),
+        ("ñńņňŉ", "n"),
+        ("òóôõöøōŏő", "o"),
+        ("ŕŗř", "r"),
+        ("śŝşš", "s"),
+        ("ţťŧ", "t"),
+        ("ùúûüũūŭůűų", "u"),
+        ("ŵ", "w"),
+        ("ýÿŷ", "y"),
+        ("źżž", "z"),
+        ("æ", "ae"),
+        ("œ", "oe"),
+        ("ß", "ss"),
+        ("þ", "th"),
+        ("ð", "d"),
+    ];
+    TABLE.iter().find(|(chars, _)| chars.contains(c)).map(|(_, ascii)| *ascii)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -63,6 +123,12 @@ mod tests {
         assert!(err.contains("cannot start with a digit"));
     }
 
+    #[test]
+    fn normalize_uid_trims_and_lowercases() {
+        assert_eq!(normalize_uid("  Root \t"), "root");
+        assert_eq!(validate_username("Root ").unwrap(), "root");
+    }
+
     #[test]
     fn case_conversion_happens_first() {
         let r = validate_username("abcXYZ").unwrap();
@@ -125,4 +191,21 @@ mod tests {
         let err = validate_group_id("-group").unwrap_err();
         assert!(err.contains("cannot start with '-'"));
     }
+
+    #[test]
+    fn slugs_are_valid_ids() {
+        assert_eq!(slugify("Platform Team!").as_deref(), Some("platform_team"));
+        assert_eq!(slugify("  42 Ops -- EU  ").as_deref(), Some("ops_eu"));
+        assert_eq!(slugify("!"), None);
+        assert_eq!(slugify("Café Zürich").as_deref(), Some("cafe_zurich"));
+        assert_eq!(slugify("Straße ØRESUND").as_deref(), Some("strasse_oresund"));
+        assert_eq!(slugify("Команда dev").as_deref(), Some("dev"));
+        assert_eq!(slugify("東京"), None);
+        let long = slugify(&"abc ".repeat(30)).unwrap();
+        assert!(long.len() <= 40 && !long.ends_with('_'));
+        for slug in ["platform_team", "ops_eu", &long] {
+            validate_username(slug).unwrap();
+            validate_group_id(slug).unwrap();
+        }
+    }
 }
@@ -0,0 +1,152 @@
+//! In-process change feed for gitops resources.
+//!
+//! Each kind gets a lazily-created `tokio::sync::broadcast` channel. The gitops
+//! handlers publish a `ChangeEvent` after every successful write, and the SSE
+//! watch endpoint subscribes to the channel for the kind it streams.
+//! Late subscribers get no backfill — only events published after they subscribe.
+//!
+//! The hub also counts the changes published per kind. That count, tagged with
+//! a per-process epoch, is the version token of the long-poll list endpoint.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::{RwLock, broadcast};
+
+/// Buffered events per kind before slow subscribers start lagging.
+pub const WATCH_CHANNEL_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeType {
+    Created,
+    Updated,
+    Deleted,
+}
+
+impl ChangeType {
+    /// SSE `event:` name for this change.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeType::Created => "created",
+            ChangeType::Updated => "updated",
+            ChangeType::Deleted => "deleted",
+        }
+    }
+}
+
+/// A single change to a resource. `doc` is the stored (internal) document so
+/// subscribers can run their own ACL check and external conversion.
+#[derive(Debug, Clone)]
+pub struct ChangeEvent {
+    pub change: ChangeType,
+    pub kind: String,
+    pub id: String,
+    pub doc: Value,
+}
+
+/// Registry of per-kind broadcast channels and change counters.
+pub struct WatchHub {
+    channels: RwLock<HashMap<String, broadcast::Sender<ChangeEvent>>>,
+    versions: Mutex<HashMap<String, u64>>,
+    /// Distinguishes this process's counters from those of a previous run, so
+    /// a token from before a restart never matches.
+    epoch: String,
+}
+
+impl WatchHub {
+    pub fn new() -> Self {
+        Self {
+            channels: RwLock::new(HashMap::new()),
+            versions: Mutex::new(HashMap::new()),
+            epoch: ulid::Ulid::new().to_string(),
+        }
+    }
+
+    /// Version token of `kind`: changes with every change published for it.
+    pub fn version(&self, kind: &str) -> String {
+        let count = self.versions.lock().unwrap().get(kind).copied().unwrap_or(0);
+        format!("{}-{}", self.epoch, count)
+    }
+
+    /// Subscribe to changes of `kind`, creating the channel on first use.
+    pub async fn subscribe(&self, kind: &str) -> broadcast::Receiver<ChangeEvent> {
+        if let Some(tx) = self.channels.read().await.get(kind) {
+            return tx.subscribe();
+        }
+        let mut channels = self.channels.write().await;
+        channels
+            .entry(kind.to_string())
+            .or_insert_with(|| broadcast::channel(WATCH_CHANNEL_CAPACITY).0)
+            .subscribe()
+    }
+
+    /// Publish a change: bump the kind's version, then notify its watchers, if any.
+    pub async fn publish(&self, change: ChangeType, kind: &str, id: &str, doc: Value) {
+        *self.versions.lock().unwrap().entry(kind.to_string()).or_default() += 1;
+        let channels = self.channels.read().await;
+        if let Some(tx) = channels.get(kind) {
+            // Err only means there are no live receivers right now.
+            let _ = tx.send(ChangeEvent {
+                change,
+                kind: kind.to_string(),
+                id: id.to_string(),
+                doc,
+            });
+        }
+    }
+}
+
+impl Default for WatchHub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn subscriber_receives_events_published_after_subscribe() {
+        let hub = WatchHub::new();
+        hub.publish(ChangeType::Created, "users", "u_early", json!({})).await;
+
+        let mut rx = hub.subscribe("users").await;
+        hub.publish(ChangeType::Updated, "users", "u_alice", json!({ "_key": "u_alice" }))
+            .await;
+
+        let ev = rx.recv().await.unwrap();
+        assert_eq!(ev.change, ChangeType::Updated);
+        assert_eq!(ev.id, "u_alice");
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn events_are_isolated_per_kind() {
+        let hub = WatchHub::new();
+        let mut users = hub.subscribe("users").await;
+        let _groups = hub.subscribe("groups").await;
+
+        hub.publish(ChangeType::Deleted, "groups", "g_ops", json!({})).await;
+        assert!(users.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn version_changes_with_each_publish_of_its_kind() {
+        let hub = WatchHub::new();
+        let before = hub.version("users");
+        assert_eq!(hub.version("users"), before);
+
+        hub.publish(ChangeType::Updated, "groups", "g_ops", json!({})).await;
+        assert_eq!(hub.version("users"), before);
+
+        // Counted even with nobody watching.
+        hub.publish(ChangeType::Created, "users", "u_alice", json!({})).await;
+        assert_ne!(hub.version("users"), before);
+        assert_ne!(WatchHub::new().version("users"), before);
+    }
+}
@@ -1,11 +1,131 @@
-use anyhow::{Result, bail};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use anyhow::Result;
+use crit_shared::requests::{ApplyResponse, ErrorBody, KindInfo, ListResponse};
+use log::debug;
+use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+/// Network behaviour shared by every request, set once from the command line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetOptions {
+    /// Extra attempts for idempotent requests after the first one fails.
+    pub retries: u32,
+    /// Per-request timeout; `None` waits forever.
+    pub timeout: Option<Duration>,
+}
+
+impl Default for NetOptions {
+    fn default() -> Self {
+        Self { retries: 2, timeout: Some(Duration::from_secs(30)) }
+    }
+}
+
+static NET_OPTIONS: OnceLock<NetOptions> = OnceLock::new();
+
+/// Set the options used by all later requests. Only the first call has an effect.
+pub fn configure(options: NetOptions) {
+    let _ = NET_OPTIONS.set(options);
+}
+
+fn net_options() -> NetOptions {
+    NET_OPTIONS.get().copied().unwrap_or_default()
+}
+
+fn client() -> reqwest::Client {
+    let mut builder = reqwest::Client::builder();
+    if let Some(timeout) = net_options().timeout {
+        builder = builder.timeout(timeout);
+    }
+    builder.build().unwrap_or_default()
+}
+
+/// Why one attempt of a request failed, as far as retrying is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Failure {
+    Status(StatusCode),
+    Timeout,
+    Connect,
+    /// Anything else (invalid URL, TLS setup, body encoding): retrying won't help.
+    Other,
+}
+
+impl From<&reqwest::Error> for Failure {
+    fn from(e: &reqwest::Error) -> Self {
+        if e.is_timeout() {
+            Failure::Timeout
+        } else if e.is_connect() {
+            Failure::Connect
+        } else {
+            Failure::Other
+        }
+    }
+}
+
+/// Transient failures worth another attempt: timeouts, refused or dropped
+/// connections, and statuses that proxies and overloaded servers return.
+/// Other 4xx/5xx answers are final.
+fn is_retryable(failure: Failure) -> bool {
+    match failure {
+        Failure::Timeout | Failure::Connect => true,
+        Failure::Status(status) => matches!(
+            status,
+            StatusCode::REQUEST_TIMEOUT
+                | StatusCode::TOO_MANY_REQUESTS
+                | StatusCode::BAD_GATEWAY
+                | StatusCode::SERVICE_UNAVAILABLE
+                | StatusCode::GATEWAY_TIMEOUT
+        ),
+        Failure::Other => false,
+    }
+}
+
+/// Wait before attempt `attempt + 1`: 250ms, 500ms, 1s, ... capped at 8s.
+fn backoff(attempt: u32) -> Duration {
+    Duration::from_millis(250 * 2u64.pow(attempt.saturating_sub(1).min(5))).min(Duration::from_secs(8))
+}
+
+/// Send an idempotent request (a GET, or an upsert keyed by resource id),
+/// retrying transient failures with exponential backoff. The last response is
+/// returned whatever its status, so callers keep their own status handling.
+async fn send_idempotent(
+    url: &str,
+    build: impl Fn(&reqwest::Client) -> reqwest::RequestBuilder,
+) -> Result<reqwest::Response> {
+    let client = client();
+    let attempts = net_options().retries + 1;
+    let mut attempt = 1;
+    loop {
+        let result = build(&client).send().await;
+        let failure = match &result {
+            Ok(resp) if resp.status().is_success() => None,
+            Ok(resp) => Some(Failure::Status(resp.status())),
+            Err(e) => Some(Failure::from(e)),
+        };
+        match failure {
+            Some(failure) if attempt < attempts && is_retryable(failure) => {
+                let wait = backoff(attempt);
+                debug!("attempt {}/{} for {} failed ({:?}), retrying in {:?}", attempt, attempts, url, failure, wait);
+                tokio::time::sleep(wait).await;
+                attempt += 1;
+            }
+            _ => {
+                return result.map_err(|e| {
+                    anyhow::anyhow!("request to {} failed after {} attempt(s): {}", url, attempt, e)
+                });
+            }
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct LoginRequest {
     pub user: String,
     pub password: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub totp_code: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -13,27 +133,45 @@ pub struct LoginResponse {
     pub token: String,
 }
 
-#[derive(Debug, Deserialize)]
-struct ApiErrorBody {
-    error: ApiErrorDetail,
+/// Render a failed response as `"{message} ({status})"` from the shared error
+/// body, falling back to the raw body text for servers that don't send one.
+/// Shown after a 413: the server refused one document as too big.
+const SPLIT_HINT: &str = "split large manifests into smaller documents and apply them separately";
+
+fn format_error(status: StatusCode, body: &str, what: &str) -> String {
+    match serde_json::from_str::<ErrorBody>(body) {
+        // Maintenance mode is an operator message, shown as the server wrote it.
+        Ok(body) if body.error.code == "read_only" => body.error.message,
+        Ok(body) if status == StatusCode::PAYLOAD_TOO_LARGE => {
+            format!("{} ({}); {}", body.error.message, status, SPLIT_HINT)
+        }
+        Err(_) if status == StatusCode::PAYLOAD_TOO_LARGE => {
+            format!("{}: request body too large ({}); {}", what, status, SPLIT_HINT)
+        }
+        Ok(body) => format!("{} ({})", body.error.message, status),
+        Err(_) if !body.trim().is_empty() => format!("{}: {} ({})", what, body.trim(), status),
+        Err(_) => format!("{} with status {}", what, status),
+    }
 }
 
-#[derive(Debug, Deserialize)]
-struct ApiErrorDetail {
-    message: String,
-    #[allow(dead_code)]
-    status: u16,
+async fn error_from(resp: reqwest::Response, what: &str) -> anyhow::Error {
+    let status = resp.status();
+    let body = resp.text().await.unwrap_or_default();
+    anyhow::anyhow!(format_error(status, &body, what))
 }
 
-pub async fn login(base_url: &str, user: &str, password: &str) -> Result<LoginResponse> {
+/// Message of the server's 401 when a user with 2FA on sent no `totp_code`.
+pub const TOTP_REQUIRED: &str = "two-factor code required";
+
+pub async fn login(base_url: &str, user: &str, password: &str, totp_code: Option<&str>) -> Result<LoginResponse> {
     let url = format!("{}/api/v1/login", base_url.trim_end_matches('/'));
 
-    let client = reqwest::Client::new();
-    let resp = client
+    let resp = client()
         .post(&url)
         .json(&LoginRequest {
             user: user.to_string(),
             password: password.to_string(),
+            totp_code: totp_code.map(str::to_string),
         })
         .send()
         .await?;
@@ -41,17 +179,13 @@ pub async fn login(base_url: &str, user: &str, password: &str) -> Result<LoginRe
     if resp.status().is_success() {
         Ok(resp.json::<LoginResponse>().await?)
     } else {
-        let status = resp.status();
-        match resp.json::<ApiErrorBody>().await {
-            Ok(body) => bail!("{} ({})", body.error.message, status),
-            Err(_) => bail!("login failed with status {}", status),
-        }
+        Err(error_from(resp, "login failed").await)
     }
 }
 
-pub async fn list_groups(base_url: &str, token: &str) -> Result<Value> {
+pub async fn list_groups(base_url: &str, token: &str) -> Result<ListResponse<Value>> {
     let url = format!("{}/api/v1/global/groups", base_url.trim_end_matches('/'));
-    fetch_authenticated(&url, token).await
+    fetch_list(&url, token).await
 }
 
 pub async fn get_group(base_url: &str, token: &str, id: &str) -> Result<Value> {
@@ -59,9 +193,9 @@ pub async fn get_group(base_url: &str, token: &str, id: &str) -> Result<Value> {
     fetch_authenticated(&url, token).await
 }
 
-pub async fn list_users(base_url: &str, token: &str) -> Result<Value> {
+pub async fn list_users(base_url: &str, token: &str) -> Result<ListResponse<Value>> {
     let url = format!("{}/api/v1/global/users", base_url.trim_end_matches('/'));
-    fetch_authenticated(&url, token).await
+    fetch_list(&url, token).await
 }
 
 pub async fn get_user(base_url: &str, token: &str, id: &str) -> Result<Value> {
@@ -69,26 +203,79 @@ pub async fn get_user(base_url: &str, token: &str, id: &str) -> Result<Value> {
     fetch_authenticated(&url, token).await
 }
 
-pub async fn list_kind(base_url: &str, token: &str, kind: &str) -> Result<Value> {
+/// List `kind` globally; a non-empty `fields` asks the server for only those paths.
+pub async fn list_kind(base_url: &str, token: &str, kind: &str, fields: &[String]) -> Result<ListResponse<Value>> {
     let url = format!("{}/api/v1/global/{}", base_url.trim_end_matches('/'), kind);
-    fetch_authenticated(&url, token).await
+    fetch_list(&with_fields(url, fields), token).await
 }
 
-pub async fn get_kind(base_url: &str, token: &str, kind: &str, id: &str) -> Result<Value> {
-    let url = format!("{}/api/v1/global/{}/{}", base_url.trim_end_matches('/'), kind, id);
+fn with_fields(url: String, fields: &[String]) -> String {
+    if fields.is_empty() { url } else { format!("{}?fields={}", url, fields.join(",")) }
+}
+
+/// Fetch one resource, globally or inside `project` for scoped kinds.
+pub async fn get_kind(base_url: &str, token: &str, kind: &str, id: &str, project: Option<&str>) -> Result<Value> {
+    let base = base_url.trim_end_matches('/');
+    let url = match project {
+        Some(project) => format!("{}/api/v1/projects/{}/{}/{}", base, project, kind, id),
+        None => format!("{}/api/v1/global/{}/{}", base, kind, id),
+    };
     fetch_authenticated(&url, token).await
 }
 
+/// The server's kind registry, or `None` for servers that don't expose one (404).
+pub async fn list_kinds(base_url: &str, token: &str) -> Result<Option<Vec<KindInfo>>> {
+    let url = format!("{}/api/v1/ops/kinds", base_url.trim_end_matches('/'));
+    let resp = get(&url, token).await?;
+    if resp.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !resp.status().is_success() {
+        return Err(error_from(resp, "listing kinds failed").await);
+    }
+    let list: ListResponse<KindInfo> = resp.json().await?;
+    Ok(Some(list.items))
+}
+
+/// List `kind`, globally or inside `project`. `None` when the caller may not
+/// read the kind (403), so aggregate listings can skip it.
+pub async fn try_list_kind(
+    base_url: &str,
+    token: &str,
+    kind: &str,
+    project: Option<&str>,
+    fields: &[String],
+) -> Result<Option<ListResponse<Value>>> {
+    let base = base_url.trim_end_matches('/');
+    let url = match project {
+        Some(project) => format!("{}/api/v1/projects/{}/{}", base, project, kind),
+        None => format!("{}/api/v1/global/{}", base, kind),
+    };
+    let resp = get(&with_fields(url, fields), token).await?;
+    if resp.status() == StatusCode::FORBIDDEN {
+        return Ok(None);
+    }
+    if !resp.status().is_success() {
+        return Err(error_from(resp, "request failed").await);
+    }
+    let body: Value = resp.json().await?;
+    serde_json::from_value(body.clone())
+        .map(Some)
+        .map_err(|_| anyhow::anyhow!("unexpected list response from server: {}", body))
+}
+
+async fn get(url: &str, token: &str) -> Result<reqwest::Response> {
+    send_idempotent(url, |client| {
+        client.get(url).header("Authorization", format!("Bearer {}", token))
+    })
+    .await
+}
+
 /// Fetch an existing resource, returning `None` if it does not exist (404).
 /// Other HTTP errors are returned as `Err`.
 pub async fn try_get_kind(base_url: &str, token: &str, kind: &str, id: &str) -> Result<Option<Value>> {
     let url = format!("{}/api/v1/global/{}/{}", base_url.trim_end_matches('/'), kind, id);
-    let client = reqwest::Client::new();
-    let resp = client
-        .get(&url)
-        .header("Authorization", format!("Bearer {}", token))
-        .send()
-        .await?;
+    let resp = get(&url, token).await?;
 
     if resp.status().as_u16() == 404 {
         return Ok(None);
@@ -96,22 +283,64 @@ pub async fn try_get_kind(base_url: &str, token: &str, kind: &str, id: &str) ->
     if resp.status().is_success() {
         return Ok(Some(resp.json::<Value>().await?));
     }
-    let status = resp.status();
-    match resp.json::<ApiErrorBody>().await {
-        Ok(body) => bail!("{} ({})", body.error.message, status),
-        Err(_) => bail!("request failed with status {}", status),
-    }
+    Err(error_from(resp, "request failed").await)
 }
 
-pub async fn apply_object(base_url: &str, token: &str, kind: &str, id: &str, body: Value) -> Result<Value> {
+/// Upsert a resource. Returns the server's `ApplyResponse`, or `None` when an
+/// older server answers with a different body.
+pub async fn apply_object(
+    base_url: &str,
+    token: &str,
+    kind: &str,
+    id: &str,
+    body: Value,
+) -> Result<Option<ApplyResponse>> {
     let url = format!("{}/api/v1/global/{}/{}", base_url.trim_end_matches('/'), kind, id);
-    post_authenticated(&url, token, body).await
+    let response = post_authenticated(&url, token, body).await?;
+    Ok(serde_json::from_value(response).ok())
 }
 
-async fn post_authenticated(url: &str, token: &str, body: Value) -> Result<Value> {
-    let client = reqwest::Client::new();
-    let resp = client
-        .post(url)
+/// Create a resource without an id (`POST /v1/global/{kind}`) and return the
+/// id the server assigned. Sent once: unlike an upsert, a retry could create
+/// a second resource.
+pub async fn create_kind(base_url: &str, token: &str, kind: &str, body: Value) -> Result<String> {
+    let url = format!("{}/api/v1/global/{}", base_url.trim_end_matches('/'), kind);
+    let resp = client()
+        .post(&url)
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| anyhow::anyhow!("request to {} failed: {}", url, e))?;
+    if !resp.status().is_success() {
+        return Err(error_from(resp, "create failed").await);
+    }
+    let created: Value = resp.json().await?;
+    created
+        .get("id")
+        .and_then(Value::as_str)
+        .map(String::from)
+        .ok_or_else(|| anyhow::anyhow!("server did not return the created id: {}", created))
+}
+
+/// Replace an existing resource (`PUT`), globally or inside `project`. A
+/// `hash_code` in `body` makes the server reject the write with 409 if the
+/// resource changed since it was read.
+pub async fn update_kind(
+    base_url: &str,
+    token: &str,
+    kind: &str,
+    id: &str,
+    project: Option<&str>,
+    body: Value,
+) -> Result<Value> {
+    let base = base_url.trim_end_matches('/');
+    let url = match project {
+        Some(project) => format!("{}/api/v1/projects/{}/{}/{}", base, project, kind, id),
+        None => format!("{}/api/v1/global/{}/{}", base, kind, id),
+    };
+    let resp = client()
+        .put(&url)
         .header("Authorization", format!("Bearer {}", token))
         .json(&body)
         .send()
@@ -120,29 +349,165 @@ async fn post_authenticated(url: &str, token: &str, body: Value) -> Result<Value
     if resp.status().is_success() {
         Ok(resp.json::<Value>().await?)
     } else {
-        let status = resp.status();
-        match resp.json::<ApiErrorBody>().await {
-            Ok(err_body) => bail!("{} ({})", err_body.error.message, status),
-            Err(_) => bail!("request failed with status {}", status),
-        }
+        Err(error_from(resp, "update failed").await)
+    }
+}
+
+/// Set and remove annotations (`op` = `annotate`) or labels (`label`) on a
+/// global resource: `PATCH /v1/ops/{op}/{kind}/{id}`. Returns the server's
+/// `{id, <field>, hash_code}` body.
+pub async fn edit_metadata(
+    base_url: &str,
+    token: &str,
+    op: &str,
+    kind: &str,
+    id: &str,
+    changes: &impl Serialize,
+) -> Result<Value> {
+    let url = format!("{}/api/v1/ops/{}/{}/{}", base_url.trim_end_matches('/'), op, kind, id);
+    let resp = send_idempotent(&url, |client| {
+        client
+            .patch(&url)
+            .header("Authorization", format!("Bearer {}", token))
+            .json(changes)
+    })
+    .await?;
+
+    if resp.status().is_success() {
+        Ok(resp.json::<Value>().await?)
+    } else {
+        Err(error_from(resp, &format!("{} failed", op)).await)
+    }
+}
+
+/// POST to a resource URL. Only used for upserts keyed by id, which are safe to repeat.
+async fn post_authenticated(url: &str, token: &str, body: Value) -> Result<Value> {
+    let resp = send_idempotent(url, |client| {
+        client
+            .post(url)
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&body)
+    })
+    .await?;
+
+    if resp.status().is_success() {
+        Ok(resp.json::<Value>().await?)
+    } else {
+        Err(error_from(resp, "request failed").await)
     }
 }
 
+async fn fetch_list(url: &str, token: &str) -> Result<ListResponse<Value>> {
+    let response = fetch_authenticated(url, token).await?;
+    serde_json::from_value(response.clone())
+        .map_err(|_| anyhow::anyhow!("unexpected list response from server: {}", response))
+}
+
 async fn fetch_authenticated(url: &str, token: &str) -> Result<Value> {
-    let client = reqwest::Client::new();
-    let resp = client
-        .get(url)
-        .header("Authorization", format!("Bearer {}", token))
-        .send()
-        .await?;
+    let resp = get(url, token).await?;
 
     if resp.status().is_success() {
         Ok(resp.json::<Value>().await?)
     } else {
-        let status = resp.status();
-        match resp.json::<ApiErrorBody>().await {
-            Ok(body) => bail!("{} ({})", body.error.message, status),
-            Err(_) => bail!("request failed with status {}", status),
+        Err(error_from(resp, "request failed").await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_uses_shared_error_body() {
+        let body = r#"{"error":{"code":"conflict","message":"groups/g_a was modified","status":409}}"#;
+        assert_eq!(
+            format_error(StatusCode::CONFLICT, body, "request failed"),
+            "groups/g_a was modified (409 Conflict)"
+        );
+    }
+
+    #[test]
+    fn error_accepts_legacy_type_field() {
+        let body = r#"{"error":{"type":"not_found","message":"groups/g_a","status":404}}"#;
+        assert_eq!(
+            format_error(StatusCode::NOT_FOUND, body, "request failed"),
+            "groups/g_a (404 Not Found)"
+        );
+    }
+
+    #[test]
+    fn read_only_message_is_printed_verbatim() {
+        let body = r#"{"error":{"code":"read_only","message":"server is in read-only maintenance mode","status":503}}"#;
+        assert_eq!(
+            format_error(StatusCode::SERVICE_UNAVAILABLE, body, "apply failed"),
+            "server is in read-only maintenance mode"
+        );
+    }
+
+    #[test]
+    fn transient_failures_are_retryable() {
+        for status in [
+            StatusCode::REQUEST_TIMEOUT,
+            StatusCode::TOO_MANY_REQUESTS,
+            StatusCode::BAD_GATEWAY,
+            StatusCode::SERVICE_UNAVAILABLE,
+            StatusCode::GATEWAY_TIMEOUT,
+        ] {
+            assert!(is_retryable(Failure::Status(status)), "{}", status);
         }
+        assert!(is_retryable(Failure::Timeout));
+        assert!(is_retryable(Failure::Connect));
+    }
+
+    #[test]
+    fn final_answers_are_not_retried() {
+        for status in [
+            StatusCode::BAD_REQUEST,
+            StatusCode::UNAUTHORIZED,
+            StatusCode::FORBIDDEN,
+            StatusCode::NOT_FOUND,
+            StatusCode::CONFLICT,
+            StatusCode::UNPROCESSABLE_ENTITY,
+            StatusCode::INTERNAL_SERVER_ERROR,
+        ] {
+            assert!(!is_retryable(Failure::Status(status)), "{}", status);
+        }
+        assert!(!is_retryable(Failure::Other));
+    }
+
+    #[test]
+    fn backoff_doubles_and_is_capped() {
+        assert_eq!(backoff(1), Duration::from_millis(250));
+        assert_eq!(backoff(2), Duration::from_millis(500));
+        assert_eq!(backoff(3), Duration::from_secs(1));
+        assert_eq!(backoff(10), Duration::from_secs(8));
+    }
+
+    #[test]
+    fn default_net_options() {
+        assert_eq!(NetOptions::default().retries, 2);
+        assert_eq!(NetOptions::default().timeout, Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn payload_too_large_suggests_splitting() {
+        let body = r#"{"error":{"code":"payload_too_large","message":"request body exceeds the limit of 1048576 bytes","status":413}}"#;
+        assert_eq!(
+            format_error(StatusCode::PAYLOAD_TOO_LARGE, body, "apply failed"),
+            format!("request body exceeds the limit of 1048576 bytes (413 Payload Too Large); {}", SPLIT_HINT)
+        );
+        assert!(format_error(StatusCode::PAYLOAD_TOO_LARGE, "length limit exceeded", "apply failed").ends_with(SPLIT_HINT));
+    }
+
+    #[test]
+    fn error_falls_back_to_raw_text() {
+        assert_eq!(
+            format_error(StatusCode::BAD_GATEWAY, "upstream down\n", "request failed"),
+            "request failed: upstream down (502 Bad Gateway)"
+        );
+        assert_eq!(
+            format_error(StatusCode::UNAUTHORIZED, "", "login failed"),
+            "login failed with status 401 Unauthorized"
+        );
     }
 }
@@ -0,0 +1,92 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::{Result, bail};
+use serde::Serialize;
+
+use crate::{api, context};
+
+/// Body of `PATCH /v1/ops/{annotate,label}/{kind}/{id}`.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct Changes {
+    pub set: BTreeMap<String, String>,
+    pub remove: Vec<String>,
+}
+
+/// Parse `key=value` (set) and `key-` (remove) arguments, kubectl style.
+/// Values may contain `=`; a key may appear only once.
+pub fn parse_changes(args: &[String]) -> Result<Changes> {
+    let mut changes = Changes::default();
+    let mut seen = BTreeSet::new();
+    for arg in args {
+        let key = if let Some((key, value)) = arg.split_once('=') {
+            if key.is_empty() {
+                bail!("invalid change '{}': missing key before '='", arg);
+            }
+            changes.set.insert(key.to_string(), value.to_string());
+            key
+        } else if let Some(key) = arg.strip_suffix('-').filter(|k| !k.is_empty()) {
+            changes.remove.push(key.to_string());
+            key
+        } else {
+            bail!("invalid change '{}': expected KEY=VALUE to set or KEY- to remove", arg);
+        };
+        if !seen.insert(key) {
+            bail!("'{}' is given more than once", key);
+        }
+    }
+    if changes.set.is_empty() && changes.remove.is_empty() {
+        bail!("nothing to change: give KEY=VALUE or KEY- arguments");
+    }
+    Ok(changes)
+}
+
+/// `cr1t annotate` / `cr1t annotate --labels`: edit one metadata map of a
+/// global resource in place, without a read-modify-write round trip.
+pub async fn run(kind: &str, id: &str, args: &[String], labels: bool) -> Result<()> {
+    let changes = parse_changes(args)?;
+    let ctx = context::require_current()?;
+    let (op, field) = if labels { ("label", "labels") } else { ("annotate", "annotations") };
+    let updated = api::edit_metadata(&ctx.url, &ctx.token, op, kind, id, &changes).await?;
+    let count = updated.get(field).and_then(|v| v.as_object()).map_or(0, |m| m.len());
+    println!("{}/{} {} ({} {})", kind, id, if labels { "labeled" } else { "annotated" }, count, field);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn sets_and_removals_are_split() {
+        let changes = parse_changes(&args(&["owner=ops", "url=https://x?a=b", "stale-", "empty="])).unwrap();
+        assert_eq!(
+            changes.set,
+            BTreeMap::from([
+                ("empty".to_string(), String::new()),
+                ("owner".to_string(), "ops".to_string()),
+                ("url".to_string(), "https://x?a=b".to_string()),
+            ])
+        );
+        assert_eq!(changes.remove, vec!["stale".to_string()]);
+    }
+
+    #[test]
+    fn malformed_and_repeated_keys_are_refused() {
+        for (input, reason) in [
+            (vec!["owner"], "expected KEY=VALUE"),
+            (vec!["-"], "expected KEY=VALUE"),
+            (vec!["=ops"], "missing key"),
+            (vec!["owner=a", "owner=b"], "more than once"),
+            (vec!["owner=a", "owner-"], "more than once"),
+            (vec!["owner-", "owner=a"], "more than once"),
+            (vec![], "nothing to change"),
+        ] {
+            let err = parse_changes(&args(&input)).unwrap_err().to_string();
+            assert!(err.contains(reason), "{:?}: {}", input, err);
+        }
+    }
+}
@@ -1,11 +1,14 @@
-use std::io::Read;
+use std::io::{Read, Write};
 use std::path::Path;
+use std::time::Duration;
 
 use anyhow::{bail, Result};
+use crit_shared::requests::{ApplyResponse, KindInfo, resolve_kind};
+use log::debug;
 use serde::de::Deserialize;
 use serde_json::Value;
 
-use crate::{api, context};
+use crate::{api, context, output::Layout};
 
 /// Pluralize a singular kind name to get the API collection name.
 /// e.g. "group" → "groups", "user" → "users", "project" → "projects"
@@ -13,9 +16,59 @@ fn to_api_kind(kind: &str) -> String {
     format!("{}s", kind)
 }
 
+/// API collection for a manifest's `kind`: the server's kind that has it as
+/// name or alias, else the plural.
+fn api_kind_for(kinds: &[KindInfo], kind: &str) -> String {
+    resolve_kind(kinds, kind).map_or_else(|| to_api_kind(kind), |k| k.name.clone())
+}
+
+/// Expand `${VAR}` and `${VAR:-default}` from `lookup`, and `$$` to a literal `$`,
+/// before the manifest is parsed. Any other `$` is left as is. A variable that
+/// is unset and has no default is an error naming the token.
+fn substitute_env(content: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<String> {
+    let mut out = String::with_capacity(content.len());
+    let mut rest = content;
+
+    while let Some(pos) = rest.find('$') {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+
+        if let Some(after) = rest.strip_prefix("$$") {
+            out.push('$');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("${") {
+            let Some(end) = after.find('}') else {
+                let line = rest.lines().next().unwrap_or(rest);
+                bail!("unterminated variable reference '{}'", line);
+            };
+            let token = &rest[..end + 3];
+            let (name, default) = match after[..end].split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (&after[..end], None),
+            };
+            let valid_name = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid_name {
+                bail!("invalid variable name in '{}'", token);
+            }
+            match lookup(name).or_else(|| default.map(String::from)) {
+                Some(value) => out.push_str(&value),
+                None => bail!("environment variable in '{}' is not set and has no default", token),
+            }
+            rest = &after[end + 1..];
+        } else {
+            out.push('$');
+            rest = &rest[1..];
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
 /// Parse a YAML string (potentially multi-document) into a list of `(kind, id, body)` tuples.
 /// `kind` is stripped from `body` since it's only used for routing, not stored in the DB.
-fn parse_documents(content: &str) -> Result<Vec<(String, String, Value)>> {
+/// A document without `id` gets one assigned by the server when it is created.
+fn parse_documents(content: &str) -> Result<Vec<(String, Option<String>, Value)>> {
     let mut docs = Vec::new();
 
     for document in serde_yaml::Deserializer::from_str(content) {
@@ -33,11 +86,11 @@ fn parse_documents(content: &str) -> Result<Vec<(String, String, Value)>> {
             .ok_or_else(|| anyhow::anyhow!("document is missing required field 'kind'"))?
             .to_string();
 
-        let id = value
-            .get("id")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow::anyhow!("{}: document is missing required field 'id'", kind))?
-            .to_string();
+        let id = match value.get("id") {
+            None => None,
+            Some(Value::String(id)) => Some(id.clone()),
+            Some(_) => bail!("{}: field 'id' must be a string", kind),
+        };
 
         // Strip 'kind' — not a DB field, only used for routing
         if let Some(obj) = value.as_object_mut() {
@@ -50,7 +103,222 @@ fn parse_documents(content: &str) -> Result<Vec<(String, String, Value)>> {
     Ok(docs)
 }
 
-pub async fn run(filename: Option<&Path>) -> Result<()> {
+/// What `apply` did (or will do) with a single document, mirroring kubectl's wording.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ApplyAction {
+    Created,
+    Configured,
+    Unchanged,
+}
+
+impl From<crit_shared::requests::ApplyAction> for ApplyAction {
+    fn from(action: crit_shared::requests::ApplyAction) -> Self {
+        use crit_shared::requests::ApplyAction as Server;
+        match action {
+            Server::Created => ApplyAction::Created,
+            Server::Updated => ApplyAction::Configured,
+            Server::Unchanged => ApplyAction::Unchanged,
+        }
+    }
+}
+
+impl ApplyAction {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ApplyAction::Created => "created",
+            ApplyAction::Configured => "configured",
+            ApplyAction::Unchanged => "unchanged",
+        }
+    }
+}
+
+/// One line of `apply` output, e.g. `group/g_ops configured`.
+/// With `color`, `unchanged` lines are dimmed to gray.
+fn status_line(kind: &str, id: &str, action: ApplyAction, color: bool) -> String {
+    let line = format!("{}/{} {}", kind, id, action.as_str());
+    if color && action == ApplyAction::Unchanged {
+        format!("\x1b[90m{}\x1b[0m", line)
+    } else {
+        line
+    }
+}
+
+/// Closing line of a multi-document apply, on stderr: `2 created, 1 configured, 14 unchanged`.
+fn summary_line(actions: &[ApplyAction]) -> String {
+    [ApplyAction::Created, ApplyAction::Configured, ApplyAction::Unchanged]
+        .iter()
+        .map(|a| format!("{} {}", actions.iter().filter(|b| *b == a).count(), a.as_str()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// `-o` for `apply`: what to print on stdout instead of status lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Output {
+    /// `kind/id` per document, e.g. `users/u_xyz`, for scripting.
+    Name,
+    /// The stored resource as JSON; an array for multi-document input.
+    Json,
+}
+
+/// What `apply` writes on stdout, one document at a time.
+struct Report {
+    output: Option<Output>,
+    color: bool,
+    resources: Vec<Value>,
+}
+
+impl Report {
+    fn new(output: Option<Output>, color: bool) -> Self {
+        Self { output, color, resources: Vec::new() }
+    }
+
+    /// Whether `applied` needs the stored resource.
+    fn wants_resource(&self) -> bool {
+        self.output == Some(Output::Json)
+    }
+
+    fn applied(
+        &mut self,
+        out: &mut impl Write,
+        kind: &str,
+        api_kind: &str,
+        id: &str,
+        action: ApplyAction,
+        resource: Option<Value>,
+    ) -> Result<()> {
+        match self.output {
+            None => writeln!(out, "{}", status_line(kind, id, action, self.color))?,
+            Some(Output::Name) => writeln!(out, "{}/{}", api_kind, id)?,
+            Some(Output::Json) => self.resources.extend(resource),
+        }
+        Ok(())
+    }
+
+    fn finish(mut self, out: &mut impl Write) -> Result<()> {
+        if self.output == Some(Output::Json) {
+            let json = if self.resources.len() == 1 {
+                self.resources.remove(0)
+            } else {
+                Value::Array(self.resources)
+            };
+            writeln!(out, "{}", serde_json::to_string_pretty(&json)?)?;
+        }
+        Ok(())
+    }
+}
+
+/// Progress for multi-document applies, on stderr: `[3/17] applying group/g_ops...`.
+fn progress_line(n: usize, total: usize, kind: &str, id: &str) -> String {
+    format!("[{}/{}] applying {}/{}...", n, total, kind, id)
+}
+
+/// The action to report when the server doesn't say which it took (servers
+/// before `ApplyResponse`). The server hashes its internal representation
+/// (prefixed ids, hashed passwords, injected defaults), so the client can't
+/// tell an unchanged write from a changed one: any write to a stored
+/// resource counts as configured.
+fn fallback_action(existing: Option<&Value>) -> ApplyAction {
+    match existing {
+        None => ApplyAction::Created,
+        Some(_) => ApplyAction::Configured,
+    }
+}
+
+/// Where `apply` reads and writes one resource; a seam so the conflict loop
+/// can be tested without a server.
+trait Target {
+    async fn fetch(&self) -> Result<Option<Value>>;
+    async fn apply(&self, body: Value) -> Result<Option<ApplyResponse>>;
+}
+
+/// One resource on the current context's server.
+struct Remote<'a> {
+    ctx: &'a context::ContextEntry,
+    kind: &'a str,
+    id: &'a str,
+}
+
+impl Target for Remote<'_> {
+    async fn fetch(&self) -> Result<Option<Value>> {
+        api::try_get_kind(&self.ctx.url, &self.ctx.token, self.kind, self.id).await
+    }
+
+    async fn apply(&self, body: Value) -> Result<Option<ApplyResponse>> {
+        api::apply_object(&self.ctx.url, &self.ctx.token, self.kind, self.id, body).await
+    }
+}
+
+/// api.rs formats errors as "{message} ({status})" — detect 409 by suffix.
+fn is_conflict(e: &anyhow::Error) -> bool {
+    e.to_string().contains("(409 Conflict)")
+}
+
+/// Wait before conflict retry `attempt` (1-based): 100ms, 200ms, 300ms, ...
+fn conflict_backoff(attempt: u32) -> Duration {
+    Duration::from_millis(100 * u64::from(attempt))
+}
+
+/// Apply `desired` to `target`. The stored resource is fetched first: its
+/// `hash_code` makes the write fail with 409 if someone else changes it in
+/// between. On a conflict the resource is fetched again and the manifest is
+/// laid over the fresh copy — manifest fields win, fields it doesn't mention
+/// keep their new stored values — up to `retries` more times.
+async fn apply_one(target: &impl Target, desired: &Value, retries: u32) -> Result<ApplyAction> {
+    let mut attempt = 0;
+    loop {
+        // A missing resource is a create, and no hash is injected. Any
+        // other error (auth, network) is surfaced immediately.
+        let existing = target.fetch().await?;
+
+        let body = match &existing {
+            Some(current) if attempt > 0 => merge_onto(current, desired),
+            _ => desired.clone(),
+        };
+        let body = with_hash(body, existing.as_ref());
+
+        match target.apply(body).await {
+            // Trust the server's verdict when it reports one; older servers don't.
+            Ok(applied) => {
+                return Ok(applied.map_or_else(|| fallback_action(existing.as_ref()), |r| r.action.into()));
+            }
+            Err(e) if is_conflict(&e) && attempt < retries => {
+                attempt += 1;
+                debug!("conflict applying, retry {}/{}: {}", attempt, retries, e);
+                tokio::time::sleep(conflict_backoff(attempt)).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// The stored document with every top-level manifest field replacing its own.
+/// Server-computed fields are dropped; the server fills them in again.
+fn merge_onto(current: &Value, desired: &Value) -> Value {
+    let mut merged = current.clone();
+    if let (Some(obj), Some(fields)) = (merged.as_object_mut(), desired.as_object()) {
+        for key in ["hash_code", "state"] {
+            obj.remove(key);
+        }
+        for (k, v) in fields {
+            obj.insert(k.clone(), v.clone());
+        }
+    }
+    merged
+}
+
+/// `body` carrying the stored `hash_code`, if there is a stored resource.
+fn with_hash(mut body: Value, existing: Option<&Value>) -> Value {
+    let hash = existing.and_then(|e| e.get("hash_code")).and_then(|v| v.as_str());
+    if let (Some(hash), Some(obj)) = (hash, body.as_object_mut()) {
+        obj.insert("hash_code".to_string(), Value::String(hash.to_string()));
+    }
+    body
+}
+
+/// `retry_on_conflict`: extra attempts after a 409, re-merging the manifest each time.
+/// `output` replaces the status lines (and the progress and summary on stderr).
+pub async fn run(filename: Option<&Path>, retry_on_conflict: u32, output: Option<Output>) -> Result<()> {
     let ctx = context::require_current()?;
 
     let content = match filename {
@@ -65,36 +333,54 @@ pub async fn run(filename: Option<&Path>) -> Result<()> {
         }
     };
 
+    let content = substitute_env(&content, |name| std::env::var(name).ok())?;
     let documents = parse_documents(&content)?;
 
     if documents.is_empty() {
         bail!("no valid YAML documents found in input");
     }
 
-    for (kind, id, mut body) in documents {
-        let api_kind = to_api_kind(&kind);
-
-        // Fetch the existing resource to obtain its hash_code. If the resource
-        // does not exist yet this is a create, and no hash is injected. Any
-        // other error (auth, network) is surfaced immediately.
-        if let Some(existing) = api::try_get_kind(&ctx.url, &ctx.token, &api_kind, &id).await? {
-            if let Some(hash) = existing.get("hash_code").and_then(|v| v.as_str()) {
-                if let Some(obj) = body.as_object_mut() {
-                    obj.insert("hash_code".to_string(), serde_json::Value::String(hash.to_string()));
-                }
-            }
+    // Older servers have no kind registry; manifests then use the plural rule.
+    let kinds = api::list_kinds(&ctx.url, &ctx.token).await.ok().flatten().unwrap_or_default();
+    let color = matches!(Layout::detect(), Layout::Terminal { color: true, .. });
+    let mut report = Report::new(output, color);
+    let mut stdout = std::io::stdout();
+    let verbose = output.is_none();
+    let total = documents.len();
+    let mut actions = Vec::with_capacity(total);
+    for (n, (kind, id, body)) in documents.into_iter().enumerate() {
+        let api_kind = api_kind_for(&kinds, &kind);
+        if total > 1 && verbose {
+            eprintln!("{}", progress_line(n + 1, total, &kind, id.as_deref().unwrap_or("<new>")));
         }
 
-        api::apply_object(&ctx.url, &ctx.token, &api_kind, &id, body).await
-            .map_err(|e| {
-                // api.rs formats errors as "{message} ({status})" — detect 409 by suffix.
-                if e.to_string().contains("(409 Conflict)") {
-                    anyhow::anyhow!("{}/{} was modified since last read — re-run apply to retry", kind, id)
-                } else {
-                    e
-                }
-            })?;
-        println!("{}/{} applied", kind, id);
+        let (id, action) = match id {
+            Some(id) => {
+                let target = Remote { ctx: &ctx, kind: &api_kind, id: &id };
+                let action = apply_one(&target, &body, retry_on_conflict).await.map_err(|e| {
+                    if !is_conflict(&e) {
+                        e
+                    } else if retry_on_conflict == 0 {
+                        anyhow::anyhow!("{}/{} was modified since last read — re-run apply to retry", kind, id)
+                    } else {
+                        anyhow::anyhow!("{}/{} still conflicting after {} retries: {}", kind, id, retry_on_conflict, e)
+                    }
+                })?;
+                (id, action)
+            }
+            None => (api::create_kind(&ctx.url, &ctx.token, &api_kind, body).await?, ApplyAction::Created),
+        };
+        let resource = if report.wants_resource() {
+            Some(api::get_kind(&ctx.url, &ctx.token, &api_kind, &id, None).await?)
+        } else {
+            None
+        };
+        report.applied(&mut stdout, &kind, &api_kind, &id, action, resource)?;
+        actions.push(action);
+    }
+    report.finish(&mut stdout)?;
+    if total > 1 && verbose {
+        eprintln!("{}", summary_line(&actions));
     }
 
     Ok(())
@@ -115,6 +401,281 @@ mod tests {
         assert_eq!(to_api_kind("ticket"), "tickets");
     }
 
+    #[test]
+    fn server_aliases_win_over_pluralizing() {
+        let kinds = [KindInfo {
+            name: "service_accounts".into(),
+            aliases: vec!["service_account".into(), "sa".into()],
+            ..Default::default()
+        }];
+        assert_eq!(api_kind_for(&kinds, "sa"), "service_accounts");
+        assert_eq!(api_kind_for(&kinds, "service_account"), "service_accounts");
+        assert_eq!(api_kind_for(&kinds, "group"), "groups");
+        assert_eq!(api_kind_for(&[], "group"), "groups");
+    }
+
+    // --- fallback_action ---
+
+    #[test]
+    fn fallback_without_stored_resource_is_created() {
+        assert_eq!(fallback_action(None), ApplyAction::Created);
+    }
+
+    #[test]
+    fn fallback_with_stored_resource_is_configured() {
+        let existing = serde_json::json!({ "id": "g_a", "name": "Alpha", "hash_code": "abc" });
+        assert_eq!(fallback_action(Some(&existing)), ApplyAction::Configured);
+    }
+
+    #[test]
+    fn progress_line_counts_documents() {
+        assert_eq!(progress_line(3, 17, "project", "foo"), "[3/17] applying project/foo...");
+    }
+
+    // --- server ApplyResponse ---
+
+    #[test]
+    fn status_line_from_server_apply_response() {
+        use crit_shared::requests::{ApplyAction as Server, ApplyResponse};
+        let cases = [
+            (Server::Created, "group/g_a created"),
+            (Server::Updated, "group/g_a configured"),
+            (Server::Unchanged, "group/g_a unchanged"),
+        ];
+        for (action, expected) in cases {
+            let resp = ApplyResponse { key: "g_a".into(), kind: "groups".into(), action, hash: "h".into() };
+            assert_eq!(status_line("group", &resp.key, resp.action.into(), false), expected);
+        }
+    }
+
+    #[test]
+    fn output_name_is_exactly_kind_slash_id() {
+        let mut out = Vec::new();
+        let mut report = Report::new(Some(Output::Name), true);
+        report.applied(&mut out, "user", "users", "u_xyz", ApplyAction::Created, None).unwrap();
+        report.finish(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "users/u_xyz\n");
+    }
+
+    #[test]
+    fn output_json_is_one_object_or_an_array() {
+        let render = |resources: &[Value]| {
+            let mut out = Vec::new();
+            let mut report = Report::new(Some(Output::Json), false);
+            assert!(report.wants_resource());
+            for r in resources {
+                report.applied(&mut out, "group", "groups", "g_a", ApplyAction::Created, Some(r.clone())).unwrap();
+            }
+            report.finish(&mut out).unwrap();
+            serde_json::from_slice::<Value>(&out).unwrap()
+        };
+        let a = serde_json::json!({ "id": "g_a" });
+        let b = serde_json::json!({ "id": "g_b" });
+        assert_eq!(render(std::slice::from_ref(&a)), a);
+        assert_eq!(render(&[a.clone(), b.clone()]), serde_json::json!([a, b]));
+    }
+
+    #[test]
+    fn unchanged_is_gray_only_with_color() {
+        assert_eq!(status_line("group", "g_a", ApplyAction::Unchanged, true), "\x1b[90mgroup/g_a unchanged\x1b[0m");
+        assert_eq!(status_line("group", "g_a", ApplyAction::Configured, true), "group/g_a configured");
+    }
+
+    #[test]
+    fn summary_counts_each_action() {
+        use ApplyAction::*;
+        assert_eq!(
+            summary_line(&[Unchanged, Created, Unchanged, Configured, Unchanged]),
+            "1 created, 1 configured, 3 unchanged"
+        );
+    }
+
+    // --- apply_one: retry on conflict ---
+
+    /// A stored group plus `conflicts` concurrent writes that land between
+    /// our read and our write, each answered with a 409.
+    struct Racing {
+        stored: std::cell::RefCell<Value>,
+        conflicts: std::cell::Cell<u32>,
+        sent: std::cell::RefCell<Vec<Value>>,
+    }
+
+    impl Racing {
+        fn new(conflicts: u32) -> Self {
+            Self {
+                stored: serde_json::json!({ "id": "g_a", "name": "Alpha", "hash_code": "h0" }).into(),
+                conflicts: conflicts.into(),
+                sent: Vec::new().into(),
+            }
+        }
+    }
+
+    impl Target for Racing {
+        async fn fetch(&self) -> Result<Option<Value>> {
+            Ok(Some(self.stored.borrow().clone()))
+        }
+
+        async fn apply(&self, body: Value) -> Result<Option<ApplyResponse>> {
+            self.sent.borrow_mut().push(body.clone());
+            if self.conflicts.get() > 0 {
+                self.conflicts.set(self.conflicts.get() - 1);
+                let mut stored = self.stored.borrow_mut();
+                let n = self.sent.borrow().len();
+                stored["labels"] = serde_json::json!({ "writer": format!("other{}", n) });
+                stored["hash_code"] = format!("h{}", n).into();
+                bail!("groups/g_a was modified since last read (409 Conflict)");
+            }
+            assert_eq!(body["hash_code"], self.stored.borrow()["hash_code"]);
+            *self.stored.borrow_mut() = body;
+            Ok(None)
+        }
+    }
+
+    /// A stored resource on a server that reports `verdict` for every write.
+    struct Verdict {
+        stored: Value,
+        verdict: crit_shared::requests::ApplyAction,
+        sent: std::cell::RefCell<Vec<Value>>,
+    }
+
+    impl Target for Verdict {
+        async fn fetch(&self) -> Result<Option<Value>> {
+            Ok(Some(self.stored.clone()))
+        }
+
+        async fn apply(&self, body: Value) -> Result<Option<ApplyResponse>> {
+            self.sent.borrow_mut().push(body);
+            Ok(Some(ApplyResponse { key: "g_a".into(), kind: "groups".into(), action: self.verdict, hash: "h1".into() }))
+        }
+    }
+
+    #[tokio::test]
+    async fn removed_field_is_sent_and_reported_by_the_server() {
+        use crit_shared::requests::ApplyAction as Server;
+        // Every manifest field matches the stored one, but `description` is gone:
+        // the write must still go out, since it replaces the whole document.
+        let target = Verdict {
+            stored: serde_json::json!({ "id": "g_a", "name": "Alpha", "description": "old", "hash_code": "h0" }),
+            verdict: Server::Updated,
+            sent: Vec::new().into(),
+        };
+        let desired = serde_json::json!({ "id": "g_a", "name": "Alpha" });
+        assert_eq!(apply_one(&target, &desired, 0).await.unwrap(), ApplyAction::Configured);
+        let sent = target.sent.borrow();
+        assert_eq!(sent.len(), 1);
+        assert!(sent[0].get("description").is_none(), "{}", sent[0]);
+    }
+
+    #[tokio::test]
+    async fn server_decides_unchanged() {
+        use crit_shared::requests::ApplyAction as Server;
+        let target = Verdict {
+            stored: serde_json::json!({ "id": "g_a", "name": "Alpha", "hash_code": "h0" }),
+            verdict: Server::Unchanged,
+            sent: Vec::new().into(),
+        };
+        let desired = serde_json::json!({ "id": "g_a", "name": "Alpha" });
+        assert_eq!(apply_one(&target, &desired, 0).await.unwrap(), ApplyAction::Unchanged);
+        assert_eq!(target.sent.borrow().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn conflict_is_retried_onto_the_fresh_copy() {
+        let target = Racing::new(1);
+        let desired = serde_json::json!({ "id": "g_a", "name": "Beta" });
+        assert_eq!(apply_one(&target, &desired, 3).await.unwrap(), ApplyAction::Configured);
+
+        let sent = target.sent.borrow();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0]["hash_code"], "h0");
+        // The second attempt carries the new hash and keeps the concurrent change.
+        assert_eq!(sent[1]["hash_code"], "h1");
+        assert_eq!(sent[1]["name"], "Beta");
+        assert_eq!(sent[1]["labels"]["writer"], "other1");
+    }
+
+    #[tokio::test]
+    async fn conflict_without_retries_fails() {
+        let target = Racing::new(1);
+        let desired = serde_json::json!({ "id": "g_a", "name": "Beta" });
+        let err = apply_one(&target, &desired, 0).await.unwrap_err();
+        assert!(is_conflict(&err), "{}", err);
+        assert_eq!(target.sent.borrow().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn exhausted_retries_return_the_last_conflict() {
+        let target = Racing::new(5);
+        let desired = serde_json::json!({ "id": "g_a", "name": "Beta" });
+        let err = apply_one(&target, &desired, 2).await.unwrap_err();
+        assert!(is_conflict(&err), "{}", err);
+        assert_eq!(target.sent.borrow().len(), 3);
+    }
+
+    #[test]
+    fn merge_onto_prefers_manifest_fields() {
+        let current = serde_json::json!({
+            "id": "g_a", "name": "Alpha", "labels": { "x": "1" }, "hash_code": "h", "state": {}
+        });
+        let desired = serde_json::json!({ "id": "g_a", "name": "Beta" });
+        assert_eq!(
+            merge_onto(&current, &desired),
+            serde_json::json!({ "id": "g_a", "name": "Beta", "labels": { "x": "1" } })
+        );
+    }
+
+    // --- substitute_env ---
+
+    fn env(name: &str) -> Option<String> {
+        match name {
+            "PROJECT_OWNER" => Some("u_alice".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn substitute_env_replaces_variables() {
+        let yaml = "kind: group\nid: g_${PROJECT_OWNER}\nowner: ${PROJECT_OWNER}\nnote: '${EMPTY}'\n";
+        assert_eq!(
+            substitute_env(yaml, env).unwrap(),
+            "kind: group\nid: g_u_alice\nowner: u_alice\nnote: ''\n"
+        );
+    }
+
+    #[test]
+    fn substitute_env_falls_back_to_default() {
+        assert_eq!(substitute_env("team: ${TEAM:-platform}", env).unwrap(), "team: platform");
+        assert_eq!(substitute_env("team: ${TEAM:-}", env).unwrap(), "team: ");
+        // A set variable wins over the default, even when empty.
+        assert_eq!(substitute_env("owner: ${PROJECT_OWNER:-u_root}", env).unwrap(), "owner: u_alice");
+        assert_eq!(substitute_env("note: ${EMPTY:-x}", env).unwrap(), "note: ");
+    }
+
+    #[test]
+    fn substitute_env_keeps_escaped_and_bare_dollars() {
+        assert_eq!(substitute_env("price: $$5", env).unwrap(), "price: $5");
+        assert_eq!(substitute_env("literal: $${PROJECT_OWNER}", env).unwrap(), "literal: ${PROJECT_OWNER}");
+        assert_eq!(substitute_env("cost: 5$ or $x", env).unwrap(), "cost: 5$ or $x");
+    }
+
+    #[test]
+    fn substitute_env_errors_name_the_token() {
+        let err = substitute_env("owner: ${MISSING}\n", env).unwrap_err().to_string();
+        assert!(err.contains("'${MISSING}'"), "{}", err);
+        let err = substitute_env("owner: ${MISSING\nid: x", env).unwrap_err().to_string();
+        assert!(err.contains("unterminated"), "{}", err);
+        let err = substitute_env("owner: ${1BAD}", env).unwrap_err().to_string();
+        assert!(err.contains("invalid variable name in '${1BAD}'"), "{}", err);
+    }
+
+    #[test]
+    fn anchors_and_aliases_are_resolved() {
+        let yaml = "kind: group\nid: g_a\nlabels: &common\n  team: platform\nannotations: *common\n";
+        let docs = parse_documents(yaml).unwrap();
+        assert_eq!(docs[0].2["annotations"]["team"], "platform");
+    }
+
     // --- parse_documents: happy paths ---
 
     #[test]
@@ -125,7 +686,7 @@ mod tests {
         assert_eq!(docs.len(), 1);
         let (kind, id, body) = &docs[0];
         assert_eq!(kind, "group");
-        assert_eq!(id, "g_test");
+        assert_eq!(id.as_deref(), Some("g_test"));
         assert_eq!(body["name"].as_str().unwrap(), "Test Group");
         // 'kind' must be stripped from body
         assert!(body.get("kind").is_none(), "kind should be removed from body");
@@ -140,9 +701,9 @@ mod tests {
 
         assert_eq!(docs.len(), 2);
         assert_eq!(docs[0].0, "group");
-        assert_eq!(docs[0].1, "g_a");
+        assert_eq!(docs[0].1.as_deref(), Some("g_a"));
         assert_eq!(docs[1].0, "group");
-        assert_eq!(docs[1].1, "g_b");
+        assert_eq!(docs[1].1.as_deref(), Some("g_b"));
     }
 
     #[test]
@@ -153,7 +714,7 @@ mod tests {
         assert_eq!(docs.len(), 2);
         assert_eq!(docs[0].0, "group");
         assert_eq!(docs[1].0, "user");
-        assert_eq!(docs[1].1, "u_alice");
+        assert_eq!(docs[1].1.as_deref(), Some("u_alice"));
     }
 
     #[test]
@@ -182,7 +743,7 @@ mod tests {
         let yaml = "kind: group\nid: g_x\nname: X\n---\n";
         let docs = parse_documents(yaml).unwrap();
         assert_eq!(docs.len(), 1);
-        assert_eq!(docs[0].1, "g_x");
+        assert_eq!(docs[0].1.as_deref(), Some("g_x"));
     }
 
     // --- parse_documents: error paths ---
@@ -199,14 +760,18 @@ mod tests {
     }
 
     #[test]
-    fn parse_missing_id_returns_error() {
+    fn parse_missing_id_leaves_it_to_the_server() {
         let yaml = "kind: group\nname: No ID\n";
+        let docs = parse_documents(yaml).unwrap();
+        assert_eq!(docs[0].1, None);
+        assert!(docs[0].2.get("id").is_none());
+    }
+
+    #[test]
+    fn parse_non_string_id_returns_error() {
+        let yaml = "kind: group\nid: 42\n";
         let err = parse_documents(yaml).unwrap_err();
-        assert!(
-            err.to_string().contains("id"),
-            "error should mention 'id', got: {}",
-            err
-        );
+        assert!(err.to_string().contains("'id'"), "got: {}", err);
     }
 
     #[test]
@@ -217,8 +782,8 @@ mod tests {
 
     #[test]
     fn parse_error_on_second_document_fails() {
-        // First doc is valid, second is missing 'id'
-        let yaml = "kind: group\nid: g_ok\n---\nkind: group\nname: missing-id\n";
+        // First doc is valid, second has a non-string 'id'
+        let yaml = "kind: group\nid: g_ok\n---\nkind: group\nid: [g_bad]\n";
         let err = parse_documents(yaml).unwrap_err();
         assert!(err.to_string().contains("id"));
     }
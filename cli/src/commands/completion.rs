@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use clap::Command;
+use clap_complete::Shell;
+use clap_complete::engine::CompletionCandidate;
+
+use crate::{api, commands::gitops::DEFAULT_KINDS, context};
+
+/// How long a `<tab>` waits for the server before falling back to the built-in kinds.
+const KINDS_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// `cr1t completion <shell>`: print the completion script to stdout.
+pub fn run(shell: Shell, cmd: &mut Command) -> Result<()> {
+    clap_complete::generate(shell, cmd, "cr1t", &mut std::io::stdout());
+    Ok(())
+}
+
+/// Kinds offered for `cr1t get <tab>`: the server's, plus `all`.
+pub fn get_kind_candidates() -> Vec<CompletionCandidate> {
+    let mut candidates = kind_candidates();
+    candidates.push(CompletionCandidate::new("all").help(Some("every kind".into())));
+    candidates
+}
+
+/// Kinds offered for a kind argument: those of the current context's server,
+/// or the built-in list when there is no context or the server doesn't answer.
+pub fn kind_candidates() -> Vec<CompletionCandidate> {
+    match server_kinds() {
+        Some(kinds) => kinds
+            .into_iter()
+            .map(|k| {
+                let help = k.scoped.then(|| "project-scoped".into());
+                CompletionCandidate::new(k.name).help(help)
+            })
+            .collect(),
+        None => DEFAULT_KINDS.iter().map(|k| CompletionCandidate::new(*k)).collect(),
+    }
+}
+
+fn server_kinds() -> Option<Vec<crit_shared::requests::KindInfo>> {
+    let ctx = context::require_current().ok()?;
+    api::configure(api::NetOptions { retries: 0, timeout: Some(KINDS_TIMEOUT) });
+    // Completers are called synchronously from inside main's runtime, so the
+    // request runs on a thread with a runtime of its own.
+    std::thread::spawn(move || {
+        let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().ok()?;
+        rt.block_on(api::list_kinds(&ctx.url, &ctx.token)).ok().flatten()
+    })
+    .join()
+    .ok()
+    .flatten()
+}
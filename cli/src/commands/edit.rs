@@ -0,0 +1,222 @@
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use serde_json::{Map, Value};
+
+use crate::{api, context};
+
+/// Server-managed fields: shown as comments, never taken from the edited text.
+const READ_ONLY_FIELDS: &[&str] = &["state", "hash_code", "acl"];
+
+/// Prefix of the error lines put on top of the buffer when it is reopened.
+const ERROR_MARKER: &str = "# error: ";
+
+/// What came back from the editor.
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    Unchanged,
+    /// The user emptied the buffer.
+    Cancelled,
+    /// The edited fields, read-only fields not included.
+    Changed(Value),
+}
+
+/// Split a fetched resource into the text the user edits and the read-only
+/// fields that are kept aside.
+fn render(doc: &Value) -> Result<(String, Map<String, Value>)> {
+    let mut editable = doc.as_object().cloned().unwrap_or_default();
+    let mut read_only = Map::new();
+    for field in READ_ONLY_FIELDS {
+        if let Some(value) = editable.remove(*field) {
+            read_only.insert(field.to_string(), value);
+        }
+    }
+
+    let mut text = String::from(
+        "# Edit the resource below; it is applied when you save and quit.\n\
+         # Remove all content to cancel.\n",
+    );
+    if !read_only.is_empty() {
+        text.push_str("#\n# Read-only (managed by the server, edits here are ignored):\n");
+        for line in serde_yaml::to_string(&read_only)?.lines() {
+            text.push_str(&format!("#   {}\n", line));
+        }
+    }
+    text.push('\n');
+    text.push_str(&serde_yaml::to_string(&Value::Object(editable))?);
+    Ok((text, read_only))
+}
+
+/// Parse the edited text. Read-only fields the user uncommented are dropped.
+fn parse(text: &str, id: &str) -> Result<Option<Value>, String> {
+    let body: String = text.lines().filter(|l| !l.starts_with(ERROR_MARKER)).map(|l| format!("{}\n", l)).collect();
+    if body.lines().all(|l| l.trim().is_empty() || l.trim_start().starts_with('#')) {
+        return Ok(None);
+    }
+    let mut value: Value = serde_yaml::from_str(&body).map_err(|e| e.to_string())?;
+    let Some(obj) = value.as_object_mut() else {
+        return Err("the resource must be a YAML mapping".to_string());
+    };
+    for field in READ_ONLY_FIELDS {
+        obj.remove(*field);
+    }
+    match obj.get("id").and_then(|v| v.as_str()) {
+        Some(edited) if edited != id => Err(format!("id cannot be changed (was '{}', now '{}')", id, edited)),
+        _ => Ok(Some(value)),
+    }
+}
+
+/// Run `editor` until the text parses, prepending the parse error as comment
+/// lines each time it does not, so the user's work is never discarded.
+fn edit_loop(original: &str, id: &str, mut editor: impl FnMut(&str) -> Result<String>) -> Result<Outcome> {
+    let unchanged = parse(original, id).map_err(anyhow::Error::msg)?;
+    let mut buffer = original.to_string();
+    loop {
+        let edited = editor(&buffer)?;
+        match parse(&edited, id) {
+            Ok(None) => return Ok(Outcome::Cancelled),
+            Ok(Some(value)) if Some(&value) == unchanged.as_ref() => return Ok(Outcome::Unchanged),
+            Ok(Some(value)) => return Ok(Outcome::Changed(value)),
+            Err(e) => {
+                let previous: String =
+                    edited.lines().filter(|l| !l.starts_with(ERROR_MARKER)).map(|l| format!("{}\n", l)).collect();
+                let errors: String = e.lines().map(|l| format!("{}{}\n", ERROR_MARKER, l)).collect();
+                buffer = format!("{}{}", errors, previous);
+            }
+        }
+    }
+}
+
+/// Open `path` in `$EDITOR` (default `vi`); the variable may carry arguments,
+/// e.g. `code --wait`.
+fn run_editor(path: &Path) -> Result<()> {
+    let editor = std::env::var("EDITOR").ok().filter(|e| !e.trim().is_empty()).unwrap_or_else(|| "vi".to_string());
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    let status = Command::new(program)
+        .args(parts)
+        .arg(path)
+        .status()
+        .with_context(|| format!("could not start editor '{}'", editor))?;
+    if !status.success() {
+        bail!("editor '{}' exited with {}", editor, status);
+    }
+    Ok(())
+}
+
+pub async fn run(kind: &str, id: &str, namespace: Option<&str>) -> Result<()> {
+    let ctx = context::require_current()?;
+    let doc = api::get_kind(&ctx.url, &ctx.token, kind, id, namespace).await?;
+    let (text, read_only) = render(&doc)?;
+
+    let path = std::env::temp_dir().join(format!("cr1t-edit-{}-{}-{}.yaml", kind, id, std::process::id()));
+    let outcome = edit_loop(&text, id, |buffer| {
+        std::fs::write(&path, buffer)?;
+        run_editor(&path)?;
+        Ok(std::fs::read_to_string(&path)?)
+    });
+    let _ = std::fs::remove_file(&path);
+
+    let mut body = match outcome? {
+        Outcome::Unchanged => {
+            println!("Edited resource unchanged.");
+            return Ok(());
+        }
+        Outcome::Cancelled => {
+            println!("Edit cancelled, no changes made.");
+            return Ok(());
+        }
+        Outcome::Changed(body) => body,
+    };
+    // A PUT replaces the document: send the read-only fields back as fetched.
+    // `hash_code` also makes the server refuse the write if someone else
+    // changed the resource while it was open.
+    if let Some(obj) = body.as_object_mut() {
+        obj.extend(read_only);
+    }
+    api::update_kind(&ctx.url, &ctx.token, kind, id, namespace, body).await?;
+    println!("{}/{} edited", kind, id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn group() -> Value {
+        json!({
+            "id": "g_ops",
+            "name": "Ops",
+            "labels": { "team": "sre" },
+            "state": { "created_at": "2025-01-01T00:00:00Z" },
+            "hash_code": "abc123",
+            "acl": { "list": [], "last_mod_date": "2025-01-01T00:00:00Z" },
+        })
+    }
+
+    #[test]
+    fn read_only_fields_are_comments() {
+        let (text, read_only) = render(&group()).unwrap();
+        let mut keys: Vec<_> = read_only.keys().collect();
+        keys.sort();
+        assert_eq!(keys, ["acl", "hash_code", "state"]);
+        assert!(text.contains("#   hash_code: abc123\n"));
+        assert!(!text.lines().any(|l| l.starts_with("hash_code") || l.starts_with("state")));
+        assert!(text.contains("\nname: Ops\n"));
+    }
+
+    #[test]
+    fn saving_without_changes_is_unchanged() {
+        let (text, _) = render(&group()).unwrap();
+        let outcome = edit_loop(&text, "g_ops", |buffer| Ok(buffer.to_string())).unwrap();
+        assert_eq!(outcome, Outcome::Unchanged);
+
+        // Reformatting alone is not a change either.
+        let outcome = edit_loop(&text, "g_ops", |buffer| Ok(buffer.replace("name: Ops", "name:   Ops"))).unwrap();
+        assert_eq!(outcome, Outcome::Unchanged);
+    }
+
+    #[test]
+    fn parse_error_reopens_with_error_and_previous_text() {
+        let (text, _) = render(&group()).unwrap();
+        let mut calls = Vec::new();
+        let outcome = edit_loop(&text, "g_ops", |buffer| {
+            calls.push(buffer.to_string());
+            Ok(match calls.len() {
+                1 => buffer.replace("name: Ops", "name: [Platform"),
+                _ => buffer.replace("name: [Platform", "name: Platform"),
+            })
+        })
+        .unwrap();
+
+        assert_eq!(calls.len(), 2);
+        assert!(calls[1].starts_with(ERROR_MARKER), "{}", calls[1]);
+        assert!(calls[1].contains("name: [Platform"));
+        let Outcome::Changed(body) = outcome else { panic!("expected a change") };
+        assert_eq!(body["name"], "Platform");
+        assert!(body.get("hash_code").is_none());
+    }
+
+    #[test]
+    fn changing_the_id_is_refused_and_emptying_cancels() {
+        let (text, _) = render(&group()).unwrap();
+        let mut calls = 0;
+        let outcome = edit_loop(&text, "g_ops", |buffer| {
+            calls += 1;
+            Ok(if calls == 1 { buffer.replace("id: g_ops", "id: g_other") } else { String::new() })
+        })
+        .unwrap();
+        assert_eq!(calls, 2);
+        assert_eq!(outcome, Outcome::Cancelled);
+    }
+
+    #[test]
+    fn uncommented_read_only_fields_are_ignored() {
+        let (text, _) = render(&group()).unwrap();
+        let outcome = edit_loop(&text, "g_ops", |buffer| Ok(format!("{}hash_code: forged\n", buffer))).unwrap();
+        assert_eq!(outcome, Outcome::Unchanged);
+    }
+}
@@ -1,33 +1,33 @@
 use anyhow::Result;
+use crit_shared::requests::{KindInfo, resolve_kind};
+use serde_json::Value;
 
-use crate::{api, context};
+use crate::{
+    api, context, describe, jsonpath,
+    output::{Format, GetOutput, Layout, Table},
+};
+
+/// String at JSON pointer `path`, or empty.
+fn str_field(item: &Value, path: &str) -> String {
+    item.pointer(path).and_then(|v| v.as_str()).unwrap_or_default().to_string()
+}
 
 pub async fn list_groups() -> Result<()> {
     let ctx = context::require_current()?;
     let response = api::list_groups(&ctx.url, &ctx.token).await?;
 
-    // Extract items from the response
-    let items: Vec<_> = response
-        .get("items")
-        .and_then(|v| v.as_array())
-        .map(|a| a.clone())
-        .unwrap_or_default();
+    let items = response.items;
 
     if items.is_empty() {
         println!("No groups found.");
         return Ok(());
     }
 
-    println!("Groups:\n");
+    let mut table = Table::new(&["ID", "NAME"]);
     for item in items {
-        if let Some(id) = item.get("id").and_then(|v| v.as_str()) {
-            if let Some(name) = item.get("name").and_then(|v| v.as_str()) {
-                println!("  {} ({})", name, id);
-            } else {
-                println!("  {}", id);
-            }
-        }
+        table.push(vec![str_field(&item, "/id"), str_field(&item, "/name")]);
     }
+    table.print();
 
     Ok(())
 }
@@ -51,36 +51,22 @@ pub async fn list_users() -> Result<()> {
     let ctx = context::require_current()?;
     let response = api::list_users(&ctx.url, &ctx.token).await?;
 
-    // Extract items from the response
-    let items: Vec<_> = response
-        .get("items")
-        .and_then(|v| v.as_array())
-        .map(|a| a.clone())
-        .unwrap_or_default();
+    let items = response.items;
 
     if items.is_empty() {
         println!("No users found.");
         return Ok(());
     }
 
-    println!("Users:\n");
+    let mut table = Table::new(&["ID", "NAME", "JOB TITLE"]);
     for item in items {
-        if let Some(id) = item.get("id").and_then(|v| v.as_str()) {
-            if let Some(personal) = item.get("personal").and_then(|v| v.as_object()) {
-                if let Some(name) = personal.get("name").and_then(|v| v.as_str()) {
-                    if !name.is_empty() {
-                        println!("  {} ({})", name, id);
-                    } else {
-                        println!("  {}", id);
-                    }
-                } else {
-                    println!("  {}", id);
-                }
-            } else {
-                println!("  {}", id);
-            }
-        }
+        table.push(vec![
+            str_field(&item, "/id"),
+            str_field(&item, "/personal/name"),
+            str_field(&item, "/personal/job_title"),
+        ]);
     }
+    table.print();
 
     Ok(())
 }
@@ -100,37 +86,461 @@ pub async fn describe_user(id: &str) -> Result<()> {
     Ok(())
 }
 
-/// Generic list: `cr1t get <kind>`
-pub async fn list_resources(kind: &str) -> Result<()> {
+/// Generic list: `cr1t get <kind> [-n <project> | --all-namespaces] [-o ...] [--columns ...]`.
+/// YAML documents by default. With `columns`, the server returns only those
+/// paths (`?fields=`) and tables show one column per path. `jsonpath` is
+/// evaluated over `{"items": [...]}`; `custom-columns` over each item.
+pub async fn list_resources(
+    kind: &str,
+    namespaces: Namespaces,
+    output: Option<GetOutput>,
+    columns: &[String],
+) -> Result<()> {
     let ctx = context::require_current()?;
-    let response = api::list_kind(&ctx.url, &ctx.token, kind).await?;
+    let scoped = namespaces != Namespaces::None;
+    let projects = match namespaces {
+        Namespaces::None => Vec::new(),
+        Namespaces::One(ns) => vec![ns],
+        Namespaces::All => project_ids(&ctx).await?,
+    };
 
-    let items = response
-        .get("items")
-        .and_then(|v| v.as_array())
-        .cloned()
-        .unwrap_or_default();
+    let mut items: Vec<(Option<String>, Value)> = Vec::new();
+    if !scoped {
+        let response = api::list_kind(&ctx.url, &ctx.token, kind, columns).await?;
+        items.extend(response.items.into_iter().map(|i| (None, i)));
+    }
+    for ns in projects {
+        let response = api::try_list_kind(&ctx.url, &ctx.token, kind, Some(&ns), columns)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("not allowed to list {} in project {}", kind, ns))?;
+        items.extend(response.items.into_iter().map(|i| (Some(ns.clone()), i)));
+    }
 
-    if items.is_empty() {
-        println!("No {} found.", kind);
-        return Ok(());
+    let format = match output {
+        Some(GetOutput::JsonPath(path)) => {
+            let list = serde_json::json!({ "items": with_namespace(&items) });
+            println!("{}", path.render(&list, " "));
+            return Ok(());
+        }
+        Some(GetOutput::CustomColumns(spec)) => {
+            print!("{}", custom_columns_table(&with_namespace(&items), &spec).render(Layout::detect()));
+            return Ok(());
+        }
+        Some(GetOutput::Format(format)) => format,
+        None => Format::Yaml,
+    };
+
+    match format {
+        Format::Yaml if items.is_empty() => println!("No {} found.", kind),
+        Format::Yaml => {
+            for item in with_namespace(&items) {
+                let yaml = serde_yaml::to_string(&item)?;
+                print!("---\n{}", yaml);
+            }
+        }
+        Format::Table if items.is_empty() => println!("No {} found.", kind),
+        Format::Table if !columns.is_empty() => {
+            print!("{}", columns_table(&items, columns, scoped).render(Layout::detect()))
+        }
+        Format::Table => print!("{}", items_table(&items, scoped).render(Layout::detect())),
+        Format::Json => println!("{}", serde_json::to_string_pretty(&with_namespace(&items))?),
+        Format::Describe if items.is_empty() => println!("No {} found.", kind),
+        Format::Describe => {
+            let now = chrono::Utc::now();
+            let rendered: Vec<String> = with_namespace(&items).iter().map(|i| describe::render(i, now)).collect();
+            print!("{}", rendered.join("\n"));
+        }
     }
 
-    for item in items {
-        let yaml = serde_yaml::to_string(&item)?;
-        print!("---\n{}", yaml);
+    Ok(())
+}
+
+/// Generic describe: `cr1t get <kind> <id> [-n <project>] [-o yaml|json|table|describe|jsonpath=|custom-columns=]`
+pub async fn get_resource(kind: &str, id: &str, namespace: Option<&str>, output: Option<GetOutput>) -> Result<()> {
+    let ctx = context::require_current()?;
+    let response = api::get_kind(&ctx.url, &ctx.token, kind, id, namespace).await?;
+
+    let format = match output {
+        Some(GetOutput::JsonPath(path)) => {
+            println!("{}", path.render(&response, " "));
+            return Ok(());
+        }
+        Some(GetOutput::CustomColumns(spec)) => {
+            print!("{}", custom_columns_table(&[response], &spec).render(Layout::detect()));
+            return Ok(());
+        }
+        Some(GetOutput::Format(format)) => format,
+        None => Format::Yaml,
+    };
+
+    match format {
+        Format::Json => println!("{}", serde_json::to_string_pretty(&response)?),
+        Format::Table => {
+            let items = [(namespace.map(String::from), response)];
+            print!("{}", items_table(&items, namespace.is_some()).render(Layout::detect()));
+        }
+        Format::Yaml => print!("{}", serde_yaml::to_string(&response)?),
+        Format::Describe => print!("{}", describe::render(&response, chrono::Utc::now())),
     }
 
     Ok(())
 }
 
-/// Generic describe: `cr1t get <kind> <id>`
-pub async fn get_resource(kind: &str, id: &str) -> Result<()> {
+async fn project_ids(ctx: &context::ContextEntry) -> Result<Vec<String>> {
+    Ok(api::list_kind(&ctx.url, &ctx.token, "projects", &[])
+        .await?
+        .items
+        .iter()
+        .map(|p| str_field(p, "/id"))
+        .collect())
+}
+
+/// The server's name for kind `input`, which may be an alias (`group`, `g`).
+/// Kinds the server doesn't describe, and servers without `/v1/ops/kinds`,
+/// leave `input` as it is for the server to judge.
+pub async fn canonical_kind(input: &str) -> String {
+    let Ok(ctx) = context::require_current() else {
+        return input.to_string();
+    };
+    match api::list_kinds(&ctx.url, &ctx.token).await {
+        Ok(Some(kinds)) => resolve_kind(&kinds, input).map_or_else(|| input.to_string(), |k| k.name.clone()),
+        _ => input.to_string(),
+    }
+}
+
+/// Kinds `get all` lists when the server has no kind registry.
+pub const DEFAULT_KINDS: &[&str] = &["users", "groups", "memberships", "projects"];
+
+/// List requests `get all` keeps in flight at once.
+const MAX_CONCURRENT_LISTS: usize = 4;
+
+/// Which projects to list scoped kinds in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Namespaces {
+    /// Scoped kinds are skipped.
+    None,
+    One(String),
+    All,
+}
+
+impl Namespaces {
+    pub fn from_args(namespace: Option<String>, all_namespaces: bool) -> Self {
+        match (namespace, all_namespaces) {
+            (_, true) => Namespaces::All,
+            (Some(ns), false) => Namespaces::One(ns),
+            (None, false) => Namespaces::None,
+        }
+    }
+}
+
+/// What `get all` found for one kind.
+#[derive(Debug, Clone, PartialEq)]
+enum Section {
+    /// `(namespace, item)` pairs; namespace is `None` for global kinds.
+    Items(Vec<(Option<String>, Value)>),
+    /// Not listed, with the reason shown in place of the table.
+    Skipped(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct KindSection {
+    kind: String,
+    scoped: bool,
+    section: Section,
+}
+
+/// Human name of an item: `name`, else `personal.name`.
+fn display_name(item: &Value) -> String {
+    let name = str_field(item, "/name");
+    if name.is_empty() { str_field(item, "/personal/name") } else { name }
+}
+
+/// ID / NAME table, with a NAMESPACE column for scoped listings.
+fn items_table(items: &[(Option<String>, Value)], scoped: bool) -> Table {
+    let mut table = if scoped {
+        Table::new(&["ID", "NAME", "NAMESPACE"])
+    } else {
+        Table::new(&["ID", "NAME"])
+    };
+    for (ns, item) in items {
+        let mut row = vec![str_field(item, "/id"), display_name(item)];
+        if scoped {
+            row.push(ns.clone().unwrap_or_default());
+        }
+        table.push(row);
+    }
+    table
+}
+
+/// One column per requested dotted path; non-string values are shown as JSON.
+fn columns_table(items: &[(Option<String>, Value)], columns: &[String], scoped: bool) -> Table {
+    let mut headers: Vec<String> = columns.iter().map(|c| c.to_uppercase()).collect();
+    if scoped {
+        headers.push("NAMESPACE".to_string());
+    }
+    let mut table = Table::new(&headers.iter().map(String::as_str).collect::<Vec<_>>());
+    for (ns, item) in items {
+        let mut row: Vec<String> = columns
+            .iter()
+            .map(|c| match item.pointer(&format!("/{}", c.replace('.', "/"))) {
+                None | Some(Value::Null) => String::new(),
+                Some(Value::String(s)) => s.clone(),
+                Some(other) => other.to_string(),
+            })
+            .collect();
+        if scoped {
+            row.push(ns.clone().unwrap_or_default());
+        }
+        table.push(row);
+    }
+    table
+}
+
+/// `-o custom-columns`: one column per `HEADER:PATH`, one row per item.
+/// Several matches are joined with `,`; no match shows `<none>`.
+fn custom_columns_table(items: &[Value], spec: &[(String, jsonpath::Path)]) -> Table {
+    let headers: Vec<&str> = spec.iter().map(|(header, _)| header.as_str()).collect();
+    let mut table = Table::new(&headers);
+    for item in items {
+        let row = spec
+            .iter()
+            .map(|(_, path)| match path.render(item, ",") {
+                cell if cell.is_empty() => "<none>".to_string(),
+                cell => cell,
+            })
+            .collect();
+        table.push(row);
+    }
+    table
+}
+
+/// Items with their project added as `namespace` (scoped listings only).
+fn with_namespace(items: &[(Option<String>, Value)]) -> Vec<Value> {
+    items
+        .iter()
+        .map(|(ns, item)| {
+            let mut item = item.clone();
+            if let (Some(ns), Some(obj)) = (ns, item.as_object_mut()) {
+                obj.entry("namespace").or_insert_with(|| Value::String(ns.clone()));
+            }
+            item
+        })
+        .collect()
+}
+
+/// Grouped tables, one per kind, in registry order.
+fn render_sections(sections: &[KindSection], layout: Layout) -> String {
+    let mut out = String::new();
+    for (i, s) in sections.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        match &s.section {
+            Section::Skipped(reason) => out.push_str(&format!("{}: skipped ({})\n", s.kind, reason)),
+            Section::Items(items) if items.is_empty() => out.push_str(&format!("{}: none\n", s.kind)),
+            Section::Items(items) => {
+                out.push_str(&format!("{}:\n", s.kind));
+                out.push_str(&items_table(items, s.scoped).render(layout));
+            }
+        }
+    }
+    out
+}
+
+/// `{ "<kind>": [items...] }`; skipped kinds are left out. Items of scoped kinds
+/// carry their project in `namespace`.
+fn sections_to_json(sections: &[KindSection]) -> Value {
+    let mut out = serde_json::Map::new();
+    for s in sections {
+        if let Section::Items(items) = &s.section {
+            out.insert(s.kind.clone(), Value::Array(with_namespace(items)));
+        }
+    }
+    Value::Object(out)
+}
+
+/// `cr1t get all [-n <project> | --all-namespaces] [-o table|json|yaml]`
+pub async fn get_all(namespaces: Namespaces, format: Format) -> Result<()> {
     let ctx = context::require_current()?;
-    let response = api::get_kind(&ctx.url, &ctx.token, kind, id).await?;
+    let kinds = match api::list_kinds(&ctx.url, &ctx.token).await? {
+        Some(kinds) => kinds,
+        None => DEFAULT_KINDS
+            .iter()
+            .map(|k| KindInfo { name: k.to_string(), ..Default::default() })
+            .collect(),
+    };
+    let projects: Vec<String> = match &namespaces {
+        Namespaces::None => Vec::new(),
+        Namespaces::One(ns) => vec![ns.clone()],
+        Namespaces::All if kinds.iter().any(|k| k.scoped) => project_ids(&ctx).await?,
+        Namespaces::All => Vec::new(),
+    };
 
-    let yaml = serde_yaml::to_string(&response)?;
-    print!("{}", yaml);
+    // One request per global kind, and per (scoped kind, project).
+    let limit = std::sync::Arc::new(tokio::sync::Semaphore::new(MAX_CONCURRENT_LISTS));
+    let mut jobs = tokio::task::JoinSet::new();
+    for (idx, kind) in kinds.iter().enumerate() {
+        let targets: Vec<Option<String>> = if kind.scoped {
+            projects.iter().cloned().map(Some).collect()
+        } else {
+            vec![None]
+        };
+        for ns in targets {
+            let (url, token, name, limit) =
+                (ctx.url.clone(), ctx.token.clone(), kind.name.clone(), limit.clone());
+            jobs.spawn(async move {
+                let _permit = limit.acquire_owned().await;
+                let result = api::try_list_kind(&url, &token, &name, ns.as_deref(), &[]).await;
+                (idx, ns, result)
+            });
+        }
+    }
 
+    let mut sections: Vec<KindSection> = kinds
+        .iter()
+        .map(|k| KindSection {
+            kind: k.name.clone(),
+            scoped: k.scoped,
+            section: if k.scoped && namespaces == Namespaces::None {
+                Section::Skipped("scoped to projects; use -n or --all-namespaces".to_string())
+            } else {
+                Section::Items(Vec::new())
+            },
+        })
+        .collect();
+    let mut results = Vec::new();
+    while let Some(joined) = jobs.join_next().await {
+        results.push(joined?);
+    }
+    // Completion order is arbitrary; sort so output is stable.
+    results.sort_by(|a, b| (a.0, &a.1).cmp(&(b.0, &b.1)));
+    for (idx, ns, result) in results {
+        let section = &mut sections[idx].section;
+        match result? {
+            None => *section = Section::Skipped("forbidden".to_string()),
+            Some(list) => {
+                if let Section::Items(items) = section {
+                    items.extend(list.items.into_iter().map(|item| (ns.clone(), item)));
+                }
+            }
+        }
+    }
+
+    match format {
+        // Listings are too long to describe item by item; keep the tables.
+        Format::Table | Format::Describe => print!("{}", render_sections(&sections, Layout::detect())),
+        Format::Json => println!("{}", serde_json::to_string_pretty(&sections_to_json(&sections))?),
+        Format::Yaml => print!("{}", serde_yaml::to_string(&sections_to_json(&sections))?),
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fixture() -> Vec<KindSection> {
+        vec![
+            KindSection {
+                kind: "users".into(),
+                scoped: false,
+                section: Section::Items(vec![
+                    (None, json!({ "id": "u_alice", "personal": { "name": "Alice" } })),
+                    (None, json!({ "id": "u_bob", "personal": { "name": "Bob" } })),
+                ]),
+            },
+            KindSection { kind: "groups".into(), scoped: false, section: Section::Skipped("forbidden".into()) },
+            KindSection { kind: "memberships".into(), scoped: false, section: Section::Items(vec![]) },
+            KindSection {
+                kind: "tasks".into(),
+                scoped: true,
+                section: Section::Items(vec![
+                    (Some("apollo".into()), json!({ "id": "t_1", "name": "Launch" })),
+                    (Some("gemini".into()), json!({ "id": "t_1", "name": "Dock" })),
+                ]),
+            },
+        ]
+    }
+
+    #[test]
+    fn sections_render_as_grouped_tables() {
+        let out = render_sections(&fixture(), Layout::Terminal { width: 80, color: false });
+        assert_eq!(
+            out,
+            "\
+users:
+ID       NAME
+u_alice  Alice
+u_bob    Bob
+
+groups: skipped (forbidden)
+
+memberships: none
+
+tasks:
+ID   NAME    NAMESPACE
+t_1  Launch  apollo
+t_1  Dock    gemini
+"
+        );
+    }
+
+    #[test]
+    fn json_output_is_keyed_by_kind() {
+        let out = sections_to_json(&fixture());
+        assert_eq!(out["users"].as_array().unwrap().len(), 2);
+        assert!(out.get("groups").is_none());
+        assert_eq!(out["memberships"], json!([]));
+        assert_eq!(out["tasks"][1], json!({ "id": "t_1", "name": "Dock", "namespace": "gemini" }));
+    }
+
+    #[test]
+    fn columns_follow_dotted_paths() {
+        let items = vec![
+            (None, json!({ "id": "u_alice", "personal": { "name": "Alice" }, "labels": { "team": "sre" } })),
+            (None, json!({ "id": "u_bob", "personal": {} })),
+        ];
+        let columns = ["id".to_string(), "personal.name".to_string(), "labels".to_string()];
+        let out = columns_table(&items, &columns, false).render(Layout::Terminal { width: 80, color: false });
+        assert_eq!(
+            out,
+            "\
+ID       PERSONAL.NAME  LABELS
+u_alice  Alice          {\"team\":\"sre\"}
+u_bob
+"
+        );
+    }
+
+    #[test]
+    fn custom_columns_render_one_row_per_item() {
+        let items = vec![
+            json!({ "id": "u_alice", "has_admin_status": true, "groups": ["g_ops", "g_dev"] }),
+            json!({ "id": "u_bob", "has_admin_status": false }),
+        ];
+        let spec = match "custom-columns=NAME:.id,ADMIN:.has_admin_status,GROUPS:.groups[*]".parse() {
+            Ok(GetOutput::CustomColumns(spec)) => spec,
+            other => panic!("unexpected {:?}", other),
+        };
+        let out = custom_columns_table(&items, &spec).render(Layout::Terminal { width: 80, color: false });
+        assert_eq!(
+            out,
+            "\
+NAME     ADMIN  GROUPS
+u_alice  true   g_ops,g_dev
+u_bob    false  <none>
+"
+        );
+        assert!("custom-columns=NAME".parse::<GetOutput>().is_err());
+        assert!("custom-columns=NAME:id".parse::<GetOutput>().is_err());
+        assert!("wide".parse::<GetOutput>().unwrap_err().to_string().contains("jsonpath=EXPR"));
+    }
+
+    #[test]
+    fn namespace_flags() {
+        assert_eq!(Namespaces::from_args(None, false), Namespaces::None);
+        assert_eq!(Namespaces::from_args(Some("apollo".into()), false), Namespaces::One("apollo".into()));
+        assert_eq!(Namespaces::from_args(Some("apollo".into()), true), Namespaces::All);
+    }
+}
@@ -0,0 +1,643 @@
+//! `cr1t lint`: offline manifest checks for CI.
+//!
+//! Every document is checked against the kinds compiled into `crit-shared`
+//! (or JSON schemas from `--schema-dir`): it must parse, name a known `kind`,
+//! carry an `id` that follows the kind's key rules, have valid labels, and use
+//! only known top-level fields. An `(kind, id)` pair may appear only once
+//! across all linted files. No server is contacted.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use crit_shared::data_models::{Group, PipelineAccount, Project, ServiceAccount, User};
+use crit_shared::labels;
+use serde_json::{json, Value};
+
+/// `--format` of `cr1t lint`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum LintFormat {
+    /// Findings grouped per file, with line numbers.
+    Human,
+    /// SARIF 2.1.0, for GitHub code scanning.
+    Sarif,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+/// Rule ids and their SARIF descriptions.
+const RULES: &[(&str, &str)] = &[
+    ("parse", "Document is not valid YAML or not a mapping"),
+    ("missing-kind", "Document has no 'kind' field"),
+    ("unknown-kind", "'kind' is not a known resource kind"),
+    ("missing-id", "Document has no 'id' field"),
+    ("invalid-id", "'id' does not follow the kind's key naming rules"),
+    ("invalid-label", "A label key or value is invalid"),
+    ("unknown-field", "Top-level field is not part of the kind"),
+    ("duplicate-id", "The same kind and id are defined more than once"),
+];
+
+/// One problem found in a manifest. `line` is 1-based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub path: String,
+    pub line: usize,
+    pub severity: Severity,
+    pub rule: &'static str,
+    pub message: String,
+}
+
+/// How the `id` of a kind is formed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeyRule {
+    /// `{prefix}{name}`: `name` is lowercase ASCII alphanumerics plus `specials`,
+    /// 2–63 chars, not starting with a digit or `-`.
+    Name { prefix: &'static str, specials: &'static str },
+    /// `{principal}::{group}`.
+    Membership,
+}
+
+#[derive(Debug, Clone)]
+struct KindSpec {
+    fields: BTreeSet<String>,
+    key: KeyRule,
+}
+
+/// Fields accepted on input but never returned by the server.
+const WRITE_ONLY_FIELDS: &[(&str, &str)] = &[("user", "password")];
+
+/// Kinds known without `--schema-dir`, keyed by the manifest `kind`.
+fn builtin_kinds() -> BTreeMap<String, KindSpec> {
+    let resources = [
+        (User::collection_name(), User::id_prefix(), User::ts_fields()),
+        (Group::collection_name(), Group::id_prefix(), Group::ts_fields()),
+        (ServiceAccount::collection_name(), ServiceAccount::id_prefix(), ServiceAccount::ts_fields()),
+        (PipelineAccount::collection_name(), PipelineAccount::id_prefix(), PipelineAccount::ts_fields()),
+        (Project::collection_name(), Project::id_prefix(), Project::ts_fields()),
+    ];
+
+    let mut kinds = BTreeMap::new();
+    for (collection, prefix, ts_fields) in resources {
+        let kind = collection.strip_suffix('s').unwrap_or(collection).to_string();
+        let mut fields: BTreeSet<String> = ts_fields.iter().map(|f| f.name.to_string()).collect();
+        fields.extend(
+            WRITE_ONLY_FIELDS
+                .iter()
+                .filter(|(k, _)| *k == kind)
+                .map(|(_, f)| f.to_string()),
+        );
+        // Usernames allow only '_'; every other kind also allows '-'.
+        let specials = if kind == "user" { "_" } else { "_-" };
+        kinds.insert(kind, KindSpec { fields, key: KeyRule::Name { prefix, specials } });
+    }
+    kinds.insert(
+        "membership".to_string(),
+        KindSpec { fields: field_set(&["id", "principal", "group"]), key: KeyRule::Membership },
+    );
+    kinds.insert(
+        "permission".to_string(),
+        KindSpec {
+            fields: field_set(&["id", "principals"]),
+            key: KeyRule::Name { prefix: "", specials: "_" },
+        },
+    );
+    for spec in kinds.values_mut() {
+        spec.fields.insert("kind".to_string());
+    }
+    kinds
+}
+
+fn field_set(names: &[&str]) -> BTreeSet<String> {
+    names.iter().map(|n| n.to_string()).collect()
+}
+
+/// Add or replace kinds from exported JSON schemas: `{kind}.json` (or
+/// `{kind}.schema.json`) whose `properties` are the allowed top-level fields.
+/// A schema for a built-in kind keeps that kind's key rule.
+fn load_schema_dir(dir: &Path, kinds: &mut BTreeMap<String, KindSpec>) -> Result<()> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| anyhow::anyhow!("failed to read schema dir {}: {}", dir.display(), e))?;
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        let kind = name.trim_end_matches(".json").trim_end_matches(".schema");
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| anyhow::anyhow!("failed to read {}: {}", path.display(), e))?;
+        let schema: Value = serde_json::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("{}: invalid JSON schema: {}", path.display(), e))?;
+        let Some(properties) = schema.get("properties").and_then(|p| p.as_object()) else {
+            bail!("{}: schema has no 'properties' object", path.display());
+        };
+
+        let mut fields: BTreeSet<String> = properties.keys().cloned().collect();
+        fields.insert("kind".to_string());
+        let key = kinds
+            .get(kind)
+            .map(|spec| spec.key)
+            .unwrap_or(KeyRule::Name { prefix: "", specials: "_-" });
+        kinds.insert(kind.to_string(), KindSpec { fields, key });
+    }
+    Ok(())
+}
+
+/// Check an `id` against its kind's key rule.
+fn check_key(rule: KeyRule, id: &str) -> Result<(), String> {
+    match rule {
+        KeyRule::Name { prefix, specials } => {
+            let Some(name) = id.strip_prefix(prefix) else {
+                return Err(format!("must start with '{}'", prefix));
+            };
+            check_name(name, specials)
+        }
+        KeyRule::Membership => {
+            let Some((principal, group)) = id.split_once("::") else {
+                return Err("must be '{principal}::{group}'".to_string());
+            };
+            check_name(principal, "_-").map_err(|e| format!("principal part {}", e))?;
+            check_name(group, "_-").map_err(|e| format!("group part {}", e))
+        }
+    }
+}
+
+fn check_name(name: &str, specials: &str) -> Result<(), String> {
+    let len = name.chars().count();
+    if !(2..=63).contains(&len) {
+        return Err(format!("must be 2 to 63 characters, got {}", len));
+    }
+    if let Some(c) = name.chars().find(|c| c.is_ascii_uppercase()) {
+        return Err(format!("must be lowercase, found '{}'", c));
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| !c.is_ascii_lowercase() && !c.is_ascii_digit() && !specials.contains(*c))
+    {
+        return Err(format!("has invalid character '{}' (allowed: a-z, 0-9, '{}')", c, specials));
+    }
+    if name.starts_with(|c: char| c.is_ascii_digit() || c == '-') {
+        return Err("must not start with a digit or '-'".to_string());
+    }
+    Ok(())
+}
+
+/// Split a multi-document file on `---` lines into `(first line, text)` pairs.
+fn split_documents(content: &str) -> Vec<(usize, String)> {
+    let mut docs = Vec::new();
+    let (mut start, mut current) = (1, String::new());
+    for (i, line) in content.lines().enumerate() {
+        if line.trim_end() == "---" || line.starts_with("--- ") {
+            docs.push((start, std::mem::take(&mut current)));
+            start = i + 2;
+        } else {
+            current.push_str(line);
+            current.push('\n');
+        }
+    }
+    docs.push((start, current));
+    docs
+}
+
+/// Line of the top-level `key:` in a document, or the document's first line.
+fn field_line(doc: &str, start: usize, key: &str) -> usize {
+    doc.lines()
+        .position(|l| {
+            l.strip_prefix(key)
+                .or_else(|| l.strip_prefix(&format!("\"{}\"", key)))
+                .is_some_and(|rest| rest.trim_start().starts_with(':'))
+        })
+        .map(|i| start + i)
+        .unwrap_or(start)
+}
+
+/// Line of the nested `key:` under top-level `parent:`, falling back to the parent.
+fn nested_line(doc: &str, start: usize, parent: &str, key: &str) -> usize {
+    let parent_line = field_line(doc, start, parent);
+    doc.lines()
+        .enumerate()
+        .skip(parent_line - start + 1)
+        .take_while(|(_, l)| l.is_empty() || l.starts_with([' ', '\t', '#']))
+        .find(|(_, l)| {
+            let l = l.trim_start();
+            l.strip_prefix(key)
+                .or_else(|| l.strip_prefix(&format!("\"{}\"", key)))
+                .is_some_and(|rest| rest.trim_start().starts_with(':'))
+        })
+        .map(|(i, _)| start + i)
+        .unwrap_or(parent_line)
+}
+
+/// Collects findings across files; remembers where each `(kind, id)` was first seen.
+struct Linter {
+    kinds: BTreeMap<String, KindSpec>,
+    strict: bool,
+    seen: HashMap<(String, String), (String, usize)>,
+    findings: Vec<Finding>,
+}
+
+impl Linter {
+    fn new(kinds: BTreeMap<String, KindSpec>, strict: bool) -> Self {
+        Self { kinds, strict, seen: HashMap::new(), findings: Vec::new() }
+    }
+
+    fn report(&mut self, path: &str, line: usize, severity: Severity, rule: &'static str, message: String) {
+        self.findings.push(Finding { path: path.to_string(), line, severity, rule, message });
+    }
+
+    fn lint_source(&mut self, path: &str, content: &str) {
+        for (start, doc) in split_documents(content) {
+            self.lint_document(path, start, &doc);
+        }
+    }
+
+    fn lint_document(&mut self, path: &str, start: usize, doc: &str) {
+        let value: Value = match serde_yaml::from_str(doc) {
+            Ok(v) => v,
+            Err(e) => {
+                // serde_yaml positions are relative to this document; report file lines instead.
+                let message = e.to_string();
+                let message = match message.rfind(" at line ") {
+                    Some(i) => &message[..i],
+                    None => &message,
+                };
+                let line = e.location().map_or(start, |l| start + l.line() - 1);
+                self.report(path, line, Severity::Error, "parse", format!("invalid YAML: {}", message));
+                return;
+            }
+        };
+        // Empty documents (comments only, trailing `---`) are skipped like `apply` does.
+        if value.is_null() {
+            return;
+        }
+        // Findings about the document as a whole point past its leading comments.
+        let first = doc
+            .lines()
+            .position(|l| !l.trim().is_empty() && !l.trim_start().starts_with('#'))
+            .map_or(start, |i| start + i);
+        let Some(obj) = value.as_object() else {
+            self.report(path, first, Severity::Error, "parse", "document must be a mapping".to_string());
+            return;
+        };
+
+        let Some(kind) = obj.get("kind").and_then(|k| k.as_str()) else {
+            self.report(path, first, Severity::Error, "missing-kind", "missing required field 'kind'".to_string());
+            return;
+        };
+        let kind_line = field_line(doc, start, "kind");
+        let Some(spec) = self.kinds.get(kind).cloned() else {
+            let known: Vec<&str> = self.kinds.keys().map(String::as_str).collect();
+            self.report(
+                path,
+                kind_line,
+                Severity::Error,
+                "unknown-kind",
+                format!("unknown kind '{}' (known: {})", kind, known.join(", ")),
+            );
+            return;
+        };
+
+        match obj.get("id").and_then(|v| v.as_str()) {
+            None => self.report(
+                path,
+                first,
+                Severity::Error,
+                "missing-id",
+                format!("{}: missing required field 'id'", kind),
+            ),
+            Some(id) => {
+                let id_line = field_line(doc, start, "id");
+                // `${VAR}` references are resolved by `apply`; their value is unknown here.
+                if !id.contains("${")
+                    && let Err(e) = check_key(spec.key, id)
+                {
+                    self.report(path, id_line, Severity::Error, "invalid-id", format!("{} id '{}' {}", kind, id, e));
+                }
+                let here = (path.to_string(), id_line);
+                match self.seen.get(&(kind.to_string(), id.to_string())) {
+                    Some((first_path, first_line)) => {
+                        let message = format!("{}/{} is already defined at {}:{}", kind, id, first_path, first_line);
+                        self.report(path, id_line, Severity::Error, "duplicate-id", message);
+                    }
+                    None => {
+                        self.seen.insert((kind.to_string(), id.to_string()), here);
+                    }
+                }
+            }
+        }
+
+        if let Some(labels) = obj.get("labels") {
+            self.lint_labels(path, start, doc, labels);
+        }
+
+        let unknown_severity = if self.strict { Severity::Error } else { Severity::Warning };
+        for field in obj.keys().filter(|f| !spec.fields.contains(*f)) {
+            let line = field_line(doc, start, field);
+            self.report(path, line, unknown_severity, "unknown-field", format!("unknown field '{}' for kind '{}'", field, kind));
+        }
+    }
+
+    fn lint_labels(&mut self, path: &str, start: usize, doc: &str, labels: &Value) {
+        let Some(map) = labels.as_object() else {
+            let line = field_line(doc, start, "labels");
+            self.report(path, line, Severity::Error, "invalid-label", "'labels' must be a mapping".to_string());
+            return;
+        };
+        for (key, value) in map {
+            let line = nested_line(doc, start, "labels", key);
+            let result = match value.as_str() {
+                Some(v) => labels::validate_key(key).and_then(|_| labels::validate_value(key, v)).map_err(|e| e.to_string()),
+                None => Err(format!("label '{}' value must be a string", key)),
+            };
+            if let Err(message) = result {
+                self.report(path, line, Severity::Error, "invalid-label", message);
+            }
+        }
+    }
+
+    fn errors(&self) -> usize {
+        self.findings.iter().filter(|f| f.severity == Severity::Error).count()
+    }
+}
+
+/// All `.yaml`/`.yml` files under `paths`, sorted per argument; files given
+/// explicitly are linted whatever their extension.
+fn collect_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let mut found = Vec::new();
+            walk(path, &mut found)?;
+            found.sort();
+            files.extend(found);
+        } else if path.exists() {
+            files.push(path.clone());
+        } else {
+            bail!("{}: no such file or directory", path.display());
+        }
+    }
+    Ok(files)
+}
+
+fn walk(dir: &Path, out: &mut Vec<PathBuf>) -> Result<()> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| anyhow::anyhow!("failed to read {}: {}", dir.display(), e))?;
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            walk(&path, out)?;
+        } else if path.extension().is_some_and(|ext| ext == "yaml" || ext == "yml") {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Findings grouped by file in order, then a one-line summary.
+fn render_human(files: usize, findings: &[Finding]) -> String {
+    let mut out = String::new();
+    let mut current: Option<&str> = None;
+    for f in findings {
+        if current != Some(f.path.as_str()) {
+            out.push_str(&format!("{}\n", f.path));
+            current = Some(f.path.as_str());
+        }
+        out.push_str(&format!("  {}: {}: {} [{}]\n", f.line, f.severity.as_str(), f.message, f.rule));
+    }
+    let errors = findings.iter().filter(|f| f.severity == Severity::Error).count();
+    let warnings = findings.len() - errors;
+    out.push_str(&format!(
+        "{} file{} checked: {} error{}, {} warning{}\n",
+        files,
+        plural(files),
+        errors,
+        plural(errors),
+        warnings,
+        plural(warnings)
+    ));
+    out
+}
+
+fn plural(n: usize) -> &'static str {
+    if n == 1 { "" } else { "s" }
+}
+
+/// A SARIF 2.1.0 log with one run.
+fn render_sarif(findings: &[Finding]) -> Value {
+    let rules: Vec<Value> = RULES
+        .iter()
+        .map(|(id, text)| json!({ "id": id, "shortDescription": { "text": text } }))
+        .collect();
+    let results: Vec<Value> = findings
+        .iter()
+        .map(|f| {
+            json!({
+                "ruleId": f.rule,
+                "level": f.severity.as_str(),
+                "message": { "text": f.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": f.path.replace('\\', "/") },
+                        "region": { "startLine": f.line }
+                    }
+                }]
+            })
+        })
+        .collect();
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": { "driver": { "name": "cr1t lint", "version": env!("CARGO_PKG_VERSION"), "rules": rules } },
+            "results": results
+        }]
+    })
+}
+
+pub fn run(paths: &[PathBuf], schema_dir: Option<&Path>, strict: bool, format: LintFormat) -> Result<()> {
+    let mut kinds = builtin_kinds();
+    if let Some(dir) = schema_dir {
+        load_schema_dir(dir, &mut kinds)?;
+    }
+
+    let files = collect_files(paths)?;
+    if files.is_empty() {
+        bail!("no YAML files found");
+    }
+
+    let mut linter = Linter::new(kinds, strict);
+    for file in &files {
+        let content = std::fs::read_to_string(file)
+            .map_err(|e| anyhow::anyhow!("failed to read {}: {}", file.display(), e))?;
+        linter.lint_source(&file.display().to_string(), &content);
+    }
+
+    match format {
+        LintFormat::Human => print!("{}", render_human(files.len(), &linter.findings)),
+        LintFormat::Sarif => println!("{}", serde_json::to_string_pretty(&render_sarif(&linter.findings))?),
+    }
+
+    let errors = linter.errors();
+    if errors > 0 {
+        bail!("lint failed with {} error{}", errors, plural(errors));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/lint");
+
+    fn lint_fixture(name: &str, strict: bool) -> Vec<Finding> {
+        let mut linter = Linter::new(builtin_kinds(), strict);
+        let path = Path::new(FIXTURES).join(name);
+        for file in collect_files(&[path]).unwrap() {
+            let content = std::fs::read_to_string(&file).unwrap();
+            let name = file.strip_prefix(FIXTURES).unwrap().display().to_string();
+            linter.lint_source(&name, &content);
+        }
+        linter.findings
+    }
+
+    /// `(rule, line)` of every finding, in order.
+    fn rules(findings: &[Finding]) -> Vec<(&'static str, usize)> {
+        findings.iter().map(|f| (f.rule, f.line)).collect()
+    }
+
+    #[test]
+    fn builtin_kinds_cover_the_shared_resources() {
+        let kinds = builtin_kinds();
+        let names: Vec<&str> = kinds.keys().map(String::as_str).collect();
+        assert_eq!(
+            names,
+            ["group", "membership", "permission", "pipeline_account", "project", "service_account", "user"]
+        );
+        assert!(kinds["user"].fields.contains("password"));
+        assert!(kinds["group"].fields.contains("acl"));
+        assert!(!kinds["user"].fields.contains("acl"));
+    }
+
+    #[test]
+    fn good_manifests_have_no_findings() {
+        assert_eq!(lint_fixture("good", true), vec![]);
+    }
+
+    #[test]
+    fn parse_errors_carry_the_line() {
+        let findings = lint_fixture("bad/parse-error.yaml", false);
+        assert_eq!(rules(&findings), [("parse", 8)]);
+        assert_eq!(findings[0].message, "invalid YAML: mapping values are not allowed in this context");
+    }
+
+    #[test]
+    fn missing_and_unknown_kind() {
+        let findings = lint_fixture("bad/kinds.yaml", false);
+        assert_eq!(rules(&findings), [("missing-kind", 2), ("unknown-kind", 6)]);
+        assert!(findings[1].message.starts_with("unknown kind 'widget' (known: group,"), "{}", findings[1].message);
+    }
+
+    #[test]
+    fn key_rules() {
+        let findings = lint_fixture("bad/keys.yaml", false);
+        assert_eq!(
+            rules(&findings),
+            [("missing-id", 1), ("invalid-id", 7), ("invalid-id", 10), ("invalid-id", 13), ("invalid-id", 16)]
+        );
+        assert_eq!(findings[1].message, "group id 'alpha' must start with 'g_'");
+        assert_eq!(findings[2].message, "user id 'u_Bob' must be lowercase, found 'B'");
+        assert!(findings[3].message.contains("invalid character '-'"), "{}", findings[3].message);
+        assert!(findings[4].message.contains("'{principal}::{group}'"), "{}", findings[4].message);
+    }
+
+    #[test]
+    fn invalid_labels_point_at_the_label() {
+        let findings = lint_fixture("bad/labels.yaml", false);
+        assert_eq!(rules(&findings), [("invalid-label", 5), ("invalid-label", 6), ("invalid-label", 11)]);
+        assert!(findings[0].message.contains("label key 'Team'"), "{}", findings[0].message);
+        assert_eq!(findings[1].message, "label 'replicas' value must be a string");
+    }
+
+    #[test]
+    fn unknown_fields_warn_unless_strict() {
+        let lenient = lint_fixture("bad/unknown-field.yaml", false);
+        assert_eq!(rules(&lenient), [("unknown-field", 4)]);
+        assert_eq!(lenient[0].severity, Severity::Warning);
+        assert_eq!(lenient[0].message, "unknown field 'colour' for kind 'group'");
+
+        let strict = lint_fixture("bad/unknown-field.yaml", true);
+        assert_eq!(strict[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn duplicates_are_found_across_files() {
+        let findings = lint_fixture("bad/duplicates", false);
+        assert_eq!(rules(&findings), [("duplicate-id", 6), ("duplicate-id", 2)]);
+        assert_eq!(findings[0].message, "group/g_ops is already defined at bad/duplicates/a.yaml:2");
+        assert_eq!(findings[1].path, "bad/duplicates/b.yaml");
+    }
+
+    #[test]
+    fn schema_dir_adds_and_replaces_kinds() {
+        let mut kinds = builtin_kinds();
+        load_schema_dir(&Path::new(FIXTURES).join("schemas"), &mut kinds).unwrap();
+        assert!(kinds["widget"].fields.contains("size"));
+        // The group schema drops `description` but keeps the g_ key rule.
+        assert!(!kinds["group"].fields.contains("description"));
+        assert_eq!(check_key(kinds["group"].key, "alpha").unwrap_err(), "must start with 'g_'");
+
+        let mut linter = Linter::new(kinds, true);
+        linter.lint_source("w.yaml", "kind: widget\nid: big_one\nsize: 3\n");
+        assert_eq!(linter.findings, vec![]);
+    }
+
+    #[test]
+    fn human_output_groups_by_file() {
+        let findings = lint_fixture("bad/duplicates", false);
+        assert_eq!(
+            render_human(2, &findings),
+            "bad/duplicates/a.yaml\n  \
+             6: error: group/g_ops is already defined at bad/duplicates/a.yaml:2 [duplicate-id]\n\
+             bad/duplicates/b.yaml\n  \
+             2: error: group/g_ops is already defined at bad/duplicates/a.yaml:2 [duplicate-id]\n\
+             2 files checked: 2 errors, 0 warnings\n"
+        );
+    }
+
+    #[test]
+    fn sarif_output_lists_rules_and_results() {
+        let sarif = render_sarif(&lint_fixture("bad/unknown-field.yaml", false));
+        assert_eq!(sarif["version"], "2.1.0");
+        let run = &sarif["runs"][0];
+        assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), RULES.len());
+        let result = &run["results"][0];
+        assert_eq!(result["ruleId"], "unknown-field");
+        assert_eq!(result["level"], "warning");
+        let location = &result["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "bad/unknown-field.yaml");
+        assert_eq!(location["region"]["startLine"], 4);
+    }
+
+    #[test]
+    fn env_references_skip_key_rules() {
+        let mut linter = Linter::new(builtin_kinds(), false);
+        linter.lint_source("e.yaml", "kind: group\nid: g_${TEAM}\nname: Team\n");
+        assert_eq!(linter.findings, vec![]);
+    }
+}
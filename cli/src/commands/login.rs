@@ -5,7 +5,7 @@ use anyhow::Result;
 use crate::api;
 use crate::context::{self, ContextEntry, ContextFile};
 
-pub async fn run(url: Option<String>, user: Option<String>) -> Result<()> {
+pub async fn run(url: Option<String>, user: Option<String>, totp_code: Option<String>) -> Result<()> {
     let url = match url {
         Some(u) => u,
         None => prompt("Server URL")?,
@@ -27,7 +27,14 @@ pub async fn run(url: Option<String>, user: Option<String>) -> Result<()> {
 
     eprintln!("Logging in to {} as {}...", &url, &user);
 
-    let resp = api::login(&url, &user, &password).await?;
+    let resp = match api::login(&url, &user, &password, totp_code.as_deref()).await {
+        // Two-factor users are asked for a code only when the server wants one.
+        Err(e) if totp_code.is_none() && io::stdin().is_terminal() && e.to_string().contains(api::TOTP_REQUIRED) => {
+            let code = prompt("Two-factor code")?;
+            api::login(&url, &user, &password, Some(&code)).await?
+        }
+        resp => resp?,
+    };
 
     let context_name = derive_context_name(&url);
 
@@ -1,3 +1,7 @@
 pub mod login;
 pub mod gitops;
 pub mod apply;
+pub mod lint;
+pub mod edit;
+pub mod completion;
+pub mod annotate;
@@ -0,0 +1,331 @@
+//! User defaults for flags (`~/.config/crit/config.yaml`, or `$CRIT_CONFIG`).
+//!
+//! Each setting is resolved from, lowest to highest precedence: built-in
+//! default, config file, environment variable, command-line flag. The
+//! resolved `Settings` remember where every value came from so
+//! `cr1t config view` can say.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context as _, Result, bail};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::output::Format;
+
+/// Keys accepted in the file and by `cr1t config set`.
+pub const KEYS: &[&str] = &["output", "context", "timeout_seconds", "retries", "no_color"];
+
+const DEFAULT_TIMEOUT_SECONDS: u64 = 30;
+const DEFAULT_RETRIES: u32 = 2;
+
+/// Contents of the config file; unset keys are left out when saving.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<Format>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_seconds: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retries: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub no_color: Option<bool>,
+}
+
+impl ConfigFile {
+    /// Set `key` from its string form, as given to `cr1t config set`.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        match key {
+            "output" => {
+                self.output = Some(
+                    Format::from_str(value, true)
+                        .map_err(|_| anyhow::anyhow!("invalid output '{}': expected one of {}", value, format_names()))?,
+                )
+            }
+            "context" if value.trim().is_empty() => bail!("context must not be empty"),
+            "context" => self.context = Some(value.trim().to_string()),
+            "timeout_seconds" => self.timeout_seconds = Some(parse_number(key, value)?),
+            "retries" => self.retries = Some(parse_number(key, value)?),
+            "no_color" => self.no_color = Some(parse_bool(key, value)?),
+            _ => bail!("unknown config key '{}'; valid keys: {}", key, KEYS.join(", ")),
+        }
+        Ok(())
+    }
+}
+
+/// Values given on the command line; `None` / `false` when absent.
+#[derive(Debug, Default, Clone)]
+pub struct Flags {
+    pub context: Option<String>,
+    pub timeout_seconds: Option<u64>,
+    pub retries: Option<u32>,
+    pub no_color: bool,
+}
+
+/// Where a resolved value came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Default,
+    File,
+    Env(&'static str),
+    Flag(&'static str),
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::File => write!(f, "config file"),
+            Source::Env(var) => write!(f, "env {}", var),
+            Source::Flag(flag) => write!(f, "flag {}", flag),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Setting<T> {
+    pub value: T,
+    pub source: Source,
+}
+
+/// Effective settings after merging every source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    /// Default `-o` for `get`; `None` keeps each command's own default.
+    pub output: Setting<Option<Format>>,
+    /// Context to use instead of the one selected in `context.yaml`.
+    pub context: Setting<Option<String>>,
+    pub timeout_seconds: Setting<u64>,
+    pub retries: Setting<u32>,
+    pub no_color: Setting<bool>,
+}
+
+impl Settings {
+    /// Merge `file`, environment (read through `env`) and `flags`.
+    pub fn resolve(file: &ConfigFile, env: impl Fn(&str) -> Option<String>, flags: &Flags) -> Result<Self> {
+        let env_value = |var: &'static str| env(var).filter(|v| !v.is_empty()).map(|v| (var, v));
+
+        let env_output = match env_value("CRIT_OUTPUT") {
+            Some((var, v)) => {
+                let format = Format::from_str(&v, true)
+                    .map_err(|_| anyhow::anyhow!("invalid {} '{}': expected one of {}", var, v, format_names()))?;
+                Some((Some(format), var))
+            }
+            None => None,
+        };
+        let output = layer(None, file.output.map(Some), env_output, None);
+
+        let env_context = env_value("CRIT_CONTEXT").map(|(var, v)| (Some(v), var));
+        let flag_context = flags.context.clone().map(|c| (Some(c), "--context"));
+        let context = layer(None, file.context.clone().map(Some), env_context, flag_context);
+
+        let env_timeout = match env_value("CRIT_TIMEOUT") {
+            Some((var, v)) => Some((parse_number(var, &v)?, var)),
+            None => None,
+        };
+        let timeout_seconds = layer(
+            DEFAULT_TIMEOUT_SECONDS,
+            file.timeout_seconds,
+            env_timeout,
+            flags.timeout_seconds.map(|t| (t, "--timeout")),
+        );
+
+        let env_retries = match env_value("CRIT_RETRIES") {
+            Some((var, v)) => Some((parse_number(var, &v)?, var)),
+            None => None,
+        };
+        let retries = layer(DEFAULT_RETRIES, file.retries, env_retries, flags.retries.map(|r| (r, "--retries")));
+
+        // Any non-empty NO_COLOR disables color (https://no-color.org).
+        let env_no_color = env_value("NO_COLOR").map(|(var, _)| (true, var));
+        let no_color = layer(false, file.no_color, env_no_color, flags.no_color.then_some((true, "--no-color")));
+
+        Ok(Settings { output, context, timeout_seconds, retries, no_color })
+    }
+
+    /// `key: value  # source` lines for `cr1t config view`, in `KEYS` order.
+    pub fn render(&self) -> String {
+        let rows = [
+            ("output", self.output.value.map(format_name).unwrap_or_else(|| "~".to_string()), &self.output.source),
+            ("context", self.context.value.clone().unwrap_or_else(|| "~".to_string()), &self.context.source),
+            ("timeout_seconds", self.timeout_seconds.value.to_string(), &self.timeout_seconds.source),
+            ("retries", self.retries.value.to_string(), &self.retries.source),
+            ("no_color", self.no_color.value.to_string(), &self.no_color.source),
+        ];
+        let width = rows.iter().map(|(k, v, _)| k.len() + v.len()).max().unwrap_or(0);
+        rows.iter()
+            .map(|(k, v, source)| {
+                let pad = " ".repeat(width - k.len() - v.len());
+                format!("{}: {}{}  # {}\n", k, v, pad, source)
+            })
+            .collect()
+    }
+}
+
+/// The highest-precedence value present: flag, then env, then file, then `default`.
+fn layer<T>(
+    default: T,
+    file: Option<T>,
+    env: Option<(T, &'static str)>,
+    flag: Option<(T, &'static str)>,
+) -> Setting<T> {
+    if let Some((value, name)) = flag {
+        return Setting { value, source: Source::Flag(name) };
+    }
+    if let Some((value, var)) = env {
+        return Setting { value, source: Source::Env(var) };
+    }
+    match file {
+        Some(value) => Setting { value, source: Source::File },
+        None => Setting { value: default, source: Source::Default },
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(name: &str, value: &str) -> Result<T> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| anyhow::anyhow!("invalid {} '{}': expected a non-negative number", name, value))
+}
+
+fn parse_bool(name: &str, value: &str) -> Result<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "yes" | "1" => Ok(true),
+        "false" | "no" | "0" => Ok(false),
+        _ => bail!("invalid {} '{}': expected true or false", name, value),
+    }
+}
+
+fn format_name(format: Format) -> String {
+    format.to_possible_value().map(|v| v.get_name().to_string()).unwrap_or_default()
+}
+
+fn format_names() -> String {
+    Format::value_variants().iter().map(|f| format_name(*f)).collect::<Vec<_>>().join(", ")
+}
+
+/// `$CRIT_CONFIG`, else `~/.config/crit/config.yaml`.
+pub fn path() -> Result<PathBuf> {
+    if let Some(path) = std::env::var_os("CRIT_CONFIG").filter(|p| !p.is_empty()) {
+        return Ok(PathBuf::from(path));
+    }
+    let home = dirs::home_dir().context("could not determine home directory")?;
+    Ok(home.join(".config").join("crit").join("config.yaml"))
+}
+
+/// The file at `path`, or an empty config when it does not exist.
+pub fn load_from(path: &Path) -> Result<ConfigFile> {
+    if !path.exists() {
+        return Ok(ConfigFile::default());
+    }
+    let contents = std::fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    serde_yaml::from_str(&contents).with_context(|| format!("failed to parse {}", path.display()))
+}
+
+pub fn save_to(config: &ConfigFile, path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    let yaml = serde_yaml::to_string(config)?;
+    std::fs::write(path, yaml).with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// `cr1t config view`
+pub fn view(settings: &Settings) -> Result<()> {
+    println!("# {}", path()?.display());
+    print!("{}", settings.render());
+    Ok(())
+}
+
+/// `cr1t config set <key> <value>`
+pub fn set(key: &str, value: &str) -> Result<()> {
+    let path = path()?;
+    let mut config = load_from(&path)?;
+    config.set(key, value)?;
+    save_to(&config, &path)?;
+    println!("Set {} in {}", key, path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let pairs: Vec<(String, String)> = pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        move |var| pairs.iter().find(|(k, _)| k == var).map(|(_, v)| v.clone())
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("crit-config-{}-{}", std::process::id(), name)).join("config.yaml")
+    }
+
+    #[test]
+    fn flags_beat_env_beat_file_beat_defaults() {
+        let file = ConfigFile {
+            output: Some(Format::Json),
+            context: Some("staging".into()),
+            timeout_seconds: Some(5),
+            retries: Some(7),
+            no_color: None,
+        };
+        let flags = Flags { context: Some("prod".into()), ..Default::default() };
+        let s = Settings::resolve(&file, env(&[("CRIT_TIMEOUT", "9"), ("CRIT_CONTEXT", "dev")]), &flags).unwrap();
+
+        assert_eq!(s.context, Setting { value: Some("prod".into()), source: Source::Flag("--context") });
+        assert_eq!(s.timeout_seconds, Setting { value: 9, source: Source::Env("CRIT_TIMEOUT") });
+        assert_eq!(s.retries, Setting { value: 7, source: Source::File });
+        assert_eq!(s.output.value, Some(Format::Json));
+        assert_eq!(s.no_color, Setting { value: false, source: Source::Default });
+
+        let s = Settings::resolve(&ConfigFile::default(), env(&[("NO_COLOR", "1")]), &Flags::default()).unwrap();
+        assert_eq!(s.no_color.source, Source::Env("NO_COLOR"));
+        assert_eq!(s.timeout_seconds, Setting { value: 30, source: Source::Default });
+        assert!(Settings::resolve(&ConfigFile::default(), env(&[("CRIT_RETRIES", "many")]), &Flags::default()).is_err());
+    }
+
+    #[test]
+    fn set_then_view_round_trips_through_the_file() {
+        let path = temp_path("roundtrip");
+        let mut config = load_from(&path).unwrap();
+        config.set("output", "Table").unwrap();
+        config.set("retries", "0").unwrap();
+        config.set("no_color", "yes").unwrap();
+        save_to(&config, &path).unwrap();
+
+        let loaded = load_from(&path).unwrap();
+        assert_eq!(loaded, config);
+        let view = Settings::resolve(&loaded, env(&[]), &Flags::default()).unwrap().render();
+        assert_eq!(
+            view,
+            "\
+output: table        # config file
+context: ~           # default
+timeout_seconds: 30  # default
+retries: 0           # config file
+no_color: true       # config file
+"
+        );
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn invalid_keys_and_values_list_what_is_valid() {
+        let mut config = ConfigFile::default();
+        let err = config.set("colour", "no").unwrap_err().to_string();
+        assert_eq!(err, "unknown config key 'colour'; valid keys: output, context, timeout_seconds, retries, no_color");
+        let err = config.set("output", "xml").unwrap_err().to_string();
+        assert!(err.contains("table, yaml, json, describe"), "{}", err);
+        assert!(config.set("timeout_seconds", "-1").is_err());
+
+        let path = temp_path("unknown");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "colour: blue\n").unwrap();
+        let err = format!("{:#}", load_from(&path).unwrap_err());
+        assert!(err.contains("unknown field `colour`") && err.contains("no_color"), "{}", err);
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
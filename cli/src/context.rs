@@ -1,4 +1,5 @@
 use std::path::PathBuf;
+use std::sync::OnceLock;
 
 use anyhow::{Context as _, Result, bail};
 use serde::{Deserialize, Serialize};
@@ -74,9 +75,23 @@ pub fn save(ctx: &ContextFile) -> Result<()> {
     save_to(ctx, &config_path()?)
 }
 
+static SELECTED: OnceLock<String> = OnceLock::new();
+
+/// Use context `name` instead of the file's `current` for the rest of the
+/// process (the `context` setting). Only the first call has an effect.
+pub fn select(name: String) {
+    let _ = SELECTED.set(name);
+}
+
 #[allow(dead_code)]
 pub fn require_current() -> Result<ContextEntry> {
     let ctx = load()?;
+    if let Some(name) = SELECTED.get() {
+        return match ctx.contexts.iter().find(|c| &c.name == name) {
+            Some(entry) => Ok(entry.clone()),
+            None => bail!("context '{}' not found. Run `cr1t context list` to see the available ones.", name),
+        };
+    }
     match ctx.current_context() {
         Some(entry) => Ok(entry.clone()),
         None => bail!("no active context. Run `cr1t login` first."),
@@ -0,0 +1,258 @@
+//! Human-readable rendering of a single resource (`cr1t get <kind> <id> -o describe`).
+//!
+//! Works on any kind's JSON: well-known top-level fields are pulled into the
+//! Metadata, Labels, Annotations and State sections, everything else lands in
+//! Spec. Within an object, values are aligned after the longest key; nested
+//! objects are indented and arrays become `- ` bullets. RFC3339 timestamps
+//! read as "3 days ago (…)", and values under secret-looking keys are masked.
+
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value};
+
+/// Top-level fields shown under Metadata, in this order.
+const METADATA_FIELDS: &[&str] = &["id", "kind", "project", "namespace", "hash_code", "deletion"];
+/// Top-level fields with a section of their own.
+const SECTION_FIELDS: &[&str] = &["labels", "annotations", "state"];
+/// Key fragments whose values are never shown.
+const SECRET_KEY_PARTS: &[&str] = &["hash", "token", "password", "secret"];
+const MASK: &str = "********";
+const INDENT: &str = "  ";
+
+/// Render `doc` as sections. `now` anchors relative timestamps.
+pub fn render(doc: &Value, now: DateTime<Utc>) -> String {
+    let empty = Map::new();
+    let obj = doc.as_object().unwrap_or(&empty);
+
+    let metadata: Vec<(String, Value)> = METADATA_FIELDS
+        .iter()
+        .filter_map(|k| obj.get(*k).map(|v| (k.to_string(), v.clone())))
+        .collect();
+    let spec: Vec<(String, Value)> = obj
+        .iter()
+        .filter(|(k, _)| !METADATA_FIELDS.contains(&k.as_str()) && !SECTION_FIELDS.contains(&k.as_str()))
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+
+    let mut out = String::new();
+    section(&mut out, "Metadata", &metadata, now);
+    section(&mut out, "Labels", &entries(obj.get("labels")), now);
+    section(&mut out, "Annotations", &entries(obj.get("annotations")), now);
+    section(&mut out, "Spec", &spec, now);
+    section(&mut out, "State", &entries(obj.get("state")), now);
+    out
+}
+
+fn entries(value: Option<&Value>) -> Vec<(String, Value)> {
+    match value {
+        Some(Value::Object(map)) => map.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn section(out: &mut String, title: &str, fields: &[(String, Value)], now: DateTime<Utc>) {
+    out.push_str(&format!("{}:\n", title));
+    if fields.is_empty() {
+        out.push_str(&format!("{}<none>\n", INDENT));
+        return;
+    }
+    for line in object_lines(fields, 1, now) {
+        out.push_str(&line);
+        out.push('\n');
+    }
+}
+
+/// `key: value` lines at `depth`, values aligned after the longest scalar key.
+fn object_lines(fields: &[(String, Value)], depth: usize, now: DateTime<Utc>) -> Vec<String> {
+    let indent = INDENT.repeat(depth);
+    let width = fields
+        .iter()
+        .filter(|(k, v)| is_inline(k, v))
+        .map(|(k, _)| k.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut lines = Vec::new();
+    for (key, value) in fields {
+        if is_inline(key, value) {
+            let pad = " ".repeat(width - key.chars().count());
+            lines.push(format!("{}{}:{}  {}", indent, key, pad, scalar(key, value, now)));
+            continue;
+        }
+        lines.push(format!("{}{}:", indent, key));
+        lines.extend(value_lines(value, depth + 1, now));
+    }
+    lines
+}
+
+/// Lines for a nested object or array at `depth`.
+fn value_lines(value: &Value, depth: usize, now: DateTime<Utc>) -> Vec<String> {
+    match value {
+        Value::Object(_) => object_lines(&entries(Some(value)), depth, now),
+        Value::Array(items) => {
+            let indent = INDENT.repeat(depth);
+            let mut lines = Vec::new();
+            for item in items {
+                match item {
+                    Value::Object(map) if !map.is_empty() => {
+                        // Render one level deeper, then turn the first line's
+                        // extra indent into the bullet.
+                        let mut nested = value_lines(item, depth + 1, now);
+                        nested[0] = format!("{}- {}", indent, &nested[0][indent.len() + INDENT.len()..]);
+                        lines.extend(nested);
+                    }
+                    Value::Array(inner) if !inner.is_empty() => {
+                        lines.push(format!("{}-", indent));
+                        lines.extend(value_lines(item, depth + 1, now));
+                    }
+                    _ => lines.push(format!("{}- {}", indent, scalar("", item, now))),
+                }
+            }
+            lines
+        }
+        _ => vec![format!("{}{}", INDENT.repeat(depth), scalar("", value, now))],
+    }
+}
+
+/// Whether `value` fits on its key's line.
+fn is_inline(key: &str, value: &Value) -> bool {
+    match value {
+        Value::Object(map) => map.is_empty() || is_secret(key),
+        Value::Array(items) => items.is_empty() || is_secret(key),
+        _ => true,
+    }
+}
+
+fn is_secret(key: &str) -> bool {
+    let key = key.to_lowercase();
+    SECRET_KEY_PARTS.iter().any(|part| key.contains(part))
+}
+
+fn scalar(key: &str, value: &Value, now: DateTime<Utc>) -> String {
+    match value {
+        Value::Null => "<none>".to_string(),
+        _ if is_secret(key) => MASK.to_string(),
+        Value::String(s) => match DateTime::parse_from_rfc3339(s) {
+            Ok(at) => format!("{} ({})", humanize(now - at.with_timezone(&Utc)), s),
+            Err(_) => s.clone(),
+        },
+        Value::Object(_) | Value::Array(_) => "<none>".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// "just now", "5 minutes ago", "3 days ago", "in 2 hours".
+fn humanize(delta: chrono::Duration) -> String {
+    let secs = delta.num_seconds();
+    let abs = secs.unsigned_abs();
+    if abs < 60 {
+        return "just now".to_string();
+    }
+    let (n, unit) = match abs {
+        ..3_600 => (abs / 60, "minute"),
+        3_600..86_400 => (abs / 3_600, "hour"),
+        86_400..2_592_000 => (abs / 86_400, "day"),
+        2_592_000..31_536_000 => (abs / 2_592_000, "month"),
+        _ => (abs / 31_536_000, "year"),
+    };
+    let plural = if n == 1 { "" } else { "s" };
+    if secs < 0 { format!("in {} {}{}", n, unit, plural) } else { format!("{} {}{} ago", n, unit, plural) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn now() -> DateTime<Utc> {
+        "2026-02-27T10:00:00Z".parse().unwrap()
+    }
+
+    fn project() -> Value {
+        json!({
+            "id": "api-v2",
+            "labels": { "team": "platform" },
+            "annotations": {},
+            "state": {
+                "created_at": "2026-02-24T10:00:00Z",
+                "created_by": "u_alice",
+                "updated_at": "2026-02-27T09:15:00Z",
+                "updated_by": "u_alice",
+                "generation": 3
+            },
+            "acl": {
+                "list": [
+                    { "permissions": 127, "principals": ["u_alice"] },
+                    { "permissions": 31, "principals": ["g_engineering"], "scope": "tasks" }
+                ],
+                "last_mod_date": "2026-02-26T10:00:00Z"
+            },
+            "deletion": null,
+            "hash_code": "b2c3d4e5f6789012",
+            "name": "API v2",
+            "description": "Next generation API project",
+            "repositories": [
+                { "url": "https://github.com/acme/api-v2", "provider": "github", "default_branch": "main" }
+            ],
+            "enabled_services": ["tasks", "pipelines"]
+        })
+    }
+
+    #[test]
+    fn project_renders_in_sections() {
+        assert_eq!(
+            render(&project(), now()),
+            "\
+Metadata:
+  id:         api-v2
+  hash_code:  ********
+  deletion:   <none>
+Labels:
+  team:  platform
+Annotations:
+  <none>
+Spec:
+  acl:
+    last_mod_date:  1 day ago (2026-02-26T10:00:00Z)
+    list:
+      - permissions:  127
+        principals:
+          - u_alice
+      - permissions:  31
+        principals:
+          - g_engineering
+        scope:        tasks
+  description:  Next generation API project
+  enabled_services:
+    - tasks
+    - pipelines
+  name:         API v2
+  repositories:
+    - default_branch:  main
+      provider:        github
+      url:             https://github.com/acme/api-v2
+State:
+  created_at:  3 days ago (2026-02-24T10:00:00Z)
+  created_by:  u_alice
+  generation:  3
+  updated_at:  45 minutes ago (2026-02-27T09:15:00Z)
+  updated_by:  u_alice
+"
+        );
+    }
+
+    #[test]
+    fn secrets_are_masked_at_any_depth() {
+        let doc = json!({ "id": "u_bob", "auth": { "api_token": "t0k", "password_hash": { "salt": "x" } } });
+        let out = render(&doc, now());
+        assert!(out.contains("api_token:      ********"), "{}", out);
+        assert!(out.contains("password_hash:  ********"), "{}", out);
+        assert!(!out.contains("t0k") && !out.contains("salt"));
+    }
+
+    #[test]
+    fn durations_read_naturally() {
+        assert_eq!(humanize(chrono::Duration::seconds(30)), "just now");
+        assert_eq!(humanize(chrono::Duration::minutes(1)), "1 minute ago");
+        assert_eq!(humanize(chrono::Duration::days(400)), "1 year ago");
+        assert_eq!(humanize(chrono::Duration::hours(-2)), "in 2 hours");
+    }
+}
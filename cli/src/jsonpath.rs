@@ -0,0 +1,151 @@
+//! Minimal JSONPath for `-o jsonpath=` and `-o custom-columns=`.
+//!
+//! Supports `.field`, `[n]` and `[*]` steps from the root, optionally wrapped
+//! in `{...}` as kubectl writes them: `{.items[*].personal.email}`. Steps that
+//! do not match (a missing field, an index past the end) yield nothing rather
+//! than an error, so one incomplete document does not break a listing.
+
+use anyhow::{Result, bail};
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    Field(String),
+    Index(usize),
+    Wildcard,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path {
+    steps: Vec<Step>,
+}
+
+impl Path {
+    pub fn parse(expr: &str) -> Result<Self> {
+        let trimmed = expr.trim();
+        let body = match (trimmed.strip_prefix('{'), trimmed.ends_with('}')) {
+            (Some(rest), true) => &rest[..rest.len() - 1],
+            (None, false) => trimmed,
+            _ => bail!("invalid jsonpath '{}': unbalanced braces", expr),
+        };
+        if body.is_empty() {
+            bail!("invalid jsonpath '{}': empty expression", expr);
+        }
+        if body == "." {
+            return Ok(Path { steps: Vec::new() });
+        }
+
+        let chars: Vec<char> = body.chars().collect();
+        let mut steps = Vec::new();
+        let mut i = 0;
+        while i < chars.len() {
+            match chars[i] {
+                '.' => {
+                    let start = i + 1;
+                    let mut end = start;
+                    while end < chars.len() && (chars[end].is_alphanumeric() || chars[end] == '_' || chars[end] == '-') {
+                        end += 1;
+                    }
+                    if end == start {
+                        bail!("invalid jsonpath '{}': expected a field name at position {}", expr, start);
+                    }
+                    steps.push(Step::Field(chars[start..end].iter().collect()));
+                    i = end;
+                }
+                '[' => {
+                    let Some(close) = chars[i..].iter().position(|c| *c == ']').map(|p| i + p) else {
+                        bail!("invalid jsonpath '{}': missing ']' after position {}", expr, i);
+                    };
+                    let inner: String = chars[i + 1..close].iter().collect();
+                    let step = match inner.trim() {
+                        "*" => Step::Wildcard,
+                        n => match n.parse() {
+                            Ok(index) => Step::Index(index),
+                            Err(_) => bail!("invalid jsonpath '{}': '[{}]' is not [*] or a non-negative index", expr, inner),
+                        },
+                    };
+                    steps.push(step);
+                    i = close + 1;
+                }
+                c => bail!("invalid jsonpath '{}': unexpected '{}' at position {}, expected '.' or '['", expr, c, i),
+            }
+        }
+        Ok(Path { steps })
+    }
+
+    /// Every value the path reaches in `root`, in document order.
+    pub fn eval<'a>(&self, root: &'a Value) -> Vec<&'a Value> {
+        let mut current = vec![root];
+        for step in &self.steps {
+            current = current
+                .into_iter()
+                .flat_map(|value| -> Vec<&'a Value> {
+                    match (step, value) {
+                        (Step::Field(name), Value::Object(map)) => map.get(name).into_iter().collect(),
+                        (Step::Index(n), Value::Array(items)) => items.get(*n).into_iter().collect(),
+                        (Step::Wildcard, Value::Array(items)) => items.iter().collect(),
+                        (Step::Wildcard, Value::Object(map)) => map.values().collect(),
+                        _ => Vec::new(),
+                    }
+                })
+                .collect();
+        }
+        current
+    }
+
+    /// Matches joined by `sep`; strings bare, other values as compact JSON.
+    pub fn render(&self, root: &Value, sep: &str) -> String {
+        self.eval(root).into_iter().map(text).collect::<Vec<_>>().join(sep)
+    }
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn list() -> Value {
+        json!({ "items": [
+            { "id": "u_alice", "personal": { "email": "alice@example.com" }, "groups": ["g_ops", "g_dev"] },
+            { "id": "u_bob", "personal": {} },
+            { "id": "u_carol", "personal": { "email": "carol@example.com" }, "groups": ["g_ops"] },
+        ]})
+    }
+
+    #[test]
+    fn wildcard_collects_a_field_from_every_item() {
+        let path = Path::parse("{.items[*].personal.email}").unwrap();
+        assert_eq!(path.render(&list(), " "), "alice@example.com carol@example.com");
+        assert_eq!(Path::parse(".items[*].id").unwrap().render(&list(), " "), "u_alice u_bob u_carol");
+    }
+
+    #[test]
+    fn indexes_and_non_string_values() {
+        assert_eq!(Path::parse("{.items[0].groups[1]}").unwrap().render(&list(), " "), "g_dev");
+        assert_eq!(Path::parse("{.items[2].groups}").unwrap().render(&list(), " "), r#"["g_ops"]"#);
+        assert_eq!(Path::parse("{.items[9].id}").unwrap().render(&list(), " "), "");
+        assert_eq!(Path::parse("{.}").unwrap().eval(&list()), vec![&list()]);
+    }
+
+    #[test]
+    fn invalid_expressions_are_explained() {
+        for (expr, reason) in [
+            ("{.items[*].id", "unbalanced braces"),
+            ("{}", "empty expression"),
+            (".items..id", "expected a field name at position 7"),
+            (".items[*", "missing ']'"),
+            (".items[-1]", "not [*] or a non-negative index"),
+            ("items", "unexpected 'i' at position 0"),
+        ] {
+            let err = Path::parse(expr).unwrap_err().to_string();
+            assert!(err.contains(reason), "{}: {}", expr, err);
+        }
+    }
+}
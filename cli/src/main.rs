@@ -1,10 +1,15 @@
 mod api;
 mod commands;
+mod config;
 mod context;
+mod jsonpath;
+mod describe;
+mod output;
 
 use std::path::PathBuf;
 
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::engine::ArgValueCandidates;
 
 /// cr1t — CLI for Critical project management
 #[derive(Parser)]
@@ -12,6 +17,23 @@ use clap::{Parser, Subcommand};
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Extra attempts for idempotent requests (GETs, apply upserts) on timeouts,
+    /// connection errors and 408/429/502/503/504 [default: 2, env: CRIT_RETRIES]
+    #[arg(long, global = true)]
+    retries: Option<u32>,
+
+    /// Per-request timeout in seconds, 0 = no timeout [default: 30, env: CRIT_TIMEOUT]
+    #[arg(long, global = true, value_name = "SECS")]
+    timeout: Option<u64>,
+
+    /// Context to use instead of the current one [env: CRIT_CONTEXT]
+    #[arg(long, global = true, value_name = "NAME")]
+    context: Option<String>,
+
+    /// Disable colored output [env: NO_COLOR]
+    #[arg(long, global = true)]
+    no_color: bool,
 }
 
 #[derive(Subcommand)]
@@ -25,6 +47,11 @@ enum Commands {
         /// Username
         #[arg(long, short)]
         user: Option<String>,
+
+        /// TOTP or recovery code, for users with two-factor authentication on
+        /// (prompted for on a terminal when the server asks for one)
+        #[arg(long, value_name = "CODE")]
+        totp_code: Option<String>,
     },
 
     /// Show or switch contexts
@@ -45,13 +72,31 @@ enum Commands {
         action: UsersAction,
     },
 
-    /// Get resources by kind (list all or describe one)
+    /// Get resources by kind (list all or describe one); `get all` lists every kind
     Get {
-        /// Resource kind (e.g. users, groups, projects, memberships, permissions)
+        /// Resource kind (e.g. users, groups, projects, memberships, permissions), or `all`
+        #[arg(add = ArgValueCandidates::new(commands::completion::get_kind_candidates))]
         kind: String,
 
         /// Resource ID (omit to list all)
         id: Option<String>,
+
+        /// Project to look in, for project-scoped kinds
+        #[arg(short = 'n', long = "namespace", value_name = "PROJECT")]
+        namespace: Option<String>,
+
+        /// List project-scoped kinds across every project (adds a NAMESPACE column)
+        #[arg(short = 'A', long = "all-namespaces", conflicts_with_all = ["namespace", "id"])]
+        all_namespaces: bool,
+
+        /// Output format: table, yaml, json, describe, jsonpath=EXPR or
+        /// custom-columns=HEADER:PATH,... (default: yaml for one kind, table for `all`)
+        #[arg(short = 'o', long = "output", value_name = "FORMAT")]
+        output: Option<output::GetOutput>,
+
+        /// Comma-separated field paths to fetch and show as table columns (e.g. id,labels.team)
+        #[arg(long = "columns", value_name = "PATHS", value_delimiter = ',', conflicts_with = "id")]
+        columns: Vec<String>,
     },
 
     /// Apply a resource from a file or stdin (create or update)
@@ -59,6 +104,81 @@ enum Commands {
         /// File to apply. Reads from stdin if not specified.
         #[arg(short = 'f', long = "filename", value_name = "FILE")]
         filename: Option<PathBuf>,
+
+        /// On a 409, re-fetch the resource, re-merge the manifest and retry up to N times
+        #[arg(long = "retry-on-conflict", value_name = "N", default_value_t = 0)]
+        retry_on_conflict: u32,
+
+        /// Print `kind/id` per document (`name`) or the stored resources (`json`) instead of status lines
+        #[arg(short = 'o', long = "output", value_enum)]
+        output: Option<commands::apply::Output>,
+    },
+
+    /// Edit a resource in $EDITOR (default vi) and save it back
+    Edit {
+        /// Resource kind (e.g. users, groups, projects)
+        #[arg(add = ArgValueCandidates::new(commands::completion::kind_candidates))]
+        kind: String,
+
+        /// Resource ID
+        id: String,
+
+        /// Project the resource lives in, for project-scoped kinds
+        #[arg(short = 'n', long = "namespace", value_name = "PROJECT")]
+        namespace: Option<String>,
+    },
+
+    /// Set (KEY=VALUE) or remove (KEY-) annotations on a resource, or labels with --labels
+    Annotate {
+        /// Resource kind (e.g. users, groups, projects)
+        #[arg(add = ArgValueCandidates::new(commands::completion::kind_candidates))]
+        kind: String,
+
+        /// Resource ID
+        id: String,
+
+        /// Changes: KEY=VALUE to set, KEY- to remove
+        #[arg(value_name = "CHANGE", required = true)]
+        changes: Vec<String>,
+
+        /// Edit labels instead of annotations
+        #[arg(long)]
+        labels: bool,
+    },
+
+    /// Check manifests offline: kinds, ids, labels, unknown fields, duplicates
+    Lint {
+        /// Manifest file or directory (searched recursively for .yaml/.yml); repeatable
+        #[arg(short = 'f', long = "filename", value_name = "FILE|DIR", required = true)]
+        filename: Vec<PathBuf>,
+
+        /// Treat unknown top-level fields as errors instead of warnings
+        #[arg(long)]
+        strict: bool,
+
+        /// Directory of exported JSON schemas ({kind}.json) that add or replace kinds
+        #[arg(long, value_name = "DIR")]
+        schema_dir: Option<PathBuf>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value = "human")]
+        format: commands::lint::LintFormat,
+    },
+
+    /// Show or change defaults in ~/.config/crit/config.yaml (or $CRIT_CONFIG)
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// Print a shell completion script to stdout
+    ///
+    /// Completes subcommands and flags offline. For resource kinds fetched from
+    /// the server as you type, load the dynamic script instead:
+    /// `source <(COMPLETE=bash cr1t)` (zsh, fish and powershell likewise).
+    Completion {
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
     },
 }
 
@@ -73,6 +193,18 @@ enum ContextAction {
     },
 }
 
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print the effective settings and where each one comes from
+    View,
+    /// Store a default in the config file
+    Set {
+        /// One of: output, context, timeout_seconds, retries, no_color
+        key: String,
+        value: String,
+    },
+}
+
 #[derive(Subcommand)]
 enum GroupsAction {
     /// List all groups
@@ -97,10 +229,41 @@ enum UsersAction {
 
 #[tokio::main]
 async fn main() {
+    // Answers `COMPLETE=<shell> cr1t ...` requests from the dynamic script and exits.
+    clap_complete::CompleteEnv::with_factory(Cli::command).complete();
     let cli = Cli::parse();
+    env_logger::init_from_env(env_logger::Env::new().default_filter_or("warn"));
+    let flags = config::Flags {
+        context: cli.context,
+        timeout_seconds: cli.timeout,
+        retries: cli.retries,
+        no_color: cli.no_color,
+    };
+    let settings = match config::path()
+        .and_then(|path| config::load_from(&path))
+        .and_then(|file| config::Settings::resolve(&file, |var| std::env::var(var).ok(), &flags))
+    {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("Error: {e:#}");
+            std::process::exit(1);
+        }
+    };
+    let timeout = settings.timeout_seconds.value;
+    api::configure(api::NetOptions {
+        retries: settings.retries.value,
+        timeout: (timeout > 0).then(|| std::time::Duration::from_secs(timeout)),
+    });
+    if let Some(name) = settings.context.value.clone() {
+        context::select(name);
+    }
+    if settings.no_color.value {
+        output::disable_color();
+    }
+    let output = |flag: Option<output::GetOutput>| flag.or(settings.output.value.map(output::GetOutput::Format));
 
     let result = match cli.command {
-        Commands::Login { url, user } => commands::login::run(url, user).await,
+        Commands::Login { url, user, totp_code } => commands::login::run(url, user, totp_code).await,
         Commands::Context { action } => match action {
             None | Some(ContextAction::List) => commands::login::run_context(true),
             Some(ContextAction::Use { name }) => commands::login::use_context(&name),
@@ -113,13 +276,44 @@ async fn main() {
             UsersAction::List => commands::gitops::list_users().await,
             UsersAction::Describe { id } => commands::gitops::describe_user(&id).await,
         },
-        Commands::Get { kind, id } => match id {
-            Some(id) => commands::gitops::get_resource(&kind, &id).await,
-            None => commands::gitops::list_resources(&kind).await,
-        },
-        Commands::Apply { filename } => {
-            commands::apply::run(filename.as_deref()).await
+        Commands::Get { kind, id, namespace, all_namespaces, output: format, columns } => {
+            let format = output(format);
+            let namespaces = commands::gitops::Namespaces::from_args(namespace.clone(), all_namespaces);
+            match (kind.as_str(), id) {
+                ("all", None) => match format {
+                    None => commands::gitops::get_all(namespaces, output::Format::Table).await,
+                    Some(output::GetOutput::Format(format)) => commands::gitops::get_all(namespaces, format).await,
+                    Some(_) => Err(anyhow::anyhow!("jsonpath and custom-columns output need a single kind, not `all`")),
+                },
+                (_, Some(id)) => {
+                    let kind = commands::gitops::canonical_kind(&kind).await;
+                    commands::gitops::get_resource(&kind, &id, namespace.as_deref(), format).await
+                }
+                (_, None) => {
+                    let kind = commands::gitops::canonical_kind(&kind).await;
+                    commands::gitops::list_resources(&kind, namespaces, format, &columns).await
+                }
+            }
+        }
+        Commands::Apply { filename, retry_on_conflict, output } => {
+            commands::apply::run(filename.as_deref(), retry_on_conflict, output).await
+        }
+        Commands::Edit { kind, id, namespace } => {
+            let kind = commands::gitops::canonical_kind(&kind).await;
+            commands::edit::run(&kind, &id, namespace.as_deref()).await
         }
+        Commands::Annotate { kind, id, changes, labels } => {
+            let kind = commands::gitops::canonical_kind(&kind).await;
+            commands::annotate::run(&kind, &id, &changes, labels).await
+        }
+        Commands::Lint { filename, strict, schema_dir, format } => {
+            commands::lint::run(&filename, schema_dir.as_deref(), strict, format)
+        }
+        Commands::Config { action } => match action {
+            ConfigAction::View => config::view(&settings),
+            ConfigAction::Set { key, value } => config::set(&key, &value),
+        },
+        Commands::Completion { shell } => commands::completion::run(shell, &mut Cli::command()),
     };
 
     if let Err(e) = result {
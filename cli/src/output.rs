@@ -0,0 +1,312 @@
+//! Table rendering for list commands.
+//!
+//! On a terminal, columns are sized from the data and fitted to the terminal
+//! width: wide columns are truncated with `…`, and if even that does not fit the
+//! rightmost (least important) columns are dropped. Piped output gets every
+//! value in full, tab-separated and without a header, so it can be fed to `cut`.
+
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::jsonpath;
+
+/// Gap between columns in terminal layout.
+const COLUMN_GAP: usize = 2;
+/// A column is never truncated below this many characters (or its header).
+const MIN_COLUMN_WIDTH: usize = 6;
+/// Width assumed when the terminal size cannot be determined.
+const DEFAULT_WIDTH: usize = 80;
+
+/// `-o` output format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Table,
+    Yaml,
+    Json,
+    /// Sectioned, human-readable view of each resource (see `describe`).
+    Describe,
+}
+
+/// `-o` for `get`: a `Format`, or a template evaluated client-side over the
+/// fetched JSON (`jsonpath=EXPR`, `custom-columns=HEADER:PATH,...`).
+#[derive(Debug, Clone, PartialEq)]
+pub enum GetOutput {
+    Format(Format),
+    JsonPath(jsonpath::Path),
+    CustomColumns(Vec<(String, jsonpath::Path)>),
+}
+
+impl std::str::FromStr for GetOutput {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        if let Some(expr) = s.strip_prefix("jsonpath=") {
+            return Ok(GetOutput::JsonPath(jsonpath::Path::parse(expr)?));
+        }
+        if let Some(spec) = s.strip_prefix("custom-columns=") {
+            let mut columns = Vec::new();
+            for entry in spec.split(',') {
+                match entry.split_once(':') {
+                    Some((header, path)) if !header.trim().is_empty() => {
+                        columns.push((header.trim().to_string(), jsonpath::Path::parse(path)?))
+                    }
+                    _ => anyhow::bail!("invalid custom-columns entry '{}': expected HEADER:PATH", entry),
+                }
+            }
+            return Ok(GetOutput::CustomColumns(columns));
+        }
+        match <Format as clap::ValueEnum>::from_str(s, true) {
+            Ok(format) => Ok(GetOutput::Format(format)),
+            Err(_) => {
+                let names: Vec<String> = <Format as clap::ValueEnum>::value_variants()
+                    .iter()
+                    .filter_map(|f| clap::ValueEnum::to_possible_value(f).map(|v| v.get_name().to_string()))
+                    .collect();
+                anyhow::bail!(
+                    "unknown output format '{}': expected one of {}, jsonpath=EXPR, custom-columns=SPEC",
+                    s,
+                    names.join(", ")
+                )
+            }
+        }
+    }
+}
+
+/// How a table is laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// Aligned columns fitted to `width`; `color` adds ANSI styling to the header.
+    Terminal { width: usize, color: bool },
+    /// Tab-separated full values, one row per line, no header.
+    Plain,
+}
+
+impl Layout {
+    /// Terminal layout when stdout is a TTY, plain otherwise.
+    /// Color is off after `disable_color` (the `no_color` setting).
+    pub fn detect() -> Self {
+        if !std::io::stdout().is_terminal() {
+            return Layout::Plain;
+        }
+        Layout::Terminal {
+            width: terminal_width(),
+            color: !NO_COLOR.load(Ordering::Relaxed),
+        }
+    }
+}
+
+static NO_COLOR: AtomicBool = AtomicBool::new(false);
+
+/// Turn off ANSI styling for the rest of the process.
+pub fn disable_color() {
+    NO_COLOR.store(true, Ordering::Relaxed);
+}
+
+/// Columns are given in order of importance: the first is never dropped.
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(headers: &[&str]) -> Self {
+        Self {
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Append a row; missing cells are rendered empty, extra cells are ignored.
+    pub fn push(&mut self, mut cells: Vec<String>) {
+        cells.resize(self.headers.len(), String::new());
+        self.rows.push(cells);
+    }
+
+    pub fn print(&self) {
+        print!("{}", self.render(Layout::detect()));
+    }
+
+    pub fn render(&self, layout: Layout) -> String {
+        match layout {
+            Layout::Plain => self
+                .rows
+                .iter()
+                .map(|row| format!("{}\n", row.join("\t")))
+                .collect(),
+            Layout::Terminal { width, color } => self.render_aligned(width, color),
+        }
+    }
+
+    fn render_aligned(&self, width: usize, color: bool) -> String {
+        let widths = self.fit_columns(width);
+        let mut out = String::new();
+
+        let header = format_line(&self.headers, &widths);
+        if color {
+            out.push_str(&format!("\x1b[1m{}\x1b[0m\n", header));
+        } else {
+            out.push_str(&format!("{}\n", header));
+        }
+        for row in &self.rows {
+            out.push_str(&format!("{}\n", format_line(row, &widths)));
+        }
+        out
+    }
+
+    /// Width of each visible column; the result is shorter than `headers`
+    /// when trailing columns had to be dropped.
+    fn fit_columns(&self, width: usize) -> Vec<usize> {
+        let natural: Vec<usize> = (0..self.headers.len())
+            .map(|i| {
+                self.rows
+                    .iter()
+                    .map(|r| char_len(&r[i]))
+                    .chain(std::iter::once(char_len(&self.headers[i])))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let minimum: Vec<usize> = natural
+            .iter()
+            .zip(&self.headers)
+            .map(|(&n, h)| n.min(MIN_COLUMN_WIDTH.max(char_len(h))))
+            .collect();
+
+        let total = |w: &[usize]| w.iter().sum::<usize>() + COLUMN_GAP * w.len().saturating_sub(1);
+
+        let mut visible = natural.len();
+        while visible > 1 && total(&minimum[..visible]) > width {
+            visible -= 1;
+        }
+
+        // Shrink the widest column that still has room until the row fits.
+        let mut widths = natural[..visible].to_vec();
+        while total(&widths) > width {
+            let Some(i) = (0..visible)
+                .filter(|&i| widths[i] > minimum[i])
+                .max_by_key(|&i| widths[i])
+            else {
+                break;
+            };
+            widths[i] -= 1;
+        }
+        widths
+    }
+}
+
+/// Pad each cell to its column width, truncating as needed.
+fn format_line(cells: &[String], widths: &[usize]) -> String {
+    widths
+        .iter()
+        .enumerate()
+        .map(|(i, &w)| format!("{:<w$}", truncate(&cells[i], w), w = w))
+        .collect::<Vec<_>>()
+        .join(&" ".repeat(COLUMN_GAP))
+        .trim_end()
+        .to_string()
+}
+
+/// Cut `value` to at most `width` characters, ending in `…` when shortened.
+pub fn truncate(value: &str, width: usize) -> String {
+    if char_len(value) <= width {
+        return value.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+    let mut cut: String = value.chars().take(width - 1).collect();
+    cut.push('…');
+    cut
+}
+
+fn char_len(s: &str) -> usize {
+    s.chars().count()
+}
+
+#[cfg(unix)]
+fn terminal_width() -> usize {
+    // SAFETY: TIOCGWINSZ only writes into the provided winsize struct.
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    let ok = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } == 0;
+    if ok && size.ws_col > 0 {
+        return size.ws_col as usize;
+    }
+    columns_env()
+}
+
+#[cfg(not(unix))]
+fn terminal_width() -> usize {
+    columns_env()
+}
+
+fn columns_env() -> usize {
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|c| c.parse().ok())
+        .filter(|&c| c > 0)
+        .unwrap_or(DEFAULT_WIDTH)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> Table {
+        let mut t = Table::new(&["ID", "NAME", "DESCRIPTION"]);
+        t.push(vec!["g_ops".into(), "Operations".into(), "On-call rotation and incident response".into()]);
+        t.push(vec!["g_design".into(), "Design".into()]);
+        t
+    }
+
+    #[test]
+    fn truncate_adds_ellipsis_only_when_needed() {
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("exactly10!", 10), "exactly10!");
+        assert_eq!(truncate("a-very-long-group-name", 8), "a-very-…");
+        assert_eq!(truncate("héllo wörld", 5), "héll…");
+    }
+
+    #[test]
+    fn wide_terminal_shows_full_values() {
+        let out = table().render(Layout::Terminal { width: 120, color: false });
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], "ID        NAME        DESCRIPTION");
+        assert_eq!(lines[1], "g_ops     Operations  On-call rotation and incident response");
+        assert_eq!(lines[2], "g_design  Design");
+    }
+
+    #[test]
+    fn long_value_is_truncated_to_fixed_width() {
+        let out = table().render(Layout::Terminal { width: 40, color: false });
+        for line in out.lines() {
+            assert!(char_len(line) <= 40, "line too wide: {:?}", line);
+        }
+        assert!(out.contains("On-call rotation …"));
+        assert!(out.contains("Operations"));
+    }
+
+    #[test]
+    fn narrow_terminal_drops_least_important_columns() {
+        let out = table().render(Layout::Terminal { width: 20, color: false });
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], "ID        NAME");
+        assert!(!out.contains("DESCRIPTION"));
+        assert!(lines.iter().all(|l| char_len(l) <= 20));
+    }
+
+    #[test]
+    fn color_wraps_header_only() {
+        let out = table().render(Layout::Terminal { width: 120, color: true });
+        assert!(out.starts_with("\x1b[1mID"));
+        assert_eq!(out.matches("\x1b[").count(), 2);
+    }
+
+    #[test]
+    fn plain_layout_is_tab_separated_without_header() {
+        assert_eq!(
+            table().render(Layout::Plain),
+            "g_ops\tOperations\tOn-call rotation and incident response\ng_design\tDesign\t\n"
+        );
+    }
+}
@@ -187,6 +187,29 @@ fn test_context_list_empty() {
         .stderr(predicate::str::contains("No contexts configured"));
 }
 
+#[test]
+fn test_completion_scripts_for_each_shell() {
+    let home = TempDir::new().unwrap();
+
+    for shell in ["bash", "zsh", "fish", "powershell"] {
+        let output = cr1t_cmd(&home).args(["completion", shell]).output().unwrap();
+        assert!(output.status.success(), "completion {} failed", shell);
+        let script = String::from_utf8(output.stdout).unwrap();
+        assert!(script.contains("cr1t"), "completion {} does not mention cr1t", shell);
+    }
+}
+
+#[test]
+fn test_completion_rejects_unknown_shell() {
+    let home = TempDir::new().unwrap();
+
+    cr1t_cmd(&home)
+        .args(["completion", "tcsh"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value"));
+}
+
 // --- Groups and Users commands (require backend) ---
 
 #[test]
@@ -443,7 +466,7 @@ fn test_apply_creates_group_from_file() {
         .assert()
         .success()
         .stdout(predicate::str::contains(format!(
-            "group/{} applied",
+            "group/{} created",
             group_id
         )));
 
@@ -513,7 +536,7 @@ fn test_apply_updates_group_from_file() {
         .assert()
         .success()
         .stdout(predicate::str::contains(format!(
-            "group/{} applied",
+            "group/{} configured",
             group_id
         )));
 
@@ -530,6 +553,39 @@ fn test_apply_updates_group_from_file() {
     delete_group(&token, &group_id);
 }
 
+#[test]
+#[ignore]
+fn test_apply_same_manifest_twice_is_unchanged() {
+    let home = TempDir::new().unwrap();
+    let user = unique_user();
+    let pass = "applypass5";
+    let group_id = format!("g_applysame_{}", &user[8..]);
+
+    register_user(&user, pass);
+    let token = login_user(&user, pass);
+    write_context(&home, &token);
+
+    let yaml_path = home.path().join("group.yaml");
+    std::fs::write(
+        &yaml_path,
+        format!("kind: group\nid: {}\nname: Same\n", group_id),
+    )
+    .unwrap();
+
+    cr1t_cmd(&home)
+        .args(["apply", "-f", yaml_path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("group/{} created", group_id)));
+    cr1t_cmd(&home)
+        .args(["apply", "-f", yaml_path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("group/{} unchanged", group_id)));
+
+    delete_group(&token, &group_id);
+}
+
 #[test]
 #[ignore]
 fn test_apply_creates_group_from_stdin() {
@@ -551,7 +607,7 @@ fn test_apply_creates_group_from_stdin() {
         .assert()
         .success()
         .stdout(predicate::str::contains(format!(
-            "group/{} applied",
+            "group/{} created",
             group_id
         )));
 
@@ -590,8 +646,8 @@ fn test_apply_multi_document_file() {
         .args(["apply", "-f", yaml_path.to_str().unwrap()])
         .assert()
         .success()
-        .stdout(predicate::str::contains(format!("group/{} applied", id_a)))
-        .stdout(predicate::str::contains(format!("group/{} applied", id_b)));
+        .stdout(predicate::str::contains(format!("group/{} created", id_a)))
+        .stdout(predicate::str::contains(format!("group/{} created", id_b)));
 
     // Verify both groups exist
     let client = reqwest::blocking::Client::new();
@@ -636,17 +692,84 @@ fn test_apply_missing_kind_fails() {
         .stderr(predicate::str::contains("kind"));
 }
 
+/// A one-shot stand-in for the backend on a free local port: answers
+/// `GET /api/v1/ops/kinds` with 404 (an old server) and the first
+/// `POST /api/v1/global/groups` with 201 and `created`, then stops. Returns the
+/// base URL and a receiver for the POST's request line and JSON body.
+fn fake_create_server(created: serde_json::Value) -> (String, std::sync::mpsc::Receiver<(String, serde_json::Value)>) {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut content_length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':')
+                    && name.eq_ignore_ascii_case("content-length")
+                {
+                    content_length = value.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+
+            let request_line = request_line.trim().to_string();
+            let (status, reply) = if request_line.starts_with("POST /api/v1/global/groups ") {
+                ("201 Created", created.to_string())
+            } else {
+                ("404 Not Found", "{}".to_string())
+            };
+            write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                reply.len(),
+                reply
+            )
+            .unwrap();
+            if status.starts_with("201") {
+                tx.send((request_line, serde_json::from_slice(&body).unwrap())).unwrap();
+                break;
+            }
+        }
+    });
+    (url, rx)
+}
+
 #[test]
-fn test_apply_missing_id_fails() {
+fn test_apply_missing_id_creates_on_server() {
     let home = TempDir::new().unwrap();
-    write_dummy_context(&home);
+    let (url, requests) = fake_create_server(serde_json::json!({ "id": "g_missing_id" }));
+    let ctx_dir = home.path().join(".cr1tical");
+    std::fs::create_dir_all(&ctx_dir).unwrap();
+    std::fs::write(
+        ctx_dir.join("context.yaml"),
+        format!("current: test\ncontexts:\n- name: test\n  url: {}\n  token: dummy\n", url),
+    )
+    .unwrap();
 
     cr1t_cmd(&home)
         .args(["apply"])
         .write_stdin("kind: group\nname: missing id\n")
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("id"));
+        .success()
+        .stdout(predicate::str::contains("group/g_missing_id created"));
+
+    // The id-less document was POSTed to the collection as-is; the server picks the id.
+    let (request_line, body) = requests.recv_timeout(std::time::Duration::from_secs(5)).unwrap();
+    assert!(request_line.starts_with("POST /api/v1/global/groups "), "{}", request_line);
+    assert_eq!(body, serde_json::json!({ "name": "missing id" }));
 }
 
 #[test]
@@ -673,3 +796,53 @@ fn test_apply_nonexistent_file_fails() {
         .failure()
         .stderr(predicate::str::contains("failed to read"));
 }
+
+// --- Lint (no infrastructure, no context) ---
+
+const LINT_FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/lint");
+
+#[test]
+fn test_lint_good_manifests_pass() {
+    let home = TempDir::new().unwrap();
+
+    cr1t_cmd(&home)
+        .args(["lint", "--strict", "-f", &format!("{}/good", LINT_FIXTURES)])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("0 errors, 0 warnings"));
+}
+
+#[test]
+fn test_lint_bad_manifests_fail() {
+    let home = TempDir::new().unwrap();
+
+    cr1t_cmd(&home)
+        .args(["lint", "-f", &format!("{}/bad", LINT_FIXTURES)])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("[duplicate-id]"))
+        .stderr(predicate::str::contains("lint failed with"));
+}
+
+#[test]
+fn test_lint_unknown_field_is_warning_unless_strict() {
+    let home = TempDir::new().unwrap();
+    let file = format!("{}/bad/unknown-field.yaml", LINT_FIXTURES);
+
+    cr1t_cmd(&home).args(["lint", "-f", &file]).assert().success();
+    cr1t_cmd(&home).args(["lint", "--strict", "-f", &file]).assert().failure();
+}
+
+#[test]
+fn test_lint_sarif_output() {
+    let home = TempDir::new().unwrap();
+
+    let output = cr1t_cmd(&home)
+        .args(["lint", "--format", "sarif", "-f", &format!("{}/bad/keys.yaml", LINT_FIXTURES)])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let sarif: serde_json::Value = serde_json::from_slice(&output.stdout).expect("SARIF is JSON");
+    assert_eq!(sarif["version"], "2.1.0");
+    assert_eq!(sarif["runs"][0]["results"].as_array().unwrap().len(), 5);
+}
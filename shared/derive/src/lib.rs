@@ -18,36 +18,61 @@ struct CritResourceArgs {
     no_acl: bool,
 }
 
+/// The string literal of a `name = "..."` argument, erroring on the value otherwise.
+fn string_arg(nv: &syn::MetaNameValue, name: &str) -> syn::Result<syn::LitStr> {
+    match &nv.value {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(s),
+            ..
+        }) => Ok(s.clone()),
+        other => Err(syn::Error::new_spanned(
+            other,
+            format!("`{}` must be a string literal, e.g. `{} = \"...\"`", name, name),
+        )),
+    }
+}
+
 impl Parse for CritResourceArgs {
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        let mut collection = None;
-        let mut prefix = None;
+        let mut collection: Option<syn::LitStr> = None;
+        let mut prefix: Option<syn::LitStr> = None;
         let mut no_acl = false;
 
+        let duplicate = |path: &syn::Path, name: &str| {
+            syn::Error::new_spanned(path, format!("duplicate `{}` argument", name))
+        };
+
         let metas = Punctuated::<Meta, Token![,]>::parse_terminated(input)?;
         for meta in metas {
             match &meta {
                 Meta::NameValue(nv) if nv.path.is_ident("collection") => {
-                    if let syn::Expr::Lit(syn::ExprLit {
-                        lit: syn::Lit::Str(s),
-                        ..
-                    }) = &nv.value
-                    {
-                        collection = Some(s.value());
+                    if collection.is_some() {
+                        return Err(duplicate(&nv.path, "collection"));
                     }
+                    let lit = string_arg(nv, "collection")?;
+                    if lit.value().is_empty() {
+                        return Err(syn::Error::new_spanned(&lit, "`collection` must not be empty"));
+                    }
+                    collection = Some(lit);
                 }
                 Meta::NameValue(nv) if nv.path.is_ident("prefix") => {
-                    if let syn::Expr::Lit(syn::ExprLit {
-                        lit: syn::Lit::Str(s),
-                        ..
-                    }) = &nv.value
-                    {
-                        prefix = Some(s.value());
+                    if prefix.is_some() {
+                        return Err(duplicate(&nv.path, "prefix"));
                     }
+                    prefix = Some(string_arg(nv, "prefix")?);
                 }
                 Meta::Path(p) if p.is_ident("no_acl") => {
+                    if no_acl {
+                        return Err(duplicate(p, "no_acl"));
+                    }
                     no_acl = true;
                 }
+                Meta::NameValue(nv) if nv.path.is_ident("no_acl") => {
+                    return Err(syn::Error::new_spanned(
+                        &meta,
+                        "`no_acl` is a flag and takes no value; write `no_acl`",
+                    ));
+                }
                 _ => {
                     return Err(syn::Error::new_spanned(
                         &meta,
@@ -58,17 +83,92 @@ impl Parse for CritResourceArgs {
         }
 
         Ok(CritResourceArgs {
-            collection: collection.ok_or_else(|| {
-                syn::Error::new(proc_macro2::Span::call_site(), "missing `collection = \"...\"`")
-            })?,
-            prefix: prefix.ok_or_else(|| {
-                syn::Error::new(proc_macro2::Span::call_site(), "missing `prefix = \"...\"`")
-            })?,
+            collection: collection
+                .ok_or_else(|| {
+                    syn::Error::new(
+                        proc_macro2::Span::call_site(),
+                        "missing `collection = \"...\"` (the ArangoDB collection, e.g. `collection = \"users\"`)",
+                    )
+                })?
+                .value(),
+            prefix: prefix
+                .ok_or_else(|| {
+                    syn::Error::new(
+                        proc_macro2::Span::call_site(),
+                        "missing `prefix = \"...\"` (the id prefix, e.g. `prefix = \"u_\"`; use `prefix = \"\"` for none)",
+                    )
+                })?
+                .value(),
             no_acl,
         })
     }
 }
 
+/// Fields `crit_resource` injects itself; user structs must not declare them.
+const INJECTED_FIELDS: &[&str] = &["id", "labels", "annotations", "acl", "state", "deletion", "hash_code"];
+
+/// Field attributes consumed by `crit_resource` and stripped before rustc sees them.
+const MARKER_ATTRS: &[&str] = &["brief", "hash_exclude"];
+
+/// Whether `field` carries the marker attribute `#[name]`. Rejects
+/// `#[name(...)]`, `#[name = ...]` and repeats, pointing at the offending attribute.
+fn has_marker(field: &syn::Field, name: &str) -> syn::Result<bool> {
+    let mut found = false;
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident(name)) {
+        if !matches!(attr.meta, Meta::Path(_)) {
+            return Err(syn::Error::new_spanned(attr, format!("`#[{}]` takes no arguments", name)));
+        }
+        if found {
+            return Err(syn::Error::new_spanned(attr, format!("duplicate `#[{}]` attribute", name)));
+        }
+        found = true;
+    }
+    Ok(found)
+}
+
+/// Whether `field` carries `#[brief]`.
+fn is_brief(field: &syn::Field) -> syn::Result<bool> {
+    has_marker(field, "brief")
+}
+
+/// The field's attributes minus `crit_resource` markers.
+fn rustc_attrs(field: &syn::Field) -> Vec<&syn::Attribute> {
+    field
+        .attrs
+        .iter()
+        .filter(|a| !MARKER_ATTRS.iter().any(|m| a.path().is_ident(m)))
+        .collect()
+}
+
+/// Rust type as written, without whitespace (e.g. `Option<Vec<RepoLink>>`),
+/// for the TypeScript field table.
+fn type_string(ty: &syn::Type) -> String {
+    quote!(#ty).to_string().chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+/// Whether serde may leave the field out of the serialized JSON.
+fn may_be_omitted(field: &syn::Field) -> bool {
+    field.attrs.iter().any(|a| {
+        a.path().is_ident("serde") && quote!(#a).to_string().contains("skip_serializing_if")
+    })
+}
+
+/// Whether a `#[serde(...)]` attribute on the field mentions `word`.
+fn has_serde_word(field: &syn::Field, word: &str) -> bool {
+    field.attrs.iter().any(|a| a.path().is_ident("serde") && quote!(#a).to_string().contains(word))
+}
+
+/// `#[serde(default)]` for a user field that has no default of its own, so
+/// documents stored before the field existed still deserialize. Flattened
+/// fields are left alone.
+fn tolerant_default(field: &syn::Field) -> TokenStream2 {
+    if has_serde_word(field, "default") || has_serde_word(field, "flatten") {
+        quote! {}
+    } else {
+        quote! { #[serde(default)] }
+    }
+}
+
 /// Attribute macro that wraps a struct to inject standard resource fields and
 /// generate companion code (Brief struct, hash computation, static metadata).
 ///
@@ -85,16 +185,30 @@ impl Parse for CritResourceArgs {
 /// ## Injected fields (at the top of the struct)
 /// - `id: PrincipalId` (with `#[serde(rename = "_key")]`)
 /// - `labels: Labels` (with `#[serde(default)]`) — queryable key-value pairs
-/// - `annotations: Labels` (with `#[serde(default)]`) — freeform key-value pairs
+/// - `annotations: Annotations` (with `#[serde(default)]`) — freeform key-value pairs
 /// - `acl: AccessControlStore` (unless `no_acl`, with `#[serde(default)]`)
 /// - `state: ResourceState` (with `#[serde(default)]`) — server-managed audit timestamps
 /// - `deletion: Option<DeletionInfo>` (with `#[serde(default, skip_serializing_if = "Option::is_none")]`)
 /// - `hash_code: String` (with `#[serde(default)]`)
 ///
+/// ## Field attributes
+/// - `#[brief]` — include the field in `{Name}Brief`
+/// - `#[hash_exclude]` — leave the field out of `compute_hash()`, for derived
+///   or volatile values (e.g. a cached `member_count`) that are not desired state
+///
+/// ## Compatibility
+/// Every user field without its own serde default gets `#[serde(default)]`,
+/// so documents written before a field was added still load; only `_key` is
+/// required. Unknown keys (written by a newer version) are ignored, as serde
+/// does by default. Put `#[serde(deny_unknown_fields)]` on the struct to opt
+/// out; note stored documents also carry ArangoDB's `_id` and `_rev`.
+///
 /// ## Generated code
-/// - `{Name}Brief` struct (from `#[brief]` fields, including injected `id`, `labels`)
+/// - `{Name}Brief` struct (from `#[brief]` fields, including injected `id`, `labels`,
+///   and `generation` lifted from `state`)
 /// - `impl {Name}` with: `to_brief()`, `brief_field_names()`, `compute_hash()`,
-///   `with_computed_hash()`, `collection_name()`, `id_prefix()`
+///   `hash_excluded_fields()`, `with_computed_hash()`, `collection_name()`,
+///   `id_prefix()`, `ts_fields()`
 #[proc_macro_attribute]
 pub fn crit_resource(attr: TokenStream, item: TokenStream) -> TokenStream {
     let args = parse_macro_input!(attr as CritResourceArgs);
@@ -113,36 +227,64 @@ fn impl_crit_resource(args: &CritResourceArgs, input: &ItemStruct) -> syn::Resul
     let collection = &args.collection;
     let prefix = &args.prefix;
 
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "crit_resource does not support generic structs; resources are stored as concrete documents",
+        ));
+    }
+
     // Extract user-defined fields
     let user_fields = match &input.fields {
         Fields::Named(fields) => &fields.named,
-        _ => {
+        other => {
             return Err(syn::Error::new_spanned(
-                name,
+                other,
                 "crit_resource can only be applied to structs with named fields",
             ))
         }
     };
 
+    for field in user_fields {
+        let Some(ident) = &field.ident else { continue };
+        let injected = INJECTED_FIELDS
+            .iter()
+            .any(|n| ident == n && !(*n == "acl" && args.no_acl));
+        if injected {
+            return Err(syn::Error::new_spanned(
+                ident,
+                format!("field `{}` is injected by crit_resource; remove it from the struct", ident),
+            ));
+        }
+    }
+
     // Determine which user fields are marked #[brief]
-    let user_brief_fields: Vec<_> = user_fields
-        .iter()
-        .filter(|f| f.attrs.iter().any(|a| a.path().is_ident("brief")))
-        .collect();
+    let mut user_brief_fields = Vec::new();
+    for field in user_fields {
+        if is_brief(field)? {
+            user_brief_fields.push(field);
+        }
+    }
+
+    // Fields marked #[hash_exclude] are left out of compute_hash
+    let mut hash_excluded = Vec::new();
+    for field in user_fields {
+        if has_marker(field, "hash_exclude")? {
+            hash_excluded.extend(field.ident.as_ref().map(|i| i.to_string()));
+        }
+    }
 
-    // Collect user-defined field definitions, stripping #[brief] attributes
-    // (they're only meaningful to this macro, not to rustc)
+    // Collect user-defined field definitions, stripping #[brief] and
+    // #[hash_exclude] (they're only meaningful to this macro, not to rustc)
     let user_field_defs = user_fields.iter().map(|f| {
         let field_name = &f.ident;
         let ty = &f.ty;
         let vis = &f.vis;
-        let attrs: Vec<_> = f
-            .attrs
-            .iter()
-            .filter(|a| !a.path().is_ident("brief"))
-            .collect();
+        let attrs = rustc_attrs(f);
+        let default = tolerant_default(f);
         quote! {
             #(#attrs)*
+            #default
             #vis #field_name: #ty
         }
     });
@@ -171,7 +313,7 @@ fn impl_crit_resource(args: &CritResourceArgs, input: &ItemStruct) -> syn::Resul
             #[serde(default)]
             pub labels: crate::util_models::Labels,
             #[serde(default)]
-            pub annotations: crate::util_models::Labels,
+            pub annotations: crate::util_models::Annotations,
             #acl_field
             #[serde(default)]
             pub state: crate::util_models::ResourceState,
@@ -191,13 +333,11 @@ fn impl_crit_resource(args: &CritResourceArgs, input: &ItemStruct) -> syn::Resul
     let user_brief_struct_fields = user_brief_fields.iter().map(|f| {
         let field_name = &f.ident;
         let ty = &f.ty;
-        let attrs: Vec<_> = f
-            .attrs
-            .iter()
-            .filter(|a| !a.path().is_ident("brief"))
-            .collect();
+        let attrs = rustc_attrs(f);
+        let default = tolerant_default(f);
         quote! {
             #(#attrs)*
+            #default
             pub #field_name: #ty
         }
     });
@@ -209,9 +349,37 @@ fn impl_crit_resource(args: &CritResourceArgs, input: &ItemStruct) -> syn::Resul
     });
 
     // Brief field name strings (for JSON filtering)
-    let user_brief_name_strs = user_brief_fields.iter().map(|f| {
-        let name_str = f.ident.as_ref().unwrap().to_string();
-        quote! { #name_str }
+    let user_brief_name_strs = user_brief_fields
+        .iter()
+        .filter_map(|f| f.ident.as_ref())
+        .map(|ident| ident.to_string());
+
+    // Serialized field layout for `crate::typegen`: (name, rust type, optional, brief),
+    // injected fields first, in struct order.
+    let injected = |name: &str, ty: &str, optional: bool, brief: bool| {
+        (name.to_string(), ty.to_string(), optional, brief)
+    };
+    let mut ts_entries = vec![
+        injected("id", "PrincipalId", false, true),
+        injected("labels", "Labels", false, true),
+        injected("annotations", "Annotations", false, false),
+    ];
+    if !args.no_acl {
+        ts_entries.push(injected("acl", "AccessControlStore", false, false));
+    }
+    ts_entries.push(injected("state", "ResourceState", false, false));
+    ts_entries.push(injected("deletion", "Option<DeletionInfo>", true, false));
+    ts_entries.push(injected("hash_code", "String", false, false));
+    for field in user_fields {
+        let Some(ident) = &field.ident else { continue };
+        let ty = type_string(&field.ty);
+        let optional = ty.starts_with("Option<") || may_be_omitted(field);
+        ts_entries.push((ident.to_string(), ty, optional, is_brief(field)?));
+    }
+    let ts_field_defs = ts_entries.iter().map(|(name, ty, optional, brief)| {
+        quote! {
+            crate::typegen::TsField { name: #name, rust_type: #ty, optional: #optional, brief: #brief }
+        }
     });
 
     let brief_def = quote! {
@@ -221,6 +389,9 @@ fn impl_crit_resource(args: &CritResourceArgs, input: &ItemStruct) -> syn::Resul
             pub id: crate::util_models::PrincipalId,
             #[serde(default)]
             pub labels: crate::util_models::Labels,
+            /// `state.generation`, lifted so lists can show the revision.
+            #[serde(default)]
+            pub generation: u64,
             // user-defined brief fields
             #(#user_brief_struct_fields,)*
         }
@@ -233,13 +404,20 @@ fn impl_crit_resource(args: &CritResourceArgs, input: &ItemStruct) -> syn::Resul
                 #brief_name {
                     id: self.id.clone(),
                     labels: self.labels.clone(),
+                    generation: self.state.generation,
                     #(#user_brief_assignments,)*
                 }
             }
 
             /// Returns the field names included in the brief representation.
             pub fn brief_field_names() -> &'static [&'static str] {
-                &["id", "labels", #(#user_brief_name_strs,)*]
+                &["id", "labels", "generation", #(#user_brief_name_strs,)*]
+            }
+
+            /// Kind string used on the wire: the `/v1/global/{kind}` route
+            /// segment and the name in `/v1/ops/kinds`. Same as the collection.
+            pub fn kind() -> &'static str {
+                #collection
             }
 
             /// ArangoDB collection name for this resource kind.
@@ -253,7 +431,8 @@ fn impl_crit_resource(args: &CritResourceArgs, input: &ItemStruct) -> syn::Resul
             }
 
             /// Compute FNV-1a hash of desired-state fields (everything except
-            /// hash_code, deletion, state). Returns 16-char hex string.
+            /// hash_code, deletion, state and `#[hash_exclude]` fields).
+            /// Returns 16-char hex string.
             pub fn compute_hash(&self) -> String {
                 // Serialize to JSON, then remove non-desired-state fields
                 let mut val = serde_json::to_value(self).unwrap_or_default();
@@ -264,6 +443,9 @@ fn impl_crit_resource(args: &CritResourceArgs, input: &ItemStruct) -> syn::Resul
                     // _id and _rev are ArangoDB internals, not desired state
                     obj.remove("_id");
                     obj.remove("_rev");
+                    for field in Self::hash_excluded_fields() {
+                        obj.remove(*field);
+                    }
                 }
                 let canonical = serde_json::to_string(&val).unwrap_or_default();
 
@@ -276,10 +458,21 @@ fn impl_crit_resource(args: &CritResourceArgs, input: &ItemStruct) -> syn::Resul
                 format!("{:016x}", hash)
             }
 
+            /// User fields marked `#[hash_exclude]`: derived or volatile
+            /// values that must not change `compute_hash`.
+            pub fn hash_excluded_fields() -> &'static [&'static str] {
+                &[#(#hash_excluded,)*]
+            }
+
             /// Set hash_code to the computed hash of current desired state.
             pub fn with_computed_hash(&mut self) {
                 self.hash_code = self.compute_hash();
             }
+
+            /// Serialized field layout, used to generate TypeScript definitions.
+            pub fn ts_fields() -> &'static [crate::typegen::TsField] {
+                &[#(#ts_field_defs,)*]
+            }
         }
     };
 
@@ -313,9 +506,9 @@ fn impl_brief(input: &DeriveInput) -> syn::Result<TokenStream2> {
     let fields = match &input.data {
         Data::Struct(data) => match &data.fields {
             Fields::Named(fields) => &fields.named,
-            _ => {
+            other => {
                 return Err(syn::Error::new_spanned(
-                    name,
+                    other,
                     "Brief can only be derived for structs with named fields",
                 ))
             }
@@ -328,10 +521,12 @@ fn impl_brief(input: &DeriveInput) -> syn::Result<TokenStream2> {
         }
     };
 
-    let brief_fields: Vec<_> = fields
-        .iter()
-        .filter(|f| f.attrs.iter().any(|a| a.path().is_ident("brief")))
-        .collect();
+    let mut brief_fields = Vec::new();
+    for field in fields {
+        if is_brief(field)? {
+            brief_fields.push(field);
+        }
+    }
 
     if brief_fields.is_empty() {
         return Err(syn::Error::new_spanned(
@@ -362,10 +557,10 @@ fn impl_brief(input: &DeriveInput) -> syn::Result<TokenStream2> {
     });
 
     // Generate field name strings for JSON filtering.
-    let field_name_strs = brief_fields.iter().map(|f| {
-        let name_str = f.ident.as_ref().unwrap().to_string();
-        quote! { #name_str }
-    });
+    let field_name_strs = brief_fields
+        .iter()
+        .filter_map(|f| f.ident.as_ref())
+        .map(|ident| ident.to_string());
 
     Ok(quote! {
         #[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
@@ -389,3 +584,132 @@ fn impl_brief(input: &DeriveInput) -> syn::Result<TokenStream2> {
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    fn args_err(args: &str) -> String {
+        match syn::parse_str::<CritResourceArgs>(args) {
+            Ok(_) => panic!("`{}` should not parse", args),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn resource_err(args: &str, item: ItemStruct) -> String {
+        let args = syn::parse_str::<CritResourceArgs>(args).unwrap();
+        impl_crit_resource(&args, &item).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn valid_resource_expands() {
+        let args = syn::parse_str::<CritResourceArgs>(r#"collection = "groups", prefix = "g_""#).unwrap();
+        let item: ItemStruct = parse_quote! {
+            pub struct Group {
+                #[brief]
+                pub name: String,
+                pub description: Option<String>,
+            }
+        };
+        assert!(impl_crit_resource(&args, &item).is_ok());
+    }
+
+    #[test]
+    fn fields_without_a_default_get_one() {
+        let args = syn::parse_str::<CritResourceArgs>(r#"collection = "groups", prefix = "g_""#).unwrap();
+        let item: ItemStruct = parse_quote! {
+            pub struct Group {
+                pub name: String,
+                #[serde(default, skip_serializing_if = "Option::is_none")]
+                pub description: Option<String>,
+            }
+        };
+        let out = impl_crit_resource(&args, &item).unwrap().to_string();
+        assert!(out.contains("# [serde (default)] pub name : String"), "{}", out);
+        assert!(!out.contains("# [serde (default)] pub description"), "{}", out);
+    }
+
+    #[test]
+    fn missing_arguments() {
+        assert!(args_err(r#"prefix = "g_""#).starts_with("missing `collection"));
+        assert!(args_err(r#"collection = "groups""#).starts_with("missing `prefix"));
+    }
+
+    #[test]
+    fn non_string_and_empty_arguments() {
+        assert!(args_err(r#"collection = groups, prefix = "g_""#).contains("`collection` must be a string literal"));
+        assert!(args_err(r#"collection = "groups", prefix = 1"#).contains("`prefix` must be a string literal"));
+        assert_eq!(args_err(r#"collection = "", prefix = "g_""#), "`collection` must not be empty");
+    }
+
+    #[test]
+    fn duplicate_arguments() {
+        assert_eq!(
+            args_err(r#"collection = "a", collection = "b", prefix = """#),
+            "duplicate `collection` argument"
+        );
+        assert_eq!(args_err(r#"collection = "a", prefix = "", no_acl, no_acl"#), "duplicate `no_acl` argument");
+    }
+
+    #[test]
+    fn unknown_and_misused_arguments() {
+        assert!(args_err(r#"collection = "a", prefix = "", key = "name""#).starts_with("unexpected argument"));
+        assert!(args_err(r#"collection = "a", prefix = "", no_acl = true"#).contains("`no_acl` is a flag"));
+    }
+
+    #[test]
+    fn tuple_and_generic_structs_are_rejected() {
+        let args = r#"collection = "a", prefix = """#;
+        assert!(resource_err(args, parse_quote! { pub struct A(String); }).contains("named fields"));
+        assert!(resource_err(args, parse_quote! { pub struct A<T> { pub t: T } }).contains("generic structs"));
+    }
+
+    #[test]
+    fn injected_field_names_are_rejected() {
+        let err = resource_err(r#"collection = "a", prefix = """#, parse_quote! { pub struct A { pub labels: String } });
+        assert_eq!(err, "field `labels` is injected by crit_resource; remove it from the struct");
+        // `acl` is only injected without `no_acl`.
+        let args = syn::parse_str::<CritResourceArgs>(r#"collection = "a", prefix = "", no_acl"#).unwrap();
+        assert!(impl_crit_resource(&args, &parse_quote! { pub struct A { pub acl: String } }).is_ok());
+    }
+
+    #[test]
+    fn misused_brief_attribute() {
+        let args = r#"collection = "a", prefix = """#;
+        let err = resource_err(args, parse_quote! { pub struct A { #[brief(full)] pub name: String } });
+        assert_eq!(err, "`#[brief]` takes no arguments");
+        let err = resource_err(args, parse_quote! { pub struct A { #[brief] #[brief] pub name: String } });
+        assert_eq!(err, "duplicate `#[brief]` attribute");
+    }
+
+    #[test]
+    fn hash_exclude_is_consumed_by_the_macro() {
+        let args = syn::parse_str::<CritResourceArgs>(r#"collection = "groups", prefix = "g_""#).unwrap();
+        let item: ItemStruct = parse_quote! {
+            pub struct Group {
+                #[brief]
+                #[hash_exclude]
+                pub member_count: u64,
+            }
+        };
+        let out = impl_crit_resource(&args, &item).unwrap().to_string();
+        assert!(!out.contains("hash_exclude ]"), "{}", out);
+        assert!(out.contains(r#"& ["member_count" ,]"#), "{}", out);
+
+        let err = resource_err(r#"collection = "a", prefix = """#, parse_quote! {
+            pub struct A { #[hash_exclude = "yes"] pub n: u64 }
+        });
+        assert_eq!(err, "`#[hash_exclude]` takes no arguments");
+    }
+
+    #[test]
+    fn brief_derive_errors() {
+        let err = impl_brief(&parse_quote! { struct A { name: String } }).unwrap_err();
+        assert!(err.to_string().contains("#[brief]"));
+        let err = impl_brief(&parse_quote! { enum A { X } }).unwrap_err();
+        assert_eq!(err.to_string(), "Brief can only be derived for structs");
+        let err = impl_brief(&parse_quote! { struct A(#[brief] String); }).unwrap_err();
+        assert!(err.to_string().contains("named fields"));
+    }
+}
@@ -0,0 +1,17 @@
+//! Write TypeScript definitions for the resource models.
+//!
+//! Usage: `cargo run -p crit-shared --bin crit-typegen [OUT_FILE]`
+//! (stdout when no file is given).
+
+fn main() {
+    let out = crit_shared::typegen::render_all();
+    match std::env::args().nth(1) {
+        Some(path) => {
+            if let Err(e) = std::fs::write(&path, out) {
+                eprintln!("crit-typegen: cannot write {}: {}", path, e);
+                std::process::exit(1);
+            }
+        }
+        None => print!("{}", out),
+    }
+}
@@ -7,6 +7,7 @@ use crate::util_models::PrincipalId;
 // ---------------------------------------------------------------------------
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
 pub struct PersonalInfo {
     pub name: String,
     pub gender: String,
@@ -14,6 +15,28 @@ pub struct PersonalInfo {
     pub manager: Option<String>,
 }
 
+/// Account at an external OpenID Connect provider that a user signs in with.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct OAuthIdentity {
+    /// The provider's issuer URL (`iss`).
+    pub issuer: String,
+    /// The user's id at the provider (`sub`), stable per issuer.
+    pub subject: String,
+}
+
+/// A user's TOTP second factor (`/v1/2fa/*`). Server-only: never returned by the API.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct TotpFactor {
+    /// Shared secret, encrypted with the server's TOTP key.
+    pub secret: String,
+    /// Set once a code has been verified; until then login ignores the factor.
+    #[serde(default)]
+    pub confirmed: bool,
+    /// SHA-256 (hex) of each recovery code not used yet.
+    #[serde(default)]
+    pub recovery_codes: Vec<String>,
+}
+
 // ---------------------------------------------------------------------------
 // Users
 // ---------------------------------------------------------------------------
@@ -31,6 +54,13 @@ pub struct User {
     /// ULID of the user's current profile wallpaper. Files in `user_wallpapers/`.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub wallpaper_ulid: Option<String>,
+    /// Set for users provisioned by OIDC login; they have no usable password.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oauth: Option<OAuthIdentity>,
+    /// Enrolled second factor; login asks for a code once it is confirmed.
+    #[hash_exclude]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub totp: Option<TotpFactor>,
 }
 
 // ---------------------------------------------------------------------------
@@ -180,3 +210,74 @@ pub struct Project {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub enabled_services: Vec<ProjectService>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn unknown_keys_from_newer_versions_are_ignored() {
+        let group: Group = serde_json::from_value(json!({
+            "_key": "g_ops",
+            "name": "Ops",
+            "color": "teal",
+            "state": { "generation": 3, "added_later": true },
+        }))
+        .unwrap();
+        assert_eq!(group.name, "Ops");
+        assert_eq!(group.state.generation, 3);
+    }
+
+    #[test]
+    fn fields_missing_from_older_documents_default() {
+        let user: User = serde_json::from_value(json!({
+            "_key": "u_alice",
+            "personal": { "name": "Alice" },
+        }))
+        .unwrap();
+        assert_eq!(user.password_hash, "");
+        assert_eq!(user.personal.name, "Alice");
+        assert_eq!(user.personal.job_title, "");
+
+        let group: Group = serde_json::from_value(json!({ "_key": "g_ops" })).unwrap();
+        assert_eq!(group.name, "");
+        assert!(group.description.is_none());
+    }
+
+    #[test]
+    fn labels_stored_under_older_key_rules_load() {
+        // Accepted on write before keys became DNS labels: underscores, capitals.
+        let user: User = serde_json::from_value(json!({
+            "_key": "u_alice",
+            "password_hash": "$argon2id$...",
+            "personal": { "name": "Alice" },
+            "labels": { "app_name": "crit", "Team": "Platform", "critical.io/owner": "u_alice" },
+        }))
+        .unwrap();
+        assert_eq!(user.labels.get("Team").map(String::as_str), Some("Platform"));
+        assert_eq!(user.labels.len(), 3);
+    }
+
+    #[crit_derive::crit_resource(collection = "widgets", prefix = "w_")]
+    struct Widget {
+        pub name: String,
+        #[hash_exclude]
+        pub member_count: u64,
+    }
+
+    #[test]
+    fn hash_excluded_fields_do_not_change_the_hash() {
+        let mut widget = Widget { name: "gear".into(), ..Default::default() };
+        let hash = widget.compute_hash();
+
+        widget.member_count = 42;
+        assert_eq!(widget.compute_hash(), hash);
+        assert_eq!(Widget::hash_excluded_fields(), &["member_count"]);
+        let value = serde_json::to_value(&widget).unwrap();
+        assert_eq!(crate::util_models::compute_value_hash_excluding(&value, Widget::hash_excluded_fields()), hash);
+
+        widget.name = "sprocket".into();
+        assert_ne!(widget.compute_hash(), hash);
+    }
+}
@@ -0,0 +1,313 @@
+//! Validated resource labels and equality-based label selectors.
+//!
+//! Label keys are `[prefix/]name`: `name` is a DNS label of at most 63 chars
+//! (`[a-z0-9]([-a-z0-9]*[a-z0-9])?`), `prefix` a DNS subdomain of such labels
+//! joined by dots, at most 253 chars. Values are at most 253 chars and must not
+//! contain control characters. The rules apply to writes: `Labels::insert`,
+//! `TryFrom`, and the server's metadata validation. Deserialization accepts any
+//! string map, so documents stored under the earlier, looser key rules
+//! (alphanumerics plus `-_./`, e.g. `app_name`) still load.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Principal that owns the resource (e.g. `u_alice`).
+pub const LABEL_OWNER: &str = "critical.io/owner";
+/// Tool that manages the resource (e.g. `cr1t`, `terraform`).
+pub const LABEL_MANAGED_BY: &str = "critical.io/managed-by";
+
+/// Max length of the name part of a key.
+pub const LABEL_NAME_MAX: usize = 63;
+/// Max length of the optional `prefix/` part of a key.
+pub const LABEL_PREFIX_MAX: usize = 253;
+/// Max length of a value.
+pub const LABEL_VALUE_MAX: usize = 253;
+
+/// A rejected label key, value or selector; the message names the offending key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelError(pub String);
+
+impl fmt::Display for LabelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for LabelError {}
+
+/// `[a-z0-9]([-a-z0-9]*[a-z0-9])?`, at most `LABEL_NAME_MAX` chars.
+fn check_dns_label(s: &str) -> Result<(), String> {
+    if s.is_empty() {
+        return Err("must not be empty".to_string());
+    }
+    if s.len() > LABEL_NAME_MAX {
+        return Err(format!("must be at most {} characters", LABEL_NAME_MAX));
+    }
+    if let Some(c) = s.chars().find(|c| !matches!(c, 'a'..='z' | '0'..='9' | '-')) {
+        return Err(format!("invalid character '{}' (allowed: a-z, 0-9, '-')", c));
+    }
+    if s.starts_with('-') || s.ends_with('-') {
+        return Err("must start and end with a-z or 0-9".to_string());
+    }
+    Ok(())
+}
+
+/// Validate a label key: `[prefix/]name`.
+pub fn validate_key(key: &str) -> Result<(), LabelError> {
+    let err = |msg: String| LabelError(format!("label key '{}': {}", key, msg));
+    let (prefix, name) = match key.split_once('/') {
+        Some((p, n)) => (Some(p), n),
+        None => (None, key),
+    };
+    if let Some(prefix) = prefix {
+        if prefix.len() > LABEL_PREFIX_MAX {
+            return Err(err(format!("prefix must be at most {} characters", LABEL_PREFIX_MAX)));
+        }
+        for part in prefix.split('.') {
+            check_dns_label(part).map_err(|e| err(format!("prefix {}", e)))?;
+        }
+    }
+    check_dns_label(name).map_err(|e| err(format!("name {}", e)))
+}
+
+/// Validate the value stored under `key`.
+pub fn validate_value(key: &str, value: &str) -> Result<(), LabelError> {
+    if value.chars().count() > LABEL_VALUE_MAX {
+        return Err(LabelError(format!(
+            "label '{}' value must be at most {} characters",
+            key, LABEL_VALUE_MAX
+        )));
+    }
+    if value.chars().any(char::is_control) {
+        return Err(LabelError(format!(
+            "label '{}' value must not contain control characters",
+            key
+        )));
+    }
+    Ok(())
+}
+
+/// Label map for `-l` selector filtering (like kubectl). Serializes as a plain
+/// JSON object; read access goes through `Deref` to the inner map.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct Labels(HashMap<String, String>);
+
+impl Labels {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert a validated label, returning the previous value.
+    pub fn insert(
+        &mut self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Result<Option<String>, LabelError> {
+        let (key, value) = (key.into(), value.into());
+        validate_key(&key)?;
+        validate_value(&key, &value)?;
+        Ok(self.0.insert(key, value))
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        self.0.remove(key)
+    }
+
+    /// Whether these labels satisfy every requirement of `selector`.
+    pub fn matches(&self, selector: &LabelSelector) -> bool {
+        selector.0.iter().all(|req| match req {
+            LabelRequirement::Equals(k, v) => self.0.get(k) == Some(v),
+            LabelRequirement::NotEquals(k, v) => self.0.get(k) != Some(v),
+            LabelRequirement::Exists(k) => self.0.contains_key(k),
+            LabelRequirement::NotExists(k) => !self.0.contains_key(k),
+        })
+    }
+
+    pub fn into_inner(self) -> HashMap<String, String> {
+        self.0
+    }
+}
+
+impl std::ops::Deref for Labels {
+    type Target = HashMap<String, String>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl TryFrom<HashMap<String, String>> for Labels {
+    type Error = LabelError;
+
+    fn try_from(map: HashMap<String, String>) -> Result<Self, Self::Error> {
+        // Sorted so the reported error is deterministic when several labels are bad.
+        let mut keys: Vec<&String> = map.keys().collect();
+        keys.sort();
+        for key in keys {
+            validate_key(key)?;
+            validate_value(key, &map[key])?;
+        }
+        Ok(Self(map))
+    }
+}
+
+impl<'de> Deserialize<'de> for Labels {
+    /// Unchecked: stored documents are read back as they are. Validate with
+    /// `Labels::try_from` where a map comes from a client.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        HashMap::<String, String>::deserialize(deserializer).map(Self)
+    }
+}
+
+/// One clause of a selector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LabelRequirement {
+    /// `key=value` (or `key==value`)
+    Equals(String, String),
+    /// `key!=value`; also matches when `key` is absent.
+    NotEquals(String, String),
+    /// `key`
+    Exists(String),
+    /// `!key`
+    NotExists(String),
+}
+
+/// Comma-separated requirements that must all hold, e.g. `env=prod,!legacy`.
+/// The empty selector matches everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LabelSelector(pub Vec<LabelRequirement>);
+
+impl std::str::FromStr for LabelSelector {
+    type Err = LabelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut reqs = Vec::new();
+        for clause in s.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+            let req = if let Some((k, v)) = clause.split_once("!=") {
+                LabelRequirement::NotEquals(k.trim().to_string(), v.trim().to_string())
+            } else if let Some((k, v)) = clause.split_once("==").or_else(|| clause.split_once('=')) {
+                LabelRequirement::Equals(k.trim().to_string(), v.trim().to_string())
+            } else if let Some(k) = clause.strip_prefix('!') {
+                LabelRequirement::NotExists(k.trim().to_string())
+            } else {
+                LabelRequirement::Exists(clause.to_string())
+            };
+            let (LabelRequirement::Equals(key, _)
+            | LabelRequirement::NotEquals(key, _)
+            | LabelRequirement::Exists(key)
+            | LabelRequirement::NotExists(key)) = &req;
+            validate_key(key).map_err(|e| LabelError(format!("selector '{}': {}", clause, e)))?;
+            reqs.push(req);
+        }
+        Ok(Self(reqs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn labels(pairs: &[(&str, &str)]) -> Labels {
+        let mut l = Labels::new();
+        for (k, v) in pairs {
+            l.insert(*k, *v).unwrap();
+        }
+        l
+    }
+
+    #[test]
+    fn valid_keys() {
+        for key in ["team", "a", "tier-1", "critical.io/owner", "a.b-c.d/x9", &"k".repeat(63)] {
+            assert!(validate_key(key).is_ok(), "{} should be valid", key);
+        }
+    }
+
+    #[test]
+    fn invalid_keys_name_the_key() {
+        for key in ["", "Team", "my_key", "-team", "team-", "a/b/c", "/team", "critical.io/", "bad..io/x"] {
+            let err = validate_key(key).unwrap_err();
+            assert!(err.0.contains(&format!("'{}'", key)), "{:?}", err);
+        }
+        assert!(validate_key(&"k".repeat(64)).unwrap_err().0.contains("at most 63"));
+        let long_prefix = format!("{}/x", vec!["a".repeat(60); 5].join("."));
+        assert!(validate_key(&long_prefix).unwrap_err().0.contains("prefix must be at most 253"));
+    }
+
+    #[test]
+    fn values() {
+        assert!(validate_value("k", "").is_ok());
+        assert!(validate_value("k", "Gold Tier / 2026").is_ok());
+        assert!(validate_value("k", &"v".repeat(253)).is_ok());
+        assert!(validate_value("k", &"v".repeat(254)).is_err());
+        let err = validate_value("team", "plat\nform").unwrap_err();
+        assert_eq!(err.0, "label 'team' value must not contain control characters");
+    }
+
+    #[test]
+    fn insert_validates() {
+        let mut l = Labels::new();
+        assert_eq!(l.insert(LABEL_OWNER, "u_alice").unwrap(), None);
+        assert_eq!(l.insert(LABEL_OWNER, "u_bob").unwrap().as_deref(), Some("u_alice"));
+        assert!(l.insert("Bad", "x").is_err());
+        assert_eq!(l.len(), 1);
+    }
+
+    #[test]
+    fn serde_keeps_plain_map() {
+        let l = labels(&[("env", "prod"), (LABEL_MANAGED_BY, "cr1t")]);
+        let wire = serde_json::to_value(&l).unwrap();
+        assert_eq!(wire, json!({ "env": "prod", "critical.io/managed-by": "cr1t" }));
+        assert_eq!(serde_json::from_value::<Labels>(wire).unwrap(), l);
+        assert!(serde_json::from_value::<Labels>(json!({ "env": 1 })).is_err());
+    }
+
+    #[test]
+    fn stored_labels_under_older_rules_still_load() {
+        let stored = json!({ "app_name": "crit", "Team": "Platform" });
+        let l = serde_json::from_value::<Labels>(stored.clone()).unwrap();
+        assert_eq!(l.get("app_name").map(String::as_str), Some("crit"));
+        assert!(l.matches(&"env!=prod".parse().unwrap()));
+        // Writes still hold them to the current rules.
+        let map: HashMap<String, String> = serde_json::from_value(stored).unwrap();
+        assert!(Labels::try_from(map).unwrap_err().0.contains("label key 'Team'"));
+    }
+
+    #[test]
+    fn selector_parsing() {
+        let sel: LabelSelector = "env=prod, tier==gold,team!=qa,critical.io/owner,!legacy".parse().unwrap();
+        assert_eq!(
+            sel.0,
+            vec![
+                LabelRequirement::Equals("env".into(), "prod".into()),
+                LabelRequirement::Equals("tier".into(), "gold".into()),
+                LabelRequirement::NotEquals("team".into(), "qa".into()),
+                LabelRequirement::Exists("critical.io/owner".into()),
+                LabelRequirement::NotExists("legacy".into()),
+            ]
+        );
+        assert_eq!("".parse::<LabelSelector>().unwrap(), LabelSelector::default());
+        assert!("Env=prod".parse::<LabelSelector>().unwrap_err().0.contains("selector 'Env=prod'"));
+    }
+
+    #[test]
+    fn selector_matching() {
+        let l = labels(&[("env", "prod"), ("team", "platform"), (LABEL_OWNER, "u_alice")]);
+        let matches = |s: &str| l.matches(&s.parse().unwrap());
+
+        assert!(matches(""));
+        assert!(matches("env=prod"));
+        assert!(matches("env=prod,team=platform"));
+        assert!(!matches("env=prod,team=qa"));
+        assert!(matches("team!=qa"));
+        assert!(matches("tier!=gold"));
+        assert!(!matches("env!=prod"));
+        assert!(matches("critical.io/owner"));
+        assert!(!matches("tier"));
+        assert!(matches("!tier"));
+        assert!(!matches("!env"));
+    }
+}
@@ -1,6 +1,9 @@
 pub mod data_models;
+pub mod labels;
+pub mod requests;
+pub mod typegen;
 pub mod util_models;
 
 pub use crit_derive::Brief;
 pub use crit_derive::crit_resource;
-pub use util_models::compute_value_hash;
+pub use util_models::{compute_value_hash, compute_value_hash_excluding};
@@ -0,0 +1,186 @@
+//! Wire types shared by the server and its clients.
+//!
+//! The server serializes these and the CLIs deserialize them, so field names
+//! are checked by the compiler on both sides. Fields added after the first
+//! release are `#[serde(default)]` so clients keep working against older servers.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Envelope of every list endpoint: `{ "items": [...], "total", "offset", "limit" }`.
+///
+/// Cursor-paginated lists omit the counters; they then deserialize as zero.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ListResponse<T> {
+    pub items: Vec<T>,
+    #[serde(default)]
+    pub total: usize,
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(default)]
+    pub limit: usize,
+}
+
+impl<T> ListResponse<T> {
+    /// A single page holding the whole result set.
+    pub fn complete(items: Vec<T>) -> Self {
+        let total = items.len();
+        Self { items, total, offset: 0, limit: total }
+    }
+}
+
+/// Body of every error response, nested under `"error"` (see [`ErrorBody`]).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    /// Machine-readable error class, e.g. `"not_found"` or `"conflict"`.
+    /// Older servers sent this as `type`.
+    #[serde(alias = "type")]
+    pub code: String,
+    pub message: String,
+    /// HTTP status code, repeated for clients that only keep the body.
+    #[serde(default)]
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+/// Top-level shape of an error response: `{ "error": { ... } }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorBody {
+    pub error: ErrorResponse,
+}
+
+/// What an apply (upsert) did with the submitted document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApplyAction {
+    Created,
+    Updated,
+    /// The stored desired-state hash already matched.
+    Unchanged,
+}
+
+/// Result of `POST /v1/global/{kind}/{id}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApplyResponse {
+    pub key: String,
+    pub kind: String,
+    pub action: ApplyAction,
+    /// `hash_code` of the stored document after the write.
+    pub hash: String,
+}
+
+/// One entry of `GET /v1/ops/kinds`: a kind the server can describe.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct KindInfo {
+    pub name: String,
+    /// Lives under a project (`/v1/projects/{project}/{kind}`).
+    #[serde(default)]
+    pub scoped: bool,
+    /// Prefix of generated ids, e.g. `u_`.
+    #[serde(default)]
+    pub id_prefix: String,
+    /// Fields of the list (brief) view, in struct order.
+    #[serde(default)]
+    pub brief_fields: Vec<String>,
+    /// Other names accepted for the kind: singular and short (id prefix) forms.
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Wire field holding the resource key, e.g. `id`.
+    #[serde(default)]
+    pub key_field: String,
+    /// The caller may list the kind with `GET /v1/global/{kind}` (results are
+    /// still ACL-filtered); scoped kinds are listed per project instead.
+    #[serde(default)]
+    pub can_list: bool,
+}
+
+impl KindInfo {
+    /// Whether `input` names this kind, by name or alias, ignoring case.
+    pub fn is_named(&self, input: &str) -> bool {
+        self.name.eq_ignore_ascii_case(input) || self.aliases.iter().any(|a| a.eq_ignore_ascii_case(input))
+    }
+}
+
+/// The kind `input` names in `kinds`, if any.
+pub fn resolve_kind<'a>(kinds: &'a [KindInfo], input: &str) -> Option<&'a KindInfo> {
+    kinds.iter().find(|k| k.is_named(input))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn list_response_round_trip() {
+        let list = ListResponse::complete(vec![json!({ "id": "g_a" }), json!({ "id": "g_b" })]);
+        let wire = serde_json::to_value(&list).unwrap();
+        assert_eq!(wire["total"], 2);
+        assert_eq!(wire["limit"], 2);
+        assert_eq!(serde_json::from_value::<ListResponse<Value>>(wire).unwrap(), list);
+    }
+
+    #[test]
+    fn list_response_accepts_cursor_envelope() {
+        let wire = json!({ "items": ["a"], "has_more": true, "next_cursor": "a" });
+        let list: ListResponse<String> = serde_json::from_value(wire).unwrap();
+        assert_eq!(list.items, vec!["a".to_string()]);
+        assert_eq!(list.total, 0);
+    }
+
+    #[test]
+    fn error_body_round_trip_and_legacy_type_field() {
+        let body = ErrorBody {
+            error: ErrorResponse {
+                code: "conflict".into(),
+                message: "groups/g_a was modified".into(),
+                status: 409,
+                details: Some(json!({ "expected": "abc" })),
+                request_id: None,
+            },
+        };
+        let wire = serde_json::to_value(&body).unwrap();
+        assert!(wire["error"].get("request_id").is_none());
+        assert_eq!(serde_json::from_value::<ErrorBody>(wire).unwrap(), body);
+
+        let legacy = json!({ "error": { "type": "not_found", "message": "nope", "status": 404 } });
+        let parsed: ErrorBody = serde_json::from_value(legacy).unwrap();
+        assert_eq!(parsed.error.code, "not_found");
+        assert_eq!(parsed.error.details, None);
+    }
+
+    #[test]
+    fn apply_response_round_trip() {
+        let resp = ApplyResponse {
+            key: "g_a".into(),
+            kind: "groups".into(),
+            action: ApplyAction::Unchanged,
+            hash: "abc".into(),
+        };
+        let wire = serde_json::to_value(&resp).unwrap();
+        assert_eq!(wire["action"], "unchanged");
+        assert_eq!(serde_json::from_value::<ApplyResponse>(wire).unwrap(), resp);
+    }
+
+    #[test]
+    fn kind_info_accepts_name_only_entries() {
+        let info: KindInfo = serde_json::from_value(json!({ "name": "groups" })).unwrap();
+        assert_eq!(info, KindInfo { name: "groups".into(), ..Default::default() });
+    }
+
+    #[test]
+    fn kinds_resolve_by_name_or_alias() {
+        let kinds = [
+            KindInfo { name: "groups".into(), aliases: vec!["group".into(), "g".into()], ..Default::default() },
+            KindInfo { name: "service_accounts".into(), aliases: vec!["service_account".into(), "sa".into()], ..Default::default() },
+        ];
+        for input in ["groups", "group", "G", "Groups"] {
+            assert_eq!(resolve_kind(&kinds, input).map(|k| k.name.as_str()), Some("groups"), "{}", input);
+        }
+        assert_eq!(resolve_kind(&kinds, "sa").map(|k| k.name.as_str()), Some("service_accounts"));
+        assert!(resolve_kind(&kinds, "tasks").is_none());
+    }
+}
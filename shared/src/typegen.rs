@@ -0,0 +1,288 @@
+//! TypeScript definitions for the web UI, generated from the resource models.
+//!
+//! `crit_resource` records each resource's serialized field layout in
+//! `ts_fields()`; this module maps those Rust types to TypeScript and renders
+//! one `{Name}Brief` and one `{Name}` interface per resource. Supporting types
+//! (ACLs, audit state, project sub-types) are hand-written in [`PRELUDE`] and
+//! must be kept in sync when those structs change. Output order is fixed by
+//! [`resources`] and struct field order, so regenerating is diff-friendly.
+//!
+//! Field names are the serialized (snake_case) names, with `id` as returned by
+//! the API rather than the stored `_key`.
+
+use crate::data_models::{Group, PipelineAccount, Project, ServiceAccount, User};
+
+/// One serialized field of a resource, as recorded by `crit_resource`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TsField {
+    pub name: &'static str,
+    /// Rust type as written, without whitespace, e.g. `Option<Vec<RepoLink>>`.
+    pub rust_type: &'static str,
+    /// `Option` or `skip_serializing_if`: the key may be absent.
+    pub optional: bool,
+    pub brief: bool,
+}
+
+/// Fields the API never returns (stripped by the kind's controller).
+const SERVER_ONLY_FIELDS: &[&str] = &["password_hash", "totp"];
+
+/// Header of the generated file.
+pub const HEADER: &str = "// Generated by crit-typegen from crit-shared. Do not edit by hand.\n";
+
+/// Supporting types referenced by resource fields.
+pub const PRELUDE: &str = r#"export interface AccessControlList {
+  /** Permission bitmask (FETCH=1, LIST=2, NOTIFY=4, CREATE=8, MODIFY=16, ...). */
+  permissions: number;
+  principals: string[];
+  scope?: string;
+}
+
+export interface AccessControlStore {
+  list: AccessControlList[];
+  last_mod_date: string;
+}
+
+export interface ResourceState {
+  created_at: string;
+  created_by?: string;
+  updated_at?: string;
+  updated_by?: string;
+  generation: number;
+}
+
+export interface DisconnectedEdge {
+  collection: string;
+  key: string;
+  from: string;
+  to: string;
+}
+
+export interface DeletionInfo {
+  deleted_at: string;
+  deleted_by: string;
+  disconnected_edges?: DisconnectedEdge[];
+}
+
+export interface PersonalInfo {
+  name: string;
+  gender: string;
+  job_title: string;
+  manager?: string;
+}
+
+export interface OAuthIdentity {
+  issuer: string;
+  subject: string;
+}
+
+export type RepoProvider = "git" | "github" | "gitlab" | "bitbucket" | "svn" | "mercurial" | "custom";
+
+export interface RepoLink {
+  url: string;
+  provider: RepoProvider;
+  name?: string;
+  default_branch?: string;
+}
+
+export type ProjectService =
+  | "integrations"
+  | "pipelines"
+  | "deployments"
+  | "secrets"
+  | "wikis"
+  | "apps"
+  | "tasks"
+  | "talks"
+  | "releases"
+  | "environments"
+  | "insights";
+"#;
+
+/// `generation` of a brief (list) view, copied there from `state`.
+const BRIEF_GENERATION: TsField = TsField { name: "generation", rust_type: "u64", optional: true, brief: true };
+
+/// Every resource kind exported to TypeScript, in output order.
+pub fn resources() -> Vec<(&'static str, &'static [TsField])> {
+    vec![
+        ("User", User::ts_fields()),
+        ("Group", Group::ts_fields()),
+        ("ServiceAccount", ServiceAccount::ts_fields()),
+        ("PipelineAccount", PipelineAccount::ts_fields()),
+        ("Project", Project::ts_fields()),
+    ]
+}
+
+/// The complete `.d.ts` file: header, prelude, then every resource.
+pub fn render_all() -> String {
+    let mut out = format!("{}\n{}", HEADER, PRELUDE);
+    for (name, fields) in resources() {
+        out.push('\n');
+        out.push_str(&render_resource(name, fields));
+    }
+    out
+}
+
+/// `{name}Brief` (the list view) followed by the full `{name}` interface.
+pub fn render_resource(name: &str, fields: &[TsField]) -> String {
+    let mut brief: Vec<TsField> = fields.iter().copied().filter(|f| f.brief).collect();
+    // List views lift `state.generation` next to the injected fields.
+    let at = brief.iter().position(|f| f.name == "labels").map_or(0, |i| i + 1);
+    brief.insert(at, BRIEF_GENERATION);
+    format!(
+        "{}\n{}",
+        render_interface(&format!("{}Brief", name), &brief),
+        render_interface(name, fields)
+    )
+}
+
+fn render_interface(name: &str, fields: &[TsField]) -> String {
+    let mut out = format!("export interface {} {{\n", name);
+    for f in fields.iter().filter(|f| !SERVER_ONLY_FIELDS.contains(&f.name)) {
+        let ty = match strip_generic(f.rust_type, "Option") {
+            Some(inner) if f.optional => ts_type(inner),
+            _ => ts_type(f.rust_type),
+        };
+        let mark = if f.optional { "?" } else { "" };
+        out.push_str(&format!("  {}{}: {};\n", f.name, mark, ty));
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Map a Rust type (as written, no whitespace) to its TypeScript equivalent.
+/// Types without a mapping keep their name and must be defined in [`PRELUDE`].
+pub fn ts_type(rust: &str) -> String {
+    let (outer, args) = match rust.find('<') {
+        Some(i) if rust.ends_with('>') => (&rust[..i], split_args(&rust[i + 1..rust.len() - 1])),
+        _ => (rust, Vec::new()),
+    };
+    let outer = outer.rsplit("::").next().unwrap_or(outer);
+    match (outer, args.as_slice()) {
+        ("Option", [inner]) => format!("{} | null", ts_type(inner)),
+        ("Vec", [inner]) => {
+            let inner = ts_type(inner);
+            if inner.contains(' ') {
+                format!("({})[]", inner)
+            } else {
+                format!("{}[]", inner)
+            }
+        }
+        ("HashMap" | "BTreeMap", [_, value]) => format!("Record<string, {}>", ts_type(value)),
+        ("Labels" | "Annotations", []) => "Record<string, string>".to_string(),
+        ("String" | "str" | "PrincipalId" | "DateTime" | "Uuid", _) => "string".to_string(),
+        ("bool", []) => "boolean".to_string(),
+        ("u8" | "u16" | "u32" | "u64" | "usize" | "i8" | "i16" | "i32" | "i64" | "isize" | "f32" | "f64", []) => {
+            "number".to_string()
+        }
+        ("Value", []) => "unknown".to_string(),
+        (other, _) => other.to_string(),
+    }
+}
+
+/// `inner` when `rust` is `{wrapper}<inner>`.
+fn strip_generic<'a>(rust: &'a str, wrapper: &str) -> Option<&'a str> {
+    rust.strip_prefix(wrapper)?.strip_prefix('<')?.strip_suffix('>')
+}
+
+/// Split generic arguments on top-level commas.
+fn split_args(args: &str) -> Vec<&str> {
+    let (mut depth, mut start, mut out) = (0usize, 0, Vec::new());
+    for (i, c) in args.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                out.push(&args[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    out.push(&args[start..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_rust_types() {
+        assert_eq!(ts_type("String"), "string");
+        assert_eq!(ts_type("crate::util_models::PrincipalId"), "string");
+        assert_eq!(ts_type("DateTime<Utc>"), "string");
+        assert_eq!(ts_type("Vec<RepoLink>"), "RepoLink[]");
+        assert_eq!(ts_type("Vec<Option<String>>"), "(string | null)[]");
+        assert_eq!(ts_type("HashMap<String,Vec<u32>>"), "Record<string, number[]>");
+        assert_eq!(ts_type("Option<bool>"), "boolean | null");
+        assert_eq!(ts_type("serde_json::Value"), "unknown");
+    }
+
+    #[test]
+    fn user_snapshot() {
+        assert_eq!(
+            render_resource("User", User::ts_fields()),
+            "\
+export interface UserBrief {
+  id: string;
+  labels: Record<string, string>;
+  generation?: number;
+  personal: PersonalInfo;
+}
+
+export interface User {
+  id: string;
+  labels: Record<string, string>;
+  annotations: Record<string, string>;
+  state: ResourceState;
+  deletion?: DeletionInfo;
+  hash_code: string;
+  personal: PersonalInfo;
+  avatar_ulid?: string;
+  wallpaper_ulid?: string;
+  oauth?: OAuthIdentity;
+}
+"
+        );
+    }
+
+    #[test]
+    fn project_snapshot() {
+        assert_eq!(
+            render_resource("Project", Project::ts_fields()),
+            "\
+export interface ProjectBrief {
+  id: string;
+  labels: Record<string, string>;
+  generation?: number;
+  name: string;
+}
+
+export interface Project {
+  id: string;
+  labels: Record<string, string>;
+  annotations: Record<string, string>;
+  acl: AccessControlStore;
+  state: ResourceState;
+  deletion?: DeletionInfo;
+  hash_code: string;
+  name: string;
+  description?: string;
+  repositories?: RepoLink[];
+  enabled_services?: ProjectService[];
+}
+"
+        );
+    }
+
+    #[test]
+    fn output_is_deterministic_and_self_contained() {
+        let out = render_all();
+        assert_eq!(out, render_all());
+        assert!(out.starts_with(HEADER));
+        for (name, _) in resources() {
+            assert!(out.contains(&format!("export interface {} {{", name)));
+            assert!(out.contains(&format!("export interface {}Brief {{", name)));
+        }
+    }
+}
@@ -4,10 +4,210 @@ use bitflags::bitflags;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-pub type PrincipalId = String;
+// ---------------------------------------------------------------------------
+// Principal identifiers
+// ---------------------------------------------------------------------------
+
+/// What kind of principal an id refers to, derived from its prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PrincipalKind {
+    User,
+    Group,
+    ServiceAccount,
+    PipelineAccount,
+    /// No known prefix (e.g. a project key, or a legacy unprefixed id).
+    Other,
+}
 
-/// Label map for `-l` / `--field-selector` filtering (like kubectl).
-pub type Labels = HashMap<String, String>;
+impl PrincipalKind {
+    /// Id prefix for this kind; empty for `Other`.
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            PrincipalKind::User => "u_",
+            PrincipalKind::Group => "g_",
+            PrincipalKind::ServiceAccount => "sa_",
+            PrincipalKind::PipelineAccount => "pa_",
+            PrincipalKind::Other => "",
+        }
+    }
+
+    /// AQL `LIKE` pattern matching ids of this kind. `_` is a LIKE wildcard,
+    /// so it is escaped to match the prefix literally.
+    pub fn like_pattern(&self) -> String {
+        format!("{}%", self.prefix().replace('_', "\\_"))
+    }
+
+    const PREFIXED: [PrincipalKind; 4] = [
+        PrincipalKind::User,
+        PrincipalKind::Group,
+        PrincipalKind::ServiceAccount,
+        PrincipalKind::PipelineAccount,
+    ];
+}
+
+/// A resource / principal id as stored in `_key`, including its kind prefix
+/// (`u_alice`, `g_admins`). Serializes as the plain prefixed string.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PrincipalId(String);
+
+impl PrincipalId {
+    /// Build an id from a kind and a bare name as a user typed it. The prefix
+    /// is always added: the name `u_bob` is the id `u_u_bob`, not user `bob`.
+    pub fn with_prefix(kind: PrincipalKind, bare: &str) -> Self {
+        Self(format!("{}{}", kind.prefix(), bare))
+    }
+
+    pub fn user(bare: &str) -> Self {
+        Self::with_prefix(PrincipalKind::User, bare)
+    }
+
+    pub fn group(bare: &str) -> Self {
+        Self::with_prefix(PrincipalKind::Group, bare)
+    }
+
+    /// A value that is usually already an id of `kind`: kept as-is when it
+    /// carries the prefix, prefixed otherwise. Only for ids handed around by
+    /// the server (JWT subjects, stored keys), never for names users type.
+    pub fn parse(kind: PrincipalKind, id_or_bare: &str) -> Self {
+        if id_or_bare.starts_with(kind.prefix()) {
+            Self(id_or_bare.to_string())
+        } else {
+            Self::with_prefix(kind, id_or_bare)
+        }
+    }
+
+    pub fn kind(&self) -> PrincipalKind {
+        PrincipalKind::PREFIXED
+            .into_iter()
+            .find(|k| self.0.starts_with(k.prefix()))
+            .unwrap_or(PrincipalKind::Other)
+    }
+
+    /// The id without its kind prefix (`u_alice` → `alice`).
+    pub fn bare(&self) -> &str {
+        &self.0[self.kind().prefix().len()..]
+    }
+
+    pub fn is_user(&self) -> bool {
+        self.kind() == PrincipalKind::User
+    }
+
+    pub fn is_group(&self) -> bool {
+        self.kind() == PrincipalKind::Group
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+impl std::fmt::Display for PrincipalId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Returned by `PrincipalId::from_str` for ids without a known kind prefix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsePrincipalIdError(pub String);
+
+impl std::fmt::Display for ParsePrincipalIdError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "'{}' is not a principal id (expected a u_, g_, sa_ or pa_ prefix followed by a name)",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParsePrincipalIdError {}
+
+impl std::str::FromStr for PrincipalId {
+    type Err = ParsePrincipalIdError;
+
+    /// Strict parse: the id must carry a known kind prefix and a non-empty name.
+    /// Use `From<&str>` to wrap ids read back from storage without checking.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let id = Self(s.to_string());
+        if id.kind() == PrincipalKind::Other || id.bare().is_empty() {
+            return Err(ParsePrincipalIdError(s.to_string()));
+        }
+        Ok(id)
+    }
+}
+
+impl std::ops::Deref for PrincipalId {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for PrincipalId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::borrow::Borrow<str> for PrincipalId {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for PrincipalId {
+    fn from(s: String) -> Self {
+        Self(s)
+    }
+}
+
+impl From<&str> for PrincipalId {
+    fn from(s: &str) -> Self {
+        Self(s.to_string())
+    }
+}
+
+impl From<PrincipalId> for String {
+    fn from(id: PrincipalId) -> Self {
+        id.0
+    }
+}
+
+impl PartialEq<str> for PrincipalId {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for PrincipalId {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<String> for PrincipalId {
+    fn eq(&self, other: &String) -> bool {
+        &self.0 == other
+    }
+}
+
+impl PartialEq<PrincipalId> for String {
+    fn eq(&self, other: &PrincipalId) -> bool {
+        *self == other.0
+    }
+}
+
+pub use crate::labels::Labels;
+
+/// Freeform annotation map; unlike `Labels`, keys and values are unchecked.
+pub type Annotations = HashMap<String, String>;
 
 bitflags! {
     // derive common traits for easier usage
@@ -30,6 +230,41 @@ bitflags! {
     }
 }
 
+/// Named permission levels for project members. Each role is stored as an
+/// unscoped project ACL entry with exactly `permissions()`; reading a role back
+/// picks the highest role whose bits the principal holds.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectRole {
+    Reporter,
+    Member,
+    Admin,
+    Owner,
+}
+
+impl ProjectRole {
+    pub fn permissions(&self) -> Permissions {
+        match self {
+            ProjectRole::Reporter => Permissions::READ | Permissions::CREATE,
+            ProjectRole::Member => Permissions::WRITE,
+            ProjectRole::Admin => Permissions::WRITE | Permissions::CUSTOM1,
+            ProjectRole::Owner => Permissions::ROOT,
+        }
+    }
+
+    /// Highest role fully covered by `permissions`, or `None` below Reporter.
+    pub fn from_permissions(permissions: Permissions) -> Option<Self> {
+        [
+            ProjectRole::Owner,
+            ProjectRole::Admin,
+            ProjectRole::Member,
+            ProjectRole::Reporter,
+        ]
+        .into_iter()
+        .find(|role| permissions.contains(role.permissions()))
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct AccessControlStore {
     pub list: Vec<AccessControlList>,
@@ -108,13 +343,22 @@ pub struct DisconnectedEdge {
 // ---------------------------------------------------------------------------
 
 /// Server-managed audit timestamps. NOT part of desired state — excluded
-/// from hash computation and not user-modifiable.
+/// from hash computation and not user-modifiable. Set on create; the
+/// `updated_*` fields stay empty until the first update. RFC3339 UTC on the wire.
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct ResourceState {
+    #[serde(default)]
     pub created_at: DateTime<Utc>,
+    #[serde(default)]
     pub created_by: Option<PrincipalId>,
-    pub updated_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub updated_by: Option<PrincipalId>,
+    /// Desired-state revision: 1 on create, +1 on every write that changes
+    /// `hash_code`. No-op writes keep it. 0 for documents written before it existed.
+    #[serde(default)]
+    pub generation: u64,
 }
 
 /// Server-injected runtime data, NOT part of desired state or history.
@@ -252,10 +496,16 @@ pub struct PersistentFile {
 /// so it can be called in handlers that work with `Value` rather than typed
 /// structs.
 pub fn compute_value_hash(val: &serde_json::Value) -> String {
+    compute_value_hash_excluding(val, &[])
+}
+
+/// `compute_value_hash` that also drops `extra` top-level fields, for kinds
+/// with `#[hash_exclude]` fields (pass `T::hash_excluded_fields()`).
+pub fn compute_value_hash_excluding(val: &serde_json::Value, extra: &[&str]) -> String {
     let mut v = val.clone();
     if let Some(obj) = v.as_object_mut() {
-        for key in ["hash_code", "deletion", "state", "_id", "_rev"] {
-            obj.remove(key);
+        for key in ["hash_code", "deletion", "state", "_id", "_rev"].iter().chain(extra) {
+            obj.remove(*key);
         }
     }
     let canonical = serde_json::to_string(&v).unwrap_or_default();
@@ -277,4 +527,108 @@ pub mod super_permissions {
     pub const ADM_CONFIG_EDITOR: &str = "adm_config_editor";
     pub const USR_CREATE_GROUPS: &str = "usr_create_groups";
     pub const USR_CREATE_PROJECTS: &str = "usr_create_projects";
+
+    /// Every super-permission; the default admin is granted all of them.
+    pub const ALL: &[&str] = &[
+        ADM_GODMODE,
+        ADM_USER_MANAGER,
+        ADM_CONFIG_EDITOR,
+        USR_CREATE_GROUPS,
+        USR_CREATE_PROJECTS,
+    ];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn principal_id_parses_prefixed_user() {
+        let id: PrincipalId = "u_alice".parse().unwrap();
+        assert_eq!(id.kind(), PrincipalKind::User);
+        assert!(id.is_user());
+        assert_eq!(id.bare(), "alice");
+        assert_eq!(id.to_string(), "u_alice");
+    }
+
+    #[test]
+    fn principal_id_parse_rejects_unknown_prefix() {
+        assert!("alice".parse::<PrincipalId>().is_err());
+        assert!("x_alice".parse::<PrincipalId>().is_err());
+        assert!("u_".parse::<PrincipalId>().is_err());
+        assert_eq!("sa_ci".parse::<PrincipalId>().unwrap().kind(), PrincipalKind::ServiceAccount);
+    }
+
+    #[test]
+    fn principal_id_compares_with_strings() {
+        let id = PrincipalId::user("alice");
+        assert_eq!(id, "u_alice");
+        assert_eq!(id, String::from("u_alice"));
+        assert_ne!(id, PrincipalId::group("alice"));
+    }
+
+    #[test]
+    fn principal_id_without_prefix_is_other() {
+        let id = PrincipalId::from("alice");
+        assert_eq!(id.kind(), PrincipalKind::Other);
+        assert_eq!(id.bare(), "alice");
+        assert!(!id.is_user() && !id.is_group());
+    }
+
+    #[test]
+    fn principal_id_names_are_always_prefixed() {
+        assert_eq!(PrincipalId::user("alice"), "u_alice");
+        // A typed name that looks like an id is still a name.
+        assert_eq!(PrincipalId::user("u_alice"), "u_u_alice");
+        assert_eq!(PrincipalId::parse(PrincipalKind::User, "u_alice"), "u_alice");
+        assert_eq!(PrincipalId::parse(PrincipalKind::User, "alice"), "u_alice");
+        assert_eq!(PrincipalId::group("admins").kind(), PrincipalKind::Group);
+        assert_eq!(
+            PrincipalId::with_prefix(PrincipalKind::ServiceAccount, "ci").as_str(),
+            "sa_ci"
+        );
+    }
+
+    #[test]
+    fn project_role_round_trips_through_permissions() {
+        for role in [
+            ProjectRole::Reporter,
+            ProjectRole::Member,
+            ProjectRole::Admin,
+            ProjectRole::Owner,
+        ] {
+            assert_eq!(ProjectRole::from_permissions(role.permissions()), Some(role));
+        }
+        assert_eq!(ProjectRole::from_permissions(Permissions::READ), None);
+        assert!(ProjectRole::Owner > ProjectRole::Admin);
+        assert_eq!(serde_json::to_string(&ProjectRole::Admin).unwrap(), "\"admin\"");
+    }
+
+    #[test]
+    fn like_pattern_escapes_underscore() {
+        assert_eq!(PrincipalKind::User.like_pattern(), "u\\_%");
+        assert_eq!(PrincipalKind::Group.like_pattern(), "g\\_%");
+    }
+
+    #[test]
+    fn principal_id_serializes_as_plain_string() {
+        let id = PrincipalId::group("ops");
+        assert_eq!(serde_json::to_string(&id).unwrap(), "\"g_ops\"");
+        let back: PrincipalId = serde_json::from_str("\"g_ops\"").unwrap();
+        assert_eq!(back, id);
+    }
+
+    #[test]
+    fn resource_state_without_update_fields() {
+        let fresh = serde_json::json!({
+            "created_at": "2026-02-23T10:00:00+00:00",
+            "created_by": "u_alice",
+        });
+        let state: ResourceState = serde_json::from_value(fresh.clone()).unwrap();
+        assert_eq!(state.created_by, Some(PrincipalId::user("alice")));
+        assert!(state.updated_at.is_none());
+        let wire = serde_json::to_value(&state).unwrap();
+        assert!(wire.get("updated_at").is_none());
+        assert_eq!(wire["created_at"], "2026-02-23T10:00:00Z");
+    }
 }